@@ -0,0 +1,27 @@
+// Generates the gRPC service from `proto/frost_cli.proto` without requiring protoc, and
+// with the `cdylib` feature, the C header of `src/ffi.rs`.
+//
+// Also sets the `native` cfg when the `native` feature is on and the target is not
+// WebAssembly, which gates the networking, server and memory locking modules.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rustc-check-cfg=cfg(native)");
+    println!("cargo:rerun-if-changed=proto/frost_cli.proto");
+    let native = std::env::var_os("CARGO_FEATURE_NATIVE").is_some()
+        && std::env::var("CARGO_CFG_TARGET_FAMILY").map_or(true, |family| {
+            !family.split(',').any(|family| family == "wasm")
+        });
+    if native {
+        println!("cargo:rustc-cfg=native");
+        let file_descriptors = protox::compile(["proto/frost_cli.proto"], ["proto"])?;
+        tonic_build::configure().compile_fds(file_descriptors)?;
+    }
+
+    #[cfg(feature = "cdylib")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR")?;
+        cbindgen::generate(&crate_dir)?.write_to_file("include/frost_cli.h");
+    }
+    Ok(())
+}
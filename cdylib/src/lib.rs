@@ -0,0 +1,10 @@
+//! Shared library of the frost_cli bindings.
+//!
+//! frost_cli only builds as a Rust library, so building the CLI never links a shared
+//! library. This crate links frost_cli into one, exporting the bindings its features
+//! turn on: the JavaScript functions of `wasm`, the C functions of `cdylib`, the
+//! `frost_dalek_cli` Python module of `python` and the Kotlin and Swift scaffolding of
+//! `uniffi`.
+
+// The exports live in frost_cli, which is only linked if it is named here.
+extern crate frost_cli;
@@ -0,0 +1,15 @@
+//! Parses arbitrary bytes as a dealer key file in any file format, and signs with what
+//! parses.
+#![no_main]
+
+use frost_cli::{parse_frost_keys, sign_with_keys, SIGNING_CONTEXT};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(frost_keys) = parse_frost_keys(data, "fuzz", &Default::default()) {
+        let signers: Vec<u32> = (0..frost_keys.private_shares.len() as u32)
+            .take(frost_keys.threshold as usize)
+            .collect();
+        let _ = sign_with_keys(b"fuzz", SIGNING_CONTEXT, &frost_keys, &signers);
+    }
+});
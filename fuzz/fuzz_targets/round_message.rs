@@ -0,0 +1,19 @@
+//! Parses arbitrary bytes as a round message envelope, and as each round message file in
+//! any file format.
+#![no_main]
+
+use frost_cli::io::format;
+use frost_cli::messages::{
+    self, PartialSignature, PublicCommitments, Round1Message, Round2Message,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(message) = messages::decode(data) {
+        let _ = messages::encode(&message);
+    }
+    let _ = format::decode::<Round1Message>(data);
+    let _ = format::decode::<Round2Message>(data);
+    let _ = format::decode::<PublicCommitments>(data);
+    let _ = format::decode::<PartialSignature>(data);
+});
@@ -0,0 +1,12 @@
+//! Parses arbitrary bytes as a participant share file in any file format.
+#![no_main]
+
+use frost_cli::parse_participant_share;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(share) = parse_participant_share(data, "fuzz", &Default::default()) {
+        assert!(share.index >= 1 && share.index <= share.participants);
+        assert!(share.threshold >= 1 && share.threshold <= share.participants);
+    }
+});
@@ -0,0 +1,39 @@
+//! Parses arbitrary bytes as a signature file in every signature format, and verifies
+//! what parses.
+#![no_main]
+
+use frost_cli::backend::Ciphersuite;
+use frost_cli::io::signature::{self, SignatureFormat};
+use frost_cli::{parse_signature, verify_with_group_key, SIGNING_CONTEXT};
+use libfuzzer_sys::fuzz_target;
+
+/// The compressed Ristretto basepoint, a valid group key to verify against.
+const GROUP_KEY: [u8; 32] = [
+    0xe2, 0xf2, 0xae, 0x0a, 0x6a, 0xbc, 0x4e, 0x71, 0xa8, 0x84, 0xa9, 0x61, 0xc5, 0x00, 0x51, 0x5f,
+    0x58, 0xe3, 0x0b, 0x6a, 0xa5, 0x82, 0xdd, 0x8d, 0xb6, 0xa6, 0x59, 0x45, 0xe0, 0x8d, 0x2d, 0x76,
+];
+
+const FORMATS: [Option<SignatureFormat>; 6] = [
+    None,
+    Some(SignatureFormat::Raw),
+    Some(SignatureFormat::Hex),
+    Some(SignatureFormat::Base64),
+    Some(SignatureFormat::Pem),
+    Some(SignatureFormat::Json),
+];
+
+fuzz_target!(|data: &[u8]| {
+    for sig_format in FORMATS {
+        if let Ok(signature) = parse_signature(data, "fuzz", sig_format) {
+            let _ = verify_with_group_key(
+                b"fuzz",
+                SIGNING_CONTEXT,
+                Ciphersuite::Ristretto255,
+                &GROUP_KEY,
+                &signature,
+            );
+        }
+    }
+    let _ = signature::decode_metadata(data, "fuzz");
+    let _ = signature::decode_signed_file(data, "fuzz");
+});
@@ -0,0 +1,141 @@
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#ifndef FROST_CLI_H
+#define FROST_CLI_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The call succeeded.
+ */
+#define FROST_OK 0
+
+/**
+ * The call failed for a reason without its own code, see [`frost_last_error`].
+ */
+#define FROST_ERROR 1
+
+/**
+ * A pointer argument is null or not valid UTF-8.
+ */
+#define FROST_INVALID_ARGUMENT 2
+
+/**
+ * The threshold, participants or signers do not fit together.
+ */
+#define FROST_INVALID_PARAMETERS 3
+
+/**
+ * A private share could not be decoded or is not a valid share.
+ */
+#define FROST_INVALID_SHARE 4
+
+/**
+ * The partial signatures could not be aggregated into a valid signature.
+ */
+#define FROST_AGGREGATION_FAILED 5
+
+/**
+ * A file could not be read or written.
+ */
+#define FROST_IO 6
+
+/**
+ * The signature does not verify.
+ */
+#define FROST_INVALID_SIGNATURE 7
+
+/**
+ * The library panicked.
+ */
+#define FROST_PANIC 8
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the message of the last error on the calling thread, or null if the last
+ * call succeeded. The string is owned by the library and valid until the next call on
+ * the same thread.
+ */
+const char *frost_last_error(void);
+
+/**
+ * Generates keys as a trusted dealer and saves them to a key file, see
+ * [`crate::generate_keys`].
+ *
+ * # Safety
+ * `output_key_file` must be a valid NUL-terminated string, and `ciphersuite` one or
+ * null for ristretto255.
+ */
+int frost_generate_keys(uint32_t t,
+                        uint32_t n,
+                        const char *output_key_file,
+                        const char *ciphersuite);
+
+/**
+ * Runs signing round one, see [`signing::commit`].
+ *
+ * # Safety
+ * Every argument must be a valid NUL-terminated string.
+ */
+int frost_commit(const char *session_id,
+                 const char *share_file,
+                 const char *public_commitments_file,
+                 const char *secret_commitments_file);
+
+/**
+ * Runs signing round two, see [`signing::partial_sign`]. A null `context` signs in
+ * [`crate::SIGNING_CONTEXT`].
+ *
+ * # Safety
+ * Every string argument but a null `context` must be a valid NUL-terminated string,
+ * and `commitment_files` an array of `commitment_files_len` of them.
+ */
+int frost_partial_sign(const char *message,
+                       const char *context,
+                       const char *share_file,
+                       const char *secret_commitments_file,
+                       const char *const *commitment_files,
+                       size_t commitment_files_len,
+                       const char *partial_signature_file);
+
+/**
+ * Combines the partial signatures of all signers into a threshold signature, see
+ * [`signing::aggregate`]. A null `context` signs in [`crate::SIGNING_CONTEXT`].
+ *
+ * # Safety
+ * Every string argument but a null `context` must be a valid NUL-terminated string,
+ * and `commitment_files` an array of `commitment_files_len` of them.
+ */
+int frost_aggregate(const char *message,
+                    const char *context,
+                    const char *key_file,
+                    const char *const *commitment_files,
+                    size_t commitment_files_len,
+                    const char *partial_signatures_dir,
+                    const char *signature_file);
+
+/**
+ * Verifies a threshold signature file against a key file, see
+ * [`crate::validate_signature`]. Returns [`FROST_INVALID_SIGNATURE`] if it does not
+ * verify. A null `context` verifies in [`crate::SIGNING_CONTEXT`].
+ *
+ * # Safety
+ * Every argument but a null `context` must be a valid NUL-terminated string.
+ */
+int frost_verify(const char *message,
+                 const char *context,
+                 const char *key_file,
+                 const char *signature_file);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FROST_CLI_H */
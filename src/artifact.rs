@@ -0,0 +1,115 @@
+//! Signatures on files of any size and content.
+//!
+//! `sign --message` signs a string, which rules out binary artifacts such as release
+//! tarballs or firmware images, and holding a large artifact in memory just to sign it
+//! is wasteful. A file is instead read in chunks of [`CHUNK_SIZE`] bytes through
+//! SHA-256, and the group signs [`FILE_CONTEXT`] followed by the 32-byte hash, see
+//! [`SignedFile::signing_input`]. The context keeps a file signature from ever being
+//! valid as the signature of a message.
+//!
+//! The signature file records the file's name, size and hash next to the signature, so
+//! a verifier is told which file was signed and a changed file is reported as such.
+
+use crate::InvalidSignature;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Context the hash of a signed file is prefixed with before it is signed.
+pub const FILE_CONTEXT: &[u8] = b"FROST-CLI FILE SHA-256";
+
+/// Number of bytes read from a file at a time while hashing it.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A signed file, as recorded in a signature file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SignedFile {
+    /// Name of the file, without its directory.
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Hex-encoded SHA-256 hash of the file's contents.
+    pub sha256: String,
+}
+
+impl SignedFile {
+    /// Hashes the file at `path` without reading it into memory as a whole.
+    ///
+    /// # Errors
+    /// Returns an error if the path has no file name or the file cannot be read.
+    pub fn hash(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = Path::new(path)
+            .file_name()
+            .ok_or_else(|| format!("Invalid file to sign: {}", path))?
+            .to_string_lossy()
+            .into_owned();
+        let (digest, size) = sha256_file(path)?;
+        Ok(SignedFile {
+            name,
+            size,
+            sha256: hex::encode(digest),
+        })
+    }
+
+    /// Returns the bytes the group signs for this file, [`FILE_CONTEXT`] followed by the
+    /// file's hash.
+    ///
+    /// # Errors
+    /// Returns an error if the recorded hash is not 32 hex-encoded bytes.
+    pub fn signing_input(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let digest = hex::decode(&self.sha256)
+            .ok()
+            .filter(|digest| digest.len() == 32)
+            .ok_or_else(|| format!("Invalid SHA-256 hash of {}: {}", self.name, self.sha256))?;
+        let mut input = FILE_CONTEXT.to_vec();
+        input.extend(digest);
+        Ok(input)
+    }
+
+    /// Checks that `other` has the contents of this recorded file. The name is not
+    /// compared, so a signed file may be renamed.
+    ///
+    /// # Errors
+    /// Returns an [`InvalidSignature`] error if the size or hash differs.
+    pub fn check(&self, other: &SignedFile) -> Result<(), Box<dyn std::error::Error>> {
+        if self.size != other.size || self.sha256 != other.sha256 {
+            return Err(InvalidSignature(format!(
+                "{} is not the signed file {} ({} bytes, SHA-256 {})",
+                other.name, self.name, self.size, self.sha256
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Returns the SHA-256 hash and size of the file at `path`, read in chunks of
+/// [`CHUNK_SIZE`] bytes.
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+pub fn sha256_file(path: &str) -> Result<([u8; 32], u64), Box<dyn std::error::Error>> {
+    let mut file = File::open(path)
+        .map_err(|err| io::Error::new(err.kind(), format!("Failed to open {}: {}", path, err)))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut size = 0u64;
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                return Err(
+                    io::Error::new(err.kind(), format!("Failed to read {}: {}", path, err)).into(),
+                )
+            }
+        };
+        hasher.update(&buffer[..read]);
+        size += read as u64;
+    }
+    Ok((hasher.finalize().into(), size))
+}
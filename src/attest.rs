@@ -0,0 +1,158 @@
+//! in-toto attestations threshold-signed by the group.
+//!
+//! An attestation is an [in-toto statement] about one or more subject artifacts,
+//! identified by their SHA-256 digests, with a predicate saying what is claimed about
+//! them: SLSA build provenance, or any predicate read from a JSON file. [`attest`]
+//! builds the statement and signs it as a DSSE envelope, see [`crate::io::dsse`], so
+//! in-toto and SLSA verifiers see the group as a single signer.
+//!
+//! [in-toto statement]: https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md
+
+use crate::artifact::sha256_file;
+use crate::io::envelope::{EnvelopeOptions, OutFormat};
+use crate::io::output::report;
+use crate::sign_message_as;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+/// Type of an in-toto statement.
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+
+/// Predicate type of SLSA build provenance.
+pub const SLSA_PROVENANCE_TYPE: &str = "https://slsa.dev/provenance/v1";
+
+/// Predicate of an attestation.
+pub enum Predicate {
+    /// SLSA build provenance of the subjects.
+    SlsaProvenance {
+        /// URI of the builder that produced the subjects.
+        builder_id: String,
+        /// URI of the type of build that produced the subjects.
+        build_type: String,
+    },
+    /// A predicate of the given type read from a JSON file.
+    Custom {
+        predicate_type: String,
+        predicate_file: String,
+    },
+}
+
+/// Returns the in-toto statement of a predicate about the subject files.
+///
+/// # Errors
+/// Returns an error if a subject file cannot be read, or a custom predicate file is not
+/// a JSON object.
+pub fn statement(
+    subject_files: &[String],
+    predicate: &Predicate,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    if subject_files.is_empty() {
+        return Err("An attestation needs at least one subject".into());
+    }
+    let mut subjects = Vec::with_capacity(subject_files.len());
+    for subject_file in subject_files {
+        let name = Path::new(subject_file)
+            .file_name()
+            .ok_or_else(|| format!("Invalid subject file: {}", subject_file))?
+            .to_string_lossy();
+        let (digest, _) = sha256_file(subject_file)?;
+        subjects.push(json!({
+            "name": name,
+            "digest": { "sha256": hex::encode(digest) },
+        }));
+    }
+
+    let (predicate_type, predicate) = match predicate {
+        Predicate::SlsaProvenance {
+            builder_id,
+            build_type,
+        } => (
+            SLSA_PROVENANCE_TYPE.to_string(),
+            json!({
+                "buildDefinition": {
+                    "buildType": build_type,
+                    "externalParameters": {},
+                },
+                "runDetails": {
+                    "builder": { "id": builder_id },
+                },
+            }),
+        ),
+        Predicate::Custom {
+            predicate_type,
+            predicate_file,
+        } => {
+            let predicate: serde_json::Value =
+                serde_json::from_slice(&fs::read(predicate_file)?)
+                    .map_err(|err| format!("Invalid predicate in {}: {}", predicate_file, err))?;
+            if !predicate.is_object() {
+                return Err(format!("Predicate in {} is not a JSON object", predicate_file).into());
+            }
+            (predicate_type.clone(), predicate)
+        }
+    };
+
+    Ok(json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subjects,
+        "predicateType": predicate_type,
+        "predicate": predicate,
+    }))
+}
+
+/// Builds the in-toto statement of a predicate about the subject files and saves it
+/// threshold-signed as a DSSE envelope.
+///
+/// # Arguments
+/// - `subject_files`: Paths to the artifacts the statement is about.
+/// - `predicate`: What the statement claims about the subjects.
+/// - `context`: Context string the statement is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `signers`: Indices of the participants signing.
+/// - `n`: Total number of participants.
+/// - `key_file`: Path to the key file.
+/// - `output_file`: Path to save the DSSE envelope.
+/// - `statement_file`: Path to also save the signed statement to, if any.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+///
+/// # Errors
+/// Returns an error if the statement cannot be built or signing fails.
+pub fn attest(
+    subject_files: &[String],
+    predicate: &Predicate,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    output_file: &str,
+    statement_file: Option<&str>,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Build the statement
+    let statement = serde_json::to_string(&statement(subject_files, predicate)?)?;
+
+    // Step 2: Sign the statement as a DSSE envelope
+    sign_message_as(
+        &statement,
+        context,
+        signers,
+        n,
+        key_file,
+        output_file,
+        OutFormat::Dsse,
+        &EnvelopeOptions::default(),
+        transcript_file,
+    )?;
+
+    // Step 3: Save the statement
+    if let Some(statement_file) = statement_file {
+        fs::write(statement_file, &statement)?;
+        report(
+            "statement_saved",
+            &format!("Signed statement saved to: {}", statement_file),
+            json!({ "statement_file": statement_file }),
+        );
+    }
+    Ok(())
+}
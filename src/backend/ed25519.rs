@@ -0,0 +1,77 @@
+//! FROST(Ed25519, SHA-512) with the Zcash Foundation's `frost-ed25519`.
+//!
+//! Key files of this ciphersuite keep the same layout as frost-dalek ones: the group
+//! key is the compressed Edwards point of the Ed25519 public key and every private
+//! share is a little-endian scalar with its participant index. Messages are signed as
+//! they are, without the [`crate::SIGNING_CONTEXT`] of frost-dalek, so the signature
+//! checks out under plain Ed25519.
+
+use crate::backend::vectors::{self, TestVectors};
+use crate::backend::{zf, Backend, Ciphersuite};
+use crate::{FrostKeys, KeygenProgress, SecureRng};
+use frost_ed25519::Ed25519Sha512;
+
+/// The frost-ed25519 backend.
+pub struct Ed25519;
+
+impl Backend for Ed25519 {
+    fn deal_keys_with_rng(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+        rng: &mut dyn SecureRng,
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+        let (mut frost_keys, verifying_key) =
+            zf::deal_keys::<Ed25519Sha512>(t, n, Ciphersuite::Ed25519, progress, rng)?;
+        frost_keys.group_key = verifying_key
+            .try_into()
+            .map_err(|_| "Invalid group public key length")?;
+        Ok(frost_keys)
+    }
+
+    fn sign(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        message: &[u8],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ciphersuite::Ed25519.check_signing_context(context)?;
+        zf::sign::<Ed25519Sha512>(
+            frost_keys,
+            &frost_keys.group_key,
+            signers,
+            message,
+            transcript_file,
+            rng,
+        )
+    }
+
+    fn verify(
+        &self,
+        group_key: &[u8; 32],
+        message: &[u8],
+        context: &str,
+        signature: &[u8; 64],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ciphersuite::Ed25519.check_signing_context(context)?;
+        let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(group_key)
+            .map_err(|_| "Invalid group public key")?;
+        verifying_key
+            .verify_strict(message, &ed25519_dalek::Signature::from_bytes(signature))
+            .map_err(|_| "Signature verification failed")?;
+        Ok(())
+    }
+
+    fn check_test_vectors(
+        &self,
+        vectors: serde_json::Value,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let vectors: TestVectors = serde_json::from_value(vectors)
+            .map_err(|err| format!("Invalid test vectors: {}", err))?;
+        vectors::check::<Ed25519Sha512>(&vectors, "FROST(Ed25519, SHA-512)")
+    }
+}
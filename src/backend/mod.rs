@@ -0,0 +1,234 @@
+//! FROST ciphersuites the trusted-dealer commands can generate keys and sign with.
+//!
+//! frost-dalek signs over Ristretto, so its signatures are only ever accepted by
+//! frost-cli itself. Built with the `ed25519` feature, `generate --ciphersuite ed25519`
+//! deals keys with the Zcash Foundation's `frost-ed25519` instead, whose signatures are
+//! plain Ed25519 signatures that `ed25519-dalek`, OpenSSH and every other Ed25519
+//! verifier accept. Likewise the `secp256k1` feature adds `--ciphersuite secp256k1`,
+//! which signs with `frost-secp256k1-tr` to produce BIP340 Schnorr signatures as used
+//! by Taproot. A key file records its ciphersuite, and [`open`] returns the backend for
+//! it, so `sign` and `verify` need no extra options.
+//!
+//! [`check_test_vectors`] runs a backend against the RFC 9591 test vectors, which only
+//! the `ed25519` backend implements exactly.
+//!
+//! The round-by-round, DKG and networked commands only support Ristretto.
+
+#[cfg(feature = "ed25519")]
+pub mod ed25519;
+pub mod ristretto255;
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+#[cfg(feature = "ed25519")]
+pub mod vectors;
+#[cfg(any(feature = "ed25519", feature = "secp256k1"))]
+mod zf;
+
+use crate::io::output::report;
+use crate::{FrostKeys, KeygenProgress, SecureRng};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
+
+/// A FROST ciphersuite.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Ciphersuite {
+    /// Schnorr signatures over Ristretto, as produced by frost-dalek.
+    #[default]
+    Ristretto255,
+    /// FROST(Ed25519, SHA-512) from RFC 9591, verifiable as Ed25519.
+    Ed25519,
+    /// FROST(secp256k1, SHA-256) with BIP340 signatures, verifiable as Taproot Schnorr.
+    Secp256k1,
+}
+
+impl Ciphersuite {
+    /// Returns whether this is the default ciphersuite, which key files leave out.
+    pub fn is_ristretto255(&self) -> bool {
+        *self == Ciphersuite::Ristretto255
+    }
+
+    /// Returns the context string messages signed in `context` are hashed with, empty
+    /// for the ciphersuites that sign messages as they are, see
+    /// [`crate::SIGNING_CONTEXT`].
+    pub fn signing_context<'a>(&self, context: &'a str) -> &'a str {
+        match self {
+            Ciphersuite::Ristretto255 => context,
+            Ciphersuite::Ed25519 | Ciphersuite::Secp256k1 => "",
+        }
+    }
+
+    /// Checks that a ciphersuite that signs messages as they are is asked for no
+    /// signing context other than the default.
+    ///
+    /// # Errors
+    /// Returns an error if another signing context is given, since it would not be
+    /// hashed into the signature.
+    pub fn check_signing_context(&self, context: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if self.signing_context(context).is_empty() && context != crate::SIGNING_CONTEXT {
+            return Err(format!(
+                "The {} ciphersuite signs messages as they are and takes no signing context",
+                self
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Ciphersuite {
+    type Err = String;
+
+    fn from_str(ciphersuite: &str) -> Result<Self, Self::Err> {
+        match ciphersuite {
+            "ristretto255" => Ok(Ciphersuite::Ristretto255),
+            "ed25519" => Ok(Ciphersuite::Ed25519),
+            "secp256k1" => Ok(Ciphersuite::Secp256k1),
+            _ => Err(format!(
+                "Unknown ciphersuite {}, expected ristretto255, ed25519 or secp256k1",
+                ciphersuite
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Ciphersuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ciphersuite::Ristretto255 => write!(f, "ristretto255"),
+            Ciphersuite::Ed25519 => write!(f, "ed25519"),
+            Ciphersuite::Secp256k1 => write!(f, "secp256k1"),
+        }
+    }
+}
+
+/// Key generation, signing and verification in one ciphersuite.
+pub trait Backend {
+    /// Generates a group key and `n` private shares, any `t` of which can sign, calling
+    /// `progress` as participants get through each stage.
+    fn deal_keys(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+        self.deal_keys_with_rng(t, n, progress, &mut rand::rngs::OsRng)
+    }
+
+    /// Generates keys like [`Backend::deal_keys`], drawing every secret from `rng`.
+    fn deal_keys_with_rng(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+        rng: &mut dyn SecureRng,
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>>;
+
+    /// Runs both signing rounds with the shares at the given positions of the key file
+    /// and returns the 64-byte signature of `message` in `context`, drawing the signing
+    /// nonces from `rng` and recording the ceremony in `transcript_file`, see
+    /// [`crate::transcript`].
+    fn sign(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        message: &[u8],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>>;
+
+    /// Signs several messages with the same signers and returns their signatures in
+    /// order. Every message is signed with fresh nonce commitments.
+    fn sign_batch(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        messages: &[Vec<u8>],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+        messages
+            .iter()
+            .map(|message| self.sign(frost_keys, signers, message, context, transcript_file, rng))
+            .collect()
+    }
+
+    /// Verifies a signature of `message` in `context` under a group public key.
+    fn verify(
+        &self,
+        group_key: &[u8; 32],
+        message: &[u8],
+        context: &str,
+        signature: &[u8; 64],
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Recomputes the RFC 9591 test vectors of this ciphersuite.
+    fn check_test_vectors(
+        &self,
+        vectors: serde_json::Value,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Returns the backend for a ciphersuite.
+///
+/// # Errors
+/// Returns an error if the ciphersuite needs a backend this build does not include.
+pub fn open(ciphersuite: Ciphersuite) -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    match ciphersuite {
+        Ciphersuite::Ristretto255 => Ok(Box::new(ristretto255::Ristretto255)),
+        Ciphersuite::Ed25519 => open_ed25519(),
+        Ciphersuite::Secp256k1 => open_secp256k1(),
+    }
+}
+
+/// Checks a ciphersuite's backend against a file of RFC 9591 test vectors.
+///
+/// # Arguments
+/// - `ciphersuite`: The ciphersuite to check.
+/// - `vectors_file`: Path to the test vectors of that ciphersuite, see [`Backend::check_test_vectors`].
+///
+/// # Errors
+/// Returns an error if the backend has no RFC 9591 ciphersuite, the file does not hold
+/// test vectors for it, or any computed value differs from the vectors.
+pub fn check_test_vectors(
+    ciphersuite: Ciphersuite,
+    vectors_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let vectors: serde_json::Value = serde_json::from_slice(&fs::read(vectors_file)?)?;
+    open(ciphersuite)?.check_test_vectors(vectors)?;
+
+    report(
+        "test_vectors_passed",
+        &format!(
+            "The {} backend passes the test vectors in: {}",
+            ciphersuite, vectors_file
+        ),
+        json!({ "valid": true, "ciphersuite": ciphersuite, "vectors_file": vectors_file }),
+    );
+    Ok(())
+}
+
+#[cfg(feature = "ed25519")]
+fn open_ed25519() -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    Ok(Box::new(ed25519::Ed25519))
+}
+
+#[cfg(not(feature = "ed25519"))]
+fn open_ed25519() -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the ed25519 feature".into())
+}
+
+#[cfg(feature = "secp256k1")]
+fn open_secp256k1() -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    Ok(Box::new(secp256k1::Secp256k1))
+}
+
+#[cfg(not(feature = "secp256k1"))]
+fn open_secp256k1() -> Result<Box<dyn Backend>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the secp256k1 feature".into())
+}
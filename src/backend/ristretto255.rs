@@ -0,0 +1,130 @@
+//! Ristretto Schnorr signatures with frost-dalek, the default ciphersuite.
+
+use crate::backend::Backend;
+use crate::error::FrostCliError;
+use crate::{
+    deal_keys, deal_keys_with_rng, sign_batch_with_secret_keys, sign_with_secret_keys, FrostKeys,
+    KeygenProgress, SecureRng,
+};
+use frost_dalek::signature::SecretKey as SignatureSecretKey;
+use frost_dalek::signature::ThresholdSignature;
+use frost_dalek::{compute_message_hash, GroupKey};
+
+/// The frost-dalek backend.
+pub struct Ristretto255;
+
+impl Backend for Ristretto255 {
+    fn deal_keys(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+        deal_keys(t, n, progress)
+    }
+
+    fn deal_keys_with_rng(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+        rng: &mut dyn SecureRng,
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+        deal_keys_with_rng(t, n, progress, rng)
+    }
+
+    fn sign(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        message: &[u8],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let (group_key, secret_keys) = signing_keys(frost_keys, signers)?;
+        sign_with_secret_keys(
+            message,
+            context,
+            &secret_keys,
+            &frost_keys.verification_shares,
+            group_key,
+            frost_keys.threshold,
+            frost_keys.private_shares.len() as u32,
+            transcript_file,
+            rng,
+        )
+    }
+
+    fn sign_batch(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        messages: &[Vec<u8>],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+        let (group_key, secret_keys) = signing_keys(frost_keys, signers)?;
+        sign_batch_with_secret_keys(
+            messages,
+            context,
+            &secret_keys,
+            &frost_keys.verification_shares,
+            group_key,
+            frost_keys.threshold,
+            frost_keys.private_shares.len() as u32,
+            transcript_file,
+            rng,
+        )
+    }
+
+    fn verify(
+        &self,
+        group_key: &[u8; 32],
+        message: &[u8],
+        context: &str,
+        signature: &[u8; 64],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let group_key = GroupKey::from_bytes(*group_key).map_err(|_| "Invalid group public key")?;
+        let threshold_signature = ThresholdSignature::from_bytes(*signature)
+            .map_err(|_| "Failed to deserialize ThresholdSignature")?;
+        let message_hash = compute_message_hash(context.as_bytes(), message);
+        threshold_signature
+            .verify(&group_key, &message_hash)
+            .map_err(|_| "Signature verification failed")?;
+        Ok(())
+    }
+
+    fn check_test_vectors(
+        &self,
+        _vectors: serde_json::Value,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Err("frost-dalek implements an earlier draft of FROST and cannot reproduce the RFC 9591 FROST(ristretto255, SHA-512) test vectors".into())
+    }
+}
+
+/// Returns the group public key and the secret keys of the signers at the given
+/// positions of the key file.
+fn signing_keys(
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+) -> Result<(GroupKey, Vec<SignatureSecretKey>), Box<dyn std::error::Error>> {
+    // Step 1: Load the group public key
+    let group_key =
+        GroupKey::from_bytes(frost_keys.group_key).map_err(|_| "Invalid group public key")?;
+
+    // Step 2: Reconstruct secret keys for the specified signers
+    let mut secret_keys = Vec::new();
+    for &signer in signers {
+        let (key_bytes, index) = &frost_keys.private_shares[signer as usize];
+        let secret_key = SignatureSecretKey::from_bytes(*index, *key_bytes).map_err(|_| {
+            FrostCliError::InvalidShare {
+                share: format!("of participant {}", index),
+                reason: format!("Invalid private key bytes of participant {}", index),
+            }
+        })?;
+        secret_keys.push(secret_key);
+    }
+    Ok((group_key, secret_keys))
+}
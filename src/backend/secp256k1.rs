@@ -0,0 +1,86 @@
+//! BIP340 Schnorr signatures over secp256k1 with the Zcash Foundation's
+//! `frost-secp256k1-tr`, FROST(secp256k1, SHA-256) adapted for Taproot.
+//!
+//! BIP340 public keys are the 32-byte x-coordinate of a point with an even Y
+//! coordinate, so the dealer keeps dealing until the group key has an even Y, and
+//! stores just its x-coordinate as the group key. Private shares are big-endian
+//! scalars. Messages are signed as they are, without the [`crate::SIGNING_CONTEXT`] of
+//! frost-dalek, and the group key is used as is, without a BIP341 Taproot tweak.
+
+use crate::backend::{zf, Backend, Ciphersuite};
+use crate::{FrostKeys, KeygenProgress, SecureRng};
+use frost_secp256k1_tr::Secp256K1Sha256TR;
+
+/// SEC1 prefix of a compressed point with an even Y coordinate.
+const EVEN_Y: u8 = 0x02;
+
+/// The frost-secp256k1-tr backend.
+pub struct Secp256k1;
+
+impl Backend for Secp256k1 {
+    fn deal_keys_with_rng(
+        &self,
+        t: u32,
+        n: u32,
+        progress: &dyn Fn(KeygenProgress),
+        rng: &mut dyn SecureRng,
+    ) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+        loop {
+            let (mut frost_keys, verifying_key) =
+                zf::deal_keys::<Secp256K1Sha256TR>(t, n, Ciphersuite::Secp256k1, progress, rng)?;
+            if verifying_key.first() != Some(&EVEN_Y) {
+                continue;
+            }
+            frost_keys.group_key = verifying_key[1..]
+                .try_into()
+                .map_err(|_| "Invalid group public key length")?;
+            return Ok(frost_keys);
+        }
+    }
+
+    fn sign(
+        &self,
+        frost_keys: &FrostKeys,
+        signers: &[u32],
+        message: &[u8],
+        context: &str,
+        transcript_file: Option<&str>,
+        rng: &mut dyn SecureRng,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ciphersuite::Secp256k1.check_signing_context(context)?;
+        let verifying_key = [&[EVEN_Y][..], &frost_keys.group_key[..]].concat();
+        zf::sign::<Secp256K1Sha256TR>(
+            frost_keys,
+            &verifying_key,
+            signers,
+            message,
+            transcript_file,
+            rng,
+        )
+    }
+
+    fn verify(
+        &self,
+        group_key: &[u8; 32],
+        message: &[u8],
+        context: &str,
+        signature: &[u8; 64],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ciphersuite::Secp256k1.check_signing_context(context)?;
+        let verifying_key = k256::schnorr::VerifyingKey::from_bytes(group_key)
+            .map_err(|_| "Invalid group public key")?;
+        let signature = k256::schnorr::Signature::try_from(&signature[..])
+            .map_err(|_| "Invalid BIP340 signature")?;
+        verifying_key
+            .verify_raw(message, &signature)
+            .map_err(|_| "Signature verification failed")?;
+        Ok(())
+    }
+
+    fn check_test_vectors(
+        &self,
+        _vectors: serde_json::Value,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Err("frost-secp256k1-tr adapts FROST(secp256k1, SHA-256) to BIP340 and cannot reproduce its RFC 9591 test vectors".into())
+    }
+}
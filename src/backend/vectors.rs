@@ -0,0 +1,230 @@
+//! Known-answer tests against the RFC 9591 test vectors.
+//!
+//! The vectors fix the group secret key, the participant shares and every signer's
+//! nonces, so each value the signers and the coordinator send each other can be
+//! recomputed and compared: the nonce commitments of round one, the signature shares of
+//! round two and the final signature. Passing them shows that a backend's messages are
+//! interchangeable with those of any other RFC 9591 implementation.
+//!
+//! Vectors are read in the JSON layout the Zcash Foundation's FROST crates ship them in,
+//! for example `tests/helpers/vectors.json` of `frost-ed25519`.
+
+use crate::io::output::report;
+use frost_core::keys::{KeyPackage, PublicKeyPackage, SigningShare, VerifyingShare};
+use frost_core::round1::{Nonce, SigningNonces};
+use frost_core::{Identifier, SigningKey, SigningPackage, VerifyingKey};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// A set of RFC 9591 test vectors for one ciphersuite.
+#[derive(Deserialize)]
+pub struct TestVectors {
+    config: Config,
+    inputs: Inputs,
+    round_one_outputs: Outputs<RoundOneOutput>,
+    round_two_outputs: Outputs<RoundTwoOutput>,
+    final_output: FinalOutput,
+}
+
+#[derive(Deserialize)]
+struct Config {
+    /// Name of the ciphersuite, such as `FROST(Ed25519, SHA-512)`.
+    name: String,
+    #[serde(rename = "MIN_PARTICIPANTS")]
+    min_participants: String,
+}
+
+#[derive(Deserialize)]
+struct Inputs {
+    group_secret_key: String,
+    group_public_key: String,
+    message: String,
+    participant_shares: Vec<ShareInput>,
+}
+
+#[derive(Deserialize)]
+struct ShareInput {
+    identifier: u16,
+    participant_share: String,
+}
+
+#[derive(Deserialize)]
+struct Outputs<T> {
+    outputs: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct RoundOneOutput {
+    identifier: u16,
+    hiding_nonce: String,
+    binding_nonce: String,
+    hiding_nonce_commitment: String,
+    binding_nonce_commitment: String,
+}
+
+#[derive(Deserialize)]
+struct RoundTwoOutput {
+    identifier: u16,
+    sig_share: String,
+}
+
+#[derive(Deserialize)]
+struct FinalOutput {
+    sig: String,
+}
+
+/// Recomputes every output of the test vectors with the ciphersuite `C`.
+///
+/// # Arguments
+/// - `vectors`: The test vectors.
+/// - `name`: RFC 9591 name of the ciphersuite `C`, which the vectors must be for.
+///
+/// # Errors
+/// Returns an error naming the first value that does not match the vectors.
+pub(crate) fn check<C: frost_core::Ciphersuite + 'static>(
+    vectors: &TestVectors,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if vectors.config.name != name {
+        return Err(format!(
+            "Test vectors are for {}, not for {}",
+            vectors.config.name, name
+        )
+        .into());
+    }
+    let inputs = &vectors.inputs;
+    let min_signers: u16 = vectors.config.min_participants.parse()?;
+
+    // Step 1: Derive the group public key from the group secret key
+    let signing_key = SigningKey::<C>::deserialize(&hex::decode(&inputs.group_secret_key)?)?;
+    let verifying_key = VerifyingKey::from(&signing_key);
+    expect(
+        "group public key",
+        &verifying_key.serialize()?,
+        &inputs.group_public_key,
+    )?;
+
+    // Step 2: Check that the participant shares belong to the group secret key
+    let mut key_packages = BTreeMap::new();
+    let mut verifying_shares = BTreeMap::new();
+    for input in &inputs.participant_shares {
+        let identifier = Identifier::<C>::try_from(input.identifier)?;
+        let signing_share = SigningShare::deserialize(&hex::decode(&input.participant_share)?)?;
+        let verifying_share = VerifyingShare::from(signing_share);
+        verifying_shares.insert(identifier, verifying_share);
+        key_packages.insert(
+            identifier,
+            KeyPackage::new(
+                identifier,
+                signing_share,
+                verifying_share,
+                verifying_key,
+                min_signers,
+            ),
+        );
+    }
+    let threshold_packages: Vec<KeyPackage<C>> = key_packages
+        .values()
+        .take(min_signers as usize)
+        .cloned()
+        .collect();
+    let group_secret_key = frost_core::keys::reconstruct(&threshold_packages)?;
+    expect(
+        "group secret key reconstructed from the participant shares",
+        &group_secret_key.serialize(),
+        &inputs.group_secret_key,
+    )?;
+    report(
+        "keys_match",
+        "Group key and participant shares match.",
+        Value::Null,
+    );
+
+    // Step 3: Recompute the nonce commitments of round one
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for output in &vectors.round_one_outputs.outputs {
+        let identifier = Identifier::<C>::try_from(output.identifier)?;
+        let signing_nonces = SigningNonces::from_nonces(
+            Nonce::deserialize(&hex::decode(&output.hiding_nonce)?)?,
+            Nonce::deserialize(&hex::decode(&output.binding_nonce)?)?,
+        );
+        let signing_commitments = *signing_nonces.commitments();
+        expect(
+            &format!(
+                "hiding nonce commitment of participant {}",
+                output.identifier
+            ),
+            &signing_commitments.hiding().serialize()?,
+            &output.hiding_nonce_commitment,
+        )?;
+        expect(
+            &format!(
+                "binding nonce commitment of participant {}",
+                output.identifier
+            ),
+            &signing_commitments.binding().serialize()?,
+            &output.binding_nonce_commitment,
+        )?;
+        nonces.insert(identifier, signing_nonces);
+        commitments.insert(identifier, signing_commitments);
+    }
+    report(
+        "commitments_match",
+        "Round one commitments match.",
+        Value::Null,
+    );
+
+    // Step 4: Recompute the signature shares of round two
+    let signing_package = SigningPackage::new(commitments, &hex::decode(&inputs.message)?);
+    let mut signature_shares = BTreeMap::new();
+    for output in &vectors.round_two_outputs.outputs {
+        let identifier = Identifier::<C>::try_from(output.identifier)?;
+        let key_package = key_packages
+            .get(&identifier)
+            .ok_or_else(|| format!("No share for participant {}", output.identifier))?;
+        let signing_nonces = nonces
+            .get(&identifier)
+            .ok_or_else(|| format!("No nonces for participant {}", output.identifier))?;
+        let signature_share =
+            frost_core::round2::sign(&signing_package, signing_nonces, key_package)?;
+        expect(
+            &format!("signature share of participant {}", output.identifier),
+            &signature_share.serialize(),
+            &output.sig_share,
+        )?;
+        signature_shares.insert(identifier, signature_share);
+    }
+    report(
+        "signature_shares_match",
+        "Round two signature shares match.",
+        Value::Null,
+    );
+
+    // Step 5: Aggregate the signature
+    let public_key_package = PublicKeyPackage::new(verifying_shares, verifying_key);
+    let signature =
+        frost_core::aggregate(&signing_package, &signature_shares, &public_key_package)?;
+    expect(
+        "signature",
+        &signature.serialize()?,
+        &vectors.final_output.sig,
+    )?;
+    report("signature_matches", "Signature matches.", Value::Null);
+    Ok(())
+}
+
+/// Compares a computed value with its hex-encoded value in the vectors.
+fn expect(what: &str, actual: &[u8], expected: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if hex::encode(actual) != expected.to_ascii_lowercase() {
+        return Err(format!(
+            "The {} does not match the test vectors: expected {}, computed {}",
+            what,
+            expected,
+            hex::encode(actual)
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Trusted-dealer key generation and signing with the Zcash Foundation's FROST crates.
+//!
+//! Every `frost-*` ciphersuite crate is an instance of `frost-core`, so the backends
+//! built on them share this code and only differ in how their group key is stored in
+//! a key file and how their signatures are verified.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::transcript::{self, CeremonyEvent, CeremonyRecord, CommitmentHash};
+use crate::{FrostKeys, KeygenProgress, KeygenStage, SecureRng, FORMAT_VERSION};
+use frost_core::keys::{
+    IdentifierList, KeyPackage, PublicKeyPackage, SigningShare, VerifyingShare,
+};
+use frost_core::{Identifier, SigningPackage, VerifyingKey};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
+
+/// Splits a fresh signing key drawn from `rng` into `n` shares, any `t` of which can
+/// sign.
+///
+/// Returns the keys, whose group key is left zeroed, and the serialized verifying key
+/// the backend stores as the group key in its own way.
+pub(crate) fn deal_keys<C: frost_core::Ciphersuite + 'static>(
+    t: u32,
+    n: u32,
+    ciphersuite: Ciphersuite,
+    progress: &dyn Fn(KeygenProgress),
+    rng: &mut dyn SecureRng,
+) -> Result<(FrostKeys, Vec<u8>), Box<dyn std::error::Error>> {
+    if t > n {
+        return Err(FrostCliError::InvalidParameters(
+            "Threshold value cannot be greater than the total number of participants".into(),
+        )
+        .into());
+    }
+
+    // Step 1: Split a fresh signing key into shares
+    let (shares, public_key_package) = frost_core::keys::generate_with_dealer::<C, _>(
+        u16::try_from(n)?,
+        u16::try_from(t)?,
+        IdentifierList::Default,
+        &mut ZfRng(rng),
+    )?;
+
+    // Step 2: Store the shares by participant index
+    let mut frost_keys = FrostKeys {
+        version: FORMAT_VERSION,
+        ciphersuite,
+        group_key: [0; 32],
+        private_shares: Vec::with_capacity(n as usize),
+        threshold: t,
+        verification_shares: Vec::new(),
+    };
+    for index in 1..=n {
+        let share = shares
+            .get(&identifier::<C>(index)?)
+            .ok_or_else(|| format!("Missing share for participant {}", index))?;
+        let share_bytes: [u8; 32] = share
+            .signing_share()
+            .serialize()
+            .try_into()
+            .map_err(|_| "Invalid signing share length")?;
+        frost_keys.private_shares.push((share_bytes, index));
+        progress(KeygenProgress::new(KeygenStage::Finish, index, n));
+    }
+
+    Ok((frost_keys, public_key_package.verifying_key().serialize()?))
+}
+
+/// Runs both signing rounds with the shares at the given positions of the key file and
+/// returns the serialized signature.
+///
+/// # Arguments
+/// - `frost_keys`: The dealer keys.
+/// - `verifying_key`: The serialized verifying key of the group.
+/// - `signers`: Positions of the signers' shares in the key file.
+/// - `message`: The message to sign.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`].
+/// - `rng`: Generator to draw the signing nonces from.
+pub(crate) fn sign<C: frost_core::Ciphersuite + 'static>(
+    frost_keys: &FrostKeys,
+    verifying_key: &[u8],
+    signers: &[u32],
+    message: &[u8],
+    transcript_file: Option<&str>,
+    rng: &mut dyn SecureRng,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let verifying_key = VerifyingKey::<C>::deserialize(verifying_key)?;
+    let min_signers = u16::try_from(frost_keys.threshold)?;
+
+    // Step 1: Rebuild the key packages of the signers
+    let mut verifying_shares = BTreeMap::new();
+    let mut key_packages = BTreeMap::new();
+    let mut indices = Vec::new();
+    for (position, (share, index)) in frost_keys.private_shares.iter().enumerate() {
+        let identifier = identifier::<C>(*index)?;
+        let signing_share = SigningShare::<C>::deserialize(share)?;
+        let verifying_share = VerifyingShare::from(signing_share);
+        verifying_shares.insert(identifier, verifying_share);
+        if signers.contains(&(position as u32)) {
+            indices.push(*index);
+            key_packages.insert(
+                identifier,
+                KeyPackage::new(
+                    identifier,
+                    signing_share,
+                    verifying_share,
+                    verifying_key,
+                    min_signers,
+                ),
+            );
+        }
+    }
+    let public_key_package = PublicKeyPackage::new(verifying_shares, verifying_key);
+
+    // Step 2: Generate the nonces and commitments of every signer
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for (identifier, key_package) in &key_packages {
+        let (signing_nonces, signing_commitments) =
+            frost_core::round1::commit(key_package.signing_share(), &mut ZfRng(&mut *rng));
+        nonces.insert(*identifier, signing_nonces);
+        commitments.insert(*identifier, signing_commitments);
+    }
+
+    let mut commitment_hashes = Vec::new();
+    for &index in &indices {
+        let commitment = &commitments[&identifier::<C>(index)?];
+        commitment_hashes.push(CommitmentHash {
+            index,
+            hash: hex::encode(Sha256::digest(&commitment.serialize()?)),
+        });
+    }
+
+    // Step 3: Create the signature shares
+    let signing_package = SigningPackage::new(commitments, message);
+    let mut signature_shares = BTreeMap::new();
+    for (identifier, key_package) in &key_packages {
+        let signature_share =
+            frost_core::round2::sign(&signing_package, &nonces[identifier], key_package)?;
+        signature_shares.insert(*identifier, signature_share);
+    }
+
+    // Step 4: Aggregate the signature, which also verifies it, and record the outcome
+    // in the transcript
+    let outcome = frost_core::aggregate(&signing_package, &signature_shares, &public_key_package)
+        .and_then(|signature| signature.serialize());
+    let mut record = CeremonyRecord::new(
+        CeremonyEvent::Signed,
+        frost_keys.ciphersuite,
+        &frost_keys.group_key,
+        frost_keys.threshold,
+        indices,
+        &Sha512::digest(message),
+    );
+    record.commitments = commitment_hashes;
+    transcript::append_outcome(transcript_file, record, &outcome)?;
+    Ok(outcome?)
+}
+
+/// A caller's generator, see [`SecureRng`], as the `rand_core` version frost-core draws
+/// from.
+struct ZfRng<'a>(&'a mut dyn SecureRng);
+
+impl rand_core::RngCore for ZfRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        rand::RngCore::next_u32(self.0)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand::RngCore::next_u64(self.0)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        rand::RngCore::fill_bytes(self.0, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        rand::RngCore::fill_bytes(self.0, dest);
+        Ok(())
+    }
+}
+
+impl rand_core::CryptoRng for ZfRng<'_> {}
+
+/// Returns the FROST identifier of a participant index.
+fn identifier<C: frost_core::Ciphersuite + 'static>(
+    index: u32,
+) -> Result<Identifier<C>, Box<dyn std::error::Error>> {
+    Ok(Identifier::try_from(u16::try_from(index)?)?)
+}
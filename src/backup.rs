@@ -0,0 +1,559 @@
+//! Human-transcribable backups of participant shares.
+//!
+//! [`export_mnemonic`] turns the 32-byte private share into a 24-word BIP39 phrase,
+//! whose last word carries a checksum, so a share can be written down and kept in cold
+//! storage. The group's public parameters are not part of the phrase and are printed
+//! next to it, because [`import_mnemonic`] needs them to rebuild the share file.
+//!
+//! For custodians who type the backup back in, [`export_paper`] prints the whole share
+//! in Crockford base32, in numbered lines of four groups with a CRC-16 per line.
+//! [`recover_paper`] ignores case, spaces and the letters Crockford base32 leaves out,
+//! and fixes a single mistyped or two swapped characters on a line with the line's CRC.
+//! A checksum over the whole share catches anything the line CRCs miss.
+//!
+//! A checksum only shows that a backup was restored as it was written. [`verify_share`]
+//! audits a share against its group key without a ceremony: a share of a 1-of-n group
+//! must be the group's secret key itself, and otherwise the share signs a trial message
+//! together with the shares of other participants in a simulated session, which only
+//! verifies under the group key if every share fits.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::types::{FORMAT_VERSION, SIGNING_CONTEXT};
+use crate::{
+    load_participant_share, point_from_bytes, save_participant_share, scalar_from_bytes,
+    sign_with_keys, verify_with_group_key, FileOptions, FrostKeys, ParticipantShare,
+};
+use bip39::Mnemonic;
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use tracing::warn;
+use zeroize::Zeroizing;
+
+/// Crockford base32 alphabet used by paper backups.
+const PAPER_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Domain separator for the checksum of a paper backup.
+const PAPER_CONTEXT: &[u8] = b"FROST-CLI PAPER BACKUP";
+
+/// Bytes encoded on one line of a paper backup, 16 base32 characters.
+const PAPER_LINE_BYTES: usize = 10;
+
+/// Index, threshold, participants, share, group key and a 4-byte checksum.
+const PAPER_BYTES: usize = 80;
+
+/// Message signed in the trial session of [`verify_share`].
+const TRIAL_MESSAGE: &[u8] = b"FROST-CLI SHARE VERIFY";
+
+/// Returns the private share at `share_file` as a 24-word BIP39 mnemonic.
+///
+/// # Arguments
+/// - `share_file`: Share location of the participant share, see [`crate::storage`].
+/// - `options`: File key the share was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded.
+pub fn export_mnemonic(
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let mnemonic = Mnemonic::from_entropy(&share.share)?;
+
+    report(
+        "share_exported",
+        &format!(
+            "Share of participant {} with threshold {} of {}, group key {}",
+            share.index,
+            share.threshold,
+            share.participants,
+            hex::encode(share.group_key)
+        ),
+        json!({
+            "index": share.index,
+            "threshold": share.threshold,
+            "participants": share.participants,
+            "group_key": hex::encode(share.group_key),
+            "group_key_fingerprint": envelope::fingerprint(&share.group_key),
+        }),
+    );
+    Ok(mnemonic.to_string())
+}
+
+/// Rebuilds a participant share from its BIP39 mnemonic and the group's parameters.
+///
+/// # Arguments
+/// - `mnemonic`: The 24 words of the private share.
+/// - `index`: This participant's index.
+/// - `group_key`: Hex-encoded group public key.
+/// - `threshold`: Minimum number of participants required to sign.
+/// - `participants`: Total number of participants.
+/// - `share_file`: Share location to save the participant share to.
+/// - `options`: How the share file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the mnemonic has a wrong word or checksum, or does not encode a
+/// valid share.
+pub fn import_mnemonic(
+    mnemonic: &str,
+    index: u32,
+    group_key: &str,
+    threshold: u32,
+    participants: u32,
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Decode the phrase and check its checksum
+    let entropy = Zeroizing::new(
+        Mnemonic::parse(mnemonic)
+            .map_err(|err| format!("Invalid mnemonic: {}", err))?
+            .to_entropy(),
+    );
+    let share: [u8; 32] = entropy
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Mnemonic does not encode a 32-byte share, expected 24 words")?;
+    scalar_from_bytes(share)?;
+
+    // Step 2: Rebuild and save the share
+    let group_key: [u8; 32] = hex::decode(group_key)?
+        .try_into()
+        .map_err(|_| "Group key must be 32 bytes")?;
+    save_participant_share(
+        share_file,
+        &ParticipantShare {
+            index,
+            share,
+            group_key,
+            threshold,
+            participants,
+            verification_shares: Vec::new(),
+        },
+        options,
+    )?;
+
+    report(
+        "share_imported",
+        &format!("Share of participant {} imported to: {}", index, share_file),
+        json!({
+            "index": index,
+            "share_file": share_file,
+            "group_key_fingerprint": envelope::fingerprint(&group_key),
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the share at `share_file` as a paper backup to be printed or written down.
+///
+/// # Arguments
+/// - `share_file`: Share location of the participant share, see [`crate::storage`].
+/// - `options`: File key the share was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded.
+pub fn export_paper(
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // Step 1: Serialize the share and append its checksum
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let mut payload = Zeroizing::new(Vec::with_capacity(PAPER_BYTES));
+    payload.extend(share.index.to_be_bytes());
+    payload.extend(share.threshold.to_be_bytes());
+    payload.extend(share.participants.to_be_bytes());
+    payload.extend(share.share);
+    payload.extend(share.group_key);
+    let checksum = paper_checksum(&payload);
+    payload.extend(checksum);
+
+    // Step 2: Print one numbered line with its CRC per 10 bytes
+    let mut backup = format!(
+        "FROST-CLI SHARE BACKUP\nParticipant {}, threshold {} of {}\n",
+        share.index, share.threshold, share.participants
+    );
+    for (number, chunk) in payload.chunks(PAPER_LINE_BYTES).enumerate() {
+        let chunk: [u8; PAPER_LINE_BYTES] = chunk.try_into()?;
+        let chars = encode_line(&chunk);
+        let groups: Vec<&str> = chars
+            .as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).unwrap_or_default())
+            .collect();
+        backup.push_str(&format!(
+            "{:02}: {}  {:04X}\n",
+            number + 1,
+            groups.join(" "),
+            line_crc(number + 1, &chunk)
+        ));
+    }
+    Ok(backup)
+}
+
+/// Restores a participant share from a typed-in paper backup.
+///
+/// Lines without a `NN:` line number, such as the header, are ignored.
+///
+/// # Arguments
+/// - `backup_file`: Path to the typed-in backup.
+/// - `share_file`: Share location to save the participant share to.
+/// - `options`: How the share file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if a line is missing, a line has more errors than can be
+/// corrected, or the restored share does not match its checksum.
+pub fn recover_paper(
+    backup_file: &str,
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Decode every numbered line, correcting what the line CRCs allow
+    let lines = PAPER_BYTES / PAPER_LINE_BYTES;
+    let mut decoded: Vec<Option<[u8; PAPER_LINE_BYTES]>> = vec![None; lines];
+    for line in fs::read_to_string(backup_file)?.lines() {
+        let Some((number, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let Ok(number) = number.trim().parse::<usize>() else {
+            continue;
+        };
+        if number == 0 || number > lines {
+            return Err(format!("Unexpected line number {} in paper backup", number).into());
+        }
+        decoded[number - 1] = Some(decode_line(number, rest)?);
+    }
+    let mut payload = Zeroizing::new(Vec::with_capacity(PAPER_BYTES));
+    for (number, line) in decoded.iter().enumerate() {
+        payload.extend(
+            line.ok_or_else(|| format!("Line {} of the paper backup is missing", number + 1))?,
+        );
+    }
+
+    // Step 2: Check the checksum over the whole share
+    let (body, checksum) = payload.split_at(PAPER_BYTES - 4);
+    if paper_checksum(body) != checksum {
+        return Err("Paper backup checksum mismatch, check the lines for typos".into());
+    }
+    let share = ParticipantShare {
+        index: u32::from_be_bytes(body[0..4].try_into()?),
+        threshold: u32::from_be_bytes(body[4..8].try_into()?),
+        participants: u32::from_be_bytes(body[8..12].try_into()?),
+        share: body[12..44].try_into()?,
+        group_key: body[44..76].try_into()?,
+        verification_shares: Vec::new(),
+    };
+    scalar_from_bytes(share.share)?;
+
+    // Step 3: Save the share
+    save_participant_share(share_file, &share, options)?;
+    report(
+        "share_recovered",
+        &format!(
+            "Share of participant {} recovered to: {}",
+            share.index, share_file
+        ),
+        json!({
+            "index": share.index,
+            "share_file": share_file,
+            "group_key_fingerprint": envelope::fingerprint(&share.group_key),
+        }),
+    );
+    Ok(())
+}
+
+/// Checks that a participant share is consistent with its group key.
+///
+/// The share's parameters and its public share are checked first, the public share
+/// against the share's verification share if the file stores one. A share of a group
+/// with threshold one is compared with the group key directly. Otherwise the share signs
+/// a trial message together with `other_share_files`, at least the threshold of shares
+/// in all, and the signature must verify under the group key. Without other shares only
+/// the share's own parameters can be checked, and the report says so.
+///
+/// # Arguments
+/// - `share_file`: Share location of the share to check, see [`crate::storage`].
+/// - `other_share_files`: Share locations of other participants of the same group.
+/// - `options`: File key the share was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns [`FrostCliError::InvalidShare`] if the share or one of the other shares does
+/// not fit the group key, and an error if fewer shares than the threshold are given.
+pub fn verify_share(
+    share_file: &str,
+    other_share_files: &[String],
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check the share's parameters and compute its public share
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let invalid = |reason: &str| -> Box<dyn std::error::Error> {
+        FrostCliError::InvalidShare {
+            share: share_file.to_string(),
+            reason: reason.to_string(),
+        }
+        .into()
+    };
+    if share.index == 0
+        || share.index > share.participants
+        || share.threshold == 0
+        || share.threshold > share.participants
+    {
+        return Err(invalid(&format!(
+            "Index {} and threshold {} do not fit {} participants",
+            share.index, share.threshold, share.participants
+        )));
+    }
+    point_from_bytes(&share.group_key).map_err(|_| invalid("Group key is not a valid point"))?;
+    let secret = Zeroizing::new(
+        scalar_from_bytes(share.share).map_err(|_| invalid("Share is not a valid scalar"))?,
+    );
+    if *secret == Scalar::zero() {
+        return Err(invalid("Share is zero"));
+    }
+    let public_share = (&RISTRETTO_BASEPOINT_TABLE * &*secret)
+        .compress()
+        .to_bytes();
+    if let Some(verification_share) = share.verification_shares.get(share.index as usize - 1) {
+        if public_share != *verification_share {
+            return Err(invalid(
+                "Public share does not match its verification share",
+            ));
+        }
+    }
+
+    // Step 2: Check the share against the group key, directly for a threshold of one
+    // and with a trial signature together with the other shares otherwise
+    let mut signers = vec![share.index];
+    if share.threshold == 1 {
+        if public_share != share.group_key {
+            return Err(invalid("Public share does not match the group key"));
+        }
+    } else if !other_share_files.is_empty() {
+        let mut private_shares = vec![(share.share, share.index)];
+        for other_file in other_share_files {
+            let other = Locked::new(load_participant_share(other_file, options)?)?;
+            if other.group_key != share.group_key
+                || other.threshold != share.threshold
+                || other.participants != share.participants
+            {
+                return Err(FrostCliError::InvalidShare {
+                    share: other_file.clone(),
+                    reason: "Share belongs to another group".into(),
+                }
+                .into());
+            }
+            if signers.contains(&other.index) {
+                return Err(FrostCliError::InvalidParameters(format!(
+                    "Share of participant {} given twice",
+                    other.index
+                ))
+                .into());
+            }
+            signers.push(other.index);
+            private_shares.push((other.share, other.index));
+        }
+        if signers.len() < share.threshold as usize {
+            return Err(FrostCliError::InvalidParameters(
+                "Number of shares is less than the threshold".into(),
+            )
+            .into());
+        }
+        let frost_keys = Locked::new(FrostKeys {
+            version: FORMAT_VERSION,
+            ciphersuite: Ciphersuite::Ristretto255,
+            group_key: share.group_key,
+            private_shares,
+            threshold: share.threshold,
+            verification_shares: share.verification_shares.clone(),
+        })?;
+        let positions: Vec<u32> = (0..signers.len() as u32).collect();
+        let signature = sign_with_keys(TRIAL_MESSAGE, SIGNING_CONTEXT, &frost_keys, &positions)
+            .map_err(|err| {
+                invalid(&format!(
+                    "Trial signature with participants {:?} failed: {}",
+                    signers, err
+                ))
+            })?;
+        verify_with_group_key(
+            TRIAL_MESSAGE,
+            SIGNING_CONTEXT,
+            Ciphersuite::Ristretto255,
+            &share.group_key,
+            &signature,
+        )
+        .map_err(|_| {
+            invalid(&format!(
+                "Trial signature with participants {:?} does not verify under the group key",
+                signers
+            ))
+        })?;
+    }
+
+    // Step 3: Report what was checked
+    let verified = share.threshold == 1 || !other_share_files.is_empty();
+    let message = if verified {
+        format!(
+            "Share of participant {} is consistent with group key {}",
+            share.index,
+            envelope::fingerprint(&share.group_key)
+        )
+    } else {
+        format!(
+            "Share of participant {} is well-formed, public share {}. Give the shares of {} \
+             other participants to check it against the group key",
+            share.index,
+            hex::encode(public_share),
+            share.threshold - 1
+        )
+    };
+    report(
+        "share_verified",
+        &message,
+        json!({
+            "index": share.index,
+            "share_file": share_file,
+            "public_share": hex::encode(public_share),
+            "group_key_fingerprint": envelope::fingerprint(&share.group_key),
+            "verified": verified,
+            "signers": signers,
+        }),
+    );
+    Ok(())
+}
+
+/// Decodes one typed-in line, correcting a single substituted character or a swap of
+/// two neighbouring characters if exactly one correction matches the line's CRC.
+fn decode_line(
+    number: usize,
+    text: &str,
+) -> Result<[u8; PAPER_LINE_BYTES], Box<dyn std::error::Error>> {
+    // Step 1: Normalize the characters and split off the CRC
+    let chars: Vec<u8> = text
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(normalize_char)
+        .collect::<Result<_, _>>()?;
+    if chars.len() != 20 {
+        return Err(format!(
+            "Line {} of the paper backup has {} characters, expected 16 and a 4-digit CRC",
+            number,
+            chars.len()
+        )
+        .into());
+    }
+    let (data, crc) = chars.split_at(16);
+    let crc = u16::from_str_radix(std::str::from_utf8(crc)?, 16)
+        .map_err(|_| format!("Line {} of the paper backup has an invalid CRC", number))?;
+    let data: Vec<u8> = data.iter().map(|c| symbol_value(*c)).collect();
+
+    // Step 2: Accept the line as typed if it matches its CRC
+    let line = decode_symbols(&data);
+    if line_crc(number, &line) == crc {
+        return Ok(line);
+    }
+
+    // Step 3: Otherwise look for the one correction that matches
+    let mut candidates = Vec::new();
+    for position in 0..data.len() {
+        for value in 0..32 {
+            if value != data[position] {
+                let mut candidate = data.clone();
+                candidate[position] = value;
+                candidates.push(candidate);
+            }
+        }
+        if position + 1 < data.len() && data[position] != data[position + 1] {
+            let mut candidate = data.clone();
+            candidate.swap(position, position + 1);
+            candidates.push(candidate);
+        }
+    }
+    let matches: Vec<[u8; PAPER_LINE_BYTES]> = candidates
+        .iter()
+        .map(|candidate| decode_symbols(candidate))
+        .filter(|candidate| line_crc(number, candidate) == crc)
+        .collect();
+    match matches.as_slice() {
+        [line] => {
+            warn!("Corrected a transcription error on line {}", number);
+            Ok(*line)
+        }
+        _ => Err(format!(
+            "Line {} of the paper backup does not match its CRC, check it for typos",
+            number
+        )
+        .into()),
+    }
+}
+
+/// Maps a typed character to the Crockford base32 alphabet.
+fn normalize_char(c: char) -> Result<u8, Box<dyn std::error::Error>> {
+    let c = match c.to_ascii_uppercase() {
+        'O' => '0',
+        'I' | 'L' => '1',
+        'U' => 'V',
+        c => c,
+    };
+    if c.is_ascii() && PAPER_ALPHABET.contains(&(c as u8)) {
+        Ok(c as u8)
+    } else {
+        Err(format!("Invalid character in paper backup: {}", c).into())
+    }
+}
+
+fn symbol_value(c: u8) -> u8 {
+    PAPER_ALPHABET
+        .iter()
+        .position(|symbol| *symbol == c)
+        .unwrap_or_default() as u8
+}
+
+/// Encodes 10 bytes as 16 base32 characters.
+fn encode_line(bytes: &[u8; PAPER_LINE_BYTES]) -> String {
+    let mut bits = [0u8; 16];
+    bits[6..].copy_from_slice(bytes);
+    let bits = u128::from_be_bytes(bits);
+    (0..16)
+        .map(|i| PAPER_ALPHABET[((bits >> (75 - 5 * i)) & 31) as usize] as char)
+        .collect()
+}
+
+/// Decodes 16 base32 symbol values back into 10 bytes.
+fn decode_symbols(symbols: &[u8]) -> [u8; PAPER_LINE_BYTES] {
+    let bits = symbols
+        .iter()
+        .fold(0u128, |bits, symbol| (bits << 5) | u128::from(*symbol));
+    let mut bytes = [0u8; PAPER_LINE_BYTES];
+    bytes.copy_from_slice(&bits.to_be_bytes()[6..]);
+    bytes
+}
+
+/// CRC-16/CCITT-FALSE of a line's number and bytes, so swapped lines are caught too.
+fn line_crc(number: usize, bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in std::iter::once(number as u8).chain(bytes.iter().copied()) {
+        crc ^= u16::from(byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// First four bytes of the SHA-256 digest of a serialized share.
+fn paper_checksum(body: &[u8]) -> [u8; 4] {
+    let mut h = Sha256::new();
+    h.update(PAPER_CONTEXT);
+    h.update(body);
+    let digest = h.finalize();
+    [digest[0], digest[1], digest[2], digest[3]]
+}
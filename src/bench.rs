@@ -0,0 +1,176 @@
+//! Measuring how long each step takes for a range of parameters.
+//!
+//! [`run`] deals ristretto255 keys for every pair of a threshold and a number of
+//! participants, and signs a test message with the first threshold of participants
+//! several times. It times the key generation, each signer's commit and partial
+//! signing, the aggregation and the verification, so operators can choose parameters
+//! knowing their latency before running a real ceremony. Everything is kept in memory
+//! and no network is involved, so the timings leave out the round trips between the
+//! signers and the coordinator.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::keygen::deal_keys;
+use crate::memlock::Locked;
+use crate::signing::{
+    aggregate_signature, commit_with_share, partial_sign_with_share, CommitOptions,
+};
+use crate::types::{ParticipantShare, SIGNING_CONTEXT};
+use crate::verify::verify_with_group_key;
+use frost_dalek::{compute_message_hash, GroupKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::time::{Duration, Instant};
+use tracing::info_span;
+
+/// The message signed in every round of the benchmark.
+const BENCH_MESSAGE: &str = "frost-cli bench";
+
+/// The timings of one threshold and number of participants.
+#[derive(Clone, Debug)]
+pub struct BenchResult {
+    pub threshold: u32,
+    pub participants: u32,
+    /// Dealing the keys of all participants.
+    pub keygen: Duration,
+    /// Signing round one of one signer.
+    pub commit: Duration,
+    /// Signing round two of one signer.
+    pub partial_sign: Duration,
+    /// Combining the partial signatures of all signers.
+    pub aggregate: Duration,
+    /// Verifying the threshold signature.
+    pub verify: Duration,
+}
+
+/// Measures every step for each threshold in `thresholds` with each number of
+/// participants in `participants`, skipping the pairs whose threshold is larger.
+///
+/// # Arguments
+/// - `thresholds`: Thresholds to measure.
+/// - `participants`: Numbers of participants to measure.
+/// - `rounds`: Number of signatures to average the signing steps over. The keys are
+///   generated once per pair.
+///
+/// # Errors
+/// Returns an error if no pair has a threshold of at least 1 and at most its number of
+/// participants, `rounds` is zero, or a step fails.
+pub fn run(
+    thresholds: &[u32],
+    participants: &[u32],
+    rounds: u32,
+) -> Result<Vec<BenchResult>, Box<dyn std::error::Error>> {
+    if rounds == 0 {
+        return Err(
+            FrostCliError::InvalidParameters("Number of rounds must be at least 1".into()).into(),
+        );
+    }
+    let pairs: Vec<(u32, u32)> = participants
+        .iter()
+        .flat_map(|&n| thresholds.iter().map(move |&t| (t, n)))
+        .filter(|&(t, n)| t >= 1 && t <= n)
+        .collect();
+    if pairs.is_empty() {
+        return Err(FrostCliError::InvalidParameters(
+            "No threshold is between 1 and the number of participants".into(),
+        )
+        .into());
+    }
+    pairs
+        .into_iter()
+        .map(|(t, n)| bench_parameters(t, n, rounds))
+        .collect()
+}
+
+/// Measures every step for one threshold and number of participants.
+fn bench_parameters(
+    t: u32,
+    n: u32,
+    rounds: u32,
+) -> Result<BenchResult, Box<dyn std::error::Error>> {
+    let _span = info_span!("bench", t, n, rounds).entered();
+    // Step 1: Deal the keys and take the shares of the first t participants
+    let start = Instant::now();
+    let frost_keys = Locked::new(deal_keys(t, n, &|_| {})?)?;
+    let keygen = start.elapsed();
+    let shares = frost_keys
+        .private_shares
+        .iter()
+        .take(t as usize)
+        .map(|(share, index)| {
+            Locked::new(ParticipantShare {
+                index: *index,
+                share: *share,
+                group_key: frost_keys.group_key,
+                threshold: t,
+                participants: n,
+                verification_shares: frost_keys.verification_shares.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let group_key =
+        GroupKey::from_bytes(frost_keys.group_key).map_err(|_| "Invalid group public key")?;
+    let message_hash = compute_message_hash(SIGNING_CONTEXT.as_bytes(), BENCH_MESSAGE.as_bytes());
+
+    // Step 2: Sign the message `rounds` times, timing each step
+    let mut commit = Duration::ZERO;
+    let mut partial_sign = Duration::ZERO;
+    let mut aggregate = Duration::ZERO;
+    let mut verify = Duration::ZERO;
+    for _ in 0..rounds {
+        let mut session_id = [0u8; 16];
+        OsRng.fill_bytes(&mut session_id);
+        let session_id = hex::encode(session_id);
+
+        let mut roster = Vec::new();
+        let mut secret_commitments = Vec::new();
+        for share in &shares {
+            let start = Instant::now();
+            let (public, secret) = commit_with_share(&session_id, share, CommitOptions::default())?;
+            commit += start.elapsed();
+            roster.push(public);
+            secret_commitments.push(secret);
+        }
+
+        let mut partial_signatures = Vec::new();
+        for (share, secret) in shares.iter().zip(&secret_commitments) {
+            let start = Instant::now();
+            let (partial, _) =
+                partial_sign_with_share(BENCH_MESSAGE, SIGNING_CONTEXT, share, secret, &roster)?;
+            partial_sign += start.elapsed();
+            partial_signatures.push(partial);
+        }
+
+        let start = Instant::now();
+        let signature = aggregate_signature(
+            &message_hash,
+            &group_key,
+            &roster,
+            &frost_keys.verification_shares,
+            &partial_signatures,
+            0,
+        )?;
+        aggregate += start.elapsed();
+
+        let start = Instant::now();
+        verify_with_group_key(
+            BENCH_MESSAGE.as_bytes(),
+            SIGNING_CONTEXT,
+            Ciphersuite::Ristretto255,
+            &frost_keys.group_key,
+            &signature.to_bytes(),
+        )?;
+        verify += start.elapsed();
+    }
+
+    // Step 3: Average the signing steps per signer and per signature
+    Ok(BenchResult {
+        threshold: t,
+        participants: n,
+        keygen,
+        commit: commit / (rounds * t),
+        partial_sign: partial_sign / (rounds * t),
+        aggregate: aggregate / rounds,
+        verify: verify / rounds,
+    })
+}
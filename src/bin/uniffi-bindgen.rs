@@ -0,0 +1,4 @@
+//! Generates the Kotlin and Swift bindings of the `uniffi` feature, see `src/mobile.rs`.
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,373 @@
+//! Checking that key files, signature files and this build of frost-cli work together
+//! before a ceremony begins.
+//!
+//! A signing or verification ceremony that stops halfway because one participant's key
+//! file was written by an older release, or because their build lacks the key's
+//! ciphersuite, costs every other participant their time. [`check_compatibility`] reads
+//! a key file and optionally a signature file without using any secret material, and
+//! returns every mismatch between them and the running binary: file format versions,
+//! ciphersuites the build supports, the signing context, threshold parameters, and
+//! whether the signature names the key's group, ciphersuite and a threshold of its
+//! participants. Each mismatch comes with what to do about it.
+
+use crate::backend::{self, Ciphersuite};
+use crate::inspect::{inspect_key, inspect_signature, KeyInspection};
+use crate::io::files::FileOptions;
+use crate::io::signature::SignatureFormat;
+use crate::{io, read_json, storage, FORMAT_VERSION};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+
+/// One thing checked and whether it passed.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// What was checked: `key_file` or `signature_file`.
+    pub subject: String,
+    pub ok: bool,
+    /// What was found, and for a mismatch, how to resolve it.
+    pub message: String,
+}
+
+/// The findings of [`check_compatibility`].
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CompatibilityReport {
+    /// Version of the running frost-cli.
+    pub tool_version: String,
+    /// File format version it reads and writes, see [`FORMAT_VERSION`].
+    pub format_version: u32,
+    /// Ciphersuites this build can sign and verify in.
+    pub ciphersuites: Vec<Ciphersuite>,
+    pub findings: Vec<Finding>,
+}
+
+impl CompatibilityReport {
+    /// Returns whether nothing checked is a mismatch.
+    pub fn is_compatible(&self) -> bool {
+        self.findings.iter().all(|finding| finding.ok)
+    }
+
+    fn pass(&mut self, subject: &str, message: String) {
+        self.push(subject, true, message);
+    }
+
+    fn fail(&mut self, subject: &str, message: String) {
+        self.push(subject, false, message);
+    }
+
+    fn push(&mut self, subject: &str, ok: bool, message: String) {
+        self.findings.push(Finding {
+            subject: subject.to_string(),
+            ok,
+            message,
+        });
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ciphersuites: Vec<String> = self.ciphersuites.iter().map(|c| c.to_string()).collect();
+        write!(
+            f,
+            "frost-cli {}, file format version {}, ciphersuites {}",
+            self.tool_version,
+            self.format_version,
+            ciphersuites.join(", ")
+        )?;
+        for finding in &self.findings {
+            let status = if finding.ok { "OK" } else { "MISMATCH" };
+            write!(f, "\n{} {}: {}", status, finding.subject, finding.message)?;
+        }
+        let verdict = if self.is_compatible() {
+            "Compatible"
+        } else {
+            "Not compatible"
+        };
+        write!(f, "\n{}", verdict)
+    }
+}
+
+/// Checks that a key file, and a signature file if given, can be used together by this
+/// build of frost-cli.
+///
+/// # Arguments
+/// - `key_file`: Path to the key file, participant share file, share bundle or PEM group
+///   key, or a share location, see [`crate::storage`].
+/// - `signature_file`: Path to a signature file to check against the key.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+/// - `context`: Context string signatures are made and verified in, see
+///   [`crate::SIGNING_CONTEXT`].
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// Files that cannot be read are findings of the report like any other mismatch, see
+/// [`CompatibilityReport::is_compatible`].
+pub fn check_compatibility(
+    key_file: &str,
+    signature_file: Option<&str>,
+    sig_format: Option<SignatureFormat>,
+    context: &str,
+    options: &FileOptions,
+) -> CompatibilityReport {
+    // Step 1: Record what the running binary supports
+    let mut report = CompatibilityReport {
+        tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        format_version: FORMAT_VERSION,
+        ciphersuites: [
+            Ciphersuite::Ristretto255,
+            Ciphersuite::Ed25519,
+            Ciphersuite::Secp256k1,
+        ]
+        .into_iter()
+        .filter(|&ciphersuite| backend::open(ciphersuite).is_ok())
+        .collect(),
+        findings: Vec::new(),
+    };
+
+    // Step 2: Check the key file's format version, ciphersuite and threshold
+    let key = check_key_file(&mut report, key_file, context, options);
+
+    // Step 3: Check the signature file against the binary and the key
+    if let Some(signature_file) = signature_file {
+        check_signature_file(
+            &mut report,
+            signature_file,
+            sig_format,
+            context,
+            key.as_ref(),
+        );
+    }
+    report
+}
+
+fn check_key_file(
+    report: &mut CompatibilityReport,
+    key_file: &str,
+    context: &str,
+    options: &FileOptions,
+) -> Option<KeyInspection> {
+    const SUBJECT: &str = "key_file";
+    if let Err(message) = check_format_version(key_file) {
+        report.fail(SUBJECT, message);
+        return None;
+    }
+    let key = match inspect_key(key_file, options) {
+        Ok(key) => key,
+        Err(err) => {
+            report.fail(SUBJECT, format!("{} cannot be read: {}", key_file, err));
+            return None;
+        }
+    };
+    report.pass(
+        SUBJECT,
+        format!(
+            "{} is a {} of group key {}",
+            key_file, key.kind, key.fingerprint
+        ),
+    );
+
+    match backend::open(key.ciphersuite) {
+        Ok(_) => report.pass(
+            SUBJECT,
+            format!("Ciphersuite {} is supported", key.ciphersuite),
+        ),
+        Err(err) => report.fail(
+            SUBJECT,
+            format!(
+                "Ciphersuite {} is not supported: {}, rebuild with `--features {}`",
+                key.ciphersuite, err, key.ciphersuite
+            ),
+        ),
+    }
+    if let Err(err) = key.ciphersuite.check_signing_context(context) {
+        report.fail(SUBJECT, format!("{}, run without `--context`", err));
+    }
+
+    if let (Some(threshold), Some(participants)) = (key.threshold, key.participants) {
+        if threshold == 0 || threshold > participants {
+            report.fail(
+                SUBJECT,
+                format!(
+                    "Threshold {} of {} participants cannot sign, generate new keys",
+                    threshold, participants
+                ),
+            );
+        } else if key
+            .indices
+            .iter()
+            .any(|&index| index == 0 || index > participants)
+        {
+            report.fail(
+                SUBJECT,
+                format!(
+                    "Shares of participants {:?} are not among participants 1 to {}",
+                    key.indices, participants
+                ),
+            );
+        } else {
+            report.pass(
+                SUBJECT,
+                format!("Threshold {} of {}", threshold, participants),
+            );
+        }
+    }
+    Some(key)
+}
+
+fn check_signature_file(
+    report: &mut CompatibilityReport,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    context: &str,
+    key: Option<&KeyInspection>,
+) {
+    const SUBJECT: &str = "signature_file";
+    if sig_format.unwrap_or(SignatureFormat::Json) == SignatureFormat::Json {
+        if let Err(message) = check_format_version(signature_file) {
+            report.fail(SUBJECT, message);
+            return;
+        }
+    }
+    let signature = match inspect_signature(signature_file, sig_format) {
+        Ok(signature) => signature,
+        Err(err) => {
+            report.fail(
+                SUBJECT,
+                format!("{} cannot be read: {}", signature_file, err),
+            );
+            return;
+        }
+    };
+    if signature.r.is_none() {
+        report.pass(
+            SUBJECT,
+            format!(
+                "{} is a {} envelope, checked against the key by `verify`",
+                signature_file, signature.format
+            ),
+        );
+        return;
+    }
+    if signature.threshold_signature == Some(false) {
+        report.fail(
+            SUBJECT,
+            format!(
+                "{} does not hold a valid Ristretto255 signature",
+                signature_file
+            ),
+        );
+    }
+    let Some(metadata) = signature.metadata else {
+        report.pass(
+            SUBJECT,
+            format!(
+                "{} is a {} signature without metadata, only `verify` can check it against the key",
+                signature_file, signature.format
+            ),
+        );
+        return;
+    };
+    let Some(key) = key else {
+        return;
+    };
+
+    // The signature names the key's group, ciphersuite and context
+    let failures = report.findings.iter().filter(|finding| !finding.ok).count();
+    if metadata.group_key_fingerprint != key.fingerprint {
+        report.fail(
+            SUBJECT,
+            format!(
+                "Signed by group key {}, not the key file's {}",
+                metadata.group_key_fingerprint, key.fingerprint
+            ),
+        );
+    }
+    if metadata.ciphersuite != key.ciphersuite {
+        report.fail(
+            SUBJECT,
+            format!(
+                "Signed in ciphersuite {}, not the key file's {}",
+                metadata.ciphersuite, key.ciphersuite
+            ),
+        );
+    }
+    if metadata.context != metadata.ciphersuite.signing_context(context) {
+        report.fail(
+            SUBJECT,
+            format!(
+                "Signed in context {:?}, verify with `--context {}`",
+                metadata.context, metadata.context
+            ),
+        );
+    }
+
+    // Its signers are a threshold of the key's participants
+    if let (Some(threshold), Some(participants)) = (key.threshold, key.participants) {
+        if (metadata.signers.len() as u32) < threshold {
+            report.fail(
+                SUBJECT,
+                format!(
+                    "Signed by {} signers, fewer than the threshold of {}",
+                    metadata.signers.len(),
+                    threshold
+                ),
+            );
+        }
+        if metadata
+            .signers
+            .iter()
+            .any(|&signer| signer == 0 || signer > participants)
+        {
+            report.fail(
+                SUBJECT,
+                format!(
+                    "Signers {:?} are not among participants 1 to {}",
+                    metadata.signers, participants
+                ),
+            );
+        }
+    }
+    if report.findings.iter().filter(|finding| !finding.ok).count() == failures {
+        report.pass(
+            SUBJECT,
+            format!(
+                "Signed by participants {:?} of group key {} in ciphersuite {}",
+                metadata.signers, metadata.group_key_fingerprint, metadata.ciphersuite
+            ),
+        );
+    }
+}
+
+/// Checks that a key or signature file is in the format version this build reads, with
+/// what to do if not. Files that carry no version, such as PEM group keys and shares kept
+/// in share storage, pass.
+fn check_format_version(path: &str) -> Result<(), String> {
+    if !storage::is_file(path) || fs::read(path).is_ok_and(|bytes| io::pem::is_pem(&bytes)) {
+        return Ok(());
+    }
+    let Ok(value) = read_json(path) else {
+        return Ok(());
+    };
+    let legacy = format!(
+        "{} was written before file format version {}, upgrade it with `frost-cli migrate --file {}`",
+        path, FORMAT_VERSION, path
+    );
+    match &value {
+        Value::Array(_) => Err(legacy),
+        Value::Object(object) => match object.get("version").and_then(Value::as_u64) {
+            Some(version) if version > u64::from(FORMAT_VERSION) => Err(format!(
+                "{} has file format version {}, but frost-cli {} reads version {}, upgrade frost-cli",
+                path,
+                version,
+                env!("CARGO_PKG_VERSION"),
+                FORMAT_VERSION
+            )),
+            Some(version) if version < u64::from(FORMAT_VERSION) => Err(legacy),
+            _ if !object.contains_key("checksum") && !object.contains_key("ciphertext") => {
+                Err(legacy)
+            }
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,91 @@
+//! Environment variables that stand in for flags, paths and secrets.
+//!
+//! Containers and CI jobs configure a tool through its environment rather than through
+//! flags written into job definitions. Every setting frost-cli reads from the
+//! environment is named here, and each is resolved the same way: a flag given on the
+//! command line wins over the variable, and the variable wins over the default.
+//!
+//! Secrets such as the PKCS#11 PIN or the Vault token can also be given as a file
+//! holding them, named by the variable with a `_FILE` suffix, so they can be mounted as
+//! container secrets instead of being exported, see [`var`].
+
+use std::env;
+use std::fs;
+
+/// Key file of the commands that take `--key-file`.
+pub const KEY_FILE: &str = "FROST_KEY_FILE";
+
+/// Participant share file of the commands that read one.
+pub const SHARE_FILE: &str = "FROST_SHARE_FILE";
+
+/// Participant identity file of the commands that read one.
+pub const IDENTITY_FILE: &str = "FROST_IDENTITY_FILE";
+
+/// Socket of the signer daemon, see [`crate::daemon`].
+pub const DAEMON_SOCKET: &str = "FROST_DAEMON_SOCKET";
+
+/// Keystore directory, see [`crate::keystore::keystore_dir`].
+pub const KEYSTORE: &str = "FROST_KEYSTORE";
+
+/// Where participant shares are kept, as given to `--store`.
+pub const STORE: &str = "FROST_STORE";
+
+/// Format files are written in, as given to `--format`.
+pub const FORMAT: &str = "FROST_FORMAT";
+
+/// How command results are printed, as given to `--output`.
+pub const OUTPUT: &str = "FROST_OUTPUT";
+
+/// Context string messages are signed in, as given to `--context`.
+pub const CONTEXT: &str = "FROST_CONTEXT";
+
+/// Transcript signing ceremonies are recorded in, as given to `--transcript`.
+pub const TRANSCRIPT: &str = "FROST_TRANSCRIPT";
+
+/// PKCS#11 module of `pkcs11` share storage.
+pub const PKCS11_MODULE: &str = "FROST_PKCS11_MODULE";
+
+/// User PIN of the PKCS#11 token, a secret.
+pub const PKCS11_PIN: &str = "FROST_PKCS11_PIN";
+
+/// KMS key new shares are encrypted under in AWS Secrets Manager.
+pub const AWS_KMS_KEY_ID: &str = "FROST_AWS_KMS_KEY_ID";
+
+/// Passphrase the checksums of key files and share files are keyed with, a secret, read
+/// into [`crate::FileOptions::file_key`] by the command line.
+pub const FILE_KEY: &str = "FROST_FILE_KEY";
+
+/// Address of the Vault server.
+pub const VAULT_ADDR: &str = "VAULT_ADDR";
+
+/// Vault token, a secret.
+pub const VAULT_TOKEN: &str = "VAULT_TOKEN";
+
+/// Role ID of the Vault AppRole.
+pub const VAULT_ROLE_ID: &str = "VAULT_ROLE_ID";
+
+/// Secret ID of the Vault AppRole, a secret.
+pub const VAULT_SECRET_ID: &str = "VAULT_SECRET_ID";
+
+/// Vault Enterprise namespace.
+pub const VAULT_NAMESPACE: &str = "VAULT_NAMESPACE";
+
+/// Returns the value of the environment variable `name`, or else the contents of the
+/// file named by `<name>_FILE` without its trailing newline. Empty values count as
+/// unset.
+///
+/// # Errors
+/// Returns an error if `<name>_FILE` names a file that cannot be read.
+pub fn var(name: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if let Some(value) = env::var(name).ok().filter(|value| !value.is_empty()) {
+        return Ok(Some(value));
+    }
+    let file_var = format!("{}_FILE", name);
+    let Some(path) = env::var(&file_var).ok().filter(|path| !path.is_empty()) else {
+        return Ok(None);
+    };
+    let value = fs::read_to_string(&path)
+        .map_err(|err| format!("Failed to read {} from {}: {}", file_var, path, err))?;
+    let value = value.trim_end_matches(['\r', '\n']).to_string();
+    Ok(Some(value).filter(|value| !value.is_empty()))
+}
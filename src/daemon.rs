@@ -0,0 +1,562 @@
+//! A signer daemon that keeps a participant share loaded between signing sessions.
+//!
+//! Busy signers otherwise load their share for every commit and partial signature,
+//! unlocking the keyring, PKCS#11 token or Vault each time. [`serve`] loads the share
+//! once into a [`Locked`] allocation and answers requests on a Unix domain socket that
+//! only its owner may connect to. Each request and response is one line of JSON-RPC
+//! 2.0:
+//!
+//! - `status` returns the participant index, threshold, number of participants and the
+//!   fingerprint of the group key.
+//! - `commit` with `{"session_id": ...}` runs signing round one and returns the public
+//!   commitments. The secret nonces never leave the daemon's memory.
+//! - `partial_sign` with `{"message": ..., "context": ..., "roster": [...]}` runs
+//!   signing round two with the nonces committed for the roster's session and returns
+//!   the partial signature. The context defaults to [`crate::SIGNING_CONTEXT`].
+//!
+//! The nonces of a session are taken out of memory before it is signed and wiped right
+//! after, whether signing succeeded or not, so they can never sign a second message.
+//! They only live as long as the daemon: a session committed to before a restart has to
+//! commit again. [`call`] sends one request to a running daemon, and [`commit`] and
+//! [`partial_sign`] exchange the usual round message files through it.
+//!
+//! How long each commit and partial signature took is recorded in [`crate::metrics`],
+//! which `daemon serve --metrics-listen` serves over HTTP.
+#![cfg_attr(not(unix), allow(dead_code, unused_imports))]
+
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::metrics::{self, Round};
+use crate::signing::{
+    commit_with_share, partial_sign_with_share, CommitOptions, PublicCommitments, SecretCommitments,
+};
+use crate::signing::{load_roster, PartialSignature};
+use crate::types::default_signing_context;
+use crate::{load_participant_share, write_json, FileOptions, ParticipantShare};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Largest request line accepted from a client, well above any signer roster.
+const MAX_REQUEST_LEN: u64 = 16 * 1024 * 1024;
+
+/// Largest number of sessions committed to but not signed yet.
+const MAX_SESSIONS: usize = 1024;
+
+/// JSON-RPC error code of a request that is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code of a request that is not a JSON-RPC 2.0 request.
+pub const INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code of an unknown method.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code of parameters the method does not take.
+pub const INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code of a request the daemon failed to carry out.
+pub const REQUEST_FAILED: i64 = -32000;
+
+/// A JSON-RPC 2.0 request.
+#[derive(Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// A JSON-RPC 2.0 response, holding either a result or an error.
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// Why the daemon did not answer a request with a result.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// Parameters of `commit`.
+#[derive(Serialize, Deserialize)]
+pub struct CommitParams {
+    pub session_id: String,
+}
+
+/// Parameters of `partial_sign`.
+#[derive(Serialize, Deserialize)]
+pub struct PartialSignParams {
+    pub message: String,
+    /// Context string the message is signed in.
+    #[serde(default = "default_signing_context")]
+    pub context: String,
+    /// Public commitments of every signer of the session, including this one.
+    pub roster: Vec<PublicCommitments>,
+}
+
+/// The share a daemon signs with and the nonces of the sessions it committed to.
+struct Daemon {
+    share: Locked<ParticipantShare>,
+    sessions: Mutex<HashMap<String, Locked<SecretCommitments>>>,
+}
+
+impl Daemon {
+    /// Answers one request line.
+    fn answer(&self, line: &str) -> Response {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(err) => {
+                return Response::error(Value::Null, PARSE_ERROR, format!("Parse error: {}", err))
+            }
+        };
+        if request.jsonrpc != "2.0" {
+            return Response::error(
+                request.id,
+                INVALID_REQUEST,
+                "Only JSON-RPC 2.0 requests are answered".into(),
+            );
+        }
+        match self.dispatch(&request.method, request.params) {
+            Ok(result) => Response {
+                jsonrpc: "2.0".into(),
+                id: request.id,
+                result: Some(result),
+                error: None,
+            },
+            Err(err) => Response::error(request.id, err.code, err.message),
+        }
+    }
+
+    fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let failed = |err: Box<dyn std::error::Error>| RpcError {
+            code: REQUEST_FAILED,
+            message: err.to_string(),
+        };
+        match method {
+            "status" => Ok(json!({
+                "index": self.share.index,
+                "threshold": self.share.threshold,
+                "participants": self.share.participants,
+                "group_key_fingerprint": envelope::fingerprint(&self.share.group_key),
+            })),
+            "commit" => {
+                let params: CommitParams = parse_params(params)?;
+                let started = Instant::now();
+                let result = self.commit(&params.session_id);
+                metrics::round_finished(Round::Commit, started.elapsed());
+                result.map_err(failed)
+            }
+            "partial_sign" => {
+                let params: PartialSignParams = parse_params(params)?;
+                let started = Instant::now();
+                let result = self.partial_sign(&params.message, &params.context, &params.roster);
+                metrics::round_finished(Round::PartialSign, started.elapsed());
+                result.map_err(failed)
+            }
+            _ => Err(RpcError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unknown method: {}", method),
+            }),
+        }
+    }
+
+    /// Runs signing round one for a session and keeps its nonces in memory.
+    fn commit(&self, session_id: &str) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| "Session table is poisoned")?;
+        if sessions.contains_key(session_id) {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Already committed to session {}",
+                session_id
+            ))
+            .into());
+        }
+        if sessions.len() >= MAX_SESSIONS {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "{} sessions are waiting to be signed, sign them or restart the daemon",
+                MAX_SESSIONS
+            ))
+            .into());
+        }
+        let (public_commitments, secret_commitments) =
+            commit_with_share(session_id, &self.share, CommitOptions::default())?;
+        sessions.insert(session_id.to_string(), secret_commitments);
+
+        report(
+            "daemon_committed",
+            &format!(
+                "Participant {} committed to session {}",
+                self.share.index, session_id
+            ),
+            json!({ "index": self.share.index, "session_id": session_id }),
+        );
+        Ok(serde_json::to_value(public_commitments)?)
+    }
+
+    /// Runs signing round two with the nonces of the roster's session, which are wiped
+    /// whether it succeeds or not.
+    fn partial_sign(
+        &self,
+        message: &str,
+        context: &str,
+        roster: &[PublicCommitments],
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        // Step 1: Take the session's nonces out of memory before anything is signed
+        let session_id = roster
+            .first()
+            .map(|signer| signer.session_id.clone())
+            .ok_or_else(|| FrostCliError::InvalidParameters("Signer roster is empty".into()))?;
+        if roster.iter().any(|signer| signer.session_id != session_id) {
+            return Err(FrostCliError::InvalidParameters(
+                "Signer roster mixes commitments of several sessions".into(),
+            )
+            .into());
+        }
+        let secret_commitments = self
+            .sessions
+            .lock()
+            .map_err(|_| "Session table is poisoned")?
+            .remove(&session_id)
+            .ok_or_else(|| FrostCliError::NonceReuse {
+                state: "daemon".into(),
+                reason: format!(
+                    "No unused nonces for session {}, it was already signed or never \
+                     committed to",
+                    session_id
+                ),
+            })?;
+
+        // Step 2: Sign, dropping the nonces on the way out
+        let (partial_signature, _) =
+            partial_sign_with_share(message, context, &self.share, &secret_commitments, roster)?;
+
+        report(
+            "daemon_partial_signed",
+            &format!(
+                "Participant {} signed session {}",
+                self.share.index, session_id
+            ),
+            json!({ "index": self.share.index, "session_id": session_id }),
+        );
+        Ok(serde_json::to_value(partial_signature)?)
+    }
+}
+
+impl Response {
+    fn error(id: Value, code: i64, message: String) -> Self {
+        Response {
+            jsonrpc: "2.0".into(),
+            id,
+            result: None,
+            error: Some(RpcError { code, message }),
+        }
+    }
+}
+
+/// Parses the parameters of a method.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|err| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("Invalid params: {}", err),
+    })
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::{Daemon, Request, Response, MAX_REQUEST_LEN};
+    use crate::error::FrostCliError;
+    use std::fs;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::process;
+    use std::sync::Arc;
+    use std::thread;
+    use tracing::warn;
+
+    /// Binds the socket, replacing the socket of a daemon that is no longer running,
+    /// and lets only the owner connect.
+    ///
+    /// The socket is bound inside a directory only the owner can enter and moved into
+    /// place once it is private, so nobody can connect in between.
+    pub(super) fn bind(socket: &str) -> Result<UnixListener, Box<dyn std::error::Error>> {
+        let path = Path::new(socket);
+        if path.exists() {
+            if UnixStream::connect(socket).is_ok() {
+                return Err(format!("A daemon is already listening on {}", socket).into());
+            }
+            fs::remove_file(socket).map_err(|err| FrostCliError::io(socket, err))?;
+        }
+        let name = path.file_name().ok_or_else(|| {
+            FrostCliError::InvalidParameters(format!("{} does not name a socket", socket))
+        })?;
+        let parent = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty());
+        let private_dir = parent.unwrap_or(Path::new(".")).join(format!(
+            ".{}.{}",
+            name.to_string_lossy(),
+            process::id()
+        ));
+        fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&private_dir)
+            .map_err(|err| FrostCliError::io(&private_dir, err))?;
+        let private_socket = private_dir.join(name);
+        let result = UnixListener::bind(&private_socket)
+            .and_then(|listener| {
+                fs::set_permissions(&private_socket, fs::Permissions::from_mode(0o600))?;
+                fs::rename(&private_socket, socket)?;
+                Ok(listener)
+            })
+            .map_err(|err| FrostCliError::io(socket, err));
+        let _ = fs::remove_file(&private_socket);
+        let _ = fs::remove_dir(&private_dir);
+        Ok(result?)
+    }
+
+    /// Answers the connections to the socket until the process is stopped.
+    pub(super) fn run(
+        listener: UnixListener,
+        daemon: Daemon,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let daemon = Arc::new(daemon);
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let daemon = Arc::clone(&daemon);
+            thread::spawn(move || {
+                if let Err(err) = handle_connection(stream, &daemon) {
+                    warn!("Daemon connection failed: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Answers every request line of a connection.
+    fn handle_connection(
+        stream: UnixStream,
+        daemon: &Daemon,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+        loop {
+            let line = match read_line(&mut reader)? {
+                Some(line) => line,
+                None => return Ok(()),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            write_line(&mut writer, &daemon.answer(&line))?;
+        }
+    }
+
+    /// Sends one request to the daemon listening on `socket` and returns its response.
+    pub(super) fn request(
+        socket: &str,
+        request: &Request,
+    ) -> Result<Response, Box<dyn std::error::Error>> {
+        let stream = UnixStream::connect(socket).map_err(|err| FrostCliError::io(socket, err))?;
+        let mut writer = stream.try_clone()?;
+        write_line(&mut writer, request)?;
+        let line = read_line(&mut BufReader::new(stream))?
+            .ok_or("Daemon closed the connection without answering")?;
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// Reads one line of at most [`MAX_REQUEST_LEN`] bytes, or `None` once the peer
+    /// closed the connection.
+    fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let mut line = String::new();
+        let read = reader.take(MAX_REQUEST_LEN).read_line(&mut line)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        if !line.ends_with('\n') && read as u64 == MAX_REQUEST_LEN {
+            return Err(format!("Line of over {} bytes exceeds the size limit", read).into());
+        }
+        Ok(Some(line))
+    }
+
+    fn write_line<T: serde::Serialize, W: Write>(
+        writer: &mut W,
+        value: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut line = serde_json::to_string(value)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Runs a signer daemon that answers requests on `socket` until the process is
+/// stopped.
+///
+/// # Arguments
+/// - `socket`: Path of the Unix domain socket to listen on. A socket left behind by a
+///   daemon that is no longer running is replaced.
+/// - `share_file`: Share location of this participant's share, see [`crate::storage`].
+/// - `options`: File key the share was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded or locked, another daemon listens on
+/// the socket, or the socket cannot be bound.
+#[cfg(unix)]
+pub fn serve(
+    socket: &str,
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the share once, for every session to come
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let daemon = Daemon {
+        share,
+        sessions: Mutex::new(HashMap::new()),
+    };
+
+    // Step 2: Listen on the socket
+    let listener = unix::bind(socket)?;
+    report(
+        "daemon_listening",
+        &format!(
+            "Daemon of participant {} listening on: {}",
+            daemon.share.index, socket
+        ),
+        json!({ "index": daemon.share.index, "socket": socket }),
+    );
+    unix::run(listener, daemon)
+}
+
+#[cfg(not(unix))]
+pub fn serve(_socket: &str, _share_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("The signer daemon needs Unix domain sockets, which this platform lacks".into())
+}
+
+/// Calls `method` on the daemon listening on `socket` and returns its result.
+///
+/// # Errors
+/// Returns an error if the daemon cannot be reached or answers with an error.
+#[cfg(unix)]
+pub fn call(
+    socket: &str,
+    method: &str,
+    params: Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let request = Request {
+        jsonrpc: "2.0".into(),
+        id: json!(1),
+        method: method.to_string(),
+        params,
+    };
+    let response = unix::request(socket, &request)?;
+    if let Some(err) = response.error {
+        return Err(format!("Daemon refused {} ({}): {}", method, err.code, err.message).into());
+    }
+    response
+        .result
+        .ok_or_else(|| format!("Daemon answered {} without a result", method).into())
+}
+
+#[cfg(not(unix))]
+pub fn call(
+    _socket: &str,
+    _method: &str,
+    _params: Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    Err("The signer daemon needs Unix domain sockets, which this platform lacks".into())
+}
+
+/// Runs signing round one on the daemon listening on `socket` and saves the public
+/// commitments for the coordinator, like [`crate::signing::commit`] without a secret
+/// commitments file.
+///
+/// # Errors
+/// Returns an error if the daemon refuses the request or the file cannot be written.
+pub fn commit(
+    socket: &str,
+    session_id: &str,
+    public_commitments_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = call(socket, "commit", json!({ "session_id": session_id }))?;
+    let public_commitments: PublicCommitments = serde_json::from_value(result)?;
+    write_json(public_commitments_file, &public_commitments, options)?;
+
+    report(
+        "commitments_saved",
+        &format!(
+            "Commitments for participant {} saved to: {}",
+            public_commitments.index, public_commitments_file
+        ),
+        json!({
+            "index": public_commitments.index,
+            "session_id": session_id,
+            "commitments_file": public_commitments_file,
+            "count": 1,
+        }),
+    );
+    Ok(())
+}
+
+/// Runs signing round two on the daemon listening on `socket` and saves the partial
+/// signature for the coordinator, like [`crate::signing::partial_sign`].
+///
+/// # Arguments
+/// - `socket`: Path of the daemon's socket.
+/// - `message`: The message to sign.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `commitment_files`: Public commitment files of every signer, including this one.
+/// - `partial_signature_file`: Path to save the partial signature to.
+/// - `options`: How the partial signature is written.
+///
+/// # Errors
+/// Returns an error if a commitment file cannot be read, the daemon refuses the request
+/// or the file cannot be written.
+pub fn partial_sign(
+    socket: &str,
+    message: &str,
+    context: &str,
+    commitment_files: &[String],
+    partial_signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let roster = load_roster(commitment_files)?;
+    let params = serde_json::to_value(PartialSignParams {
+        message: message.to_string(),
+        context: context.to_string(),
+        roster,
+    })?;
+    let partial_signature: PartialSignature =
+        serde_json::from_value(call(socket, "partial_sign", params)?)?;
+    write_json(partial_signature_file, &partial_signature, options)?;
+
+    report(
+        "partial_signature_saved",
+        &format!(
+            "Partial signature of participant {} saved to: {}",
+            partial_signature.index, partial_signature_file
+        ),
+        json!({
+            "index": partial_signature.index,
+            "session_id": partial_signature.session_id,
+            "partial_signature_file": partial_signature_file,
+        }),
+    );
+    Ok(())
+}
@@ -0,0 +1,338 @@
+//! Trusted-dealer key generation with every share encrypted to its participant.
+//!
+//! [`crate::generate_keys`] writes all private shares into a single plaintext file,
+//! which then has to be split up and handed out by other means. With a roster of the
+//! participants' X25519 public keys, [`generate_encrypted_shares`] instead seals each
+//! participant's share file to their key in a one-way `Noise_N` handshake, so the
+//! resulting bundles can be sent over any channel. Each participant turns their bundle
+//! back into a regular share file with [`decrypt_share`]. The roster keys are the
+//! Noise keys of the participants' identities, see [`write_roster`].
+//!
+//! Every bundle also carries the verification shares of all participants, their shares
+//! times the base point. A participant checks their share against its verification
+//! share, and the verification shares against the group key, so a dealer cannot hand out
+//! a share that does not sign with the others. Participants who compare the verification
+//! shares of their bundles know they were all dealt from the same key.
+
+use crate::error::FrostCliError;
+use crate::identity::{load_identity, load_public_identity, Identity};
+use crate::io::envelope;
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::net::noise;
+use crate::signing::{lagrange_at, lagrange_coefficient};
+use crate::{
+    deal_keys, point_from_bytes, read_json, save_participant_share, scalar_from_bytes, write_json,
+    FileOptions, ParticipantShare,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity as _;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// A participant's public encryption key in a roster file.
+#[derive(Serialize, Deserialize)]
+pub struct RosterEntry {
+    pub index: u32,
+    /// X25519 public key the participant's share is sealed to.
+    pub public_key: [u8; 32],
+}
+
+/// A participant's share, encrypted to their roster key.
+#[derive(Serialize, Deserialize)]
+pub struct ShareBundle {
+    pub index: u32,
+    pub group_key: [u8; 32],
+    pub threshold: u32,
+    pub participants: u32,
+    /// Verification shares of participants 1 to `participants`, each their share times
+    /// the base point.
+    pub verification_shares: Vec<[u8; 32]>,
+    /// Hex-encoded Noise handshake message carrying the [`ParticipantShare`].
+    pub ciphertext: String,
+}
+
+/// Writes a roster file with the identity keys of participants 1 to `n`.
+///
+/// # Arguments
+/// - `identities_dir`: Directory containing the public identities of the participants.
+/// - `n`: Total number of participants.
+/// - `roster_file`: Path to save the roster.
+/// - `options`: How the roster is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if a participant's public identity is missing.
+pub fn write_roster(
+    identities_dir: &str,
+    n: u32,
+    roster_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut roster = Vec::with_capacity(n as usize);
+    for index in 1..=n {
+        let identity = load_public_identity(identities_dir, index)?;
+        roster.push(RosterEntry {
+            index,
+            public_key: identity.noise_public_key,
+        });
+    }
+    write_json(roster_file, &roster, options)?;
+
+    report(
+        "roster_saved",
+        &format!(
+            "Roster of {} participants saved to: {}",
+            roster.len(),
+            roster_file
+        ),
+        json!({ "roster_file": roster_file, "participants": roster.len() }),
+    );
+    Ok(())
+}
+
+/// Generates a group key and seals every participant's share to their roster key.
+///
+/// No plaintext share is ever written to disk.
+///
+/// # Arguments
+/// - `t`: Threshold value, the minimum number of participants required to sign.
+/// - `n`: Total number of participants.
+/// - `roster_file`: Path to the roster with the public key of every participant.
+/// - `output_dir`: Directory to write one `share_bundle_<index>.json` per participant to.
+/// - `options`: How the share bundles are written.
+///
+/// # Errors
+/// Returns an error if the roster does not contain exactly one key for each of the
+/// participants 1 to `n`, or the key generation fails.
+pub fn generate_encrypted_shares(
+    t: u32,
+    n: u32,
+    roster_file: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check that the roster covers every participant exactly once
+    let mut roster: Vec<RosterEntry> = read_json(roster_file)?;
+    roster.sort_by_key(|entry| entry.index);
+    let indices: Vec<u32> = roster.iter().map(|entry| entry.index).collect();
+    if indices != (1..=n).collect::<Vec<u32>>() {
+        return Err(format!(
+            "Expected roster keys for participants 1 to {}, found {:?}",
+            n, indices
+        )
+        .into());
+    }
+
+    // Step 2: Generate the keys and their verification shares
+    let frost_keys = Locked::new(deal_keys(t, n, &|_| {})?)?;
+    let verification_shares = frost_keys.verification_shares.clone();
+
+    // Step 3: Seal each share to its participant
+    fs::create_dir_all(output_dir)?;
+    for ((share, index), entry) in frost_keys.private_shares.iter().zip(&roster) {
+        if *index != entry.index {
+            return Err(format!("Unexpected share for participant {}", index).into());
+        }
+        let participant_share = ParticipantShare {
+            index: *index,
+            share: *share,
+            group_key: frost_keys.group_key,
+            threshold: t,
+            participants: n,
+            verification_shares: verification_shares.clone(),
+        };
+        let ciphertext = noise::seal_anonymous(
+            &entry.public_key,
+            &prologue(*index, &frost_keys.group_key),
+            &serde_json::to_vec(&participant_share)?,
+        )?;
+        write_json(
+            Path::new(output_dir).join(format!("share_bundle_{}.json", index)),
+            &ShareBundle {
+                index: *index,
+                group_key: frost_keys.group_key,
+                threshold: t,
+                participants: n,
+                verification_shares: verification_shares.clone(),
+                ciphertext: hex::encode(ciphertext),
+            },
+            options,
+        )?;
+    }
+
+    report(
+        "share_bundles_saved",
+        &format!(
+            "Generated {} shares with threshold {}. Encrypted share bundles saved to: {}",
+            n, t, output_dir
+        ),
+        json!({
+            "output_dir": output_dir,
+            "group_key_fingerprint": envelope::fingerprint(&frost_keys.group_key),
+            "threshold": t,
+            "participants": n,
+        }),
+    );
+    Ok(())
+}
+
+/// Decrypts a share bundle with the participant's identity and saves the share.
+///
+/// # Arguments
+/// - `identity_file`: Path to the participant's private identity.
+/// - `bundle_file`: Path to the participant's share bundle.
+/// - `share_file`: Share location to save the participant share to, see [`crate::storage`].
+/// - `options`: How the decrypted share is written.
+///
+/// # Errors
+/// Returns an error if the bundle belongs to another participant or was not sealed to
+/// this identity.
+pub fn decrypt_share(
+    identity_file: &str,
+    bundle_file: &str,
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the identity and the bundle
+    let identity = load_identity(identity_file)?;
+    let bundle: ShareBundle = read_json(bundle_file)?;
+
+    // Step 2: Decrypt the share and check it against the bundle
+    let share = open_share_bundle(&identity, &bundle)?;
+
+    // Step 3: Save the share
+    save_participant_share(share_file, &*share, options)?;
+    report(
+        "share_decrypted",
+        &format!(
+            "Share of participant {} decrypted to: {}",
+            share.index, share_file
+        ),
+        json!({
+            "index": share.index,
+            "share_file": share_file,
+            "group_key_fingerprint": envelope::fingerprint(&share.group_key),
+        }),
+    );
+    Ok(())
+}
+
+/// Decrypts a share bundle with the participant's identity, without touching any file.
+///
+/// # Errors
+/// Returns an error if the bundle belongs to another participant, was not sealed to
+/// this identity, holds a share other than the one it describes, or the share does not
+/// match the verification shares or they do not match the group key.
+pub fn open_share_bundle(
+    identity: &Identity,
+    bundle: &ShareBundle,
+) -> Result<Locked<ParticipantShare>, Box<dyn std::error::Error>> {
+    // Step 1: Check the bundle is this participant's
+    if bundle.index != identity.index {
+        return Err(format!(
+            "Share bundle belongs to participant {}, not to participant {}",
+            bundle.index, identity.index
+        )
+        .into());
+    }
+
+    // Step 2: Decrypt the share and check it against the bundle
+    let payload = Zeroizing::new(noise::open_anonymous(
+        identity,
+        &prologue(bundle.index, &bundle.group_key),
+        &hex::decode(&bundle.ciphertext)?,
+    )?);
+    let mut share: ParticipantShare = serde_json::from_slice(&payload)?;
+    if share.verification_shares.is_empty() {
+        share.verification_shares = bundle.verification_shares.clone();
+    }
+    let share = Locked::new(share)?;
+    share
+        .check()
+        .map_err(|reason| FrostCliError::InvalidShare {
+            share: format!("in the bundle of participant {}", bundle.index),
+            reason,
+        })?;
+    if share.index != bundle.index
+        || share.group_key != bundle.group_key
+        || share.threshold != bundle.threshold
+        || share.participants != bundle.participants
+        || share.verification_shares != bundle.verification_shares
+    {
+        return Err(FrostCliError::InvalidShare {
+            share: format!("in the bundle of participant {}", bundle.index),
+            reason: "Unexpected share in bundle".into(),
+        }
+        .into());
+    }
+
+    // Step 3: Check the share against the verification shares and those against the
+    // group key
+    check_verification_shares(&share, bundle)?;
+    Ok(share)
+}
+
+/// Checks that a share matches its verification share, and that the verification shares
+/// lie on one polynomial of degree `threshold - 1` whose value at zero is the group key,
+/// so any threshold of the shares can sign.
+fn check_verification_shares(
+    share: &ParticipantShare,
+    bundle: &ShareBundle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let invalid = |reason: &str| FrostCliError::InvalidShare {
+        share: format!("in the bundle of participant {}", bundle.index),
+        reason: reason.to_string(),
+    };
+    let verification_shares = bundle
+        .verification_shares
+        .iter()
+        .map(point_from_bytes)
+        .collect::<Result<Vec<RistrettoPoint>, _>>()?;
+    if verification_shares.len() != bundle.participants as usize {
+        return Err(
+            invalid("The bundle does not hold a verification share for every participant").into(),
+        );
+    }
+    let secret = Zeroizing::new(scalar_from_bytes(share.share)?);
+    if &RISTRETTO_BASEPOINT_TABLE * &*secret != verification_shares[share.index as usize - 1] {
+        return Err(invalid("The share does not match its verification share").into());
+    }
+
+    // The first threshold of verification shares fix the polynomial, which must give
+    // the group key at zero and every other verification share at its index.
+    let base: Vec<u32> = (1..=bundle.threshold).collect();
+    let interpolate = |coefficient: &dyn Fn(u32) -> Scalar| {
+        base.iter().fold(RistrettoPoint::identity(), |sum, &index| {
+            sum + verification_shares[index as usize - 1] * coefficient(index)
+        })
+    };
+    if interpolate(&|index| lagrange_coefficient(index, &base))
+        .compress()
+        .to_bytes()
+        != bundle.group_key
+    {
+        return Err(invalid("The verification shares do not match the group key").into());
+    }
+    for x in bundle.threshold + 1..=bundle.participants {
+        if interpolate(&|index| lagrange_at(index, &base, x)) != verification_shares[x as usize - 1]
+        {
+            return Err(invalid("The verification shares are not shares of one key").into());
+        }
+    }
+    Ok(())
+}
+
+/// Binds a share bundle to its participant and group.
+fn prologue(index: u32, group_key: &[u8; 32]) -> Vec<u8> {
+    format!(
+        "FROST-CLI DEALER SHARE {} {}",
+        index,
+        hex::encode(group_key)
+    )
+    .into_bytes()
+}
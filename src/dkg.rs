@@ -0,0 +1,1077 @@
+//! Per-participant distributed key generation.
+//!
+//! Each participant runs [`round_one`], [`round_two`] and [`finish`] in their own
+//! process and exchanges the files these functions produce. Secret coefficients and
+//! received shares only ever live in the participant's local state file.
+//!
+//! If a share does not verify, [`finish`] fails naming its sender. The ceremony can
+//! instead settle the dispute in a complaint round: the participants who received an
+//! invalid share publish a complaint with [`complain`], the accused reveal the disputed
+//! share with [`answer`], and every participant judges the revealed shares with
+//! [`resolve`], which disqualifies the accused whose share does not verify and either
+//! finishes without them or aborts with the evidence against them.
+//!
+//! frost-dalek keeps its DKG round state and proofs crate-private, so they cannot be
+//! written to disk between rounds. The same Pedersen/Feldman protocol is therefore run
+//! here directly over the Ristretto group; the resulting share and group key load into
+//! frost-dalek's `SecretKey::from_bytes` and `GroupKey::from_bytes` unchanged.
+
+use crate::error::FrostCliError;
+use crate::identity::IdentitySignature;
+use crate::io::envelope;
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::{
+    point_from_bytes, read_json, save_participant_share, scalar_from_bytes, scalars_from_bytes,
+    write_json, write_secret_json, FileOptions, ParticipantShare,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha512};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, info_span};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Context string for the proof of knowledge of a participant's secret.
+const PROOF_CONTEXT: &[u8] = b"FROST-CLI DKG PROOF OF SECRET KEY";
+
+/// Schnorr proof that a participant knows the constant term of their polynomial.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProofOfSecretKey {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+/// Round one broadcast: a participant's polynomial commitments and proof.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Round1Message {
+    pub index: u32,
+    pub commitments: Vec<[u8; 32]>,
+    pub proof: ProofOfSecretKey,
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Round two private message: the share one participant evaluated for another.
+#[derive(Serialize, Deserialize)]
+pub struct Round2Message {
+    pub sender: u32,
+    pub receiver: u32,
+    pub share: [u8; 32],
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+impl Drop for Round2Message {
+    fn drop(&mut self) {
+        self.share.zeroize();
+    }
+}
+
+/// Complaint a participant publishes about a share it received, or did not receive.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DkgComplaint {
+    /// The participant who complains.
+    pub sender: u32,
+    /// The participant who sent the disputed share.
+    pub accused: u32,
+    pub reason: String,
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// An accused participant's answer to a complaint: the disputed share, revealed to
+/// everyone so they can check it against the sender's commitments.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevealedShare {
+    /// The accused participant, who evaluated the share.
+    pub sender: u32,
+    /// The participant who complained, for whom the share was evaluated.
+    pub receiver: u32,
+    pub share: [u8; 32],
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Evidence against the participants [`resolve`] disqualified, which anyone can check
+/// against the round one messages it contains.
+#[derive(Serialize, Deserialize)]
+pub struct DkgEvidence {
+    /// Indices of the disqualified participants.
+    pub disqualified: Vec<u32>,
+    /// Round one messages of the disqualified participants.
+    pub round1_messages: Vec<Round1Message>,
+    /// Complaints against the disqualified participants.
+    pub complaints: Vec<DkgComplaint>,
+    /// Shares the disqualified participants revealed, which do not match their
+    /// commitments. A complaint without a revealed share was not answered.
+    pub reveals: Vec<RevealedShare>,
+}
+
+/// Participant-local state kept between rounds.
+///
+/// Holds the secret polynomial coefficients, so it must never leave the
+/// participant's machine.
+#[derive(Serialize, Deserialize)]
+pub struct DkgState {
+    pub index: u32,
+    pub threshold: u32,
+    pub participants: u32,
+    pub coefficients: Vec<[u8; 32]>,
+    /// Verified round one messages of every participant, filled in by round two.
+    pub round1_messages: Vec<Round1Message>,
+}
+
+impl Drop for DkgState {
+    fn drop(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
+/// Runs round one of the DKG for a single participant.
+///
+/// # Arguments
+/// - `index`: This participant's index, between 1 and `n`.
+/// - `t`: Threshold value, the minimum number of participants required to sign.
+/// - `n`: Total number of participants.
+/// - `state_file`: Path to save the participant's secret local state.
+/// - `output_dir`: Directory to write the public `round1_<index>.json` message to.
+/// - `options`: How the state and the round one message are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the parameters are invalid or the files cannot be written.
+pub fn round_one(
+    index: u32,
+    t: u32,
+    n: u32,
+    state_file: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    round_one_with_rng(index, t, n, state_file, output_dir, options, &mut OsRng)
+}
+
+/// Runs round one of the DKG like [`round_one`], drawing the secret polynomial and the
+/// proof's nonce from `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if the parameters are invalid or the files cannot be written.
+pub fn round_one_with_rng<R: RngCore + CryptoRng>(
+    index: u32,
+    t: u32,
+    n: u32,
+    state_file: &str,
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = info_span!("dkg_round_one", index, t, n).entered();
+    // check the parameters before doing any work
+    if t > n {
+        return Err(FrostCliError::InvalidParameters(
+            "Threshold value cannot be greater than the total number of participants".into(),
+        )
+        .into());
+    }
+    if t == 0 {
+        return Err(
+            FrostCliError::InvalidParameters("Threshold value must be at least 1".into()).into(),
+        );
+    }
+    if index == 0 || index > n {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant index must be between 1 and {}",
+            n
+        ))
+        .into());
+    }
+
+    // Step 1: Sample the secret polynomial, commit to it and prove knowledge of its
+    // constant term.
+    let (coefficients, message) = commit_polynomial(index, t, rng);
+
+    // Step 2: Save the local state and the public round one message.
+    let state = DkgState {
+        index,
+        threshold: t,
+        participants: n,
+        coefficients: coefficients.iter().map(|c| c.to_bytes()).collect(),
+        round1_messages: Vec::new(),
+    };
+    write_secret_json(state_file, &state, options)?;
+
+    fs::create_dir_all(output_dir)?;
+    let output_file = Path::new(output_dir).join(format!("round1_{}.json", index));
+    write_json(&output_file, &message, options)?;
+
+    report(
+        "dkg_round_one_complete",
+        &format!(
+            "DKG round 1 complete for participant {}. Commitments saved to: {}",
+            index,
+            output_file.display()
+        ),
+        json!({ "index": index, "output_file": output_file }),
+    );
+    Ok(())
+}
+
+/// Runs round two of the DKG for a single participant.
+///
+/// Verifies every participant's round one message and writes one
+/// `share_<index>_<receiver>.json` file per other participant. Each of these files
+/// is secret and must be delivered privately to its receiver.
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round one.
+/// - `round1_dir`: Directory containing the `round1_<i>.json` messages of all participants.
+/// - `output_dir`: Directory to write the outgoing secret shares to.
+/// - `options`: How the state and the outgoing shares are written.
+///
+/// # Errors
+/// Returns an error if a round one message is missing, or a
+/// [`FrostCliError::Misbehaving`] naming every participant whose proof does not verify.
+pub fn round_two(
+    state_file: &str,
+    round1_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the local state.
+    let mut state: DkgState = read_json(state_file)?;
+    let _span = info_span!("dkg_round_two", index = state.index).entered();
+    let coefficients = scalars_from_bytes(&state.coefficients)?;
+
+    // Step 2: Load and verify every participant's round one message.
+    let messages = read_round1_messages(round1_dir)?;
+    let indices: Vec<u32> = messages.iter().map(|message| message.index).collect();
+    if indices != (1..=state.participants).collect::<Vec<u32>>() {
+        return Err(format!(
+            "Expected round 1 messages from participants 1 to {}, found {:?}",
+            state.participants, indices
+        )
+        .into());
+    }
+    let mut complaints = Vec::new();
+    for message in &messages {
+        if message.commitments.len() != state.threshold as usize {
+            complaints.push((
+                message.index,
+                format!(
+                    "sent {} commitments, expected {}",
+                    message.commitments.len(),
+                    state.threshold
+                ),
+            ));
+            continue;
+        }
+        if message.index == state.index {
+            // Make sure the round one message on disk is the one we produced.
+            let own_commitments: Vec<[u8; 32]> = coefficients
+                .iter()
+                .map(|c| (&RISTRETTO_BASEPOINT_TABLE * c).compress().to_bytes())
+                .collect();
+            if message.commitments != own_commitments {
+                return Err(
+                    "Round 1 message for this participant does not match the state file".into(),
+                );
+            }
+            continue;
+        }
+        if verify_proof_of_secret_key(message).is_err() {
+            complaints.push((
+                message.index,
+                "sent an invalid proof of secret key".to_string(),
+            ));
+        }
+    }
+    if !complaints.is_empty() {
+        return Err(misbehaving(&complaints).into());
+    }
+    info!("All participants verified their proofs of secret keys");
+
+    // Step 3: Evaluate our polynomial for every other participant.
+    fs::create_dir_all(output_dir)?;
+    for receiver in (1..=state.participants).filter(|&i| i != state.index) {
+        let share = Zeroizing::new(evaluate_polynomial(&coefficients, receiver));
+        let share_file = share_file_path(output_dir, state.index, receiver);
+        write_secret_json(
+            &share_file,
+            &Round2Message {
+                sender: state.index,
+                receiver,
+                share: share.to_bytes(),
+                identity_signature: None,
+            },
+            options,
+        )?;
+    }
+
+    // Step 4: Remember the verified commitments for the final step.
+    state.round1_messages = messages;
+    write_secret_json(state_file, &state, options)?;
+
+    report(
+        "dkg_round_two_complete",
+        &format!(
+            "DKG round 2 complete for participant {}. Shares saved to: {}",
+            state.index, output_dir
+        ),
+        json!({ "index": state.index, "output_dir": output_dir }),
+    );
+    Ok(())
+}
+
+/// Finishes the DKG for a single participant.
+///
+/// Verifies the shares received from every other participant against their round one
+/// commitments, then derives this participant's private share and the group key.
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round two.
+/// - `shares_dir`: Directory containing the `share_<sender>_<index>.json` files sent to
+///   this participant.
+/// - `output_share_file`: Share location to save the resulting participant share to, see
+///   [`crate::storage`].
+/// - `options`: How the participant share is written.
+///
+/// # Errors
+/// Returns a [`FrostCliError::Misbehaving`] naming every participant whose share is
+/// missing or does not match their commitments, so the DKG can be run again without
+/// them.
+pub fn finish(
+    state_file: &str,
+    shares_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the local state.
+    let state: DkgState = read_json(state_file)?;
+    let _span = info_span!("dkg_finish", index = state.index).entered();
+    if state.round1_messages.is_empty() {
+        return Err("DKG round 2 has not been run for this state file".into());
+    }
+    let coefficients = scalars_from_bytes(&state.coefficients)?;
+
+    // Step 2: Verify and sum the shares sent to us, checking every share before giving
+    // up so all participants who sent an invalid one are named.
+    let (shares, complaints) = receive_shares(&state, shares_dir)?;
+    if !complaints.is_empty() {
+        return Err(misbehaving(&complaints).into());
+    }
+    report(
+        "shares_verified",
+        "All received secret shares verified!",
+        json!({ "index": state.index }),
+    );
+
+    // Step 3: Derive and save this participant's share and the group key.
+    let qualified: Vec<u32> = (1..=state.participants).collect();
+    save_share(
+        &state,
+        &coefficients,
+        &shares,
+        &qualified,
+        output_share_file,
+        options,
+    )
+}
+
+/// Publishes a complaint about every participant whose share sent to this participant
+/// is missing or does not match their commitments, instead of giving up in [`finish`].
+///
+/// Every accused participant then runs [`answer`], and every participant [`resolve`].
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round two.
+/// - `shares_dir`: Directory containing the secret shares sent to this participant.
+/// - `output_dir`: Directory to write the public `complaint_<index>_<accused>.json`
+///   complaints to.
+/// - `options`: How the complaints are written.
+///
+/// # Errors
+/// Returns an error if round two has not been run or a complaint cannot be written.
+pub fn complain(
+    state_file: &str,
+    shares_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    // Step 1: Check the shares sent to us.
+    let state: DkgState = read_json(state_file)?;
+    let _span = info_span!("dkg_complain", index = state.index).entered();
+    if state.round1_messages.is_empty() {
+        return Err("DKG round 2 has not been run for this state file".into());
+    }
+    let (_, complaints) = receive_shares(&state, shares_dir)?;
+
+    // Step 2: Publish a complaint about every participant who sent an invalid share.
+    fs::create_dir_all(output_dir)?;
+    let accused: Vec<u32> = complaints.iter().map(|(accused, _)| *accused).collect();
+    let mut complaint_files = Vec::new();
+    for (accused, reason) in complaints {
+        let complaint_file = complaint_file_path(output_dir, state.index, accused);
+        write_json(
+            &complaint_file,
+            &DkgComplaint {
+                sender: state.index,
+                accused,
+                reason,
+                identity_signature: None,
+            },
+            options,
+        )?;
+        complaint_files.push(complaint_file);
+    }
+
+    report(
+        "dkg_complaints_published",
+        &format!(
+            "Participant {} complained about participants {:?}. Complaints saved to: {}",
+            state.index, accused, output_dir
+        ),
+        json!({ "index": state.index, "accused": accused, "output_dir": output_dir }),
+    );
+    Ok(complaint_files)
+}
+
+/// Answers every complaint against this participant by revealing the disputed share.
+///
+/// A share that matches this participant's commitments clears it of the complaint, and
+/// gives the complaining participant the share it is missing.
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round two.
+/// - `complaints_dir`: Directory containing the complaints of all participants.
+/// - `output_dir`: Directory to write the public `reveal_<index>_<receiver>.json`
+///   shares to.
+/// - `options`: How the reveals are written.
+///
+/// # Errors
+/// Returns an error if a complaint cannot be read or a share cannot be written.
+pub fn answer(
+    state_file: &str,
+    complaints_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    // Step 1: Load the local state and the complaints against this participant.
+    let state: DkgState = read_json(state_file)?;
+    let _span = info_span!("dkg_answer", index = state.index).entered();
+    let coefficients = scalars_from_bytes(&state.coefficients)?;
+    let complaints: Vec<DkgComplaint> = read_complaints(complaints_dir)?
+        .into_iter()
+        .filter(|complaint| complaint.accused == state.index)
+        .collect();
+
+    // Step 2: Reveal the share evaluated for every complaining participant.
+    fs::create_dir_all(output_dir)?;
+    let mut reveal_files = Vec::new();
+    for complaint in &complaints {
+        if complaint.sender == 0 || complaint.sender > state.participants {
+            return Err(FrostCliError::Dkg {
+                index: complaint.sender,
+                reason: format!("Complaint from unknown participant {}", complaint.sender),
+            }
+            .into());
+        }
+        let share = Zeroizing::new(evaluate_polynomial(&coefficients, complaint.sender));
+        let reveal_file = reveal_file_path(output_dir, state.index, complaint.sender);
+        write_json(
+            &reveal_file,
+            &RevealedShare {
+                sender: state.index,
+                receiver: complaint.sender,
+                share: share.to_bytes(),
+                identity_signature: None,
+            },
+            options,
+        )?;
+        reveal_files.push(reveal_file);
+    }
+
+    let accusers: Vec<u32> = complaints
+        .iter()
+        .map(|complaint| complaint.sender)
+        .collect();
+    report(
+        "dkg_complaints_answered",
+        &format!(
+            "Participant {} answered the complaints of participants {:?}. Shares saved to: {}",
+            state.index, accusers, output_dir
+        ),
+        json!({ "index": state.index, "accusers": accusers, "output_dir": output_dir }),
+    );
+    Ok(reveal_files)
+}
+
+/// Judges every complaint by its revealed share and finishes the DKG, in place of
+/// [`finish`] once complaints were published.
+///
+/// An accused participant who did not reveal the disputed share, or revealed one that
+/// does not match their commitments, is disqualified, and the evidence against them is
+/// saved. A complaint answered with a valid share is dismissed, and the share replaces
+/// the one the complaining participant received. With `exclude`, the DKG then finishes
+/// with the remaining participants, whose commitments alone make up the group key,
+/// as long as at least the threshold of them remain. Otherwise it aborts.
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round two.
+/// - `dkg_dir`: Directory containing the secret shares sent to this participant, and
+///   the complaints and revealed shares of all participants.
+/// - `output_share_file`: Share location to save the resulting participant share to.
+/// - `exclude`: Whether to finish without the disqualified participants.
+/// - `evidence_file`: Path to save the [`DkgEvidence`] to if anyone is disqualified.
+/// - `options`: How the share and the evidence are written.
+///
+/// # Errors
+/// Returns a [`FrostCliError::Misbehaving`] naming the disqualified participants if
+/// `exclude` is not set, fewer than the threshold remain or this participant is among
+/// them, or naming participants who sent an invalid share nobody complained about.
+pub fn resolve(
+    state_file: &str,
+    dkg_dir: &str,
+    output_share_file: &str,
+    exclude: bool,
+    evidence_file: &str,
+    options: &FileOptions,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    // Step 1: Load the local state and every complaint.
+    let state: DkgState = read_json(state_file)?;
+    let _span = info_span!("dkg_resolve", index = state.index).entered();
+    if state.round1_messages.is_empty() {
+        return Err("DKG round 2 has not been run for this state file".into());
+    }
+    let coefficients = scalars_from_bytes(&state.coefficients)?;
+    let complaints = read_complaints(dkg_dir)?;
+
+    // Step 2: Judge every complaint by the share the accused revealed.
+    let mut disqualifying = Vec::new();
+    let mut evidence = DkgEvidence {
+        disqualified: Vec::new(),
+        round1_messages: Vec::new(),
+        complaints: Vec::new(),
+        reveals: Vec::new(),
+    };
+    let mut revealed = Vec::new();
+    for complaint in complaints {
+        let accused = match state
+            .round1_messages
+            .iter()
+            .find(|message| message.index == complaint.accused)
+        {
+            Some(accused)
+                if complaint.sender != complaint.accused
+                    && (1..=state.participants).contains(&complaint.sender) =>
+            {
+                accused
+            }
+            _ => {
+                return Err(FrostCliError::Dkg {
+                    index: complaint.sender,
+                    reason: format!(
+                        "Complaint from participant {} against unknown participant {}",
+                        complaint.sender, complaint.accused
+                    ),
+                }
+                .into())
+            }
+        };
+        let reveal_file = reveal_file_path(dkg_dir, complaint.accused, complaint.sender);
+        if !reveal_file.exists() {
+            disqualifying.push((
+                complaint.accused,
+                format!(
+                    "did not answer the complaint of participant {}",
+                    complaint.sender
+                ),
+            ));
+            evidence.complaints.push(complaint);
+            continue;
+        }
+        let reveal: RevealedShare = read_json(&reveal_file)?;
+        if reveal.sender != complaint.accused || reveal.receiver != complaint.sender {
+            return Err(FrostCliError::Dkg {
+                index: complaint.accused,
+                reason: format!("Unexpected revealed share: {}", reveal_file.display()),
+            }
+            .into());
+        }
+        let valid = match scalar_from_bytes(reveal.share) {
+            Ok(share) => {
+                verify_share(reveal.receiver, &share, &accused.commitments).unwrap_or(false)
+            }
+            Err(_) => false,
+        };
+        if !valid {
+            disqualifying.push((
+                complaint.accused,
+                format!(
+                    "revealed an invalid share for participant {}",
+                    complaint.sender
+                ),
+            ));
+            evidence.complaints.push(complaint);
+            evidence.reveals.push(reveal);
+            continue;
+        }
+        info!(
+            accused = complaint.accused,
+            accuser = complaint.sender,
+            "Complaint dismissed by a valid revealed share"
+        );
+        if complaint.sender == state.index {
+            revealed.push((
+                reveal.sender,
+                Zeroizing::new(scalar_from_bytes(reveal.share)?),
+            ));
+        }
+    }
+
+    // Step 3: Save the evidence and decide whether to go on without the disqualified.
+    let qualified: Vec<u32> = (1..=state.participants)
+        .filter(|index| !disqualifying.iter().any(|(accused, _)| accused == index))
+        .collect();
+    if !disqualifying.is_empty() {
+        evidence.disqualified = (1..=state.participants)
+            .filter(|index| !qualified.contains(index))
+            .collect();
+        evidence.round1_messages = state
+            .round1_messages
+            .iter()
+            .filter(|message| evidence.disqualified.contains(&message.index))
+            .cloned()
+            .collect();
+        write_json(evidence_file, &evidence, options)?;
+        report(
+            "dkg_evidence_saved",
+            &format!(
+                "Participants {:?} disqualified. Evidence saved to: {}",
+                evidence.disqualified, evidence_file
+            ),
+            json!({
+                "disqualified": evidence.disqualified,
+                "evidence_file": evidence_file,
+            }),
+        );
+        if !exclude
+            || qualified.len() < state.threshold as usize
+            || !qualified.contains(&state.index)
+        {
+            return Err(misbehaving(&disqualifying).into());
+        }
+    }
+
+    // Step 4: Sum the shares of the remaining participants, taking the revealed ones in
+    // place of those we complained about.
+    let (mut shares, complaints) = receive_shares(&state, dkg_dir)?;
+    shares.retain(|(sender, _)| !revealed.iter().any(|(revealer, _)| revealer == sender));
+    shares.extend(revealed);
+    let unresolved: Vec<(u32, String)> = complaints
+        .into_iter()
+        .filter(|(sender, _)| qualified.contains(sender))
+        .filter(|(sender, _)| !shares.iter().any(|(valid, _)| valid == sender))
+        .collect();
+    if !unresolved.is_empty() {
+        return Err(misbehaving(&unresolved).into());
+    }
+    shares.retain(|(sender, _)| qualified.contains(sender));
+    save_share(
+        &state,
+        &coefficients,
+        &shares,
+        &qualified,
+        output_share_file,
+        options,
+    )?;
+    Ok(evidence.disqualified)
+}
+
+/// Returns the complaint and revealed share files in `dir`, to check their identity
+/// signatures before [`answer`] or [`resolve`] reads them.
+///
+/// # Errors
+/// Returns an error if the directory cannot be read.
+pub fn dispute_files(dir: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|err| FrostCliError::io(dir, err))? {
+        let path = entry?.path();
+        let is_dispute = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| {
+                (name.starts_with("complaint_") || name.starts_with("reveal_"))
+                    && name.ends_with(".json")
+            });
+        if is_dispute {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the paths of the secret shares [`round_two`] writes for the other participants.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn outgoing_share_files(
+    state_file: &str,
+    output_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: DkgState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .filter(|&receiver| receiver != state.index)
+        .map(|receiver| share_file_path(output_dir, state.index, receiver))
+        .collect())
+}
+
+/// Returns the paths of the round one messages [`round_two`] reads from `round1_dir`.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn round1_files(
+    state_file: &str,
+    round1_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: DkgState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .map(|index| Path::new(round1_dir).join(format!("round1_{}.json", index)))
+        .collect())
+}
+
+/// Returns the paths of the secret shares [`finish`] reads from `shares_dir`.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn incoming_share_files(
+    state_file: &str,
+    shares_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: DkgState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .filter(|&sender| sender != state.index)
+        .map(|sender| share_file_path(shares_dir, sender, state.index))
+        .collect())
+}
+
+/// Path of the share `sender` evaluated for `receiver` inside `dir`.
+fn share_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("share_{}_{}.json", sender, receiver))
+}
+
+/// Path of the complaint `sender` published about `accused` inside `dir`.
+fn complaint_file_path(dir: &str, sender: u32, accused: u32) -> PathBuf {
+    Path::new(dir).join(format!("complaint_{}_{}.json", sender, accused))
+}
+
+/// Path of the share `sender` revealed for `receiver` inside `dir`.
+fn reveal_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("reveal_{}_{}.json", sender, receiver))
+}
+
+/// Loads every `complaint_*.json` in `dir`, sorted by sender and accused.
+fn read_complaints(dir: &str) -> Result<Vec<DkgComplaint>, Box<dyn std::error::Error>> {
+    let mut complaints: Vec<DkgComplaint> = Vec::new();
+    for path in dispute_files(dir)? {
+        let is_complaint = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("complaint_"));
+        if is_complaint {
+            complaints.push(read_json(&path)?);
+        }
+    }
+    complaints.sort_by_key(|complaint| (complaint.sender, complaint.accused));
+    Ok(complaints)
+}
+
+/// Reads and verifies the shares sent to this participant against the senders' round
+/// one commitments. Returns the valid shares by sender, and a complaint about every
+/// sender whose share is missing or invalid.
+#[allow(clippy::type_complexity)]
+fn receive_shares(
+    state: &DkgState,
+    shares_dir: &str,
+) -> Result<(Vec<(u32, Zeroizing<Scalar>)>, Vec<(u32, String)>), Box<dyn std::error::Error>> {
+    let mut shares = Vec::new();
+    let mut complaints = Vec::new();
+    for message in state
+        .round1_messages
+        .iter()
+        .filter(|message| message.index != state.index)
+    {
+        let share_file = share_file_path(shares_dir, message.index, state.index);
+        let share_message: Round2Message = match read_json(&share_file) {
+            Ok(share_message) => share_message,
+            Err(err) => {
+                complaints.push((message.index, format!("sent no secret share ({})", err)));
+                continue;
+            }
+        };
+        if share_message.sender != message.index || share_message.receiver != state.index {
+            return Err(FrostCliError::Dkg {
+                index: message.index,
+                reason: format!("Unexpected share file: {}", share_file.display()),
+            }
+            .into());
+        }
+
+        let share = match scalar_from_bytes(share_message.share) {
+            Ok(share) => Zeroizing::new(share),
+            Err(_) => {
+                complaints.push((message.index, "sent a malformed secret share".to_string()));
+                continue;
+            }
+        };
+        if !verify_share(state.index, &share, &message.commitments).unwrap_or(false) {
+            complaints.push((message.index, "sent an invalid secret share".to_string()));
+            continue;
+        }
+        shares.push((message.index, share));
+    }
+    Ok((shares, complaints))
+}
+
+/// Adds up this participant's own share and the `shares` received from the other
+/// `qualified` participants, derives the group key from the qualified participants'
+/// commitments and saves the result.
+fn save_share(
+    state: &DkgState,
+    coefficients: &[Scalar],
+    shares: &[(u32, Zeroizing<Scalar>)],
+    qualified: &[u32],
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Add up the shares.
+    let mut secret = Zeroizing::new(evaluate_polynomial(coefficients, state.index));
+    for (_, share) in shares {
+        *secret += **share;
+    }
+
+    // Step 2: Derive the group key from the qualified constant term commitments.
+    let mut group_key = RistrettoPoint::identity();
+    for message in &state.round1_messages {
+        if qualified.contains(&message.index) {
+            let constant = message
+                .commitments
+                .first()
+                .ok_or_else(|| FrostCliError::Dkg {
+                    index: message.index,
+                    reason: "Sent no commitments".into(),
+                })?;
+            group_key += point_from_bytes(constant)?;
+        }
+    }
+
+    // Step 3: Save this participant's share, the group key and the verification shares.
+    let verification_shares = verification_shares(
+        state
+            .round1_messages
+            .iter()
+            .filter(|message| qualified.contains(&message.index))
+            .map(|message| message.commitments.as_slice()),
+        state.participants,
+    )?;
+    let participant_share = Locked::new(ParticipantShare {
+        index: state.index,
+        share: secret.to_bytes(),
+        group_key: group_key.compress().to_bytes(),
+        threshold: state.threshold,
+        participants: state.participants,
+        verification_shares,
+    })?;
+    save_participant_share(output_share_file, &participant_share, options)?;
+
+    report(
+        "dkg_complete",
+        &format!(
+            "DKG complete for participant {}. Share saved to: {}",
+            state.index, output_share_file
+        ),
+        json!({
+            "index": state.index,
+            "share_file": output_share_file,
+            "group_key_fingerprint": envelope::fingerprint(&participant_share.group_key),
+            "threshold": state.threshold,
+            "participants": state.participants,
+        }),
+    );
+    Ok(())
+}
+
+/// Samples a secret polynomial of `t` coefficients from `rng` and returns it with the
+/// participant's round one message: the commitments to the coefficients and the proof
+/// of knowledge of the constant term.
+pub(crate) fn commit_polynomial<R: RngCore + CryptoRng>(
+    index: u32,
+    t: u32,
+    rng: &mut R,
+) -> (Zeroizing<Vec<Scalar>>, Round1Message) {
+    let coefficients: Zeroizing<Vec<Scalar>> =
+        Zeroizing::new((0..t).map(|_| Scalar::random(rng)).collect());
+    let commitments: Vec<[u8; 32]> = coefficients
+        .iter()
+        .map(|coefficient| {
+            (&RISTRETTO_BASEPOINT_TABLE * coefficient)
+                .compress()
+                .to_bytes()
+        })
+        .collect();
+    let proof = prove_secret_key(index, &coefficients[0], &commitments[0], rng);
+    let message = Round1Message {
+        index,
+        commitments,
+        proof,
+        identity_signature: None,
+    };
+    (coefficients, message)
+}
+
+/// Creates a proof of knowledge of `secret` bound to the participant's index.
+fn prove_secret_key<R: RngCore + CryptoRng>(
+    index: u32,
+    secret: &Scalar,
+    public: &[u8; 32],
+    rng: &mut R,
+) -> ProofOfSecretKey {
+    let nonce = Zeroizing::new(Scalar::random(rng));
+    let r = (&RISTRETTO_BASEPOINT_TABLE * &*nonce).compress().to_bytes();
+    let challenge = proof_challenge(index, public, &r);
+
+    ProofOfSecretKey {
+        r,
+        s: (*nonce + secret * challenge).to_bytes(),
+    }
+}
+
+/// Verifies the proof of knowledge in a round one message.
+pub(crate) fn verify_proof_of_secret_key(
+    message: &Round1Message,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let constant = message.commitments.first().ok_or("No commitments")?;
+    let public = point_from_bytes(constant)?;
+    let r = point_from_bytes(&message.proof.r)?;
+    let s = scalar_from_bytes(message.proof.s)?;
+    let challenge = proof_challenge(message.index, constant, &message.proof.r);
+
+    if &RISTRETTO_BASEPOINT_TABLE * &s != r + public * challenge {
+        return Err("Invalid proof of secret key".into());
+    }
+    Ok(())
+}
+
+fn proof_challenge(index: u32, public: &[u8; 32], r: &[u8; 32]) -> Scalar {
+    let mut h = Sha512::new();
+    h.update(PROOF_CONTEXT);
+    h.update(index.to_be_bytes());
+    h.update(public);
+    h.update(r);
+    Scalar::from_hash(h)
+}
+
+/// Returns a [`FrostCliError::Misbehaving`] error naming every participant with a
+/// complaint, and what each of them did.
+pub(crate) fn misbehaving(complaints: &[(u32, String)]) -> FrostCliError {
+    let mut participants: Vec<u32> = complaints.iter().map(|(index, _)| *index).collect();
+    participants.sort_unstable();
+    participants.dedup();
+    let reason = complaints
+        .iter()
+        .map(|(index, complaint)| format!("participant {} {}", index, complaint))
+        .collect::<Vec<_>>()
+        .join(", ");
+    FrostCliError::Misbehaving {
+        participants,
+        reason,
+    }
+}
+
+/// Evaluates the polynomial with the given coefficients at `x`.
+pub(crate) fn evaluate_polynomial(coefficients: &[Scalar], x: u32) -> Scalar {
+    let x = Scalar::from(x);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient)
+}
+
+/// Checks a received share against the sender's polynomial commitments.
+pub(crate) fn verify_share(
+    index: u32,
+    share: &Scalar,
+    commitments: &[[u8; 32]],
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let x = Scalar::from(index);
+    let mut power = Scalar::one();
+    let mut expected = RistrettoPoint::identity();
+    for commitment in commitments {
+        expected += point_from_bytes(commitment)? * power;
+        power *= x;
+    }
+    Ok(&RISTRETTO_BASEPOINT_TABLE * share == expected)
+}
+
+/// Returns the public verification share of every participant from 1 to `n`, that of
+/// participant `i` at position `i - 1`, given the polynomial commitments of every
+/// qualified participant. Participant `i`'s verification share is the public key of
+/// their share: the sum of the qualified polynomials' commitments evaluated at `i`.
+pub(crate) fn verification_shares<'a>(
+    commitments: impl IntoIterator<Item = &'a [[u8; 32]]>,
+    n: u32,
+) -> Result<Vec<[u8; 32]>, Box<dyn std::error::Error>> {
+    // Step 1: Add up the commitments to each coefficient.
+    let mut group_commitments: Vec<RistrettoPoint> = Vec::new();
+    for polynomial in commitments {
+        if polynomial.len() > group_commitments.len() {
+            group_commitments.resize(polynomial.len(), RistrettoPoint::identity());
+        }
+        for (sum, commitment) in group_commitments.iter_mut().zip(polynomial) {
+            *sum += point_from_bytes(commitment)?;
+        }
+    }
+
+    // Step 2: Evaluate the summed commitments at every participant's index.
+    Ok((1..=n)
+        .map(|index| {
+            let x = Scalar::from(index);
+            group_commitments
+                .iter()
+                .rev()
+                .fold(RistrettoPoint::identity(), |acc, commitment| {
+                    acc * x + commitment
+                })
+                .compress()
+                .to_bytes()
+        })
+        .collect())
+}
+
+/// Loads every `round1_*.json` message in `dir`, sorted by participant index.
+fn read_round1_messages(dir: &str) -> Result<Vec<Round1Message>, Box<dyn std::error::Error>> {
+    let mut messages: Vec<Round1Message> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_round1 = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("round1_") && name.ends_with(".json"));
+        if is_round1 {
+            messages.push(read_json(&path)?);
+        }
+    }
+    messages.sort_by_key(|message| message.index);
+    Ok(messages)
+}
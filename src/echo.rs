@@ -0,0 +1,194 @@
+//! Echo-broadcast consistency check of the signer roster.
+//!
+//! In a coordinated signing session every signer learns the roster and everyone's
+//! commitments from the coordinator. A malicious coordinator could show different
+//! commitment sets to different signers and use the resulting partial signatures
+//! against them. Before signing round two, each signer therefore hashes what they
+//! received with [`write_echo`] and sends the resulting [`Echo`] to the other signers
+//! over any channel other than the coordinator. [`check_echoes`] then aborts unless
+//! every other signer saw exactly the same message, roster and commitments.
+
+use crate::error::FrostCliError;
+use crate::identity::{verify_message, IdentitySignature};
+use crate::io::output::report;
+use crate::signing::PublicCommitments;
+use crate::{load_participant_share, read_json, write_json, FileOptions};
+use frost_dalek::compute_message_hash;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// Domain separator for roster digests.
+const ECHO_CONTEXT: &[u8] = b"FROST-CLI ECHO";
+
+/// A signer's view of the signer roster, shared with the other signers.
+#[derive(Serialize, Deserialize)]
+pub struct Echo {
+    pub session_id: String,
+    pub index: u32,
+    /// SHA-256 digest of the message, the roster and every signer's commitments.
+    pub digest: [u8; 32],
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Hashes the roster a signer received and saves it as an echo message.
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `share_file`: Path to the participant's share file.
+/// - `roster`: Public commitments of every signer, as received from the coordinator.
+/// - `echo_file`: Path to save the echo message for the other signers.
+/// - `options`: How the echo is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded, and a
+/// [`FrostCliError::InvalidParameters`] if this participant is not part of the roster.
+pub fn write_echo(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    roster: &[PublicCommitments],
+    echo_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Find ourselves in the roster
+    let share = load_participant_share(share_file, options)?;
+    let signer = roster
+        .iter()
+        .find(|signer| signer.index == share.index)
+        .ok_or_else(|| {
+            FrostCliError::InvalidParameters(
+                "This participant is not part of the signer roster".into(),
+            )
+        })?;
+
+    // Step 2: Hash the roster and save the echo
+    write_json(
+        echo_file,
+        &Echo {
+            session_id: signer.session_id.clone(),
+            index: share.index,
+            digest: roster_digest(message, context, roster),
+            identity_signature: None,
+        },
+        options,
+    )?;
+
+    report(
+        "echo_saved",
+        &format!(
+            "Echo of the signer roster for participant {} saved to: {}",
+            share.index, echo_file
+        ),
+        json!({ "index": share.index, "echo_file": echo_file }),
+    );
+    Ok(())
+}
+
+/// Checks that every other signer echoed the same roster as the one received.
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `share_file`: Path to the participant's share file.
+/// - `roster`: Public commitments of every signer, as received from the coordinator.
+/// - `echo_files`: Paths to the echo messages of the other signers.
+/// - `identities_dir`: Directory of public identities to check the echoes' identity
+///   signatures against, if any.
+/// - `options`: File key the share was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns a [`FrostCliError::InvalidMessage`] naming the signer if their echo is
+/// missing, belongs to another session, is not signed by their identity, or shows a
+/// different roster.
+pub fn check_echoes(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    roster: &[PublicCommitments],
+    echo_files: &[String],
+    identities_dir: Option<&str>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Hash our own view of the roster
+    let share = load_participant_share(share_file, options)?;
+    let session_id = &roster
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("The signer roster is empty".into()))?
+        .session_id;
+    let digest = roster_digest(message, context, roster);
+
+    // Step 2: Load the other signers' echoes
+    let mut echoes = Vec::with_capacity(echo_files.len());
+    for echo_file in echo_files {
+        let echo: Echo = read_json(echo_file)?;
+        if let Some(identities_dir) = identities_dir {
+            verify_message(identities_dir, &echo)?;
+        }
+        echoes.push(echo);
+    }
+
+    // Step 3: Compare every echo with our own view
+    for signer in roster.iter().filter(|signer| signer.index != share.index) {
+        let echo = echoes
+            .iter()
+            .find(|echo| echo.index == signer.index)
+            .ok_or_else(|| FrostCliError::InvalidMessage {
+                index: signer.index,
+                reason: format!("No echo from participant {}", signer.index),
+            })?;
+        if &echo.session_id != session_id {
+            return Err(FrostCliError::InvalidMessage {
+                index: echo.index,
+                reason: format!(
+                    "Echo of participant {} belongs to session {}, expected {}",
+                    echo.index, echo.session_id, session_id
+                ),
+            }
+            .into());
+        }
+        if echo.digest != digest {
+            return Err(FrostCliError::InvalidMessage {
+                index: echo.index,
+                reason: format!(
+                    "Participant {} received a different signer roster, aborting",
+                    echo.index
+                ),
+            }
+            .into());
+        }
+    }
+
+    report(
+        "echoes_verified",
+        "All signers received the same signer roster",
+        json!({
+            "valid": true,
+            "signers": roster.iter().map(|c| c.index).collect::<Vec<u32>>(),
+        }),
+    );
+    Ok(())
+}
+
+/// Hashes the message in its context and every signer's commitments, in roster order.
+fn roster_digest(message: &str, context: &str, roster: &[PublicCommitments]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    h.update(ECHO_CONTEXT);
+    h.update(&compute_message_hash(context.as_bytes(), message.as_bytes())[..]);
+    h.update((roster.len() as u32).to_be_bytes());
+    for signer in roster {
+        h.update(signer.index.to_be_bytes());
+        h.update((signer.session_id.len() as u32).to_be_bytes());
+        h.update(signer.session_id.as_bytes());
+        h.update(signer.public_key);
+        h.update((signer.commitments.len() as u32).to_be_bytes());
+        for (hiding, binding) in &signer.commitments {
+            h.update(hiding);
+            h.update(binding);
+        }
+    }
+    h.finalize().into()
+}
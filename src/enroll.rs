@@ -0,0 +1,649 @@
+//! Enrollment of a new participant into an existing group.
+//!
+//! A threshold of the current participants, the helpers, compute the new participant's
+//! share without a new key generation and without any of them learning it. Each helper
+//! weights its share by its Lagrange coefficient at the new index and splits the result
+//! into random pieces, one per helper. Every helper adds up the pieces it receives and
+//! sends the sum to the new participant, whose share is the sum of those sums.
+//!
+//! Every helper runs [`round_one`], which writes a public `enroll_<index>.json` message
+//! with its public share and a secret `enroll_piece_<index>_<helper>.json` file for every
+//! helper, then [`round_two`], which writes a secret `enroll_share_<index>_<new_index>.json`
+//! file for the new participant. The secret files are [`Round2Message`]s, sealed and
+//! delivered like DKG shares. The new participant runs [`finish`], which checks the
+//! helpers' public shares against the group key and its new share against them.
+//!
+//! The group then has one more participant, which every other participant records in
+//! their share with [`update_share`]. [`enroll_keys`] enrolls a new participant into a
+//! dealer key file in one process.
+//!
+//! Only an index past the group's participants that was never revoked can be enrolled.
+//! The share of an existing participant is rebuilt with [`crate::repair`] instead, which
+//! runs the same rounds for that index.
+
+use crate::dkg::Round2Message;
+use crate::error::FrostCliError;
+use crate::identity::IdentitySignature;
+use crate::io::envelope;
+use crate::io::files::{write_checked_json, write_secret_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::revoke::revoked_indices;
+use crate::signing::{lagrange_at, lagrange_coefficient};
+use crate::{
+    load_frost_keys, load_participant_share, point_from_bytes, read_json, save_participant_share,
+    scalar_from_bytes, write_json, ParticipantShare,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info_span;
+use zeroize::Zeroizing;
+
+/// Public enrollment message of a helper.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnrollMessage {
+    pub index: u32,
+    /// Group key of the group being joined.
+    pub group_key: [u8; 32],
+    pub threshold: u32,
+    /// Number of participants before the enrollment.
+    pub participants: u32,
+    /// Indices of all helpers.
+    pub helpers: Vec<u32>,
+    /// Index of the new participant.
+    pub new_index: u32,
+    /// The helper's public share, its share times the base point.
+    pub public_share: [u8; 32],
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Enrolls a new participant into a dealer key file with the shares at the given
+/// positions, and saves the keys with the new participant's share added.
+///
+/// # Arguments
+/// - `key_file`: Path to the dealer key file.
+/// - `signers`: Positions of the helpers' shares in the key file, at least its threshold.
+/// - `output_key_file`: Path to save the keys with the new share to.
+/// - `options`: How the key file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the keys are not ristretto255 keys or the signers are invalid.
+pub fn enroll_keys(
+    key_file: &str,
+    signers: &[u32],
+    output_key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut frost_keys = load_frost_keys(key_file, options)?;
+    let _span = info_span!("enroll_keys").entered();
+    // Step 1: Check the keys and the helpers.
+    if !frost_keys.ciphersuite.is_ristretto255() {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Only ristretto255 keys can enroll participants, not {} keys",
+            frost_keys.ciphersuite
+        ))
+        .into());
+    }
+    if let Some(&signer) = signers
+        .iter()
+        .find(|&&signer| signer as usize >= frost_keys.private_shares.len())
+    {
+        return Err(
+            FrostCliError::InvalidParameters(format!("Invalid signer index: {}", signer)).into(),
+        );
+    }
+    let helpers: Vec<u32> = signers
+        .iter()
+        .map(|&signer| frost_keys.private_shares[signer as usize].1)
+        .collect();
+    let new_index = frost_keys
+        .private_shares
+        .iter()
+        .map(|(_, index)| *index)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    check_helpers(&helpers, frost_keys.threshold, new_index)?;
+
+    // Step 2: Every helper splits its weighted share, and the pieces add up to the new share.
+    let mut share = Zeroizing::new(Scalar::zero());
+    for &signer in signers {
+        let (helper_share, index) = &frost_keys.private_shares[signer as usize];
+        let helper_share = Zeroizing::new(scalar_from_bytes(*helper_share)?);
+        let pieces = split(*index, &helper_share, &helpers, new_index, &mut OsRng);
+        for piece in pieces.iter() {
+            *share += piece;
+        }
+    }
+
+    // Step 3: Save the keys with the new share.
+    frost_keys
+        .private_shares
+        .push((share.to_bytes(), new_index));
+    let frost_keys = Locked::new(frost_keys)?;
+    write_checked_json(output_key_file, &*frost_keys, options)?;
+
+    report(
+        "participant_enrolled",
+        &format!(
+            "Enrolled participant {}. Keys saved to: {}",
+            new_index, output_key_file
+        ),
+        json!({
+            "index": new_index,
+            "key_file": output_key_file,
+            "group_key_fingerprint": envelope::fingerprint(&frost_keys.group_key),
+            "participants": frost_keys.private_shares.len(),
+        }),
+    );
+    Ok(())
+}
+
+/// Runs a helper's first round of an enrollment.
+///
+/// # Arguments
+/// - `share_file`: Share location of the helper's share, see [`crate::storage`].
+/// - `helpers`: Indices of all helpers, at least the threshold of them.
+/// - `new_index`: Index of the new participant.
+/// - `output_dir`: Directory to write the public `enroll_<index>.json` message and the
+///   secret pieces for the other helpers to.
+/// - `options`: How the round one messages are written.
+///
+/// # Errors
+/// Returns an error if the helpers or the new index are invalid, or the files cannot be
+/// written.
+pub fn round_one(
+    share_file: &str,
+    helpers: &[u32],
+    new_index: u32,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    round_one_with_rng(
+        share_file, helpers, new_index, output_dir, options, &mut OsRng,
+    )
+}
+
+/// Runs a helper's first round of an enrollment like [`round_one`], drawing the pieces
+/// from `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if the helpers or the new index are invalid, or the files cannot be
+/// written.
+pub fn round_one_with_rng<R: RngCore + CryptoRng>(
+    share_file: &str,
+    helpers: &[u32],
+    new_index: u32,
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_new_index(share_file, new_index, options)?;
+    split_share(share_file, helpers, new_index, output_dir, options, rng)
+}
+
+/// Runs a helper's first round for the participant at `new_index`, which [`round_one`]
+/// and [`crate::repair::round_one`] check first.
+pub(crate) fn split_share<R: RngCore + CryptoRng>(
+    share_file: &str,
+    helpers: &[u32],
+    new_index: u32,
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the helper's share and check the parameters.
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let _span = info_span!("enroll_round_one", index = share.index, new_index).entered();
+    check_helpers(helpers, share.threshold, new_index)?;
+    if !helpers.contains(&share.index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is not one of the helpers {:?}",
+            share.index, helpers
+        ))
+        .into());
+    }
+
+    // Step 2: Split the weighted share into one piece per helper.
+    let secret = Zeroizing::new(scalar_from_bytes(share.share)?);
+    let pieces = split(share.index, &secret, helpers, new_index, rng);
+
+    // Step 3: Save the public message and the secret pieces.
+    fs::create_dir_all(output_dir)?;
+    write_json(
+        message_file(output_dir, share.index),
+        &EnrollMessage {
+            index: share.index,
+            group_key: share.group_key,
+            threshold: share.threshold,
+            participants: share.participants,
+            helpers: helpers.to_vec(),
+            new_index,
+            public_share: (&RISTRETTO_BASEPOINT_TABLE * &*secret)
+                .compress()
+                .to_bytes(),
+            identity_signature: None,
+        },
+        options,
+    )?;
+    for (&receiver, piece) in helpers.iter().zip(pieces.iter()) {
+        write_secret_json(
+            piece_file_path(output_dir, share.index, receiver),
+            &Round2Message {
+                sender: share.index,
+                receiver,
+                share: piece.to_bytes(),
+                identity_signature: None,
+            },
+            options,
+        )?;
+    }
+
+    report(
+        "enroll_round_one_complete",
+        &format!(
+            "Enrollment round 1 complete for helper {}. Messages saved to: {}",
+            share.index, output_dir
+        ),
+        json!({ "index": share.index, "output_dir": output_dir }),
+    );
+    Ok(())
+}
+
+/// Runs a helper's second round of an enrollment: adds up the pieces every helper sent
+/// to this one and writes the sum for the new participant.
+///
+/// # Arguments
+/// - `share_file`: Share location of the helper's share.
+/// - `helpers`: Indices of all helpers.
+/// - `new_index`: Index of the new participant.
+/// - `enroll_dir`: Directory containing the `enroll_piece_<helper>_<index>.json` files
+///   sent to this helper.
+/// - `output_dir`: Directory to write the secret share for the new participant to.
+/// - `options`: How the share for the new participant is written.
+///
+/// # Errors
+/// Returns an error if the new index is already part of the group or was revoked, or a
+/// piece is missing or was not sent to this helper.
+pub fn round_two(
+    share_file: &str,
+    helpers: &[u32],
+    new_index: u32,
+    enroll_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_new_index(share_file, new_index, options)?;
+    add_pieces(
+        share_file, helpers, new_index, enroll_dir, output_dir, options,
+    )
+}
+
+/// Runs a helper's second round for the participant at `new_index`, which
+/// [`round_two`] and [`crate::repair::round_two`] check first.
+pub(crate) fn add_pieces(
+    share_file: &str,
+    helpers: &[u32],
+    new_index: u32,
+    enroll_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the helper's index.
+    let index = load_participant_share(share_file, options)?.index;
+    let _span = info_span!("enroll_round_two", index, new_index).entered();
+
+    // Step 2: Add up the pieces sent to this helper.
+    let mut sum = Zeroizing::new(Scalar::zero());
+    for &helper in helpers {
+        let piece_file = piece_file_path(enroll_dir, helper, index);
+        let piece: Round2Message = read_json(&piece_file)?;
+        if piece.sender != helper || piece.receiver != index {
+            return Err(format!("Unexpected piece file: {}", piece_file.display()).into());
+        }
+        *sum += *Zeroizing::new(scalar_from_bytes(piece.share)?);
+    }
+
+    // Step 3: Save the sum for the new participant.
+    fs::create_dir_all(output_dir)?;
+    let output_file = share_file_path(output_dir, index, new_index);
+    write_secret_json(
+        &output_file,
+        &Round2Message {
+            sender: index,
+            receiver: new_index,
+            share: sum.to_bytes(),
+            identity_signature: None,
+        },
+        options,
+    )?;
+
+    report(
+        "enroll_round_two_complete",
+        &format!(
+            "Enrollment round 2 complete for helper {}. Share saved to: {}",
+            index,
+            output_file.display()
+        ),
+        json!({ "index": index, "output_file": output_file }),
+    );
+    Ok(())
+}
+
+/// Finishes an enrollment for the new participant.
+///
+/// Checks that the helpers' public shares interpolate to the group key, adds up the
+/// sums the helpers sent and checks the result against the public shares before saving
+/// it as the new participant's share.
+///
+/// # Arguments
+/// - `new_index`: This participant's index.
+/// - `helpers`: Indices of all helpers.
+/// - `enroll_dir`: Directory containing the `enroll_<helper>.json` messages and the
+///   `enroll_share_<helper>_<new_index>.json` files sent to this participant.
+/// - `output_share_file`: Share location to save the new share to.
+/// - `options`: How the new share is written.
+///
+/// # Errors
+/// Returns an error if the new index is already part of the group, a message or share
+/// is missing, the messages disagree or do not match the group key, or the shares do not
+/// add up to this participant's share.
+pub fn finish(
+    new_index: u32,
+    helpers: &[u32],
+    enroll_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let helper = helpers
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("No helpers given".into()))?;
+    let message: EnrollMessage = read_json(message_file(enroll_dir, *helper))?;
+    if new_index <= message.participants {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is already one of the {} participants, repair its share instead",
+            new_index, message.participants
+        ))
+        .into());
+    }
+    finish_share(new_index, helpers, enroll_dir, output_share_file, options)
+}
+
+/// Finishes an enrollment for the participant at `new_index`, which [`finish`] and
+/// [`crate::repair::finish`] check first.
+pub(crate) fn finish_share(
+    new_index: u32,
+    helpers: &[u32],
+    enroll_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = info_span!("enroll_finish", new_index).entered();
+    // Step 1: Load the helpers' messages and check that they describe the same enrollment.
+    let messages: Vec<EnrollMessage> = helpers
+        .iter()
+        .map(|&helper| read_json(message_file(enroll_dir, helper)))
+        .collect::<Result<_, _>>()?;
+    let first = messages
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("No helpers given".into()))?;
+    for (&helper, message) in helpers.iter().zip(&messages) {
+        if message.index != helper
+            || message.group_key != first.group_key
+            || message.threshold != first.threshold
+            || message.participants != first.participants
+            || message.helpers != helpers
+            || message.new_index != new_index
+        {
+            return Err(FrostCliError::Dkg {
+                index: helper,
+                reason: "Enrollment message disagrees with the other helpers".into(),
+            }
+            .into());
+        }
+    }
+    check_helpers(helpers, first.threshold, new_index)?;
+
+    // Step 2: The helpers' public shares must interpolate to the group key.
+    let mut group_key = RistrettoPoint::identity();
+    let mut expected = RistrettoPoint::identity();
+    for message in &messages {
+        let public_share = point_from_bytes(&message.public_share)?;
+        group_key += public_share * lagrange_coefficient(message.index, helpers);
+        expected += public_share * lagrange_at(message.index, helpers, new_index);
+    }
+    if group_key.compress().to_bytes() != first.group_key {
+        return Err("The helpers' public shares do not match the group key".into());
+    }
+
+    // Step 3: Add up the sums the helpers sent and check the result.
+    let mut secret = Zeroizing::new(Scalar::zero());
+    for &helper in helpers {
+        let share_file = share_file_path(enroll_dir, helper, new_index);
+        let share_message: Round2Message = read_json(&share_file)?;
+        if share_message.sender != helper || share_message.receiver != new_index {
+            return Err(format!("Unexpected share file: {}", share_file.display()).into());
+        }
+        *secret += *Zeroizing::new(scalar_from_bytes(share_message.share)?);
+    }
+    if &RISTRETTO_BASEPOINT_TABLE * &*secret != expected {
+        return Err(FrostCliError::InvalidShare {
+            share: output_share_file.to_string(),
+            reason: "The helpers' shares do not add up to this participant's share".into(),
+        }
+        .into());
+    }
+    report(
+        "shares_verified",
+        "The enrolled share matches the helpers' public shares!",
+        json!({ "index": new_index }),
+    );
+
+    // Step 4: Save the new share with every participant's verification share, which the
+    // helpers' public shares interpolate to.
+    let participants = first.participants.max(new_index);
+    let public_shares = messages
+        .iter()
+        .map(|message| point_from_bytes(&message.public_share))
+        .collect::<Result<Vec<_>, _>>()?;
+    let verification_shares = (1..=participants)
+        .map(|index| {
+            helpers
+                .iter()
+                .zip(&public_shares)
+                .fold(
+                    RistrettoPoint::identity(),
+                    |sum, (&helper, public_share)| {
+                        sum + public_share * lagrange_at(helper, helpers, index)
+                    },
+                )
+                .compress()
+                .to_bytes()
+        })
+        .collect();
+    let participant_share = Locked::new(ParticipantShare {
+        index: new_index,
+        share: secret.to_bytes(),
+        group_key: first.group_key,
+        threshold: first.threshold,
+        participants,
+        verification_shares,
+    })?;
+    save_participant_share(output_share_file, &participant_share, options)?;
+
+    report(
+        "enroll_complete",
+        &format!(
+            "Enrollment complete for participant {}. Share saved to: {}",
+            new_index, output_share_file
+        ),
+        json!({
+            "index": new_index,
+            "share_file": output_share_file,
+            "group_key_fingerprint": envelope::fingerprint(&first.group_key),
+            "threshold": first.threshold,
+            "participants": participants,
+        }),
+    );
+    Ok(())
+}
+
+/// Records in a share that its group has `participants` participants after an
+/// enrollment, so it signs together with the new participant's share.
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded or saved, or `participants` is fewer
+/// than the share already records.
+pub fn update_share(
+    share_file: &str,
+    participants: u32,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut share = load_participant_share(share_file, options)?;
+    if participants < share.participants {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "The group already has {} participants",
+            share.participants
+        ))
+        .into());
+    }
+    share.participants = participants;
+    let share = Locked::new(share)?;
+    save_participant_share(share_file, &share, options)?;
+
+    report(
+        "share_updated",
+        &format!(
+            "Share saved to {} with {} participants.",
+            share_file, participants
+        ),
+        json!({ "share_file": share_file, "participants": participants }),
+    );
+    Ok(())
+}
+
+/// Returns the path of helper `index`'s enrollment message inside `dir`.
+pub fn message_file(dir: &str, index: u32) -> PathBuf {
+    Path::new(dir).join(format!("enroll_{}.json", index))
+}
+
+/// Returns the paths of the pieces [`round_one`] writes for the helpers.
+pub fn outgoing_piece_files(dir: &str, index: u32, helpers: &[u32]) -> Vec<PathBuf> {
+    helpers
+        .iter()
+        .map(|&helper| piece_file_path(dir, index, helper))
+        .collect()
+}
+
+/// Returns the paths of the pieces [`round_two`] reads from `dir`.
+pub fn incoming_piece_files(dir: &str, index: u32, helpers: &[u32]) -> Vec<PathBuf> {
+    helpers
+        .iter()
+        .map(|&helper| piece_file_path(dir, helper, index))
+        .collect()
+}
+
+/// Returns the path of the share [`round_two`] writes for the new participant.
+pub fn outgoing_share_file(dir: &str, index: u32, new_index: u32) -> PathBuf {
+    share_file_path(dir, index, new_index)
+}
+
+/// Returns the paths of the shares [`finish`] reads from `dir`.
+pub fn incoming_share_files(dir: &str, new_index: u32, helpers: &[u32]) -> Vec<PathBuf> {
+    helpers
+        .iter()
+        .map(|&helper| share_file_path(dir, helper, new_index))
+        .collect()
+}
+
+/// Splits `index`'s share weighted by its Lagrange coefficient at `new_index` into one
+/// random piece per helper.
+fn split<R: RngCore + CryptoRng>(
+    index: u32,
+    share: &Scalar,
+    helpers: &[u32],
+    new_index: u32,
+    rng: &mut R,
+) -> Zeroizing<Vec<Scalar>> {
+    let mut pieces: Zeroizing<Vec<Scalar>> =
+        Zeroizing::new((1..helpers.len()).map(|_| Scalar::random(rng)).collect());
+    let rest = lagrange_at(index, helpers, new_index) * share - pieces.iter().sum::<Scalar>();
+    pieces.push(rest);
+    pieces
+}
+
+/// Checks that `new_index` is past the helper's group and was never revoked, since the
+/// share of an existing participant is rebuilt with [`crate::repair`].
+fn check_new_index(
+    share_file: &str,
+    new_index: u32,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    if new_index <= share.participants {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is already one of the {} participants, repair its share instead",
+            new_index, share.participants
+        ))
+        .into());
+    }
+    if revoked_indices(share_file, options)?.contains(&new_index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} has been revoked",
+            new_index
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Checks that `helpers` are at least `threshold` distinct indices and that `new_index`
+/// is a valid index other than theirs.
+fn check_helpers(
+    helpers: &[u32],
+    threshold: u32,
+    new_index: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if helpers.len() < threshold as usize {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of helpers is less than the threshold".into(),
+        )
+        .into());
+    }
+    let mut sorted = helpers.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != helpers.len() || sorted.first() == Some(&0) {
+        return Err(
+            FrostCliError::InvalidParameters(format!("Invalid helpers: {:?}", helpers)).into(),
+        );
+    }
+    if new_index == 0 || helpers.contains(&new_index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Invalid index for the new participant: {}",
+            new_index
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Path of the piece `sender` split off for helper `receiver` inside `dir`.
+fn piece_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("enroll_piece_{}_{}.json", sender, receiver))
+}
+
+/// Path of the share helper `sender` adds up for the new participant inside `dir`.
+fn share_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("enroll_share_{}_{}.json", sender, receiver))
+}
@@ -0,0 +1,109 @@
+//! Typed errors for library consumers.
+//!
+//! Functions of frost-cli return `Box<dyn std::error::Error>` so every failure reads
+//! well as a message. The failures a caller may want to act on are [`FrostCliError`]s
+//! inside that box, next to [`crate::InvalidSignature`] for signatures that do not
+//! verify, and can be matched on after a downcast:
+//!
+//! ```no_run
+//! use frost_cli::error::FrostCliError;
+//!
+//! match frost_cli::generate_keys(4, 3, "keys.json") {
+//!     Err(err) => match err.downcast_ref::<FrostCliError>() {
+//!         Some(FrostCliError::InvalidParameters(reason)) => eprintln!("Fix the flags: {}", reason),
+//!         Some(FrostCliError::Io { path, .. }) => eprintln!("Check {}", path),
+//!         _ => eprintln!("{}", err),
+//!     },
+//!     Ok(()) => {}
+//! }
+//! ```
+
+use std::error::Error;
+use thiserror::Error;
+
+/// A failure of frost-cli a caller can tell apart from others.
+#[derive(Debug, Error)]
+pub enum FrostCliError {
+    /// The threshold, number of participants, participant indices or signers given do
+    /// not fit together.
+    #[error("{0}")]
+    InvalidParameters(String),
+    /// The key generation failed because of a participant's round message.
+    #[error("Key generation failed for participant {index}: {reason}")]
+    Dkg { index: u32, reason: String },
+    /// Participants sent invalid round messages in a key generation, so it can be run
+    /// again without them.
+    #[error("Key generation failed, misbehaving participants {participants:?}: {reason}")]
+    Misbehaving {
+        /// Indices of the participants whose round messages failed, sorted.
+        participants: Vec<u32>,
+        reason: String,
+    },
+    /// A private share could not be decoded or is not a valid share.
+    #[error("{reason}")]
+    InvalidShare {
+        /// The share location, or the participant whose share it is.
+        share: String,
+        reason: String,
+    },
+    /// The partial signatures could not be aggregated into a valid signature.
+    #[error("Failed to aggregate the signature, misbehaving signers {signers:?}: {reason}")]
+    Aggregation {
+        /// Participant indices of the signers whose partial signatures failed, or of
+        /// every signer if they cannot be told apart.
+        signers: Vec<u32>,
+        reason: String,
+    },
+    /// A participant's round message was rejected: it is not signed by their identity,
+    /// or shows another signing round than the one this participant received, see
+    /// [`crate::identity`] and [`crate::echo`].
+    #[error("{reason}")]
+    InvalidMessage {
+        /// Participant index of the sender.
+        index: u32,
+        reason: String,
+    },
+    /// A nonce pair was already used to sign, or the nonce state that would tell is
+    /// missing or was rolled back, see [`crate::nonces`].
+    #[error("{reason}")]
+    NonceReuse {
+        /// Path to the nonce state.
+        state: String,
+        reason: String,
+    },
+    /// A file could not be read or written.
+    #[error("{path}: {source}")]
+    Io {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl FrostCliError {
+    /// Returns an [`FrostCliError::Io`] error of a file.
+    pub(crate) fn io(path: impl AsRef<std::path::Path>, source: std::io::Error) -> Self {
+        FrostCliError::Io {
+            path: path.as_ref().display().to_string(),
+            source,
+        }
+    }
+}
+
+/// Returns whether an error is a file that could not be read or written, either a
+/// [`FrostCliError::Io`] or a plain `std::io::Error`.
+pub fn is_io_error(err: &(dyn Error + 'static)) -> bool {
+    err.is::<std::io::Error>() || matches!(err.downcast_ref(), Some(FrostCliError::Io { .. }))
+}
+
+/// Turns an error of reading a share into [`FrostCliError::InvalidShare`], leaving
+/// typed errors such as an unreadable file as they are.
+pub(crate) fn invalid_share(share: &str, err: Box<dyn Error>) -> Box<dyn Error> {
+    if err.is::<FrostCliError>() || err.is::<std::io::Error>() {
+        return err;
+    }
+    Box::new(FrostCliError::InvalidShare {
+        share: share.to_string(),
+        reason: err.to_string(),
+    })
+}
@@ -0,0 +1,275 @@
+//! C bindings for signers written in other languages, with the `cdylib` feature.
+//!
+//! A C or C++ program, such as an HSM gateway, links against the `frost_cli` shared
+//! library and calls the same key generation, signing rounds, aggregation and
+//! verification as the CLI, on the same files and share locations, see
+//! [`crate::storage`]. The build writes the declarations of this module to
+//! `include/frost_cli.h`.
+//!
+//! Every function takes NUL-terminated UTF-8 strings and returns [`FROST_OK`] or one of
+//! the other `FROST_*` error codes. The message of the last error on the calling thread
+//! is returned by [`frost_last_error`]. Panics are caught and returned as
+//! [`FROST_PANIC`], so they never unwind into the caller.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::{
+    generate_keys_with_ciphersuite, signing, validate_signature, FileOptions, InvalidSignature,
+    SIGNING_CONTEXT,
+};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use thiserror::Error;
+
+/// The call succeeded.
+pub const FROST_OK: c_int = 0;
+/// The call failed for a reason without its own code, see [`frost_last_error`].
+pub const FROST_ERROR: c_int = 1;
+/// A pointer argument is null or not valid UTF-8.
+pub const FROST_INVALID_ARGUMENT: c_int = 2;
+/// The threshold, participants or signers do not fit together.
+pub const FROST_INVALID_PARAMETERS: c_int = 3;
+/// A private share could not be decoded or is not a valid share.
+pub const FROST_INVALID_SHARE: c_int = 4;
+/// The partial signatures could not be aggregated into a valid signature.
+pub const FROST_AGGREGATION_FAILED: c_int = 5;
+/// A file could not be read or written.
+pub const FROST_IO: c_int = 6;
+/// The signature does not verify.
+pub const FROST_INVALID_SIGNATURE: c_int = 7;
+/// The library panicked.
+pub const FROST_PANIC: c_int = 8;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Returns the message of the last error on the calling thread, or null if the last
+/// call succeeded. The string is owned by the library and valid until the next call on
+/// the same thread.
+#[no_mangle]
+pub extern "C" fn frost_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| {
+        last_error
+            .borrow()
+            .as_ref()
+            .map_or(std::ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Generates keys as a trusted dealer and saves them to a key file, see
+/// [`crate::generate_keys`].
+///
+/// # Safety
+/// `output_key_file` must be a valid NUL-terminated string, and `ciphersuite` one or
+/// null for ristretto255.
+#[no_mangle]
+pub unsafe extern "C" fn frost_generate_keys(
+    t: u32,
+    n: u32,
+    output_key_file: *const c_char,
+    ciphersuite: *const c_char,
+) -> c_int {
+    call(|| {
+        let ciphersuite = if ciphersuite.is_null() {
+            Ciphersuite::Ristretto255
+        } else {
+            str_arg(ciphersuite, "ciphersuite")?.parse()?
+        };
+        generate_keys_with_ciphersuite(
+            t,
+            n,
+            str_arg(output_key_file, "output_key_file")?,
+            ciphersuite,
+        )
+    })
+}
+
+/// Runs signing round one, see [`signing::commit`].
+///
+/// # Safety
+/// Every argument must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn frost_commit(
+    session_id: *const c_char,
+    share_file: *const c_char,
+    public_commitments_file: *const c_char,
+    secret_commitments_file: *const c_char,
+) -> c_int {
+    call(|| {
+        signing::commit(
+            str_arg(session_id, "session_id")?,
+            str_arg(share_file, "share_file")?,
+            str_arg(public_commitments_file, "public_commitments_file")?,
+            str_arg(secret_commitments_file, "secret_commitments_file")?,
+            signing::CommitOptions::default(),
+        )
+    })
+}
+
+/// Runs signing round two, see [`signing::partial_sign`]. A null `context` signs in
+/// [`crate::SIGNING_CONTEXT`].
+///
+/// # Safety
+/// Every string argument but a null `context` must be a valid NUL-terminated string,
+/// and `commitment_files` an array of `commitment_files_len` of them.
+#[no_mangle]
+pub unsafe extern "C" fn frost_partial_sign(
+    message: *const c_char,
+    context: *const c_char,
+    share_file: *const c_char,
+    secret_commitments_file: *const c_char,
+    commitment_files: *const *const c_char,
+    commitment_files_len: usize,
+    partial_signature_file: *const c_char,
+) -> c_int {
+    call(|| {
+        signing::partial_sign(
+            str_arg(message, "message")?,
+            context_arg(context)?,
+            str_arg(share_file, "share_file")?,
+            str_arg(secret_commitments_file, "secret_commitments_file")?,
+            &str_array_arg(commitment_files, commitment_files_len, "commitment_files")?,
+            str_arg(partial_signature_file, "partial_signature_file")?,
+            &FileOptions::default(),
+        )
+    })
+}
+
+/// Combines the partial signatures of all signers into a threshold signature, see
+/// [`signing::aggregate`]. A null `context` signs in [`crate::SIGNING_CONTEXT`].
+///
+/// # Safety
+/// Every string argument but a null `context` must be a valid NUL-terminated string,
+/// and `commitment_files` an array of `commitment_files_len` of them.
+#[no_mangle]
+pub unsafe extern "C" fn frost_aggregate(
+    message: *const c_char,
+    context: *const c_char,
+    key_file: *const c_char,
+    commitment_files: *const *const c_char,
+    commitment_files_len: usize,
+    partial_signatures_dir: *const c_char,
+    signature_file: *const c_char,
+) -> c_int {
+    call(|| {
+        signing::aggregate(
+            str_arg(message, "message")?,
+            context_arg(context)?,
+            str_arg(key_file, "key_file")?,
+            &str_array_arg(commitment_files, commitment_files_len, "commitment_files")?,
+            str_arg(partial_signatures_dir, "partial_signatures_dir")?,
+            str_arg(signature_file, "signature_file")?,
+        )
+    })
+}
+
+/// Verifies a threshold signature file against a key file, see
+/// [`crate::validate_signature`]. Returns [`FROST_INVALID_SIGNATURE`] if it does not
+/// verify. A null `context` verifies in [`crate::SIGNING_CONTEXT`].
+///
+/// # Safety
+/// Every argument but a null `context` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn frost_verify(
+    message: *const c_char,
+    context: *const c_char,
+    key_file: *const c_char,
+    signature_file: *const c_char,
+) -> c_int {
+    call(|| {
+        validate_signature(
+            str_arg(message, "message")?,
+            context_arg(context)?,
+            str_arg(key_file, "key_file")?,
+            str_arg(signature_file, "signature_file")?,
+        )
+    })
+}
+
+/// An argument that is null or not valid UTF-8.
+#[derive(Debug, Error)]
+#[error("{0}")]
+struct InvalidArgument(String);
+
+/// Runs a call, records its error for [`frost_last_error`] and returns its code.
+fn call<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<(), Box<dyn std::error::Error>>,
+{
+    let (code, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (FROST_OK, None),
+        Ok(Err(err)) => (error_code(&*err), Some(err.to_string())),
+        Err(_) => (FROST_PANIC, Some("frost-cli panicked".to_string())),
+    };
+    LAST_ERROR.with(|last_error| {
+        *last_error.borrow_mut() =
+            message.map(|message| CString::new(message.replace('\0', " ")).unwrap_or_default());
+    });
+    code
+}
+
+fn error_code(err: &(dyn std::error::Error + 'static)) -> c_int {
+    if err.is::<InvalidArgument>() {
+        return FROST_INVALID_ARGUMENT;
+    }
+    if err.is::<InvalidSignature>() {
+        return FROST_INVALID_SIGNATURE;
+    }
+    if err.is::<std::io::Error>() {
+        return FROST_IO;
+    }
+    match err.downcast_ref::<FrostCliError>() {
+        Some(FrostCliError::InvalidParameters(_)) => FROST_INVALID_PARAMETERS,
+        Some(FrostCliError::InvalidShare { .. }) => FROST_INVALID_SHARE,
+        Some(FrostCliError::Aggregation { .. }) => FROST_AGGREGATION_FAILED,
+        Some(FrostCliError::Io { .. }) => FROST_IO,
+        _ => FROST_ERROR,
+    }
+}
+
+/// Borrows a string argument.
+///
+/// # Safety
+/// `arg` must be null or a valid NUL-terminated string that outlives the call.
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> Result<&'a str, InvalidArgument> {
+    if arg.is_null() {
+        return Err(InvalidArgument(format!("{} is null", name)));
+    }
+    CStr::from_ptr(arg)
+        .to_str()
+        .map_err(|_| InvalidArgument(format!("{} is not valid UTF-8", name)))
+}
+
+/// Borrows a signing context argument, [`SIGNING_CONTEXT`] if it is null.
+///
+/// # Safety
+/// `context` must be null or a valid NUL-terminated string that outlives the call.
+unsafe fn context_arg<'a>(context: *const c_char) -> Result<&'a str, InvalidArgument> {
+    if context.is_null() {
+        return Ok(SIGNING_CONTEXT);
+    }
+    str_arg(context, "context")
+}
+
+/// Copies an array of string arguments.
+///
+/// # Safety
+/// `args` must be null with `len` 0, or point to `len` valid NUL-terminated strings.
+unsafe fn str_array_arg(
+    args: *const *const c_char,
+    len: usize,
+    name: &str,
+) -> Result<Vec<String>, InvalidArgument> {
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if args.is_null() {
+        return Err(InvalidArgument(format!("{} is null", name)));
+    }
+    std::slice::from_raw_parts(args, len)
+        .iter()
+        .map(|&arg| str_arg(arg, name).map(str::to_string))
+        .collect()
+}
@@ -0,0 +1,208 @@
+//! A `gpg.program` for git, so commits and tags are threshold-signed.
+//!
+//! git signs a commit or tag by running `gpg.program --status-fd=2 -bsau <key>` with the
+//! object on stdin, and expects an armored detached signature on stdout and a
+//! `SIG_CREATED` status line on the status file descriptor. It verifies one by running
+//! `gpg.program --keyid-format=long --status-fd=1 --verify <signature file> -` with the
+//! object on stdin, and reads the `GOODSIG` and `VALIDSIG` status lines. [`run`] answers
+//! both with OpenPGP signatures of an Ed25519 group key, see [`crate::io::openpgp`].
+//!
+//! git runs `gpg.program` without a shell, so it is pointed at a script that calls
+//! `frost-cli git-sign` with the key options, followed by `--` and git's arguments. The
+//! key git names with `-u` is ignored in favour of those options.
+
+use crate::io::envelope::{self, EnvelopeOptions, OutFormat};
+use crate::io::files::FileOptions;
+use crate::io::openpgp;
+use crate::{backend, load_group_key_bytes, seal_message, utc_date, SIGNING_CONTEXT};
+use rand::rngs::OsRng;
+use std::fs;
+use std::io::{Read, Write};
+
+/// User ID the group key is reported under in status lines.
+pub const USER_ID: &str = "FROST group key";
+
+/// What git asked for.
+enum Request {
+    Sign,
+    Verify { signature_file: String },
+}
+
+/// Parses git's `gpg` arguments into the request and the status file descriptor.
+fn parse_args(gpg_args: &[String]) -> Result<(Request, u32), Box<dyn std::error::Error>> {
+    let mut request = None;
+    let mut status_fd = None;
+    let mut args = gpg_args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Detached, signed, armored, with the key named by the next argument
+            "-bsau" => {
+                args.next().ok_or("Missing key after -bsau")?;
+                request = Some(Request::Sign);
+            }
+            "--verify" => {
+                let signature_file = args.next().ok_or("Missing signature file after --verify")?;
+                request = Some(Request::Verify {
+                    signature_file: signature_file.clone(),
+                });
+            }
+            "--status-fd" => {
+                status_fd = Some(args.next().ok_or("Missing --status-fd value")?.parse()?);
+            }
+            // The object to verify comes from stdin
+            "-" => {}
+            arg if arg.starts_with("--status-fd=") => {
+                status_fd = Some(arg["--status-fd=".len()..].parse()?);
+            }
+            arg if arg.starts_with("--keyid-format") => {}
+            arg => return Err(format!("Unsupported gpg argument: {}", arg).into()),
+        }
+    }
+    let request = request.ok_or("Expected -bsau to sign or --verify to verify")?;
+    let status_fd = status_fd.ok_or("Expected --status-fd")?;
+    if status_fd != 1 && status_fd != 2 {
+        return Err(format!("Unsupported status file descriptor {}", status_fd).into());
+    }
+    Ok((request, status_fd))
+}
+
+/// Answers git's signing or verification request.
+///
+/// # Arguments
+/// - `gpg_args`: The arguments git passed to `gpg.program`.
+/// - `key_file`: Path to the key file of an Ed25519 group key. Verifying only needs its
+///   public part, so a participant share file or PEM group key works as well.
+/// - `signers`: Indices of the participants signing.
+/// - `n`: Total number of participants.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+/// - `stdin`, `stdout`, `stderr`: The standard streams git connected to the program.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the arguments are not a request git makes, signing fails, or
+/// the signature is invalid.
+pub fn run<R: Read, W: Write, E: Write>(
+    gpg_args: &[String],
+    key_file: &str,
+    signers: Vec<u32>,
+    n: u32,
+    transcript_file: Option<&str>,
+    mut stdin: R,
+    mut stdout: W,
+    mut stderr: E,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Parse the request and read the object
+    let (request, status_fd) = parse_args(gpg_args)?;
+    let mut payload = Vec::new();
+    stdin.read_to_end(&mut payload)?;
+    let mut status = String::new();
+
+    // Step 2: Sign or verify the object
+    let result = match request {
+        Request::Sign => sign(
+            &payload,
+            key_file,
+            signers,
+            n,
+            transcript_file,
+            &mut stdout,
+            &mut status,
+            options,
+        ),
+        Request::Verify { signature_file } => {
+            verify(&payload, key_file, &signature_file, &mut status, options)
+        }
+    };
+
+    // Step 3: Report the status lines
+    if status_fd == 1 {
+        stdout.write_all(status.as_bytes())?;
+    } else {
+        stderr.write_all(status.as_bytes())?;
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sign<W: Write>(
+    payload: &[u8],
+    key_file: &str,
+    signers: Vec<u32>,
+    n: u32,
+    transcript_file: Option<&str>,
+    stdout: &mut W,
+    status: &mut String,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, group_key) = load_group_key_bytes(key_file, options)?;
+    let signature = seal_message(
+        payload,
+        SIGNING_CONTEXT,
+        signers,
+        n,
+        key_file,
+        OutFormat::Openpgp,
+        &EnvelopeOptions::default(),
+        transcript_file,
+        &mut OsRng,
+    )?;
+    let created = openpgp::signature_created(&String::from_utf8_lossy(&signature))?;
+    stdout.write_all(&signature)?;
+
+    // git looks for the SIG_CREATED line after a newline
+    status.push_str("[GNUPG:] BEGIN_SIGNING H10\n");
+    status.push_str(&format!(
+        "[GNUPG:] SIG_CREATED D 22 10 00 {} {}\n",
+        created,
+        hex::encode_upper(openpgp::fingerprint(&group_key))
+    ));
+    Ok(())
+}
+
+fn verify(
+    payload: &[u8],
+    key_file: &str,
+    signature_file: &str,
+    status: &mut String,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let fingerprint = hex::encode_upper(openpgp::fingerprint(&group_key));
+    let key_id = &fingerprint[24..];
+    let bytes = fs::read(signature_file)?;
+    status.push_str("[GNUPG:] NEWSIG\n");
+
+    let result = envelope::open(
+        OutFormat::Openpgp,
+        &bytes,
+        ciphersuite,
+        &group_key,
+        payload,
+        &EnvelopeOptions::default(),
+    )
+    .and_then(|opened| {
+        backend::open(ciphersuite)?.verify(
+            &group_key,
+            &opened.signing_input,
+            SIGNING_CONTEXT,
+            &opened.signature,
+        )
+    });
+    if let Err(err) = result {
+        status.push_str(&format!("[GNUPG:] BADSIG {} {}\n", key_id, USER_ID));
+        return Err(err);
+    }
+
+    let created = openpgp::signature_created(&String::from_utf8_lossy(&bytes))?;
+    status.push_str(&format!("[GNUPG:] GOODSIG {} {}\n", key_id, USER_ID));
+    status.push_str(&format!(
+        "[GNUPG:] VALIDSIG {} {} {} 0 4 0 22 10 00 {}\n",
+        fingerprint,
+        utc_date(u64::from(created)),
+        created,
+        fingerprint
+    ));
+    Ok(())
+}
@@ -0,0 +1,331 @@
+//! Long-term participant identity keys.
+//!
+//! A participant creates their identity once, keeps the identity file private and
+//! hands the public `identity_<index>.json` file to the other participants, the same
+//! way round one messages are shared. Transports use the identities to authenticate
+//! and encrypt the messages participants send each other.
+//!
+//! Every DKG and signing round message can also carry an Ed25519 signature by the
+//! identity of its sender, added with [`sign_message_file`]. Receivers check it with
+//! [`verify_message_file`] or [`verify_message`] before using the message, so a
+//! coordinator, mailbox or peer that passes the message on cannot swap in its own
+//! commitments or shares.
+
+use crate::error::FrostCliError;
+use crate::io::format;
+use crate::io::output::report;
+use crate::{read_json, write_json, write_secret_json, FileOptions};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroize;
+
+/// Domain separator for identity signatures on round messages.
+const SIGNATURE_CONTEXT: &[u8] = b"FROST-CLI ROUND MESSAGE";
+
+/// Name of the field that holds the identity signature in a round message.
+const SIGNATURE_FIELD: &str = "identity_signature";
+
+/// A participant's private identity keys. Never leaves the participant's machine.
+#[derive(Serialize, Deserialize)]
+pub struct Identity {
+    pub index: u32,
+    /// X25519 static key used in Noise handshakes.
+    pub noise_private_key: [u8; 32],
+    pub noise_public_key: [u8; 32],
+    /// Ed25519 key used to sign round messages.
+    pub signing_private_key: [u8; 32],
+    pub signing_public_key: [u8; 32],
+}
+
+impl Drop for Identity {
+    fn drop(&mut self) {
+        self.noise_private_key.zeroize();
+        self.signing_private_key.zeroize();
+    }
+}
+
+/// The public half of an [`Identity`], shared with the other participants.
+#[derive(Serialize, Deserialize)]
+pub struct PublicIdentity {
+    pub index: u32,
+    pub noise_public_key: [u8; 32],
+    pub signing_public_key: [u8; 32],
+}
+
+/// A sender's signature on a round message.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IdentitySignature {
+    /// Index of the participant whose identity signed the message.
+    pub signer: u32,
+    /// Hex-encoded Ed25519 signature.
+    pub signature: String,
+}
+
+/// A participant's own identity file and the public identities of the others.
+pub struct IdentityFiles {
+    /// Path to the participant's private identity.
+    pub identity_file: String,
+    /// Directory containing the public identities of all participants.
+    pub identities_dir: String,
+}
+
+/// Creates a new identity for a participant.
+///
+/// # Arguments
+/// - `index`: The participant's index.
+/// - `identity_file`: Path to save the private identity.
+/// - `identities_dir`: Directory to write the public `identity_<index>.json` file to.
+/// - `options`: How the identity files are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the keys cannot be generated or the files cannot be written.
+pub fn generate_identity(
+    index: u32,
+    identity_file: &str,
+    identities_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Generate the Noise static key and the signing key
+    let (noise_private_key, noise_public_key) = crate::net::noise::generate_keypair()?;
+    let mut signing_private_key = [0u8; 32];
+    OsRng.fill_bytes(&mut signing_private_key);
+    let signing_public_key = SigningKey::from_bytes(&signing_private_key)
+        .verifying_key()
+        .to_bytes();
+    let identity = Identity {
+        index,
+        noise_private_key,
+        noise_public_key,
+        signing_private_key,
+        signing_public_key,
+    };
+
+    // Step 2: Save the private identity and publish the public half
+    write_secret_json(identity_file, &identity, options)?;
+    fs::create_dir_all(identities_dir)?;
+    let public_file = public_identity_path(identities_dir, index);
+    write_json(
+        &public_file,
+        &PublicIdentity {
+            index,
+            noise_public_key,
+            signing_public_key,
+        },
+        options,
+    )?;
+
+    report(
+        "identity_saved",
+        &format!(
+            "Identity for participant {} saved to: {}, public identity to: {}",
+            index,
+            identity_file,
+            public_file.display()
+        ),
+        json!({
+            "index": index,
+            "identity_file": identity_file,
+            "public_identity_file": public_file,
+        }),
+    );
+    Ok(())
+}
+
+/// Loads a participant's private identity file.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a valid identity file.
+pub fn load_identity(identity_file: &str) -> Result<Identity, Box<dyn std::error::Error>> {
+    read_json(identity_file)
+}
+
+/// Loads the public identity of participant `index` from `identities_dir`.
+///
+/// # Errors
+/// Returns an error if the participant's public identity file is missing or invalid.
+pub fn load_public_identity(
+    identities_dir: &str,
+    index: u32,
+) -> Result<PublicIdentity, Box<dyn std::error::Error>> {
+    let public_file = public_identity_path(identities_dir, index);
+    let identity: PublicIdentity = read_json(&public_file).map_err(|err| {
+        format!(
+            "Failed to load identity of participant {} from {}: {}",
+            index,
+            public_file.display(),
+            err
+        )
+    })?;
+    if identity.index != index {
+        return Err(format!("Unexpected identity file: {}", public_file.display()).into());
+    }
+    Ok(identity)
+}
+
+/// Signs the round message in `path` with the sender's identity, in place.
+///
+/// The message must be sent by the identity's participant, that is its `sender` field,
+/// or its `index` field if it has none, must be the identity's index. Any previous
+/// identity signature is replaced, and the message keeps the format it was written in.
+///
+/// # Errors
+/// Returns an error if the file is not a round message of this participant.
+pub fn sign_message_file<P: AsRef<Path>>(
+    identity_file: &str,
+    path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the identity and the message without any old signature
+    let path = path.as_ref();
+    let identity = load_identity(identity_file)?;
+    let bytes = fs::read(path).map_err(|err| FrostCliError::io(path, err))?;
+    let mut message: Value = format::decode(&bytes)?;
+    let fields = message
+        .as_object_mut()
+        .ok_or_else(|| format!("Not a round message: {}", path.display()))?;
+    fields.remove(SIGNATURE_FIELD);
+    let sender = message_sender(fields)?;
+    if sender != identity.index {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "{} was sent by participant {}, not by participant {}",
+            path.display(),
+            sender,
+            identity.index
+        ))
+        .into());
+    }
+
+    // Step 2: Sign the message and store the signature in it
+    let signature = SigningKey::from_bytes(&identity.signing_private_key)
+        .sign(&signed_payload(fields)?)
+        .to_bytes();
+    fields.insert(
+        SIGNATURE_FIELD.to_string(),
+        serde_json::to_value(IdentitySignature {
+            signer: identity.index,
+            signature: hex::encode(signature),
+        })?,
+    );
+    write_json(
+        path,
+        &message,
+        &FileOptions {
+            format: format::detect(&bytes),
+            ..Default::default()
+        },
+    )?;
+
+    report(
+        "message_signed",
+        &format!(
+            "Round message {} signed by participant {}",
+            path.display(),
+            identity.index
+        ),
+        json!({ "file": path, "index": identity.index }),
+    );
+    Ok(())
+}
+
+/// Checks the identity signature on the round message in `path`.
+///
+/// Returns the index of the participant that sent the message.
+///
+/// # Errors
+/// Returns a [`FrostCliError::InvalidMessage`] naming the sender if the message is
+/// unsigned or not signed by the identity of its sender, or an error if it is not a
+/// round message or the sender's public identity cannot be loaded.
+pub fn verify_message_file<P: AsRef<Path>>(
+    identities_dir: &str,
+    path: P,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    verify_value(identities_dir, read_json(path)?)
+}
+
+/// Checks the identity signature on a round message that has already been loaded.
+///
+/// Returns the index of the participant that sent the message.
+///
+/// # Errors
+/// Returns a [`FrostCliError::InvalidMessage`] naming the sender if the message is
+/// unsigned or not signed by the identity of its sender, or an error if it is not a
+/// round message or the sender's public identity cannot be loaded.
+pub fn verify_message<T: Serialize>(
+    identities_dir: &str,
+    message: &T,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    verify_value(identities_dir, serde_json::to_value(message)?)
+}
+
+fn verify_value(
+    identities_dir: &str,
+    mut message: Value,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    // Step 1: Split the signature off the message
+    let fields = message.as_object_mut().ok_or("Not a round message")?;
+    let sender = message_sender(fields)?;
+    let signature: IdentitySignature = match fields.remove(SIGNATURE_FIELD) {
+        Some(Value::Null) | None => {
+            return Err(FrostCliError::InvalidMessage {
+                index: sender,
+                reason: format!("Round message from participant {} is not signed", sender),
+            }
+            .into())
+        }
+        Some(signature) => serde_json::from_value(signature)?,
+    };
+    if signature.signer != sender {
+        return Err(FrostCliError::InvalidMessage {
+            index: sender,
+            reason: format!(
+                "Round message from participant {} was signed by participant {}",
+                sender, signature.signer
+            ),
+        }
+        .into());
+    }
+
+    // Step 2: Check the signature against the sender's public identity
+    let public = load_public_identity(identities_dir, sender)?;
+    let invalid = || FrostCliError::InvalidMessage {
+        index: sender,
+        reason: format!(
+            "Invalid signature on round message from participant {}",
+            sender
+        ),
+    };
+    let verifying_key =
+        VerifyingKey::from_bytes(&public.signing_public_key).map_err(|_| invalid())?;
+    let signature =
+        Signature::from_slice(&hex::decode(&signature.signature)?).map_err(|_| invalid())?;
+    verifying_key
+        .verify_strict(&signed_payload(fields)?, &signature)
+        .map_err(|_| invalid())?;
+    Ok(sender)
+}
+
+/// Returns the participant a round message claims to come from.
+fn message_sender(fields: &Map<String, Value>) -> Result<u32, Box<dyn std::error::Error>> {
+    fields
+        .get("sender")
+        .or_else(|| fields.get("index"))
+        .and_then(Value::as_u64)
+        .and_then(|index| u32::try_from(index).ok())
+        .ok_or_else(|| "Round message has no sender".into())
+}
+
+/// The bytes an identity signature covers: the message without its signature.
+fn signed_payload(fields: &Map<String, Value>) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut payload = SIGNATURE_CONTEXT.to_vec();
+    payload.extend(serde_json::to_vec(fields)?);
+    Ok(payload)
+}
+
+/// Path of participant `index`'s public identity inside `dir`.
+fn public_identity_path(dir: &str, index: u32) -> PathBuf {
+    Path::new(dir).join(format!("identity_{}.json", index))
+}
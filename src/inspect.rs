@@ -0,0 +1,383 @@
+//! Inspecting key and signature files without using them.
+//!
+//! Answering basic questions about a key file, such as its threshold or which
+//! participants' shares it holds, otherwise means reading its raw JSON, which also
+//! puts the private shares on screen. [`inspect_key`] reads any file or location that
+//! carries a group key and returns only its public parameters and whether it holds
+//! private shares, and if so, whether they are encrypted. No secret material is ever
+//! part of the result.
+//!
+//! [`inspect_signature`] decodes a signature file and shows what it holds, whether or
+//! not the signature verifies: its format, the signature's `R` and `s` components, and
+//! the metadata recorded with it.
+
+use crate::artifact::SignedFile;
+use crate::backend::Ciphersuite;
+use crate::dealer::ShareBundle;
+use crate::io::envelope::{self, OutFormat};
+use crate::io::signature::{SignatureFormat, SIGNATURE_LEN};
+use crate::{
+    io, load_participant_share, parse_versioned, read_checked_json, read_json, storage, utc_date,
+    FileOptions, FrostKeys, ParticipantShare, SignatureFile, SignatureMetadata,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use frost_dalek::signature::ThresholdSignature;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+
+/// Kind of file a group key was read from.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyFileKind {
+    /// A dealer key file with the shares of every participant.
+    Keys,
+    /// A single participant's share.
+    Share,
+    /// A participant's share sealed to their identity, see [`crate::dealer`].
+    ShareBundle,
+    /// A PEM group key, see [`crate::io::pem`].
+    GroupKey,
+}
+
+impl fmt::Display for KeyFileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyFileKind::Keys => "dealer key file",
+            KeyFileKind::Share => "participant share",
+            KeyFileKind::ShareBundle => "encrypted share bundle",
+            KeyFileKind::GroupKey => "PEM group key",
+        })
+    }
+}
+
+/// How a key file holds private shares.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Shares {
+    /// The file holds no private share.
+    None,
+    /// The shares are in the clear.
+    Plaintext,
+    /// The share is sealed to its participant's identity.
+    Encrypted,
+    /// The share is kept by a share storage backend, see [`crate::storage`].
+    Stored,
+}
+
+impl fmt::Display for Shares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Shares::None => "none",
+            Shares::Plaintext => "present, not encrypted",
+            Shares::Encrypted => "present, encrypted",
+            Shares::Stored => "kept in share storage",
+        })
+    }
+}
+
+/// The public parameters of a key file, see [`inspect_key`].
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyInspection {
+    pub kind: KeyFileKind,
+    /// Format version of the file, left out for files that carry none.
+    pub version: Option<u32>,
+    pub ciphersuite: Ciphersuite,
+    /// Hex-encoded group key.
+    pub group_key: String,
+    /// Base64-encoded group key.
+    pub group_key_base64: String,
+    /// Fingerprint of the group key, see [`envelope::fingerprint`].
+    pub fingerprint: String,
+    /// Signing threshold, unknown for a PEM group key.
+    pub threshold: Option<u32>,
+    /// Number of participants of the group, unknown for a PEM group key.
+    pub participants: Option<u32>,
+    /// Indices of the participants whose shares the file holds.
+    pub indices: Vec<u32>,
+    pub shares: Shares,
+}
+
+impl KeyInspection {
+    fn new(kind: KeyFileKind, ciphersuite: Ciphersuite, group_key: &[u8; 32]) -> Self {
+        KeyInspection {
+            kind,
+            version: None,
+            ciphersuite,
+            group_key: hex::encode(group_key),
+            group_key_base64: BASE64.encode(group_key),
+            fingerprint: envelope::fingerprint(group_key),
+            threshold: None,
+            participants: None,
+            indices: Vec::new(),
+            shares: Shares::None,
+        }
+    }
+}
+
+impl fmt::Display for KeyInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Kind: {}", self.kind)?;
+        if let Some(version) = self.version {
+            writeln!(f, "Format version: {}", version)?;
+        }
+        writeln!(f, "Ciphersuite: {}", self.ciphersuite)?;
+        writeln!(f, "Group key (hex): {}", self.group_key)?;
+        writeln!(f, "Group key (base64): {}", self.group_key_base64)?;
+        writeln!(f, "Fingerprint: {}", self.fingerprint)?;
+        if let (Some(threshold), Some(participants)) = (self.threshold, self.participants) {
+            writeln!(f, "Threshold: {} of {}", threshold, participants)?;
+        }
+        if !self.indices.is_empty() {
+            let indices: Vec<String> = self.indices.iter().map(u32::to_string).collect();
+            writeln!(f, "Shares of participants: {}", indices.join(", "))?;
+        }
+        write!(f, "Private shares: {}", self.shares)
+    }
+}
+
+/// Returns the public parameters of a key file, participant share, share bundle or PEM
+/// group key, without any of its secret material.
+///
+/// # Arguments
+/// - `key_file`: Path to the file, or a share location, see [`crate::storage`].
+/// - `options`: File key the file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the file cannot be read or carries no group key.
+pub fn inspect_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<KeyInspection, Box<dyn std::error::Error>> {
+    // Step 1: Shares kept elsewhere than in a file are only read through their backend
+    if !storage::is_file(key_file) {
+        let share = load_participant_share(key_file, options)?;
+        return Ok(share_inspection(&share, Shares::Stored));
+    }
+
+    // Step 2: Tell the kind of file apart
+    if io::pem::is_pem(&fs::read(key_file)?) {
+        let group_key = io::pem::decode_group_key(&fs::read_to_string(key_file)?)?;
+        return Ok(KeyInspection::new(
+            KeyFileKind::GroupKey,
+            Ciphersuite::Ristretto255,
+            &group_key,
+        ));
+    }
+    let value: serde_json::Value = read_json(key_file)?;
+    if value.get("private_shares").is_some() {
+        let frost_keys: FrostKeys =
+            parse_versioned(value, key_file, "key", options.file_key.as_ref())?;
+        let mut inspection = KeyInspection::new(
+            KeyFileKind::Keys,
+            frost_keys.ciphersuite,
+            &frost_keys.group_key,
+        );
+        inspection.version = Some(frost_keys.version);
+        inspection.threshold = Some(frost_keys.threshold);
+        inspection.participants = Some(frost_keys.private_shares.len() as u32);
+        inspection.indices = frost_keys
+            .private_shares
+            .iter()
+            .map(|(_, index)| *index)
+            .collect();
+        inspection.shares = Shares::Plaintext;
+        return Ok(inspection);
+    }
+    if value.get("ciphertext").is_some() {
+        let bundle: ShareBundle = serde_json::from_value(value)
+            .map_err(|err| format!("Invalid share bundle {}: {}", key_file, err))?;
+        let mut inspection = KeyInspection::new(
+            KeyFileKind::ShareBundle,
+            Ciphersuite::Ristretto255,
+            &bundle.group_key,
+        );
+        inspection.threshold = Some(bundle.threshold);
+        inspection.participants = Some(bundle.participants);
+        inspection.indices = vec![bundle.index];
+        inspection.shares = Shares::Encrypted;
+        return Ok(inspection);
+    }
+
+    // Step 3: Anything else must be a participant share
+    let share: ParticipantShare = read_checked_json(key_file, "share", options.file_key.as_ref())?;
+    Ok(share_inspection(&share, Shares::Plaintext))
+}
+
+fn share_inspection(share: &ParticipantShare, shares: Shares) -> KeyInspection {
+    let mut inspection = KeyInspection::new(
+        KeyFileKind::Share,
+        Ciphersuite::Ristretto255,
+        &share.group_key,
+    );
+    inspection.threshold = Some(share.threshold);
+    inspection.participants = Some(share.participants);
+    inspection.indices = vec![share.index];
+    inspection.shares = shares;
+    inspection
+}
+
+/// What a signature file holds, see [`inspect_signature`].
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SignatureInspection {
+    /// Format of the file: `raw`, `hex`, `base64`, `pem` or `json` for a signature
+    /// file, or the name of the envelope, see [`OutFormat`].
+    pub format: String,
+    /// Format version of a `json` signature file.
+    pub version: Option<u32>,
+    /// Hex-encoded commitment `R`, the first half of the signature. Left out of
+    /// envelopes, whose signature is only taken out when verifying them.
+    pub r: Option<String>,
+    /// Hex-encoded response `s`, the second half of the signature.
+    pub s: Option<String>,
+    /// Whether the signature decodes as a frost-dalek `ThresholdSignature`, with `R` a
+    /// valid Ristretto point and `s` a canonical scalar. Left out for signatures of
+    /// other ciphersuites and for envelopes.
+    pub threshold_signature: Option<bool>,
+    pub metadata: Option<SignatureMetadata>,
+    /// The signed file, for signatures made with `sign --file`.
+    pub file: Option<SignedFile>,
+    /// Whether the file carries an RFC 3161 timestamp token, see [`crate::timestamp`].
+    pub timestamped: bool,
+}
+
+impl fmt::Display for SignatureInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Format: {}", self.format)?;
+        if let Some(version) = self.version {
+            write!(f, "\nFormat version: {}", version)?;
+        }
+        match (&self.r, &self.s) {
+            (Some(r), Some(s)) => write!(f, "\nR: {}\ns: {}", r, s)?,
+            _ => write!(
+                f,
+                "\nThe signature of an envelope is only taken out by verify"
+            )?,
+        }
+        if let Some(valid) = self.threshold_signature {
+            let answer = if valid { "yes" } else { "no" };
+            write!(f, "\nParses as a ThresholdSignature: {}", answer)?;
+        }
+        if let Some(metadata) = &self.metadata {
+            write!(f, "\nCiphersuite: {}", metadata.ciphersuite)?;
+            if !metadata.context.is_empty() {
+                write!(f, "\nContext: {}", metadata.context)?;
+            }
+            write!(
+                f,
+                "\nGroup key fingerprint: {}\nSigners: {:?}\nSigned on: {} (Unix time {})",
+                metadata.group_key_fingerprint,
+                metadata.signers,
+                utc_date(metadata.timestamp),
+                metadata.timestamp
+            )?;
+        }
+        if let Some(file) = &self.file {
+            write!(
+                f,
+                "\nSigned file: {} ({} bytes, SHA-256 {})",
+                file.name, file.size, file.sha256
+            )?;
+        }
+        let answer = if self.timestamped { "yes" } else { "no" };
+        write!(f, "\nTimestamped: {}", answer)
+    }
+}
+
+/// Decodes a signature file and returns what it holds, without verifying the
+/// signature.
+///
+/// # Arguments
+/// - `signature_file`: Path to the signature file or envelope.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or is not a signature file in the format.
+pub fn inspect_signature(
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+) -> Result<SignatureInspection, Box<dyn std::error::Error>> {
+    // Step 1: Envelopes are only told apart by their format
+    let bytes = fs::read(signature_file)?;
+    let out_format = envelope::detect(&bytes);
+    if sig_format.is_none() && out_format != OutFormat::Signature {
+        return Ok(SignatureInspection {
+            format: out_format_name(out_format).to_string(),
+            version: None,
+            r: None,
+            s: None,
+            threshold_signature: None,
+            metadata: None,
+            file: None,
+            timestamped: false,
+        });
+    }
+
+    // Step 2: Decode the signature file in its format
+    let sig_format = sig_format.unwrap_or_else(|| io::signature::detect(&bytes));
+    let (signature, file) = match sig_format {
+        SignatureFormat::Json => {
+            let file: SignatureFile = parse_versioned(
+                io::format::decode(&bytes)?,
+                signature_file,
+                "signature",
+                None,
+            )?;
+            if file.signature.len() != SIGNATURE_LEN {
+                return Err("Invalid length for threshold signature".into());
+            }
+            (file.signature.clone(), Some(file))
+        }
+        _ => (
+            io::signature::decode(&bytes, signature_file, sig_format)?,
+            None,
+        ),
+    };
+
+    // Step 3: Split the signature and check that it decodes in its ciphersuite
+    let metadata = file.as_ref().and_then(|file| file.metadata.clone());
+    let ciphersuite = metadata
+        .as_ref()
+        .map_or(Ciphersuite::Ristretto255, |metadata| metadata.ciphersuite);
+    let mut bytes = [0u8; SIGNATURE_LEN];
+    bytes.copy_from_slice(&signature);
+    let threshold_signature = ciphersuite
+        .is_ristretto255()
+        .then(|| ThresholdSignature::from_bytes(bytes).is_ok());
+    Ok(SignatureInspection {
+        format: signature_format_name(sig_format).to_string(),
+        version: file.as_ref().map(|file| file.version),
+        r: Some(hex::encode(&signature[..32])),
+        s: Some(hex::encode(&signature[32..])),
+        threshold_signature,
+        metadata,
+        timestamped: file
+            .as_ref()
+            .is_some_and(|file| file.timestamp_token.is_some()),
+        file: file.and_then(|file| file.file),
+    })
+}
+
+fn signature_format_name(format: SignatureFormat) -> &'static str {
+    match format {
+        SignatureFormat::Raw => "raw",
+        SignatureFormat::Hex => "hex",
+        SignatureFormat::Base64 => "base64",
+        SignatureFormat::Pem => "pem",
+        SignatureFormat::Json => "json",
+    }
+}
+
+fn out_format_name(format: OutFormat) -> &'static str {
+    match format {
+        OutFormat::Signature => "signature",
+        OutFormat::Jws => "jws",
+        OutFormat::Sshsig => "sshsig",
+        OutFormat::Openpgp => "openpgp",
+        OutFormat::OpenpgpKey => "openpgp-key",
+        OutFormat::CosignBundle => "cosign-bundle",
+        OutFormat::Cose => "cose",
+        OutFormat::Dsse => "dsse",
+    }
+}
@@ -0,0 +1,202 @@
+//! COSE_Sign1 envelopes and COSE keys, see [RFC 9052].
+//!
+//! COSE only registers a signature algorithm for the Ed25519 ciphersuite, `EdDSA`
+//! (-8), so only Ed25519 keys can sign a COSE_Sign1. The algorithm goes in the
+//! protected header and the key identifier, the SHA-256 hash of the group key, in the
+//! unprotected header. The same key identifier is set on the group key exported as a
+//! `COSE_Key` by [`encode_key`], so devices provisioned with that key find it for the
+//! envelopes it signed.
+//!
+//! [RFC 9052]: https://www.rfc-editor.org/rfc/rfc9052
+
+use crate::backend::Ciphersuite;
+use crate::io::envelope::{key_id, Opened};
+use crate::InvalidSignature;
+use ciborium::Value;
+
+/// CBOR tag of a COSE_Sign1.
+const SIGN1_TAG: u64 = 18;
+
+/// Header label of the algorithm.
+const HEADER_ALG: i64 = 1;
+
+/// Header label of the critical headers.
+const HEADER_CRIT: i64 = 2;
+
+/// Header label of the key identifier.
+const HEADER_KID: i64 = 4;
+
+/// COSE algorithm `EdDSA`.
+const ALG_EDDSA: i64 = -8;
+
+/// Returns whether `bytes` hold a tagged COSE_Sign1, an array of four items with tag 18.
+pub fn is_sign1(bytes: &[u8]) -> bool {
+    if !bytes.starts_with(&[0xd2, 0x84]) {
+        return false;
+    }
+    match ciborium::from_reader::<Value, _>(bytes) {
+        Ok(Value::Tag(SIGN1_TAG, value)) => {
+            matches!(*value, Value::Array(items) if items.len() == 4)
+        }
+        _ => false,
+    }
+}
+
+/// Returns the COSE algorithm of a ciphersuite.
+fn algorithm(ciphersuite: Ciphersuite) -> Result<i64, Box<dyn std::error::Error>> {
+    match ciphersuite {
+        Ciphersuite::Ed25519 => Ok(ALG_EDDSA),
+        _ => Err(format!(
+            "COSE has no algorithm for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into()),
+    }
+}
+
+fn to_vec(value: &Value) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Returns the encoded protected header naming the algorithm.
+fn protected_header(ciphersuite: Ciphersuite) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    to_vec(&Value::Map(vec![(
+        HEADER_ALG.into(),
+        algorithm(ciphersuite)?.into(),
+    )]))
+}
+
+/// Returns the encoded `Sig_structure` a COSE_Sign1 signature is made over.
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    to_vec(&Value::Array(vec![
+        Value::Text("Signature1".to_string()),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+        Value::Bytes(payload.to_vec()),
+    ]))
+}
+
+/// Returns the bytes the group signs for a COSE_Sign1 carrying `message`.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no COSE algorithm.
+pub fn signing_input(
+    ciphersuite: Ciphersuite,
+    message: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    sig_structure(&protected_header(ciphersuite)?, message)
+}
+
+/// Returns the tagged COSE_Sign1 of a message and its signature over [`signing_input`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no COSE algorithm.
+pub fn encode(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    to_vec(&Value::Tag(
+        SIGN1_TAG,
+        Box::new(Value::Array(vec![
+            Value::Bytes(protected_header(ciphersuite)?),
+            Value::Map(vec![(HEADER_KID.into(), Value::Bytes(key_id(group_key)))]),
+            Value::Bytes(message.to_vec()),
+            Value::Bytes(signature.to_vec()),
+        ])),
+    ))
+}
+
+/// Returns the value of an integer label in a header map.
+fn header_value(header: &[(Value, Value)], label: i64) -> Option<&Value> {
+    header
+        .iter()
+        .find(|(key, _)| key.as_integer().map(i128::from) == Some(label.into()))
+        .map(|(_, value)| value)
+}
+
+/// Takes the signature out of a COSE_Sign1 whose payload is `message`.
+///
+/// # Errors
+/// Returns an error if the COSE_Sign1 is malformed, uses another algorithm than the
+/// ciphersuite, has critical headers, or carries another key identifier or message.
+pub fn open(
+    bytes: &[u8],
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let value: Value = ciborium::from_reader(bytes)?;
+    let Value::Tag(SIGN1_TAG, value) = value else {
+        return Err("Expected a COSE_Sign1 tagged 18".into());
+    };
+    let Value::Array(items) = *value else {
+        return Err("A COSE_Sign1 is an array".into());
+    };
+    let [Value::Bytes(protected), Value::Map(unprotected), payload, Value::Bytes(signature)] =
+        &items[..]
+    else {
+        return Err(
+            "A COSE_Sign1 has a protected header, an unprotected header, a payload and a signature"
+                .into(),
+        );
+    };
+
+    // Step 1: Check the headers
+    let Value::Map(header) = ciborium::from_reader::<Value, _>(&protected[..])? else {
+        return Err("The COSE protected header is not a map".into());
+    };
+    let alg = header_value(&header, HEADER_ALG)
+        .and_then(Value::as_integer)
+        .map(i128::from);
+    if alg != Some(algorithm(ciphersuite)?.into()) {
+        return Err(format!("Unexpected COSE algorithm {:?}", alg).into());
+    }
+    if header_value(&header, HEADER_CRIT).is_some() {
+        return Err("Unsupported critical COSE headers".into());
+    }
+    let kid = header_value(&header, HEADER_KID).or(header_value(unprotected, HEADER_KID));
+    if let Some(kid) = kid {
+        if kid.as_bytes() != Some(&key_id(group_key)) {
+            return Err(
+                InvalidSignature("COSE_Sign1 was signed with another group key".into()).into(),
+            );
+        }
+    }
+
+    // Step 2: Check the payload
+    let Value::Bytes(payload) = payload else {
+        return Err("Detached COSE payloads are not supported".into());
+    };
+    if payload != message {
+        return Err(InvalidSignature("The COSE_Sign1 carries a different message".into()).into());
+    }
+    Ok(Opened {
+        signing_input: sig_structure(protected, payload)?,
+        signature: signature
+            .clone()
+            .try_into()
+            .map_err(|_| "Invalid length for threshold signature")?,
+    })
+}
+
+/// Returns the group key as a `COSE_Key` with its key identifier, see [`key_id`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no COSE algorithm.
+pub fn encode_key(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    // kty OKP (1), kid, alg, crv Ed25519 (6), x
+    to_vec(&Value::Map(vec![
+        (Value::from(1i64), Value::from(1i64)),
+        (Value::from(2i64), Value::Bytes(key_id(group_key))),
+        (Value::from(3i64), algorithm(ciphersuite)?.into()),
+        (Value::from(-1i64), Value::from(6i64)),
+        (Value::from(-2i64), Value::Bytes(group_key.to_vec())),
+    ]))
+}
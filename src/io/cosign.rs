@@ -0,0 +1,105 @@
+//! cosign blob signature bundles.
+//!
+//! `cosign sign-blob --bundle` saves a blob's signature as a JSON bundle, which
+//! `cosign verify-blob --bundle` checks against the signer's public key. cosign signs
+//! blobs with Ed25519 keys without hashing them first, so a threshold signature of the
+//! blob by an Ed25519 group key is exactly what it expects. The bundle carries no
+//! transparency log entry, so it has to be verified with `--insecure-ignore-tlog`.
+
+use crate::backend::Ciphersuite;
+use crate::io::envelope::Opened;
+use crate::io::pem;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// PEM label of a PKIX public key.
+pub const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+
+/// DER prefix of the `SubjectPublicKeyInfo` of an Ed25519 public key.
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// A cosign blob signature bundle.
+#[derive(Serialize, Deserialize)]
+pub struct Bundle {
+    #[serde(rename = "base64Signature")]
+    pub base64_signature: String,
+}
+
+/// Returns whether `bytes` hold a cosign bundle.
+pub fn is_bundle(bytes: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .map(|value| value.get("base64Signature").is_some())
+        .unwrap_or(false)
+}
+
+/// Returns the group key as the PKIX public key cosign loads with `--key`.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no PKIX key type cosign verifies.
+pub fn public_key(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    Ok(pem::encode(
+        PUBLIC_KEY_LABEL,
+        &[ED25519_SPKI_PREFIX, &group_key[..]].concat(),
+    ))
+}
+
+/// Returns the bytes cosign verifies a blob signature over, the blob itself.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no PKIX key type cosign verifies.
+pub fn signing_input(
+    ciphersuite: Ciphersuite,
+    blob: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    Ok(blob.to_vec())
+}
+
+/// Returns the bundle of a blob signature.
+///
+/// # Errors
+/// Returns an error if the bundle cannot be serialized.
+pub fn encode(signature: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_vec_pretty(&Bundle {
+        base64_signature: BASE64.encode(signature),
+    })?)
+}
+
+/// Takes the signature of `blob` out of a bundle.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no PKIX key type or the bundle is malformed.
+pub fn open(
+    bytes: &[u8],
+    ciphersuite: Ciphersuite,
+    blob: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let signing_input = signing_input(ciphersuite, blob)?;
+    let bundle: Bundle = serde_json::from_slice(bytes)?;
+    Ok(Opened {
+        signing_input,
+        signature: BASE64
+            .decode(&bundle.base64_signature)
+            .map_err(|err| format!("Invalid cosign bundle signature: {}", err))?
+            .try_into()
+            .map_err(|_| "Invalid length for threshold signature")?,
+    })
+}
+
+fn check_ciphersuite(ciphersuite: Ciphersuite) -> Result<(), Box<dyn std::error::Error>> {
+    if ciphersuite != Ciphersuite::Ed25519 {
+        return Err(format!(
+            "cosign has no key type for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! DSSE envelopes, the signature envelope of in-toto and SLSA attestations, see the
+//! [DSSE protocol].
+//!
+//! DSSE signs the pre-authentication encoding of a payload and its type, and wraps the
+//! base64 payload, its type and the signature in a JSON envelope. DSSE leaves the
+//! algorithm to the verifier's key, so envelopes can be made with any ciphersuite, but
+//! in-toto and Sigstore tooling only check Ed25519 signatures. The signature's `keyid`
+//! is the hex-encoded key identifier of [`crate::io::envelope::key_id`].
+//!
+//! [DSSE protocol]: https://github.com/secure-systems-lab/dsse/blob/master/protocol.md
+
+use crate::io::envelope::{key_id, Opened};
+use crate::InvalidSignature;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Payload type of envelopes unless another is given, that of an in-toto statement, see
+/// [`crate::io::envelope::EnvelopeOptions`].
+pub const DEFAULT_PAYLOAD_TYPE: &str = "application/vnd.in-toto+json";
+
+/// A DSSE envelope.
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    #[serde(rename = "payloadType")]
+    pub payload_type: String,
+    /// Base64 payload.
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+/// A signature in a DSSE envelope.
+#[derive(Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    /// Base64 signature.
+    pub sig: String,
+}
+
+/// Returns whether `bytes` hold a DSSE envelope.
+pub fn is_envelope(bytes: &[u8]) -> bool {
+    serde_json::from_slice::<serde_json::Value>(bytes)
+        .map(|value| value.get("payloadType").is_some() && value.get("signatures").is_some())
+        .unwrap_or(false)
+}
+
+/// Returns the pre-authentication encoding of a payload, the bytes DSSE signs.
+pub fn signing_input(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut pae = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    pae.extend_from_slice(payload);
+    pae
+}
+
+/// Returns the envelope of a payload and its signature over [`signing_input`].
+///
+/// # Errors
+/// Returns an error if the envelope cannot be serialized.
+pub fn encode(
+    group_key: &[u8; 32],
+    payload_type: &str,
+    payload: &[u8],
+    signature: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_vec_pretty(&Envelope {
+        payload_type: payload_type.to_string(),
+        payload: BASE64.encode(payload),
+        signatures: vec![EnvelopeSignature {
+            keyid: hex::encode(key_id(group_key)),
+            sig: BASE64.encode(signature),
+        }],
+    })?)
+}
+
+/// Takes the group's signature out of an envelope whose payload is `message`.
+///
+/// The signature is the first one whose `keyid` names the group key or is empty.
+///
+/// # Errors
+/// Returns an error if the envelope is malformed, has another payload type than
+/// `payload_type`, carries another message or has no signature by the group key.
+pub fn open(
+    bytes: &[u8],
+    group_key: &[u8; 32],
+    payload_type: &str,
+    message: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let envelope: Envelope = serde_json::from_slice(bytes)?;
+
+    // Step 1: Check the payload type and payload
+    if envelope.payload_type != payload_type {
+        return Err(format!(
+            "DSSE envelope has payload type {}, expected {}",
+            envelope.payload_type, payload_type
+        )
+        .into());
+    }
+    let payload = BASE64
+        .decode(&envelope.payload)
+        .map_err(|err| format!("Invalid DSSE payload: {}", err))?;
+    if payload != message {
+        return Err(
+            InvalidSignature("The DSSE envelope carries a different message".into()).into(),
+        );
+    }
+
+    // Step 2: Find the group's signature
+    let keyid = hex::encode(key_id(group_key));
+    let signature = envelope
+        .signatures
+        .iter()
+        .find(|signature| signature.keyid.is_empty() || signature.keyid == keyid)
+        .ok_or("DSSE envelope has no signature by the group key")?;
+    Ok(Opened {
+        signing_input: signing_input(&envelope.payload_type, &payload),
+        signature: BASE64
+            .decode(&signature.sig)
+            .map_err(|err| format!("Invalid DSSE signature: {}", err))?
+            .try_into()
+            .map_err(|_| "Invalid length for threshold signature")?,
+    })
+}
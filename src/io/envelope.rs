@@ -0,0 +1,211 @@
+//! Envelopes that carry a signed message together with its signature.
+//!
+//! A plain signature file only holds the 64 signature bytes, see
+//! [`crate::io::signature`], and leaves it to the verifier to get hold of the message.
+//! Other systems expect the message and signature wrapped in their own envelope, and
+//! most of them sign an encoding of the envelope rather than the bare message. So the
+//! output format is chosen before signing, and [`seal`] hands the bytes the group has
+//! to sign to the signing code and wraps the resulting signature.
+//!
+//! Envelopes are recognised by [`detect`] when a signature is verified.
+
+use crate::backend::Ciphersuite;
+use crate::io::files::FileOptions;
+use crate::io::jcs::Canonicalization;
+use crate::io::signature::SignatureFormat;
+use crate::io::{cose, cosign, dsse, jws, openpgp, signature, sshsig};
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Parameters an envelope binds its signature to besides the message, how a plain
+/// signature file is encoded, and how the message is canonicalized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvelopeOptions {
+    /// Namespace of an SSH signature, see [`sshsig::DEFAULT_NAMESPACE`].
+    pub namespace: String,
+    /// Payload type of a DSSE envelope, see [`dsse::DEFAULT_PAYLOAD_TYPE`].
+    pub payload_type: String,
+    /// Encoding a plain signature is saved in, see [`signature`]. Verifying detects it
+    /// from the signature file instead.
+    pub signature_format: SignatureFormat,
+    /// How a `json` signature file is written and key files are read, see
+    /// [`FileOptions`].
+    pub files: FileOptions,
+    /// How the message is canonicalized before it is signed or verified, see
+    /// [`crate::io::jcs`].
+    pub canonicalization: Canonicalization,
+}
+
+impl Default for EnvelopeOptions {
+    fn default() -> Self {
+        EnvelopeOptions {
+            namespace: sshsig::DEFAULT_NAMESPACE.to_string(),
+            payload_type: dsse::DEFAULT_PAYLOAD_TYPE.to_string(),
+            signature_format: SignatureFormat::Json,
+            files: FileOptions::default(),
+            canonicalization: Canonicalization::None,
+        }
+    }
+}
+
+/// Output format of a signed message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutFormat {
+    /// A signature file in the signature format, see [`crate::io::signature`].
+    Signature,
+    /// A compact JWS, see [`crate::io::jws`].
+    Jws,
+    /// An armored OpenSSH signature, see [`crate::io::sshsig`].
+    Sshsig,
+    /// An armored OpenPGP detached signature, see [`crate::io::openpgp`].
+    Openpgp,
+    /// The group key as an armored OpenPGP public key, with the message as its user ID.
+    OpenpgpKey,
+    /// A cosign blob signature bundle, see [`crate::io::cosign`].
+    CosignBundle,
+    /// A tagged COSE_Sign1 carrying the message, see [`crate::io::cose`].
+    Cose,
+    /// A DSSE envelope carrying the message, see [`crate::io::dsse`].
+    Dsse,
+}
+
+impl FromStr for OutFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "signature" => Ok(OutFormat::Signature),
+            "jws" => Ok(OutFormat::Jws),
+            "sshsig" => Ok(OutFormat::Sshsig),
+            "openpgp" => Ok(OutFormat::Openpgp),
+            "openpgp-key" => Ok(OutFormat::OpenpgpKey),
+            "cosign-bundle" => Ok(OutFormat::CosignBundle),
+            "cose" => Ok(OutFormat::Cose),
+            "dsse" => Ok(OutFormat::Dsse),
+            _ => Err(format!(
+                "Unknown output format {}, expected signature, jws, sshsig, openpgp, openpgp-key, cosign-bundle, cose or dsse",
+                format
+            )),
+        }
+    }
+}
+
+/// The signature taken out of an envelope.
+pub struct Opened {
+    /// The bytes the signature was made over.
+    pub signing_input: Vec<u8>,
+    pub signature: [u8; 64],
+}
+
+/// Returns the output format of a signature file from its contents.
+pub fn detect(bytes: &[u8]) -> OutFormat {
+    if jws::is_jws(bytes) {
+        OutFormat::Jws
+    } else if sshsig::is_sshsig(bytes) {
+        OutFormat::Sshsig
+    } else if openpgp::is_signature(bytes) {
+        OutFormat::Openpgp
+    } else if openpgp::is_key(bytes) {
+        OutFormat::OpenpgpKey
+    } else if cosign::is_bundle(bytes) {
+        OutFormat::CosignBundle
+    } else if cose::is_sign1(bytes) {
+        OutFormat::Cose
+    } else if dsse::is_envelope(bytes) {
+        OutFormat::Dsse
+    } else {
+        OutFormat::Signature
+    }
+}
+
+/// Puts `message` in an envelope of the given format with the given options, signing
+/// the envelope's signing input with `sign`.
+///
+/// # Errors
+/// Returns an error if the format has no way to carry signatures of the ciphersuite, or
+/// signing fails.
+pub fn seal<F>(
+    format: OutFormat,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+    options: &EnvelopeOptions,
+    sign: F,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>,
+{
+    match format {
+        OutFormat::Signature => {
+            let signature = sign(message)?;
+            signature::encode(&signature, options.signature_format, options.files.format)
+        }
+        OutFormat::Jws => {
+            let signature = sign(&jws::signing_input(ciphersuite, group_key, message)?)?;
+            Ok(jws::encode(ciphersuite, group_key, message, &signature)?.into_bytes())
+        }
+        OutFormat::Sshsig => {
+            let namespace = &options.namespace;
+            let signature = sign(&sshsig::signing_input(ciphersuite, namespace, message)?)?;
+            Ok(sshsig::encode(ciphersuite, group_key, namespace, &signature)?.into_bytes())
+        }
+        OutFormat::Openpgp => {
+            Ok(openpgp::seal_signature(ciphersuite, group_key, message, sign)?.into_bytes())
+        }
+        OutFormat::OpenpgpKey => {
+            Ok(openpgp::seal_key(ciphersuite, group_key, message, sign)?.into_bytes())
+        }
+        OutFormat::CosignBundle => {
+            let signature = sign(&cosign::signing_input(ciphersuite, message)?)?;
+            cosign::encode(&signature)
+        }
+        OutFormat::Cose => {
+            let signature = sign(&cose::signing_input(ciphersuite, message)?)?;
+            cose::encode(ciphersuite, group_key, message, &signature)
+        }
+        OutFormat::Dsse => {
+            let payload_type = &options.payload_type;
+            let signature = sign(&dsse::signing_input(payload_type, message))?;
+            dsse::encode(group_key, payload_type, message, &signature)
+        }
+    }
+}
+
+/// Returns the key identifier envelopes name the group key by, its SHA-256 hash.
+pub fn key_id(group_key: &[u8; 32]) -> Vec<u8> {
+    Sha256::digest(group_key).to_vec()
+}
+
+/// Returns the fingerprint commands report the group key by, its hex-encoded [`key_id`].
+pub fn fingerprint(group_key: &[u8; 32]) -> String {
+    hex::encode(key_id(group_key))
+}
+
+/// Takes the signature out of an envelope, checking that it is for `message`, names
+/// the given group key and ciphersuite and was made with the given options.
+///
+/// # Errors
+/// Returns an error if the envelope is malformed, carries another message or was made
+/// for another key.
+pub fn open(
+    format: OutFormat,
+    bytes: &[u8],
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+    options: &EnvelopeOptions,
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let text = String::from_utf8_lossy(bytes);
+    match format {
+        OutFormat::Signature => Err("A signature file is not an envelope".into()),
+        OutFormat::Jws => jws::open(&text, ciphersuite, group_key, message),
+        OutFormat::Sshsig => {
+            sshsig::open(&text, ciphersuite, group_key, &options.namespace, message)
+        }
+        OutFormat::Openpgp => openpgp::open_signature(&text, ciphersuite, group_key, message),
+        OutFormat::OpenpgpKey => openpgp::open_key(&text, ciphersuite, group_key, message),
+        OutFormat::CosignBundle => cosign::open(bytes, ciphersuite, message),
+        OutFormat::Cose => cose::open(bytes, ciphersuite, group_key, message),
+        OutFormat::Dsse => dsse::open(bytes, group_key, &options.payload_type, message),
+    }
+}
@@ -0,0 +1,708 @@
+//! Reading and writing key, share and signature files.
+//!
+//! Key and signature files carry a format version and a checksum, see
+//! [`crate::FORMAT_VERSION`], and are encoded in any of the formats of [`io::format`].
+//! Shares can also be kept in other share locations, see [`crate::storage`].
+//!
+//! Every loader reads the file and hands its contents to a `parse_*` function, which
+//! the fuzz targets in `fuzz/` call directly. Those check what the rest of the code
+//! relies on, like a share's index being one of its participants, so a malformed file
+//! fails to load instead of panicking later.
+
+use crate::backend::Ciphersuite;
+use crate::config;
+use crate::error::{invalid_share, FrostCliError};
+use crate::io;
+use crate::io::format::Format;
+use crate::io::signature::SignatureFormat;
+use crate::storage;
+use crate::types::{FrostKeys, KeyFile, ParticipantShare, SignatureMetadata, FORMAT_VERSION};
+use argon2::Argon2;
+use frost_dalek::signature::ThresholdSignature;
+use frost_dalek::GroupKey;
+use hmac::{Hmac, Mac, NewMac};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::Path;
+use zeroize::Zeroizing;
+
+/// How files are written and read, from the command line's `--format` and
+/// [`config::FILE_KEY`], or [`FileOptions::default`] for JSON without a file key.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileOptions {
+    /// Encoding files are written in, see [`io::format`]. Reading detects it instead.
+    pub format: Format,
+    /// Passphrase the checksums of key files and share files are keyed with, see
+    /// [`write_checked_json`], or `None` to write and accept digests.
+    pub file_key: Option<FileKey>,
+}
+
+/// Passphrase the checksums of key files and share files are keyed with, wiped when
+/// dropped and left out of debug output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct FileKey(Zeroizing<String>);
+
+impl FileKey {
+    /// Wraps a passphrase, such as the value of [`config::FILE_KEY`].
+    pub fn new(passphrase: impl Into<String>) -> FileKey {
+        FileKey(Zeroizing::new(passphrase.into()))
+    }
+}
+
+impl fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileKey(..)")
+    }
+}
+
+/// Domain separator for file checksums.
+const CHECKSUM_CONTEXT: &[u8] = b"FROST-CLI FILE CHECKSUM";
+
+/// Prefix of the checksums keyed with a [`FileKey`], followed by the hex salt the MAC key
+/// was derived with, a `:` and the hex MAC.
+pub(crate) const MAC_PREFIX: &str = "argon2id-hmac-sha256:";
+
+/// Length of the random salt of every keyed checksum.
+const MAC_SALT_LEN: usize = 16;
+
+/// Loads a participant share from a share file or another share location, see
+/// [`crate::storage`].
+///
+/// # Errors
+/// Returns an error if the share cannot be read, is not a valid share, or its keyed
+/// checksum does not match the file key of `options`.
+pub fn load_participant_share(
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+    let share = storage::open(share_file, options)?.load()?;
+    share
+        .check()
+        .map_err(|reason| FrostCliError::InvalidShare {
+            share: share_file.to_string(),
+            reason,
+        })?;
+    Ok(share)
+}
+
+/// Parses the contents of a share file, checking its checksum and that its index and
+/// threshold fit its number of participants.
+///
+/// # Errors
+/// Returns [`FrostCliError::InvalidShare`] if the contents are not a valid share.
+pub fn parse_participant_share(
+    bytes: &[u8],
+    share_file: &str,
+    options: &FileOptions,
+) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+    let share: ParticipantShare =
+        parse_checked_json(bytes, share_file, "share", options.file_key.as_ref())
+            .map_err(|err| invalid_share(share_file, err))?;
+    share
+        .check()
+        .map_err(|reason| FrostCliError::InvalidShare {
+            share: share_file.to_string(),
+            reason,
+        })?;
+    Ok(share)
+}
+
+/// Saves a participant share to a share file or another share location, see
+/// [`crate::storage`].
+///
+/// # Errors
+/// Returns an error if the share cannot be written.
+pub fn save_participant_share(
+    share_file: &str,
+    share: &ParticipantShare,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    storage::open(share_file, options)?.save(share)
+}
+
+/// Loads either a key file or a participant share from any share location.
+pub(crate) fn load_key_file(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<KeyFile, Box<dyn std::error::Error>> {
+    if !storage::is_file(key_file) {
+        return Ok(KeyFile::Share(load_participant_share(key_file, options)?));
+    }
+    let value: serde_json::Value = read_json(key_file)?;
+    if value.get("private_shares").is_some() {
+        Ok(KeyFile::Keys(frost_keys_from_value(
+            value, key_file, options,
+        )?))
+    } else {
+        Ok(KeyFile::Share(load_participant_share(key_file, options)?))
+    }
+}
+
+/// Loads a dealer key file, checking its format version.
+///
+/// # Errors
+/// Returns an error if the file is not a key file of the current format version, or its
+/// keyed checksum does not match the file key of `options`.
+pub fn load_frost_keys(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    let bytes = fs::read(key_file).map_err(|err| FrostCliError::io(key_file, err))?;
+    parse_frost_keys(&bytes, key_file, options)
+}
+
+/// Parses the contents of a dealer key file, checking its format version and that its
+/// threshold and participant indices are usable.
+///
+/// # Errors
+/// Returns an error if the contents are not a valid key file of the current format
+/// version.
+pub fn parse_frost_keys(
+    bytes: &[u8],
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    frost_keys_from_value(io::format::decode(bytes)?, key_file, options)
+}
+
+/// Parses and checks the keys of a key file, see [`parse_frost_keys`].
+fn frost_keys_from_value(
+    value: serde_json::Value,
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    let frost_keys: FrostKeys = parse_versioned(value, key_file, "key", options.file_key.as_ref())?;
+    frost_keys
+        .check()
+        .map_err(|reason| format!("Invalid key file {}: {}", key_file, reason))?;
+    Ok(frost_keys)
+}
+
+/// Loads a threshold signature file, checking its format version.
+///
+/// # Arguments
+/// - `signature_file`: Path to the signature file.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+///
+/// # Errors
+/// Returns an error if the file is not a signature file of the current format version
+/// or the signature is malformed.
+pub fn load_signature(
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+) -> Result<ThresholdSignature, Box<dyn std::error::Error>> {
+    ThresholdSignature::from_bytes(read_signature(signature_file, sig_format)?)
+        .map_err(|_| "Failed to deserialize ThresholdSignature".into())
+}
+
+/// Reads the 64 bytes of a signature file of any ciphersuite, see [`load_signature`].
+pub(crate) fn read_signature(
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+    let bytes = fs::read(signature_file).map_err(|err| FrostCliError::io(signature_file, err))?;
+    parse_signature(&bytes, signature_file, sig_format)
+}
+
+/// Parses the contents of a signature file of any ciphersuite into its 64 bytes.
+///
+/// # Arguments
+/// - `bytes`: Contents of the signature file.
+/// - `signature_file`: Path of the signature file, for error messages.
+/// - `sig_format`: Format the signature was saved in, detected from the contents if
+///   `None`.
+///
+/// # Errors
+/// Returns an error if the contents are not a signature in that format.
+pub fn parse_signature(
+    bytes: &[u8],
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+    let sig_format = sig_format.unwrap_or_else(|| io::signature::detect(bytes));
+    io::signature::decode(bytes, signature_file, sig_format)?
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature".into())
+}
+
+/// Saves a threshold signature in the given signature format, a `json` signature file
+/// written in `file_format`, see [`io::signature`].
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+pub fn save_signature(
+    signature_file: &str,
+    signature: &[u8],
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = io::signature::encode(signature, format, file_format)?;
+    fs::write(signature_file, bytes)?;
+    Ok(())
+}
+
+/// Saves a threshold signature and its metadata like [`save_signature`]. Only the
+/// `json` signature format keeps the metadata, see [`io::signature`].
+///
+/// # Errors
+/// Returns an error if the file cannot be written.
+pub fn save_signature_with_metadata(
+    signature_file: &str,
+    signature: &[u8],
+    metadata: &SignatureMetadata,
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes =
+        io::signature::encode_with_metadata(signature, Some(metadata), format, file_format)?;
+    fs::write(signature_file, bytes)?;
+    Ok(())
+}
+
+/// Parses a versioned file strictly, by the rules of the version it declares, after
+/// checking its checksum, see [`verify_checksum`].
+pub(crate) fn parse_versioned<T: DeserializeOwned>(
+    value: serde_json::Value,
+    path: &str,
+    kind: &str,
+    file_key: Option<&FileKey>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match value.get("version").and_then(|version| version.as_u64()) {
+        None => Err(format!(
+            "{} is an unversioned {} file, upgrade it with `frost-cli migrate --file {}`",
+            path, kind, path
+        )
+        .into()),
+        Some(version) if version < u64::from(FORMAT_VERSION) => Err(format!(
+            "{} has {} file format version {}, upgrade it with `frost-cli migrate --file {}`",
+            path, kind, version, path
+        )
+        .into()),
+        Some(version) if version == u64::from(FORMAT_VERSION) => Ok(serde_json::from_value(
+            verify_checksum(value, path, kind, file_key)?,
+        )
+        .map_err(|err| format!("Invalid {} file {}: {}", kind, path, err))?),
+        Some(version) => Err(format!(
+            "{} has {} file format version {}, this frost-cli only reads version {}",
+            path, kind, version, FORMAT_VERSION
+        )
+        .into()),
+    }
+}
+
+/// Loads the group public key from a key file, a participant share file or a PEM group
+/// key, see [`io::pem`].
+pub(crate) fn load_group_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<GroupKey, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    if !ciphersuite.is_ristretto255() {
+        return Err(format!(
+            "{} holds a {} group key, only ristretto255 is supported here",
+            key_file, ciphersuite
+        )
+        .into());
+    }
+    GroupKey::from_bytes(group_key).map_err(|_| "Invalid group public key".into())
+}
+
+/// Loads the ciphersuite and compressed group public key from any file
+/// [`load_group_key`] accepts. PEM group keys are always Ristretto.
+pub(crate) fn load_group_key_bytes(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<(Ciphersuite, [u8; 32]), Box<dyn std::error::Error>> {
+    if storage::is_file(key_file) && io::pem::is_pem(&fs::read(key_file)?) {
+        let group_key = io::pem::decode_group_key(&fs::read_to_string(key_file)?)?;
+        return Ok((Ciphersuite::Ristretto255, group_key));
+    }
+    let key = load_key_file(key_file, options)?;
+    Ok((key.ciphersuite(), key.group_key()))
+}
+
+/// Returns the group public key of a key file or participant share, as hex or as PEM.
+///
+/// # Arguments
+/// - `key_file`: Path to the key file or participant share file.
+/// - `pem`: Armor the group key as PEM instead of returning a line of hex.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded, or PEM is asked for a group key
+/// that is not Ristretto.
+pub fn export_group_key(
+    key_file: &str,
+    pem: bool,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let key = load_key_file(key_file, options)?;
+    let group_key = key.group_key();
+    if pem && !key.ciphersuite().is_ristretto255() {
+        return Err("PEM group keys can only hold Ristretto group keys".into());
+    }
+    if pem {
+        Ok(io::pem::encode_group_key(&group_key))
+    } else {
+        Ok(format!("{}\n", hex::encode(group_key)))
+    }
+}
+
+/// Returns the group public key of an Ed25519 key file as an OpenSSH public key line,
+/// see [`io::sshsig`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded or its ciphersuite has no OpenSSH
+/// key type.
+pub fn export_ssh_public_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    io::sshsig::public_key(ciphersuite, &group_key)
+}
+
+/// Returns the group public key of an Ed25519 key file as a PKIX `PUBLIC KEY` PEM, as
+/// cosign and most TLS and JOSE libraries load it, see [`io::cosign`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded or its ciphersuite has no PKIX key
+/// type.
+pub fn export_pkix_public_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    io::cosign::public_key(ciphersuite, &group_key)
+}
+
+/// Returns the group public key of an Ed25519 key file as a hex-encoded `COSE_Key`, see
+/// [`io::cose`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded or its ciphersuite has no COSE
+/// algorithm.
+pub fn export_cose_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    Ok(format!(
+        "{}\n",
+        hex::encode(io::cose::encode_key(ciphersuite, &group_key)?)
+    ))
+}
+
+/// Reads a file in any of the formats of [`io::format`].
+pub(crate) fn read_json<T: DeserializeOwned, P: AsRef<Path>>(
+    path: P,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let bytes = fs::read(&path).map_err(|err| FrostCliError::io(&path, err))?;
+    io::format::decode(&bytes)
+}
+
+/// Writes a file in the format of `options`.
+pub(crate) fn write_json<T: Serialize, P: AsRef<Path>>(
+    path: P,
+    value: &T,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = io::format::encode_as(value, options.format)?;
+    fs::write(&path, bytes).map_err(|err| FrostCliError::io(&path, err))?;
+    Ok(())
+}
+
+/// Writes a secret file, such as an identity or secret commitments, in the format of
+/// `options`. On Unix only its owner can read or write it.
+pub(crate) fn write_secret_json<T: Serialize, P: AsRef<Path>>(
+    path: P,
+    value: &T,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_secret(
+        path.as_ref(),
+        &io::format::encode_as(value, options.format)?,
+        false,
+    )?;
+    Ok(())
+}
+
+/// Overwrites a secret file with zeros, then removes it.
+///
+/// The secret can no longer be read back through the file system, but copies that a
+/// journaling or copy-on-write file system or an SSD keeps elsewhere are not reached.
+pub(crate) fn secure_delete(path: impl AsRef<Path>) -> Result<(), FrostCliError> {
+    let path = path.as_ref();
+    let to_error = |err| FrostCliError::io(path, err);
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(to_error)?;
+    let len = file.metadata().map_err(to_error)?.len();
+    file.write_all(&vec![0u8; len as usize]).map_err(to_error)?;
+    file.sync_all().map_err(to_error)?;
+    drop(file);
+    fs::remove_file(path).map_err(to_error)
+}
+
+/// Writes `bytes` to a file with mode 0600 on Unix, also restricting the mode of a file
+/// that already exists before anything is written to it. With `create_new`, a file that
+/// already exists is left alone and an error is returned.
+fn write_secret(path: &Path, bytes: &[u8], create_new: bool) -> Result<(), FrostCliError> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|err| FrostCliError::io(path, err))?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(|err| FrostCliError::io(path, err))?;
+    file.write_all(bytes)
+        .map_err(|err| FrostCliError::io(path, err))
+}
+
+/// Writes a secret JSON object, such as a key file or a share file, with a `checksum`
+/// field over the rest of its fields.
+///
+/// With a [`FileOptions::file_key`], the checksum is an HMAC-SHA256 under a key derived
+/// from it with Argon2id and a random salt, which only holders of the passphrase can
+/// recompute. Without it, the checksum is a SHA-256 digest, so it detects corrupted and
+/// accidentally edited files, but anyone who can write the file can also recompute it.
+/// The file is written like [`write_secret_json`].
+pub(crate) fn write_checked_json<T: Serialize, P: AsRef<Path>>(
+    path: P,
+    value: &T,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(value)?;
+    add_secret_checksum(&mut value, options.file_key.as_ref())?;
+    write_secret_json(path, &value, options)
+}
+
+/// Writes a secret JSON object like [`write_checked_json`] to a new file, refusing to
+/// overwrite a file that already exists.
+pub(crate) fn create_checked_json<T: Serialize, P: AsRef<Path>>(
+    path: P,
+    value: &T,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(value)?;
+    add_secret_checksum(&mut value, options.file_key.as_ref())?;
+    write_secret(
+        path.as_ref(),
+        &io::format::encode_as(&value, options.format)?,
+        true,
+    )?;
+    Ok(())
+}
+
+/// Reads a JSON object written by [`write_checked_json`] and checks its checksum against
+/// `file_key`, see [`verify_checksum`].
+///
+/// # Errors
+/// Returns an error if the file has no checksum or does not match it.
+pub(crate) fn read_checked_json<T: DeserializeOwned>(
+    path: &str,
+    kind: &str,
+    file_key: Option<&FileKey>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path).map_err(|err| FrostCliError::io(path, err))?;
+    parse_checked_json(&bytes, path, kind, file_key)
+}
+
+/// Parses the contents of a file written by [`write_checked_json`] and checks its
+/// checksum.
+pub(crate) fn parse_checked_json<T: DeserializeOwned>(
+    bytes: &[u8],
+    path: &str,
+    kind: &str,
+    file_key: Option<&FileKey>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let value = verify_checksum(io::format::decode(bytes)?, path, kind, file_key)?;
+    Ok(serde_json::from_value(value)
+        .map_err(|err| format!("Invalid {} file {}: {}", kind, path, err))?)
+}
+
+/// Sets the `checksum` field of a public JSON object, such as a signature file, to the
+/// digest of its other fields.
+pub(crate) fn add_checksum(
+    value: &mut serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let object = value
+        .as_object_mut()
+        .ok_or("Only JSON objects can carry a checksum")?;
+    object.remove("checksum");
+    let checksum = file_checksum(object)?;
+    object.insert("checksum".to_string(), checksum.into());
+    Ok(())
+}
+
+/// Sets the `checksum` field of a secret JSON object to the HMAC of its other fields
+/// under a key derived from `file_key` with a fresh salt, or to their digest without a
+/// file key.
+pub(crate) fn add_secret_checksum(
+    value: &mut serde_json::Value,
+    file_key: Option<&FileKey>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(file_key) = file_key else {
+        return add_checksum(value);
+    };
+    let object = value
+        .as_object_mut()
+        .ok_or("Only JSON objects can carry a checksum")?;
+    object.remove("checksum");
+    let mut salt = [0u8; MAC_SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mac = file_mac(file_key, &salt, object)?.finalize().into_bytes();
+    object.insert(
+        "checksum".to_string(),
+        format!("{}{}:{}", MAC_PREFIX, hex::encode(salt), hex::encode(mac)).into(),
+    );
+    Ok(())
+}
+
+/// Checks and removes the `checksum` field of a JSON object.
+///
+/// Key files and share files must carry a keyed checksum when a `file_key` is given, so
+/// one cannot be swapped for a file with a digest anyone can recompute.
+pub(crate) fn verify_checksum(
+    value: serde_json::Value,
+    path: &str,
+    kind: &str,
+    file_key: Option<&FileKey>,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let keyed = matches!(
+        value.get("checksum").and_then(serde_json::Value::as_str),
+        Some(checksum) if checksum.starts_with(MAC_PREFIX)
+    );
+    if keyed {
+        let file_key = file_key.ok_or_else(|| {
+            format!(
+                "{} has a keyed checksum, set {} to the key it was written with",
+                path,
+                config::FILE_KEY
+            )
+        })?;
+        return verify_mac(value, file_key, path, kind);
+    }
+    if file_key.is_some() && matches!(kind, "key" | "share") {
+        return Err(format!(
+            "{} has an unkeyed checksum although {} is set, key it with `frost-cli migrate \
+             --file {}`",
+            path,
+            config::FILE_KEY,
+            path
+        )
+        .into());
+    }
+    verify_digest(value, path, kind)
+}
+
+/// Checks and removes the digest in the `checksum` field of a JSON object, even of a
+/// secret file read with a file key, for [`crate::migrate`] to key it.
+pub(crate) fn verify_digest(
+    mut value: serde_json::Value,
+    path: &str,
+    kind: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| format!("Invalid {} file {}", kind, path))?;
+    let checksum = take_checksum(object, path, kind)?;
+    // Anyone can recompute a digest, so a mismatch only shows that the file is corrupted.
+    if file_checksum(object)? != checksum {
+        return Err(format!(
+            "{}: {} file corrupted, its checksum does not match",
+            path, kind
+        )
+        .into());
+    }
+    Ok(value)
+}
+
+/// Checks and removes the HMAC in the `checksum` field of a JSON object.
+fn verify_mac(
+    mut value: serde_json::Value,
+    file_key: &FileKey,
+    path: &str,
+    kind: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| format!("Invalid {} file {}", kind, path))?;
+    let checksum = take_checksum(object, path, kind)?;
+    let (salt, mac) = checksum
+        .strip_prefix(MAC_PREFIX)
+        .and_then(|checksum| checksum.split_once(':'))
+        .and_then(|(salt, mac)| Some((hex::decode(salt).ok()?, hex::decode(mac).ok()?)))
+        .ok_or_else(|| format!("{}: {} file has an invalid checksum", path, kind))?;
+    if file_mac(file_key, &salt, object)?.verify(&mac).is_err() {
+        return Err(format!(
+            "{}: {} file corrupted or tampered, or written with another {}",
+            path,
+            kind,
+            config::FILE_KEY
+        )
+        .into());
+    }
+    Ok(value)
+}
+
+/// Removes the `checksum` field of a JSON object and returns it.
+fn take_checksum(
+    object: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    kind: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    match object.remove("checksum") {
+        Some(serde_json::Value::String(checksum)) => Ok(checksum),
+        Some(_) => Err(format!("{}: {} file has an invalid checksum", path, kind).into()),
+        None => Err(format!(
+            "{} has no checksum, upgrade it with `frost-cli migrate --file {}`",
+            path, path
+        )
+        .into()),
+    }
+}
+
+fn file_checksum(
+    object: &serde_json::Map<String, serde_json::Value>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut h = Sha256::new();
+    h.update(CHECKSUM_CONTEXT);
+    h.update(serde_json::to_vec(object)?);
+    Ok(hex::encode(h.finalize()))
+}
+
+/// Returns the HMAC-SHA256 of a JSON object's fields under the key Argon2id derives from
+/// `file_key` and `salt`.
+fn file_mac(
+    file_key: &FileKey,
+    salt: &[u8],
+    object: &serde_json::Map<String, serde_json::Value>,
+) -> Result<Hmac<Sha256>, Box<dyn std::error::Error>> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(file_key.0.as_bytes(), salt, key.as_mut())
+        .map_err(|err| format!("Cannot derive the file key: {}", err))?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_ref())
+        .map_err(|_| format!("Invalid {}", config::FILE_KEY))?;
+    mac.update(CHECKSUM_CONTEXT);
+    mac.update(&serde_json::to_vec(object)?);
+    Ok(mac)
+}
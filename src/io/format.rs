@@ -0,0 +1,405 @@
+//! JSON, CBOR and bincode encodings of key, share, signature and round message files.
+//!
+//! Every file is written in the format of the [`FileOptions`] it is saved with, JSON
+//! by default. CBOR and bincode files start with a marker, the CBOR self-describe tag
+//! and [`BINCODE_MAGIC`], so [`decode`] reads files in any of the three formats no
+//! matter which format they are written in.
+//!
+//! [`FileOptions`]: crate::io::files::FileOptions
+//!
+//! Both binary formats carry exactly the data of the JSON file, with arrays of small
+//! integers such as keys and signatures stored as byte strings. A file therefore keeps
+//! its format version and checksum when it is converted, and JSON stays the format
+//! other tools can read.
+//!
+//! Files may come from anyone, so [`decode`] refuses nesting deeper than JSON allows
+//! instead of overflowing the stack, see [`MAX_DEPTH`].
+
+use bincode::Options;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, EnumAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
+};
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// CBOR self-describe tag (55799) that starts every CBOR file.
+const CBOR_SELF_DESCRIBE: u64 = 55799;
+
+/// Encoding of the CBOR self-describe tag.
+const CBOR_MAGIC: &[u8] = &[0xd9, 0xd9, 0xf7];
+
+/// Marker at the start of every bincode file.
+pub const BINCODE_MAGIC: &[u8] = b"FROSTBIN";
+
+/// Deepest nesting of lists and maps in a bincode file, the limit serde_json applies to
+/// JSON files.
+pub const MAX_DEPTH: usize = 128;
+
+/// Encoding of a file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    Cbor,
+    Bincode,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "json" => Ok(Format::Json),
+            "cbor" => Ok(Format::Cbor),
+            "bincode" => Ok(Format::Bincode),
+            _ => Err(format!(
+                "Unknown format {}, expected json, cbor or bincode",
+                format
+            )),
+        }
+    }
+}
+
+/// Returns the format of a file from its first bytes.
+pub fn detect(bytes: &[u8]) -> Format {
+    if bytes.starts_with(CBOR_MAGIC) {
+        Format::Cbor
+    } else if bytes.starts_with(BINCODE_MAGIC) {
+        Format::Bincode
+    } else {
+        Format::Json
+    }
+}
+
+/// Encodes `value` in the given format.
+///
+/// # Errors
+/// Returns an error if the value cannot be serialized.
+pub fn encode_as<T: Serialize>(
+    value: &T,
+    format: Format,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match format {
+        Format::Json => Ok(serde_json::to_vec_pretty(value)?),
+        Format::Cbor => {
+            let value = ciborium::Value::Tag(
+                CBOR_SELF_DESCRIBE,
+                Box::new(to_cbor(serde_json::to_value(value)?)),
+            );
+            let mut bytes = Vec::new();
+            ciborium::into_writer(&value, &mut bytes)?;
+            Ok(bytes)
+        }
+        Format::Bincode => {
+            let mut bytes = BINCODE_MAGIC.to_vec();
+            bytes.extend(bincode_options().serialize(&Node::from(serde_json::to_value(value)?))?);
+            Ok(bytes)
+        }
+    }
+}
+
+/// Decodes a file in any format, see [`detect`].
+///
+/// # Errors
+/// Returns an error if the file is not valid in its format or does not hold a `T`.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Box<dyn std::error::Error>> {
+    let value = match detect(bytes) {
+        Format::Json => return Ok(serde_json::from_slice(bytes)?),
+        Format::Cbor => from_cbor(ciborium::from_reader(bytes)?)?,
+        Format::Bincode => bincode_options()
+            .deserialize_seed(NodeSeed(0), &bytes[BINCODE_MAGIC.len()..])?
+            .try_into()?,
+    };
+    Ok(serde_json::from_value(value)?)
+}
+
+fn bincode_options() -> impl Options {
+    bincode::DefaultOptions::new().reject_trailing_bytes()
+}
+
+/// Returns the bytes of an array that only holds integers from 0 to 255.
+fn as_bytes(values: &[serde_json::Value]) -> Option<Vec<u8>> {
+    if values.is_empty() {
+        return None;
+    }
+    values
+        .iter()
+        .map(|value| value.as_u64().and_then(|x| u8::try_from(x).ok()))
+        .collect()
+}
+
+fn to_cbor(value: serde_json::Value) -> ciborium::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null => ciborium::Value::Null,
+        Value::Bool(b) => ciborium::Value::Bool(b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => ciborium::Value::Integer(u.into()),
+            (None, Some(i)) => ciborium::Value::Integer(i.into()),
+            _ => ciborium::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => ciborium::Value::Text(s),
+        Value::Array(values) => match as_bytes(&values) {
+            Some(bytes) => ciborium::Value::Bytes(bytes),
+            None => ciborium::Value::Array(values.into_iter().map(to_cbor).collect()),
+        },
+        Value::Object(object) => ciborium::Value::Map(
+            object
+                .into_iter()
+                .map(|(key, value)| (ciborium::Value::Text(key), to_cbor(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn from_cbor(value: ciborium::Value) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    use serde_json::Value;
+    Ok(match value {
+        ciborium::Value::Null => Value::Null,
+        ciborium::Value::Bool(b) => Value::Bool(b),
+        ciborium::Value::Integer(i) => {
+            let i = i128::from(i);
+            match u64::try_from(i) {
+                Ok(u) => Value::from(u),
+                Err(_) => Value::from(i64::try_from(i)?),
+            }
+        }
+        ciborium::Value::Float(f) => {
+            Value::Number(serde_json::Number::from_f64(f).ok_or("Invalid number in CBOR file")?)
+        }
+        ciborium::Value::Text(s) => Value::String(s),
+        ciborium::Value::Bytes(bytes) => bytes.into_iter().map(Value::from).collect(),
+        ciborium::Value::Array(values) => values
+            .into_iter()
+            .map(from_cbor)
+            .collect::<Result<_, _>>()?,
+        ciborium::Value::Map(entries) => {
+            let mut object = serde_json::Map::new();
+            for (key, value) in entries {
+                let ciborium::Value::Text(key) = key else {
+                    return Err("CBOR file has a map key that is not a string".into());
+                };
+                object.insert(key, from_cbor(value)?);
+            }
+            Value::Object(object)
+        }
+        ciborium::Value::Tag(_, value) => from_cbor(*value)?,
+        _ => return Err("Unsupported value in CBOR file".into()),
+    })
+}
+
+/// Self-describing tree of a JSON value, since bincode itself carries no field names
+/// or types. It is read with [`NodeSeed`].
+#[derive(Serialize)]
+enum Node {
+    Null,
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    Text(String),
+    Bytes(Vec<u8>),
+    List(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl From<serde_json::Value> for Node {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+        match value {
+            Value::Null => Node::Null,
+            Value::Bool(b) => Node::Bool(b),
+            Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+                (Some(u), _) => Node::Unsigned(u),
+                (None, Some(i)) => Node::Signed(i),
+                _ => Node::Float(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => Node::Text(s),
+            Value::Array(values) => match as_bytes(&values) {
+                Some(bytes) => Node::Bytes(bytes),
+                None => Node::List(values.into_iter().map(Node::from).collect()),
+            },
+            Value::Object(object) => Node::Map(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (key, Node::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TryFrom<Node> for serde_json::Value {
+    type Error = Box<dyn std::error::Error>;
+
+    fn try_from(node: Node) -> Result<Self, Self::Error> {
+        use serde_json::Value;
+        Ok(match node {
+            Node::Null => Value::Null,
+            Node::Bool(b) => Value::Bool(b),
+            Node::Unsigned(u) => Value::from(u),
+            Node::Signed(i) => Value::from(i),
+            Node::Float(f) => Value::Number(
+                serde_json::Number::from_f64(f).ok_or("Invalid number in bincode file")?,
+            ),
+            Node::Text(s) => Value::String(s),
+            Node::Bytes(bytes) => bytes.into_iter().map(Value::from).collect(),
+            Node::List(nodes) => nodes
+                .into_iter()
+                .map(Value::try_from)
+                .collect::<Result<_, _>>()?,
+            Node::Map(entries) => {
+                let mut object = serde_json::Map::new();
+                for (key, node) in entries {
+                    object.insert(key, Value::try_from(node)?);
+                }
+                Value::Object(object)
+            }
+        })
+    }
+}
+
+/// Names of the variants of [`Node`], in the order bincode numbers them.
+const NODE_VARIANTS: &[&str] = &[
+    "Null", "Bool", "Unsigned", "Signed", "Float", "Text", "Bytes", "List", "Map",
+];
+
+/// Reads a [`Node`] inside the given number of lists and maps, refusing to nest deeper
+/// than [`MAX_DEPTH`].
+struct NodeSeed(usize);
+
+impl<'de> DeserializeSeed<'de> for NodeSeed {
+    type Value = Node;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Node, D::Error> {
+        deserializer.deserialize_enum("Node", NODE_VARIANTS, self)
+    }
+}
+
+impl<'de> Visitor<'de> for NodeSeed {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a bincode node")
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Node, A::Error> {
+        let (variant, value): (u32, _) = data.variant()?;
+        if matches!(variant, 7 | 8) && self.0 >= MAX_DEPTH {
+            return Err(de::Error::custom(format!(
+                "bincode file is nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        match variant {
+            0 => value.unit_variant().map(|()| Node::Null),
+            1 => value.newtype_variant().map(Node::Bool),
+            2 => value.newtype_variant().map(Node::Unsigned),
+            3 => value.newtype_variant().map(Node::Signed),
+            4 => value.newtype_variant().map(Node::Float),
+            5 => value.newtype_variant().map(Node::Text),
+            6 => value.newtype_variant().map(Node::Bytes),
+            7 => value
+                .newtype_variant_seed(ListSeed(self.0 + 1))
+                .map(Node::List),
+            8 => value
+                .newtype_variant_seed(MapSeed(self.0 + 1))
+                .map(Node::Map),
+            _ => Err(de::Error::invalid_value(
+                Unexpected::Unsigned(variant.into()),
+                &self,
+            )),
+        }
+    }
+}
+
+/// Reads the nodes of a [`Node::List`] at the given depth.
+struct ListSeed(usize);
+
+impl<'de> DeserializeSeed<'de> for ListSeed {
+    type Value = Vec<Node>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<Node>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ListSeed {
+    type Value = Vec<Node>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of bincode nodes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Node>, A::Error> {
+        let mut nodes = Vec::new();
+        while let Some(node) = seq.next_element_seed(NodeSeed(self.0))? {
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+}
+
+/// Reads the entries of a [`Node::Map`] at the given depth.
+struct MapSeed(usize);
+
+impl<'de> DeserializeSeed<'de> for MapSeed {
+    type Value = Vec<(String, Node)>;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Vec<(String, Node)>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for MapSeed {
+    type Value = Vec<(String, Node)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of bincode map entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<(String, Node)>, A::Error> {
+        let mut entries = Vec::new();
+        while let Some(entry) = seq.next_element_seed(EntrySeed(self.0))? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+/// Reads one key and node of a [`Node::Map`] at the given depth.
+struct EntrySeed(usize);
+
+impl<'de> DeserializeSeed<'de> for EntrySeed {
+    type Value = (String, Node);
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<(String, Node), D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de> Visitor<'de> for EntrySeed {
+    type Value = (String, Node);
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a bincode map entry")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(String, Node), A::Error> {
+        let key = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let node = seq
+            .next_element_seed(NodeSeed(self.0))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        Ok((key, node))
+    }
+}
@@ -0,0 +1,166 @@
+//! JSON Canonicalization Scheme, see [RFC 8785].
+//!
+//! Two JSON documents that only differ in whitespace, key order or how a number or
+//! string is written mean the same, but their bytes and thus their signatures differ.
+//! With [`Canonicalization::Jcs`] in their envelope options, `sign` and `verify` take
+//! the message as JSON and sign and check its canonical form instead, with keys sorted
+//! by their UTF-16 code units, no whitespace, and strings and numbers written the way
+//! ECMAScript's `JSON.stringify` writes them.
+//!
+//! [RFC 8785]: https://www.rfc-editor.org/rfc/rfc8785
+
+use serde_json::Value;
+use std::str::FromStr;
+
+/// How a message is canonicalized before it is signed or verified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Canonicalization {
+    /// The message is signed as given.
+    #[default]
+    None,
+    /// The message is a JSON document, signed in its RFC 8785 canonical form.
+    Jcs,
+}
+
+impl FromStr for Canonicalization {
+    type Err = String;
+
+    fn from_str(canonicalization: &str) -> Result<Self, Self::Err> {
+        match canonicalization {
+            "none" => Ok(Canonicalization::None),
+            "jcs" => Ok(Canonicalization::Jcs),
+            _ => Err(format!(
+                "Unknown canonicalization {}, expected none or jcs",
+                canonicalization
+            )),
+        }
+    }
+}
+
+/// Returns the bytes of a message to sign or verify, canonicalized as given.
+///
+/// # Errors
+/// Returns an error if the message has to be canonicalized but is not valid JSON.
+pub fn canonicalize_as(
+    message: &str,
+    canonicalization: Canonicalization,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match canonicalization {
+        Canonicalization::None => Ok(message.as_bytes().to_vec()),
+        Canonicalization::Jcs => {
+            let value: Value = serde_json::from_str(message)
+                .map_err(|err| format!("Message is not a JSON document: {}", err))?;
+            encode(&value)
+        }
+    }
+}
+
+/// Returns the canonical form of a JSON value.
+///
+/// # Errors
+/// Returns an error if the value holds a number that is not finite.
+pub fn encode(value: &Value) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut out = String::new();
+    write_value(value, &mut out)?;
+    Ok(out.into_bytes())
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&number(n.as_f64().ok_or("Invalid JSON number")?)?),
+        Value::String(s) => write_string(s, out),
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(value, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            entries.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes a number the way ECMAScript's `Number.prototype.toString` does.
+fn number(x: f64) -> Result<String, Box<dyn std::error::Error>> {
+    if !x.is_finite() {
+        return Err(format!("JSON number {} is not finite", x).into());
+    }
+    if x == 0.0 {
+        return Ok("0".to_string());
+    }
+
+    // Shortest digits that round-trip, and the position of the decimal point after
+    // the first digit
+    let scientific = format!("{:e}", x.abs());
+    let (mantissa, exponent) = scientific
+        .split_once('e')
+        .ok_or("Invalid scientific notation")?;
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>()? + 1;
+
+    let mut s = if x < 0.0 {
+        "-".to_string()
+    } else {
+        String::new()
+    };
+    if k <= n && n <= 21 {
+        s.push_str(&digits);
+        s.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        s.push_str(&digits[..n as usize]);
+        s.push('.');
+        s.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        s.push_str("0.");
+        s.push_str(&"0".repeat(-n as usize));
+        s.push_str(&digits);
+    } else {
+        s.push_str(&digits[..1]);
+        if k > 1 {
+            s.push('.');
+            s.push_str(&digits[1..]);
+        }
+        s.push('e');
+        s.push(if n - 1 < 0 { '-' } else { '+' });
+        s.push_str(&(n - 1).abs().to_string());
+    }
+    Ok(s)
+}
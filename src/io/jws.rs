@@ -0,0 +1,140 @@
+//! Compact JSON Web Signatures, see [RFC 7515].
+//!
+//! JOSE only registers an algorithm for the Ed25519 ciphersuite, `EdDSA` from
+//! [RFC 8037], so only Ed25519 keys can sign a JWS. The protected header carries the
+//! group public key as an `OKP` JWK, which lets a service verify the token without
+//! being configured with the key first, and is checked against the key on verify.
+//!
+//! [RFC 7515]: https://www.rfc-editor.org/rfc/rfc7515
+//! [RFC 8037]: https://www.rfc-editor.org/rfc/rfc8037
+
+use crate::backend::Ciphersuite;
+use crate::io::envelope::Opened;
+use crate::InvalidSignature;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL;
+use base64::Engine;
+use serde_json::json;
+
+/// Returns whether `bytes` look like a compact JWS, three base64url parts joined by dots.
+pub fn is_jws(bytes: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    text.split('.').count() == 3
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Returns the JWS algorithm of a ciphersuite.
+fn algorithm(ciphersuite: Ciphersuite) -> Result<&'static str, Box<dyn std::error::Error>> {
+    match ciphersuite {
+        Ciphersuite::Ed25519 => Ok("EdDSA"),
+        _ => Err(format!(
+            "JWS has no algorithm for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into()),
+    }
+}
+
+/// Returns the protected header naming the algorithm and the group key.
+fn header(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let header = json!({
+        "alg": algorithm(ciphersuite)?,
+        "jwk": {
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "x": BASE64URL.encode(group_key),
+        },
+    });
+    Ok(BASE64URL.encode(serde_json::to_vec(&header)?))
+}
+
+/// Returns the JWS signing input, the encoded header and payload joined by a dot.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no JWS algorithm.
+pub fn signing_input(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(format!(
+        "{}.{}",
+        header(ciphersuite, group_key)?,
+        BASE64URL.encode(message)
+    )
+    .into_bytes())
+}
+
+/// Returns the compact JWS of a message and its signature over [`signing_input`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no JWS algorithm.
+pub fn encode(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(format!(
+        "{}.{}\n",
+        String::from_utf8(signing_input(ciphersuite, group_key, message)?)?,
+        BASE64URL.encode(signature)
+    ))
+}
+
+/// Takes the signature out of a compact JWS whose payload is `message`.
+///
+/// # Errors
+/// Returns an error if the JWS is malformed, uses another algorithm than the
+/// ciphersuite, has critical header parameters, or carries another group key or
+/// message.
+pub fn open(
+    text: &str,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let parts: Vec<&str> = text.trim().split('.').collect();
+    let [header, payload, signature] = parts[..] else {
+        return Err("A compact JWS has exactly three parts".into());
+    };
+    let decode = |part: &str, name: &str| {
+        BASE64URL
+            .decode(part)
+            .map_err(|err| format!("Invalid JWS {}: {}", name, err))
+    };
+
+    // Step 1: Check the protected header
+    let protected: serde_json::Value = serde_json::from_slice(&decode(header, "header")?)?;
+    let alg = protected.get("alg").and_then(|alg| alg.as_str());
+    if alg != Some(algorithm(ciphersuite)?) {
+        return Err(format!("Unexpected JWS algorithm {:?}", alg).into());
+    }
+    if protected.get("crit").is_some() {
+        return Err("Unsupported critical JWS header parameters".into());
+    }
+    if let Some(jwk) = protected.get("jwk") {
+        let x = jwk.get("x").and_then(|x| x.as_str()).unwrap_or_default();
+        if jwk.get("crv").and_then(|crv| crv.as_str()) != Some("Ed25519")
+            || decode(x, "key")? != group_key
+        {
+            return Err(InvalidSignature("JWS was signed with another group key".into()).into());
+        }
+    }
+
+    // Step 2: Check the payload and decode the signature
+    if decode(payload, "payload")? != message {
+        return Err(InvalidSignature("The JWS carries a different message".into()).into());
+    }
+    Ok(Opened {
+        signing_input: format!("{}.{}", header, payload).into_bytes(),
+        signature: decode(signature, "signature")?
+            .try_into()
+            .map_err(|_| "Invalid length for threshold signature")?,
+    })
+}
@@ -0,0 +1,15 @@
+//! Encoding of the files the commands read and write.
+
+pub mod cose;
+pub mod cosign;
+pub mod dsse;
+pub mod envelope;
+pub mod files;
+pub mod format;
+pub mod jcs;
+pub mod jws;
+pub mod openpgp;
+pub mod output;
+pub mod pem;
+pub mod signature;
+pub mod sshsig;
@@ -0,0 +1,469 @@
+//! OpenPGP detached signatures and group keys, see [RFC 4880] and [RFC 9580].
+//!
+//! Ed25519 group keys are written as version 4 `EdDSALegacy` keys, the form every
+//! OpenPGP implementation reads. A detached signature hashes the message together
+//! with its creation time and issuer with SHA-512, and the group signs that digest, so
+//! `gpg --verify` accepts the armored `.asc` file once the group key is imported.
+//!
+//! OpenPGP implementations only import keys whose user ID is certified by the key
+//! itself, so the group key is exported by threshold-signing a certification of a user
+//! ID with [`seal_key`], rather than from the key file alone. The key's creation time
+//! is fixed at zero, so its fingerprint only depends on the group key and every
+//! certification and signature names the same key.
+//!
+//! [RFC 4880]: https://www.rfc-editor.org/rfc/rfc4880
+//! [RFC 9580]: https://www.rfc-editor.org/rfc/rfc9580
+
+use crate::backend::Ciphersuite;
+use crate::io::envelope::Opened;
+use crate::InvalidSignature;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha1::Digest as _;
+use sha1::Sha1;
+use sha2::Digest as _;
+use sha2::Sha512;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Armor label of a detached signature.
+pub const SIGNATURE_LABEL: &str = "PGP SIGNATURE";
+
+/// Armor label of a public key.
+pub const PUBLIC_KEY_LABEL: &str = "PGP PUBLIC KEY BLOCK";
+
+const VERSION: u8 = 4;
+const EDDSA_LEGACY: u8 = 22;
+const SHA512: u8 = 10;
+const ED25519_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+const KEY_CREATED: u32 = 0;
+
+const BINARY_DOCUMENT: u8 = 0x00;
+const POSITIVE_CERTIFICATION: u8 = 0x13;
+
+const SIGNATURE_TAG: u8 = 2;
+const PUBLIC_KEY_TAG: u8 = 6;
+const USER_ID_TAG: u8 = 13;
+
+const CREATION_TIME: u8 = 2;
+const ISSUER: u8 = 16;
+const KEY_FLAGS: u8 = 27;
+const ISSUER_FINGERPRINT: u8 = 33;
+
+/// Key flags of the group key: it certifies user IDs and signs data.
+const CERTIFY_AND_SIGN: u8 = 0x03;
+
+/// Returns whether `bytes` hold an armored detached signature.
+pub fn is_signature(bytes: &[u8]) -> bool {
+    starts_with_armor(bytes, SIGNATURE_LABEL)
+}
+
+/// Returns whether `bytes` hold an armored public key.
+pub fn is_key(bytes: &[u8]) -> bool {
+    starts_with_armor(bytes, PUBLIC_KEY_LABEL)
+}
+
+/// Signs `message` with `sign` and returns the armored detached signature.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no OpenPGP algorithm or signing fails.
+pub fn seal_signature<F>(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+    sign: F,
+) -> Result<String, Box<dyn std::error::Error>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>,
+{
+    check_ciphersuite(ciphersuite)?;
+    let packet = signature_packet(group_key, BINARY_DOCUMENT, message, sign)?;
+    Ok(armor(SIGNATURE_LABEL, &packet))
+}
+
+/// Certifies `user_id` for the group key with `sign` and returns the armored public key.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no OpenPGP algorithm or signing fails.
+pub fn seal_key<F>(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    user_id: &[u8],
+    sign: F,
+) -> Result<String, Box<dyn std::error::Error>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>,
+{
+    check_ciphersuite(ciphersuite)?;
+    let mut packets = packet(PUBLIC_KEY_TAG, &key_body(group_key));
+    packets.extend(packet(USER_ID_TAG, user_id));
+    packets.extend(signature_packet(
+        group_key,
+        POSITIVE_CERTIFICATION,
+        &certified_data(group_key, user_id),
+        sign,
+    )?);
+    Ok(armor(PUBLIC_KEY_LABEL, &packets))
+}
+
+/// Takes the signature out of an armored detached signature of `message`.
+///
+/// # Errors
+/// Returns an error if the signature is malformed, was made by another key or is not
+/// for `message`.
+pub fn open_signature(
+    text: &str,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    message: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    let data = dearmor(SIGNATURE_LABEL, text)?;
+    let mut reader = Reader(&data);
+    let body = reader.packet(SIGNATURE_TAG)?;
+    open_signature_packet(body, group_key, BINARY_DOCUMENT, message)
+}
+
+/// Takes the certification out of an armored group key whose user ID is `user_id`.
+///
+/// # Errors
+/// Returns an error if the key is malformed, is not the group key or certifies
+/// another user ID.
+pub fn open_key(
+    text: &str,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    user_id: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    let data = dearmor(PUBLIC_KEY_LABEL, text)?;
+    let mut reader = Reader(&data);
+    if reader.packet(PUBLIC_KEY_TAG)? != key_body(group_key) {
+        return Err(InvalidSignature("OpenPGP key is not the group key".into()).into());
+    }
+    if reader.packet(USER_ID_TAG)? != user_id {
+        return Err("OpenPGP key certifies a different user ID".into());
+    }
+    let body = reader.packet(SIGNATURE_TAG)?;
+    open_signature_packet(
+        body,
+        group_key,
+        POSITIVE_CERTIFICATION,
+        &certified_data(group_key, user_id),
+    )
+}
+
+/// Returns the creation time of an armored detached signature, in seconds since the
+/// epoch.
+///
+/// # Errors
+/// Returns an error if the signature is malformed or has no creation time.
+pub fn signature_created(text: &str) -> Result<u32, Box<dyn std::error::Error>> {
+    let data = dearmor(SIGNATURE_LABEL, text)?;
+    let mut body = Reader(Reader(&data).packet(SIGNATURE_TAG)?);
+    body.take(4)?;
+    let hashed_len = u16::from_be_bytes(body.take(2)?.try_into()?) as usize;
+    let mut subpackets = Reader(body.take(hashed_len)?);
+    while !subpackets.0.is_empty() {
+        let subpacket = subpackets.subpacket()?;
+        if subpacket.len() == 5 && subpacket[0] == CREATION_TIME {
+            return Ok(u32::from_be_bytes(subpacket[1..].try_into()?));
+        }
+    }
+    Err("OpenPGP signature has no creation time".into())
+}
+
+fn check_ciphersuite(ciphersuite: Ciphersuite) -> Result<(), Box<dyn std::error::Error>> {
+    if ciphersuite != Ciphersuite::Ed25519 {
+        return Err(format!(
+            "OpenPGP has no algorithm for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Returns the body of the public key packet of the group key.
+fn key_body(group_key: &[u8; 32]) -> Vec<u8> {
+    let mut body = vec![VERSION];
+    body.extend(KEY_CREATED.to_be_bytes());
+    body.push(EDDSA_LEGACY);
+    body.push(ED25519_OID.len() as u8);
+    body.extend(ED25519_OID);
+    // Legacy EdDSA points carry the 0x40 prefix of native point encodings.
+    body.extend(mpi(&[&[0x40][..], &group_key[..]].concat()));
+    body
+}
+
+/// Returns the version 4 fingerprint of the group key.
+pub fn fingerprint(group_key: &[u8; 32]) -> [u8; 20] {
+    Sha1::digest(key_hash_prefix(group_key)).into()
+}
+
+/// Returns the key packet as it is hashed into fingerprints and certifications.
+fn key_hash_prefix(group_key: &[u8; 32]) -> Vec<u8> {
+    let body = key_body(group_key);
+    let mut data = vec![0x99];
+    data.extend((body.len() as u16).to_be_bytes());
+    data.extend(body);
+    data
+}
+
+/// Returns the data a certification of `user_id` is made over.
+fn certified_data(group_key: &[u8; 32], user_id: &[u8]) -> Vec<u8> {
+    let mut data = key_hash_prefix(group_key);
+    data.push(0xb4);
+    data.extend((user_id.len() as u32).to_be_bytes());
+    data.extend(user_id);
+    data
+}
+
+/// Returns the hashed part of a signature packet, up to its hashed subpackets.
+fn hashed_part(group_key: &[u8; 32], signature_type: u8, created: u32) -> Vec<u8> {
+    let mut subpackets = vec![5, CREATION_TIME];
+    subpackets.extend(created.to_be_bytes());
+    subpackets.extend([22, ISSUER_FINGERPRINT, VERSION]);
+    subpackets.extend(fingerprint(group_key));
+    if signature_type == POSITIVE_CERTIFICATION {
+        subpackets.extend([2, KEY_FLAGS, CERTIFY_AND_SIGN]);
+    }
+
+    let mut hashed = vec![VERSION, signature_type, EDDSA_LEGACY, SHA512];
+    hashed.extend((subpackets.len() as u16).to_be_bytes());
+    hashed.extend(subpackets);
+    hashed
+}
+
+/// Returns the SHA-512 digest a signature packet signs.
+fn digest(data: &[u8], hashed: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha512::new();
+    hasher.update(data);
+    hasher.update(hashed);
+    hasher.update([VERSION, 0xff]);
+    hasher.update((hashed.len() as u32).to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Signs `data` and returns the signature packet.
+fn signature_packet<F>(
+    group_key: &[u8; 32],
+    signature_type: u8,
+    data: &[u8],
+    sign: F,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>>
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>,
+{
+    let created = u32::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())?;
+    let hashed = hashed_part(group_key, signature_type, created);
+    let digest = digest(data, &hashed);
+    let signature = sign(&digest)?;
+    if signature.len() != 64 {
+        return Err("Invalid length for threshold signature".into());
+    }
+
+    let mut body = hashed;
+    body.extend(10u16.to_be_bytes());
+    body.extend([9, ISSUER]);
+    body.extend(&fingerprint(group_key)[12..]);
+    body.extend(&digest[..2]);
+    body.extend(mpi(&signature[..32]));
+    body.extend(mpi(&signature[32..]));
+    Ok(packet(SIGNATURE_TAG, &body))
+}
+
+/// Checks a signature packet over `data` and takes out its digest and signature.
+fn open_signature_packet(
+    body: &[u8],
+    group_key: &[u8; 32],
+    signature_type: u8,
+    data: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    let mut reader = Reader(body);
+
+    // Step 1: Check the algorithms and the issuer
+    if reader.take(4)? != [VERSION, signature_type, EDDSA_LEGACY, SHA512] {
+        return Err(
+            "Unsupported OpenPGP signature, expected a version 4 EdDSA signature with SHA-512"
+                .into(),
+        );
+    }
+    let hashed_len = u16::from_be_bytes(reader.take(2)?.try_into()?) as usize;
+    let mut subpackets = Reader(reader.take(hashed_len)?);
+    while !subpackets.0.is_empty() {
+        let subpacket = subpackets.subpacket()?;
+        if subpacket.first() == Some(&ISSUER_FINGERPRINT)
+            && subpacket.get(2..) != Some(&fingerprint(group_key)[..])
+        {
+            return Err(
+                InvalidSignature("OpenPGP signature was made by another key".into()).into(),
+            );
+        }
+    }
+    let hashed = &body[..6 + hashed_len];
+    let unhashed_len = u16::from_be_bytes(reader.take(2)?.try_into()?) as usize;
+    reader.take(unhashed_len)?;
+
+    // Step 2: Check the digest and take out the signature
+    let digest = digest(data, hashed);
+    if reader.take(2)? != &digest[..2] {
+        return Err(InvalidSignature("OpenPGP signature does not match the message".into()).into());
+    }
+    let mut signature = [0; 64];
+    signature[..32].copy_from_slice(&reader.mpi(32)?);
+    signature[32..].copy_from_slice(&reader.mpi(32)?);
+    Ok(Opened {
+        signing_input: digest,
+        signature,
+    })
+}
+
+/// Encodes a big-endian integer as a multiprecision integer.
+fn mpi(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    let bits = match bytes.first() {
+        Some(first) => (bytes.len() * 8 - first.leading_zeros() as usize) as u16,
+        None => 0,
+    };
+    let mut encoded = bits.to_be_bytes().to_vec();
+    encoded.extend(bytes);
+    encoded
+}
+
+/// Returns a packet with a new format header.
+fn packet(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0xc0 | tag];
+    match body.len() {
+        len if len < 192 => packet.push(len as u8),
+        len if len < 8384 => {
+            let len = len - 192;
+            packet.extend([(len >> 8) as u8 + 192, len as u8]);
+        }
+        len => {
+            packet.push(0xff);
+            packet.extend((len as u32).to_be_bytes());
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn starts_with_armor(bytes: &[u8], label: &str) -> bool {
+    String::from_utf8_lossy(bytes)
+        .trim_start()
+        .starts_with(&format!("-----BEGIN {}-----", label))
+}
+
+/// Returns the ASCII armor of `data`, with its CRC-24 checksum line.
+fn armor(label: &str, data: &[u8]) -> String {
+    let mut text = format!("-----BEGIN {}-----\n\n", label);
+    for line in BASE64.encode(data).as_bytes().chunks(64) {
+        text.push_str(&String::from_utf8_lossy(line));
+        text.push('\n');
+    }
+    text.push_str(&format!(
+        "={}\n-----END {}-----\n",
+        BASE64.encode(&crc24(data).to_be_bytes()[1..]),
+        label
+    ));
+    text
+}
+
+/// Removes the ASCII armor with the given label, checking its checksum if present.
+fn dearmor(label: &str, text: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next() != Some(format!("-----BEGIN {}-----", label).as_str()) {
+        return Err(format!("Expected an armored {}", label).into());
+    }
+    // Armor headers end at the first blank line.
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+    }
+    let mut body = String::new();
+    let mut checksum = None;
+    for line in lines {
+        if line == format!("-----END {}-----", label) {
+            let data = BASE64
+                .decode(&body)
+                .map_err(|err| format!("Invalid OpenPGP armor: {}", err))?;
+            if let Some(checksum) = checksum {
+                if BASE64.decode(checksum)? != crc24(&data).to_be_bytes()[1..] {
+                    return Err("OpenPGP armor checksum does not match".into());
+                }
+            }
+            return Ok(data);
+        }
+        match line.strip_prefix('=') {
+            Some(line) if line.len() == 4 => checksum = Some(line.to_string()),
+            _ => body.push_str(line),
+        }
+    }
+    Err(format!("OpenPGP armor is missing -----END {}-----", label).into())
+}
+
+/// Returns the CRC-24 checksum of the ASCII armor.
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xb704ce;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+/// Reads packets and their fields.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        if self.0.len() < len {
+            return Err("Truncated OpenPGP data".into());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    /// Reads a packet with a new format header and returns its body.
+    fn packet(&mut self, tag: u8) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        if self.take(1)?[0] != 0xc0 | tag {
+            return Err(format!("Expected an OpenPGP packet with tag {}", tag).into());
+        }
+        let len = self.length()?;
+        self.take(len)
+    }
+
+    /// Reads a subpacket and returns its type and data.
+    fn subpacket(&mut self) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        let len = self.length()?;
+        self.take(len)
+    }
+
+    fn length(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        Ok(match self.take(1)?[0] {
+            first @ 0..=191 => first as usize,
+            first @ 192..=254 => ((first as usize - 192) << 8) + self.take(1)?[0] as usize + 192,
+            _ => u32::from_be_bytes(self.take(4)?.try_into()?) as usize,
+        })
+    }
+
+    /// Reads a multiprecision integer, left-padded to `len` bytes.
+    fn mpi(&mut self, len: usize) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let bits = u16::from_be_bytes(self.take(2)?.try_into()?) as usize;
+        let bytes = self.take((bits + 7) / 8)?;
+        if bytes.len() > len {
+            return Err("OpenPGP integer is too long".into());
+        }
+        let mut padded = vec![0; len - bytes.len()];
+        padded.extend(bytes);
+        Ok(padded)
+    }
+}
@@ -0,0 +1,90 @@
+//! Human-readable or JSON output of command results.
+//!
+//! Every result a command reports goes through [`report`]. In the default `text`
+//! output mode it is printed as a sentence. In the `json` output mode chosen with
+//! [`set_output_mode`], it is printed instead as a single line holding a JSON object
+//! with the kind of result as `event`, the sentence as `message` and the result's fields
+//! (file paths, key fingerprints, signers, verification status), so scripts and other
+//! services can read stdout line by line.
+//!
+//! Until an output mode is set, results are not printed at all, so applications using
+//! frost-cli as a library keep their stdout to themselves. The CLI prints text unless
+//! told otherwise. Diagnostics never go through here but are `tracing` events, which
+//! the CLI writes to stderr.
+
+use serde_json::{Map, Value};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static OUTPUT_MODE: AtomicU8 = AtomicU8::new(OutputMode::None as u8);
+
+/// How command results are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Results are not printed.
+    None,
+    /// One sentence per result.
+    Text,
+    /// One JSON object per line and result.
+    Json,
+}
+
+impl FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "text" => Ok(OutputMode::Text),
+            "json" => Ok(OutputMode::Json),
+            "none" => Ok(OutputMode::None),
+            _ => Err(format!(
+                "Unknown output mode {}, expected text, json or none",
+                mode
+            )),
+        }
+    }
+}
+
+/// Sets how every result is printed from now on.
+pub fn set_output_mode(mode: OutputMode) {
+    OUTPUT_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+/// Returns how results are printed, see [`set_output_mode`].
+pub fn output_mode() -> OutputMode {
+    match OUTPUT_MODE.load(Ordering::Relaxed) {
+        x if x == OutputMode::Text as u8 => OutputMode::Text,
+        x if x == OutputMode::Json as u8 => OutputMode::Json,
+        _ => OutputMode::None,
+    }
+}
+
+/// Prints a result in the output mode.
+///
+/// # Arguments
+/// - `event`: Kind of result, such as `signature_saved`.
+/// - `message`: The result as a sentence.
+/// - `fields`: The result's fields as a JSON object, merged into the JSON line.
+pub fn report(event: &str, message: &str, fields: Value) {
+    match output_mode() {
+        OutputMode::None => {}
+        OutputMode::Text => println!("{}", message),
+        OutputMode::Json => println!("{}", json_line(event, message, fields)),
+    }
+}
+
+/// Returns the JSON object a result is printed as in the json output mode, see
+/// [`report`].
+pub fn json_line(event: &str, message: &str, fields: Value) -> Value {
+    let mut object = Map::new();
+    object.insert("event".to_string(), event.into());
+    object.insert("message".to_string(), message.into());
+    match fields {
+        Value::Object(fields) => object.extend(fields),
+        Value::Null => {}
+        value => {
+            object.insert("value".to_string(), value);
+        }
+    }
+    Value::Object(object)
+}
@@ -0,0 +1,84 @@
+//! PEM armor for group public keys and signatures.
+//!
+//! Config management and ticketing systems tend to mangle binary files and JSON, but
+//! pass a block of base64 between `-----BEGIN ...-----` and `-----END ...-----` lines
+//! through untouched. A group key armored by [`encode_group_key`] can be given anywhere
+//! a key file is expected to check signatures, and signatures are armored with the `pem`
+//! signature format of [`crate::io::signature`].
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// Label of an armored group public key.
+pub const GROUP_KEY_LABEL: &str = "FROST GROUP KEY";
+
+/// Label of an armored threshold signature.
+pub const SIGNATURE_LABEL: &str = "FROST SIGNATURE";
+
+/// Length of the base64 lines between the armor lines.
+const LINE_LEN: usize = 64;
+
+/// Returns whether `bytes` start with a PEM armor line.
+pub fn is_pem(bytes: &[u8]) -> bool {
+    String::from_utf8_lossy(bytes)
+        .trim_start()
+        .starts_with("-----BEGIN ")
+}
+
+/// Armors `bytes` under `label`.
+pub fn encode(label: &str, bytes: &[u8]) -> String {
+    let body = BASE64.encode(bytes);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(LINE_LEN) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Removes the armor from a PEM block with the given label.
+///
+/// # Errors
+/// Returns an error if the text is not a single PEM block with `label` or its body is
+/// not valid base64.
+pub fn decode(label: &str, text: &str) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let begin = format!("-----BEGIN {}-----", label);
+    let end = format!("-----END {}-----", label);
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if lines.next() != Some(begin.as_str()) {
+        return Err(format!("Expected a PEM block starting with {}", begin).into());
+    }
+    let mut body = String::new();
+    loop {
+        match lines.next() {
+            Some(line) if line == end => break,
+            Some(line) if line.starts_with("-----") => {
+                return Err(format!("Unexpected PEM line: {}", line).into())
+            }
+            Some(line) => body.push_str(line),
+            None => return Err(format!("PEM block is missing {}", end).into()),
+        }
+    }
+    if lines.next().is_some() {
+        return Err("Unexpected text after the PEM block".into());
+    }
+    Ok(BASE64
+        .decode(body)
+        .map_err(|err| format!("Invalid PEM body: {}", err))?)
+}
+
+/// Armors a group public key.
+pub fn encode_group_key(group_key: &[u8; 32]) -> String {
+    encode(GROUP_KEY_LABEL, group_key)
+}
+
+/// Removes the armor from a group public key.
+///
+/// # Errors
+/// Returns an error if the text is not an armored 32-byte group key.
+pub fn decode_group_key(text: &str) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    decode(GROUP_KEY_LABEL, text)?
+        .try_into()
+        .map_err(|_| "Group key must be 32 bytes".into())
+}
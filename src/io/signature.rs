@@ -0,0 +1,207 @@
+//! Signature file encodings for use outside of frost-cli.
+//!
+//! By default a threshold signature is saved as a versioned, checksummed signature
+//! file in the output format of [`crate::io::format`]. Other systems rarely read that,
+//! so a signature can also be saved as its bare 64 bytes, as a single line of hex or
+//! base64 to paste elsewhere, or armored as PEM, see [`crate::io::pem`]. These carry no
+//! version, checksum, [`SignatureMetadata`] or [`SignedFile`].
+//!
+//! [`detect`] tells the encodings apart when a signature is read, so verifying works
+//! without knowing how the signature was saved.
+
+use crate::artifact::SignedFile;
+use crate::io::format::{self, Format};
+use crate::io::pem::{self, SIGNATURE_LABEL};
+use crate::{add_checksum, parse_versioned, SignatureFile, SignatureMetadata, FORMAT_VERSION};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::str::FromStr;
+
+/// Length of a threshold signature in bytes.
+pub const SIGNATURE_LEN: usize = 64;
+
+/// Encoding of a signature file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// The 64 signature bytes.
+    Raw,
+    /// The signature as a line of lowercase hex.
+    Hex,
+    /// The signature as a line of standard base64.
+    Base64,
+    /// The signature armored as `FROST SIGNATURE` PEM.
+    Pem,
+    /// A versioned signature file, see [`SignatureFile`].
+    #[default]
+    Json,
+}
+
+impl FromStr for SignatureFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "raw" => Ok(SignatureFormat::Raw),
+            "hex" => Ok(SignatureFormat::Hex),
+            "base64" => Ok(SignatureFormat::Base64),
+            "pem" => Ok(SignatureFormat::Pem),
+            "json" => Ok(SignatureFormat::Json),
+            _ => Err(format!(
+                "Unknown signature format {}, expected raw, hex, base64, pem or json",
+                format
+            )),
+        }
+    }
+}
+
+/// Returns the format of a signature file from its contents.
+///
+/// No signature file in one of the formats of [`crate::io::format`] is exactly 64 bytes
+/// long, so a file of that length is always taken to be a raw signature.
+pub fn detect(bytes: &[u8]) -> SignatureFormat {
+    if bytes.len() == SIGNATURE_LEN {
+        return SignatureFormat::Raw;
+    }
+    if format::detect(bytes) != Format::Json {
+        return SignatureFormat::Json;
+    }
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    if text.starts_with('{') || text.starts_with('[') {
+        SignatureFormat::Json
+    } else if pem::is_pem(text.as_bytes()) {
+        SignatureFormat::Pem
+    } else if text.len() == 2 * SIGNATURE_LEN && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        SignatureFormat::Hex
+    } else {
+        SignatureFormat::Base64
+    }
+}
+
+/// Encodes a signature in the given format, a `json` signature file written in
+/// `file_format`, see [`crate::io::format`].
+///
+/// # Errors
+/// Returns an error if the signature file cannot be serialized.
+pub fn encode(
+    signature: &[u8],
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    encode_with_metadata(signature, None, format, file_format)
+}
+
+/// Encodes a signature and its metadata like [`encode`]. Formats other than `json` drop
+/// the metadata.
+///
+/// # Errors
+/// Returns an error if the signature file cannot be serialized.
+pub fn encode_with_metadata(
+    signature: &[u8],
+    metadata: Option<&SignatureMetadata>,
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    encode_signature_file(signature, metadata, None, format, file_format)
+}
+
+/// Encodes the signature of a file and its metadata like [`encode`]. Formats other than
+/// `json` drop the metadata and the record of the signed file.
+///
+/// # Errors
+/// Returns an error if the signature file cannot be serialized.
+pub fn encode_file_signature(
+    signature: &[u8],
+    metadata: &SignatureMetadata,
+    file: &SignedFile,
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    encode_signature_file(signature, Some(metadata), Some(file), format, file_format)
+}
+
+fn encode_signature_file(
+    signature: &[u8],
+    metadata: Option<&SignatureMetadata>,
+    file: Option<&SignedFile>,
+    format: SignatureFormat,
+    file_format: Format,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match format {
+        SignatureFormat::Raw => Ok(signature.to_vec()),
+        SignatureFormat::Hex => Ok(format!("{}\n", hex::encode(signature)).into_bytes()),
+        SignatureFormat::Base64 => Ok(format!("{}\n", BASE64.encode(signature)).into_bytes()),
+        SignatureFormat::Pem => Ok(pem::encode(SIGNATURE_LABEL, signature).into_bytes()),
+        SignatureFormat::Json => {
+            let mut value = serde_json::to_value(SignatureFile {
+                version: FORMAT_VERSION,
+                signature: signature.to_vec(),
+                metadata: metadata.cloned(),
+                file: file.cloned(),
+                timestamp_token: None,
+            })?;
+            add_checksum(&mut value)?;
+            format::encode_as(&value, file_format)
+        }
+    }
+}
+
+/// Decodes the signature file at `path` in the given format.
+///
+/// # Errors
+/// Returns an error if the file is not a valid signature file in that format.
+pub fn decode(
+    bytes: &[u8],
+    path: &str,
+    format: SignatureFormat,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let signature = match format {
+        SignatureFormat::Raw => bytes.to_vec(),
+        SignatureFormat::Hex => hex::decode(String::from_utf8_lossy(bytes).trim())
+            .map_err(|err| format!("Invalid hex signature in {}: {}", path, err))?,
+        SignatureFormat::Base64 => BASE64
+            .decode(String::from_utf8_lossy(bytes).trim())
+            .map_err(|err| format!("Invalid base64 signature in {}: {}", path, err))?,
+        SignatureFormat::Pem => pem::decode(SIGNATURE_LABEL, &String::from_utf8_lossy(bytes))
+            .map_err(|err| format!("Invalid PEM signature in {}: {}", path, err))?,
+        SignatureFormat::Json => {
+            let file: SignatureFile =
+                parse_versioned(format::decode(bytes)?, path, "signature", None)?;
+            file.signature
+        }
+    };
+    if signature.len() != SIGNATURE_LEN {
+        return Err("Invalid length for threshold signature".into());
+    }
+    Ok(signature)
+}
+
+/// Returns the metadata of the signature file at `path`, if it has any.
+///
+/// # Errors
+/// Returns an error if a versioned signature file is not valid.
+pub fn decode_metadata(
+    bytes: &[u8],
+    path: &str,
+) -> Result<Option<SignatureMetadata>, Box<dyn std::error::Error>> {
+    if detect(bytes) != SignatureFormat::Json {
+        return Ok(None);
+    }
+    let file: SignatureFile = parse_versioned(format::decode(bytes)?, path, "signature", None)?;
+    Ok(file.metadata)
+}
+
+/// Returns the record of the signed file in the signature file at `path`, if it has one.
+///
+/// # Errors
+/// Returns an error if a versioned signature file is not valid.
+pub fn decode_signed_file(
+    bytes: &[u8],
+    path: &str,
+) -> Result<Option<SignedFile>, Box<dyn std::error::Error>> {
+    if detect(bytes) != SignatureFormat::Json {
+        return Ok(None);
+    }
+    let file: SignatureFile = parse_versioned(format::decode(bytes)?, path, "signature", None)?;
+    Ok(file.file)
+}
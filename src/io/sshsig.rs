@@ -0,0 +1,193 @@
+//! OpenSSH signatures, see `PROTOCOL.sshsig` in the OpenSSH sources.
+//!
+//! `ssh-keygen -Y sign` signs a SHA-512 hash of the message bound to a namespace, such
+//! as `file` or `git`, and saves it as an armored `SSH SIGNATURE` blob next to the
+//! signer's public key. Ed25519 group keys produce the same blob, so the signature can
+//! be checked with `ssh-keygen -Y verify` against an allowed signers line holding the
+//! group key from [`public_key`]. Other ciphersuites have no OpenSSH key type.
+
+use crate::backend::Ciphersuite;
+use crate::io::envelope::Opened;
+use crate::io::pem;
+use crate::InvalidSignature;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha512};
+
+/// Label of an armored SSH signature.
+pub const SSHSIG_LABEL: &str = "SSH SIGNATURE";
+
+/// Namespace signatures are bound to unless another is given, the one
+/// `ssh-keygen -Y sign` uses for files, see [`crate::io::envelope::EnvelopeOptions`].
+pub const DEFAULT_NAMESPACE: &str = "file";
+
+const MAGIC_PREAMBLE: &[u8] = b"SSHSIG";
+const SIG_VERSION: u32 = 1;
+const HASH_ALGORITHM: &str = "sha512";
+const KEY_TYPE: &str = "ssh-ed25519";
+
+/// Returns whether `bytes` hold an armored SSH signature.
+pub fn is_sshsig(bytes: &[u8]) -> bool {
+    String::from_utf8_lossy(bytes)
+        .trim_start()
+        .starts_with(&format!("-----BEGIN {}-----", SSHSIG_LABEL))
+}
+
+/// Returns the group key as an OpenSSH public key line, `ssh-ed25519 <base64>`.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no OpenSSH key type.
+pub fn public_key(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    Ok(format!(
+        "{} {}\n",
+        KEY_TYPE,
+        BASE64.encode(public_key_blob(group_key))
+    ))
+}
+
+/// Returns the data an SSH signature of `message` in `namespace` is made over.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no OpenSSH key type.
+pub fn signing_input(
+    ciphersuite: Ciphersuite,
+    namespace: &str,
+    message: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    let mut data = MAGIC_PREAMBLE.to_vec();
+    put_string(&mut data, namespace.as_bytes());
+    put_string(&mut data, b"");
+    put_string(&mut data, HASH_ALGORITHM.as_bytes());
+    put_string(&mut data, &Sha512::digest(message));
+    Ok(data)
+}
+
+/// Returns the armored SSH signature of a signature over [`signing_input`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no OpenSSH key type.
+pub fn encode(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    namespace: &str,
+    signature: &[u8],
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    let mut signature_blob = Vec::new();
+    put_string(&mut signature_blob, KEY_TYPE.as_bytes());
+    put_string(&mut signature_blob, signature);
+
+    let mut blob = MAGIC_PREAMBLE.to_vec();
+    blob.extend(SIG_VERSION.to_be_bytes());
+    put_string(&mut blob, &public_key_blob(group_key));
+    put_string(&mut blob, namespace.as_bytes());
+    put_string(&mut blob, b"");
+    put_string(&mut blob, HASH_ALGORITHM.as_bytes());
+    put_string(&mut blob, &signature_blob);
+    Ok(pem::encode(SSHSIG_LABEL, &blob))
+}
+
+/// Takes the signature out of an armored SSH signature of `message` in `namespace`.
+///
+/// # Errors
+/// Returns an error if the signature is malformed, was made by another key or in
+/// another namespace.
+pub fn open(
+    text: &str,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    namespace: &str,
+    message: &[u8],
+) -> Result<Opened, Box<dyn std::error::Error>> {
+    check_ciphersuite(ciphersuite)?;
+    let blob = pem::decode(SSHSIG_LABEL, text)?;
+    let mut reader = Reader(&blob);
+
+    // Step 1: Check the header and the signer's key
+    if reader.take(MAGIC_PREAMBLE.len())? != MAGIC_PREAMBLE {
+        return Err("Not an SSH signature".into());
+    }
+    let version = u32::from_be_bytes(reader.take(4)?.try_into()?);
+    if version != SIG_VERSION {
+        return Err(format!("Unsupported SSH signature version {}", version).into());
+    }
+    if reader.string()? != public_key_blob(group_key) {
+        return Err(InvalidSignature("SSH signature was made by another key".into()).into());
+    }
+    let signed_namespace = String::from_utf8_lossy(reader.string()?).into_owned();
+    if signed_namespace != namespace {
+        return Err(format!(
+            "SSH signature is for namespace {}, not {}",
+            signed_namespace, namespace
+        )
+        .into());
+    }
+    reader.string()?;
+    if reader.string()? != HASH_ALGORITHM.as_bytes() {
+        return Err("Unsupported SSH signature hash algorithm".into());
+    }
+
+    // Step 2: Take out the signature
+    let mut signature_blob = Reader(reader.string()?);
+    if signature_blob.string()? != KEY_TYPE.as_bytes() {
+        return Err("Unexpected SSH signature type".into());
+    }
+    let signature = signature_blob
+        .string()?
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature")?;
+
+    Ok(Opened {
+        signing_input: signing_input(ciphersuite, namespace, message)?,
+        signature,
+    })
+}
+
+fn check_ciphersuite(ciphersuite: Ciphersuite) -> Result<(), Box<dyn std::error::Error>> {
+    if ciphersuite != Ciphersuite::Ed25519 {
+        return Err(format!(
+            "OpenSSH has no key type for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Returns the group key in the OpenSSH wire encoding.
+fn public_key_blob(group_key: &[u8; 32]) -> Vec<u8> {
+    let mut blob = Vec::new();
+    put_string(&mut blob, KEY_TYPE.as_bytes());
+    put_string(&mut blob, group_key);
+    blob
+}
+
+/// Appends an SSH `string`, the bytes prefixed with their length.
+fn put_string(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend((bytes.len() as u32).to_be_bytes());
+    buffer.extend(bytes);
+}
+
+/// Reads SSH wire encoded fields.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        if self.0.len() < len {
+            return Err("Truncated SSH signature".into());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn string(&mut self) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        let len = u32::from_be_bytes(self.take(4)?.try_into()?);
+        self.take(len as usize)
+    }
+}
@@ -0,0 +1,583 @@
+//! Key generation by a trusted dealer.
+//!
+//! The dealer runs every participant's side of the DKG in one process and saves all
+//! private shares to one key file, or returns them with [`generate_frost_keys`]. For a
+//! DKG run by the participants on their own machines, see [`crate::dkg`]. With the
+//! `insecure-deterministic` feature, [`generate_keys_from_seed`] derives the keys from
+//! a seed instead, for tests and demos that need the same keys on every run.
+
+use crate::backend::{self, Ciphersuite};
+use crate::dkg;
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::files::{save_participant_share, write_checked_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::types::{
+    point_from_bytes, seeded_rng, FrostKeys, KeygenProgress, KeygenStage, ParticipantShare,
+    SecureRng, FORMAT_VERSION,
+};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use frost_dalek::{DistributedKeyGeneration, Parameters, Participant};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, info_span, warn};
+use zeroize::Zeroizing;
+
+/// Generates a public key and private key shares using FROST.
+///
+/// # Parameters
+/// - `t`: Threshold value, the minimum number of participants required to reconstruct the private key.
+/// - `n`: Total number of participants (key shares).
+///
+/// # Returns
+/// - Saves the keys to `./results/frost_keys.json` in JSON format.
+///
+/// Generates the public key and private key shares.
+pub fn generate_keys(
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    generate_keys_with_ciphersuite(t, n, output_key_file, Ciphersuite::Ristretto255)
+}
+
+/// Generates the public key and private key shares in the given ciphersuite, see
+/// [`generate_keys`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the key
+/// generation fails.
+pub fn generate_keys_with_ciphersuite(
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+    ciphersuite: Ciphersuite,
+) -> Result<(), Box<dyn std::error::Error>> {
+    generate_keys_with_progress(
+        t,
+        n,
+        output_key_file,
+        ciphersuite,
+        &|_| {},
+        &FileOptions::default(),
+    )
+}
+
+/// Generates the public key and private key shares in the given ciphersuite like
+/// [`generate_keys_with_ciphersuite`], calling `progress` each time a participant gets
+/// through a stage of the key generation. With many participants the key generation
+/// takes minutes, and the callback lets a caller show how far along it is. The key file
+/// is written as `options` say.
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the key
+/// generation fails.
+pub fn generate_keys_with_progress(
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+    ciphersuite: Ciphersuite,
+    progress: &dyn Fn(KeygenProgress),
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let frost_keys = Locked::new(generate_frost_keys(t, n, ciphersuite, progress)?)?;
+    save_keys(&frost_keys, output_key_file, options)
+}
+
+/// Generates the public key and private key shares like [`generate_keys_with_progress`],
+/// deriving every secret from `seed` with [`seeded_rng`], so the same seed always gives
+/// the same key file.
+///
+/// **Anyone who knows the seed can sign alone.** This is only meant for tests and
+/// reproducible demos, and needs the `insecure-deterministic` feature.
+///
+/// # Errors
+/// Returns an error if frost-cli was built without the `insecure-deterministic`
+/// feature, the ciphersuite is not included in this build or the key generation fails.
+pub fn generate_keys_from_seed(
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+    ciphersuite: Ciphersuite,
+    seed: &[u8],
+    progress: &dyn Fn(KeygenProgress),
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = seeded_rng(seed)?;
+    let frost_keys = Locked::new(generate_frost_keys_with_rng(
+        t,
+        n,
+        ciphersuite,
+        progress,
+        &mut *rng,
+    )?)?;
+    warn!("Generated keys from a seed, anyone who knows the seed can sign alone");
+    save_keys(&frost_keys, output_key_file, options)
+}
+
+/// Saves generated keys to a key file and reports them.
+fn save_keys(
+    frost_keys: &FrostKeys,
+    output_key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Save the keys to a key file.
+    write_checked_json(output_key_file, frost_keys, options)?;
+
+    report(
+        "keys_generated",
+        &format!(
+            "Generated {} shares with threshold {}. Keys saved.",
+            frost_keys.private_shares.len(),
+            frost_keys.threshold
+        ),
+        json!({
+            "key_file": output_key_file,
+            "group_key_fingerprint": envelope::fingerprint(&frost_keys.group_key),
+            "ciphersuite": frost_keys.ciphersuite,
+            "threshold": frost_keys.threshold,
+            "participants": frost_keys.private_shares.len(),
+        }),
+    );
+    Ok(())
+}
+
+/// Generates the public key and private key shares in the given ciphersuite and
+/// returns them instead of saving them, see [`generate_keys_with_progress`].
+///
+/// Nothing is written to the filesystem. Callers that keep the keys are responsible for
+/// storing the private shares securely.
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the key
+/// generation fails.
+pub fn generate_frost_keys(
+    t: u32,
+    n: u32,
+    ciphersuite: Ciphersuite,
+    progress: &dyn Fn(KeygenProgress),
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    backend::open(ciphersuite)?.deal_keys(t, n, progress)
+}
+
+/// Generates keys like [`generate_frost_keys`], drawing every secret from `rng`, see
+/// [`crate::SecureRng`].
+///
+/// With ristretto255, the dealer runs every participant's side of the DKG of
+/// [`crate::dkg`] instead of frost-dalek's, which draws from the operating system's
+/// generator itself. The key file is the same either way.
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the parameters
+/// are invalid.
+pub fn generate_frost_keys_with_rng(
+    t: u32,
+    n: u32,
+    ciphersuite: Ciphersuite,
+    progress: &dyn Fn(KeygenProgress),
+    rng: &mut dyn SecureRng,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    backend::open(ciphersuite)?.deal_keys_with_rng(t, n, progress, rng)
+}
+
+/// Generates ristretto255 keys like [`generate_keys_with_progress`], but saves each
+/// participant's share to its own file as soon as that participant is done, instead of
+/// building and saving one key file with every share.
+///
+/// For thousands of participants a single key file is large to hold in memory and to
+/// write, while each share file is small and can be handed out as it appears.
+///
+/// # Arguments
+/// - `t`: Threshold value.
+/// - `n`: Total number of participants.
+/// - `shares_dir`: Directory to save `participant_share_<index>.json` to, see
+///   [`share_file`].
+/// - `progress`: Called each time a participant gets through a stage.
+/// - `options`: How the share files are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the parameters are invalid, the key generation fails or a share
+/// cannot be saved. Shares saved before the failure are left in `shares_dir`.
+pub fn generate_share_files(
+    t: u32,
+    n: u32,
+    shares_dir: &str,
+    progress: &dyn Fn(KeygenProgress),
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(shares_dir).map_err(|err| FrostCliError::io(shares_dir, err))?;
+    let (group_key, _, _) = deal(t, n, progress, |share| {
+        let share_file = share_file(shares_dir, share.index);
+        save_participant_share(&share_file.to_string_lossy(), &share, options).map_err(|err| {
+            FrostCliError::Dkg {
+                index: share.index,
+                reason: format!("Failed to save the share: {}", err),
+            }
+        })
+    })?;
+
+    report(
+        "shares_generated",
+        &format!(
+            "Generated {} shares with threshold {}. Shares saved to: {}",
+            n, t, shares_dir
+        ),
+        json!({
+            "shares_dir": shares_dir,
+            "group_key_fingerprint": envelope::fingerprint(&group_key),
+            "ciphersuite": Ciphersuite::Ristretto255,
+            "threshold": t,
+            "participants": n,
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the path [`generate_share_files`] saves the share of participant `index` to.
+pub fn share_file(shares_dir: &str, index: u32) -> PathBuf {
+    Path::new(shares_dir).join(format!("participant_share_{}.json", index))
+}
+
+/// Runs the whole key generation in this process, as a trusted dealer, calling
+/// `progress` as participants get through each stage.
+pub(crate) fn deal_keys(
+    t: u32,
+    n: u32,
+    progress: &dyn Fn(KeygenProgress),
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    let (group_key, verification_shares, private_shares) =
+        deal(t, n, progress, |share| Ok((share.share, share.index)))?;
+
+    // Combine group key and private shares into a single structure.
+    Ok(FrostKeys {
+        version: FORMAT_VERSION,
+        ciphersuite: Ciphersuite::Ristretto255,
+        group_key,
+        private_shares,
+        threshold: t,
+        verification_shares,
+    })
+}
+
+/// Runs every participant's side of the DKG of [`crate::dkg`] in this process, drawing
+/// every secret from `rng`, see [`generate_frost_keys_with_rng`].
+pub(crate) fn deal_keys_with_rng(
+    t: u32,
+    n: u32,
+    progress: &dyn Fn(KeygenProgress),
+    mut rng: &mut dyn SecureRng,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    let _span = info_span!("generate_keys", t, n).entered();
+    check_parameters(t, n)?;
+
+    // Step 1: Sample every participant's polynomial and commit to it.
+    let (polynomials, messages): (Vec<_>, Vec<_>) = (1..=n)
+        .map(|index| {
+            let committed = dkg::commit_polynomial(index, t, &mut rng);
+            progress(KeygenProgress::new(KeygenStage::Commitments, index, n));
+            committed
+        })
+        .unzip();
+
+    // Step 2: Verify zero-knowledge proof of secret keys for all participants.
+    for message in &messages {
+        dkg::verify_proof_of_secret_key(message).map_err(|_| FrostCliError::Dkg {
+            index: message.index,
+            reason: "Proof of secret key verification failed".into(),
+        })?;
+        progress(KeygenProgress::new(KeygenStage::Proofs, message.index, n));
+    }
+    info!("All participants verified their proofs of secret keys");
+
+    // Step 3: Evaluate every participant's polynomial for every other participant.
+    let mut received: Vec<Vec<(u32, Zeroizing<Scalar>)>> = (0..n).map(|_| Vec::new()).collect();
+    for (sender, polynomial) in (1..=n).zip(&polynomials) {
+        for (receiver, shares) in (1..=n).zip(&mut received) {
+            let share = dkg::evaluate_polynomial(polynomial, receiver);
+            shares.push((sender, Zeroizing::new(share)));
+        }
+        progress(KeygenProgress::new(KeygenStage::RoundOne, sender, n));
+    }
+    drop(polynomials);
+    info!("DKG round 1 complete");
+
+    // Step 4: Verify the shares each participant received and add them up.
+    let mut private_shares = Vec::with_capacity(n as usize);
+    for (receiver, shares) in (1..=n).zip(received) {
+        let mut secret = Zeroizing::new(Scalar::zero());
+        for (sender, share) in &shares {
+            let commitments = &messages[*sender as usize - 1].commitments;
+            if !dkg::verify_share(receiver, share, commitments)? {
+                return Err(FrostCliError::Dkg {
+                    index: *sender,
+                    reason: format!("Sent participant {} an invalid secret share", receiver),
+                }
+                .into());
+            }
+            *secret += **share;
+        }
+        private_shares.push((secret.to_bytes(), receiver));
+        progress(KeygenProgress::new(KeygenStage::RoundTwo, receiver, n));
+    }
+    info!("Share secret shares round 2 complete");
+
+    // Step 5: Derive the group key from the constant term commitments.
+    let mut group_key = RistrettoPoint::identity();
+    for message in &messages {
+        group_key += point_from_bytes(&message.commitments[0])?;
+        progress(KeygenProgress::new(KeygenStage::Finish, message.index, n));
+    }
+
+    Ok(FrostKeys {
+        version: FORMAT_VERSION,
+        ciphersuite: Ciphersuite::Ristretto255,
+        group_key: group_key.compress().to_bytes(),
+        private_shares,
+        threshold: t,
+        verification_shares: dkg::verification_shares(
+            messages
+                .iter()
+                .map(|message| message.commitments.as_slice()),
+            n,
+        )?,
+    })
+}
+
+/// Checks that the threshold is at least 1 and at most the number of participants.
+fn check_parameters(t: u32, n: u32) -> Result<(), FrostCliError> {
+    if t > n {
+        return Err(FrostCliError::InvalidParameters(
+            "Threshold value cannot be greater than the total number of participants".into(),
+        ));
+    }
+    if t == 0 {
+        return Err(FrostCliError::InvalidParameters(
+            "Threshold value must be at least 1".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Runs the key generation like [`deal_keys`], handing each participant's share to
+/// `on_share` as soon as that participant is done. Returns the group key, the
+/// verification shares and what `on_share` returned for every participant, in the order
+/// of the participants.
+#[allow(clippy::type_complexity)]
+fn deal<R: Send>(
+    t: u32,
+    n: u32,
+    progress: &dyn Fn(KeygenProgress),
+    on_share: impl Fn(ParticipantShare) -> Result<R, FrostCliError> + Sync,
+) -> Result<([u8; 32], Vec<[u8; 32]>, Vec<R>), Box<dyn std::error::Error>> {
+    let _span = info_span!("generate_keys", t, n).entered();
+
+    // check that the threshold is between 1 and the total number of participants
+    check_parameters(t, n)?;
+
+    // Initialize the parameters for the key generation.
+    let params = Parameters { t, n };
+
+    // Step 1: Create participants and their polynomial coefficients.
+    let (participants, coefficients): (Vec<_>, Vec<_>) = per_participant(
+        (1..=n).collect(),
+        |_, i| Ok(Participant::new(&params, i)),
+        |i| progress(KeygenProgress::new(KeygenStage::Commitments, i, n)),
+    )?
+    .into_iter()
+    .unzip();
+
+    // Step 2: Verify zero-knowledge proof of secret keys for all participants.
+    per_participant(
+        participants.iter().collect(),
+        |_, participant| {
+            participant
+                .proof_of_secret_key
+                .verify(&participant.index, &participant.public_key().unwrap())
+                .map_err(|_| FrostCliError::Dkg {
+                    index: participant.index,
+                    reason: "Proof of secret key verification failed".into(),
+                })
+        },
+        |i| progress(KeygenProgress::new(KeygenStage::Proofs, i, n)),
+    )?;
+    info!("All participants verified their proofs of secret keys");
+
+    // Derive every participant's verification share from the commitments, for the shares.
+    let commitments: Vec<Vec<[u8; 32]>> = participants
+        .iter()
+        .map(|participant| {
+            participant
+                .commitments
+                .iter()
+                .map(|commitment| commitment.compress().to_bytes())
+                .collect()
+        })
+        .collect();
+    let verification_shares = dkg::verification_shares(commitments.iter().map(Vec::as_slice), n)?;
+
+    // Step 3: Perform the first round of Distributed Key Generation (DKG).
+    let (dkg_states, all_secret_shares): (Vec<_>, Vec<_>) = per_participant(
+        participants.iter().collect(),
+        |i, participant| {
+            // The DKG takes the other participants as an owned vector, which is built
+            // here and dropped with this step rather than kept for every participant.
+            let mut other_participants: Vec<Participant> = participants
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| other.clone())
+                .collect();
+
+            let participant_state = DistributedKeyGeneration::<_>::new(
+                &params,
+                &participant.index,
+                &coefficients[i],
+                &mut other_participants,
+            )
+            .map_err(|misbehaving| FrostCliError::Misbehaving {
+                participants: misbehaving,
+                reason: format!(
+                    "Participant {} found invalid proofs of secret key",
+                    participant.index
+                ),
+            })?;
+
+            let participant_their_secret_shares = participant_state
+                .their_secret_shares()
+                .map_err(|_| FrostCliError::Dkg {
+                    index: participant.index,
+                    reason: "Secret shares retrieval failed".into(),
+                })?
+                .to_vec();
+            Ok((participant_state, participant_their_secret_shares))
+        },
+        |i| progress(KeygenProgress::new(KeygenStage::RoundOne, i, n)),
+    )?
+    .into_iter()
+    .unzip();
+    drop(coefficients);
+    info!("DKG round 1 complete");
+
+    // Step 4: Hand every participant the shares sent to them, moving each share out of
+    // its sender's list so no second copy of all n² shares is kept.
+    let mut received: Vec<Vec<_>> = (0..n).map(|_| Vec::new()).collect();
+    for (j, shares) in all_secret_shares.into_iter().enumerate() {
+        for (pos, share) in shares.into_iter().enumerate() {
+            let i = if pos < j { pos } else { pos + 1 };
+            if let Some(my_secret_shares) = received.get_mut(i) {
+                my_secret_shares.push(share);
+            }
+        }
+    }
+
+    // Step 5: Complete Round 2 of DKG and finish it for each participant in turn, so the
+    // round 1 and round 2 states of a participant are dropped as soon as it is done.
+    let finished = per_participant(
+        dkg_states.into_iter().zip(received).collect(),
+        |i, (dkg_state, my_secret_shares)| {
+            // Ensure the correct number of shares are received.
+            if my_secret_shares.len() != (params.n - 1) as usize {
+                return Err(FrostCliError::Dkg {
+                    index: participants[i].index,
+                    reason: format!(
+                        "Received incorrect number of shares: expected {}, got {}",
+                        params.n - 1,
+                        my_secret_shares.len()
+                    ),
+                });
+            }
+
+            let round_two_state =
+                dkg_state
+                    .to_round_two(my_secret_shares)
+                    .map_err(|_| FrostCliError::Dkg {
+                        index: participants[i].index,
+                        reason: "Received a share that does not match its commitments".into(),
+                    })?;
+            let (dkg_group_key, dkg_secret_key) = round_two_state
+                .finish(participants[i].public_key().unwrap())
+                .map_err(|_| FrostCliError::Dkg {
+                    index: participants[i].index,
+                    reason: "Failed to finish DKG".into(),
+                })?;
+            let (share, index) = dkg_secret_key.to_bytes();
+            let result = on_share(ParticipantShare {
+                index,
+                share,
+                group_key: dkg_group_key.to_bytes(),
+                threshold: t,
+                participants: n,
+                verification_shares: verification_shares.clone(),
+            })?;
+            Ok((dkg_group_key, result))
+        },
+        |i| progress(KeygenProgress::new(KeygenStage::RoundTwo, i, n)),
+    )?;
+    info!("Share secret shares round 2 complete");
+
+    // Step 6: Ensure all group keys are identical.
+    let (group_keys, results): (Vec<_>, Vec<_>) = finished.into_iter().unzip();
+    for (i, group_key) in group_keys.iter().enumerate() {
+        if *group_key != group_keys[0] {
+            return Err(FrostCliError::Dkg {
+                index: i as u32 + 1,
+                reason: "Derived a different group key than participant 1".into(),
+            }
+            .into());
+        }
+        progress(KeygenProgress::new(KeygenStage::Finish, i as u32 + 1, n));
+    }
+    Ok((group_keys[0].to_bytes(), verification_shares, results))
+}
+
+/// Runs `step` for every participant's item, in the order of the participants, and
+/// calls `done` with the participant's 1-based position after its step.
+///
+/// The steps of the participants are independent of each other. With the `parallel`
+/// feature they run on all cores, and `done` is called for every participant once all
+/// steps finished, since a progress callback need not be thread-safe.
+#[cfg(not(feature = "parallel"))]
+fn per_participant<T, R>(
+    items: Vec<T>,
+    step: impl Fn(usize, T) -> Result<R, FrostCliError> + Sync,
+    done: impl Fn(u32),
+) -> Result<Vec<R>, FrostCliError>
+where
+    T: Send,
+    R: Send,
+{
+    let mut results = Vec::with_capacity(items.len());
+    for (i, item) in items.into_iter().enumerate() {
+        results.push(step(i, item)?);
+        done(i as u32 + 1);
+    }
+    Ok(results)
+}
+
+/// Runs `step` for every participant's item on all cores, see the serial version.
+#[cfg(feature = "parallel")]
+fn per_participant<T, R>(
+    items: Vec<T>,
+    step: impl Fn(usize, T) -> Result<R, FrostCliError> + Sync,
+    done: impl Fn(u32),
+) -> Result<Vec<R>, FrostCliError>
+where
+    T: Send,
+    R: Send,
+{
+    use rayon::prelude::*;
+
+    let results: Vec<R> = items
+        .into_par_iter()
+        .enumerate()
+        .map(|(i, item)| step(i, item))
+        .collect::<Result<_, _>>()?;
+    for i in 1..=results.len() {
+        done(i as u32);
+    }
+    Ok(results)
+}
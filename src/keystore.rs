@@ -0,0 +1,337 @@
+//! Keystore directory of named keys.
+//!
+//! Instead of passing key file paths around, keys can be kept under a name in a
+//! keystore directory, `~/.frost/keys` unless `FROST_KEYSTORE` or an explicit
+//! directory says otherwise. Each key is a subdirectory `<name>/` holding either the
+//! dealer's `frost_keys.json` or a single `participant_share.json`, so several
+//! independent signing groups can live side by side and be picked by name. A key's
+//! directory also keeps the `revocations.json` of its group, see [`crate::revoke`].
+//!
+//! [`KeyStore`] is what signing and verification need of such a collection of named
+//! keys, so keys kept in a keyring, an HSM or Vault can be signed with by implementing
+//! it, see [`crate::sign_message_with_keystore`]. [`FileKeyStore`] is the keystore
+//! directory.
+
+use crate::backend::Ciphersuite;
+use crate::config;
+use crate::io::output::report;
+use crate::{
+    load_group_key_bytes, load_key_file, save_participant_share, FileOptions, KeyFile,
+    ParticipantShare,
+};
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a dealer key file in the keystore.
+const KEYS_FILE: &str = "frost_keys.json";
+
+/// File name of a participant share in the keystore.
+const SHARE_FILE: &str = "participant_share.json";
+
+/// A key in the keystore, as shown by [`list_keys`].
+pub struct KeyInfo {
+    pub name: String,
+    /// Whether the key holds every share of the group or a single participant's share.
+    pub kind: KeyKind,
+    pub group_key: [u8; 32],
+    pub threshold: u32,
+}
+
+/// What a key in the keystore holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    /// A dealer key file with every participant's share.
+    Keys,
+    /// A single participant's share.
+    Share { index: u32 },
+}
+
+/// A collection of named keys that group keys and participant shares are loaded from.
+pub trait KeyStore {
+    /// Returns the ciphersuite and compressed group public key of the key called `name`.
+    fn load_group_key(
+        &self,
+        name: &str,
+    ) -> Result<(Ciphersuite, [u8; 32]), Box<dyn std::error::Error>>;
+
+    /// Loads the participant share kept as `name`.
+    fn load_share(&self, name: &str) -> Result<ParticipantShare, Box<dyn std::error::Error>>;
+
+    /// Keeps a participant share as `name`, which must not be taken yet.
+    fn store_share(
+        &self,
+        name: &str,
+        share: &ParticipantShare,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Lists the keys, sorted by name.
+    fn list_keys(&self) -> Result<Vec<KeyInfo>, Box<dyn std::error::Error>>;
+}
+
+/// The keystore directory as a [`KeyStore`].
+pub struct FileKeyStore {
+    dir: PathBuf,
+    options: FileOptions,
+}
+
+impl FileKeyStore {
+    pub fn new(dir: impl Into<PathBuf>) -> FileKeyStore {
+        FileKeyStore {
+            dir: dir.into(),
+            options: FileOptions::default(),
+        }
+    }
+
+    /// Writes and reads the keys it keeps as `options` say, instead of as JSON without a
+    /// file key.
+    pub fn with_options(mut self, options: FileOptions) -> FileKeyStore {
+        self.options = options;
+        self
+    }
+
+    /// Opens the keystore directory, see [`keystore_dir`].
+    ///
+    /// # Errors
+    /// Returns an error if no directory is given and the home directory is unknown.
+    pub fn open(dir: Option<&str>) -> Result<FileKeyStore, Box<dyn std::error::Error>> {
+        Ok(FileKeyStore::new(keystore_dir(dir)?))
+    }
+
+    /// Returns the keystore directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn load_group_key(
+        &self,
+        name: &str,
+    ) -> Result<(Ciphersuite, [u8; 32]), Box<dyn std::error::Error>> {
+        load_group_key_bytes(&key_file(&self.dir, name)?, &self.options)
+    }
+
+    fn load_share(&self, name: &str) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        match load_key_file(&key_file(&self.dir, name)?, &self.options)? {
+            KeyFile::Share(share) => Ok(share),
+            KeyFile::Keys(_) => Err(format!(
+                "Key {} holds every share of its group, not a single participant's share",
+                name
+            )
+            .into()),
+        }
+    }
+
+    fn store_share(
+        &self,
+        name: &str,
+        share: &ParticipantShare,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = key_dir(&self.dir, name)?;
+        if dir.exists() {
+            return Err(format!("Key {} already exists in {}", name, self.dir.display()).into());
+        }
+        fs::create_dir_all(&dir)?;
+        save_participant_share(
+            &dir.join(SHARE_FILE).to_string_lossy(),
+            share,
+            &self.options,
+        )
+    }
+
+    fn list_keys(&self) -> Result<Vec<KeyInfo>, Box<dyn std::error::Error>> {
+        list_keys(&self.dir, &self.options)
+    }
+}
+
+/// Returns the keystore directory: `dir` if given, else `FROST_KEYSTORE`, else
+/// `~/.frost/keys`.
+///
+/// # Errors
+/// Returns an error if no directory is given and the home directory is unknown.
+pub fn keystore_dir(dir: Option<&str>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(dir) = dir {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = config::var(config::KEYSTORE)? {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .ok_or("Cannot find the home directory, set FROST_KEYSTORE")?;
+    Ok(Path::new(&home).join(".frost").join("keys"))
+}
+
+/// Returns the path of the key called `name`, whichever kind it is.
+///
+/// # Errors
+/// Returns an error if the keystore has no key called `name`.
+pub fn key_file(keystore: &Path, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = key_dir(keystore, name)?;
+    [KEYS_FILE, SHARE_FILE]
+        .iter()
+        .map(|file| dir.join(file))
+        .find(|path| path.exists())
+        .map(|path| path.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("No key called {} in {}", name, keystore.display()).into())
+}
+
+/// Returns the path of the dealer key file called `name`, for signing with every share.
+///
+/// # Errors
+/// Returns an error if the keystore has no key called `name` or it only holds a single
+/// participant's share.
+pub fn dealer_key_file(keystore: &Path, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let path = key_file(keystore, name)?;
+    if !path.ends_with(KEYS_FILE) {
+        return Err(format!(
+            "Key {} holds a single participant's share and cannot sign on its own",
+            name
+        )
+        .into());
+    }
+    Ok(path)
+}
+
+/// Creates the directory for a new dealer key file called `name` and returns its path.
+///
+/// # Errors
+/// Returns an error if the name is invalid or a key with that name already exists.
+pub fn new_key_file(keystore: &Path, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let dir = key_dir(keystore, name)?;
+    if dir.exists() {
+        return Err(format!("Key {} already exists in {}", name, keystore.display()).into());
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(KEYS_FILE).to_string_lossy().into_owned())
+}
+
+/// Copies a key file or participant share file into the keystore as `name`.
+///
+/// # Arguments
+/// - `keystore`: The keystore directory.
+/// - `name`: Name to store the key under.
+/// - `key_file`: Path to the key file or participant share file to import.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the file is not a key file, the name is invalid, or a key with
+/// that name already exists.
+pub fn import_key(
+    keystore: &Path,
+    name: &str,
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check what kind of key the file holds
+    let file = match load_key_file(key_file, options)? {
+        KeyFile::Keys(_) => KEYS_FILE,
+        KeyFile::Share(_) => SHARE_FILE,
+    };
+
+    // Step 2: Copy it into its own directory
+    let dir = key_dir(keystore, name)?;
+    if dir.exists() {
+        return Err(format!("Key {} already exists in {}", name, keystore.display()).into());
+    }
+    fs::create_dir_all(&dir)?;
+    fs::copy(key_file, dir.join(file))?;
+
+    report(
+        "key_imported",
+        &format!("Key {} imported to: {}", name, dir.display()),
+        json!({ "name": name, "dir": dir }),
+    );
+    Ok(())
+}
+
+/// Lists the keys in the keystore, sorted by name.
+///
+/// # Errors
+/// Returns an error if a key in the keystore cannot be read.
+pub fn list_keys(
+    keystore: &Path,
+    options: &FileOptions,
+) -> Result<Vec<KeyInfo>, Box<dyn std::error::Error>> {
+    if !keystore.exists() {
+        return Ok(Vec::new());
+    }
+    let mut keys = Vec::new();
+    for entry in fs::read_dir(keystore)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let key = load_key_file(&key_file(keystore, &name)?, options)?;
+        keys.push(KeyInfo {
+            kind: match &key {
+                KeyFile::Keys(_) => KeyKind::Keys,
+                KeyFile::Share(share) => KeyKind::Share { index: share.index },
+            },
+            group_key: key.group_key(),
+            threshold: key.threshold(),
+            name,
+        });
+    }
+    keys.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(keys)
+}
+
+/// Returns the names of the keys in the keystore, sorted, without reading the keys, for
+/// completing `--key` in a shell.
+///
+/// # Errors
+/// Returns an error if the keystore directory cannot be read.
+pub fn key_names(keystore: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !keystore.exists() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(keystore)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() && key_file(keystore, &name).is_ok() {
+            names.push(name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Deletes the key called `name` and its directory.
+///
+/// # Errors
+/// Returns an error if the keystore has no key called `name`.
+pub fn delete_key(keystore: &Path, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    key_file(keystore, name)?;
+    let dir = key_dir(keystore, name)?;
+    fs::remove_dir_all(&dir)?;
+
+    report(
+        "key_deleted",
+        &format!("Key {} deleted from: {}", name, keystore.display()),
+        json!({ "name": name, "keystore": keystore }),
+    );
+    Ok(())
+}
+
+/// Returns the directory of the key called `name`, rejecting names that are not a
+/// single path component.
+fn key_dir(keystore: &Path, name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(format!(
+            "Invalid key name: {}, use letters, digits, '-', '_' and '.'",
+            name
+        )
+        .into());
+    }
+    Ok(keystore.join(name))
+}
@@ -2,20 +2,221 @@
 //!
 //! This tool supports:
 //! - Generating a public key and private key shares.
+//! - Showing the progress of large key generations.
+//! - Generating keys in the Ed25519 ciphersuite for standard Ed25519 signatures.
+//! - Generating keys in the secp256k1 ciphersuite for BIP340 Schnorr signatures.
+//! - Checking a ciphersuite against the RFC 9591 test vectors.
+//! - Encrypting each generated share to its participant's public key.
+//! - Backing up participant shares as BIP39 mnemonics or typed paper backups.
+//! - Keeping participant shares in the operating system's keyring, on a PKCS#11 token,
+//!   in HashiCorp Vault or in AWS Secrets Manager.
+//! - Keeping several named keys in a keystore directory.
+//! - Plugging other key stores into signing and verification through a trait.
+//! - Inspecting the public parameters of key files without showing secrets.
+//! - Inspecting the format, components and metadata of signature files.
+//! - Taking paths, settings and secrets from environment variables.
+//! - Generating shell completions for subcommands, flags and keystore key names.
 //! - Signing a message using a threshold of private key shares.
+//! - Signing binary files of any size by their streamed SHA-256 hash.
+//! - Signing every message and file of a manifest in one signing ceremony.
+//! - Verifying every signature of a manifest with a per-entry report.
 //! - Verifying a signature using the public key.
+//! - Generating, signing and verifying in memory through the library API.
+//! - Telling library failures apart by kind through typed errors.
+//! - Supplying the random number generator of library functions.
+//! - Driving many ceremonies concurrently through the async library API.
+//! - Telling invalid signatures, bad input and I/O errors apart by `verify`'s exit code.
+//! - Checksumming key, share and signature files, and upgrading older files.
+//! - Checking key files, signature files and the binary for compatibility up front.
+//! - Locking decrypted shares and secret nonces in memory.
+//! - Writing files as JSON, CBOR or bincode, and signatures as raw bytes, hex or base64.
+//! - Exporting group keys and signatures as PEM.
+//! - Saving signed messages as a compact JWS.
+//! - Saving signatures in the OpenSSH `sshsig` format.
+//! - Saving OpenPGP detached signatures and certified OpenPGP group keys.
+//! - Saving cosign blob signature bundles.
+//! - Saving COSE_Sign1 envelopes and exporting group keys as COSE keys.
+//! - Saving DSSE envelopes for in-toto and SLSA attestations.
+//! - Threshold-signing in-toto attestations of artifacts.
+//! - Signing and verifying git commits and tags as git's `gpg.program`.
+//! - Signing TUF metadata with the group key as one of a role's keys.
+//! - Canonicalizing JSON messages (RFC 8785) before signing and verifying them.
+//! - Recording the signers, group key and time of signing in signature files.
+//! - Choosing signers by their participant IDs, checked against the key file.
+//! - Signing with any threshold or more distinct participants.
+//! - Naming every signer whose partial signature does not verify.
+//! - Recording every signing ceremony in an append-only transcript for audit.
+//! - Deriving keys from a seed for tests and demos, behind the `insecure-deterministic` feature.
+//! - Refusing malformed key, share, signature and round message files without panicking.
+//! - Keeping a share loaded in a signer daemon that answers JSON-RPC on a Unix socket.
+//! - Signing in a context string of the application, for domain separation.
+//! - Timestamping signatures with an RFC 3161 time stamping authority.
+//! - Printing command results as JSON lines for scripts.
+//! - Writing diagnostics to stderr with adjustable verbosity.
+//! - Running one participant's side of a distributed key generation.
+//! - Naming every participant who sent an invalid DKG message.
+//! - Settling disputed DKG shares in a complaint round.
+//! - Running one participant's side of a two-round signing.
+//! - Precomputing a pool of signing commitments for later sessions.
+//! - Refusing to reuse signing nonces, whose use is tracked on disk.
+//! - Committing to several nonce pairs at once to sign several messages in a session.
+//! - Coordinating a resumable signing session.
+//! - Moving round messages to and from air-gapped machines as QR codes.
+//! - Exchanging round messages through a TCP mailbox.
+//! - Running an HTTP or gRPC coordinator for signing sessions.
+//! - Serving an authenticated HTTP API that signs with the keystore's shares once approved.
+//! - Exposing Prometheus metrics of signing ceremonies, rounds and verifications.
+//! - Exchanging round messages peer-to-peer over libp2p.
+//! - Answering a coordinator's requests as a WebSocket participant daemon.
+//! - Refilling a participant daemon's commitment pool before it runs out.
+//! - Generating keys for all participants on every core with the `parallel` feature.
+//! - Benchmarking key generation and signing for a matrix of parameters.
+//! - Saving each participant's share to its own file as key generation finishes it.
+//! - Sealing DKG shares to the receivers' identity keys with Noise.
+//! - Authenticating participants to the coordinator with mutual TLS.
+//! - Forwarding sealed round messages through a relay.
+//! - Running DKG and signing ceremonies over pluggable transports.
+//! - Exchanging protocol messages with stable, versioned schemas.
+//! - Signing from a browser through WebAssembly bindings.
+//! - Embedding in C and C++ signers through a C FFI.
+//! - Running ceremonies from Python through the `frost_dalek_cli` module.
+//! - Signing on iOS and Android through Kotlin and Swift bindings.
+//! - Refreshing participant shares without changing the group key.
+//! - Resharing a group key to a new threshold and participant set.
+//! - Enrolling a new participant into an existing group.
+//! - Revoking a participant and rejecting its share in later sessions.
+//! - Verifying a participant's share against the group key.
+//! - Reconstructing the full group secret key for migration or disaster recovery.
+//! - Repairing a participant's lost share with the help of a threshold of others.
+//! - Rotating to a new group key with a statement signed by the old group.
+//! - Signing round messages with long-term participant identities.
+//! - Checking that all signers received the same roster (echo broadcast).
 
-use clap::{Parser, Subcommand};
-use frost_cli::{generate_keys, sign_message, validate_signature};
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use frost_cli::{
+    attest::{self, Predicate},
+    backend::{self, Ciphersuite},
+    backup, bench, check, config, daemon, dealer, dkg, echo, enroll, error, export_cose_key,
+    export_group_key, export_pkix_public_key, export_ssh_public_key, generate_keys_from_seed,
+    generate_keys_with_progress, generate_share_files, git,
+    identity::{self, IdentityFiles},
+    inspect,
+    io::dsse,
+    io::envelope::{EnvelopeOptions, OutFormat},
+    io::format::Format,
+    io::jcs::Canonicalization,
+    io::output::{self, OutputMode},
+    io::signature::SignatureFormat,
+    io::sshsig,
+    keystore, load_participant_share, manifest, memlock, metrics, migrate,
+    net::api,
+    net::grpc,
+    net::http,
+    net::noise,
+    net::p2p,
+    net::relay,
+    net::tcp,
+    net::tls::{ClientTls, ServerTls},
+    net::ws,
+    qr, reconstruct, refresh, repair, reshare, revoke, rotate, seeded_rng, session, sign_file,
+    sign_message_as_with_rng, sign_message_with_shares_as,
+    signing::{self, CommitOptions},
+    storage, timestamp, transcript, tuf, validate_file_signature, validate_signature_as, FileKey,
+    FileOptions, InvalidSignature, KeygenProgress, ParticipantId, SecureRng, SIGNING_CONTEXT,
+};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use rand::rngs::OsRng;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, warn, Level};
 
 /// Defines the structure for the CLI interface.
 #[derive(Parser)]
 #[command(name = "frost-cli")]
 #[command(about = "CLI for FROST threshold signatures", long_about = None)]
 struct Cli {
-    /// Subcommand to execute (generate, sign, or verify).
+    /// Subcommand to execute (generate, decrypt-share, share, key, sig, transcript, completions, sign, verify, group-key, check, migrate, dkg, commit, echo, partial-sign, aggregate, session, qr-decode, coordinator, participant, identity, relay, daemon, serve, api-client, or mailbox).
     #[command(subcommand)]
     command: Commands,
+    /// Where participant shares are kept: `file`, `keyring`, `pkcs11`, `aws` or
+    /// `vault://<mount>/<path>`. Except with `file`, share file arguments name an entry in
+    /// the operating system's keyring, an object on a PKCS#11 token, a secret in AWS
+    /// Secrets Manager or a secret under `<path>` in Vault instead of a file.
+    #[arg(long, global = true, value_parser = parse_store, default_value = "file", env = config::STORE)]
+    store: Store,
+    /// Keystore directory for `--key` and the key subcommands (default: `FROST_KEYSTORE`
+    /// or `~/.frost/keys`).
+    #[arg(long, global = true, env = config::KEYSTORE)]
+    keystore: Option<String>,
+    /// Lock decrypted shares and secret nonces in memory, so they are never swapped to
+    /// disk.
+    #[arg(long, global = true)]
+    mlock: bool,
+    /// Format to write key, share, signature and round message files in: `json`, `cbor`
+    /// or `bincode`. Files in any of these formats are recognised when they are read.
+    #[arg(long, global = true, default_value = "json", env = config::FORMAT)]
+    format: Format,
+    /// Context string to sign and verify messages in, so applications sharing a group
+    /// key cannot use each other's signatures. Signers and verifiers must use the same
+    /// context (default: `THRESHOLD SIGNING CONTEXT`).
+    #[arg(long, global = true, env = config::CONTEXT)]
+    context: Option<String>,
+    /// Print command results as `text`, as `json` with one JSON object per line and
+    /// result, for scripts and other services, or not at all with `none`.
+    #[arg(long, global = true, default_value = "text", env = config::OUTPUT)]
+    output: OutputMode,
+    /// Append a record of every signing ceremony to this transcript: the signers, their
+    /// commitments, the message hash and the signature, one JSON object per line.
+    #[arg(long, global = true, env = config::TRANSCRIPT)]
+    transcript: Option<String>,
+    /// Print more diagnostics on stderr, `-v` for debug and `-vv` for trace messages.
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Only print errors on stderr. `verify` prints nothing at all and only tells the
+    /// result by its exit code.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+/// Backends for participant shares.
+#[derive(Clone)]
+enum Store {
+    File,
+    Keyring,
+    Pkcs11,
+    Aws,
+    /// Vault KV mount and the path shares are kept under.
+    Vault(String),
+}
+
+impl Store {
+    /// Returns the share location for a share file argument.
+    fn location(&self, share_file: &str) -> String {
+        match self {
+            Store::File => share_file.to_string(),
+            Store::Keyring => format!("{}{}", storage::KEYRING_PREFIX, share_file),
+            Store::Pkcs11 => format!("{}object={}", storage::PKCS11_PREFIX, share_file),
+            Store::Aws => format!("{}{}", storage::AWS_PREFIX, share_file),
+            Store::Vault(prefix) => format!("{}/{}", prefix.trim_end_matches('/'), share_file),
+        }
+    }
+}
+
+fn parse_store(store: &str) -> Result<Store, String> {
+    match store {
+        "file" => Ok(Store::File),
+        "keyring" => Ok(Store::Keyring),
+        "pkcs11" => Ok(Store::Pkcs11),
+        "aws" => Ok(Store::Aws),
+        _ if store.starts_with(storage::VAULT_PREFIX) => Ok(Store::Vault(store.to_string())),
+        _ => Err(format!(
+            "Expected file, keyring, pkcs11, aws or vault://<mount>/<path>, got: {}",
+            store
+        )),
+    }
 }
 
 /// Enum representing available CLI commands.
@@ -31,71 +232,4017 @@ enum Commands {
         n: u32,
         #[arg(short, long, default_value = "./results/frost_keys.json")]
         output_key_file: String,
+        /// Save the keys under this name in the keystore instead of the output key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Roster of the participants' public keys. Writes one encrypted share bundle per
+        /// participant instead of the plaintext key file.
+        #[arg(short, long)]
+        roster_file: Option<String>,
+        /// Directory to write the encrypted share bundles to.
+        #[arg(long, default_value = "./results/share_bundles")]
+        bundles_dir: String,
+        /// Ciphersuite of the keys: `ristretto255`, `ed25519` for signatures any Ed25519
+        /// verifier accepts, or `secp256k1` for BIP340 Schnorr signatures.
+        #[arg(long, default_value = "ristretto255")]
+        ciphersuite: Ciphersuite,
+        /// Save each participant's share to `participant_share_<index>.json` in this
+        /// directory as soon as it is finished, instead of one key file with every share.
+        #[arg(long, conflicts_with_all = ["key", "roster_file"])]
+        shares_dir: Option<String>,
+        /// Derive the keys from this seed, so the same seed always gives the same keys.
+        /// Anyone who knows the seed can sign alone, only use it for tests and demos.
+        /// Needs the `insecure-deterministic` feature.
+        #[arg(long, conflicts_with_all = ["roster_file", "shares_dir"])]
+        seed: Option<String>,
     },
-    /// Sign a message using a threshold of private key shares.
+    /// Decrypt a share bundle created by generate with a roster file.
+    DecryptShare {
+        /// Path to this participant's private identity.
+        #[arg(short = 'f', long, default_value = "./results/identity.json", env = config::IDENTITY_FILE)]
+        identity_file: String,
+        /// Path to this participant's share bundle.
+        #[arg(short, long)]
+        bundle_file: String,
+        /// Path to save this participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+    },
+    /// Back up, restore and verify a participant's share.
+    Share {
+        #[command(subcommand)]
+        command: ShareCommands,
+    },
+    /// Sign a message, a file or every entry of a manifest using a threshold of private
+    /// key shares.
+    #[command(group(ArgGroup::new("input").required(true).args(["message", "file", "manifest"])))]
     Sign {
         /// The message to sign.
         #[arg(short, long)]
-        message: String,
-        /// Threshold value for signing.
-        #[arg(short = 'i', long, default_value = "1,2,3")]
-        signers: String,
+        message: Option<String>,
+        /// File of any size and content to sign by its SHA-256 hash instead of a message.
+        #[arg(long, conflicts_with_all = ["canonicalize", "share_files"])]
+        file: Option<String>,
+        /// JSON manifest of messages and files to sign in one signing ceremony, each
+        /// saved to the signature file the manifest gives it.
+        #[arg(long, conflicts_with_all = ["share_files", "signature_file", "tsa_url"])]
+        manifest: Option<String>,
+        /// IDs of the participants signing, from 1.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
         /// Total number of participants.
         #[arg(short, long, default_value = "5")]
         n: u32,
         /// Path to the JSON file containing key shares.
-        #[arg(short, long, default_value = "./results/frost_keys.json")]
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
         key_file: String,
+        /// Name of the keys in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
         /// Path to save the resulting signature.
         #[arg(short = 'f', long, default_value = "./results/signature.json")]
         signature_file: String,
+        /// Format to save the signature in: `raw`, `hex`, `base64`, `pem` or `json`.
+        #[arg(long, default_value = "json")]
+        sig_format: SignatureFormat,
+        /// Save a plain `signature` in the signature format, the message and its
+        /// signature as a compact `jws`, an OpenSSH `sshsig` signature, an `openpgp`
+        /// detached signature, the group key as an `openpgp-key` with the message as its
+        /// user ID, a `cosign-bundle` of the message as a blob, a `cose` COSE_Sign1 or a
+        /// `dsse` envelope.
+        #[arg(long, default_value = "signature")]
+        out_format: OutFormat,
+        /// Save a cosign blob signature bundle, the same as `--out-format cosign-bundle`.
+        #[arg(long, conflicts_with = "out_format")]
+        cosign_bundle: bool,
+        /// Namespace of an `sshsig` signature, as in `ssh-keygen -Y sign -n`.
+        #[arg(long, default_value = sshsig::DEFAULT_NAMESPACE)]
+        namespace: String,
+        /// Payload type of a `dsse` envelope.
+        #[arg(long, default_value = dsse::DEFAULT_PAYLOAD_TYPE)]
+        payload_type: String,
+        /// Canonicalize the message before signing it: `none`, or `jcs` to sign a JSON
+        /// message in its RFC 8785 canonical form.
+        #[arg(long, default_value = "none")]
+        canonicalize: Canonicalization,
+        /// Comma-separated participant share files to sign with instead of the key file.
+        #[arg(short = 'p', long)]
+        share_files: Option<String>,
+        /// URL of an RFC 3161 time stamping authority to timestamp the signature with.
+        #[arg(long)]
+        tsa_url: Option<String>,
+        /// Draw the signing nonces from this seed, so the same keys, message and seed
+        /// always give the same signature. Anyone who sees two messages signed with the
+        /// same seed can compute the shares, only use it for tests and demos. Needs the
+        /// `insecure-deterministic` feature.
+        #[arg(long, conflicts_with_all = ["file", "manifest", "share_files"])]
+        seed: Option<String>,
     },
-    /// Verify a signature using the public key.
+    /// Verify a signature on a message or a file, or the signatures of every entry of a
+    /// manifest, using the public key.
+    ///
+    /// Exits with 0 if the signature is valid, 1 if it is invalid, 2 on bad input and 3
+    /// if a file cannot be read.
+    #[command(group(ArgGroup::new("input").required(true).args(["message", "file", "manifest"])))]
     Verify {
         /// The signed message to verify.
         #[arg(short, long)]
+        message: Option<String>,
+        /// The signed file to verify, hashed by `sign --file`.
+        #[arg(long, conflicts_with = "canonicalize")]
+        file: Option<String>,
+        /// JSON manifest of messages and files to verify with their signature files,
+        /// reporting each as passed or failed and failing if any entry fails.
+        #[arg(long, conflicts_with_all = ["signature_file", "tsa_cert"])]
+        manifest: Option<String>,
+        /// Path to the key file, participant share file or PEM group key containing the
+        /// public key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Path to the file containing the signature.
+        #[arg(short, long, default_value = "./results/signature.json")]
+        signature_file: String,
+        /// Format the signature was saved in: `raw`, `hex`, `base64`, `pem` or `json`.
+        /// Detected from the signature file if not given.
+        #[arg(long)]
+        sig_format: Option<SignatureFormat>,
+        /// Namespace an `sshsig` signature must have been made in.
+        #[arg(long, default_value = sshsig::DEFAULT_NAMESPACE)]
+        namespace: String,
+        /// Payload type a `dsse` envelope must have.
+        #[arg(long, default_value = dsse::DEFAULT_PAYLOAD_TYPE)]
+        payload_type: String,
+        /// Canonicalize the message before verifying it, as it was when signed.
+        #[arg(long, default_value = "none")]
+        canonicalize: Canonicalization,
+        /// Certificate of the time stamping authority, as PEM or DER, to also verify the
+        /// signature's timestamp with.
+        #[arg(long)]
+        tsa_cert: Option<String>,
+    },
+    /// Threshold-sign an in-toto attestation about artifacts as a DSSE envelope.
+    #[command(group(ArgGroup::new("predicate_kind").required(true).args(["builder_id", "predicate_file"])))]
+    Attest {
+        /// Comma-separated artifact files the attestation is about.
+        #[arg(short = 'a', long)]
+        subjects: String,
+        /// Builder ID of a SLSA provenance predicate.
+        #[arg(long, requires = "build_type")]
+        builder_id: Option<String>,
+        /// Build type of a SLSA provenance predicate.
+        #[arg(long, requires = "builder_id")]
+        build_type: Option<String>,
+        /// JSON file with a custom predicate instead of SLSA provenance.
+        #[arg(long, requires = "predicate_type")]
+        predicate_file: Option<String>,
+        /// Predicate type URI of the custom predicate.
+        #[arg(long, requires = "predicate_file")]
+        predicate_type: Option<String>,
+        /// IDs of the participants signing, from 1.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Total number of participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Path to the JSON file containing key shares.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the keys in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Path to save the DSSE envelope.
+        #[arg(short, long, default_value = "./results/attestation.dsse.json")]
+        output_file: String,
+        /// Path to also save the signed statement to, as the message for `verify`.
+        #[arg(long)]
+        statement_file: Option<String>,
+    },
+    /// Sign or verify a git commit or tag as git's `gpg.program`.
+    GitSign {
+        /// IDs of the participants signing, from 1.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Total number of participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Path to the key file of an Ed25519 group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// The arguments git passes to `gpg.program`, after `--`.
+        #[arg(last = true)]
+        gpg_args: Vec<String>,
+    },
+    /// Sign and verify TUF metadata with the group key.
+    Tuf {
+        #[command(subcommand)]
+        command: TufCommands,
+    },
+    /// Export the group public key of a key file or participant share.
+    GroupKey {
+        /// Path to the key file or participant share file.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Armor the group key as PEM instead of printing it as hex.
+        #[arg(long)]
+        pem: bool,
+        /// Print an Ed25519 group key as an OpenSSH public key, for allowed signers files.
+        #[arg(long, conflicts_with = "pem")]
+        ssh: bool,
+        /// Print an Ed25519 group key as a PKIX `PUBLIC KEY` PEM, for `cosign --key`.
+        #[arg(long, conflicts_with_all = ["pem", "ssh"])]
+        pkix: bool,
+        /// Print an Ed25519 group key as a hex-encoded `COSE_Key`.
+        #[arg(long, conflicts_with_all = ["pem", "ssh", "pkix"])]
+        cose: bool,
+        /// Path to save the group key to instead of printing it.
+        #[arg(short, long)]
+        output_file: Option<String>,
+    },
+    /// Check a ciphersuite against the RFC 9591 test vectors.
+    Testvectors {
+        /// Ciphersuite to check. Only `ed25519` implements RFC 9591 exactly.
+        #[arg(long, default_value = "ed25519")]
+        ciphersuite: Ciphersuite,
+        /// Path to the JSON test vectors of the ciphersuite.
+        #[arg(long)]
+        vectors_file: String,
+    },
+    /// Check that a key file, a signature file and this build of frost-cli work together
+    /// before a ceremony begins, listing every mismatch with how to resolve it.
+    ///
+    /// Exits with 0 if they are compatible and 1 if not.
+    Check {
+        /// Path to the key file, participant share file, share bundle or PEM group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Path to a signature file to check against the key.
+        #[arg(short, long)]
+        signature_file: Option<String>,
+        /// Format the signature was saved in: `raw`, `hex`, `base64`, `pem` or `json`.
+        /// Detected from the signature file if not given.
+        #[arg(long)]
+        sig_format: Option<SignatureFormat>,
+    },
+    /// Upgrade a key, share or signature file to the current format.
+    Migrate {
+        /// Path to the key, share or signature file.
+        #[arg(short, long)]
+        file: String,
+        /// Path to save the upgraded file. Without it, the file is upgraded in place and
+        /// the original is kept with a `.bak` extension.
+        #[arg(short, long)]
+        output_file: Option<String>,
+    },
+    /// Create signing commitments for one participant (signing round 1).
+    Commit {
+        /// ID of the signing session announced by the coordinator.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Path to save the public commitments for the coordinator.
+        #[arg(short = 'c', long, default_value = "./results/public_commitments.json")]
+        public_commitments_file: String,
+        /// Path to save the secret nonces locally.
+        #[arg(short, long, default_value = "./results/secret_commitments.json")]
+        secret_commitments_file: String,
+        /// Take the next unused nonces from this commitment pool instead of generating
+        /// fresh ones, see `commitments generate`.
+        #[arg(long)]
+        pool_file: Option<String>,
+        /// Number of nonce pairs to commit to, one for each message signed in the
+        /// session with its own `--commitment-index`.
+        #[arg(long, default_value = "1", conflicts_with = "pool_file")]
+        count: u32,
+        /// Draw the nonces from this seed, so the same share and seed always give the
+        /// same commitments. Anyone who sees two messages signed with them can compute
+        /// the share, only use it for tests and demos. Needs the `insecure-deterministic`
+        /// feature.
+        #[arg(long, conflicts_with = "pool_file")]
+        seed: Option<String>,
+        /// Also print the public commitments as a QR code.
+        #[arg(long)]
+        qr: bool,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        /// Send the public commitments to the coordinator at this URL.
+        #[arg(long)]
+        coordinator_url: Option<String>,
+        /// Talk to the coordinator over gRPC instead of HTTP.
+        #[arg(long, requires = "coordinator_url")]
+        grpc: bool,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Manage a participant's pool of precomputed signing commitments.
+    Commitments {
+        #[command(subcommand)]
+        command: CommitmentsCommands,
+    },
+    /// Hash the signer roster received from the coordinator for the other signers.
+    Echo {
+        /// The message to sign.
+        #[arg(short, long)]
+        message: String,
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Comma-separated public commitment files of every signer, including this one.
+        #[arg(
+            short,
+            long,
+            required_unless_present_any = ["session_file", "coordinator_url"]
+        )]
+        commitment_files: Option<String>,
+        /// Session manifest to take the signer roster from instead of commitment files.
+        #[arg(short = 'e', long, conflicts_with = "commitment_files")]
+        session_file: Option<String>,
+        /// Take the signer roster from the coordinator at this URL.
+        #[arg(
+            long,
+            requires = "session_id",
+            conflicts_with_all = ["commitment_files", "session_file"]
+        )]
+        coordinator_url: Option<String>,
+        /// ID of the signing session on the coordinator.
+        #[arg(long)]
+        session_id: Option<String>,
+        /// Talk to the coordinator over gRPC instead of HTTP.
+        #[arg(long, requires = "coordinator_url")]
+        grpc: bool,
+        /// Path to save the echo for the other signers.
+        #[arg(short, long, default_value = "./results/echo.json")]
+        output_file: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+        #[command(flatten)]
+        p2p: P2pArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Create a partial signature for one participant (signing round 2).
+    PartialSign {
+        /// The message to sign.
+        #[arg(short, long)]
+        message: String,
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Path to the secret nonces saved by the commit command.
+        #[arg(short, long, default_value = "./results/secret_commitments.json")]
+        secret_commitments_file: String,
+        /// Comma-separated public commitment files of every signer, including this one.
+        #[arg(
+            short,
+            long,
+            required_unless_present_any = ["session_file", "coordinator_url"]
+        )]
+        commitment_files: Option<String>,
+        /// Session manifest to take the signer roster from instead of commitment files.
+        #[arg(short = 'e', long, conflicts_with = "commitment_files")]
+        session_file: Option<String>,
+        /// Path to save the partial signature for the coordinator.
+        #[arg(short, long, default_value = "./results/partial_signature.json")]
+        output_file: String,
+        /// Index of the commitments to sign with, for sessions committed with `commit
+        /// --count`. Only used with `--commitment-files`.
+        #[arg(long, default_value = "0")]
+        commitment_index: u32,
+        /// Comma-separated echo files of the other signers. Signing aborts unless they
+        /// all received the same signer roster.
+        #[arg(long)]
+        echo_files: Option<String>,
+        /// Also print the partial signature as a QR code.
+        #[arg(long)]
+        qr: bool,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        /// Take the signer roster from the coordinator at this URL and send the partial
+        /// signature back to it.
+        #[arg(long, conflicts_with_all = ["commitment_files", "session_file"])]
+        coordinator_url: Option<String>,
+        /// Talk to the coordinator over gRPC instead of HTTP.
+        #[arg(long, requires = "coordinator_url")]
+        grpc: bool,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+        #[command(flatten)]
+        p2p: P2pArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Combine the signers' partial signatures into a threshold signature.
+    Aggregate {
+        /// The signed message.
+        #[arg(short, long)]
+        message: String,
+        /// Path to a key file or participant share file containing the group key.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        key_file: String,
+        /// Comma-separated public commitment files of every signer.
+        #[arg(short, long)]
+        commitment_files: String,
+        /// Directory containing the partial signature files of every signer.
+        #[arg(short, long, default_value = "./results/partial_signatures")]
+        partial_signatures_dir: String,
+        /// Index of the commitments the signers signed with, see `partial-sign
+        /// --commitment-index`.
+        #[arg(long, default_value = "0")]
+        commitment_index: u32,
+        /// Path to save the resulting signature.
+        #[arg(short = 'f', long, default_value = "./results/signature.json")]
+        signature_file: String,
+        /// Format to save the signature in: `raw`, `hex`, `base64`, `pem` or `json`.
+        #[arg(long, default_value = "json")]
+        sig_format: SignatureFormat,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        p2p: P2pArgs,
+        /// Check the identity signature on every round message against the public
+        /// identities in this directory.
+        #[arg(long)]
+        identities_dir: Option<String>,
+    },
+    /// Run one participant's side of a distributed key generation.
+    Dkg {
+        #[command(subcommand)]
+        command: DkgCommands,
+    },
+    /// Refresh a participant's share without changing the group key.
+    Refresh {
+        #[command(subcommand)]
+        command: RefreshCommands,
+    },
+    /// Reshare a group key to a new threshold and participant set.
+    Reshare {
+        #[command(subcommand)]
+        command: ReshareCommands,
+    },
+    /// Add a participant to an existing group.
+    Enroll {
+        #[command(subcommand)]
+        command: EnrollCommands,
+    },
+    /// Restore a participant's lost share with the help of a threshold of others.
+    Repair {
+        #[command(subcommand)]
+        command: RepairCommands,
+    },
+    /// Revoke a lost or compromised participant.
+    Revoke {
+        #[command(subcommand)]
+        command: RevokeCommands,
+    },
+    /// Rotate to a new group key with a statement signed by the old group.
+    Rotate {
+        #[command(subcommand)]
+        command: RotateCommands,
+    },
+    /// Measure key generation and each signing step for a matrix of thresholds and
+    /// numbers of participants, to choose parameters before a real ceremony.
+    Bench {
+        /// Comma-separated thresholds to measure.
+        #[arg(short, long, value_delimiter = ',', default_value = "2,3,5")]
+        t: Vec<u32>,
+        /// Comma-separated numbers of participants to measure. Pairs whose threshold is
+        /// larger are skipped.
+        #[arg(short, long, value_delimiter = ',', default_value = "3,5,10")]
+        n: Vec<u32>,
+        /// Number of signatures to average the signing steps over.
+        #[arg(short, long, default_value = "10")]
+        rounds: u32,
+    },
+    /// Reconstruct the full group secret key from a threshold of shares. Whoever holds
+    /// it can sign alone, so this ends the threshold security of the group.
+    #[command(group(ArgGroup::new("shares").required(true).args(["share_files", "key_file"])))]
+    Reconstruct {
+        /// Comma-separated participant share files to combine.
+        #[arg(short = 'p', long)]
+        share_files: Option<String>,
+        /// Path to a dealer key file whose shares to combine.
+        #[arg(short, long)]
+        key_file: Option<String>,
+        /// IDs of the participants whose shares in the key file to combine, at least its
+        /// threshold.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Path to save the group secret key.
+        #[arg(short, long, default_value = "./results/group_secret_key.json")]
+        output_file: String,
+        /// Confirm that the reconstructed key can sign without the other participants.
+        #[arg(long)]
+        accept_risk: bool,
+    },
+    /// Coordinate a resumable signing session.
+    Session {
+        #[command(subcommand)]
+        command: SessionCommands,
+    },
+    /// Decode a photographed QR code back into a round message file.
+    QrDecode {
+        /// Path to a PNG or JPEG image of the QR code.
+        #[arg(short, long)]
+        image_file: String,
+        /// Path to save the decoded message file.
+        #[arg(short, long)]
+        output_file: String,
+    },
+    /// Run or talk to an HTTP coordinator for signing sessions.
+    Coordinator {
+        #[command(subcommand)]
+        command: CoordinatorCommands,
+    },
+    /// Run a signer daemon that commits and signs whenever the HTTP coordinator asks.
+    Participant {
+        /// WebSocket URL of the HTTP coordinator, for example `ws://coordinator:8080`.
+        #[arg(short, long)]
+        url: String,
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Directory to keep the per-session secret commitments in.
+        #[arg(short, long, default_value = "./results/participant")]
+        state_dir: String,
+        /// Commit from a commitment pool in the state directory, and generate a new batch
+        /// whenever fewer than this many unused commitments are left.
+        #[arg(long)]
+        min_commitments: Option<u32>,
+        /// Number of commitments generated per batch when the pool runs low.
+        #[arg(long, default_value = "100", requires = "min_commitments")]
+        batch_size: u32,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Manage the named keys in the keystore.
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+    /// Inspect signature files.
+    Sig {
+        #[command(subcommand)]
+        command: SigCommands,
+    },
+    /// Check signing ceremony transcripts.
+    Transcript {
+        #[command(subcommand)]
+        command: TranscriptCommands,
+    },
+    /// Print a shell completion script for subcommands, flags and the names of the keys
+    /// in the keystore.
+    Completions {
+        /// Shell to complete in: `bash`, `zsh`, `fish`, `powershell` or `elvish`.
+        shell: Shell,
+    },
+    /// Manage long-term participant identities.
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommands,
+    },
+    /// Run or use a relay that forwards sealed round messages between participants.
+    Relay {
+        #[command(subcommand)]
+        command: RelayCommands,
+    },
+    /// Run a signer daemon that keeps a share loaded, or sign through a running one.
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommands,
+    },
+    /// Run the authenticated HTTP signing API, which signs with the keystore's shares
+    /// once enough clients approved.
+    Serve {
+        /// Address to serve the HTTP API on, for example `127.0.0.1:8443`.
+        #[arg(long)]
+        http: String,
+        /// Clients allowed to call the API, see `api-client add`.
+        #[arg(long, default_value = "./results/api_clients.json")]
+        clients_file: String,
+        /// Directory to keep the signing sessions in.
+        #[arg(short, long, default_value = "./results/api_sessions")]
+        sessions_dir: String,
+    },
+    /// Manage the clients of the HTTP signing API.
+    ApiClient {
+        #[command(subcommand)]
+        command: ApiClientCommands,
+    },
+    /// Run a TCP mailbox that stores round messages for the participants.
+    Mailbox {
+        /// Address to listen on.
+        #[arg(short, long, default_value = "0.0.0.0:7000")]
+        listen: String,
+        /// Directory to store the uploaded round messages in.
+        #[arg(short, long, default_value = "./results/mailbox")]
+        dir: String,
+    },
+}
+
+/// Options for exchanging round messages with the other peers over libp2p.
+#[derive(Args)]
+struct P2pArgs {
+    /// Exchange round messages with the other peers of this named ceremony over libp2p.
+    #[arg(long)]
+    p2p: Option<String>,
+    /// Multiaddr to listen on in peer-to-peer mode.
+    #[arg(long, default_value = "/ip4/0.0.0.0/tcp/0")]
+    p2p_listen: String,
+    /// Comma-separated multiaddrs of peers to dial in addition to those found with mDNS.
+    #[arg(long, value_delimiter = ',')]
+    p2p_peers: Vec<String>,
+    /// Seconds to keep sharing this participant's round messages once the step is done.
+    #[arg(long, default_value = "30")]
+    p2p_linger: u64,
+}
+
+impl P2pArgs {
+    /// Starts a libp2p node if peer-to-peer mode was requested.
+    fn start(&self) -> Option<p2p::Node> {
+        self.p2p.as_ref().map(|ceremony| {
+            p2p::Node::start(ceremony, &self.p2p_listen, &self.p2p_peers)
+                .expect("Failed to start libp2p node")
+        })
+    }
+
+    /// Returns whether peer-to-peer mode was requested.
+    fn enabled(&self) -> bool {
+        self.p2p.is_some()
+    }
+
+    fn linger(&self, node: Option<p2p::Node>) {
+        if let Some(node) = node {
+            node.linger(Duration::from_secs(self.p2p_linger));
+        }
+    }
+}
+
+/// Options for signing round messages and sealing DKG shares with participant identities.
+#[derive(Args)]
+struct IdentityArgs {
+    /// Sign outgoing round messages, verify incoming ones, and seal and open DKG shares
+    /// with this private identity file.
+    #[arg(long, env = config::IDENTITY_FILE)]
+    identity_file: Option<String>,
+    /// Directory containing the public identities of all participants.
+    #[arg(long, default_value = "./results/identities")]
+    identities_dir: String,
+}
+
+impl IdentityArgs {
+    fn files(&self) -> Option<IdentityFiles> {
+        Some(IdentityFiles {
+            identity_file: self.identity_file.clone()?,
+            identities_dir: self.identities_dir.clone(),
+        })
+    }
+
+    /// Signs an outgoing round message if an identity was given.
+    fn sign(&self, path: &Path) {
+        if let Some(identity_file) = &self.identity_file {
+            identity::sign_message_file(identity_file, path).expect("Failed to sign round message");
+        }
+    }
+
+    /// Returns the directory to check incoming round messages against if an identity
+    /// was given.
+    fn verifying_dir(&self) -> Option<&str> {
+        self.identity_file
+            .as_ref()
+            .map(|_| self.identities_dir.as_str())
+    }
+
+    /// Checks the identity signature on an incoming round message if an identity was given.
+    fn verify(&self, path: &Path) {
+        if let Some(identities_dir) = self.verifying_dir() {
+            identity::verify_message_file(identities_dir, path)
+                .expect("Failed to verify round message");
+        }
+    }
+
+    /// Refuses to carry secret shares over the network unsealed. Anyone who can reach a
+    /// mailbox can download any share by name, and peer-to-peer shares are gossiped to
+    /// every peer, so a single participant could collect the group secret.
+    fn require_sealing(&self, networked: bool) -> Result<(), error::FrostCliError> {
+        if networked && self.identity_file.is_none() {
+            return Err(error::FrostCliError::InvalidParameters(
+                "Secret shares are never sent over --connect or --p2p in plaintext, pass \
+                 --identity-file to seal them"
+                    .into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Seals an outgoing share if an identity was given and returns the file to send.
+    fn seal(&self, share_file: &Path, options: &FileOptions) -> PathBuf {
+        match &self.identity_file {
+            Some(identity_file) => {
+                noise::seal_share(identity_file, &self.identities_dir, share_file, options)
+                    .expect("Failed to seal share")
+            }
+            None => share_file.to_path_buf(),
+        }
+    }
+
+    /// Returns the file a share is sent in.
+    fn wire_file(&self, share_file: &Path) -> PathBuf {
+        match &self.identity_file {
+            Some(_) => noise::sealed_path(share_file).expect("Invalid share file"),
+            None => share_file.to_path_buf(),
+        }
+    }
+
+    /// Opens an incoming sealed share if an identity was given.
+    fn open(&self, share_file: &Path, options: &FileOptions) {
+        if let Some(identity_file) = &self.identity_file {
+            noise::open_share(identity_file, &self.identities_dir, share_file, options)
+                .expect("Failed to open sealed share");
+        }
+    }
+}
+
+/// Options for serving the coordinator with mutual TLS.
+#[derive(Args)]
+struct ServerTlsArgs {
+    /// PEM certificate chain of the server. Enables TLS with client certificates.
+    #[arg(long, requires_all = ["tls_key", "tls_client_ca"])]
+    tls_cert: Option<String>,
+    /// PEM private key of the server.
+    #[arg(long)]
+    tls_key: Option<String>,
+    /// PEM certificate of the CA that issues the participants' client certificates.
+    #[arg(long)]
+    tls_client_ca: Option<String>,
+    /// Directory containing each participant's `participant_<index>.pem` client certificate.
+    #[arg(long, default_value = "./results/tls/participants")]
+    tls_participants_dir: String,
+}
+
+impl ServerTlsArgs {
+    fn tls(&self) -> Option<ServerTls> {
+        Some(ServerTls {
+            cert_file: self.tls_cert.clone()?,
+            key_file: self.tls_key.clone()?,
+            client_ca_file: self.tls_client_ca.clone()?,
+            participants_dir: self.tls_participants_dir.clone(),
+        })
+    }
+}
+
+/// Options for connecting to the coordinator with a client certificate.
+#[derive(Args)]
+struct ClientTlsArgs {
+    /// PEM certificate of the CA that issued the coordinator's certificate.
+    #[arg(long, requires_all = ["tls_cert", "tls_key"])]
+    tls_ca: Option<String>,
+    /// PEM client certificate of this participant.
+    #[arg(long, requires = "tls_ca")]
+    tls_cert: Option<String>,
+    /// PEM private key of the client certificate.
+    #[arg(long, requires = "tls_ca")]
+    tls_key: Option<String>,
+}
+
+impl ClientTlsArgs {
+    fn tls(&self) -> Option<ClientTls> {
+        Some(ClientTls {
+            ca_file: self.tls_ca.clone()?,
+            cert_file: self.tls_cert.clone()?,
+            key_file: self.tls_key.clone()?,
+        })
+    }
+}
+
+/// Enum representing the HTTP and gRPC coordinator commands.
+#[derive(Subcommand)]
+enum CoordinatorCommands {
+    /// Run the coordinator server.
+    Serve {
+        /// Address to listen on.
+        #[arg(short, long, default_value = "0.0.0.0:8080")]
+        listen: String,
+        /// Path to a key file or participant share file of the group.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        key_file: String,
+        /// Directory to keep the session manifests in.
+        #[arg(short, long, default_value = "./results/sessions")]
+        sessions_dir: String,
+        /// Serve the gRPC service instead of the HTTP endpoints.
+        #[arg(long)]
+        grpc: bool,
+        #[command(flatten)]
+        tls: ServerTlsArgs,
+        /// Also serve the Prometheus metrics at /metrics on this address, which the HTTP
+        /// coordinator serves on its own address too.
+        #[arg(long)]
+        metrics_listen: Option<String>,
+    },
+    /// Start a new signing session on a running coordinator.
+    New {
+        /// URL of the coordinator.
+        #[arg(short, long)]
+        url: String,
+        /// The message to sign.
+        #[arg(short, long)]
+        message: String,
+        /// Comma-separated participant indices expected to sign.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Talk to the coordinator over gRPC instead of HTTP.
+        #[arg(long)]
+        grpc: bool,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+    },
+    /// Download the threshold signature of a completed session.
+    Signature {
+        /// URL of the coordinator.
+        #[arg(short, long)]
+        url: String,
+        /// ID of the signing session.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to save the threshold signature.
+        #[arg(short = 'f', long, default_value = "./results/signature.json")]
+        signature_file: String,
+        /// Talk to the coordinator over gRPC instead of HTTP.
+        #[arg(long)]
+        grpc: bool,
+        #[command(flatten)]
+        tls: ClientTlsArgs,
+    },
+}
+
+/// Enum representing the steps of a coordinator's signing session.
+#[derive(Subcommand)]
+enum SessionCommands {
+    /// Start a new signing session.
+    New {
+        /// The message to sign.
+        #[arg(short, long)]
         message: String,
-        /// Path to the JSON file containing the public key.
+        /// Path to a key file or participant share file of the group.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        key_file: String,
+        /// Comma-separated participant indices expected to sign.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Path to save the session manifest.
+        #[arg(short, long, default_value = "./results/session.json")]
+        session_file: String,
+    },
+    /// Add a signer's public commitments to the session.
+    AddCommitments {
+        /// Path to the session manifest.
+        #[arg(short, long, default_value = "./results/session.json")]
+        session_file: String,
+        /// Path to the signer's public commitments.
+        #[arg(short, long)]
+        commitments_file: String,
+    },
+    /// Add a signer's partial signature, aggregating once all have arrived.
+    AddPartial {
+        /// Path to the session manifest.
+        #[arg(short, long, default_value = "./results/session.json")]
+        session_file: String,
+        /// Path to the signer's partial signature.
+        #[arg(short, long)]
+        partial_signature_file: String,
+        /// Path to save the threshold signature once the session completes.
+        #[arg(short = 'f', long, default_value = "./results/signature.json")]
+        signature_file: String,
+    },
+    /// Show the session status and the signers it is waiting for.
+    Status {
+        /// Path to the session manifest.
+        #[arg(short, long, default_value = "./results/session.json")]
+        session_file: String,
+    },
+}
+
+/// Enum representing the rounds of the distributed key generation.
+#[derive(Subcommand)]
+enum DkgCommands {
+    /// Commit to a secret polynomial and publish the round 1 message.
+    Round1 {
+        /// This participant's index, between 1 and n.
+        #[arg(short, long)]
+        index: u32,
+        /// Threshold value for key shares.
+        #[arg(short, long, default_value = "3")]
+        t: u32,
+        /// Total number of participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Path to save this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory to write the public round 1 message to.
+        #[arg(short, long, default_value = "./results/dkg")]
+        output_dir: String,
+        /// Also print the round 1 message as a QR code.
+        #[arg(long)]
+        qr: bool,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Verify all round 1 messages and create secret shares for the others.
+    Round2 {
+        /// Path to this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory containing the round 1 messages of all participants.
+        #[arg(short, long, default_value = "./results/dkg")]
+        round1_dir: String,
+        /// Directory to write the outgoing secret shares to.
+        #[arg(short, long, default_value = "./results/dkg")]
+        output_dir: String,
+        /// Also print every outgoing share as a QR code.
+        #[arg(long)]
+        qr: bool,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        p2p: P2pArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Verify the received shares and derive the private share and group key.
+    Finish {
+        /// Path to this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory containing the secret shares sent to this participant.
+        #[arg(short = 'd', long, default_value = "./results/dkg")]
+        shares_dir: String,
+        /// Path to save this participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        p2p: P2pArgs,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Publish a complaint about every invalid or missing share sent to this participant.
+    Complain {
+        /// Path to this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory containing the secret shares sent to this participant.
+        #[arg(short = 'd', long, default_value = "./results/dkg")]
+        shares_dir: String,
+        /// Directory to write the public complaints to.
+        #[arg(short, long, default_value = "./results/dkg")]
+        output_dir: String,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Reveal the disputed share for every complaint against this participant.
+    Answer {
+        /// Path to this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory containing the complaints of all participants.
+        #[arg(short, long, default_value = "./results/dkg")]
+        complaints_dir: String,
+        /// Directory to write the revealed shares to.
+        #[arg(short, long, default_value = "./results/dkg")]
+        output_dir: String,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Judge the complaints by the revealed shares and finish the DKG, or abort with
+    /// evidence against the participants who cheated.
+    Resolve {
+        /// Path to this participant's secret DKG state.
+        #[arg(short, long, default_value = "./results/dkg_state.json")]
+        state_file: String,
+        /// Directory containing the shares sent to this participant, and the complaints
+        /// and revealed shares of all participants.
+        #[arg(short = 'd', long, default_value = "./results/dkg")]
+        dkg_dir: String,
+        /// Path to save this participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+        /// Finish without the disqualified participants if at least the threshold
+        /// remain, instead of aborting.
+        #[arg(long)]
+        exclude: bool,
+        /// Path to save the evidence against disqualified participants to.
+        #[arg(long, default_value = "./results/dkg_evidence.json")]
+        evidence_file: String,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+}
+
+/// Enum representing the rounds of a proactive share refresh.
+#[derive(Subcommand)]
+enum RefreshCommands {
+    /// Commit to a sharing of zero and create refresh shares for the others.
+    Round1 {
+        /// ID of the refresh announced by the coordinator, a fresh one for every refresh.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to this participant's current share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Path to save this participant's secret refresh state.
+        #[arg(short, long, default_value = "./results/refresh_state.json")]
+        state_file: String,
+        /// Directory to write the refresh message and the outgoing shares to.
+        #[arg(short, long, default_value = "./results/refresh")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Verify the received refresh shares and save the refreshed share.
+    Finish {
+        /// Path to this participant's secret refresh state.
+        #[arg(short, long, default_value = "./results/refresh_state.json")]
+        state_file: String,
+        /// Path to this participant's current share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Directory containing the refresh messages and the shares sent to this
+        /// participant.
+        #[arg(short = 'd', long, default_value = "./results/refresh")]
+        refresh_dir: String,
+        /// Path to save the refreshed share. Defaults to replacing the current share.
+        #[arg(short, long)]
+        output_share_file: Option<String>,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+}
+
+/// Enum representing the ways of resharing a group key.
+#[derive(Subcommand)]
+enum ReshareCommands {
+    /// Reshare the keys of a dealer key file in one process.
+    Keys {
+        /// IDs of the participants in the key file that deal, at least its threshold.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Threshold value of the new keys.
+        #[arg(short, long, default_value = "3")]
+        t: u32,
+        /// Total number of shares of the new keys.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Path to the dealer key file.
+        #[arg(short, long, default_value = "./results/frost_keys.json")]
+        key_file: String,
+        /// Path to save the new keys.
+        #[arg(short, long, default_value = "./results/frost_keys_reshared.json")]
+        output_key_file: String,
+    },
+    /// Deal this participant's share to the new participants.
+    Round1 {
+        /// Path to this participant's current share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all dealers, at least the old threshold of them.
+        #[arg(short, long)]
+        dealers: String,
+        /// Threshold value of the new participant set.
+        #[arg(short, long, default_value = "3")]
+        t: u32,
+        /// Total number of new participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Directory to write the reshare message and the outgoing shares to.
+        #[arg(short, long, default_value = "./results/reshare")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Verify the dealt shares and save the new share.
+    Finish {
+        /// This participant's index in the new participant set.
+        #[arg(short, long)]
+        index: u32,
+        /// Indices of all dealers.
+        #[arg(short, long)]
+        dealers: String,
+        /// Directory containing the reshare messages and the shares sent to this
+        /// participant.
+        #[arg(short, long, default_value = "./results/reshare")]
+        reshare_dir: String,
+        /// Path to save the new share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+}
+
+/// Enum representing the steps of enrolling a new participant.
+#[derive(Subcommand)]
+enum EnrollCommands {
+    /// Enroll a new participant into a dealer key file in one process.
+    Keys {
+        /// IDs of the helpers in the key file, at least its threshold.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Path to the dealer key file.
+        #[arg(short, long, default_value = "./results/frost_keys.json")]
+        key_file: String,
+        /// Path to save the keys with the new share. Defaults to updating the key file.
+        #[arg(short, long)]
+        output_key_file: Option<String>,
+    },
+    /// Split this helper's share into pieces for the other helpers.
+    Round1 {
+        /// Path to this helper's share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all helpers, at least the threshold of them.
+        #[arg(long)]
+        helpers: String,
+        /// Index of the new participant.
+        #[arg(short = 'x', long)]
+        new_index: u32,
+        /// Directory to write the enrollment message and the outgoing pieces to.
+        #[arg(short, long, default_value = "./results/enroll")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Add up the pieces sent to this helper for the new participant.
+    Round2 {
+        /// Path to this helper's share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all helpers.
+        #[arg(long)]
+        helpers: String,
+        /// Index of the new participant.
+        #[arg(short = 'x', long)]
+        new_index: u32,
+        /// Directory containing the pieces sent to this helper.
+        #[arg(short = 'd', long, default_value = "./results/enroll")]
+        enroll_dir: String,
+        /// Directory to write the share for the new participant to.
+        #[arg(short, long, default_value = "./results/enroll")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Check the helpers' shares and save the new participant's share.
+    Finish {
+        /// Index of the new participant.
+        #[arg(short = 'x', long)]
+        new_index: u32,
+        /// Indices of all helpers.
+        #[arg(long)]
+        helpers: String,
+        /// Directory containing the enrollment messages and the shares sent to the new
+        /// participant.
+        #[arg(short = 'd', long, default_value = "./results/enroll")]
+        enroll_dir: String,
+        /// Path to save the new participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Record the new number of participants in an existing participant's share.
+    Update {
+        /// Path to this participant's share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Number of participants after the enrollment.
+        #[arg(short, long)]
+        n: u32,
+    },
+}
+
+/// Enum representing the steps of repairing a lost share.
+#[derive(Subcommand)]
+enum RepairCommands {
+    /// Split this helper's share into pieces for the other helpers.
+    Round1 {
+        /// Path to this helper's share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all helpers, at least the threshold of them.
+        #[arg(long)]
+        helpers: String,
+        /// Index of the participant who lost their share.
+        #[arg(short = 'x', long)]
+        lost_index: u32,
+        /// Directory to write the repair message and the outgoing pieces to.
+        #[arg(short, long, default_value = "./results/repair")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Add up the pieces sent to this helper for the lost share.
+    Round2 {
+        /// Path to this helper's share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all helpers.
+        #[arg(long)]
+        helpers: String,
+        /// Index of the participant who lost their share.
+        #[arg(short = 'x', long)]
+        lost_index: u32,
+        /// Directory containing the pieces sent to this helper.
+        #[arg(short = 'd', long, default_value = "./results/repair")]
+        repair_dir: String,
+        /// Directory to write the share for the participant who lost theirs to.
+        #[arg(short, long, default_value = "./results/repair")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Check the helpers' shares and save the repaired share.
+    Finish {
+        /// This participant's index.
+        #[arg(short = 'x', long)]
+        lost_index: u32,
+        /// Indices of all helpers.
+        #[arg(long)]
+        helpers: String,
+        /// Directory containing the repair messages and the shares sent to this
+        /// participant.
+        #[arg(short = 'd', long, default_value = "./results/repair")]
+        repair_dir: String,
+        /// Path to save the repaired share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+}
+
+/// Enum representing the commitment pool commands.
+#[derive(Subcommand)]
+enum CommitmentsCommands {
+    /// Precompute signing commitments for one participant, taken in order by `commit
+    /// --pool-file`.
+    Generate {
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Number of commitments to add to the pool.
+        #[arg(short, long, default_value = "100")]
+        count: u32,
+        /// Path to the commitment pool, kept locally.
+        #[arg(short = 'o', long, default_value = "./results/commitment_pool.json")]
+        pool_file: String,
+    },
+}
+
+/// Enum representing the steps of revoking participants.
+#[derive(Subcommand)]
+enum RevokeCommands {
+    /// Revoke participants of a dealer key file in one process.
+    Keys {
+        /// IDs of the participants in the key file that deal, at least its threshold.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Indices of the participants to revoke.
+        #[arg(short, long)]
+        revoked: String,
+        /// Path to the dealer key file.
         #[arg(short, long, default_value = "./results/frost_keys.json")]
         key_file: String,
-        /// Path to the JSON file containing the signature.
+        /// Path to save the new keys.
+        #[arg(short, long, default_value = "./results/frost_keys_revoked.json")]
+        output_key_file: String,
+    },
+    /// Deal this participant's share to the participants that remain.
+    Round1 {
+        /// Path to this participant's current share.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json")]
+        share_file: String,
+        /// Indices of all dealers, at least the threshold of them.
+        #[arg(short, long)]
+        dealers: String,
+        /// Indices of the participants to revoke.
+        #[arg(short, long)]
+        revoked: String,
+        /// Directory to write the reshare message and the outgoing shares to. The
+        /// remaining participants finish with `reshare finish`.
+        #[arg(short, long, default_value = "./results/reshare")]
+        output_dir: String,
+        /// Exchange round messages through the mailbox at this address.
+        #[arg(long)]
+        connect: Option<String>,
+        #[command(flatten)]
+        identity: IdentityArgs,
+    },
+    /// Write the revocation statement for the group to sign.
+    Statement {
+        /// Path to a key file or participant share file of the group.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        key_file: String,
+        /// Indices of the revoked participants.
+        #[arg(short, long)]
+        revoked: String,
+        /// Path to save the statement.
+        #[arg(short, long, default_value = "./results/revocation.json")]
+        output_file: String,
+    },
+    /// Record a signed revocation statement next to the key file.
+    Record {
+        /// Path to a key file or participant share file of the group.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        key_file: String,
+        /// Path to the revocation statement.
+        #[arg(short = 't', long, default_value = "./results/revocation.json")]
+        statement_file: String,
+        /// Path to the group's signature on the statement.
+        #[arg(short, long, default_value = "./results/signature.json")]
+        signature_file: String,
+    },
+}
+
+/// Enum representing the steps of rotating to a new group key.
+#[derive(Subcommand)]
+enum RotateCommands {
+    /// Rotate a dealer key file to new keys in one process.
+    Keys {
+        /// IDs of the old participants in the key file that sign the statement.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Threshold of the new keys.
+        #[arg(short, default_value = "3")]
+        t: u32,
+        /// Number of participants of the new keys.
+        #[arg(short, default_value = "5")]
+        n: u32,
+        /// Path to the old dealer key file.
+        #[arg(short, long, default_value = "./results/frost_keys.json")]
+        key_file: String,
+        /// Name of the old keys in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Path to save the new keys, in a directory of their own.
+        #[arg(short, long, default_value = "./results/rotated/frost_keys.json")]
+        output_key_file: String,
+        /// Save the new keys under this name in the keystore instead of the output key
+        /// file.
+        #[arg(long)]
+        new_key: Option<String>,
+        /// Unix time until which signatures by the old key are accepted.
+        #[arg(long)]
+        valid_until: Option<u64>,
+    },
+    /// Write the rotation statement for the old group to sign.
+    Statement {
+        /// Path to a key file or participant share file of the old group.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        key_file: String,
+        /// Path to a key file or participant share file of the new group.
+        #[arg(long)]
+        new_key_file: String,
+        /// Unix time until which signatures by the old key are accepted.
+        #[arg(long)]
+        valid_until: Option<u64>,
+        /// Path to save the statement.
+        #[arg(short, long, default_value = "./results/rotation.json")]
+        output_file: String,
+    },
+    /// Record a rotation statement signed by the old group next to the new key file.
+    Record {
+        /// Path to a key file or participant share file of the old group.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        key_file: String,
+        /// Path to a key file or participant share file of the new group.
+        #[arg(long)]
+        new_key_file: String,
+        /// Path to the rotation statement.
+        #[arg(short = 't', long, default_value = "./results/rotation.json")]
+        statement_file: String,
+        /// Path to the old group's signature on the statement.
         #[arg(short, long, default_value = "./results/signature.json")]
         signature_file: String,
     },
+    /// Show the rotations that led to a group key, after checking them.
+    History {
+        /// Path to a key file or participant share file.
+        #[arg(short, long, default_value = "./results/frost_keys.json")]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+    },
+}
+
+/// Enum representing the relay commands.
+#[derive(Subcommand)]
+enum RelayCommands {
+    /// Run the relay.
+    Serve {
+        /// Address to listen on.
+        #[arg(short, long, default_value = "0.0.0.0:7100")]
+        listen: String,
+    },
+    /// Seal a round message file to another participant and send it through the relay.
+    Send {
+        /// Address of the relay.
+        #[arg(short, long)]
+        connect: String,
+        /// ID of the signing session or DKG ceremony the message belongs to.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to this participant's private identity.
+        #[arg(long, default_value = "./results/identity.json", env = config::IDENTITY_FILE)]
+        identity_file: String,
+        /// Directory containing the public identities of all participants.
+        #[arg(long, default_value = "./results/identities")]
+        identities_dir: String,
+        /// Index of the receiving participant.
+        #[arg(short, long)]
+        to: u32,
+        /// Round message file to send.
+        #[arg(short, long)]
+        file: String,
+    },
+    /// Wait for a round message from another participant and open it.
+    Receive {
+        /// Address of the relay.
+        #[arg(short, long)]
+        connect: String,
+        /// ID of the signing session or DKG ceremony the message belongs to.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to this participant's private identity.
+        #[arg(long, default_value = "./results/identity.json", env = config::IDENTITY_FILE)]
+        identity_file: String,
+        /// Directory containing the public identities of all participants.
+        #[arg(long, default_value = "./results/identities")]
+        identities_dir: String,
+        /// Index of the sending participant.
+        #[arg(short = 'r', long)]
+        from: u32,
+        /// Path to save the round message to. Must have the file name the sender used.
+        #[arg(short, long)]
+        file: String,
+    },
+}
+
+/// Enum representing the participant identity commands.
+#[derive(Subcommand)]
+enum IdentityCommands {
+    /// Create a new identity for a participant.
+    New {
+        /// This participant's index.
+        #[arg(short, long)]
+        index: u32,
+        /// Path to save this participant's private identity.
+        #[arg(short = 'f', long, default_value = "./results/identity.json", env = config::IDENTITY_FILE)]
+        identity_file: String,
+        /// Directory to write the public identity to.
+        #[arg(short = 'd', long, default_value = "./results/identities")]
+        identities_dir: String,
+    },
+    /// Collect the participants' public keys into a roster file for generate.
+    Roster {
+        /// Total number of participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Directory containing the public identities of all participants.
+        #[arg(short = 'd', long, default_value = "./results/identities")]
+        identities_dir: String,
+        /// Path to save the roster.
+        #[arg(short, long, default_value = "./results/roster.json")]
+        roster_file: String,
+    },
+}
+
+/// Enum representing the keystore subcommands.
+#[derive(Subcommand)]
+enum KeyCommands {
+    /// List the keys in the keystore.
+    List,
+    /// Copy a key file or participant share file into the keystore.
+    Import {
+        /// Name to store the key under.
+        name: String,
+        /// Path to the key file or participant share file.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+    },
+    /// Print what a key holds: its group key, threshold, participants and whether it
+    /// holds private shares, without any secret material.
+    Inspect {
+        /// Name of the key in the keystore, instead of the key file.
+        name: Option<String>,
+        /// Path to the key file, participant share file, share bundle or PEM group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+    },
+    /// Delete a key from the keystore.
+    Delete {
+        /// Name of the key to delete.
+        name: String,
+    },
+}
+
+/// Enum representing the signature file subcommands.
+#[derive(Subcommand)]
+enum SigCommands {
+    /// Print what a signature file holds: its format, the signature's R and s
+    /// components, its metadata and whether it parses as a threshold signature, without
+    /// verifying it.
+    Inspect {
+        /// Path to the signature file or envelope.
+        #[arg(short, long, default_value = "./results/signature.json")]
+        signature_file: String,
+        /// Format the signature was saved in: `raw`, `hex`, `base64`, `pem` or `json`.
+        /// Detected from the signature file if not given.
+        #[arg(long)]
+        sig_format: Option<SignatureFormat>,
+    },
+}
+
+/// Enum representing the signer daemon subcommands.
+#[derive(Subcommand)]
+enum DaemonCommands {
+    /// Load a participant share once and answer commit and partial-sign requests on a
+    /// Unix domain socket until stopped.
+    Serve {
+        /// Path of the socket to listen on.
+        #[arg(long, default_value = "./results/frost-daemon.sock", env = config::DAEMON_SOCKET)]
+        socket: String,
+        /// Path to this participant's share file.
+        #[arg(short = 'p', long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Serve the Prometheus metrics at /metrics on this address.
+        #[arg(long)]
+        metrics_listen: Option<String>,
+    },
+    /// Run signing round one on the daemon, which keeps the secret nonces in memory.
+    Commit {
+        /// Path of the daemon's socket.
+        #[arg(long, default_value = "./results/frost-daemon.sock", env = config::DAEMON_SOCKET)]
+        socket: String,
+        /// ID of the signing session announced by the coordinator.
+        #[arg(short = 'e', long)]
+        session_id: String,
+        /// Path to save the public commitments for the coordinator.
+        #[arg(short = 'c', long, default_value = "./results/public_commitments.json")]
+        public_commitments_file: String,
+    },
+    /// Run signing round two on the daemon with the nonces it committed to.
+    PartialSign {
+        /// Path of the daemon's socket.
+        #[arg(long, default_value = "./results/frost-daemon.sock", env = config::DAEMON_SOCKET)]
+        socket: String,
+        /// The message to sign.
+        #[arg(short, long)]
+        message: String,
+        /// Comma-separated public commitment files of every signer, including this one.
+        #[arg(short, long, value_delimiter = ',', required = true)]
+        commitment_files: Vec<String>,
+        /// Path to save the partial signature for the coordinator.
+        #[arg(short, long, default_value = "./results/partial_signature.json")]
+        output_file: String,
+    },
+}
+
+/// Enum representing the HTTP signing API client subcommands.
+#[derive(Subcommand)]
+enum ApiClientCommands {
+    /// Add a client and print its bearer token, which is shown only once.
+    Add {
+        /// Name of the client, recorded with its approvals.
+        #[arg(long)]
+        name: String,
+        /// Path to the clients file.
+        #[arg(long, default_value = "./results/api_clients.json")]
+        clients_file: String,
+        /// Allow the client to create signing sessions.
+        #[arg(long)]
+        create_sessions: bool,
+        /// Name of the participant share in the keystore the client approves with.
+        #[arg(long)]
+        share: Option<String>,
+    },
+}
+
+/// Enum representing the ceremony transcript subcommands.
+#[derive(Subcommand)]
+enum TranscriptCommands {
+    /// Check that no entry of a transcript was removed, changed or inserted, and that
+    /// every ristretto255 signature it records verifies.
+    Check {
+        /// Path to the transcript.
+        #[arg(short, long)]
+        transcript_file: String,
+    },
+}
+
+/// Enum representing the TUF metadata subcommands.
+#[derive(Subcommand)]
+enum TufCommands {
+    /// Threshold-sign TUF metadata and add the signature under the group's key ID.
+    Sign {
+        /// Path to the TUF metadata, such as `root.json` or `targets.json`.
+        #[arg(short, long)]
+        metadata_file: String,
+        /// IDs of the participants signing, from 1.
+        #[arg(short = 'i', long, value_delimiter = ',', default_value = "1,2,3")]
+        signers: Vec<ParticipantId>,
+        /// Total number of participants.
+        #[arg(short, long, default_value = "5")]
+        n: u32,
+        /// Path to the key file of an Ed25519 group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the keys in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+        /// Path to save the signed metadata. Without it, the metadata file is updated.
+        #[arg(short, long)]
+        output_file: Option<String>,
+    },
+    /// Check the group's signature on TUF metadata.
+    Verify {
+        /// Path to the signed TUF metadata.
+        #[arg(short, long)]
+        metadata_file: String,
+        /// Path to the key file, participant share file or PEM group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// Print the group key as a `keys` entry of TUF root metadata.
+    Key {
+        /// Path to the key file, participant share file or PEM group key.
+        #[arg(short, long, default_value = "./results/frost_keys.json", env = config::KEY_FILE)]
+        key_file: String,
+        /// Name of the key in the keystore to use instead of the key file.
+        #[arg(long)]
+        key: Option<String>,
+    },
+}
+
+/// Enum representing the share backup and verification subcommands.
+#[derive(Subcommand)]
+enum ShareCommands {
+    /// Print a participant's share in a human-transcribable format.
+    #[command(group(ArgGroup::new("format").required(true).args(["mnemonic"])))]
+    Export {
+        /// Path to this participant's share file.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Print the share as a 24-word BIP39 mnemonic.
+        #[arg(long)]
+        mnemonic: bool,
+    },
+    /// Print a participant's share as a backup to be written down or typed back in.
+    #[command(group(ArgGroup::new("format").required(true).args(["paper"])))]
+    Backup {
+        /// Path to this participant's share file.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Print the share in numbered base32 lines with a CRC per line.
+        #[arg(long)]
+        paper: bool,
+    },
+    /// Restore a participant's share from a typed-in paper backup.
+    Recover {
+        /// Path to the typed-in paper backup.
+        #[arg(short, long)]
+        backup_file: String,
+        /// Path to save this participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+    },
+    /// Check that a participant's share is consistent with its group key.
+    Verify {
+        /// Path to this participant's share file.
+        #[arg(short, long, default_value = "./results/participant_share.json", env = config::SHARE_FILE)]
+        share_file: String,
+        /// Comma-separated share files of other participants to sign a trial message
+        /// with, so that the share is checked against the group key.
+        #[arg(short = 'w', long)]
+        with_shares: Option<String>,
+    },
+    /// Restore a participant's share from an exported backup.
+    Import {
+        /// The 24 words printed by export --mnemonic.
+        #[arg(long)]
+        mnemonic: String,
+        /// This participant's index.
+        #[arg(short, long)]
+        index: u32,
+        /// Hex-encoded group public key, printed by export.
+        #[arg(short, long)]
+        group_key: String,
+        /// Threshold value of the group.
+        #[arg(short, long)]
+        t: u32,
+        /// Total number of participants in the group.
+        #[arg(short, long)]
+        n: u32,
+        /// Path to save this participant's share.
+        #[arg(short, long, default_value = "./results/participant_share.json")]
+        output_share_file: String,
+    },
 }
 
 fn main() {
     let cli = Cli::parse();
+    if cli.mlock {
+        memlock::enable();
+    }
+    output::set_output_mode(cli.output);
+    // Diagnostics go to stderr, keeping stdout for the command results
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::INFO,
+        (false, 1) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(level)
+        .with_target(false)
+        .without_time()
+        .init();
+    if cli.output == OutputMode::Json {
+        // Commands fail by panicking with the error, so report it as a result as well
+        std::panic::set_hook(Box::new(|info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<String>()
+                .map(String::as_str)
+                .or_else(|| payload.downcast_ref::<&str>().copied())
+                .unwrap_or("Command failed");
+            output::report("error", message, json!({ "ok": false }));
+        }));
+    }
+    let store = cli.store;
+    let context = cli.context.as_deref().unwrap_or(SIGNING_CONTEXT);
+    let transcript_file = cli.transcript.as_deref();
+    let file_options = FileOptions {
+        format: cli.format,
+        file_key: config::var(config::FILE_KEY)
+            .expect("Failed to read the file key")
+            .map(FileKey::new),
+    };
+    let keystore =
+        || keystore::keystore_dir(cli.keystore.as_deref()).expect("Failed to find the keystore");
 
     match &cli.command {
         Commands::Generate {
             t,
             n,
             output_key_file,
+            key,
+            roster_file,
+            bundles_dir,
+            ciphersuite,
+            shares_dir,
+            seed,
+        } => {
+            if let Some(roster_file) = roster_file {
+                if !ciphersuite.is_ristretto255() {
+                    panic!("Encrypted share bundles only support the ristretto255 ciphersuite");
+                }
+                dealer::generate_encrypted_shares(*t, *n, roster_file, bundles_dir, &file_options)
+                    .expect("Failed to generate keys");
+            } else if let Some(shares_dir) = shares_dir {
+                if !ciphersuite.is_ristretto255() {
+                    panic!("Share files only support the ristretto255 ciphersuite");
+                }
+                let bar = keygen_progress_bar(*n, cli.quiet);
+                generate_share_files(
+                    *t,
+                    *n,
+                    shares_dir,
+                    &|progress| {
+                        bar.set_message(progress.stage.to_string());
+                        bar.set_length(u64::from(progress.total));
+                        bar.set_position(u64::from(progress.done));
+                    },
+                    &file_options,
+                )
+                .expect("Failed to generate keys");
+                bar.finish_and_clear();
+            } else {
+                let output_key_file = match key {
+                    Some(key) => keystore::new_key_file(&keystore(), key)
+                        .expect("Failed to create key in the keystore"),
+                    None => output_key_file.clone(),
+                };
+                let bar = keygen_progress_bar(*n, cli.quiet);
+                let progress = |progress: KeygenProgress| {
+                    bar.set_message(progress.stage.to_string());
+                    bar.set_length(u64::from(progress.total));
+                    bar.set_position(u64::from(progress.done));
+                };
+                match seed {
+                    Some(seed) => generate_keys_from_seed(
+                        *t,
+                        *n,
+                        &output_key_file,
+                        *ciphersuite,
+                        seed.as_bytes(),
+                        &progress,
+                        &file_options,
+                    ),
+                    None => generate_keys_with_progress(
+                        *t,
+                        *n,
+                        &output_key_file,
+                        *ciphersuite,
+                        &progress,
+                        &file_options,
+                    ),
+                }
+                .expect("Failed to generate keys");
+                bar.finish_and_clear();
+            }
+        }
+        Commands::DecryptShare {
+            identity_file,
+            bundle_file,
+            output_share_file,
         } => {
-            generate_keys(*t, *n, output_key_file).expect("Failed to generate keys");
+            let output_share_file = &store.location(output_share_file);
+            dealer::decrypt_share(identity_file, bundle_file, output_share_file, &file_options)
+                .expect("Failed to decrypt share");
         }
         Commands::Sign {
             message,
+            file,
+            manifest,
             signers,
             n,
             key_file,
+            key,
             signature_file,
+            sig_format,
+            out_format,
+            cosign_bundle,
+            namespace,
+            payload_type,
+            canonicalize,
+            share_files,
+            tsa_url,
+            seed,
         } => {
-            let signers: Vec<u32> = signers
-                .split(',')
-                .map(|s| s.parse().expect("Invalid signer index"))
-                .collect();
-            sign_message(message, signers, *n, key_file, signature_file)
+            let out_format = if *cosign_bundle {
+                &OutFormat::CosignBundle
+            } else {
+                out_format
+            };
+            let options = EnvelopeOptions {
+                namespace: namespace.clone(),
+                payload_type: payload_type.clone(),
+                signature_format: *sig_format,
+                files: file_options.clone(),
+                canonicalization: *canonicalize,
+            };
+            if (file.is_some() || manifest.is_some()) && *out_format != OutFormat::Signature {
+                panic!("Files and manifests are only signed as plain signatures");
+            }
+            let message = message.as_deref().unwrap_or_default();
+            if let Some(share_files) = share_files {
+                if *out_format != OutFormat::Signature {
+                    panic!("Signing with share files only saves plain signatures");
+                }
+                let share_files: Vec<String> =
+                    share_files.split(',').map(|s| store.location(s)).collect();
+                sign_message_with_shares_as(
+                    message,
+                    context,
+                    &share_files,
+                    signature_file,
+                    &options,
+                    transcript_file,
+                )
                 .expect("Failed to sign message");
+            } else {
+                let key_file = match key {
+                    Some(key) => keystore::dealer_key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let signers = signing::signer_positions(&key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                match (file, manifest) {
+                    (Some(file), _) => sign_file(
+                        file,
+                        context,
+                        signers,
+                        *n,
+                        &key_file,
+                        signature_file,
+                        *sig_format,
+                        &file_options,
+                        transcript_file,
+                    )
+                    .expect("Failed to sign file"),
+                    (_, Some(manifest_file)) => manifest::sign_manifest(
+                        manifest_file,
+                        context,
+                        signers,
+                        *n,
+                        &key_file,
+                        &options,
+                        transcript_file,
+                    )
+                    .expect("Failed to sign manifest"),
+                    (None, None) => sign_message_as_with_rng(
+                        message,
+                        context,
+                        signers,
+                        *n,
+                        &key_file,
+                        signature_file,
+                        *out_format,
+                        &options,
+                        transcript_file,
+                        &mut *nonce_rng(seed.as_deref()),
+                    )
+                    .expect("Failed to sign message"),
+                }
+            }
+            if let Some(tsa_url) = tsa_url {
+                timestamp::timestamp_signature(signature_file, tsa_url)
+                    .expect("Failed to timestamp signature");
+            }
         }
         Commands::Verify {
             message,
+            file,
+            manifest,
+            key_file,
+            key,
+            signature_file,
+            sig_format,
+            namespace,
+            payload_type,
+            canonicalize,
+            tsa_cert,
+        } => {
+            if cli.quiet {
+                // Scripts only read the exit code
+                output::set_output_mode(OutputMode::None);
+            }
+            let options = EnvelopeOptions {
+                namespace: namespace.clone(),
+                payload_type: payload_type.clone(),
+                files: file_options.clone(),
+                canonicalization: *canonicalize,
+                ..Default::default()
+            };
+            let key_file = match key {
+                Some(key) => keystore::key_file(&keystore(), key).unwrap_or_else(|err| {
+                    verification_failed("Failed to find key in the keystore", err, cli.quiet)
+                }),
+                None => key_file.clone(),
+            };
+            let result = match (file, manifest) {
+                (Some(file), _) => validate_file_signature(
+                    file,
+                    context,
+                    &key_file,
+                    signature_file,
+                    *sig_format,
+                    &file_options,
+                ),
+                (_, Some(manifest_file)) => manifest::verify_manifest(
+                    manifest_file,
+                    context,
+                    &key_file,
+                    *sig_format,
+                    &options,
+                ),
+                (None, None) => validate_signature_as(
+                    message.as_deref().unwrap_or_default(),
+                    context,
+                    &key_file,
+                    signature_file,
+                    *sig_format,
+                    &options,
+                ),
+            };
+            if let Err(err) = result {
+                verification_failed("Failed to verify signature", err, cli.quiet);
+            }
+            if let Some(tsa_cert) = tsa_cert {
+                if let Err(err) = timestamp::verify_signature_timestamp(signature_file, tsa_cert) {
+                    verification_failed("Failed to verify timestamp", err, cli.quiet);
+                }
+            }
+        }
+        Commands::Attest {
+            subjects,
+            builder_id,
+            build_type,
+            predicate_file,
+            predicate_type,
+            signers,
+            n,
+            key_file,
+            key,
+            output_file,
+            statement_file,
+        } => {
+            let subjects: Vec<String> = subjects.split(',').map(str::to_string).collect();
+            let predicate = match (builder_id, build_type, predicate_file, predicate_type) {
+                (Some(builder_id), Some(build_type), _, _) => Predicate::SlsaProvenance {
+                    builder_id: builder_id.clone(),
+                    build_type: build_type.clone(),
+                },
+                (_, _, Some(predicate_file), Some(predicate_type)) => Predicate::Custom {
+                    predicate_type: predicate_type.clone(),
+                    predicate_file: predicate_file.clone(),
+                },
+                _ => unreachable!("clap requires a complete predicate"),
+            };
+            let key_file = match key {
+                Some(key) => keystore::dealer_key_file(&keystore(), key)
+                    .expect("Failed to find key in the keystore"),
+                None => key_file.clone(),
+            };
+            let signers = signing::signer_positions(&key_file, signers, &file_options)
+                .expect("Invalid signers");
+            attest::attest(
+                &subjects,
+                &predicate,
+                context,
+                signers,
+                *n,
+                &key_file,
+                output_file,
+                statement_file.as_deref(),
+                transcript_file,
+            )
+            .expect("Failed to attest");
+        }
+        Commands::GitSign {
+            signers,
+            n,
             key_file,
+            key,
+            gpg_args,
+        } => {
+            let key_file = match key {
+                Some(key) => keystore::dealer_key_file(&keystore(), key)
+                    .expect("Failed to find key in the keystore"),
+                None => key_file.clone(),
+            };
+            let signers = signing::signer_positions(&key_file, signers, &file_options)
+                .expect("Invalid signers");
+            git::run(
+                gpg_args,
+                &key_file,
+                signers,
+                *n,
+                transcript_file,
+                std::io::stdin().lock(),
+                std::io::stdout().lock(),
+                std::io::stderr().lock(),
+                &file_options,
+            )
+            .expect("Failed to answer git");
+        }
+        Commands::Tuf { command } => match command {
+            TufCommands::Sign {
+                metadata_file,
+                signers,
+                n,
+                key_file,
+                key,
+                output_file,
+            } => {
+                let key_file = match key {
+                    Some(key) => keystore::dealer_key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let signers = signing::signer_positions(&key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                tuf::sign_metadata(
+                    metadata_file,
+                    signers,
+                    *n,
+                    &key_file,
+                    output_file.as_deref(),
+                    transcript_file,
+                    &file_options,
+                )
+                .expect("Failed to sign TUF metadata");
+            }
+            TufCommands::Verify {
+                metadata_file,
+                key_file,
+                key,
+            } => {
+                let key_file = match key {
+                    Some(key) => keystore::key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                tuf::verify_metadata(metadata_file, &key_file, &file_options)
+                    .expect("Failed to verify TUF metadata");
+            }
+            TufCommands::Key { key_file, key } => {
+                let key_file = match key {
+                    Some(key) => keystore::key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let tuf_key = tuf::export_key(&key_file, &file_options)
+                    .expect("Failed to export the TUF key");
+                output::report(
+                    "tuf_key",
+                    tuf_key.trim_end(),
+                    json!({ "key": serde_json::from_str::<serde_json::Value>(&tuf_key).ok() }),
+                );
+            }
+        },
+        Commands::GroupKey {
+            key_file,
+            key,
+            pem,
+            ssh,
+            pkix,
+            cose,
+            output_file,
+        } => {
+            let key_file = match key {
+                Some(key) => keystore::key_file(&keystore(), key)
+                    .expect("Failed to find key in the keystore"),
+                None => key_file.clone(),
+            };
+            let group_key = if *ssh {
+                export_ssh_public_key(&key_file, &file_options)
+            } else if *pkix {
+                export_pkix_public_key(&key_file, &file_options)
+            } else if *cose {
+                export_cose_key(&key_file, &file_options)
+            } else {
+                export_group_key(&key_file, *pem, &file_options)
+            }
+            .expect("Failed to export the group key");
+            match output_file {
+                Some(output_file) => {
+                    std::fs::write(output_file, group_key).expect("Failed to save the group key");
+                    output::report(
+                        "group_key_saved",
+                        &format!("Group key saved to: {}", output_file),
+                        json!({ "output_file": output_file }),
+                    );
+                }
+                None => output::report(
+                    "group_key",
+                    group_key.trim_end(),
+                    json!({ "group_key": group_key.trim_end() }),
+                ),
+            }
+        }
+        Commands::Testvectors {
+            ciphersuite,
+            vectors_file,
+        } => {
+            backend::check_test_vectors(*ciphersuite, vectors_file)
+                .expect("Failed to check the test vectors");
+        }
+        Commands::Check {
+            key_file,
+            key,
             signature_file,
+            sig_format,
+        } => {
+            let key_file = match key {
+                Some(key) => keystore::key_file(&keystore(), key)
+                    .expect("Failed to find key in the keystore"),
+                None => key_file.clone(),
+            };
+            let report = check::check_compatibility(
+                &key_file,
+                signature_file.as_deref(),
+                *sig_format,
+                context,
+                &file_options,
+            );
+            let mut fields = serde_json::to_value(&report).expect("Failed to check compatibility");
+            fields["compatible"] = json!(report.is_compatible());
+            output::report("compatibility_checked", &report.to_string(), fields);
+            if !report.is_compatible() {
+                std::process::exit(1);
+            }
+        }
+        Commands::Migrate { file, output_file } => {
+            migrate::migrate(file, output_file.as_deref(), &file_options)
+                .expect("Failed to migrate file");
+        }
+        Commands::Commit {
+            session_id,
+            share_file,
+            public_commitments_file,
+            secret_commitments_file,
+            pool_file,
+            count,
+            seed,
+            qr,
+            connect,
+            coordinator_url,
+            grpc,
+            tls,
+            identity,
         } => {
-            validate_signature(message, key_file, signature_file)
-                .expect("Failed to verify signature");
+            let share_file = &store.location(share_file);
+            match pool_file {
+                Some(pool_file) => signing::commit_from_pool(
+                    session_id,
+                    share_file,
+                    pool_file,
+                    public_commitments_file,
+                    secret_commitments_file,
+                    &file_options,
+                ),
+                None => signing::commit(
+                    session_id,
+                    share_file,
+                    public_commitments_file,
+                    secret_commitments_file,
+                    CommitOptions {
+                        count: *count,
+                        rng: Some(&mut *nonce_rng(seed.as_deref())),
+                        files: file_options.clone(),
+                    },
+                ),
+            }
+            .expect("Failed to create commitments");
+            identity.sign(Path::new(public_commitments_file));
+            if *qr {
+                qr::print_file(Path::new(public_commitments_file))
+                    .expect("Failed to print QR code");
+            }
+            if let Some(connect) = connect {
+                tcp::upload(connect, public_commitments_file)
+                    .expect("Failed to upload commitments");
+            }
+            if let Some(url) = coordinator_url {
+                let submit_commitments_file = if *grpc {
+                    grpc::submit_commitments_file
+                } else {
+                    http::submit_commitments_file
+                };
+                submit_commitments_file(url, public_commitments_file, tls.tls().as_ref())
+                    .expect("Failed to send commitments");
+            }
         }
+        Commands::Commitments { command } => match command {
+            CommitmentsCommands::Generate {
+                share_file,
+                count,
+                pool_file,
+            } => {
+                let share_file = &store.location(share_file);
+                signing::generate_commitments(share_file, *count, pool_file, &file_options)
+                    .expect("Failed to generate commitments");
+            }
+        },
+        Commands::Echo {
+            message,
+            share_file,
+            commitment_files,
+            session_file,
+            coordinator_url,
+            session_id,
+            grpc,
+            output_file,
+            connect,
+            tls,
+            p2p,
+            identity,
+        } => {
+            let share_file = &store.location(share_file);
+            let roster = if let Some(url) = coordinator_url {
+                let fetch_session = if *grpc {
+                    grpc::fetch_session
+                } else {
+                    http::fetch_session
+                };
+                fetch_session(
+                    url,
+                    session_id.as_deref().unwrap_or_default(),
+                    tls.tls().as_ref(),
+                )
+                .expect("Failed to fetch session")
+                .commitments
+            } else if let Some(session_file) = session_file {
+                session::load_session(session_file)
+                    .expect("Failed to load session")
+                    .commitments
+            } else {
+                let commitment_files: Vec<String> = commitment_files
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.to_string())
+                    .collect();
+                signing::load_roster(&commitment_files).expect("Failed to load commitments")
+            };
+            echo::write_echo(
+                message,
+                context,
+                share_file,
+                &roster,
+                output_file,
+                &file_options,
+            )
+            .expect("Failed to create echo");
+            identity.sign(Path::new(output_file));
+            let share =
+                load_participant_share(share_file, &file_options).expect("Failed to load share");
+            if let Some(connect) = connect {
+                tcp::upload_as(connect, &echo_name(share.index), output_file)
+                    .expect("Failed to upload echo");
+            }
+            let node = p2p.start();
+            if let Some(node) = &node {
+                node.publish_as(&echo_name(share.index), output_file)
+                    .expect("Failed to publish echo");
+            }
+            p2p.linger(node);
+        }
+        Commands::PartialSign {
+            message,
+            share_file,
+            secret_commitments_file,
+            commitment_files,
+            session_file,
+            output_file,
+            commitment_index,
+            echo_files,
+            qr,
+            connect,
+            coordinator_url,
+            grpc,
+            tls,
+            p2p,
+            identity,
+        } => {
+            let share_file = &store.location(share_file);
+            let echo_files: Vec<String> = echo_files
+                .iter()
+                .flat_map(|files| files.split(','))
+                .map(|s| s.to_string())
+                .collect();
+            if let Some(connect) = connect {
+                for commitment_file in commitment_files.iter().flat_map(|files| files.split(',')) {
+                    tcp::download(connect, commitment_file, &file_options)
+                        .expect("Failed to download commitments");
+                }
+                for echo_file in &echo_files {
+                    tcp::download(connect, echo_file, &file_options)
+                        .expect("Failed to download echo");
+                }
+            }
+            let node = p2p.start();
+            if let Some(node) = &node {
+                let files: Vec<PathBuf> = commitment_files
+                    .iter()
+                    .flat_map(|files| files.split(','))
+                    .chain(echo_files.iter().map(String::as_str))
+                    .map(PathBuf::from)
+                    .collect();
+                exchange_with_peers(node, &files, &file_options);
+            }
+            if let Some(url) = coordinator_url {
+                let partial_sign_remote = if *grpc {
+                    grpc::partial_sign_remote
+                } else {
+                    http::partial_sign_remote
+                };
+                partial_sign_remote(
+                    url,
+                    message,
+                    context,
+                    share_file,
+                    secret_commitments_file,
+                    output_file,
+                    tls.tls().as_ref(),
+                    identity.files().as_ref(),
+                    &echo_files,
+                    &file_options,
+                )
+                .expect("Failed to create partial signature");
+            } else if let Some(session_file) = session_file {
+                session::partial_sign(
+                    message,
+                    context,
+                    share_file,
+                    secret_commitments_file,
+                    session_file,
+                    output_file,
+                    identity.files().as_ref(),
+                    &echo_files,
+                    &file_options,
+                )
+                .expect("Failed to create partial signature");
+            } else {
+                let commitment_files: Vec<String> = commitment_files
+                    .as_deref()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|s| s.to_string())
+                    .collect();
+                for commitment_file in &commitment_files {
+                    identity.verify(Path::new(commitment_file));
+                }
+                if !echo_files.is_empty() {
+                    let roster = signing::load_roster(&commitment_files)
+                        .expect("Failed to load commitments");
+                    echo::check_echoes(
+                        message,
+                        context,
+                        share_file,
+                        &roster,
+                        &echo_files,
+                        identity.verifying_dir(),
+                        &file_options,
+                    )
+                    .expect("Echo check failed");
+                }
+                signing::partial_sign_at(
+                    message,
+                    context,
+                    share_file,
+                    secret_commitments_file,
+                    &commitment_files,
+                    *commitment_index,
+                    output_file,
+                    &file_options,
+                )
+                .expect("Failed to create partial signature");
+                identity.sign(Path::new(output_file));
+            }
+            if *qr {
+                qr::print_file(Path::new(output_file)).expect("Failed to print QR code");
+            }
+            if let Some(connect) = connect {
+                let share = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share");
+                tcp::upload_as(connect, &partial_signature_name(share.index), output_file)
+                    .expect("Failed to upload partial signature");
+            }
+            if let Some(node) = &node {
+                let share = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share");
+                node.publish_as(&partial_signature_name(share.index), output_file)
+                    .expect("Failed to publish partial signature");
+            }
+            p2p.linger(node);
+        }
+        Commands::Aggregate {
+            message,
+            key_file,
+            commitment_files,
+            partial_signatures_dir,
+            commitment_index,
+            signature_file,
+            sig_format,
+            connect,
+            p2p,
+            identities_dir,
+        } => {
+            let commitment_files: Vec<String> =
+                commitment_files.split(',').map(|s| s.to_string()).collect();
+            if let Some(node) = p2p.start() {
+                let files: Vec<PathBuf> = commitment_files.iter().map(PathBuf::from).collect();
+                exchange_with_peers(&node, &files, &file_options);
+                let signers =
+                    signing::roster_indices(&commitment_files).expect("Failed to load commitments");
+                for index in signers {
+                    let partial_file =
+                        Path::new(partial_signatures_dir).join(partial_signature_name(index));
+                    if !partial_file.exists() {
+                        node.receive(partial_file, &file_options)
+                            .expect("Failed to receive partial signature");
+                    }
+                }
+            }
+            if let Some(connect) = connect {
+                for commitment_file in &commitment_files {
+                    tcp::download(connect, commitment_file, &file_options)
+                        .expect("Failed to download commitments");
+                }
+                let signers =
+                    signing::roster_indices(&commitment_files).expect("Failed to load commitments");
+                for index in signers {
+                    let partial_file =
+                        Path::new(partial_signatures_dir).join(partial_signature_name(index));
+                    tcp::download(connect, partial_file, &file_options)
+                        .expect("Failed to download partial signature");
+                }
+            }
+            if let Some(identities_dir) = identities_dir {
+                for commitment_file in &commitment_files {
+                    identity::verify_message_file(identities_dir, commitment_file)
+                        .expect("Failed to verify commitments");
+                }
+                let signers =
+                    signing::roster_indices(&commitment_files).expect("Failed to load commitments");
+                for index in signers {
+                    let partial_file =
+                        Path::new(partial_signatures_dir).join(partial_signature_name(index));
+                    identity::verify_message_file(identities_dir, partial_file)
+                        .expect("Failed to verify partial signature");
+                }
+            }
+            signing::aggregate_at(
+                message,
+                context,
+                key_file,
+                &commitment_files,
+                partial_signatures_dir,
+                *commitment_index,
+                signature_file,
+                *sig_format,
+                &file_options,
+                transcript_file,
+            )
+            .expect("Failed to aggregate signature");
+        }
+        Commands::Session { command } => match command {
+            SessionCommands::New {
+                message,
+                key_file,
+                signers,
+                session_file,
+            } => {
+                let signers: Vec<u32> = signers.iter().map(|signer| signer.get()).collect();
+                session::new_session(
+                    message,
+                    context,
+                    key_file,
+                    signers,
+                    session_file,
+                    &file_options,
+                )
+                .expect("Failed to create session");
+            }
+            SessionCommands::AddCommitments {
+                session_file,
+                commitments_file,
+            } => {
+                session::add_commitments(
+                    session_file,
+                    commitments_file,
+                    transcript_file,
+                    &file_options,
+                )
+                .expect("Failed to add commitments");
+            }
+            SessionCommands::AddPartial {
+                session_file,
+                partial_signature_file,
+                signature_file,
+            } => {
+                session::add_partial_signature(
+                    session_file,
+                    partial_signature_file,
+                    signature_file,
+                    transcript_file,
+                    &file_options,
+                )
+                .expect("Failed to add partial signature");
+            }
+            SessionCommands::Status { session_file } => {
+                session::print_status(session_file).expect("Failed to read session");
+            }
+        },
+        Commands::QrDecode {
+            image_file,
+            output_file,
+        } => {
+            qr::decode_image(image_file, output_file, &file_options)
+                .expect("Failed to decode QR code");
+        }
+        Commands::Coordinator { command } => match command {
+            CoordinatorCommands::Serve {
+                listen,
+                key_file,
+                sessions_dir,
+                grpc,
+                tls,
+                metrics_listen,
+            } => {
+                spawn_metrics(metrics_listen.as_deref());
+                let serve = if *grpc { grpc::serve } else { http::serve };
+                serve(
+                    listen,
+                    key_file,
+                    sessions_dir,
+                    transcript_file,
+                    tls.tls().as_ref(),
+                    &file_options,
+                )
+                .expect("Failed to run coordinator");
+            }
+            CoordinatorCommands::New {
+                url,
+                message,
+                signers,
+                grpc,
+                tls,
+            } => {
+                let signers: Vec<u32> = signers.iter().map(|signer| signer.get()).collect();
+                let create_remote_session = if *grpc {
+                    grpc::create_remote_session
+                } else {
+                    http::create_remote_session
+                };
+                create_remote_session(url, message, context, signers, tls.tls().as_ref())
+                    .expect("Failed to create session");
+            }
+            CoordinatorCommands::Signature {
+                url,
+                session_id,
+                signature_file,
+                grpc,
+                tls,
+            } => {
+                let download_signature = if *grpc {
+                    grpc::download_signature
+                } else {
+                    http::download_signature
+                };
+                download_signature(
+                    url,
+                    session_id,
+                    signature_file,
+                    tls.tls().as_ref(),
+                    &file_options,
+                )
+                .expect("Failed to download signature");
+            }
+        },
+        Commands::Participant {
+            url,
+            share_file,
+            state_dir,
+            min_commitments,
+            batch_size,
+            tls,
+            identity,
+        } => {
+            let share_file = &store.location(share_file);
+            let refill = min_commitments.map(|min_commitments| ws::PoolRefill {
+                min_commitments,
+                batch_size: *batch_size,
+            });
+            ws::run_participant_with_pool(
+                url,
+                share_file,
+                context,
+                state_dir,
+                refill.as_ref(),
+                tls.tls().as_ref(),
+                identity.files().as_ref(),
+                &file_options,
+            )
+            .expect("Failed to run participant");
+        }
+        Commands::Share { command } => match command {
+            ShareCommands::Export {
+                share_file,
+                mnemonic: _,
+            } => {
+                let share_file = &store.location(share_file);
+                let mnemonic = backup::export_mnemonic(share_file, &file_options)
+                    .expect("Failed to export share");
+                output::report("mnemonic", &mnemonic, json!({ "mnemonic": mnemonic }));
+            }
+            ShareCommands::Backup {
+                share_file,
+                paper: _,
+            } => {
+                let share_file = &store.location(share_file);
+                let backup = backup::export_paper(share_file, &file_options)
+                    .expect("Failed to back up share");
+                output::report(
+                    "paper_backup",
+                    backup.trim_end(),
+                    json!({ "backup": backup }),
+                );
+            }
+            ShareCommands::Recover {
+                backup_file,
+                output_share_file,
+            } => {
+                let output_share_file = &store.location(output_share_file);
+                backup::recover_paper(backup_file, output_share_file, &file_options)
+                    .expect("Failed to recover share");
+            }
+            ShareCommands::Verify {
+                share_file,
+                with_shares,
+            } => {
+                let share_file = &store.location(share_file);
+                let other_share_files: Vec<String> = with_shares
+                    .iter()
+                    .flat_map(|files| files.split(','))
+                    .map(|s| store.location(s))
+                    .collect();
+                backup::verify_share(share_file, &other_share_files, &file_options)
+                    .expect("Failed to verify share");
+            }
+            ShareCommands::Import {
+                mnemonic,
+                index,
+                group_key,
+                t,
+                n,
+                output_share_file,
+            } => {
+                let output_share_file = &store.location(output_share_file);
+                backup::import_mnemonic(
+                    mnemonic,
+                    *index,
+                    group_key,
+                    *t,
+                    *n,
+                    output_share_file,
+                    &file_options,
+                )
+                .expect("Failed to import share");
+            }
+        },
+        Commands::Key { command } => match command {
+            KeyCommands::List => {
+                let keystore = keystore();
+                let keys =
+                    keystore::list_keys(&keystore, &file_options).expect("Failed to list keys");
+                let mut lines = Vec::new();
+                let mut entries = Vec::new();
+                for key in &keys {
+                    let (kind, index) = match key.kind {
+                        keystore::KeyKind::Keys => ("all shares".to_string(), None),
+                        keystore::KeyKind::Share { index } => {
+                            (format!("share {}", index), Some(index))
+                        }
+                    };
+                    lines.push(format!(
+                        "{}: {}, threshold {}, group key {}",
+                        key.name,
+                        kind,
+                        key.threshold,
+                        hex::encode(key.group_key)
+                    ));
+                    entries.push(json!({
+                        "name": key.name,
+                        "index": index,
+                        "threshold": key.threshold,
+                        "group_key": hex::encode(key.group_key),
+                    }));
+                }
+                if keys.is_empty() {
+                    lines.push(format!("No keys in {}", keystore.display()));
+                }
+                output::report(
+                    "keys",
+                    &lines.join("\n"),
+                    json!({ "keystore": keystore, "keys": entries }),
+                );
+            }
+            KeyCommands::Import { name, key_file } => {
+                keystore::import_key(&keystore(), name, key_file, &file_options)
+                    .expect("Failed to import key");
+            }
+            KeyCommands::Inspect { name, key_file } => {
+                let key_file = match name {
+                    Some(name) => keystore::key_file(&keystore(), name)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let inspection =
+                    inspect::inspect_key(&key_file, &file_options).expect("Failed to inspect key");
+                let mut fields = serde_json::to_value(&inspection).expect("Failed to inspect key");
+                fields["key_file"] = json!(key_file);
+                output::report(
+                    "key_inspected",
+                    &format!("Key file: {}\n{}", key_file, inspection),
+                    fields,
+                );
+            }
+            KeyCommands::Delete { name } => {
+                keystore::delete_key(&keystore(), name).expect("Failed to delete key");
+            }
+        },
+        Commands::Sig { command } => match command {
+            SigCommands::Inspect {
+                signature_file,
+                sig_format,
+            } => {
+                let inspection = inspect::inspect_signature(signature_file, *sig_format)
+                    .expect("Failed to inspect signature");
+                let mut fields =
+                    serde_json::to_value(&inspection).expect("Failed to inspect signature");
+                fields["signature_file"] = json!(signature_file);
+                output::report(
+                    "signature_inspected",
+                    &format!("Signature file: {}\n{}", signature_file, inspection),
+                    fields,
+                );
+            }
+        },
+        Commands::Transcript { command } => match command {
+            TranscriptCommands::Check { transcript_file } => {
+                transcript::check(transcript_file).expect("Failed to check transcript");
+            }
+        },
+        Commands::Completions { shell } => {
+            // Key names are taken from the keystore now, so regenerate after adding keys.
+            // Without a readable keystore, only subcommands and flags are completed.
+            let names = keystore::keystore_dir(cli.keystore.as_deref())
+                .and_then(|keystore| keystore::key_names(&keystore))
+                .unwrap_or_default();
+            let mut command = complete_key_names(Cli::command(), &names);
+            let bin_name = command.get_name().to_string();
+            clap_complete::generate(*shell, &mut command, bin_name, &mut std::io::stdout());
+        }
+        Commands::Identity { command } => match command {
+            IdentityCommands::New {
+                index,
+                identity_file,
+                identities_dir,
+            } => {
+                identity::generate_identity(*index, identity_file, identities_dir, &file_options)
+                    .expect("Failed to create identity");
+            }
+            IdentityCommands::Roster {
+                n,
+                identities_dir,
+                roster_file,
+            } => {
+                dealer::write_roster(identities_dir, *n, roster_file, &file_options)
+                    .expect("Failed to create roster");
+            }
+        },
+        Commands::Relay { command } => match command {
+            RelayCommands::Serve { listen } => {
+                relay::serve(listen).expect("Failed to run relay");
+            }
+            RelayCommands::Send {
+                connect,
+                session_id,
+                identity_file,
+                identities_dir,
+                to,
+                file,
+            } => {
+                relay::send(
+                    connect,
+                    session_id,
+                    identity_file,
+                    identities_dir,
+                    *to,
+                    file,
+                )
+                .expect("Failed to send round message");
+            }
+            RelayCommands::Receive {
+                connect,
+                session_id,
+                identity_file,
+                identities_dir,
+                from,
+                file,
+            } => {
+                relay::receive(
+                    connect,
+                    session_id,
+                    identity_file,
+                    identities_dir,
+                    *from,
+                    file,
+                )
+                .expect("Failed to receive round message");
+            }
+        },
+        Commands::Daemon { command } => match command {
+            DaemonCommands::Serve {
+                socket,
+                share_file,
+                metrics_listen,
+            } => {
+                // The share stays in memory for as long as the daemon runs, so never swap it
+                memlock::enable();
+                spawn_metrics(metrics_listen.as_deref());
+                let share_file = &store.location(share_file);
+                daemon::serve(socket, share_file, &file_options).expect("Failed to run daemon");
+            }
+            DaemonCommands::Commit {
+                socket,
+                session_id,
+                public_commitments_file,
+            } => {
+                daemon::commit(socket, session_id, public_commitments_file, &file_options)
+                    .expect("Failed to commit through the daemon");
+            }
+            DaemonCommands::PartialSign {
+                socket,
+                message,
+                commitment_files,
+                output_file,
+            } => {
+                daemon::partial_sign(
+                    socket,
+                    message,
+                    context,
+                    commitment_files,
+                    output_file,
+                    &file_options,
+                )
+                .expect("Failed to sign through the daemon");
+            }
+        },
+        Commands::Serve {
+            http,
+            clients_file,
+            sessions_dir,
+        } => {
+            api::serve(http, &keystore(), clients_file, sessions_dir, &file_options)
+                .expect("Failed to run signing API");
+        }
+        Commands::ApiClient { command } => match command {
+            ApiClientCommands::Add {
+                name,
+                clients_file,
+                create_sessions,
+                share,
+            } => {
+                api::add_client(
+                    clients_file,
+                    name,
+                    *create_sessions,
+                    share.as_deref(),
+                    &file_options,
+                )
+                .expect("Failed to add client");
+            }
+        },
+        Commands::Mailbox { listen, dir } => {
+            tcp::serve(listen, dir, &file_options).expect("Failed to run mailbox");
+        }
+        Commands::Dkg { command } => match command {
+            DkgCommands::Round1 {
+                index,
+                t,
+                n,
+                state_file,
+                output_dir,
+                qr,
+                connect,
+                identity,
+            } => {
+                dkg::round_one(*index, *t, *n, state_file, output_dir, &file_options)
+                    .expect("Failed to run DKG round 1");
+                let round1_file = Path::new(output_dir).join(format!("round1_{}.json", index));
+                identity.sign(&round1_file);
+                if *qr {
+                    qr::print_file(&round1_file).expect("Failed to print QR code");
+                }
+                if let Some(connect) = connect {
+                    tcp::upload(connect, &round1_file).expect("Failed to upload round 1 message");
+                }
+            }
+            DkgCommands::Round2 {
+                state_file,
+                round1_dir,
+                output_dir,
+                qr,
+                connect,
+                p2p,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some() || p2p.enabled())
+                    .expect("Refusing to send unsealed shares");
+                if let Some(connect) = connect {
+                    let round1_files = dkg::round1_files(state_file, round1_dir)
+                        .expect("Failed to read DKG state");
+                    for round1_file in round1_files {
+                        tcp::download(connect, round1_file, &file_options)
+                            .expect("Failed to download round 1 message");
+                    }
+                }
+                let node = p2p.start();
+                if let Some(node) = &node {
+                    let round1_files = dkg::round1_files(state_file, round1_dir)
+                        .expect("Failed to read DKG state");
+                    exchange_with_peers(node, &round1_files, &file_options);
+                }
+                for round1_file in
+                    dkg::round1_files(state_file, round1_dir).expect("Failed to read DKG state")
+                {
+                    identity.verify(&round1_file);
+                }
+                dkg::round_two(state_file, round1_dir, output_dir, &file_options)
+                    .expect("Failed to run DKG round 2");
+                let share_files = dkg::outgoing_share_files(state_file, output_dir)
+                    .expect("Failed to read DKG state");
+                for share_file in &share_files {
+                    identity.sign(share_file);
+                    let wire_file = identity.seal(share_file, &file_options);
+                    if *qr {
+                        qr::print_file(&wire_file).expect("Failed to print QR code");
+                    }
+                    if let Some(connect) = connect {
+                        tcp::upload(connect, &wire_file).expect("Failed to upload share");
+                    }
+                    if let Some(node) = &node {
+                        node.publish(&wire_file).expect("Failed to publish share");
+                    }
+                }
+                p2p.linger(node);
+            }
+            DkgCommands::Finish {
+                state_file,
+                shares_dir,
+                output_share_file,
+                connect,
+                p2p,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some() || p2p.enabled())
+                    .expect("Refusing to send unsealed shares");
+                let output_share_file = &store.location(output_share_file);
+                let incoming_files = dkg::incoming_share_files(state_file, shares_dir)
+                    .expect("Failed to read DKG state");
+                if let Some(connect) = connect {
+                    for share_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(share_file), &file_options)
+                            .expect("Failed to download share");
+                    }
+                }
+                let node = p2p.start();
+                if let Some(node) = &node {
+                    // Keep sharing the round 1 message and shares for peers still behind.
+                    let mut files = dkg::round1_files(state_file, shares_dir)
+                        .expect("Failed to read DKG state");
+                    files.retain(|file| file.exists());
+                    files.extend(
+                        dkg::outgoing_share_files(state_file, shares_dir)
+                            .expect("Failed to read DKG state")
+                            .iter()
+                            .chain(&incoming_files)
+                            .map(|share_file| identity.wire_file(share_file)),
+                    );
+                    exchange_with_peers(node, &files, &file_options);
+                }
+                for share_file in &incoming_files {
+                    identity.open(share_file, &file_options);
+                    identity.verify(share_file);
+                }
+                dkg::finish(state_file, shares_dir, output_share_file, &file_options)
+                    .expect("Failed to finish DKG");
+                p2p.linger(node);
+            }
+            DkgCommands::Complain {
+                state_file,
+                shares_dir,
+                output_dir,
+                identity,
+            } => {
+                // Missing shares are complained about, so only open the ones that came.
+                for share_file in dkg::incoming_share_files(state_file, shares_dir)
+                    .expect("Failed to read DKG state")
+                {
+                    if identity.wire_file(&share_file).exists() {
+                        identity.open(&share_file, &file_options);
+                        identity.verify(&share_file);
+                    }
+                }
+                let complaint_files =
+                    dkg::complain(state_file, shares_dir, output_dir, &file_options)
+                        .expect("Failed to publish DKG complaints");
+                for complaint_file in &complaint_files {
+                    identity.sign(complaint_file);
+                }
+            }
+            DkgCommands::Answer {
+                state_file,
+                complaints_dir,
+                output_dir,
+                identity,
+            } => {
+                for dispute_file in
+                    dkg::dispute_files(complaints_dir).expect("Failed to read DKG complaints")
+                {
+                    identity.verify(&dispute_file);
+                }
+                let reveal_files =
+                    dkg::answer(state_file, complaints_dir, output_dir, &file_options)
+                        .expect("Failed to answer DKG complaints");
+                for reveal_file in &reveal_files {
+                    identity.sign(reveal_file);
+                }
+            }
+            DkgCommands::Resolve {
+                state_file,
+                dkg_dir,
+                output_share_file,
+                exclude,
+                evidence_file,
+                identity,
+            } => {
+                let output_share_file = &store.location(output_share_file);
+                for dispute_file in
+                    dkg::dispute_files(dkg_dir).expect("Failed to read DKG complaints")
+                {
+                    identity.verify(&dispute_file);
+                }
+                dkg::resolve(
+                    state_file,
+                    dkg_dir,
+                    output_share_file,
+                    *exclude,
+                    evidence_file,
+                    &file_options,
+                )
+                .expect("Failed to resolve DKG complaints");
+            }
+        },
+        Commands::Refresh { command } => match command {
+            RefreshCommands::Round1 {
+                session_id,
+                share_file,
+                state_file,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                refresh::round_one(
+                    session_id,
+                    share_file,
+                    state_file,
+                    output_dir,
+                    &file_options,
+                )
+                .expect("Failed to run refresh round 1");
+                let message_file = refresh::own_message_file(state_file, output_dir)
+                    .expect("Failed to read refresh state");
+                let share_files = refresh::outgoing_share_files(state_file, output_dir)
+                    .expect("Failed to read refresh state");
+                identity.sign(&message_file);
+                let mut wire_files = vec![message_file];
+                for share_file in &share_files {
+                    identity.sign(share_file);
+                    wire_files.push(identity.seal(share_file, &file_options));
+                }
+                if let Some(connect) = connect {
+                    for wire_file in &wire_files {
+                        tcp::upload(connect, wire_file).expect("Failed to upload refresh message");
+                    }
+                }
+            }
+            RefreshCommands::Finish {
+                state_file,
+                share_file,
+                refresh_dir,
+                output_share_file,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let output_share_file = output_share_file
+                    .as_deref()
+                    .map_or_else(|| share_file.clone(), |file| store.location(file));
+                let message_files = refresh::message_files(state_file, refresh_dir)
+                    .expect("Failed to read refresh state");
+                let incoming_files = refresh::incoming_share_files(state_file, refresh_dir)
+                    .expect("Failed to read refresh state");
+                if let Some(connect) = connect {
+                    for message_file in &message_files {
+                        tcp::download(connect, message_file, &file_options)
+                            .expect("Failed to download refresh message");
+                    }
+                    for share_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(share_file), &file_options)
+                            .expect("Failed to download refresh share");
+                    }
+                }
+                for message_file in &message_files {
+                    identity.verify(message_file);
+                }
+                for share_file in &incoming_files {
+                    identity.open(share_file, &file_options);
+                    identity.verify(share_file);
+                }
+                refresh::finish(
+                    state_file,
+                    share_file,
+                    refresh_dir,
+                    &output_share_file,
+                    &file_options,
+                )
+                .expect("Failed to finish refresh");
+            }
+        },
+        Commands::Reshare { command } => match command {
+            ReshareCommands::Keys {
+                signers,
+                t,
+                n,
+                key_file,
+                output_key_file,
+            } => {
+                let signers = signing::signer_positions(key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                reshare::reshare_keys(key_file, &signers, *t, *n, output_key_file, &file_options)
+                    .expect("Failed to reshare keys");
+            }
+            ReshareCommands::Round1 {
+                share_file,
+                dealers,
+                t,
+                n,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let dealers: Vec<u32> = dealers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid dealer index"))
+                    .collect();
+                reshare::round_one(share_file, &dealers, *t, *n, output_dir, &file_options)
+                    .expect("Failed to run reshare round 1");
+                let index = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share")
+                    .index;
+                let message_file = reshare::message_file(output_dir, index);
+                identity.sign(&message_file);
+                let mut wire_files = vec![message_file];
+                for share_file in reshare::outgoing_share_files(output_dir, index, *n) {
+                    identity.sign(&share_file);
+                    wire_files.push(identity.seal(&share_file, &file_options));
+                }
+                if let Some(connect) = connect {
+                    for wire_file in &wire_files {
+                        tcp::upload(connect, wire_file).expect("Failed to upload reshare message");
+                    }
+                }
+            }
+            ReshareCommands::Finish {
+                index,
+                dealers,
+                reshare_dir,
+                output_share_file,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let output_share_file = &store.location(output_share_file);
+                let dealers: Vec<u32> = dealers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid dealer index"))
+                    .collect();
+                let message_files: Vec<PathBuf> = dealers
+                    .iter()
+                    .map(|&dealer| reshare::message_file(reshare_dir, dealer))
+                    .collect();
+                let incoming_files = reshare::incoming_share_files(reshare_dir, *index, &dealers);
+                if let Some(connect) = connect {
+                    for message_file in &message_files {
+                        tcp::download(connect, message_file, &file_options)
+                            .expect("Failed to download reshare message");
+                    }
+                    for share_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(share_file), &file_options)
+                            .expect("Failed to download reshare share");
+                    }
+                }
+                for message_file in &message_files {
+                    identity.verify(message_file);
+                }
+                for share_file in &incoming_files {
+                    identity.open(share_file, &file_options);
+                    identity.verify(share_file);
+                }
+                reshare::finish(
+                    *index,
+                    &dealers,
+                    reshare_dir,
+                    output_share_file,
+                    &file_options,
+                )
+                .expect("Failed to finish reshare");
+            }
+        },
+        Commands::Enroll { command } => match command {
+            EnrollCommands::Keys {
+                signers,
+                key_file,
+                output_key_file,
+            } => {
+                let signers = signing::signer_positions(key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                let output_key_file = output_key_file.as_deref().unwrap_or(key_file);
+                enroll::enroll_keys(key_file, &signers, output_key_file, &file_options)
+                    .expect("Failed to enroll participant");
+            }
+            EnrollCommands::Round1 {
+                share_file,
+                helpers,
+                new_index,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                enroll::round_one(share_file, &helpers, *new_index, output_dir, &file_options)
+                    .expect("Failed to run enrollment round 1");
+                let index = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share")
+                    .index;
+                let message_file = enroll::message_file(output_dir, index);
+                identity.sign(&message_file);
+                let mut wire_files = vec![message_file];
+                for piece_file in enroll::outgoing_piece_files(output_dir, index, &helpers) {
+                    identity.sign(&piece_file);
+                    wire_files.push(identity.seal(&piece_file, &file_options));
+                }
+                if let Some(connect) = connect {
+                    for wire_file in &wire_files {
+                        tcp::upload(connect, wire_file)
+                            .expect("Failed to upload enrollment message");
+                    }
+                }
+            }
+            EnrollCommands::Round2 {
+                share_file,
+                helpers,
+                new_index,
+                enroll_dir,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                let index = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share")
+                    .index;
+                let incoming_files = enroll::incoming_piece_files(enroll_dir, index, &helpers);
+                if let Some(connect) = connect {
+                    for piece_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(piece_file), &file_options)
+                            .expect("Failed to download piece");
+                    }
+                }
+                for piece_file in &incoming_files {
+                    identity.open(piece_file, &file_options);
+                    identity.verify(piece_file);
+                }
+                enroll::round_two(
+                    share_file,
+                    &helpers,
+                    *new_index,
+                    enroll_dir,
+                    output_dir,
+                    &file_options,
+                )
+                .expect("Failed to run enrollment round 2");
+                let share_file = enroll::outgoing_share_file(output_dir, index, *new_index);
+                identity.sign(&share_file);
+                let wire_file = identity.seal(&share_file, &file_options);
+                if let Some(connect) = connect {
+                    tcp::upload(connect, &wire_file).expect("Failed to upload share");
+                }
+            }
+            EnrollCommands::Finish {
+                new_index,
+                helpers,
+                enroll_dir,
+                output_share_file,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let output_share_file = &store.location(output_share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                let message_files: Vec<PathBuf> = helpers
+                    .iter()
+                    .map(|&helper| enroll::message_file(enroll_dir, helper))
+                    .collect();
+                let incoming_files = enroll::incoming_share_files(enroll_dir, *new_index, &helpers);
+                if let Some(connect) = connect {
+                    for message_file in &message_files {
+                        tcp::download(connect, message_file, &file_options)
+                            .expect("Failed to download enrollment message");
+                    }
+                    for share_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(share_file), &file_options)
+                            .expect("Failed to download share");
+                    }
+                }
+                for message_file in &message_files {
+                    identity.verify(message_file);
+                }
+                for share_file in &incoming_files {
+                    identity.open(share_file, &file_options);
+                    identity.verify(share_file);
+                }
+                enroll::finish(
+                    *new_index,
+                    &helpers,
+                    enroll_dir,
+                    output_share_file,
+                    &file_options,
+                )
+                .expect("Failed to finish enrollment");
+            }
+            EnrollCommands::Update { share_file, n } => {
+                let share_file = &store.location(share_file);
+                enroll::update_share(share_file, *n, &file_options)
+                    .expect("Failed to update share");
+            }
+        },
+        Commands::Bench { t, n, rounds } => {
+            let results = bench::run(t, n, *rounds).expect("Failed to run the benchmark");
+            let millis = |duration: std::time::Duration| duration.as_secs_f64() * 1000.0;
+            let mut lines = vec![format!(
+                "{:>5} {:>5} {:>12} {:>12} {:>12} {:>12} {:>12}",
+                "t", "n", "keygen ms", "commit ms", "sign ms", "aggregate ms", "verify ms"
+            )];
+            let mut rows = Vec::new();
+            for result in &results {
+                lines.push(format!(
+                    "{:>5} {:>5} {:>12.3} {:>12.3} {:>12.3} {:>12.3} {:>12.3}",
+                    result.threshold,
+                    result.participants,
+                    millis(result.keygen),
+                    millis(result.commit),
+                    millis(result.partial_sign),
+                    millis(result.aggregate),
+                    millis(result.verify)
+                ));
+                rows.push(json!({
+                    "threshold": result.threshold,
+                    "participants": result.participants,
+                    "keygen_ms": millis(result.keygen),
+                    "commit_ms": millis(result.commit),
+                    "partial_sign_ms": millis(result.partial_sign),
+                    "aggregate_ms": millis(result.aggregate),
+                    "verify_ms": millis(result.verify),
+                }));
+            }
+            output::report(
+                "bench",
+                &lines.join("\n"),
+                json!({ "rounds": rounds, "results": rows }),
+            );
+        }
+        Commands::Reconstruct {
+            share_files,
+            key_file,
+            signers,
+            output_file,
+            accept_risk,
+        } => {
+            if !accept_risk {
+                panic!(
+                    "Reconstructing the group secret key ends its threshold security, pass \
+                     --accept-risk to confirm"
+                );
+            }
+            if let Some(share_files) = share_files {
+                let share_files: Vec<String> =
+                    share_files.split(',').map(|s| store.location(s)).collect();
+                reconstruct::reconstruct(&share_files, output_file, &file_options)
+                    .expect("Failed to reconstruct the group secret key");
+            } else if let Some(key_file) = key_file {
+                let signers = signing::signer_positions(key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                reconstruct::reconstruct_keys(key_file, &signers, output_file, &file_options)
+                    .expect("Failed to reconstruct the group secret key");
+            }
+        }
+        Commands::Repair { command } => match command {
+            RepairCommands::Round1 {
+                share_file,
+                helpers,
+                lost_index,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                repair::round_one(share_file, &helpers, *lost_index, output_dir, &file_options)
+                    .expect("Failed to run repair round 1");
+                let index = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share")
+                    .index;
+                let message_file = enroll::message_file(output_dir, index);
+                identity.sign(&message_file);
+                let mut wire_files = vec![message_file];
+                for piece_file in enroll::outgoing_piece_files(output_dir, index, &helpers) {
+                    identity.sign(&piece_file);
+                    wire_files.push(identity.seal(&piece_file, &file_options));
+                }
+                if let Some(connect) = connect {
+                    for wire_file in &wire_files {
+                        tcp::upload(connect, wire_file).expect("Failed to upload repair message");
+                    }
+                }
+            }
+            RepairCommands::Round2 {
+                share_file,
+                helpers,
+                lost_index,
+                repair_dir,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                let index = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share")
+                    .index;
+                let incoming_files = enroll::incoming_piece_files(repair_dir, index, &helpers);
+                if let Some(connect) = connect {
+                    for piece_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(piece_file), &file_options)
+                            .expect("Failed to download piece");
+                    }
+                }
+                for piece_file in &incoming_files {
+                    identity.open(piece_file, &file_options);
+                    identity.verify(piece_file);
+                }
+                repair::round_two(
+                    share_file,
+                    &helpers,
+                    *lost_index,
+                    repair_dir,
+                    output_dir,
+                    &file_options,
+                )
+                .expect("Failed to run repair round 2");
+                let share_file = enroll::outgoing_share_file(output_dir, index, *lost_index);
+                identity.sign(&share_file);
+                let wire_file = identity.seal(&share_file, &file_options);
+                if let Some(connect) = connect {
+                    tcp::upload(connect, &wire_file).expect("Failed to upload share");
+                }
+            }
+            RepairCommands::Finish {
+                lost_index,
+                helpers,
+                repair_dir,
+                output_share_file,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let output_share_file = &store.location(output_share_file);
+                let helpers: Vec<u32> = helpers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid helper index"))
+                    .collect();
+                let message_files: Vec<PathBuf> = helpers
+                    .iter()
+                    .map(|&helper| enroll::message_file(repair_dir, helper))
+                    .collect();
+                let incoming_files =
+                    enroll::incoming_share_files(repair_dir, *lost_index, &helpers);
+                if let Some(connect) = connect {
+                    for message_file in &message_files {
+                        tcp::download(connect, message_file, &file_options)
+                            .expect("Failed to download repair message");
+                    }
+                    for share_file in &incoming_files {
+                        tcp::download(connect, identity.wire_file(share_file), &file_options)
+                            .expect("Failed to download share");
+                    }
+                }
+                for message_file in &message_files {
+                    identity.verify(message_file);
+                }
+                for share_file in &incoming_files {
+                    identity.open(share_file, &file_options);
+                    identity.verify(share_file);
+                }
+                repair::finish(
+                    *lost_index,
+                    &helpers,
+                    repair_dir,
+                    output_share_file,
+                    &file_options,
+                )
+                .expect("Failed to finish repair");
+            }
+        },
+        Commands::Revoke { command } => match command {
+            RevokeCommands::Keys {
+                signers,
+                revoked,
+                key_file,
+                output_key_file,
+            } => {
+                let signers = signing::signer_positions(key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                let revoked: Vec<u32> = revoked
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid revoked index"))
+                    .collect();
+                revoke::revoke_keys(key_file, &signers, &revoked, output_key_file, &file_options)
+                    .expect("Failed to revoke participants");
+            }
+            RevokeCommands::Round1 {
+                share_file,
+                dealers,
+                revoked,
+                output_dir,
+                connect,
+                identity,
+            } => {
+                identity
+                    .require_sealing(connect.is_some())
+                    .expect("Refusing to send unsealed shares");
+                let share_file = &store.location(share_file);
+                let dealers: Vec<u32> = dealers
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid dealer index"))
+                    .collect();
+                let mut revoked: Vec<u32> = revoked
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid revoked index"))
+                    .collect();
+                revoke::round_one(share_file, &dealers, &revoked, output_dir, &file_options)
+                    .expect("Failed to run revocation round 1");
+                revoked.extend(
+                    revoke::revoked_indices(share_file, &file_options)
+                        .expect("Failed to load revocations"),
+                );
+                let share = load_participant_share(share_file, &file_options)
+                    .expect("Failed to load share");
+                let message_file = reshare::message_file(output_dir, share.index);
+                identity.sign(&message_file);
+                let mut wire_files = vec![message_file];
+                for share_file in revoke::outgoing_share_files(
+                    output_dir,
+                    share.index,
+                    share.participants,
+                    &revoked,
+                ) {
+                    identity.sign(&share_file);
+                    wire_files.push(identity.seal(&share_file, &file_options));
+                }
+                if let Some(connect) = connect {
+                    for wire_file in &wire_files {
+                        tcp::upload(connect, wire_file)
+                            .expect("Failed to upload revocation message");
+                    }
+                }
+            }
+            RevokeCommands::Statement {
+                key_file,
+                revoked,
+                output_file,
+            } => {
+                let revoked: Vec<u32> = revoked
+                    .split(',')
+                    .map(|s| s.parse().expect("Invalid revoked index"))
+                    .collect();
+                revoke::new_statement(key_file, &revoked, output_file, &file_options)
+                    .expect("Failed to write revocation statement");
+            }
+            RevokeCommands::Record {
+                key_file,
+                statement_file,
+                signature_file,
+            } => {
+                revoke::record(key_file, statement_file, signature_file, &file_options)
+                    .expect("Failed to record revocation");
+            }
+        },
+        Commands::Rotate { command } => match command {
+            RotateCommands::Keys {
+                signers,
+                t,
+                n,
+                key_file,
+                key,
+                output_key_file,
+                new_key,
+                valid_until,
+            } => {
+                let key_file = match key {
+                    Some(key) => keystore::dealer_key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let signers = signing::signer_positions(&key_file, signers, &file_options)
+                    .expect("Invalid signers");
+                let output_key_file = match new_key {
+                    Some(new_key) => keystore::new_key_file(&keystore(), new_key)
+                        .expect("Failed to create key in the keystore"),
+                    None => output_key_file.clone(),
+                };
+                rotate::rotate_keys(
+                    &key_file,
+                    &signers,
+                    *t,
+                    *n,
+                    &output_key_file,
+                    *valid_until,
+                    &file_options,
+                )
+                .expect("Failed to rotate keys");
+            }
+            RotateCommands::Statement {
+                key_file,
+                new_key_file,
+                valid_until,
+                output_file,
+            } => {
+                rotate::new_statement(
+                    key_file,
+                    new_key_file,
+                    *valid_until,
+                    output_file,
+                    &file_options,
+                )
+                .expect("Failed to write rotation statement");
+            }
+            RotateCommands::Record {
+                key_file,
+                new_key_file,
+                statement_file,
+                signature_file,
+            } => {
+                rotate::record(
+                    key_file,
+                    new_key_file,
+                    statement_file,
+                    signature_file,
+                    &file_options,
+                )
+                .expect("Failed to record rotation");
+            }
+            RotateCommands::History { key_file, key } => {
+                let key_file = match key {
+                    Some(key) => keystore::key_file(&keystore(), key)
+                        .expect("Failed to find key in the keystore"),
+                    None => key_file.clone(),
+                };
+                let statements =
+                    rotate::history(&key_file, &file_options).expect("Failed to check rotations");
+                let lines: Vec<String> = statements
+                    .iter()
+                    .map(|statement| {
+                        format!(
+                            "{} -> {} from {}{}",
+                            statement.old_group_key,
+                            statement.new_group_key,
+                            statement.valid_from,
+                            statement
+                                .valid_until
+                                .map(|until| format!(", old key valid until {}", until))
+                                .unwrap_or_default()
+                        )
+                    })
+                    .collect();
+                output::report(
+                    "rotation_history",
+                    &if lines.is_empty() {
+                        "No rotations recorded".to_string()
+                    } else {
+                        lines.join("\n")
+                    },
+                    json!({ "rotations": serde_json::to_value(&statements).ok() }),
+                );
+            }
+        },
+    }
+}
+
+/// Offers `names` as the values of every `--key` argument and of `key inspect` and
+/// `key delete`, leaving the arguments as they are if the keystore is empty.
+fn complete_key_names(mut command: clap::Command, names: &[String]) -> clap::Command {
+    if names.is_empty() {
+        return command;
+    }
+    let key_name = |arg: Arg| arg.value_parser(PossibleValuesParser::new(names.to_vec()));
+    if command.get_arguments().any(|arg| arg.get_id() == "key") {
+        command = command.mut_arg("key", key_name);
+    }
+    if matches!(command.get_name(), "inspect" | "delete")
+        && command.get_arguments().any(|arg| arg.get_id() == "name")
+    {
+        command = command.mut_arg("name", key_name);
+    }
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_string())
+        .collect();
+    subcommands.iter().fold(command, |command, name| {
+        command.mut_subcommand(name, |subcommand| complete_key_names(subcommand, names))
+    })
+}
+
+/// Exit code of `verify` for a signature that was read but is not valid.
+const EXIT_INVALID_SIGNATURE: i32 = 1;
+/// Exit code of `verify` for input that cannot be parsed or used, such as a malformed
+/// signature file or an unknown key.
+const EXIT_BAD_INPUT: i32 = 2;
+/// Exit code of `verify` for a file that cannot be read.
+const EXIT_IO_ERROR: i32 = 3;
+
+/// Reports why `verify` failed, unless `quiet`, and exits with the code of the kind of
+/// failure.
+fn verification_failed(context: &str, err: Box<dyn std::error::Error>, quiet: bool) -> ! {
+    let code = if err.is::<InvalidSignature>() {
+        EXIT_INVALID_SIGNATURE
+    } else if error::is_io_error(err.as_ref()) {
+        EXIT_IO_ERROR
+    } else {
+        EXIT_BAD_INPUT
+    };
+    let message = format!("{}: {}", context, err);
+    if output::output_mode() == OutputMode::Json {
+        output::report("error", &message, json!({ "ok": false, "exit_code": code }));
+    } else if !quiet {
+        error!("{}", message);
+    }
+    std::process::exit(code)
+}
+
+/// Returns the progress bar of a key generation with `n` participants, drawn on stderr
+/// unless `quiet`.
+fn keygen_progress_bar(n: u32, quiet: bool) -> ProgressBar {
+    let bar = ProgressBar::new(u64::from(n));
+    if quiet {
+        bar.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    bar.set_style(
+        ProgressStyle::with_template("{msg:16} [{bar:40}] {pos}/{len} participants")
+            .expect("Invalid progress bar template")
+            .progress_chars("=> "),
+    );
+    bar
+}
+
+/// Returns the generator to draw signing nonces from: the operating system's, or with
+/// `seed` one seeded with it, see [`seeded_rng`].
+fn nonce_rng(seed: Option<&str>) -> Box<dyn SecureRng> {
+    match seed {
+        Some(seed) => {
+            warn!("Drawing nonces from a seed, never sign two messages with the same seed");
+            seeded_rng(seed.as_bytes()).expect("Failed to seed the nonce generator")
+        }
+        None => Box::new(OsRng),
+    }
+}
+
+/// Mailbox name under which a signer's echo of the roster is exchanged.
+fn echo_name(index: u32) -> String {
+    format!("echo_{}.json", index)
+}
+
+/// Mailbox name under which a signer's partial signature is exchanged.
+fn partial_signature_name(index: u32) -> String {
+    format!("partial_signature_{}.json", index)
+}
+
+/// Publishes the round messages in `files` that exist locally and waits for the rest.
+fn exchange_with_peers(node: &p2p::Node, files: &[PathBuf], options: &FileOptions) {
+    let (local, missing): (Vec<&PathBuf>, Vec<&PathBuf>) =
+        files.iter().partition(|file| file.exists());
+    for file in local {
+        node.publish(file).expect("Failed to publish round message");
+    }
+    for file in missing {
+        node.receive(file, options)
+            .expect("Failed to receive round message");
+    }
+}
+
+/// Serves the Prometheus metrics on `listen` next to a server, if an address is given.
+fn spawn_metrics(listen: Option<&str>) {
+    if let Some(listen) = listen.map(str::to_string) {
+        std::thread::spawn(move || {
+            metrics::serve(&listen).expect("Failed to serve metrics");
+        });
     }
 }
@@ -0,0 +1,341 @@
+//! Signing many messages and files listed in a manifest in one signing ceremony, and
+//! verifying them all at once.
+//!
+//! Signing a release one artifact at a time takes a ceremony per artifact. A manifest
+//! lists every message and file to sign with the signature file to save it to:
+//!
+//! ```json
+//! {
+//!   "entries": [
+//!     { "message": "release v1.2.0", "signature_file": "release.sig" },
+//!     { "file": "frost-cli.tar.gz", "signature_file": "frost-cli.tar.gz.sig" }
+//!   ]
+//! }
+//! ```
+//!
+//! [`sign_manifest`] loads the shares once and signs all entries together, with a fresh
+//! nonce commitment per entry, see [`crate::backend::Backend::sign_batch`]. Files are
+//! signed by their hash as with `sign --file`, see [`crate::artifact`]. Relative paths
+//! are taken relative to the manifest's directory.
+//!
+//! [`verify_manifest`] checks the signature of every entry, reports each as passed or
+//! failed, and fails if any entry does, so CI can verify a whole release in one step.
+
+use crate::artifact::SignedFile;
+use crate::io::envelope::EnvelopeOptions;
+use crate::io::jcs::Canonicalization;
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::{
+    backend, check_file_signature, check_signature, io, load_signing_keys, InvalidSignature,
+    SignatureMetadata,
+};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// A list of messages and files to sign.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub entries: Vec<ManifestEntry>,
+}
+
+/// A message or file in a manifest and its signature file. Exactly one of `message`
+/// and `file` is set.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    /// The message to sign.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Path to a file to sign by its hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Path of the entry's signature file.
+    pub signature_file: String,
+}
+
+impl ManifestEntry {
+    /// Returns what the entry is reported as, its file or its message.
+    pub fn name(&self) -> &str {
+        self.file
+            .as_deref()
+            .or(self.message.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Returns the bytes the group signs for the entry, with its message canonicalized as
+    /// given, and the record of its file if it is a file.
+    ///
+    /// # Errors
+    /// Returns an error if the message cannot be canonicalized or the file cannot be read.
+    pub fn signing_input(
+        &self,
+        canonicalization: Canonicalization,
+    ) -> Result<(Vec<u8>, Option<SignedFile>), Box<dyn std::error::Error>> {
+        match (&self.message, &self.file) {
+            (Some(message), None) => {
+                Ok((io::jcs::canonicalize_as(message, canonicalization)?, None))
+            }
+            (None, Some(file)) => {
+                let signed_file = SignedFile::hash(file)?;
+                Ok((signed_file.signing_input()?, Some(signed_file)))
+            }
+            _ => Err("A manifest entry needs either a message or a file".into()),
+        }
+    }
+}
+
+/// Loads a manifest, with the paths of its entries made relative to the current
+/// directory.
+///
+/// # Errors
+/// Returns an error if the manifest is not valid JSON, has no entries, an entry has
+/// both or neither of a message and a file, or two entries share a signature file.
+pub fn load_manifest(
+    manifest_file: &str,
+) -> Result<Vec<ManifestEntry>, Box<dyn std::error::Error>> {
+    // Step 1: Parse the manifest
+    let manifest: Manifest = serde_json::from_slice(&fs::read(manifest_file)?)
+        .map_err(|err| format!("Invalid manifest {}: {}", manifest_file, err))?;
+    if manifest.entries.is_empty() {
+        return Err(format!("Manifest {} has no entries", manifest_file).into());
+    }
+
+    // Step 2: Check the entries and resolve their paths against the manifest's directory
+    let base = Path::new(manifest_file).parent().unwrap_or(Path::new(""));
+    let resolve = |path: &str| base.join(path).to_string_lossy().into_owned();
+    let mut signature_files = HashSet::new();
+    let mut entries = Vec::with_capacity(manifest.entries.len());
+    for (i, entry) in manifest.entries.into_iter().enumerate() {
+        if entry.message.is_some() == entry.file.is_some() {
+            return Err(format!(
+                "Entry {} of manifest {} needs either a message or a file",
+                i, manifest_file
+            )
+            .into());
+        }
+        let entry = ManifestEntry {
+            message: entry.message,
+            file: entry.file.as_deref().map(resolve),
+            signature_file: resolve(&entry.signature_file),
+        };
+        if !signature_files.insert(entry.signature_file.clone()) {
+            return Err(format!(
+                "Manifest {} saves more than one signature to {}",
+                manifest_file, entry.signature_file
+            )
+            .into());
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Signs every entry of a manifest in one signing ceremony and saves each signature in
+/// the signature format of `options`, see [`crate::io::signature`].
+///
+/// # Arguments
+/// - `manifest_file`: Path to the manifest listing the messages and files to sign.
+/// - `context`: Context string the entries are signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `signers`: Positions of the signers' shares in the key file.
+/// - `n`: The total number of participants.
+/// - `key_file`: Path to the file containing the generated keys.
+/// - `options`: How the messages are canonicalized and the signatures saved, see
+///   [`EnvelopeOptions`].
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+///
+/// # Errors
+/// Returns an error if the manifest is invalid, a file cannot be read, or loading keys
+/// or signing fails. No signature is saved unless all entries were signed.
+pub fn sign_manifest(
+    manifest_file: &str,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the manifest and hash its files
+    let entries = load_manifest(manifest_file)?;
+    let mut inputs = Vec::with_capacity(entries.len());
+    let mut signed_files = Vec::with_capacity(entries.len());
+    for entry in &entries {
+        let (input, signed_file) = entry.signing_input(options.canonicalization)?;
+        inputs.push(input);
+        signed_files.push(signed_file);
+    }
+
+    // Step 2: Sign all entries with the shares loaded once
+    let frost_keys = load_signing_keys(&signers, n, key_file, &options.files)?;
+    let signatures = backend::open(frost_keys.ciphersuite)?.sign_batch(
+        &frost_keys,
+        &signers,
+        &inputs,
+        context,
+        transcript_file,
+        &mut OsRng,
+    )?;
+
+    // Step 3: Save every signature with its metadata and the record of its file
+    let metadata = SignatureMetadata::new(
+        frost_keys.ciphersuite,
+        context,
+        &frost_keys.group_key,
+        signers
+            .iter()
+            .map(|&signer| frost_keys.private_shares[signer as usize].1)
+            .collect(),
+    )?;
+    let format = options.signature_format;
+    for ((entry, signature), signed_file) in entries.iter().zip(&signatures).zip(&signed_files) {
+        let bytes = match signed_file {
+            Some(signed_file) => io::signature::encode_file_signature(
+                signature,
+                &metadata,
+                signed_file,
+                format,
+                options.files.format,
+            )?,
+            None => io::signature::encode_with_metadata(
+                signature,
+                Some(&metadata),
+                format,
+                options.files.format,
+            )?,
+        };
+        fs::write(&entry.signature_file, bytes)?;
+        report(
+            "signature_saved",
+            &format!(
+                "Threshold signature of {} saved to: {}",
+                entry.name(),
+                entry.signature_file
+            ),
+            json!({
+                "signature_file": entry.signature_file,
+                "group_key_fingerprint": metadata.group_key_fingerprint,
+                "signers": metadata.signers,
+                "message": entry.message,
+                "file": signed_file,
+            }),
+        );
+    }
+
+    report(
+        "manifest_signed",
+        &format!(
+            "Signed {} entries of {} in one signing ceremony",
+            entries.len(),
+            manifest_file
+        ),
+        json!({ "manifest_file": manifest_file, "entries": entries.len() }),
+    );
+    Ok(())
+}
+
+/// Verifies the signature of every entry of a manifest, reporting each entry as passed
+/// or failed.
+///
+/// # Arguments
+/// - `manifest_file`: Path to the manifest listing the messages and files and their
+///   signature files.
+/// - `context`: Context string the entries were signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to the key file or participant share file containing the group public key.
+/// - `sig_format`: Format the signatures were saved in, detected from each file if `None`.
+/// - `options`: How the messages were canonicalized, see [`EnvelopeOptions`].
+///
+/// # Errors
+/// Returns an error if the manifest is invalid, or once all entries are checked, an
+/// [`InvalidSignature`] error if the signature of any entry failed to verify.
+pub fn verify_manifest(
+    manifest_file: &str,
+    context: &str,
+    key_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &EnvelopeOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the manifest
+    let entries = load_manifest(manifest_file)?;
+
+    // Step 2: Verify every entry, carrying on past the ones that fail
+    let mut failed = 0;
+    for entry in &entries {
+        let result = match (&entry.message, &entry.file) {
+            (_, Some(file)) => check_file_signature(
+                file,
+                context,
+                key_file,
+                &entry.signature_file,
+                sig_format,
+                &options.files,
+            ),
+            (Some(message), None) => check_signature(
+                message,
+                context,
+                key_file,
+                &entry.signature_file,
+                sig_format,
+                options,
+            ),
+            (None, None) => Err("A manifest entry needs either a message or a file".into()),
+        };
+        match result {
+            Ok((_, mut fields)) => {
+                fields["entry"] = json!(entry.name());
+                report(
+                    "manifest_entry",
+                    &format!("PASS {} ({})", entry.name(), entry.signature_file),
+                    fields,
+                );
+            }
+            Err(err) => {
+                failed += 1;
+                report(
+                    "manifest_entry",
+                    &format!("FAIL {} ({}): {}", entry.name(), entry.signature_file, err),
+                    json!({
+                        "valid": false,
+                        "entry": entry.name(),
+                        "signature_file": entry.signature_file,
+                        "error": err.to_string(),
+                    }),
+                );
+            }
+        }
+    }
+
+    // Step 3: Report the totals and fail if any entry failed
+    let passed = entries.len() - failed;
+    report(
+        "manifest_verified",
+        &format!(
+            "{} of {} entries of {} verified",
+            passed,
+            entries.len(),
+            manifest_file
+        ),
+        json!({
+            "valid": failed == 0,
+            "manifest_file": manifest_file,
+            "passed": passed,
+            "failed": failed,
+        }),
+    );
+    if failed > 0 {
+        return Err(InvalidSignature(format!(
+            "{} of {} entries of {} failed to verify",
+            failed,
+            entries.len(),
+            manifest_file
+        ))
+        .into());
+    }
+    Ok(())
+}
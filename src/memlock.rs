@@ -0,0 +1,160 @@
+//! Optional locking of secrets in memory.
+//!
+//! Shares and nonces are wiped when they are dropped, but until then the operating
+//! system is free to write the pages holding them to swap, where they outlive the
+//! process. Once [`enable`] has been called, every decrypted share, dealer key file and
+//! set of secret nonces is kept in a [`Locked`] allocation whose pages are locked with
+//! `mlock` (`VirtualLock` on Windows) until the value is dropped.
+//!
+//! Locking is off by default, because most systems only allow a process to lock a few
+//! kilobytes to megabytes of memory, see `ulimit -l`. Builds without the `native`
+//! feature, such as WebAssembly, cannot lock memory and refuse to load secrets once
+//! locking is enabled.
+
+use crate::signing::{CommitmentPool, SecretCommitments};
+use crate::{FrostKeys, ParticipantShare};
+use std::mem;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Locks every secret loaded from now on in memory.
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether secrets are locked in memory, see [`enable`].
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A value holding secret bytes that can be locked in memory.
+pub trait Secret {
+    /// Returns the start and length of every memory region holding secret bytes,
+    /// borrowed mutably since the regions are wiped through the returned pointers.
+    fn secret_regions(&mut self) -> Vec<(*mut u8, usize)>;
+}
+
+impl Secret for ParticipantShare {
+    fn secret_regions(&mut self) -> Vec<(*mut u8, usize)> {
+        vec![(self.share.as_mut_ptr(), self.share.len())]
+    }
+}
+
+impl Secret for FrostKeys {
+    fn secret_regions(&mut self) -> Vec<(*mut u8, usize)> {
+        vec![(
+            self.private_shares.as_mut_ptr().cast(),
+            mem::size_of_val(self.private_shares.as_slice()),
+        )]
+    }
+}
+
+impl Secret for SecretCommitments {
+    fn secret_regions(&mut self) -> Vec<(*mut u8, usize)> {
+        vec![(
+            self.nonces.as_mut_ptr().cast(),
+            mem::size_of_val(self.nonces.as_slice()),
+        )]
+    }
+}
+
+impl Secret for CommitmentPool {
+    fn secret_regions(&mut self) -> Vec<(*mut u8, usize)> {
+        vec![(
+            self.nonces.as_mut_ptr().cast(),
+            mem::size_of_val(self.nonces.as_slice()),
+        )]
+    }
+}
+
+/// A secret kept on the heap with its pages locked in memory while locking is enabled.
+///
+/// The value can only be read, so its secret regions never move while they are locked.
+/// On drop the locked regions are wiped and unlocked before the value itself is dropped.
+pub struct Locked<T: Secret> {
+    value: Box<T>,
+    regions: Vec<(*mut u8, usize)>,
+}
+
+impl<T: Secret> Locked<T> {
+    /// Moves `value` to the heap and locks its secret regions if locking is enabled.
+    ///
+    /// # Errors
+    /// Returns an error if the operating system refuses to lock the memory, usually
+    /// because the process reached its locked memory limit.
+    pub fn new(value: T) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut locked = Locked {
+            value: Box::new(value),
+            regions: Vec::new(),
+        };
+        if !is_enabled() {
+            return Ok(locked);
+        }
+        for (ptr, len) in locked.value.secret_regions() {
+            if len == 0 {
+                continue;
+            }
+            // SAFETY: The region was derived from a mutable borrow of the boxed value,
+            // which is neither moved nor modified until the region is unlocked on drop.
+            if !unsafe { mlock(ptr, len) } {
+                return Err(LOCK_FAILED.into());
+            }
+            locked.regions.push((ptr, len));
+        }
+        Ok(locked)
+    }
+}
+
+// SAFETY: The regions only point into the boxed value, which the `Locked` owns and never
+// hands out mutably, so it can be moved to or shared with another thread like the value.
+unsafe impl<T: Secret + Send> Send for Locked<T> {}
+unsafe impl<T: Secret + Sync> Sync for Locked<T> {}
+
+impl<T: Secret> Deref for Locked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Secret> Drop for Locked<T> {
+    fn drop(&mut self) {
+        for &(ptr, len) in &self.regions {
+            // SAFETY: The region was locked in `new` and the value is still alive.
+            // `munlock` wipes the region before unlocking it.
+            unsafe {
+                munlock(ptr, len);
+            }
+        }
+    }
+}
+
+#[cfg(native)]
+const LOCK_FAILED: &str =
+    "Failed to lock secrets in memory, check the locked memory limit (ulimit -l)";
+
+#[cfg(not(native))]
+const LOCK_FAILED: &str = "Locking secrets in memory needs the native feature";
+
+/// Locks a memory region, returning whether it worked.
+#[cfg(native)]
+unsafe fn mlock(ptr: *mut u8, len: usize) -> bool {
+    memsec::mlock(ptr, len)
+}
+
+#[cfg(not(native))]
+unsafe fn mlock(_ptr: *mut u8, _len: usize) -> bool {
+    false
+}
+
+/// Wipes a region locked with [`mlock`] and unlocks it.
+#[cfg(native)]
+unsafe fn munlock(ptr: *mut u8, len: usize) {
+    memsec::munlock(ptr, len);
+}
+
+#[cfg(not(native))]
+unsafe fn munlock(_ptr: *mut u8, _len: usize) {}
@@ -0,0 +1,138 @@
+//! Serializable types of every protocol message, with stable schemas.
+//!
+//! The round commands exchange their messages as files, and [`crate::net`] moves those
+//! files. An application with its own exchange mechanism, such as a message queue or a
+//! web frontend, sends the same messages as the types of this module:
+//!
+//! | Message | Type | frost-dalek name |
+//! |---|---|---|
+//! | DKG round one broadcast of a participant | [`Round1Message`] | [`Participant`] |
+//! | Proof of knowledge of a participant's secret | [`ProofOfSecretKey`] | [`NizkOfSecretKey`] |
+//! | DKG secret share for one receiver | [`Round2Message`] | [`SecretShare`] |
+//! | Signer's public nonce commitments | [`PublicCommitments`] | [`PublicCommitmentShareList`] |
+//! | Signer's partial signature | [`PartialSignature`] | [`PartialThresholdSignature`] |
+//!
+//! Any of them is sent as a [`RoundMessage`], which [`encode`] wraps in a
+//! [`MessageEnvelope`] with the schema version, see [`MESSAGE_VERSION`]:
+//!
+//! ```json
+//! { "version": 1, "message": { "partial_signature": { "session_id": "...", "index": 2, "z": [...] } } }
+//! ```
+//!
+//! Points, scalars and shares are 32-byte arrays in their canonical compressed
+//! encoding. Fields may be added within a schema version, so readers ignore unknown
+//! fields. Any other change bumps the version.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+
+pub use crate::dkg::{ProofOfSecretKey, Round1Message, Round2Message};
+pub use crate::signing::{PartialSignature, PublicCommitments};
+
+/// A participant's DKG round one broadcast, named as in frost-dalek.
+pub type Participant = Round1Message;
+/// A proof of knowledge of a participant's secret, named as in frost-dalek.
+pub type NizkOfSecretKey = ProofOfSecretKey;
+/// A DKG secret share for one receiver, named as in frost-dalek.
+pub type SecretShare = Round2Message;
+/// A signer's public nonce commitments, named as in frost-dalek.
+pub type PublicCommitmentShareList = PublicCommitments;
+/// A signer's partial signature, named as in frost-dalek.
+pub type PartialThresholdSignature = PartialSignature;
+
+/// Version of the message schemas, written to every [`MessageEnvelope`].
+pub const MESSAGE_VERSION: u32 = 1;
+
+/// A message of a DKG or signing round, sent from one participant to another.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundMessage {
+    /// A participant's DKG round one broadcast.
+    DkgRound1(Round1Message),
+    /// The secret share a participant evaluated for the receiver in DKG round two.
+    DkgShare(Round2Message),
+    /// A signer's public nonce commitments.
+    Commitments(PublicCommitments),
+    /// A signer's partial signature.
+    PartialSignature(PartialSignature),
+}
+
+impl RoundMessage {
+    /// Returns which round the message belongs to.
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            RoundMessage::DkgRound1(_) => MessageKind::DkgRound1,
+            RoundMessage::DkgShare(_) => MessageKind::DkgShare,
+            RoundMessage::Commitments(_) => MessageKind::Commitments,
+            RoundMessage::PartialSignature(_) => MessageKind::PartialSignature,
+        }
+    }
+}
+
+/// The kind of a [`RoundMessage`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    DkgRound1,
+    DkgShare,
+    Commitments,
+    PartialSignature,
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MessageKind::DkgRound1 => "dkg_round1",
+            MessageKind::DkgShare => "dkg_share",
+            MessageKind::Commitments => "commitments",
+            MessageKind::PartialSignature => "partial_signature",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A round message with the version of its schema.
+#[derive(Serialize, Deserialize)]
+pub struct MessageEnvelope {
+    /// Schema version, see [`MESSAGE_VERSION`].
+    pub version: u32,
+    pub message: RoundMessage,
+}
+
+/// Encodes a round message as the JSON of its [`MessageEnvelope`].
+///
+/// # Errors
+/// Returns an error if the message cannot be serialized.
+pub fn encode(message: &RoundMessage) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_vec(
+        &json!({ "version": MESSAGE_VERSION, "message": message }),
+    )?)
+}
+
+/// Decodes a round message encoded by [`encode`].
+///
+/// # Errors
+/// Returns an error if the bytes are not a message envelope, or its schema version is
+/// not [`MESSAGE_VERSION`].
+pub fn decode(bytes: &[u8]) -> Result<RoundMessage, Box<dyn std::error::Error>> {
+    // Step 1: Check the schema version before parsing the message by its schema
+    let value: Value =
+        serde_json::from_slice(bytes).map_err(|err| format!("Invalid round message: {}", err))?;
+    match value.get("version").and_then(Value::as_u64) {
+        Some(version) if version == u64::from(MESSAGE_VERSION) => {}
+        Some(version) => {
+            return Err(format!(
+                "Round message has schema version {}, this frost-cli reads version {}",
+                version, MESSAGE_VERSION
+            )
+            .into())
+        }
+        None => return Err("Round message has no schema version".into()),
+    }
+
+    // Step 2: Parse the message
+    let envelope: MessageEnvelope =
+        serde_json::from_value(value).map_err(|err| format!("Invalid round message: {}", err))?;
+    Ok(envelope.message)
+}
@@ -0,0 +1,280 @@
+//! Prometheus metrics of the coordinator servers, the signing API and the signer daemon.
+//!
+//! Signing ceremonies, partial signatures and verifications are counted in process-wide
+//! atomics as they happen, and [`render`] writes them out in the Prometheus text format:
+//!
+//! - `frost_ceremonies_started_total`, `frost_ceremonies_completed_total` and
+//!   `frost_ceremonies_failed_total` count the coordinator sessions and signing API
+//!   sessions that were created, made their signature, or failed to aggregate or sign.
+//! - `frost_partial_signatures_received_total` counts the partial signatures sent to a
+//!   coordinator session.
+//! - `frost_verifications_total` counts partial signature and signature verifications
+//!   by their `kind` and `result`.
+//! - `frost_round_duration_seconds` is a histogram of how long each `round` took, see
+//!   [`Round`].
+//!
+//! The HTTP coordinator and the signing API answer `GET /metrics` on their own address,
+//! and `serve` answers it on a separate address for the gRPC coordinator and the
+//! signer daemon, all of them with the `native` feature. The metrics hold no secrets,
+//! messages or session IDs.
+
+#[cfg(native)]
+use crate::io::output::report;
+#[cfg(native)]
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+#[cfg(native)]
+use serde_json::json;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds of the round duration buckets in seconds, from a signer's computation to
+/// approvals that wait on people for a day.
+const BUCKETS: [f64; 14] = [
+    0.001, 0.01, 0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0, 900.0, 3600.0, 21600.0, 86400.0,
+];
+
+/// A step of a ceremony whose duration is recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Round {
+    /// A coordinator session from its creation until every signer committed.
+    Commitments,
+    /// A coordinator session from the last commitment until its signature.
+    PartialSignatures,
+    /// A signing API session from its creation until its signature.
+    Approvals,
+    /// Signing round one in the signer daemon.
+    Commit,
+    /// Signing round two in the signer daemon.
+    PartialSign,
+}
+
+impl Round {
+    const ALL: [Round; 5] = [
+        Round::Commitments,
+        Round::PartialSignatures,
+        Round::Approvals,
+        Round::Commit,
+        Round::PartialSign,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Round::Commitments => "commitments",
+            Round::PartialSignatures => "partial_signatures",
+            Round::Approvals => "approvals",
+            Round::Commit => "commit",
+            Round::PartialSign => "partial_sign",
+        }
+    }
+}
+
+/// What a verification checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// A signer's partial signature, before aggregation.
+    PartialSignature,
+    /// A threshold signature.
+    Signature,
+}
+
+impl Verification {
+    const ALL: [Verification; 2] = [Verification::PartialSignature, Verification::Signature];
+
+    fn label(self) -> &'static str {
+        match self {
+            Verification::PartialSignature => "partial_signature",
+            Verification::Signature => "signature",
+        }
+    }
+}
+
+/// Cumulative bucket counts, count and sum of one round's durations.
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Histogram {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Counts the duration before its buckets, so a bucket read before the count never
+    /// exceeds it.
+    fn observe(&self, duration: Duration) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter().zip(BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        self.sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+}
+
+static CEREMONIES_STARTED: AtomicU64 = AtomicU64::new(0);
+static CEREMONIES_COMPLETED: AtomicU64 = AtomicU64::new(0);
+static CEREMONIES_FAILED: AtomicU64 = AtomicU64::new(0);
+static PARTIAL_SIGNATURES_RECEIVED: AtomicU64 = AtomicU64::new(0);
+/// Verifications by kind, then invalid and valid.
+static VERIFICATIONS: [[AtomicU64; 2]; Verification::ALL.len()] =
+    [const { [const { AtomicU64::new(0) }; 2] }; Verification::ALL.len()];
+static ROUND_DURATIONS: [Histogram; Round::ALL.len()] =
+    [const { Histogram::new() }; Round::ALL.len()];
+
+/// Counts a ceremony that was started.
+pub(crate) fn ceremony_started() {
+    CEREMONIES_STARTED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a ceremony that made its signature.
+pub(crate) fn ceremony_completed() {
+    CEREMONIES_COMPLETED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a ceremony whose signature could not be made.
+pub(crate) fn ceremony_failed() {
+    CEREMONIES_FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a partial signature sent to a coordinator session.
+pub(crate) fn partial_signature_received() {
+    PARTIAL_SIGNATURES_RECEIVED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts the result of a verification.
+pub(crate) fn verification(kind: Verification, valid: bool) {
+    VERIFICATIONS[kind as usize][usize::from(valid)].fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records how long a round took.
+pub(crate) fn round_finished(round: Round, duration: Duration) {
+    ROUND_DURATIONS[round as usize].observe(duration);
+}
+
+/// Returns every metric in the Prometheus text format.
+pub fn render() -> String {
+    let mut out = String::new();
+    for (name, help, counter) in [
+        (
+            "frost_ceremonies_started_total",
+            "Signing ceremonies started.",
+            &CEREMONIES_STARTED,
+        ),
+        (
+            "frost_ceremonies_completed_total",
+            "Signing ceremonies that made their signature.",
+            &CEREMONIES_COMPLETED,
+        ),
+        (
+            "frost_ceremonies_failed_total",
+            "Signing ceremonies whose signature could not be made.",
+            &CEREMONIES_FAILED,
+        ),
+        (
+            "frost_partial_signatures_received_total",
+            "Partial signatures sent to coordinator sessions.",
+            &PARTIAL_SIGNATURES_RECEIVED,
+        ),
+    ] {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} counter", name);
+        let _ = writeln!(out, "{} {}", name, counter.load(Ordering::Relaxed));
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP frost_verifications_total Partial signature and signature verifications by result."
+    );
+    let _ = writeln!(out, "# TYPE frost_verifications_total counter");
+    for kind in Verification::ALL {
+        for (result, count) in ["invalid", "valid"]
+            .iter()
+            .zip(&VERIFICATIONS[kind as usize])
+        {
+            let _ = writeln!(
+                out,
+                "frost_verifications_total{{kind=\"{}\",result=\"{}\"}} {}",
+                kind.label(),
+                result,
+                count.load(Ordering::Relaxed)
+            );
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "# HELP frost_round_duration_seconds How long each round of a ceremony took."
+    );
+    let _ = writeln!(out, "# TYPE frost_round_duration_seconds histogram");
+    for round in Round::ALL {
+        let histogram = &ROUND_DURATIONS[round as usize];
+        for (bound, bucket) in BUCKETS.iter().zip(&histogram.buckets) {
+            let _ = writeln!(
+                out,
+                "frost_round_duration_seconds_bucket{{round=\"{}\",le=\"{}\"}} {}",
+                round.label(),
+                bound,
+                bucket.load(Ordering::SeqCst)
+            );
+        }
+        let count = histogram.count.load(Ordering::SeqCst);
+        let _ = writeln!(
+            out,
+            "frost_round_duration_seconds_bucket{{round=\"{}\",le=\"+Inf\"}} {}",
+            round.label(),
+            count
+        );
+        let _ = writeln!(
+            out,
+            "frost_round_duration_seconds_sum{{round=\"{}\"}} {}",
+            round.label(),
+            histogram.sum_micros.load(Ordering::Relaxed) as f64 / 1e6
+        );
+        let _ = writeln!(
+            out,
+            "frost_round_duration_seconds_count{{round=\"{}\"}} {}",
+            round.label(),
+            count
+        );
+    }
+    out
+}
+
+/// Answers `GET /metrics` with [`render`].
+#[cfg(native)]
+pub(crate) async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render(),
+    )
+}
+
+/// Answers `GET /metrics` on `listen` until the process is stopped, for the modes that
+/// do not serve HTTP themselves.
+///
+/// # Errors
+/// Returns an error if the address cannot be bound.
+#[cfg(native)]
+pub fn serve(listen: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let app = Router::new().route("/metrics", get(metrics_handler));
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(listen).await?;
+        report(
+            "metrics_listening",
+            &format!("Metrics served on http://{}/metrics", listen),
+            json!({ "listen": listen }),
+        );
+        axum::serve(listener, app).await?;
+        Ok::<(), Box<dyn std::error::Error>>(())
+    })
+}
@@ -0,0 +1,167 @@
+//! Upgrades of key, share and signature files to the current format.
+//!
+//! Key files and signature files carry a `version` field, see
+//! [`crate::FORMAT_VERSION`], and loaders refuse files of any other version. Since
+//! version 2, key, share and signature files also carry a checksum that every loader
+//! checks. Files written before that are upgraded by [`migrate`]: a key file gains its
+//! `version` field, a signature file that is a bare JSON array of bytes is wrapped into a
+//! [`SignatureFile`], and every file gains its checksum.
+//!
+//! Given a [`FileOptions::file_key`], [`migrate`] also keys the checksum of a key file or
+//! share file that still carries a digest, see [`crate::write_checked_json`].
+
+use crate::io::files::MAC_PREFIX;
+use crate::io::output::report;
+use crate::{
+    add_checksum, add_secret_checksum, parse_versioned, read_json, verify_checksum, verify_digest,
+    write_json, FileOptions, FrostKeys, ParticipantShare, SignatureFile, FORMAT_VERSION,
+};
+use serde_json::{json, Map, Value};
+use std::fs;
+
+/// Upgrades a key file, share file or signature file to the current format.
+///
+/// Unless `output_file` is given, the file is upgraded in place and the original is
+/// kept next to it with a `.bak` extension.
+///
+/// # Arguments
+/// - `file`: Path to the file to upgrade.
+/// - `output_file`: Path to save the upgraded file to, if not in place.
+/// - `options`: How the upgraded file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the file is not a key, share or signature file, was written by
+/// a newer release, or already has a checksum it does not match.
+pub fn migrate(
+    file: &str,
+    output_file: Option<&str>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Recognize the file and bring it to the current version
+    let mut upgraded = match read_json(file)? {
+        Value::Array(signature) => {
+            let signature: Vec<u8> = serde_json::from_value(Value::Array(signature))
+                .map_err(|_| format!("{} is not a signature file", file))?;
+            serde_json::to_value(SignatureFile {
+                version: FORMAT_VERSION,
+                signature,
+                metadata: None,
+                file: None,
+                timestamp_token: None,
+            })?
+        }
+        Value::Object(mut object) => {
+            let kind = kind(&object, file)?;
+            if needs_key(&object, kind, options) {
+                // The digest is checked before the file is keyed.
+                verify_digest(Value::Object(object.clone()), file, kind)?;
+            } else if object.contains_key("checksum") {
+                check(Value::Object(object), file, options)?;
+                report(
+                    "file_current",
+                    &format!("{} is already in the current format", file),
+                    json!({ "file": file, "version": FORMAT_VERSION }),
+                );
+                return Ok(());
+            } else if !object.contains_key("share") {
+                match object.get("version").and_then(Value::as_u64) {
+                    Some(version) if version >= u64::from(FORMAT_VERSION) => {
+                        return Err(format!(
+                            "{} has format version {} but no checksum",
+                            file, version
+                        )
+                        .into())
+                    }
+                    _ => {
+                        object.insert("version".to_string(), FORMAT_VERSION.into());
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        _ => return Err(format!("{} is not a key, share or signature file", file).into()),
+    };
+
+    // Step 2: Add the checksum and parse the result strictly
+    if upgraded.get("signature").is_some() {
+        add_checksum(&mut upgraded)?;
+    } else {
+        add_secret_checksum(&mut upgraded, options.file_key.as_ref())?;
+    }
+    check(upgraded.clone(), file, options)?;
+
+    // Step 3: Save the upgraded file, keeping the original when upgrading in place
+    let output_file = match output_file {
+        Some(output_file) => output_file.to_string(),
+        None => {
+            let backup_file = format!("{}.bak", file);
+            fs::copy(file, &backup_file)?;
+            report(
+                "backup_saved",
+                &format!("Original kept at: {}", backup_file),
+                json!({ "file": file, "backup_file": backup_file }),
+            );
+            file.to_string()
+        }
+    };
+    write_json(&output_file, &upgraded, options)?;
+
+    report(
+        "file_migrated",
+        &format!(
+            "{} upgraded to format version {}: {}",
+            file, FORMAT_VERSION, output_file
+        ),
+        json!({ "file": file, "version": FORMAT_VERSION, "output_file": output_file }),
+    );
+    Ok(())
+}
+
+/// Parses a file in the current format strictly, by what kind of file it is.
+fn check(
+    value: Value,
+    file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if value.get("private_shares").is_some() {
+        parse_versioned::<FrostKeys>(value, file, "key", options.file_key.as_ref())?;
+    } else if value.get("signature").is_some() {
+        parse_versioned::<SignatureFile>(value, file, "signature", None)?;
+    } else if value.get("share").is_some() {
+        serde_json::from_value::<ParticipantShare>(verify_checksum(
+            value,
+            file,
+            "share",
+            options.file_key.as_ref(),
+        )?)?;
+    } else {
+        return Err(format!("{} is not a key, share or signature file", file).into());
+    }
+    Ok(())
+}
+
+/// Returns what kind of file a JSON object is, as its errors name it.
+fn kind(
+    object: &Map<String, Value>,
+    file: &str,
+) -> Result<&'static str, Box<dyn std::error::Error>> {
+    if object.contains_key("private_shares") {
+        Ok("key")
+    } else if object.contains_key("signature") {
+        Ok("signature")
+    } else if object.contains_key("share") {
+        Ok("share")
+    } else {
+        Err(format!("{} is not a key, share or signature file", file).into())
+    }
+}
+
+/// Returns whether a key or share file carries a digest although `options` has a file
+/// key, so its checksum is to be keyed.
+fn needs_key(object: &Map<String, Value>, kind: &str, options: &FileOptions) -> bool {
+    let digest = matches!(
+        object.get("checksum").and_then(Value::as_str),
+        Some(checksum) if !checksum.starts_with(MAC_PREFIX)
+    );
+    digest && kind != "signature" && options.file_key.is_some()
+}
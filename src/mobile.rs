@@ -0,0 +1,102 @@
+//! Kotlin and Swift bindings for signer apps, with the `uniffi` feature.
+//!
+//! A phone takes part in ceremonies as a quorum member like any other signer: it
+//! decrypts the share bundle a dealer sealed to it, see [`crate::dealer`], commits to
+//! nonces and signs once the coordinator has the roster. The app keeps the share and
+//! its secret commitments in the platform keystore, the iOS Keychain or the Android
+//! Keystore, and passes them in with every call, so they never rest in app storage in
+//! the clear. Shares, commitments and partial signatures are the JSON of the CLI's
+//! files, see [`crate::messages`].
+//!
+//! The bindings are generated from the built library with the `uniffi-bindgen` binary:
+//!
+//! ```bash
+//! cargo build --release -p frost-cli-bindings --features uniffi
+//! cargo run --features uniffi --bin uniffi-bindgen -- generate --library target/release/libfrost_cli_bindings.so --language kotlin --out-dir bindings
+//! ```
+
+use crate::error::FrostCliError;
+use crate::{participant, SIGNING_CONTEXT};
+use thiserror::Error;
+
+/// A failure of a signer operation.
+#[derive(Debug, Error, uniffi::Error)]
+pub enum SignerError {
+    /// The share could not be decoded or is not a valid share.
+    #[error("{message}")]
+    InvalidShare { message: String },
+    /// The roster is smaller than the threshold or does not include this signer.
+    #[error("{message}")]
+    InvalidParameters { message: String },
+    /// Any other failure, such as malformed JSON, a bundle sealed to another identity
+    /// or used-up secret commitments.
+    #[error("{message}")]
+    Failed { message: String },
+}
+
+impl From<Box<dyn std::error::Error>> for SignerError {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        let message = err.to_string();
+        match err.downcast_ref::<FrostCliError>() {
+            Some(FrostCliError::InvalidShare { .. }) => SignerError::InvalidShare { message },
+            Some(FrostCliError::InvalidParameters(_)) => SignerError::InvalidParameters { message },
+            _ => SignerError::Failed { message },
+        }
+    }
+}
+
+/// The result of [`commit`].
+#[derive(uniffi::Record)]
+pub struct Commitments {
+    /// The public commitments to upload to the coordinator.
+    pub public_commitments: String,
+    /// The secret nonces to keep in the platform keystore for [`partial_sign`].
+    pub secret_commitments: String,
+}
+
+/// The result of [`partial_sign`].
+#[derive(uniffi::Record)]
+pub struct PartialSignatureResult {
+    /// The partial signature to upload to the coordinator.
+    pub partial_signature: String,
+    /// The secret commitments without the nonces just used, which must replace the
+    /// stored ones before the partial signature is uploaded.
+    pub secret_commitments: String,
+}
+
+/// Decrypts a share bundle with the participant's identity and returns the JSON of the
+/// share file.
+#[uniffi::export]
+pub fn decrypt_share(identity: String, bundle: String) -> Result<String, SignerError> {
+    Ok(participant::decrypt_share(&identity, &bundle)?)
+}
+
+/// Runs signing round one for the JSON of a share file.
+#[uniffi::export]
+pub fn commit(session_id: String, share: String) -> Result<Commitments, SignerError> {
+    let (public_commitments, secret_commitments) = participant::commit(&session_id, &share)?;
+    Ok(Commitments {
+        public_commitments: public_commitments.to_string(),
+        secret_commitments: secret_commitments.to_string(),
+    })
+}
+
+/// Runs signing round two for the JSON of a share file, the secret commitments returned
+/// by [`commit`] and the roster, a JSON array of every signer's public commitments. The
+/// message is signed in `context`, or in [`crate::SIGNING_CONTEXT`] if it is null.
+#[uniffi::export]
+pub fn partial_sign(
+    message: String,
+    share: String,
+    secret_commitments: String,
+    roster: String,
+    context: Option<String>,
+) -> Result<PartialSignatureResult, SignerError> {
+    let context = context.as_deref().unwrap_or(SIGNING_CONTEXT);
+    let (partial_signature, remaining) =
+        participant::partial_sign(&message, context, &share, &secret_commitments, &roster)?;
+    Ok(PartialSignatureResult {
+        partial_signature: partial_signature.to_string(),
+        secret_commitments: remaining.to_string(),
+    })
+}
@@ -0,0 +1,536 @@
+//! Authenticated HTTP signing API backed by the keystore.
+//!
+//! Where [`crate::net::http`] moves round messages between signers on other machines,
+//! [`serve`] signs on the server itself, with participant shares kept in its keystore,
+//! once enough people approved. Services ask for signatures over plain HTTP, so the API
+//! can sit behind an API gateway instead of a service wrapping the CLI:
+//!
+//! - `POST /sessions` creates a signing session from a [`CreateSessionRequest`].
+//! - `GET /sessions/{id}` returns the [`ApprovalSession`].
+//! - `POST /sessions/{id}/approvals` approves the session with the caller's share.
+//! - `GET /sessions/{id}/signature` returns the JSON signature file once complete, which
+//!   `verify` takes as it is.
+//! - `GET /metrics` returns the Prometheus metrics, see [`crate::metrics`].
+//!
+//! Every request but `GET /metrics` carries `Authorization: Bearer <token>` of a client
+//! in the clients file, which only keeps the SHA-256 hash of each token, see
+//! [`add_client`]. Clients with `create_sessions` may create sessions, and clients with
+//! a `share` approve with the participant share kept under that name in the keystore.
+//! Once approvals from the threshold of distinct participants are in, the server signs
+//! with their shares and the session is complete.
+
+use crate::io::output::report;
+use crate::keystore::{FileKeyStore, KeyStore};
+use crate::memlock::Locked;
+use crate::metrics::{self, Round};
+use crate::net::http::api_error;
+use crate::net::store::StoreError;
+use crate::signing::sign_with_shares;
+use crate::types::{default_signing_context, SignatureFile, SignatureMetadata, FORMAT_VERSION};
+use crate::{add_checksum, read_json, write_json, FileOptions};
+use axum::extract::{Path as UrlPath, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A client allowed to call the API, as kept in the clients file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApiClient {
+    pub name: String,
+    /// Hex-encoded SHA-256 hash of the client's bearer token.
+    pub token_hash: String,
+    /// Whether the client may create signing sessions.
+    #[serde(default)]
+    pub create_sessions: bool,
+    /// Keystore name of the participant share the client approves with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub share: Option<String>,
+}
+
+/// Body of a `POST /sessions` request.
+#[derive(Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    /// Keystore name of a key of the group that signs.
+    pub key: String,
+    pub message: String,
+    /// Context string the message is signed in, [`crate::SIGNING_CONTEXT`] if left out.
+    #[serde(default = "default_signing_context")]
+    pub context: String,
+}
+
+/// Progress of an [`ApprovalSession`].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    CollectingApprovals,
+    Complete,
+    /// The approvals were in but signing with them failed, see the session's `error`.
+    Failed,
+}
+
+/// A client's approval of a session.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Approval {
+    /// Name of the approving client.
+    pub client: String,
+    /// Participant index of the client's share.
+    pub index: u32,
+    /// Unix time of the approval.
+    pub timestamp: u64,
+}
+
+/// A signing session of the API, kept as `<session_id>.json` in the sessions directory.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ApprovalSession {
+    /// Random hex ID of the session.
+    pub session_id: String,
+    pub status: ApprovalStatus,
+    /// Keystore name of the key the session was created for.
+    pub key: String,
+    pub message: String,
+    /// Context string the message is signed in.
+    #[serde(default = "default_signing_context")]
+    pub context: String,
+    /// Hex-encoded group key that signs.
+    pub group_key: String,
+    pub threshold: u32,
+    /// Name of the client that created the session.
+    pub created_by: String,
+    /// Unix time the session was created at.
+    #[serde(default)]
+    pub created_at: u64,
+    pub approvals: Vec<Approval>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SignatureMetadata>,
+    /// Why signing failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What the API handlers share.
+struct Api {
+    keystore: Arc<dyn KeyStore + Send + Sync>,
+    clients: Vec<ApiClient>,
+    sessions_dir: PathBuf,
+    /// How the session files are written.
+    options: FileOptions,
+    /// Serializes every read-modify-write of a session.
+    lock: Mutex<()>,
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+/// Runs the signing API with the keys of a keystore directory until the process is
+/// stopped.
+///
+/// # Arguments
+/// - `listen`: Address to listen on, for example `127.0.0.1:8443`.
+/// - `keystore`: The keystore directory holding the approvers' shares.
+/// - `clients_file`: Path to the clients allowed to call the API, see [`add_client`].
+/// - `sessions_dir`: Directory to keep the sessions in.
+/// - `options`: How the sessions and the shares of the keystore are written and read.
+///
+/// # Errors
+/// Returns an error if the clients file cannot be read, the directory cannot be
+/// created, or the address cannot be bound.
+pub fn serve(
+    listen: &str,
+    keystore: &Path,
+    clients_file: &str,
+    sessions_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    serve_with_keystore(
+        listen,
+        Arc::new(FileKeyStore::new(keystore).with_options(options.clone())),
+        clients_file,
+        sessions_dir,
+        options,
+    )
+}
+
+/// Runs the signing API like [`serve`] with the shares of any [`KeyStore`].
+///
+/// # Errors
+/// Returns an error if the clients file cannot be read, the directory cannot be
+/// created, or the address cannot be bound.
+pub fn serve_with_keystore(
+    listen: &str,
+    keystore: Arc<dyn KeyStore + Send + Sync>,
+    clients_file: &str,
+    sessions_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let clients: Vec<ApiClient> = read_json(clients_file)?;
+    if clients.is_empty() {
+        return Err(format!(
+            "No clients in {}, add one with `api-client add`",
+            clients_file
+        )
+        .into());
+    }
+    fs::create_dir_all(sessions_dir)?;
+    let api = Arc::new(Api {
+        keystore,
+        clients,
+        sessions_dir: PathBuf::from(sessions_dir),
+        options: options.clone(),
+        lock: Mutex::new(()),
+    });
+    let app = Router::new()
+        .route("/sessions", post(create_session))
+        .route("/sessions/:session_id", get(get_session))
+        .route("/sessions/:session_id/approvals", post(approve))
+        .route("/sessions/:session_id/signature", get(get_signature))
+        .route("/metrics", get(metrics::metrics_handler))
+        .with_state(api);
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(listen).await?;
+        report(
+            "api_listening",
+            &format!(
+                "Signing API listening on {}, storing sessions in: {}",
+                listen, sessions_dir
+            ),
+            json!({ "listen": listen, "sessions_dir": sessions_dir }),
+        );
+        axum::serve(listener, app).await?;
+        Ok::<(), Box<dyn std::error::Error>>(())
+    })
+}
+
+/// Adds a client to the clients file, creating it if needed, and returns the client's
+/// new bearer token. Only its hash is kept, so the token cannot be shown again.
+///
+/// # Arguments
+/// - `clients_file`: Path to the clients file.
+/// - `name`: Name of the client, recorded with its approvals.
+/// - `create_sessions`: Whether the client may create signing sessions.
+/// - `share`: Keystore name of the participant share the client approves with.
+/// - `options`: How the clients file is written.
+///
+/// # Errors
+/// Returns an error if the clients file cannot be read or written, or already has a
+/// client called `name`.
+pub fn add_client(
+    clients_file: &str,
+    name: &str,
+    create_sessions: bool,
+    share: Option<&str>,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut clients: Vec<ApiClient> = if Path::new(clients_file).exists() {
+        read_json(clients_file)?
+    } else {
+        Vec::new()
+    };
+    if clients.iter().any(|client| client.name == name) {
+        return Err(format!("{} already has a client called {}", clients_file, name).into());
+    }
+
+    let mut token = [0u8; 32];
+    OsRng.fill_bytes(&mut token);
+    let token = hex::encode(token);
+    clients.push(ApiClient {
+        name: name.to_string(),
+        token_hash: token_hash(&token),
+        create_sessions,
+        share: share.map(str::to_string),
+    });
+    write_json(clients_file, &clients, options)?;
+
+    report(
+        "api_client_added",
+        &format!(
+            "Client {} added to {}, its bearer token is shown only once: {}",
+            name, clients_file, token
+        ),
+        json!({ "name": name, "clients_file": clients_file, "token": token }),
+    );
+    Ok(token)
+}
+
+async fn create_session(
+    State(api): State<Arc<Api>>,
+    headers: HeaderMap,
+    Json(request): Json<CreateSessionRequest>,
+) -> ApiResult<ApprovalSession> {
+    let client = api.authenticate(&headers)?;
+    if !client.create_sessions {
+        return Err(api_error(StoreError::Forbidden(format!(
+            "Client {} may not create sessions",
+            client.name
+        ))));
+    }
+    let (ciphersuite, group_key) = api
+        .keystore
+        .load_group_key(&request.key)
+        .map_err(|err| api_error(StoreError::Invalid(err.to_string())))?;
+    if !ciphersuite.is_ristretto255() {
+        return Err(api_error(StoreError::Invalid(format!(
+            "Key {} is not a ristretto255 key, which the API signs with",
+            request.key
+        ))));
+    }
+    let threshold = api
+        .keystore
+        .list_keys()
+        .map_err(internal)?
+        .into_iter()
+        .find(|key| key.name == request.key)
+        .map(|key| key.threshold)
+        .ok_or_else(|| {
+            api_error(StoreError::NotFound(format!(
+                "Unknown key: {}",
+                request.key
+            )))
+        })?;
+
+    let mut session_id = [0u8; 16];
+    OsRng.fill_bytes(&mut session_id);
+    let session = ApprovalSession {
+        session_id: hex::encode(session_id),
+        status: ApprovalStatus::CollectingApprovals,
+        key: request.key,
+        message: request.message,
+        context: request.context,
+        group_key: hex::encode(group_key),
+        threshold,
+        created_by: client.name.clone(),
+        created_at: unix_time()?,
+        approvals: Vec::new(),
+        signature: None,
+        metadata: None,
+        error: None,
+    };
+    write_json(
+        api.session_file(&session.session_id),
+        &session,
+        &api.options,
+    )
+    .map_err(internal)?;
+    metrics::ceremony_started();
+
+    report(
+        "api_session_created",
+        &format!(
+            "Client {} created signing session {} for key {}",
+            client.name, session.session_id, session.key
+        ),
+        json!({
+            "client": client.name,
+            "session_id": session.session_id,
+            "key": session.key,
+        }),
+    );
+    Ok(Json(session))
+}
+
+async fn get_session(
+    State(api): State<Arc<Api>>,
+    headers: HeaderMap,
+    UrlPath(session_id): UrlPath<String>,
+) -> ApiResult<ApprovalSession> {
+    api.authenticate(&headers)?;
+    Ok(Json(api.load(&session_id)?))
+}
+
+async fn approve(
+    State(api): State<Arc<Api>>,
+    headers: HeaderMap,
+    UrlPath(session_id): UrlPath<String>,
+) -> ApiResult<ApprovalSession> {
+    let client = api.authenticate(&headers)?;
+    let share_name = client.share.as_deref().ok_or_else(|| {
+        api_error(StoreError::Forbidden(format!(
+            "Client {} has no share to approve with",
+            client.name
+        )))
+    })?;
+    let _guard = api.lock.lock().map_err(internal)?;
+
+    // Step 1: Check that the client's share belongs to the session's group and has not
+    // approved yet
+    let mut session = api.load(&session_id)?;
+    if session.status != ApprovalStatus::CollectingApprovals {
+        return Err(api_error(StoreError::Rejected(format!(
+            "Session {} is no longer collecting approvals",
+            session_id
+        ))));
+    }
+    let share =
+        Locked::new(api.keystore.load_share(share_name).map_err(internal)?).map_err(internal)?;
+    if hex::encode(share.group_key) != session.group_key {
+        return Err(api_error(StoreError::Forbidden(format!(
+            "Share of client {} belongs to a different group",
+            client.name
+        ))));
+    }
+    if session
+        .approvals
+        .iter()
+        .any(|approval| approval.index == share.index)
+    {
+        return Err(api_error(StoreError::Rejected(format!(
+            "Participant {} already approved session {}",
+            share.index, session_id
+        ))));
+    }
+    let now = unix_time()?;
+    session.approvals.push(Approval {
+        client: client.name.clone(),
+        index: share.index,
+        timestamp: now,
+    });
+
+    // Step 2: Sign with the approvers' shares once the threshold approved
+    if session.approvals.len() as u32 >= session.threshold {
+        match api.sign(&session) {
+            Ok((signature, metadata)) => {
+                session.status = ApprovalStatus::Complete;
+                session.signature = Some(signature);
+                session.metadata = Some(metadata);
+                metrics::ceremony_completed();
+            }
+            Err(err) => {
+                session.status = ApprovalStatus::Failed;
+                session.error = Some(err.to_string());
+                metrics::ceremony_failed();
+            }
+        }
+        if session.created_at > 0 {
+            metrics::round_finished(
+                Round::Approvals,
+                Duration::from_secs(now.saturating_sub(session.created_at)),
+            );
+        }
+    }
+    write_json(api.session_file(&session_id), &session, &api.options).map_err(internal)?;
+
+    report(
+        "api_session_approved",
+        &format!(
+            "Client {} approved signing session {} as participant {}, {} of {} approvals",
+            client.name,
+            session_id,
+            share.index,
+            session.approvals.len(),
+            session.threshold
+        ),
+        json!({
+            "client": client.name,
+            "session_id": session_id,
+            "index": share.index,
+            "status": session.status,
+        }),
+    );
+    Ok(Json(session))
+}
+
+async fn get_signature(
+    State(api): State<Arc<Api>>,
+    headers: HeaderMap,
+    UrlPath(session_id): UrlPath<String>,
+) -> ApiResult<serde_json::Value> {
+    api.authenticate(&headers)?;
+    let session = api.load(&session_id)?;
+    let signature = session.signature.ok_or_else(|| {
+        api_error(StoreError::NotFound(
+            "Session is not complete yet".to_string(),
+        ))
+    })?;
+    let mut signature_file = serde_json::to_value(SignatureFile {
+        version: FORMAT_VERSION,
+        signature,
+        metadata: session.metadata,
+        file: None,
+        timestamp_token: None,
+    })
+    .map_err(internal)?;
+    add_checksum(&mut signature_file).map_err(internal)?;
+    Ok(Json(signature_file))
+}
+
+impl Api {
+    /// Returns the client whose bearer token the request carries.
+    fn authenticate(&self, headers: &HeaderMap) -> Result<&ApiClient, (StatusCode, String)> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or((StatusCode::UNAUTHORIZED, "Missing bearer token".to_string()))?;
+        let hash = token_hash(token);
+        self.clients
+            .iter()
+            .find(|client| client.token_hash == hash)
+            .ok_or((StatusCode::UNAUTHORIZED, "Unknown bearer token".to_string()))
+    }
+
+    /// Signs a session's message with the shares of its approvers.
+    fn sign(
+        &self,
+        session: &ApprovalSession,
+    ) -> Result<(Vec<u8>, SignatureMetadata), Box<dyn std::error::Error>> {
+        let mut shares = Vec::new();
+        for approval in &session.approvals {
+            let client = self
+                .clients
+                .iter()
+                .find(|client| client.name == approval.client)
+                .and_then(|client| client.share.as_deref())
+                .ok_or_else(|| format!("Client {} has no share any more", approval.client))?;
+            shares.push(Locked::new(self.keystore.load_share(client)?)?);
+        }
+        sign_with_shares(session.message.as_bytes(), &session.context, &shares, None)
+    }
+
+    fn load(&self, session_id: &str) -> Result<ApprovalSession, (StatusCode, String)> {
+        if session_id.len() != 32 || hex::decode(session_id).is_err() {
+            return Err(api_error(StoreError::Invalid(format!(
+                "Invalid session ID: {}",
+                session_id
+            ))));
+        }
+        let session_file = self.session_file(session_id);
+        if !session_file.exists() {
+            return Err(api_error(StoreError::NotFound(format!(
+                "Unknown session: {}",
+                session_id
+            ))));
+        }
+        read_json(&session_file).map_err(internal)
+    }
+
+    fn session_file(&self, session_id: &str) -> PathBuf {
+        self.sessions_dir.join(format!("{}.json", session_id))
+    }
+}
+
+/// Returns the hex-encoded SHA-256 hash a bearer token is kept as.
+fn token_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn internal<E: ToString>(err: E) -> (StatusCode, String) {
+    api_error(StoreError::Internal(err.to_string()))
+}
+
+fn unix_time() -> Result<u64, (StatusCode, String)> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(internal)?
+        .as_secs())
+}
@@ -0,0 +1,493 @@
+//! gRPC coordinator for signing sessions.
+//!
+//! Offers the same session steps as the HTTP coordinator through the `SigningSessions`
+//! service defined in `proto/frost_cli.proto`, so the coordinator can run inside
+//! existing gRPC infrastructure. Sessions are kept in a [`SessionStore`]. With
+//! [`ServerTls`] the server requires client certificates and only accepts round
+//! messages from the participant the certificate belongs to.
+
+use crate::identity::{IdentityFiles, IdentitySignature};
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::net::store::{SessionStore, StoreError};
+use crate::net::tls::{self, ClientTls, Peer, ServerTls};
+use crate::session::{partial_sign_with_session, Session, SessionStatus};
+use crate::signing::{load_secret_commitments, PartialSignature, PublicCommitments};
+use crate::{read_json, save_signature, FileOptions, SIGNING_CONTEXT};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::transport::Channel;
+use tonic::{Request, Response, Status};
+
+/// Types generated from `proto/frost_cli.proto`.
+pub mod proto {
+    tonic::include_proto!("frost_cli.v1");
+}
+
+use proto::signing_sessions_client::SigningSessionsClient;
+use proto::signing_sessions_server::{SigningSessions, SigningSessionsServer};
+
+/// The `SigningSessions` service backed by a session store.
+struct SigningSessionsService {
+    store: Arc<SessionStore>,
+    /// Participant index of each client certificate, when serving with TLS.
+    participants: Option<HashMap<Vec<u8>, u32>>,
+}
+
+impl SigningSessionsService {
+    /// Returns the client of a TLS connection, or `None` without TLS.
+    fn peer<T>(&self, request: &Request<T>) -> Option<Peer> {
+        let participants = self.participants.as_ref()?;
+        let certs = request.peer_certs();
+        let cert: Option<&[u8]> = certs
+            .as_ref()
+            .and_then(|certs| certs.first())
+            .map(|cert| cert.as_ref());
+        Some(tls::peer(participants, cert))
+    }
+}
+
+#[tonic::async_trait]
+impl SigningSessions for SigningSessionsService {
+    async fn create_session(
+        &self,
+        request: Request<proto::CreateSessionRequest>,
+    ) -> Result<Response<proto::SessionReply>, Status> {
+        let request = request.into_inner();
+        let context = match request.context.as_str() {
+            "" => SIGNING_CONTEXT,
+            context => context,
+        };
+        let session = self
+            .store
+            .create(&request.message, context, request.signers)
+            .map_err(status)?;
+        Ok(Response::new(session_reply(&session)))
+    }
+
+    async fn get_session(
+        &self,
+        request: Request<proto::GetSessionRequest>,
+    ) -> Result<Response<proto::SessionReply>, Status> {
+        let session = self
+            .store
+            .get(&request.into_inner().session_id)
+            .map_err(status)?;
+        Ok(Response::new(session_reply(&session)))
+    }
+
+    async fn submit_commitment(
+        &self,
+        request: Request<proto::Commitment>,
+    ) -> Result<Response<proto::SessionReply>, Status> {
+        let peer = self.peer(&request);
+        let commitments = commitments_from_proto(request.into_inner())?;
+        tls::authorize(peer.as_ref(), commitments.index).map_err(status)?;
+        let session = self.store.add_commitments(commitments).map_err(status)?;
+        Ok(Response::new(session_reply(&session)))
+    }
+
+    async fn submit_partial(
+        &self,
+        request: Request<proto::PartialSignature>,
+    ) -> Result<Response<proto::SessionReply>, Status> {
+        let peer = self.peer(&request);
+        let request = request.into_inner();
+        let partial = PartialSignature {
+            session_id: request.session_id,
+            index: request.index,
+            z: bytes32(&request.z)?,
+            identity_signature: request.identity_signature.map(signature_from_proto),
+        };
+        tls::authorize(peer.as_ref(), partial.index).map_err(status)?;
+        let session = self.store.add_partial_signature(partial).map_err(status)?;
+        Ok(Response::new(session_reply(&session)))
+    }
+
+    async fn get_signature(
+        &self,
+        request: Request<proto::GetSignatureRequest>,
+    ) -> Result<Response<proto::SignatureReply>, Status> {
+        let signature = self
+            .store
+            .signature(&request.into_inner().session_id)
+            .map_err(status)?;
+        Ok(Response::new(proto::SignatureReply { signature }))
+    }
+}
+
+/// Runs the gRPC coordinator server until the process is stopped.
+///
+/// # Arguments
+/// - `listen`: Address to listen on, for example `0.0.0.0:50051`.
+/// - `key_file`: Path to a key file or participant share file of the group.
+/// - `sessions_dir`: Directory to keep the session manifests in.
+/// - `transcript_file`: Transcript to record the sessions in, see [`crate::transcript`],
+///   or `None`.
+/// - `tls`: Serve over TLS and require client certificates.
+/// - `options`: How the session manifests are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the address is invalid or cannot be bound, or the TLS
+/// certificates cannot be loaded.
+pub fn serve(
+    listen: &str,
+    key_file: &str,
+    sessions_dir: &str,
+    transcript_file: Option<&str>,
+    tls: Option<&ServerTls>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service = SigningSessionsService {
+        store: Arc::new(
+            SessionStore::open(key_file, sessions_dir, transcript_file)?
+                .with_options(options.clone()),
+        ),
+        participants: tls.map(|tls| tls.participants()).transpose()?,
+    };
+    let addr: std::net::SocketAddr = listen.parse()?;
+    let mut server = tonic::transport::Server::builder();
+    if let Some(tls) = tls {
+        server = server.tls_config(tls.tonic_config()?)?;
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        report(
+            "coordinator_listening",
+            &format!(
+                "gRPC coordinator listening on {}, storing sessions in: {}",
+                listen, sessions_dir
+            ),
+            json!({ "listen": listen, "sessions_dir": sessions_dir }),
+        );
+        server
+            .add_service(SigningSessionsServer::new(service))
+            .serve(addr)
+            .await?;
+        Ok::<(), Box<dyn std::error::Error>>(())
+    })
+}
+
+/// Asks the gRPC coordinator at `url` to start a new signing session of `message` in `context`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the session.
+pub fn create_remote_session(
+    url: &str,
+    message: &str,
+    context: &str,
+    signers: Vec<u32>,
+    tls: Option<&ClientTls>,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    let request = proto::CreateSessionRequest {
+        message: message.to_string(),
+        context: context.to_string(),
+        signers,
+    };
+    let reply = block_on(async {
+        let mut client = connect(url, tls).await?;
+        Ok(client.create_session(request).await?.into_inner())
+    })?;
+    let session = session_from_reply(reply)?;
+
+    report(
+        "session_created",
+        &format!(
+            "Signing session {} created with signers {:?}",
+            session.session_id, session.roster
+        ),
+        json!({ "session_id": session.session_id, "signers": session.roster }),
+    );
+    Ok(session)
+}
+
+/// Downloads a session from the gRPC coordinator at `url`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or does not know the session.
+pub fn fetch_session(
+    url: &str,
+    session_id: &str,
+    tls: Option<&ClientTls>,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    let request = proto::GetSessionRequest {
+        session_id: session_id.to_string(),
+    };
+    let reply = block_on(async {
+        let mut client = connect(url, tls).await?;
+        Ok(client.get_session(request).await?.into_inner())
+    })?;
+    Ok(session_from_reply(reply)?)
+}
+
+/// Sends the public commitments in `commitments_file` to the gRPC coordinator at `url`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the commitments.
+pub fn submit_commitments_file(
+    url: &str,
+    commitments_file: &str,
+    tls: Option<&ClientTls>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let commitments: PublicCommitments = read_json(commitments_file)?;
+    let request = commitments_to_proto(&commitments);
+    block_on(async {
+        let mut client = connect(url, tls).await?;
+        client.submit_commitment(request).await?;
+        Ok(())
+    })?;
+
+    report(
+        "commitments_sent",
+        &format!(
+            "Commitments of participant {} sent to: {}",
+            commitments.index, url
+        ),
+        json!({ "index": commitments.index, "url": url }),
+    );
+    Ok(())
+}
+
+/// Runs signing round two of `message` in `context` against the session held by the
+/// gRPC coordinator at `url` and sends the resulting partial signature back.
+///
+/// The session is taken from the session ID in the secret commitments file. With
+/// `identity`, the other signers' commitments are checked against their identities
+/// and the partial signature is signed. With `echo_files`, the other signers must have
+/// echoed the same roster. See [`partial_sign_with_session`].
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures or signs
+/// another message or in another context, signing fails, or the coordinator rejects the
+/// partial signature.
+pub fn partial_sign_remote(
+    url: &str,
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    partial_signature_file: &str,
+    tls: Option<&ClientTls>,
+    identity: Option<&IdentityFiles>,
+    echo_files: &[String],
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Fetch the session this participant committed to
+    let secret_commitments = load_secret_commitments(secret_commitments_file)?;
+    let session = fetch_session(url, &secret_commitments.session_id, tls)?;
+
+    // Step 2: Sign and send the partial signature
+    partial_sign_with_session(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        &session,
+        partial_signature_file,
+        identity,
+        echo_files,
+        options,
+    )?;
+    let partial: PartialSignature = read_json(partial_signature_file)?;
+    let request = proto::PartialSignature {
+        session_id: partial.session_id.clone(),
+        index: partial.index,
+        z: partial.z.to_vec(),
+        identity_signature: partial.identity_signature.clone().map(signature_to_proto),
+    };
+    block_on(async {
+        let mut client = connect(url, tls).await?;
+        client.submit_partial(request).await?;
+        Ok(())
+    })?;
+
+    report(
+        "partial_signature_sent",
+        &format!(
+            "Partial signature of participant {} sent to: {}",
+            partial.index, url
+        ),
+        json!({ "index": partial.index, "url": url }),
+    );
+    Ok(())
+}
+
+/// Downloads the threshold signature of a completed session and saves it.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or the session is not complete.
+pub fn download_signature(
+    url: &str,
+    session_id: &str,
+    signature_file: &str,
+    tls: Option<&ClientTls>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = proto::GetSignatureRequest {
+        session_id: session_id.to_string(),
+    };
+    let reply = block_on(async {
+        let mut client = connect(url, tls).await?;
+        Ok(client.get_signature(request).await?.into_inner())
+    })?;
+    save_signature(
+        signature_file,
+        &reply.signature,
+        SignatureFormat::Json,
+        options.format,
+    )?;
+
+    report(
+        "signature_saved",
+        &format!("Threshold signature saved to: {}", signature_file),
+        json!({ "signature_file": signature_file }),
+    );
+    Ok(())
+}
+
+/// Connects to the coordinator, presenting the client certificate of `tls`, if any.
+async fn connect(
+    url: &str,
+    tls: Option<&ClientTls>,
+) -> Result<SigningSessionsClient<Channel>, Box<dyn std::error::Error>> {
+    let mut endpoint = Channel::from_shared(url.to_string())?;
+    if let Some(tls) = tls {
+        endpoint = endpoint.tls_config(tls.tonic_config()?)?;
+    }
+    Ok(SigningSessionsClient::new(endpoint.connect().await?))
+}
+
+/// Runs a client call to completion on the runtime of the calling blocking task, such
+/// as the steps of [`crate::nonblocking`], or else on a fresh runtime.
+fn block_on<T>(
+    future: impl std::future::Future<Output = Result<T, Box<dyn std::error::Error>>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => handle.block_on(future),
+        Err(_) => tokio::runtime::Runtime::new()?.block_on(future),
+    }
+}
+
+fn status(err: StoreError) -> Status {
+    match err {
+        StoreError::Invalid(err) => Status::invalid_argument(err),
+        StoreError::NotFound(err) => Status::not_found(err),
+        StoreError::Rejected(err) => Status::failed_precondition(err),
+        StoreError::Forbidden(err) => Status::permission_denied(err),
+        StoreError::Internal(err) => Status::internal(err),
+    }
+}
+
+fn status_name(status: SessionStatus) -> &'static str {
+    match status {
+        SessionStatus::CollectingCommitments => "collecting_commitments",
+        SessionStatus::CollectingPartials => "collecting_partials",
+        SessionStatus::Complete => "complete",
+    }
+}
+
+fn session_reply(session: &Session) -> proto::SessionReply {
+    proto::SessionReply {
+        session_id: session.session_id.clone(),
+        status: status_name(session.status).to_string(),
+        message: session.message.clone(),
+        context: session.context.clone(),
+        roster: session.roster.clone(),
+        missing_signers: session.missing_signers(),
+        message_hash: session.message_hash.clone(),
+        group_key: session.group_key.to_vec(),
+        threshold: session.threshold,
+        commitments: session
+            .commitments
+            .iter()
+            .map(commitments_to_proto)
+            .collect(),
+    }
+}
+
+/// Rebuilds the parts of a session a signer needs from a [`proto::SessionReply`].
+fn session_from_reply(reply: proto::SessionReply) -> Result<Session, Status> {
+    let status = [
+        SessionStatus::CollectingCommitments,
+        SessionStatus::CollectingPartials,
+        SessionStatus::Complete,
+    ]
+    .into_iter()
+    .find(|status| status_name(*status) == reply.status)
+    .ok_or_else(|| Status::invalid_argument(format!("Invalid status: {}", reply.status)))?;
+
+    Ok(Session {
+        session_id: reply.session_id,
+        status,
+        message: reply.message,
+        message_hash: reply.message_hash,
+        context: reply.context,
+        group_key: bytes32(&reply.group_key)?,
+        threshold: reply.threshold,
+        verification_shares: Vec::new(),
+        roster: reply.roster,
+        commitments: reply
+            .commitments
+            .into_iter()
+            .map(commitments_from_proto)
+            .collect::<Result<_, _>>()?,
+        partial_signatures: Vec::new(),
+        signature: None,
+    })
+}
+
+fn commitments_to_proto(commitments: &PublicCommitments) -> proto::Commitment {
+    proto::Commitment {
+        session_id: commitments.session_id.clone(),
+        index: commitments.index,
+        public_key: commitments.public_key.to_vec(),
+        commitments: commitments
+            .commitments
+            .iter()
+            .map(|(hiding, binding)| proto::CommitmentPair {
+                hiding: hiding.to_vec(),
+                binding: binding.to_vec(),
+            })
+            .collect(),
+        identity_signature: commitments
+            .identity_signature
+            .clone()
+            .map(signature_to_proto),
+    }
+}
+
+fn commitments_from_proto(commitments: proto::Commitment) -> Result<PublicCommitments, Status> {
+    Ok(PublicCommitments {
+        session_id: commitments.session_id,
+        index: commitments.index,
+        public_key: bytes32(&commitments.public_key)?,
+        commitments: commitments
+            .commitments
+            .iter()
+            .map(|pair| Ok::<_, Status>((bytes32(&pair.hiding)?, bytes32(&pair.binding)?)))
+            .collect::<Result<_, _>>()?,
+        identity_signature: commitments.identity_signature.map(signature_from_proto),
+    })
+}
+
+fn signature_to_proto(signature: IdentitySignature) -> proto::IdentitySignature {
+    proto::IdentitySignature {
+        signer: signature.signer,
+        signature: signature.signature,
+    }
+}
+
+fn signature_from_proto(signature: proto::IdentitySignature) -> IdentitySignature {
+    IdentitySignature {
+        signer: signature.signer,
+        signature: signature.signature,
+    }
+}
+
+fn bytes32(bytes: &[u8]) -> Result<[u8; 32], Status> {
+    bytes
+        .try_into()
+        .map_err(|_| Status::invalid_argument("Expected 32 bytes"))
+}
@@ -0,0 +1,417 @@
+//! HTTP coordinator for signing sessions.
+//!
+//! [`serve`] keeps one session manifest per signing session in a directory and exposes
+//! the session steps as JSON endpoints:
+//!
+//! - `POST /sessions` creates a session from a [`CreateSessionRequest`].
+//! - `GET /sessions/{id}` returns the session manifest.
+//! - `POST /sessions/{id}/commitments` adds a signer's public commitments.
+//! - `POST /sessions/{id}/partials` adds a signer's partial signature.
+//! - `GET /sessions/{id}/signature` returns the threshold signature once complete.
+//! - `GET /participants/{index}/ws` opens a WebSocket that pushes work to a signer
+//!   daemon, see [`crate::net::ws`].
+//! - `GET /metrics` returns the Prometheus metrics, see [`crate::metrics`].
+//!
+//! Participants only make outbound requests, using the client functions below.
+//! Sessions are kept in a [`SessionStore`]. With [`ServerTls`] the server requires
+//! client certificates and only accepts round messages from the participant the
+//! certificate belongs to.
+
+use crate::identity::IdentityFiles;
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::metrics;
+use crate::net::store::{SessionStore, StoreError};
+use crate::net::tls::{self, ClientTls, Peer, ServerTls};
+use crate::net::ws;
+use crate::session::{partial_sign_with_session, Session};
+use crate::signing::{load_secret_commitments, PartialSignature, PublicCommitments};
+use crate::types::default_signing_context;
+use crate::{read_json, save_signature, FileOptions};
+use axum::extract::{Path as UrlPath, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio_rustls::TlsAcceptor;
+use tower::Service;
+use tracing::warn;
+
+/// Body of a `POST /sessions` request.
+#[derive(Serialize, Deserialize)]
+pub struct CreateSessionRequest {
+    pub message: String,
+    /// Context string the message is signed in, [`crate::SIGNING_CONTEXT`] if left out.
+    #[serde(default = "default_signing_context")]
+    pub context: String,
+    /// Participant indices expected to sign.
+    pub signers: Vec<u32>,
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, String)>;
+
+/// Runs the coordinator server until the process is stopped.
+///
+/// # Arguments
+/// - `listen`: Address to listen on, for example `0.0.0.0:8080`.
+/// - `key_file`: Path to a key file or participant share file of the group.
+/// - `sessions_dir`: Directory to keep the session manifests in.
+/// - `transcript_file`: Transcript to record the sessions in, see [`crate::transcript`],
+///   or `None`.
+/// - `tls`: Serve HTTPS and require client certificates.
+/// - `options`: How the session manifests are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the address cannot be bound, the directory cannot be created,
+/// or the TLS certificates cannot be loaded.
+pub fn serve(
+    listen: &str,
+    key_file: &str,
+    sessions_dir: &str,
+    transcript_file: Option<&str>,
+    tls: Option<&ServerTls>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = Arc::new(
+        SessionStore::open(key_file, sessions_dir, transcript_file)?.with_options(options.clone()),
+    );
+    let app = Router::new()
+        .route("/sessions", post(create_session))
+        .route("/sessions/:session_id", get(get_session))
+        .route(
+            "/sessions/:session_id/commitments",
+            post(submit_commitments),
+        )
+        .route("/sessions/:session_id/partials", post(submit_partial))
+        .route("/sessions/:session_id/signature", get(get_signature))
+        .route("/participants/:index/ws", get(ws::participant_socket))
+        .route("/metrics", get(metrics::metrics_handler))
+        .with_state(store);
+    let tls = match tls {
+        Some(tls) => Some((
+            TlsAcceptor::from(tls.rustls_config()?),
+            Arc::new(tls.participants()?),
+        )),
+        None => None,
+    };
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(listen).await?;
+        report(
+            "coordinator_listening",
+            &format!(
+                "Coordinator listening on {}, storing sessions in: {}",
+                listen, sessions_dir
+            ),
+            json!({ "listen": listen, "sessions_dir": sessions_dir }),
+        );
+        match tls {
+            Some((acceptor, participants)) => {
+                serve_tls(listener, app, acceptor, participants).await
+            }
+            None => {
+                axum::serve(listener, app).await?;
+                Ok::<(), Box<dyn std::error::Error>>(())
+            }
+        }
+    })
+}
+
+/// Accepts TLS connections and tells the handlers which participant is connected.
+async fn serve_tls(
+    listener: tokio::net::TcpListener,
+    app: Router,
+    acceptor: TlsAcceptor,
+    participants: Arc<HashMap<Vec<u8>, u32>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        let acceptor = acceptor.clone();
+        let participants = Arc::clone(&participants);
+        let app = app.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("TLS handshake with {} failed: {}", addr, err);
+                    return;
+                }
+            };
+            let cert = stream
+                .get_ref()
+                .1
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.as_ref());
+            let peer = tls::peer(&participants, cert);
+            let app = app.layer(Extension(peer));
+            let service = hyper::service::service_fn(
+                move |request: hyper::Request<hyper::body::Incoming>| app.clone().call(request),
+            );
+            if let Err(err) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Connection from {} failed: {}", addr, err);
+            }
+        });
+    }
+}
+
+async fn create_session(
+    State(store): State<Arc<SessionStore>>,
+    Json(request): Json<CreateSessionRequest>,
+) -> ApiResult<Session> {
+    Ok(Json(
+        store
+            .create(&request.message, &request.context, request.signers)
+            .map_err(api_error)?,
+    ))
+}
+
+async fn get_session(
+    State(store): State<Arc<SessionStore>>,
+    UrlPath(session_id): UrlPath<String>,
+) -> ApiResult<Session> {
+    Ok(Json(store.get(&session_id).map_err(api_error)?))
+}
+
+async fn submit_commitments(
+    State(store): State<Arc<SessionStore>>,
+    UrlPath(session_id): UrlPath<String>,
+    peer: Option<Extension<Peer>>,
+    Json(commitments): Json<PublicCommitments>,
+) -> ApiResult<Session> {
+    check_url_session_id(&session_id, &commitments.session_id)?;
+    tls::authorize(peer.as_deref(), commitments.index).map_err(api_error)?;
+    Ok(Json(store.add_commitments(commitments).map_err(api_error)?))
+}
+
+async fn submit_partial(
+    State(store): State<Arc<SessionStore>>,
+    UrlPath(session_id): UrlPath<String>,
+    peer: Option<Extension<Peer>>,
+    Json(partial): Json<PartialSignature>,
+) -> ApiResult<Session> {
+    check_url_session_id(&session_id, &partial.session_id)?;
+    tls::authorize(peer.as_deref(), partial.index).map_err(api_error)?;
+    Ok(Json(
+        store.add_partial_signature(partial).map_err(api_error)?,
+    ))
+}
+
+async fn get_signature(
+    State(store): State<Arc<SessionStore>>,
+    UrlPath(session_id): UrlPath<String>,
+) -> ApiResult<Vec<u8>> {
+    Ok(Json(store.signature(&session_id).map_err(api_error)?))
+}
+
+/// Rejects round messages posted to the URL of a different session.
+fn check_url_session_id(
+    url_session_id: &str,
+    session_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    if url_session_id != session_id {
+        return Err((
+            StatusCode::CONFLICT,
+            format!(
+                "Round message belongs to session {}, expected {}",
+                session_id, url_session_id
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn api_error(err: StoreError) -> (StatusCode, String) {
+    let status = match err {
+        StoreError::Invalid(_) => StatusCode::BAD_REQUEST,
+        StoreError::NotFound(_) => StatusCode::NOT_FOUND,
+        StoreError::Rejected(_) => StatusCode::CONFLICT,
+        StoreError::Forbidden(_) => StatusCode::FORBIDDEN,
+        StoreError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, err.to_string())
+}
+
+/// Asks the coordinator at `url` to start a new signing session of `message` in `context`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the session.
+pub fn create_remote_session(
+    url: &str,
+    message: &str,
+    context: &str,
+    signers: Vec<u32>,
+    tls: Option<&ClientTls>,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    let request = CreateSessionRequest {
+        message: message.to_string(),
+        context: context.to_string(),
+        signers,
+    };
+    let session: Session = agent(tls)?
+        .post(&format!("{}/sessions", url.trim_end_matches('/')))
+        .send_json(&request)?
+        .into_json()?;
+
+    report(
+        "session_created",
+        &format!(
+            "Signing session {} created with signers {:?}",
+            session.session_id, session.roster
+        ),
+        json!({ "session_id": session.session_id, "signers": session.roster }),
+    );
+    Ok(session)
+}
+
+/// Downloads a session manifest from the coordinator at `url`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or does not know the session.
+pub fn fetch_session(
+    url: &str,
+    session_id: &str,
+    tls: Option<&ClientTls>,
+) -> Result<Session, Box<dyn std::error::Error>> {
+    Ok(agent(tls)?
+        .get(&session_url(url, session_id, ""))
+        .call()?
+        .into_json()?)
+}
+
+/// Sends the public commitments in `commitments_file` to the coordinator at `url`.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the commitments.
+pub fn submit_commitments_file(
+    url: &str,
+    commitments_file: &str,
+    tls: Option<&ClientTls>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let commitments: PublicCommitments = read_json(commitments_file)?;
+    agent(tls)?
+        .post(&session_url(url, &commitments.session_id, "/commitments"))
+        .send_json(&commitments)?;
+
+    report(
+        "commitments_sent",
+        &format!(
+            "Commitments of participant {} sent to: {}",
+            commitments.index, url
+        ),
+        json!({ "index": commitments.index, "url": url }),
+    );
+    Ok(())
+}
+
+/// Runs signing round two of `message` in `context` against the session held by the
+/// coordinator at `url` and sends the resulting partial signature back.
+///
+/// The session is taken from the session ID in the secret commitments file. With
+/// `identity`, the other signers' commitments are checked against their identities
+/// and the partial signature is signed. With `echo_files`, the other signers must have
+/// echoed the same roster. See [`partial_sign_with_session`].
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures or signs
+/// another message or in another context, signing fails, or the coordinator rejects the
+/// partial signature.
+pub fn partial_sign_remote(
+    url: &str,
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    partial_signature_file: &str,
+    tls: Option<&ClientTls>,
+    identity: Option<&IdentityFiles>,
+    echo_files: &[String],
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Fetch the session this participant committed to
+    let secret_commitments = load_secret_commitments(secret_commitments_file)?;
+    let session = fetch_session(url, &secret_commitments.session_id, tls)?;
+
+    // Step 2: Sign and send the partial signature
+    partial_sign_with_session(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        &session,
+        partial_signature_file,
+        identity,
+        echo_files,
+        options,
+    )?;
+    let partial: PartialSignature = read_json(partial_signature_file)?;
+    agent(tls)?
+        .post(&session_url(url, &partial.session_id, "/partials"))
+        .send_json(&partial)?;
+
+    report(
+        "partial_signature_sent",
+        &format!(
+            "Partial signature of participant {} sent to: {}",
+            partial.index, url
+        ),
+        json!({ "index": partial.index, "url": url }),
+    );
+    Ok(())
+}
+
+/// Downloads the threshold signature of a completed session and saves it.
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or the session is not complete.
+pub fn download_signature(
+    url: &str,
+    session_id: &str,
+    signature_file: &str,
+    tls: Option<&ClientTls>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let signature: Vec<u8> = agent(tls)?
+        .get(&session_url(url, session_id, "/signature"))
+        .call()?
+        .into_json()?;
+    save_signature(
+        signature_file,
+        &signature,
+        SignatureFormat::Json,
+        options.format,
+    )?;
+
+    report(
+        "signature_saved",
+        &format!("Threshold signature saved to: {}", signature_file),
+        json!({ "signature_file": signature_file }),
+    );
+    Ok(())
+}
+
+/// Returns an HTTP client that presents the client certificate of `tls`, if any.
+fn agent(tls: Option<&ClientTls>) -> Result<ureq::Agent, Box<dyn std::error::Error>> {
+    let mut builder = ureq::AgentBuilder::new();
+    if let Some(tls) = tls {
+        builder = builder.tls_config(tls.rustls_config()?);
+    }
+    Ok(builder.build())
+}
+
+fn session_url(url: &str, session_id: &str, endpoint: &str) -> String {
+    format!(
+        "{}/sessions/{}{}",
+        url.trim_end_matches('/'),
+        session_id,
+        endpoint
+    )
+}
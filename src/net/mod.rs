@@ -0,0 +1,29 @@
+//! Network transports for exchanging round messages.
+//!
+//! The round commands read and write plain JSON message files. The transports in this
+//! module only move those files between participants, so every command works the same
+//! whether its messages arrived over the network or on a USB stick.
+//!
+//! Only [`noise`] and [`transport`] are built without the `native` feature, which the
+//! network transports need.
+
+#[cfg(native)]
+pub mod api;
+#[cfg(native)]
+pub mod grpc;
+#[cfg(native)]
+pub mod http;
+pub mod noise;
+#[cfg(native)]
+pub mod p2p;
+#[cfg(native)]
+pub mod relay;
+#[cfg(native)]
+pub mod store;
+#[cfg(native)]
+pub mod tcp;
+#[cfg(native)]
+pub mod tls;
+pub mod transport;
+#[cfg(native)]
+pub mod ws;
@@ -0,0 +1,284 @@
+//! Noise-sealed DKG shares.
+//!
+//! Mailboxes, gossip peers and coordinators all get to see the round messages they
+//! carry. Before a DKG share leaves its sender, [`seal_share`] encrypts it to the
+//! receiver's identity key with a one-way `Noise_K` handshake, which also proves that
+//! the share came from the sender's identity key. Only the receiver can turn the
+//! sealed file back into a share with [`open_share`].
+//!
+//! Sealed shares are kept under the same file name in a `sealed` directory next to
+//! the share, so the transports exchange them exactly like any other round message.
+//! The relay seals every message it forwards the same way, while the share bundles of
+//! [`crate::dealer`] are sealed without a sender identity.
+
+use crate::dkg::Round2Message;
+use crate::identity::{load_identity, load_public_identity, Identity, PublicIdentity};
+use crate::io::output::report;
+use crate::{read_json, write_json, write_secret_json, FileOptions};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Noise protocol used to seal shares: both static keys are known in advance.
+const NOISE_PARAMS: &str = "Noise_K_25519_ChaChaPoly_BLAKE2s";
+
+/// Noise protocol used to seal to a receiver without a sender identity.
+const ANONYMOUS_NOISE_PARAMS: &str = "Noise_N_25519_ChaChaPoly_BLAKE2s";
+
+/// Largest message a single Noise handshake message can carry.
+const MAX_NOISE_MESSAGE_LEN: usize = 65535;
+
+/// A DKG share encrypted to its receiver.
+#[derive(Serialize, Deserialize)]
+pub struct SealedShare {
+    pub sender: u32,
+    pub receiver: u32,
+    /// Hex-encoded Noise handshake message carrying the [`Round2Message`].
+    pub ciphertext: String,
+}
+
+/// Generates an X25519 key pair for use as a Noise static key.
+pub(crate) fn generate_keypair() -> Result<([u8; 32], [u8; 32]), Box<dyn std::error::Error>> {
+    let keypair = snow::Builder::new(NOISE_PARAMS.parse()?).generate_keypair()?;
+    Ok((
+        keypair.private.as_slice().try_into()?,
+        keypair.public.as_slice().try_into()?,
+    ))
+}
+
+/// Returns where the sealed version of `share_file` is kept.
+///
+/// # Errors
+/// Returns an error if `share_file` has no file name.
+pub fn sealed_path(share_file: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let name = share_file
+        .file_name()
+        .ok_or_else(|| format!("Invalid share file: {}", share_file.display()))?;
+    let dir = share_file.parent().unwrap_or_else(|| Path::new("."));
+    Ok(dir.join("sealed").join(name))
+}
+
+/// Seals a share written by DKG round two to its receiver.
+///
+/// # Arguments
+/// - `identity_file`: Path to the sender's private identity.
+/// - `identities_dir`: Directory containing the public identities of the participants.
+/// - `share_file`: Path to the plaintext share.
+/// - `options`: How the sealed share is written, see [`FileOptions`].
+///
+/// Returns the path of the sealed share, see [`sealed_path`].
+///
+/// # Errors
+/// Returns an error if the share was not created by this identity or the receiver's
+/// public identity is missing.
+pub fn seal_share(
+    identity_file: &str,
+    identities_dir: &str,
+    share_file: &Path,
+    options: &FileOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    // Step 1: Load the share and both identities
+    let identity = load_identity(identity_file)?;
+    let share: Round2Message = read_json(share_file)?;
+    if share.sender != identity.index {
+        return Err(format!(
+            "Share was created by participant {}, not by participant {}",
+            share.sender, identity.index
+        )
+        .into());
+    }
+    let receiver = load_public_identity(identities_dir, share.receiver)?;
+
+    // Step 2: Encrypt the share to the receiver in a one-way handshake
+    let ciphertext = seal(
+        &identity,
+        &receiver,
+        &prologue(share.sender, share.receiver),
+        &serde_json::to_vec(&share)?,
+    )?;
+
+    // Step 3: Save the sealed share next to the plaintext one
+    let sealed_file = sealed_path(share_file)?;
+    if let Some(parent) = sealed_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_json(
+        &sealed_file,
+        &SealedShare {
+            sender: share.sender,
+            receiver: share.receiver,
+            ciphertext: hex::encode(ciphertext),
+        },
+        options,
+    )?;
+
+    report(
+        "share_sealed",
+        &format!(
+            "Share for participant {} sealed to: {}",
+            share.receiver,
+            sealed_file.display()
+        ),
+        json!({ "receiver": share.receiver, "sealed_file": sealed_file }),
+    );
+    Ok(sealed_file)
+}
+
+/// Opens the sealed version of `share_file` and saves the plaintext share there.
+///
+/// # Arguments
+/// - `identity_file`: Path to the receiver's private identity.
+/// - `identities_dir`: Directory containing the public identities of the participants.
+/// - `share_file`: Path to save the plaintext share, see [`sealed_path`].
+/// - `options`: How the opened share is written.
+///
+/// # Errors
+/// Returns an error if the share was not sealed to this identity or was not sealed
+/// by the identity of its sender.
+pub fn open_share(
+    identity_file: &str,
+    identities_dir: &str,
+    share_file: &Path,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the sealed share and both identities
+    let identity = load_identity(identity_file)?;
+    let sealed: SealedShare = read_json(sealed_path(share_file)?)?;
+    if sealed.receiver != identity.index {
+        return Err(format!(
+            "Share was sealed to participant {}, not to participant {}",
+            sealed.receiver, identity.index
+        )
+        .into());
+    }
+    let sender = load_public_identity(identities_dir, sealed.sender)?;
+
+    // Step 2: Decrypt the share and check it against the sender's identity
+    let payload = open(
+        &identity,
+        &sender,
+        &prologue(sealed.sender, sealed.receiver),
+        &hex::decode(&sealed.ciphertext)?,
+    )?;
+    let share: Round2Message = serde_json::from_slice(&payload)?;
+    if share.sender != sealed.sender || share.receiver != sealed.receiver {
+        return Err(format!("Unexpected sealed share: {}", share_file.display()).into());
+    }
+
+    // Step 3: Save the plaintext share where DKG finish expects it
+    write_secret_json(share_file, &share, options)?;
+    report(
+        "share_opened",
+        &format!(
+            "Share from participant {} opened to: {}",
+            share.sender,
+            share_file.display()
+        ),
+        json!({ "sender": share.sender, "share_file": share_file }),
+    );
+    Ok(())
+}
+
+/// Encrypts `payload` from `identity` to `receiver` in a one-way `Noise_K` handshake.
+///
+/// # Errors
+/// Returns an error if the payload is too large for a single Noise message.
+pub(crate) fn seal(
+    identity: &Identity,
+    receiver: &PublicIdentity,
+    prologue: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut handshake = snow::Builder::new(NOISE_PARAMS.parse()?)
+        .local_private_key(&identity.noise_private_key)
+        .remote_public_key(&receiver.noise_public_key)
+        .prologue(prologue)
+        .build_initiator()?;
+    let mut ciphertext = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    let len = handshake.write_message(payload, &mut ciphertext)?;
+    ciphertext.truncate(len);
+    Ok(ciphertext)
+}
+
+/// Decrypts a payload sealed by [`seal`] and checks that `sender` sealed it.
+///
+/// # Errors
+/// Returns an error if the ciphertext was not sealed by `sender` to `identity` with
+/// the same prologue.
+pub(crate) fn open(
+    identity: &Identity,
+    sender: &PublicIdentity,
+    prologue: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut handshake = snow::Builder::new(NOISE_PARAMS.parse()?)
+        .local_private_key(&identity.noise_private_key)
+        .remote_public_key(&sender.noise_public_key)
+        .prologue(prologue)
+        .build_responder()?;
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    let len = handshake
+        .read_message(ciphertext, &mut payload)
+        .map_err(|_| {
+            format!(
+                "Message from participant {} was not sealed by their identity",
+                sender.index
+            )
+        })?;
+    payload.truncate(len);
+    Ok(payload)
+}
+
+/// Encrypts `payload` to `receiver_public_key` in a one-way `Noise_N` handshake.
+///
+/// Unlike [`seal`], the receiver learns nothing about who sealed the payload.
+///
+/// # Errors
+/// Returns an error if the payload is too large for a single Noise message.
+pub(crate) fn seal_anonymous(
+    receiver_public_key: &[u8; 32],
+    prologue: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut handshake = snow::Builder::new(ANONYMOUS_NOISE_PARAMS.parse()?)
+        .remote_public_key(receiver_public_key)
+        .prologue(prologue)
+        .build_initiator()?;
+    let mut ciphertext = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    let len = handshake.write_message(payload, &mut ciphertext)?;
+    ciphertext.truncate(len);
+    Ok(ciphertext)
+}
+
+/// Decrypts a payload sealed to `identity` by [`seal_anonymous`].
+///
+/// # Errors
+/// Returns an error if the ciphertext was not sealed to `identity` with the same
+/// prologue.
+pub(crate) fn open_anonymous(
+    identity: &Identity,
+    prologue: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut handshake = snow::Builder::new(ANONYMOUS_NOISE_PARAMS.parse()?)
+        .local_private_key(&identity.noise_private_key)
+        .prologue(prologue)
+        .build_responder()?;
+    let mut payload = vec![0u8; MAX_NOISE_MESSAGE_LEN];
+    let len = handshake
+        .read_message(ciphertext, &mut payload)
+        .map_err(|_| {
+            format!(
+                "Message was not sealed to the identity of participant {}",
+                identity.index
+            )
+        })?;
+    payload.truncate(len);
+    Ok(payload)
+}
+
+/// Binds a sealed share to its sender and receiver.
+fn prologue(sender: u32, receiver: u32) -> Vec<u8> {
+    format!("FROST-CLI DKG SHARE {} {}", sender, receiver).into_bytes()
+}
@@ -0,0 +1,309 @@
+//! Peer-to-peer exchange of round messages over libp2p.
+//!
+//! Every participant of a ceremony runs a [`Node`] that joins the gossipsub topic
+//! named after the ceremony. Peers on the local network are found with mDNS, and
+//! peers elsewhere can be dialed directly with their multiaddr. Nodes keep
+//! re-publishing their own round messages, so participants that join late still
+//! receive everything and no coordinator host is needed.
+//!
+//! Gossip reaches every peer of the ceremony, so the CLI only publishes DKG secret
+//! shares sealed to their receivers with [`crate::net::noise`].
+
+use crate::io::files::FileOptions;
+use crate::io::output::report;
+use futures::StreamExt;
+use libp2p::swarm::{NetworkBehaviour, SwarmEvent};
+use libp2p::{gossipsub, mdns, noise, tcp, yamux, Multiaddr, SwarmBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc as async_mpsc;
+use tracing::info;
+
+/// How often a node re-publishes its own round messages.
+const REPUBLISH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a connection to a quiet peer is kept open.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often [`Node::receive`] checks for a new message.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A round message as it is gossiped to the other peers.
+#[derive(Serialize, Deserialize, Clone)]
+struct Envelope {
+    name: String,
+    message: serde_json::Value,
+}
+
+#[derive(NetworkBehaviour)]
+struct Behaviour {
+    gossipsub: gossipsub::Behaviour,
+    mdns: mdns::tokio::Behaviour,
+}
+
+/// A running libp2p node taking part in one ceremony.
+pub struct Node {
+    publish: async_mpsc::UnboundedSender<Envelope>,
+    received: Arc<Mutex<HashMap<String, serde_json::Value>>>,
+}
+
+impl Node {
+    /// Starts a node in the background and joins the ceremony's topic.
+    ///
+    /// # Arguments
+    /// - `ceremony`: Name of the ceremony, shared by all of its participants.
+    /// - `listen`: Multiaddr to listen on, for example `/ip4/0.0.0.0/tcp/0`.
+    /// - `peers`: Multiaddrs of peers to dial in addition to those found with mDNS.
+    ///
+    /// # Errors
+    /// Returns an error if an address is invalid or the node cannot start listening.
+    pub fn start(
+        ceremony: &str,
+        listen: &str,
+        peers: &[String],
+    ) -> Result<Node, Box<dyn std::error::Error>> {
+        let listen: Multiaddr = listen.parse()?;
+        let peers = peers
+            .iter()
+            .map(|peer| peer.parse())
+            .collect::<Result<Vec<Multiaddr>, _>>()?;
+        let topic = gossipsub::IdentTopic::new(format!("frost-cli/{}", ceremony));
+
+        let (publish, publish_rx) = async_mpsc::unbounded_channel();
+        let received = Arc::new(Mutex::new(HashMap::new()));
+        let (started, started_rx) = mpsc::channel();
+        let node_received = Arc::clone(&received);
+        thread::spawn(move || {
+            let runtime = match tokio::runtime::Runtime::new() {
+                Ok(runtime) => runtime,
+                Err(err) => {
+                    let _ = started.send(Err(err.to_string()));
+                    return;
+                }
+            };
+            runtime.block_on(run_node(
+                topic,
+                listen,
+                peers,
+                publish_rx,
+                node_received,
+                started,
+            ));
+        });
+        started_rx
+            .recv()
+            .map_err(|_| "libp2p node stopped unexpectedly")??;
+
+        Ok(Node { publish, received })
+    }
+
+    /// Publishes a round message file under its file name.
+    ///
+    /// # Errors
+    /// Returns an error if the file is not valid JSON.
+    pub fn publish<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn std::error::Error>> {
+        let name = file_name(path.as_ref())?;
+        self.publish_as(&name, path)
+    }
+
+    /// Publishes a round message file under `name`.
+    ///
+    /// # Errors
+    /// Returns an error if the file is not valid JSON.
+    pub fn publish_as<P: AsRef<Path>>(
+        &self,
+        name: &str,
+        path: P,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let envelope = Envelope {
+            name: name.to_string(),
+            message: crate::read_json(path)?,
+        };
+        self.publish
+            .send(envelope)
+            .map_err(|_| "libp2p node stopped unexpectedly")?;
+        report(
+            "message_published",
+            &format!("Published {}", name),
+            json!({ "name": name }),
+        );
+        Ok(())
+    }
+
+    /// Waits for the round message with the same file name as `path` and saves it there.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn receive<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &FileOptions,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path.as_ref();
+        let name = file_name(path)?;
+        let mut waiting = false;
+        loop {
+            let message = self
+                .received
+                .lock()
+                .map_err(|_| "libp2p node stopped unexpectedly")?
+                .get(&name)
+                .cloned();
+            if let Some(message) = message {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                crate::write_json(path, &message, options)?;
+                report(
+                    "message_received",
+                    &format!("Received {}", name),
+                    json!({ "name": name }),
+                );
+                return Ok(());
+            }
+            if !waiting {
+                info!("Waiting for {} from the other peers...", name);
+                waiting = true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Keeps re-publishing this node's messages for `duration` before it stops, so
+    /// slower peers can still receive them.
+    pub fn linger(self, duration: Duration) {
+        info!(
+            "Sharing round messages with the other peers for {} seconds...",
+            duration.as_secs()
+        );
+        thread::sleep(duration);
+    }
+}
+
+/// Runs the libp2p swarm until the [`Node`] is dropped.
+async fn run_node(
+    topic: gossipsub::IdentTopic,
+    listen: Multiaddr,
+    peers: Vec<Multiaddr>,
+    mut publish_rx: async_mpsc::UnboundedReceiver<Envelope>,
+    received: Arc<Mutex<HashMap<String, serde_json::Value>>>,
+    started: mpsc::Sender<Result<(), String>>,
+) {
+    // Step 1: Build the swarm and join the topic
+    let swarm = SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::default(),
+            noise::Config::new,
+            yamux::Config::default,
+        )
+        .map_err(|err| err.to_string())
+        .and_then(|builder| {
+            builder
+                .with_behaviour(|key| {
+                    let gossipsub = gossipsub::Behaviour::new(
+                        gossipsub::MessageAuthenticity::Signed(key.clone()),
+                        gossipsub::Config::default(),
+                    )?;
+                    let mdns = mdns::tokio::Behaviour::new(
+                        mdns::Config::default(),
+                        key.public().to_peer_id(),
+                    )?;
+                    Ok(Behaviour { gossipsub, mdns })
+                })
+                .map_err(|err| err.to_string())
+        });
+    let mut swarm = match swarm {
+        Ok(builder) => builder
+            .with_swarm_config(|config| config.with_idle_connection_timeout(IDLE_TIMEOUT))
+            .build(),
+        Err(err) => {
+            let _ = started.send(Err(err));
+            return;
+        }
+    };
+    let setup = swarm
+        .behaviour_mut()
+        .gossipsub
+        .subscribe(&topic)
+        .map_err(|err| err.to_string())
+        .and_then(|_| swarm.listen_on(listen).map_err(|err| err.to_string()))
+        .and_then(|_| {
+            peers
+                .into_iter()
+                .try_for_each(|peer| swarm.dial(peer).map_err(|err| err.to_string()))
+        });
+    let failed = setup.is_err();
+    let _ = started.send(setup);
+    if failed {
+        return;
+    }
+
+    // Step 2: Gossip our messages and collect everyone else's
+    let mut published: Vec<Envelope> = Vec::new();
+    let mut republish = tokio::time::interval(REPUBLISH_INTERVAL);
+    loop {
+        tokio::select! {
+            envelope = publish_rx.recv() => match envelope {
+                Some(envelope) => {
+                    gossip(&mut swarm, &topic, &envelope);
+                    published.push(envelope);
+                }
+                None => return,
+            },
+            _ = republish.tick() => {
+                for envelope in &published {
+                    gossip(&mut swarm, &topic, envelope);
+                }
+            }
+            event = swarm.select_next_some() => match event {
+                SwarmEvent::NewListenAddr { address, .. } => {
+                    report(
+                        "node_listening",
+                        &format!("libp2p node listening on {}", address),
+                        json!({ "address": address.to_string() }),
+                    );
+                }
+                SwarmEvent::Behaviour(BehaviourEvent::Mdns(mdns::Event::Discovered(found))) => {
+                    for (peer, _) in found {
+                        swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer);
+                    }
+                }
+                SwarmEvent::Behaviour(BehaviourEvent::Gossipsub(gossipsub::Event::Message {
+                    message,
+                    ..
+                })) => {
+                    if let Ok(envelope) = serde_json::from_slice::<Envelope>(&message.data) {
+                        if let Ok(mut received) = received.lock() {
+                            received.insert(envelope.name, envelope.message);
+                        }
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Publishes one envelope, ignoring the error gossipsub returns while no peer is connected.
+fn gossip(
+    swarm: &mut libp2p::Swarm<Behaviour>,
+    topic: &gossipsub::IdentTopic,
+    envelope: &Envelope,
+) {
+    if let Ok(data) = serde_json::to_vec(envelope) {
+        let _ = swarm.behaviour_mut().gossipsub.publish(topic.clone(), data);
+    }
+}
+
+fn file_name(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid message file: {}", path.display()).into())
+}
@@ -0,0 +1,358 @@
+//! Relay for participants that cannot reach each other directly.
+//!
+//! The relay runs on a host every participant can reach and forwards round messages
+//! between them over the same length-prefixed JSON framing as the TCP mailbox. Before
+//! a message is handed to the relay, [`send`] seals it to its receiver's identity key
+//! and files it under a tag derived from the sender, receiver and message name and
+//! keyed with a secret only the two of them share. The relay therefore only ever sees
+//! session IDs, opaque tags and the size of each message, and it drops every message
+//! once it has been delivered.
+//!
+//! Anyone who can reach the relay can hand it messages, so it holds at most
+//! [`MAX_SESSION_MESSAGES`] messages and [`MAX_SESSION_BYTES`] per session and
+//! [`MAX_PENDING_BYTES`] in total, and drops messages nobody fetched within
+//! [`MESSAGE_TTL`].
+
+use crate::identity::{load_identity, load_public_identity, Identity, PublicIdentity};
+use crate::io::output::report;
+use crate::net::noise;
+use crate::net::tcp::{receive_message, send_message};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+/// How long [`receive`] waits before asking the relay again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Largest number of messages the relay holds for one session, enough for a key
+/// generation of 32 participants.
+pub const MAX_SESSION_MESSAGES: usize = 1024;
+
+/// Largest size of the sealed messages the relay holds for one session.
+pub const MAX_SESSION_BYTES: usize = 4 * 1024 * 1024;
+
+/// Largest size of the sealed messages the relay holds for all sessions together.
+pub const MAX_PENDING_BYTES: usize = 256 * 1024 * 1024;
+
+/// How long the relay holds a message its receiver does not fetch, long enough for
+/// ceremonies whose participants take their turns over a day.
+pub const MESSAGE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A request sent to the relay.
+#[derive(Serialize, Deserialize)]
+pub enum RelayRequest {
+    /// Hold a sealed message until its receiver fetches it.
+    Forward {
+        session_id: String,
+        tag: String,
+        /// Hex-encoded sealed message.
+        payload: String,
+    },
+    /// Fetch and remove the sealed message filed under `tag`.
+    Fetch { session_id: String, tag: String },
+}
+
+/// The relay's answer to a [`RelayRequest`].
+#[derive(Serialize, Deserialize)]
+pub enum RelayResponse {
+    Forwarded,
+    /// The sealed message, or `None` if it has not arrived yet.
+    Payload(Option<String>),
+    Error(String),
+}
+
+/// Sealed messages waiting for their receivers.
+#[derive(Default)]
+struct Pending {
+    /// Messages by session ID and tag, each with the time it arrived.
+    sessions: HashMap<String, HashMap<String, (String, Instant)>>,
+    /// Size of all messages held, in bytes of their hex encoding.
+    bytes: usize,
+}
+
+impl Pending {
+    /// Holds a message for its receiver, replacing one filed under the same tag.
+    fn forward(
+        &mut self,
+        session_id: String,
+        tag: String,
+        payload: String,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Step 1: Drop the messages held for too long
+        let now = Instant::now();
+        let mut expired = 0;
+        for messages in self.sessions.values_mut() {
+            messages.retain(|_, (payload, received)| {
+                let keep = now.duration_since(*received) < MESSAGE_TTL;
+                if !keep {
+                    expired += payload.len();
+                }
+                keep
+            });
+        }
+        self.sessions.retain(|_, messages| !messages.is_empty());
+        self.bytes -= expired;
+
+        // Step 2: Check the limits without the message it replaces
+        let messages = self.sessions.get(&session_id);
+        let replaced = messages
+            .and_then(|messages| messages.get(&tag))
+            .map_or(0, |(payload, _)| payload.len());
+        let (count, session_bytes) = messages.map_or((0, 0), |messages| {
+            let count = messages.len() - usize::from(replaced > 0);
+            let bytes: usize = messages.values().map(|(payload, _)| payload.len()).sum();
+            (count, bytes - replaced)
+        });
+        if count >= MAX_SESSION_MESSAGES {
+            return Err(format!(
+                "Session {} already has {} messages waiting",
+                session_id, MAX_SESSION_MESSAGES
+            )
+            .into());
+        }
+        if session_bytes + payload.len() > MAX_SESSION_BYTES {
+            return Err(format!(
+                "Session {} would have more than {} bytes waiting",
+                session_id, MAX_SESSION_BYTES
+            )
+            .into());
+        }
+        if self.bytes - replaced + payload.len() > MAX_PENDING_BYTES {
+            return Err("The relay holds too many messages, try again later".into());
+        }
+
+        // Step 3: Hold the message
+        self.bytes = self.bytes - replaced + payload.len();
+        self.sessions
+            .entry(session_id)
+            .or_default()
+            .insert(tag, (payload, now));
+        Ok(())
+    }
+
+    /// Removes and returns the message filed under `tag`, if any.
+    fn fetch(&mut self, session_id: &str, tag: &str) -> Option<String> {
+        let messages = self.sessions.get_mut(session_id)?;
+        let (payload, received) = messages.remove(tag)?;
+        if messages.is_empty() {
+            self.sessions.remove(session_id);
+        }
+        self.bytes -= payload.len();
+        (received.elapsed() < MESSAGE_TTL).then_some(payload)
+    }
+}
+
+/// Runs a relay until the process is stopped.
+///
+/// Messages are only kept in memory, so a restarted relay starts out empty, and within
+/// the limits of the [module](self).
+///
+/// # Errors
+/// Returns an error if the address cannot be bound.
+pub fn serve(listen: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = TcpListener::bind(listen)?;
+    let pending = Arc::new(Mutex::new(Pending::default()));
+    report(
+        "relay_listening",
+        &format!("Relay listening on {}", listen),
+        json!({ "listen": listen }),
+    );
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let pending = Arc::clone(&pending);
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            if let Err(err) = handle_connection(stream, &pending) {
+                warn!("Connection from {} failed: {}", peer, err);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Seals a round message file to participant `to` and hands it to the relay.
+///
+/// # Arguments
+/// - `connect`: Address of the relay.
+/// - `session_id`: ID of the signing session or DKG ceremony the message belongs to.
+/// - `identity_file`: Path to the sender's private identity.
+/// - `identities_dir`: Directory containing the public identities of the participants.
+/// - `to`: Index of the receiving participant.
+/// - `path`: Round message file to send. The receiver saves it under the same name.
+///
+/// # Errors
+/// Returns an error if the message cannot be sealed or the relay rejects it.
+pub fn send<P: AsRef<Path>>(
+    connect: &str,
+    session_id: &str,
+    identity_file: &str,
+    identities_dir: &str,
+    to: u32,
+    path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Seal the message to the receiver
+    let identity = load_identity(identity_file)?;
+    let receiver = load_public_identity(identities_dir, to)?;
+    let name = file_name(path.as_ref())?;
+    let label = label(session_id, identity.index, to, &name);
+    let sealed = noise::seal(&identity, &receiver, &label, &fs::read(path.as_ref())?)?;
+
+    // Step 2: Hand it to the relay under an opaque tag
+    let request = RelayRequest::Forward {
+        session_id: session_id.to_string(),
+        tag: tag(&identity, &receiver, &label),
+        payload: hex::encode(sealed),
+    };
+    match request_once(connect, &request)? {
+        RelayResponse::Forwarded => {
+            report(
+                "message_sent",
+                &format!("Sent {} to participant {} through {}", name, to, connect),
+                json!({ "name": name, "to": to, "relay": connect }),
+            );
+            Ok(())
+        }
+        RelayResponse::Error(err) => Err(format!("Relay rejected {}: {}", name, err).into()),
+        RelayResponse::Payload(_) => Err("Unexpected response from relay".into()),
+    }
+}
+
+/// Waits for the round message participant `from` sent with the same file name as
+/// `path`, opens it and saves it there.
+///
+/// # Errors
+/// Returns an error if the relay cannot be reached or the message was not sealed by
+/// participant `from`.
+pub fn receive<P: AsRef<Path>>(
+    connect: &str,
+    session_id: &str,
+    identity_file: &str,
+    identities_dir: &str,
+    from: u32,
+    path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Wait for the sealed message under its tag
+    let path = path.as_ref();
+    let identity = load_identity(identity_file)?;
+    let sender = load_public_identity(identities_dir, from)?;
+    let name = file_name(path)?;
+    let label = label(session_id, from, identity.index, &name);
+    let request = RelayRequest::Fetch {
+        session_id: session_id.to_string(),
+        tag: tag(&identity, &sender, &label),
+    };
+    let mut waiting = false;
+    let payload = loop {
+        match request_once(connect, &request)? {
+            RelayResponse::Payload(Some(payload)) => break payload,
+            RelayResponse::Payload(None) => {
+                if !waiting {
+                    info!(
+                        "Waiting for {} from participant {} at {}...",
+                        name, from, connect
+                    );
+                    waiting = true;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            RelayResponse::Error(err) => {
+                return Err(format!("Relay failed to return {}: {}", name, err).into())
+            }
+            RelayResponse::Forwarded => return Err("Unexpected response from relay".into()),
+        }
+    };
+
+    // Step 2: Open the message and save it
+    let message = noise::open(&identity, &sender, &label, &hex::decode(payload)?)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, message)?;
+    report(
+        "message_received",
+        &format!("Received {} from participant {}", name, from),
+        json!({ "name": name, "from": from }),
+    );
+    Ok(())
+}
+
+/// Answers requests on one connection until the peer closes it.
+fn handle_connection(
+    mut stream: TcpStream,
+    pending: &Mutex<Pending>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    while let Some(request) = receive_message::<RelayRequest, _>(&mut stream)? {
+        let mut pending = pending.lock().map_err(|_| "Relay state is poisoned")?;
+        let response = match request {
+            RelayRequest::Forward {
+                session_id,
+                tag,
+                payload,
+            } => {
+                debug!(
+                    "Holding {} bytes for session {}",
+                    payload.len() / 2,
+                    session_id
+                );
+                match pending.forward(session_id, tag, payload) {
+                    Ok(()) => RelayResponse::Forwarded,
+                    Err(err) => RelayResponse::Error(err.to_string()),
+                }
+            }
+            RelayRequest::Fetch { session_id, tag } => {
+                RelayResponse::Payload(pending.fetch(&session_id, &tag))
+            }
+        };
+        drop(pending);
+        send_message(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+/// Sends a single request on a fresh connection and returns the response.
+fn request_once(
+    connect: &str,
+    request: &RelayRequest,
+) -> Result<RelayResponse, Box<dyn std::error::Error>> {
+    let mut stream = TcpStream::connect(connect)?;
+    send_message(&mut stream, request)?;
+    receive_message(&mut stream)?.ok_or_else(|| "Relay closed the connection".into())
+}
+
+/// Binds a sealed message to its session, sender, receiver and name.
+fn label(session_id: &str, from: u32, to: u32, name: &str) -> Vec<u8> {
+    format!("FROST-CLI RELAY {} {} {} {}", session_id, from, to, name).into_bytes()
+}
+
+/// Files a message at the relay without revealing its label.
+///
+/// The label alone is easy to guess, so it is hashed together with the Diffie-Hellman
+/// secret of the two participants' identity keys.
+fn tag(identity: &Identity, peer: &PublicIdentity, label: &[u8]) -> String {
+    let secret = StaticSecret::from(identity.noise_private_key)
+        .diffie_hellman(&PublicKey::from(peer.noise_public_key));
+    let mut hasher = Sha256::new();
+    hasher.update(secret.as_bytes());
+    hasher.update(label);
+    hex::encode(hasher.finalize())
+}
+
+fn file_name(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid message file: {}", path.display()).into())
+}
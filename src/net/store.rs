@@ -0,0 +1,236 @@
+//! Session manifests kept by a coordinator server.
+//!
+//! Both the HTTP and the gRPC coordinator store one `<session_id>.json` manifest per
+//! signing session in a directory, so a restarted server picks up where it stopped.
+//! Every created or changed session is also broadcast to [`SessionStore::subscribe`],
+//! which is how connected participant daemons learn that they should commit or sign.
+
+use crate::io::files::FileOptions;
+use crate::io::output::report;
+use crate::metrics::{self, Round};
+use crate::session::{load_session, save_session, Session, SessionStatus};
+use crate::signing::{PartialSignature, PublicCommitments};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::broadcast;
+
+/// How many session updates a slow subscriber may fall behind before it misses some.
+const UPDATE_CAPACITY: usize = 64;
+
+/// Why a coordinator request failed, so each transport can pick its own status code.
+#[derive(Debug)]
+pub enum StoreError {
+    /// The request was malformed, for example an invalid session ID.
+    Invalid(String),
+    /// The session does not exist or has no signature yet.
+    NotFound(String),
+    /// The session rejected the round message.
+    Rejected(String),
+    /// The client may not submit this round message.
+    Forbidden(String),
+    /// The manifest could not be read or written.
+    Internal(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Invalid(err)
+            | StoreError::NotFound(err)
+            | StoreError::Rejected(err)
+            | StoreError::Forbidden(err)
+            | StoreError::Internal(err) => f.write_str(err),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// The session manifests of a coordinator server.
+pub struct SessionStore {
+    key_file: String,
+    sessions_dir: PathBuf,
+    /// Transcript the sessions are recorded in, see [`crate::transcript`].
+    transcript_file: Option<String>,
+    /// Serializes every read-modify-write of a session manifest.
+    lock: Mutex<()>,
+    updates: broadcast::Sender<Session>,
+    /// When the current round of each session started, for the round duration metrics.
+    /// Sessions picked up from before a restart are not timed.
+    round_started: Mutex<HashMap<String, Instant>>,
+    /// How the session manifests are written.
+    options: FileOptions,
+}
+
+impl SessionStore {
+    /// Opens the store in `sessions_dir`, creating the directory if needed. Every round
+    /// message and signature of its sessions is recorded in `transcript_file`, if one is
+    /// given.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created.
+    pub fn open(
+        key_file: &str,
+        sessions_dir: &str,
+        transcript_file: Option<&str>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        fs::create_dir_all(sessions_dir)?;
+        Ok(SessionStore {
+            key_file: key_file.to_string(),
+            sessions_dir: PathBuf::from(sessions_dir),
+            transcript_file: transcript_file.map(str::to_string),
+            lock: Mutex::new(()),
+            updates: broadcast::channel(UPDATE_CAPACITY).0,
+            round_started: Mutex::new(HashMap::new()),
+            options: FileOptions::default(),
+        })
+    }
+
+    /// Writes the session manifests as `options` say instead of as JSON.
+    pub fn with_options(mut self, options: FileOptions) -> SessionStore {
+        self.options = options;
+        self
+    }
+
+    /// Starts a new signing session for the group of the store's key file, signing
+    /// `message` in `context`.
+    pub fn create(
+        &self,
+        message: &str,
+        context: &str,
+        signers: Vec<u32>,
+    ) -> Result<Session, StoreError> {
+        let session = Session::new(message, context, &self.key_file, signers, &self.options)
+            .map_err(|err| StoreError::Invalid(err.to_string()))?;
+        let session_file = self
+            .sessions_dir
+            .join(format!("{}.json", session.session_id));
+        save_session(&session_file.to_string_lossy(), &session, &self.options).map_err(internal)?;
+        self.round_started
+            .lock()
+            .map_err(internal)?
+            .insert(session.session_id.clone(), Instant::now());
+        let _ = self.updates.send(session.clone());
+
+        report(
+            "session_created",
+            &format!(
+                "Signing session {} created with signers {:?}",
+                session.session_id, session.roster
+            ),
+            json!({ "session_id": session.session_id, "signers": session.roster }),
+        );
+        Ok(session)
+    }
+
+    /// Loads the manifest of a session.
+    pub fn get(&self, session_id: &str) -> Result<Session, StoreError> {
+        load_session(&self.session_file(session_id)?).map_err(internal)
+    }
+
+    /// Adds a signer's public commitments to their session.
+    pub fn add_commitments(&self, commitments: PublicCommitments) -> Result<Session, StoreError> {
+        let session_id = commitments.session_id.clone();
+        self.update(&session_id, |session| {
+            session.add_commitments(commitments, self.transcript_file.as_deref())
+        })
+    }
+
+    /// Adds a signer's partial signature to their session.
+    pub fn add_partial_signature(&self, partial: PartialSignature) -> Result<Session, StoreError> {
+        let session_id = partial.session_id.clone();
+        self.update(&session_id, |session| {
+            session.add_partial_signature(partial, self.transcript_file.as_deref())
+        })
+    }
+
+    /// Returns the threshold signature of a completed session.
+    pub fn signature(&self, session_id: &str) -> Result<Vec<u8>, StoreError> {
+        self.get(session_id)?
+            .signature
+            .ok_or_else(|| StoreError::NotFound("Session is not complete yet".to_string()))
+    }
+
+    /// Returns every session that is still collecting commitments or partial signatures.
+    pub fn open_sessions(&self) -> Result<Vec<Session>, StoreError> {
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&self.sessions_dir).map_err(internal)? {
+            let path = entry.map_err(internal)?.path();
+            if path.extension().map_or(true, |ext| ext != "json") {
+                continue;
+            }
+            let session = load_session(&path.to_string_lossy()).map_err(internal)?;
+            if session.status != SessionStatus::Complete {
+                sessions.push(session);
+            }
+        }
+        Ok(sessions)
+    }
+
+    /// Subscribes to every session created or changed from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Session> {
+        self.updates.subscribe()
+    }
+
+    /// Loads a session, applies `step` to it and saves it again.
+    fn update(
+        &self,
+        session_id: &str,
+        step: impl FnOnce(&mut Session) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<Session, StoreError> {
+        let _guard = self.lock.lock().map_err(internal)?;
+        let session_file = self.session_file(session_id)?;
+        let mut session = load_session(&session_file).map_err(internal)?;
+        let status = session.status;
+        step(&mut session).map_err(|err| StoreError::Rejected(err.to_string()))?;
+        save_session(&session_file, &session, &self.options).map_err(internal)?;
+        if session.status != status {
+            self.round_finished(session_id, status)?;
+        }
+        let _ = self.updates.send(session.clone());
+        Ok(session)
+    }
+
+    /// Records how long the round a session just left took, and starts timing the next.
+    fn round_finished(&self, session_id: &str, status: SessionStatus) -> Result<(), StoreError> {
+        let round = match status {
+            SessionStatus::CollectingCommitments => Round::Commitments,
+            SessionStatus::CollectingPartials => Round::PartialSignatures,
+            SessionStatus::Complete => return Ok(()),
+        };
+        let mut round_started = self.round_started.lock().map_err(internal)?;
+        if let Some(started) = round_started.remove(session_id) {
+            metrics::round_finished(round, started.elapsed());
+            if round == Round::Commitments {
+                round_started.insert(session_id.to_string(), Instant::now());
+            }
+        }
+        Ok(())
+    }
+
+    fn session_file(&self, session_id: &str) -> Result<String, StoreError> {
+        if session_id.len() != 32 || hex::decode(session_id).is_err() {
+            return Err(StoreError::Invalid(format!(
+                "Invalid session ID: {}",
+                session_id
+            )));
+        }
+        let path = self.sessions_dir.join(format!("{}.json", session_id));
+        if !path.exists() {
+            return Err(StoreError::NotFound(format!(
+                "Unknown session: {}",
+                session_id
+            )));
+        }
+        Ok(path.to_string_lossy().into_owned())
+    }
+}
+
+fn internal<E: ToString>(err: E) -> StoreError {
+    StoreError::Internal(err.to_string())
+}
@@ -0,0 +1,272 @@
+//! Length-prefixed JSON messages over TCP.
+//!
+//! One machine runs a mailbox with [`serve`] and every participant connects to it with
+//! [`upload`] and [`download`]. The mailbox stores each round message under its file
+//! name, so participants never have to reach each other directly. Messages are sent
+//! in plaintext and the mailbox is meant for ceremonies on a trusted LAN. The CLI only
+//! uploads secret shares sealed to their receivers with [`crate::net::noise`].
+
+use crate::io::files::FileOptions;
+use crate::io::output::report;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Largest message accepted from a peer, well above any round message.
+const MAX_MESSAGE_LEN: u32 = 16 * 1024 * 1024;
+
+/// How long [`download`] waits before asking the mailbox again.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A request sent to the mailbox.
+#[derive(Serialize, Deserialize)]
+pub enum Request {
+    /// Store a round message under `name`, replacing any previous one.
+    Put {
+        name: String,
+        message: serde_json::Value,
+    },
+    /// Fetch the round message stored under `name`.
+    Get { name: String },
+}
+
+/// The mailbox's answer to a [`Request`].
+#[derive(Serialize, Deserialize)]
+pub enum Response {
+    Stored,
+    /// The requested message, or `None` if nobody has uploaded it yet.
+    Message(Option<serde_json::Value>),
+    Error(String),
+}
+
+/// Writes `value` to `stream` as a big-endian `u32` length followed by its JSON.
+///
+/// # Errors
+/// Returns an error if the value cannot be serialized or the stream is closed.
+pub fn send_message<T: Serialize, W: Write>(
+    stream: &mut W,
+    value: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let payload = serde_json::to_vec(value)?;
+    let len = u32::try_from(payload.len())
+        .ok()
+        .filter(|&len| len <= MAX_MESSAGE_LEN)
+        .ok_or("Message is too large to send")?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(&payload)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Reads one length-prefixed JSON message from `stream`.
+///
+/// Returns `None` if the peer closed the connection before a new message started.
+///
+/// # Errors
+/// Returns an error if the message is too large, truncated, or not valid JSON for `T`.
+pub fn receive_message<T: DeserializeOwned, R: Read>(
+    stream: &mut R,
+) -> Result<Option<T>, Box<dyn std::error::Error>> {
+    let mut len = [0u8; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MESSAGE_LEN {
+        return Err(format!("Message of {} bytes exceeds the size limit", len).into());
+    }
+
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+    Ok(Some(serde_json::from_slice(&payload)?))
+}
+
+/// Runs a mailbox that stores round messages in `dir` until the process is stopped.
+///
+/// # Arguments
+/// - `listen`: Address to listen on, for example `0.0.0.0:7000`.
+/// - `dir`: Directory to store the uploaded round messages in.
+/// - `options`: How the uploaded round messages are stored, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the address cannot be bound or the directory cannot be created.
+pub fn serve(
+    listen: &str,
+    dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let listener = TcpListener::bind(listen)?;
+    report(
+        "mailbox_listening",
+        &format!(
+            "Mailbox listening on {}, storing messages in: {}",
+            listen, dir
+        ),
+        json!({ "listen": listen, "dir": dir }),
+    );
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let dir = PathBuf::from(dir);
+        let options = options.clone();
+        thread::spawn(move || {
+            let peer = stream
+                .peer_addr()
+                .map(|addr| addr.to_string())
+                .unwrap_or_default();
+            if let Err(err) = handle_connection(stream, &dir, &options) {
+                warn!("Connection from {} failed: {}", peer, err);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Uploads a round message file to the mailbox under its file name.
+///
+/// # Errors
+/// Returns an error if the file is not valid JSON or the mailbox rejects it.
+pub fn upload<P: AsRef<Path>>(connect: &str, path: P) -> Result<(), Box<dyn std::error::Error>> {
+    let name = file_name(path.as_ref())?;
+    upload_as(connect, &name, path)
+}
+
+/// Uploads a round message file to the mailbox under `name`.
+///
+/// # Errors
+/// Returns an error if the file is not valid JSON or the mailbox rejects it.
+pub fn upload_as<P: AsRef<Path>>(
+    connect: &str,
+    name: &str,
+    path: P,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = Request::Put {
+        name: name.to_string(),
+        message: crate::read_json(path)?,
+    };
+    match request_once(connect, &request)? {
+        Response::Stored => {
+            report(
+                "message_uploaded",
+                &format!("Uploaded {} to {}", name, connect),
+                json!({ "name": name, "mailbox": connect }),
+            );
+            Ok(())
+        }
+        Response::Error(err) => Err(format!("Mailbox rejected {}: {}", name, err).into()),
+        Response::Message(_) => Err("Unexpected response from mailbox".into()),
+    }
+}
+
+/// Downloads the round message with the same file name as `path` and saves it there.
+///
+/// Waits until some participant has uploaded the message.
+///
+/// # Errors
+/// Returns an error if the mailbox cannot be reached or the file cannot be written.
+pub fn download<P: AsRef<Path>>(
+    connect: &str,
+    path: P,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = path.as_ref();
+    let name = file_name(path)?;
+    let mut waiting = false;
+    loop {
+        let request = Request::Get { name: name.clone() };
+        match request_once(connect, &request)? {
+            Response::Message(Some(message)) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                crate::write_json(path, &message, options)?;
+                report(
+                    "message_downloaded",
+                    &format!("Downloaded {} from {}", name, connect),
+                    json!({ "name": name, "mailbox": connect }),
+                );
+                return Ok(());
+            }
+            Response::Message(None) => {
+                if !waiting {
+                    info!("Waiting for {} at {}...", name, connect);
+                    waiting = true;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            Response::Error(err) => {
+                return Err(format!("Mailbox failed to return {}: {}", name, err).into())
+            }
+            Response::Stored => return Err("Unexpected response from mailbox".into()),
+        }
+    }
+}
+
+/// Sends a single request on a fresh connection and returns the response.
+fn request_once(connect: &str, request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+    let mut stream = TcpStream::connect(connect)?;
+    send_message(&mut stream, request)?;
+    receive_message(&mut stream)?.ok_or_else(|| "Mailbox closed the connection".into())
+}
+
+/// Answers requests on one connection until the peer closes it.
+fn handle_connection(
+    mut stream: TcpStream,
+    dir: &Path,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    while let Some(request) = receive_message::<Request, _>(&mut stream)? {
+        let response = match request {
+            Request::Put { name, message } => match mailbox_path(dir, &name) {
+                Ok(path) => {
+                    crate::write_json(&path, &message, options)?;
+                    report(
+                        "message_stored",
+                        &format!("Stored {}", name),
+                        json!({ "name": name }),
+                    );
+                    Response::Stored
+                }
+                Err(err) => Response::Error(err.to_string()),
+            },
+            Request::Get { name } => match mailbox_path(dir, &name) {
+                Ok(path) if path.exists() => Response::Message(Some(crate::read_json(&path)?)),
+                Ok(_) => Response::Message(None),
+                Err(err) => Response::Error(err.to_string()),
+            },
+        };
+        send_message(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+/// Resolves a message name inside the mailbox directory, rejecting anything but a
+/// plain `.json` file name.
+fn mailbox_path(dir: &Path, name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let is_plain = !name.starts_with('.')
+        && name.ends_with(".json")
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !is_plain {
+        return Err(format!("Invalid message name: {}", name).into());
+    }
+    Ok(dir.join(name))
+}
+
+fn file_name(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Invalid message file: {}", path.display()).into())
+}
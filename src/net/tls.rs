@@ -0,0 +1,167 @@
+//! Mutual TLS for the coordinator servers and their clients.
+//!
+//! The server only accepts clients with a certificate issued by the client CA. Each
+//! participant's client certificate is also kept as `participant_<index>.pem` in a
+//! directory on the server, which is how the server knows which participant it is
+//! talking to and rejects round messages submitted on behalf of someone else.
+
+use crate::net::store::StoreError;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::sync::Arc;
+use tracing::info;
+
+/// TLS settings of a coordinator server.
+pub struct ServerTls {
+    /// PEM certificate chain of the server.
+    pub cert_file: String,
+    /// PEM private key of the server.
+    pub key_file: String,
+    /// PEM certificate of the CA that issues client certificates.
+    pub client_ca_file: String,
+    /// Directory containing each participant's `participant_<index>.pem` certificate.
+    pub participants_dir: String,
+}
+
+/// TLS settings of a coordinator client.
+pub struct ClientTls {
+    /// PEM certificate of the CA that issued the server certificate.
+    pub ca_file: String,
+    /// PEM client certificate chain.
+    pub cert_file: String,
+    /// PEM private key of the client certificate.
+    pub key_file: String,
+}
+
+/// The client on the other end of a TLS connection.
+#[derive(Clone, Copy, Debug)]
+pub struct Peer {
+    /// Participant index the client certificate is mapped to, if any.
+    pub participant: Option<u32>,
+}
+
+impl ServerTls {
+    pub(crate) fn rustls_config(&self) -> Result<Arc<ServerConfig>, Box<dyn std::error::Error>> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let verifier = WebPkiClientVerifier::builder_with_provider(
+            Arc::new(load_roots(&self.client_ca_file)?),
+            Arc::clone(&provider),
+        )
+        .build()?;
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(load_certs(&self.cert_file)?, load_key(&self.key_file)?)?;
+        Ok(Arc::new(config))
+    }
+
+    pub(crate) fn tonic_config(
+        &self,
+    ) -> Result<tonic::transport::ServerTlsConfig, Box<dyn std::error::Error>> {
+        Ok(tonic::transport::ServerTlsConfig::new()
+            .identity(tonic::transport::Identity::from_pem(
+                fs::read(&self.cert_file)?,
+                fs::read(&self.key_file)?,
+            ))
+            .client_ca_root(tonic::transport::Certificate::from_pem(fs::read(
+                &self.client_ca_file,
+            )?)))
+    }
+
+    /// Maps the certificate in each `participant_<index>.pem` to its index.
+    pub(crate) fn participants(&self) -> Result<HashMap<Vec<u8>, u32>, Box<dyn std::error::Error>> {
+        let mut participants = HashMap::new();
+        for entry in fs::read_dir(&self.participants_dir)? {
+            let path = entry?.path();
+            let index = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("participant_"))
+                .and_then(|name| name.strip_suffix(".pem"))
+                .and_then(|index| index.parse::<u32>().ok());
+            let Some(index) = index else { continue };
+            let cert = load_certs(&path.to_string_lossy())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No certificate in {}", path.display()))?;
+            participants.insert(cert.to_vec(), index);
+        }
+        info!(
+            "Loaded client certificates of {} participants from: {}",
+            participants.len(),
+            self.participants_dir
+        );
+        Ok(participants)
+    }
+}
+
+impl ClientTls {
+    pub(crate) fn rustls_config(&self) -> Result<Arc<ClientConfig>, Box<dyn std::error::Error>> {
+        let config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_root_certificates(load_roots(&self.ca_file)?)
+                .with_client_auth_cert(load_certs(&self.cert_file)?, load_key(&self.key_file)?)?;
+        Ok(Arc::new(config))
+    }
+
+    pub(crate) fn tonic_config(
+        &self,
+    ) -> Result<tonic::transport::ClientTlsConfig, Box<dyn std::error::Error>> {
+        Ok(tonic::transport::ClientTlsConfig::new()
+            .ca_certificate(tonic::transport::Certificate::from_pem(fs::read(
+                &self.ca_file,
+            )?))
+            .identity(tonic::transport::Identity::from_pem(
+                fs::read(&self.cert_file)?,
+                fs::read(&self.key_file)?,
+            )))
+    }
+}
+
+/// Returns the peer of a TLS connection given the DER of its client certificate.
+pub(crate) fn peer(participants: &HashMap<Vec<u8>, u32>, cert: Option<&[u8]>) -> Peer {
+    Peer {
+        participant: cert.and_then(|cert| participants.get(cert).copied()),
+    }
+}
+
+/// Checks that `peer` may submit the round messages of participant `index`.
+///
+/// Connections without TLS have no peer and are not checked.
+pub(crate) fn authorize(peer: Option<&Peer>, index: u32) -> Result<(), StoreError> {
+    match peer.map(|peer| peer.participant) {
+        None => Ok(()),
+        Some(Some(participant)) if participant == index => Ok(()),
+        Some(Some(participant)) => Err(StoreError::Forbidden(format!(
+            "Participant {} cannot submit round messages of participant {}",
+            participant, index
+        ))),
+        Some(None) => Err(StoreError::Forbidden(
+            "Client certificate is not mapped to a participant".to_string(),
+        )),
+    }
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    Ok(rustls_pemfile::certs(&mut reader).collect::<Result<_, _>>()?)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, Box<dyn std::error::Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    rustls_pemfile::private_key(&mut reader)?
+        .ok_or_else(|| format!("No private key in {}", path).into())
+}
+
+fn load_roots(path: &str) -> Result<RootCertStore, Box<dyn std::error::Error>> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(cert)?;
+    }
+    Ok(roots)
+}
@@ -0,0 +1,453 @@
+//! A transport trait for typed round messages, and DKG and signing ceremonies run over it.
+//!
+//! The other modules of [`crate::net`] move message files by name. A [`Transport`]
+//! instead sends a typed [`RoundMessage`] to a participant index and receives the one a
+//! participant sent, so a new transport such as TCP, HTTP or libp2p only has to move
+//! bytes between indices. [`run_dkg`] and [`run_signing`] drive the same round functions
+//! as the `dkg` and signing commands over any transport, keeping their state and round
+//! files in a work directory.
+//!
+//! Two transports come with the library: [`FileTransport`] exchanges messages through a
+//! directory every participant can reach, such as a network share, and
+//! [`MemoryTransport`] connects participants running in one process.
+
+use crate::dkg::{self, Round2Message};
+use crate::io::files::{load_participant_share, FileOptions};
+use crate::io::signature::SignatureFormat;
+use crate::messages;
+use crate::signing;
+use crate::{read_json, write_json, write_secret_json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+pub use crate::messages::{MessageKind, RoundMessage};
+
+/// How long [`FileTransport::receive`] waits before looking for the message again.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Sends and receives round messages on behalf of one participant.
+///
+/// Messages are addressed by participant index. A transport keeps each message until it
+/// is received, so a participant may send before the receiver asks for it.
+pub trait Transport {
+    /// Returns the index of the participant this transport sends for.
+    fn index(&self) -> u32;
+
+    /// Sends a round message to participant `to`.
+    ///
+    /// # Errors
+    /// Returns an error if the message cannot be delivered.
+    fn send(&self, to: u32, message: &RoundMessage) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Waits for the message of `kind` that participant `from` sent to this one.
+    ///
+    /// # Errors
+    /// Returns an error if the message cannot be read, or does not arrive in time.
+    fn receive(
+        &self,
+        from: u32,
+        kind: MessageKind,
+    ) -> Result<RoundMessage, Box<dyn std::error::Error>>;
+}
+
+/// Exchanges round messages as JSON files in a directory shared by all participants.
+///
+/// A message from `from` to `to` is written to `<kind>_<from>_<to>.json`, encoded by
+/// [`messages::encode`], and removed once received.
+pub struct FileTransport {
+    dir: PathBuf,
+    index: u32,
+    timeout: Option<Duration>,
+}
+
+impl FileTransport {
+    /// Returns a transport for participant `index` through the directory `dir`, which is
+    /// created if needed. Receiving waits for as long as it takes.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created.
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        index: u32,
+    ) -> Result<FileTransport, Box<dyn std::error::Error>> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(FileTransport {
+            dir,
+            index,
+            timeout: None,
+        })
+    }
+
+    /// Makes [`Transport::receive`] fail after waiting `timeout` for a message.
+    pub fn with_timeout(mut self, timeout: Duration) -> FileTransport {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn message_path(&self, kind: MessageKind, from: u32, to: u32) -> PathBuf {
+        self.dir.join(format!("{}_{}_{}.json", kind, from, to))
+    }
+}
+
+impl Transport for FileTransport {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn send(&self, to: u32, message: &RoundMessage) -> Result<(), Box<dyn std::error::Error>> {
+        // Write beside the final name and rename, so a receiver never reads half a message
+        let path = self.message_path(message.kind(), self.index, to);
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, messages::encode(message)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(())
+    }
+
+    fn receive(
+        &self,
+        from: u32,
+        kind: MessageKind,
+    ) -> Result<RoundMessage, Box<dyn std::error::Error>> {
+        let path = self.message_path(kind, from, self.index);
+        let started = Instant::now();
+        let mut waiting = false;
+        while !path.exists() {
+            if self
+                .timeout
+                .is_some_and(|timeout| started.elapsed() >= timeout)
+            {
+                return Err(timed_out(kind, from));
+            }
+            if !waiting {
+                info!("Waiting for {}...", path.display());
+                waiting = true;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+        let message = messages::decode(&fs::read(&path)?)?;
+        fs::remove_file(&path)?;
+        check_kind(message, from, kind)
+    }
+}
+
+/// Messages sent over a [`MemoryTransport`] network and not yet received, keyed by kind,
+/// sender and receiver.
+type Mailbox = HashMap<(MessageKind, u32, u32), RoundMessage>;
+
+/// Exchanges round messages between participants in one process, such as threads of a
+/// test or a service that runs every participant.
+///
+/// [`MemoryTransport::network`] returns one connected transport per participant.
+pub struct MemoryTransport {
+    mailbox: Arc<(Mutex<Mailbox>, Condvar)>,
+    index: u32,
+    timeout: Option<Duration>,
+}
+
+impl MemoryTransport {
+    /// Returns connected transports for participants 1 to `n`, in index order.
+    pub fn network(n: u32) -> Vec<MemoryTransport> {
+        let mailbox = Arc::new((Mutex::new(HashMap::new()), Condvar::new()));
+        (1..=n)
+            .map(|index| MemoryTransport {
+                mailbox: Arc::clone(&mailbox),
+                index,
+                timeout: None,
+            })
+            .collect()
+    }
+
+    /// Makes [`Transport::receive`] fail after waiting `timeout` for a message.
+    pub fn with_timeout(mut self, timeout: Duration) -> MemoryTransport {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn send(&self, to: u32, message: &RoundMessage) -> Result<(), Box<dyn std::error::Error>> {
+        // Copy the message through its encoding, as every other transport delivers its own copy
+        let message = messages::decode(&messages::encode(message)?)?;
+        let (mailbox, arrived) = &*self.mailbox;
+        mailbox
+            .lock()
+            .map_err(|_| "In-memory transport is poisoned")?
+            .insert((message.kind(), self.index, to), message);
+        arrived.notify_all();
+        Ok(())
+    }
+
+    fn receive(
+        &self,
+        from: u32,
+        kind: MessageKind,
+    ) -> Result<RoundMessage, Box<dyn std::error::Error>> {
+        let (mailbox, arrived) = &*self.mailbox;
+        let key = (kind, from, self.index);
+        let started = Instant::now();
+        let mut mailbox = mailbox
+            .lock()
+            .map_err(|_| "In-memory transport is poisoned")?;
+        loop {
+            if let Some(message) = mailbox.remove(&key) {
+                return check_kind(message, from, kind);
+            }
+            mailbox = match self.timeout {
+                None => arrived
+                    .wait(mailbox)
+                    .map_err(|_| "In-memory transport is poisoned")?,
+                Some(timeout) => {
+                    let remaining = timeout.saturating_sub(started.elapsed());
+                    if remaining.is_zero() {
+                        return Err(timed_out(kind, from));
+                    }
+                    arrived
+                        .wait_timeout(mailbox, remaining)
+                        .map_err(|_| "In-memory transport is poisoned")?
+                        .0
+                }
+            };
+        }
+    }
+}
+
+/// Runs one participant's side of a DKG over a transport and saves its share.
+///
+/// Runs [`dkg::round_one`], sends the round one message to every other participant,
+/// runs [`dkg::round_two`] on everyone's round one messages, sends each secret share to
+/// its receiver, and runs [`dkg::finish`] on the shares received.
+///
+/// # Arguments
+/// - `transport`: The transport of this participant, see [`Transport::index`].
+/// - `t`: Threshold value, the minimum number of participants required to sign.
+/// - `n`: Total number of participants.
+/// - `work_dir`: Directory to keep the secret DKG state and round messages in.
+/// - `output_share_file`: Share location to save the resulting participant share to, see
+///   [`crate::storage`].
+/// - `options`: How the share and the round messages are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if a round fails, or a message cannot be sent or received.
+pub fn run_dkg(
+    transport: &dyn Transport,
+    t: u32,
+    n: u32,
+    work_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = transport.index();
+    let others: Vec<u32> = (1..=n).filter(|&other| other != index).collect();
+    let state_file = work_path(work_dir, &format!("dkg_state_{}.json", index));
+    let round1_dir = work_path(work_dir, "round1");
+    let outgoing_dir = work_path(work_dir, "outgoing");
+    let shares_dir = work_path(work_dir, "shares");
+
+    // Step 1: Run round one and send the round one message to every other participant
+    fs::create_dir_all(work_dir)?;
+    dkg::round_one(index, t, n, &state_file, &round1_dir, options)?;
+    let round1_files = dkg::round1_files(&state_file, &round1_dir)?;
+    let round1 = RoundMessage::DkgRound1(read_json(&round1_files[index as usize - 1])?);
+    for &other in &others {
+        transport.send(other, &round1)?;
+    }
+
+    // Step 2: Receive everyone else's round one message and run round two
+    for &other in &others {
+        let RoundMessage::DkgRound1(message) = transport.receive(other, MessageKind::DkgRound1)?
+        else {
+            unreachable!("transports check the kind of received messages");
+        };
+        write_json(&round1_files[other as usize - 1], &message, options)?;
+    }
+    dkg::round_two(&state_file, &round1_dir, &outgoing_dir, options)?;
+
+    // Step 3: Send each secret share to its receiver
+    for (&other, share_file) in others
+        .iter()
+        .zip(dkg::outgoing_share_files(&state_file, &outgoing_dir)?)
+    {
+        let share: Round2Message = read_json(&share_file)?;
+        transport.send(other, &RoundMessage::DkgShare(share))?;
+        fs::remove_file(&share_file)?;
+    }
+
+    // Step 4: Receive the shares sent to this participant and derive its share
+    fs::create_dir_all(&shares_dir)?;
+    for (&other, share_file) in others
+        .iter()
+        .zip(dkg::incoming_share_files(&state_file, &shares_dir)?)
+    {
+        let RoundMessage::DkgShare(share) = transport.receive(other, MessageKind::DkgShare)? else {
+            unreachable!("transports check the kind of received messages");
+        };
+        write_secret_json(&share_file, &share, options)?;
+    }
+    let result = dkg::finish(&state_file, &shares_dir, output_share_file, options);
+    fs::remove_dir_all(&shares_dir)?;
+    result
+}
+
+/// Runs one signer's side of a signing ceremony over a transport. Every signer
+/// aggregates the signature and saves it.
+///
+/// Runs [`signing::commit`], exchanges the public commitments with the other signers,
+/// runs [`signing::partial_sign`], exchanges the partial signatures, and runs
+/// [`signing::aggregate_at`].
+///
+/// # Arguments
+/// - `transport`: The transport of this signer, whose index is that of its share.
+/// - `message`: The message to sign.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `session_id`: ID of the signing session, the same for every signer.
+/// - `share_file`: Path to the signer's share file.
+/// - `signers`: Participant indices of every signer, including this one.
+/// - `work_dir`: Directory to keep the secret nonces and round messages in.
+/// - `signature_file`: Path to save the threshold signature.
+/// - `options`: How the signature and the round messages are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if this signer's share is not participant `transport.index()`'s or
+/// is not among `signers`, a round fails, or a message cannot be sent or received.
+pub fn run_signing(
+    transport: &dyn Transport,
+    message: &str,
+    context: &str,
+    session_id: &str,
+    share_file: &str,
+    signers: &[u32],
+    work_dir: &str,
+    signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let index = transport.index();
+    if load_participant_share(share_file, options)?.index != index {
+        return Err(format!("{} is not the share of participant {}", share_file, index).into());
+    }
+    if !signers.contains(&index) {
+        return Err(format!("Participant {} is not among signers {:?}", index, signers).into());
+    }
+    let others: Vec<u32> = signers
+        .iter()
+        .copied()
+        .filter(|&other| other != index)
+        .collect();
+    fs::create_dir_all(work_dir)?;
+    let commitment_files: Vec<String> = signers
+        .iter()
+        .map(|signer| work_path(work_dir, &format!("commitments_{}.json", signer)))
+        .collect();
+    let secret_commitments_file =
+        work_path(work_dir, &format!("secret_commitments_{}.json", index));
+    let partials_dir = work_path(work_dir, "partials");
+    let partial_file =
+        |signer: u32| work_path(&partials_dir, &format!("partial_signature_{}.json", signer));
+    let own_commitments = work_path(work_dir, &format!("commitments_{}.json", index));
+
+    // Step 1: Commit to nonces and send the public commitments to every other signer
+    signing::commit(
+        session_id,
+        share_file,
+        &own_commitments,
+        &secret_commitments_file,
+        signing::CommitOptions {
+            files: options.clone(),
+            ..Default::default()
+        },
+    )?;
+    let commitments = RoundMessage::Commitments(read_json(&own_commitments)?);
+    for &other in &others {
+        transport.send(other, &commitments)?;
+    }
+
+    // Step 2: Receive the other signers' commitments and sign
+    for (&signer, commitment_file) in signers.iter().zip(&commitment_files) {
+        if signer == index {
+            continue;
+        }
+        let RoundMessage::Commitments(commitments) =
+            transport.receive(signer, MessageKind::Commitments)?
+        else {
+            unreachable!("transports check the kind of received messages");
+        };
+        write_json(commitment_file, &commitments, options)?;
+    }
+    fs::create_dir_all(&partials_dir)?;
+    signing::partial_sign(
+        message,
+        context,
+        share_file,
+        &secret_commitments_file,
+        &commitment_files,
+        &partial_file(index),
+        options,
+    )?;
+
+    // Step 3: Send the partial signature to every other signer
+    let partial = RoundMessage::PartialSignature(read_json(partial_file(index))?);
+    for &other in &others {
+        transport.send(other, &partial)?;
+    }
+
+    // Step 4: Receive the other signers' partial signatures and aggregate them
+    for &other in &others {
+        let RoundMessage::PartialSignature(partial) =
+            transport.receive(other, MessageKind::PartialSignature)?
+        else {
+            unreachable!("transports check the kind of received messages");
+        };
+        write_json(partial_file(other), &partial, options)?;
+    }
+    signing::aggregate_at(
+        message,
+        context,
+        share_file,
+        &commitment_files,
+        &partials_dir,
+        0,
+        signature_file,
+        SignatureFormat::Json,
+        options,
+        None,
+    )
+}
+
+/// Returns the received message if it is of the kind asked for.
+fn check_kind(
+    message: RoundMessage,
+    from: u32,
+    kind: MessageKind,
+) -> Result<RoundMessage, Box<dyn std::error::Error>> {
+    if message.kind() != kind {
+        return Err(format!(
+            "Participant {} sent a {} message, expected {}",
+            from,
+            message.kind(),
+            kind
+        )
+        .into());
+    }
+    Ok(message)
+}
+
+fn timed_out(kind: MessageKind, from: u32) -> Box<dyn std::error::Error> {
+    format!(
+        "Timed out waiting for the {} message of participant {}",
+        kind, from
+    )
+    .into()
+}
+
+fn work_path(dir: &str, name: &str) -> String {
+    Path::new(dir).join(name).to_string_lossy().into_owned()
+}
@@ -0,0 +1,449 @@
+//! WebSocket connection between the HTTP coordinator and participant daemons.
+//!
+//! A signer behind a firewall runs [`run_participant`], which keeps one outbound
+//! WebSocket open to `/participants/{index}/ws` on the coordinator. Whenever a session
+//! with this signer on its roster needs commitments or a partial signature, the
+//! coordinator pushes a [`Push`] and the daemon answers with a [`Reply`].
+//!
+//! With [`run_participant_with_pool`] the daemon commits from a commitment pool in its
+//! state directory, see [`crate::signing::generate_commitments`], and generates a fresh
+//! batch whenever the pool drops below a minimum, so a session never waits for a manual
+//! `commitments generate` run. The public commitments of the batch are published to
+//! the coordinator one by one, as the replies to the sessions that use them.
+
+use crate::error::FrostCliError;
+use crate::identity::{sign_message_file, IdentityFiles};
+use crate::io::output::report;
+use crate::net::http::api_error;
+use crate::net::store::{SessionStore, StoreError};
+use crate::net::tls::{self, ClientTls, Peer};
+use crate::session::{partial_sign_with_session, Session, SessionStatus};
+use crate::signing::{
+    commit, commit_from_pool, generate_commitments, load_commitment_pool, CommitOptions,
+    PartialSignature, PublicCommitments,
+};
+use crate::{load_participant_share, read_json, FileOptions};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path as UrlPath, State};
+use axum::response::{IntoResponse, Response};
+use axum::Extension;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::Connector;
+
+type WsError = Box<dyn std::error::Error + Send + Sync>;
+
+/// How a participant daemon keeps its commitment pool filled.
+#[derive(Clone, Copy)]
+pub struct PoolRefill {
+    /// Number of unused nonce pairs below which a new batch is generated.
+    pub min_commitments: u32,
+    /// Number of nonce pairs generated per batch.
+    pub batch_size: u32,
+}
+
+/// A message pushed by the coordinator to a participant.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Push {
+    /// The session is waiting for this participant's commitments.
+    Commit { session: Session },
+    /// The session is waiting for this participant's partial signature.
+    Sign { session: Session },
+    /// The coordinator did not accept the participant's last reply.
+    Rejected { reason: String },
+}
+
+/// A participant's answer to a [`Push`].
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "body", rename_all = "snake_case")]
+pub enum Reply {
+    Commitments(PublicCommitments),
+    Partial(PartialSignature),
+}
+
+/// Upgrades `GET /participants/{index}/ws` to a WebSocket for participant `index`.
+pub(crate) async fn participant_socket(
+    State(store): State<Arc<SessionStore>>,
+    UrlPath(index): UrlPath<u32>,
+    peer: Option<Extension<Peer>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if let Err(err) = tls::authorize(peer.as_deref(), index) {
+        return api_error(err).into_response();
+    }
+    upgrade.on_upgrade(move |socket| async move {
+        if let Err(err) = handle_participant(socket, store, index).await {
+            warn!("Participant {} disconnected: {}", index, err);
+        }
+    })
+}
+
+/// Pushes pending work to one participant and applies its replies until it disconnects.
+async fn handle_participant(
+    mut socket: WebSocket,
+    store: Arc<SessionStore>,
+    index: u32,
+) -> Result<(), WsError> {
+    // Step 1: Subscribe first, so no update between catching up and listening is lost
+    let mut updates = store.subscribe();
+    info!("Participant {} connected", index);
+    for session in store.open_sessions()? {
+        push_session(&mut socket, &session, index).await?;
+    }
+
+    // Step 2: Forward session updates and apply the participant's replies
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(session) => push_session(&mut socket, &session, index).await?,
+                Err(RecvError::Lagged(_)) => {
+                    for session in store.open_sessions()? {
+                        push_session(&mut socket, &session, index).await?;
+                    }
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Err(err) = apply_reply(&store, &text, index) {
+                        let push = Push::Rejected { reason: err.to_string() };
+                        socket.send(Message::Text(serde_json::to_string(&push)?)).await?;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    info!("Participant {} disconnected", index);
+                    return Ok(());
+                }
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err.into()),
+            },
+        }
+    }
+}
+
+/// Sends `session` to participant `index` if it is waiting for that participant.
+async fn push_session(
+    socket: &mut WebSocket,
+    session: &Session,
+    index: u32,
+) -> Result<(), WsError> {
+    if !session.roster.contains(&index) {
+        return Ok(());
+    }
+    let push = match session.status {
+        SessionStatus::CollectingCommitments
+            if !session.commitments.iter().any(|c| c.index == index) =>
+        {
+            Push::Commit {
+                session: session.clone(),
+            }
+        }
+        SessionStatus::CollectingPartials
+            if !session.partial_signatures.iter().any(|p| p.index == index) =>
+        {
+            Push::Sign {
+                session: session.clone(),
+            }
+        }
+        _ => return Ok(()),
+    };
+    socket
+        .send(Message::Text(serde_json::to_string(&push)?))
+        .await?;
+    Ok(())
+}
+
+fn apply_reply(store: &SessionStore, text: &str, index: u32) -> Result<(), StoreError> {
+    let reply: Reply =
+        serde_json::from_str(text).map_err(|err| StoreError::Invalid(err.to_string()))?;
+    match reply {
+        Reply::Commitments(commitments) if commitments.index == index => {
+            store.add_commitments(commitments)?;
+        }
+        Reply::Partial(partial) if partial.index == index => {
+            store.add_partial_signature(partial)?;
+        }
+        _ => {
+            return Err(StoreError::Invalid(format!(
+                "Reply is not from participant {}",
+                index
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Connects a signer to the coordinator at `url` and answers its requests until the
+/// connection closes.
+///
+/// Every session the participant is on the roster of is committed to and signed
+/// without further confirmation, so only connect to a coordinator you trust to pick
+/// the messages. The secret nonces and partial signatures are kept in `state_dir`,
+/// one file per session and participant, so a session is never committed to or
+/// signed twice. The daemon does not run the echo check of [`crate::echo`].
+///
+/// # Arguments
+/// - `url`: WebSocket URL of the HTTP coordinator, for example `ws://coordinator:8080`,
+///   or `wss://coordinator:8443` with `tls`.
+/// - `share_file`: Path to the participant's share file.
+/// - `context`: Context string the participant signs in, sessions in another context
+///   are refused, see [`crate::SIGNING_CONTEXT`].
+/// - `state_dir`: Directory to keep the per-session secret commitments in.
+/// - `tls`: Client certificate to connect with.
+/// - `identity`: Identity to sign the replies with and to check the other signers'
+///   commitments against.
+/// - `options`: How the per-session files are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded or the connection fails.
+pub fn run_participant(
+    url: &str,
+    share_file: &str,
+    context: &str,
+    state_dir: &str,
+    tls: Option<&ClientTls>,
+    identity: Option<&IdentityFiles>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    run_participant_with_pool(
+        url, share_file, context, state_dir, None, tls, identity, options,
+    )
+}
+
+/// Connects a signer to the coordinator like [`run_participant`], committing from a
+/// commitment pool that is refilled with `refill`.
+///
+/// The pool is kept in `state_dir` as `commitment_pool_<index>.json`. It is filled
+/// before connecting, and after every session that takes from it a new batch is
+/// generated if fewer than `min_commitments` nonce pairs are left.
+///
+/// # Errors
+/// Returns an error if the refill settings are zero, the share cannot be loaded, the
+/// pool cannot be filled or the connection fails.
+#[allow(clippy::too_many_arguments)]
+pub fn run_participant_with_pool(
+    url: &str,
+    share_file: &str,
+    context: &str,
+    state_dir: &str,
+    refill: Option<&PoolRefill>,
+    tls: Option<&ClientTls>,
+    identity: Option<&IdentityFiles>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Fill the commitment pool, then connect as this share's participant
+    if let Some(refill) = refill.filter(|r| r.min_commitments == 0 || r.batch_size == 0) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Minimum commitments ({}) and batch size ({}) must be at least 1",
+            refill.min_commitments, refill.batch_size
+        ))
+        .into());
+    }
+    let share = load_participant_share(share_file, options)?;
+    fs::create_dir_all(state_dir)?;
+    let pool = refill.map(|refill| (pool_file(state_dir, share.index), refill));
+    if let Some((pool_file, refill)) = &pool {
+        refill_pool(share_file, pool_file, refill, options)?;
+    }
+    let endpoint = format!(
+        "{}/participants/{}/ws",
+        url.trim_end_matches('/'),
+        share.index
+    );
+    let request = endpoint.as_str().into_client_request()?;
+    let (mut socket, _) = match tls {
+        Some(tls) => {
+            let host = request.uri().host().ok_or("Coordinator URL has no host")?;
+            let port = request.uri().port_u16().unwrap_or(443);
+            let stream = TcpStream::connect((host, port))?;
+            let connector = Connector::Rustls(tls.rustls_config()?);
+            tungstenite::client_tls_with_config(request, stream, None, Some(connector))
+                .map_err(|err| format!("Failed to connect to {}: {}", url, err))?
+        }
+        None => tungstenite::connect(request)?,
+    };
+    report(
+        "connected",
+        &format!(
+            "Participant {} connected to {}, waiting for signing requests...",
+            share.index, url
+        ),
+        json!({ "index": share.index, "url": url }),
+    );
+
+    // Step 2: Answer every push until the coordinator closes the connection
+    loop {
+        let text = match socket.read()? {
+            tungstenite::Message::Text(text) => text,
+            tungstenite::Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        let reply = match serde_json::from_str::<Push>(&text)? {
+            Push::Commit { session } => answer_commit(
+                &session,
+                share.index,
+                share_file,
+                state_dir,
+                pool.as_ref(),
+                identity,
+                options,
+            ),
+            Push::Sign { session } => answer_sign(
+                &session,
+                share.index,
+                share_file,
+                context,
+                state_dir,
+                identity,
+                options,
+            ),
+            Push::Rejected { reason } => {
+                warn!("Coordinator rejected a reply: {}", reason);
+                continue;
+            }
+        };
+        match reply {
+            Ok(Some(reply)) => {
+                socket.send(tungstenite::Message::Text(serde_json::to_string(&reply)?))?;
+            }
+            Ok(None) => {}
+            Err(err) => warn!("Failed to answer the coordinator: {}", err),
+        }
+    }
+}
+
+/// Runs signing round one for a pushed session, unless it already did, and refills the
+/// commitment pool if it took from one.
+fn answer_commit(
+    session: &Session,
+    index: u32,
+    share_file: &str,
+    state_dir: &str,
+    pool: Option<&(String, &PoolRefill)>,
+    identity: Option<&IdentityFiles>,
+    options: &FileOptions,
+) -> Result<Option<Reply>, Box<dyn std::error::Error>> {
+    let (public_file, secret_file) = commitment_files(state_dir, &session.session_id, index);
+    if Path::new(&secret_file).exists() {
+        return Ok(None);
+    }
+    report(
+        "signing_requested",
+        &format!(
+            "Session {} asks to sign: {}",
+            session.session_id, session.message
+        ),
+        json!({ "session_id": session.session_id, "message": session.message }),
+    );
+    match pool {
+        Some((pool_file, refill)) => {
+            commit_from_pool(
+                &session.session_id,
+                share_file,
+                pool_file,
+                &public_file,
+                &secret_file,
+                options,
+            )?;
+            refill_pool(share_file, pool_file, refill, options)?;
+        }
+        None => commit(
+            &session.session_id,
+            share_file,
+            &public_file,
+            &secret_file,
+            CommitOptions {
+                files: options.clone(),
+                ..Default::default()
+            },
+        )?,
+    }
+    if let Some(identity) = identity {
+        sign_message_file(&identity.identity_file, &public_file)?;
+    }
+    Ok(Some(Reply::Commitments(read_json(&public_file)?)))
+}
+
+/// Runs signing round two for a pushed session, unless it already did.
+fn answer_sign(
+    session: &Session,
+    index: u32,
+    share_file: &str,
+    context: &str,
+    state_dir: &str,
+    identity: Option<&IdentityFiles>,
+    options: &FileOptions,
+) -> Result<Option<Reply>, Box<dyn std::error::Error>> {
+    let (_, secret_file) = commitment_files(state_dir, &session.session_id, index);
+    let partial_file = Path::new(state_dir)
+        .join(format!(
+            "partial_signature_{}_{}.json",
+            session.session_id, index
+        ))
+        .to_string_lossy()
+        .into_owned();
+    if Path::new(&partial_file).exists() {
+        return Ok(None);
+    }
+    partial_sign_with_session(
+        &session.message,
+        context,
+        share_file,
+        &secret_file,
+        session,
+        &partial_file,
+        identity,
+        &[],
+        options,
+    )?;
+    Ok(Some(Reply::Partial(read_json(&partial_file)?)))
+}
+
+/// Generates a batch of nonce pairs if the pool has fewer than the minimum unused.
+fn refill_pool(
+    share_file: &str,
+    pool_file: &str,
+    refill: &PoolRefill,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let unused = if Path::new(pool_file).exists() {
+        load_commitment_pool(pool_file)?.nonces.len()
+    } else {
+        0
+    };
+    if unused >= refill.min_commitments as usize {
+        return Ok(());
+    }
+    info!(
+        "Commitment pool has {} unused nonce pairs, generating {} more",
+        unused, refill.batch_size
+    );
+    generate_commitments(share_file, refill.batch_size, pool_file, options)
+}
+
+fn pool_file(state_dir: &str, index: u32) -> String {
+    Path::new(state_dir)
+        .join(format!("commitment_pool_{}.json", index))
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn commitment_files(state_dir: &str, session_id: &str, index: u32) -> (String, String) {
+    let dir = Path::new(state_dir);
+    (
+        dir.join(format!("public_commitments_{}_{}.json", session_id, index))
+            .to_string_lossy()
+            .into_owned(),
+        dir.join(format!("secret_commitments_{}_{}.json", session_id, index))
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
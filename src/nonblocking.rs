@@ -0,0 +1,541 @@
+//! `async` variants of the key generation, signing and verification steps, for tokio.
+//!
+//! A coordinator or daemon driving many ceremonies at once should not park a thread per
+//! ceremony while shares are read, nonces are drawn or a coordinator answers. Every
+//! function here runs the synchronous step of the same name on tokio's blocking pool,
+//! see [`tokio::task::spawn_blocking`], so the calling task only awaits it and any
+//! number of ceremonies can be in flight on a few worker threads:
+//!
+//! ```no_run
+//! # async fn ceremonies() -> Result<(), frost_cli::nonblocking::Error> {
+//! use frost_cli::{nonblocking, SIGNING_CONTEXT};
+//!
+//! let (first, second) = tokio::join!(
+//!     nonblocking::sign_message("release v1.2.0", SIGNING_CONTEXT, vec![0, 1], 3, "keys.json", "v1.2.0.sig"),
+//!     nonblocking::sign_message("release v1.3.0", SIGNING_CONTEXT, vec![1, 2], 3, "keys.json", "v1.3.0.sig"),
+//! );
+//! first?;
+//! second?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The functions take owned arguments so their futures can be spawned, and return
+//! [`Error`], which is `Send`. Typed errors such as [`FrostCliError`] and
+//! [`InvalidSignature`] can still be matched on after a downcast, see [`crate::error`].
+//! They must be called from within a tokio runtime.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::identity::IdentityFiles;
+use crate::io::files::FileOptions;
+use crate::net::tls::ClientTls;
+use crate::net::{grpc, http};
+use crate::session::Session;
+use crate::{dkg, session, signing, FrostKeys, InvalidSignature};
+use std::sync::Arc;
+
+/// Error of an async step, a `Send` counterpart of the errors of the synchronous steps.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Runs a synchronous step on the blocking pool and waits for it.
+async fn run<T, F>(step: F) -> Result<T, Error>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || step().map_err(sendable)).await?
+}
+
+/// Moves an error of a synchronous step into an [`Error`], keeping the typed errors a
+/// caller can act on and the message of any other.
+fn sendable(err: Box<dyn std::error::Error>) -> Error {
+    let err = match err.downcast::<FrostCliError>() {
+        Ok(err) => return err,
+        Err(err) => err,
+    };
+    let err = match err.downcast::<InvalidSignature>() {
+        Ok(err) => return err,
+        Err(err) => err,
+    };
+    match err.downcast::<std::io::Error>() {
+        Ok(err) => err,
+        Err(err) => err.to_string().into(),
+    }
+}
+
+/// Generates the public key and private key shares and saves them, see
+/// [`crate::generate_keys_with_ciphersuite`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the key
+/// generation fails.
+pub async fn generate_keys(
+    t: u32,
+    n: u32,
+    output_key_file: impl Into<String>,
+    ciphersuite: Ciphersuite,
+) -> Result<(), Error> {
+    let output_key_file = output_key_file.into();
+    run(move || crate::generate_keys_with_ciphersuite(t, n, &output_key_file, ciphersuite)).await
+}
+
+/// Generates the public key and private key shares and returns them, see
+/// [`crate::generate_frost_keys`].
+///
+/// # Errors
+/// Returns an error if the ciphersuite is not included in this build or the key
+/// generation fails.
+pub async fn generate_frost_keys(
+    t: u32,
+    n: u32,
+    ciphersuite: Ciphersuite,
+) -> Result<FrostKeys, Error> {
+    run(move || crate::generate_frost_keys(t, n, ciphersuite, &|_| {})).await
+}
+
+/// Signs a message with the shares of the chosen signers in a key file, see
+/// [`crate::sign_message`].
+///
+/// # Errors
+/// Returns an error if loading keys, generating commitment shares, or signing fails.
+pub async fn sign_message(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: impl Into<String>,
+    signature_file: impl Into<String>,
+) -> Result<(), Error> {
+    let (message, context) = (message.into(), context.into());
+    let (key_file, signature_file) = (key_file.into(), signature_file.into());
+    run(move || crate::sign_message(&message, &context, signers, n, &key_file, &signature_file))
+        .await
+}
+
+/// Signs a message with keys held in memory, see [`crate::sign_with_keys`]. The keys
+/// are shared so concurrent signings need not copy them.
+///
+/// # Errors
+/// Returns an error if fewer signers than the threshold or an invalid position are
+/// given, the ciphersuite is not included in this build, or signing fails.
+pub async fn sign_with_keys(
+    message: Vec<u8>,
+    context: impl Into<String>,
+    frost_keys: Arc<FrostKeys>,
+    signers: Vec<u32>,
+) -> Result<[u8; 64], Error> {
+    let context = context.into();
+    run(move || crate::sign_with_keys(&message, &context, &frost_keys, &signers)).await
+}
+
+/// Validates a threshold signature, see [`crate::validate_signature`].
+///
+/// # Errors
+/// Returns an [`InvalidSignature`] error if the signature is read but invalid, or
+/// another error if it cannot be read.
+pub async fn validate_signature(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    key_file: impl Into<String>,
+    signature_file: impl Into<String>,
+) -> Result<(), Error> {
+    let (message, context) = (message.into(), context.into());
+    let (key_file, signature_file) = (key_file.into(), signature_file.into());
+    run(move || crate::validate_signature(&message, &context, &key_file, &signature_file)).await
+}
+
+/// Verifies a signature under a group key held in memory, see
+/// [`crate::verify_with_group_key`].
+///
+/// # Errors
+/// Returns an [`InvalidSignature`] error if the signature is not valid, or another error
+/// if the ciphersuite is not included in this build.
+pub async fn verify_with_group_key(
+    message: Vec<u8>,
+    context: impl Into<String>,
+    ciphersuite: Ciphersuite,
+    group_key: [u8; 32],
+    signature: [u8; 64],
+) -> Result<(), Error> {
+    let context = context.into();
+    run(move || {
+        crate::verify_with_group_key(&message, &context, ciphersuite, &group_key, &signature)
+    })
+    .await
+}
+
+/// Runs round one of the DKG for one participant, see [`dkg::round_one`].
+///
+/// # Errors
+/// Returns an error if the parameters are invalid or the files cannot be written.
+pub async fn dkg_round_one(
+    index: u32,
+    t: u32,
+    n: u32,
+    state_file: impl Into<String>,
+    output_dir: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (state_file, output_dir) = (state_file.into(), output_dir.into());
+    run(move || dkg::round_one(index, t, n, &state_file, &output_dir, &options)).await
+}
+
+/// Runs round two of the DKG for one participant, see [`dkg::round_two`].
+///
+/// # Errors
+/// Returns an error if a round one message is missing or invalid.
+pub async fn dkg_round_two(
+    state_file: impl Into<String>,
+    round1_dir: impl Into<String>,
+    output_dir: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (state_file, round1_dir, output_dir) =
+        (state_file.into(), round1_dir.into(), output_dir.into());
+    run(move || dkg::round_two(&state_file, &round1_dir, &output_dir, &options)).await
+}
+
+/// Finishes the DKG for one participant and saves their share, see [`dkg::finish`].
+///
+/// # Errors
+/// Returns an error if round two has not been run or a share sent to the participant
+/// is missing or invalid.
+pub async fn dkg_finish(
+    state_file: impl Into<String>,
+    shares_dir: impl Into<String>,
+    output_share_file: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (state_file, shares_dir, output_share_file) = (
+        state_file.into(),
+        shares_dir.into(),
+        output_share_file.into(),
+    );
+    run(move || dkg::finish(&state_file, &shares_dir, &output_share_file, &options)).await
+}
+
+/// Runs signing round one for a participant, see [`signing::commit`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded or the files cannot be written.
+pub async fn commit(
+    session_id: impl Into<String>,
+    share_file: impl Into<String>,
+    public_commitments_file: impl Into<String>,
+    secret_commitments_file: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (session_id, share_file) = (session_id.into(), share_file.into());
+    let (public_commitments_file, secret_commitments_file) = (
+        public_commitments_file.into(),
+        secret_commitments_file.into(),
+    );
+    run(move || {
+        signing::commit(
+            &session_id,
+            &share_file,
+            &public_commitments_file,
+            &secret_commitments_file,
+            signing::CommitOptions {
+                files: options,
+                ..Default::default()
+            },
+        )
+    })
+    .await
+}
+
+/// Runs signing round two for a participant, see [`signing::partial_sign`].
+///
+/// # Errors
+/// Returns an error if this participant is not part of the roster, the roster is smaller
+/// than the threshold, or the secret commitments do not match the published ones.
+pub async fn partial_sign(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    share_file: impl Into<String>,
+    secret_commitments_file: impl Into<String>,
+    commitment_files: Vec<String>,
+    partial_signature_file: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (message, context, share_file) = (message.into(), context.into(), share_file.into());
+    let (secret_commitments_file, partial_signature_file) = (
+        secret_commitments_file.into(),
+        partial_signature_file.into(),
+    );
+    run(move || {
+        signing::partial_sign(
+            &message,
+            &context,
+            &share_file,
+            &secret_commitments_file,
+            &commitment_files,
+            &partial_signature_file,
+            &options,
+        )
+    })
+    .await
+}
+
+/// Aggregates the partial signatures into a threshold signature, see
+/// [`signing::aggregate`].
+///
+/// # Errors
+/// Returns an error if a signer's partial signature is missing or the aggregated
+/// signature does not verify under the group key.
+pub async fn aggregate(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    key_file: impl Into<String>,
+    commitment_files: Vec<String>,
+    partial_signatures_dir: impl Into<String>,
+    signature_file: impl Into<String>,
+) -> Result<(), Error> {
+    let (message, context, key_file) = (message.into(), context.into(), key_file.into());
+    let (partial_signatures_dir, signature_file) =
+        (partial_signatures_dir.into(), signature_file.into());
+    run(move || {
+        signing::aggregate(
+            &message,
+            &context,
+            &key_file,
+            &commitment_files,
+            &partial_signatures_dir,
+            &signature_file,
+        )
+    })
+    .await
+}
+
+/// Creates a new signing session, see [`session::new_session`].
+///
+/// # Errors
+/// Returns an error if fewer signers than the threshold are given.
+pub async fn new_session(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    key_file: impl Into<String>,
+    signers: Vec<u32>,
+    session_file: impl Into<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (message, context) = (message.into(), context.into());
+    let (key_file, session_file) = (key_file.into(), session_file.into());
+    run(move || {
+        session::new_session(
+            &message,
+            &context,
+            &key_file,
+            signers,
+            &session_file,
+            &options,
+        )
+    })
+    .await
+}
+
+/// Adds a signer's public commitments to a session manifest, see
+/// [`session::add_commitments`].
+///
+/// # Errors
+/// Returns an error if the commitments are rejected.
+pub async fn add_commitments(
+    session_file: impl Into<String>,
+    commitments_file: impl Into<String>,
+    transcript_file: Option<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (session_file, commitments_file) = (session_file.into(), commitments_file.into());
+    run(move || {
+        session::add_commitments(
+            &session_file,
+            &commitments_file,
+            transcript_file.as_deref(),
+            &options,
+        )
+    })
+    .await
+}
+
+/// Adds a signer's partial signature to a session manifest, see
+/// [`session::add_partial_signature`].
+///
+/// # Errors
+/// Returns an error if the partial signature is rejected.
+pub async fn add_partial_signature(
+    session_file: impl Into<String>,
+    partial_signature_file: impl Into<String>,
+    signature_file: impl Into<String>,
+    transcript_file: Option<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (session_file, partial_signature_file, signature_file) = (
+        session_file.into(),
+        partial_signature_file.into(),
+        signature_file.into(),
+    );
+    run(move || {
+        session::add_partial_signature(
+            &session_file,
+            &partial_signature_file,
+            &signature_file,
+            transcript_file.as_deref(),
+            &options,
+        )
+    })
+    .await
+}
+
+/// Runs signing round two for a participant using the roster recorded in a session, see
+/// [`session::partial_sign`].
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures or `message`
+/// is not the message the session was created for.
+pub async fn session_partial_sign(
+    message: impl Into<String>,
+    context: impl Into<String>,
+    share_file: impl Into<String>,
+    secret_commitments_file: impl Into<String>,
+    session_file: impl Into<String>,
+    partial_signature_file: impl Into<String>,
+    identity: Option<IdentityFiles>,
+    echo_files: Vec<String>,
+    options: FileOptions,
+) -> Result<(), Error> {
+    let (message, context) = (message.into(), context.into());
+    let (share_file, session_file) = (share_file.into(), session_file.into());
+    let (secret_commitments_file, partial_signature_file) = (
+        secret_commitments_file.into(),
+        partial_signature_file.into(),
+    );
+    run(move || {
+        session::partial_sign(
+            &message,
+            &context,
+            &share_file,
+            &secret_commitments_file,
+            &session_file,
+            &partial_signature_file,
+            identity.as_ref(),
+            &echo_files,
+            &options,
+        )
+    })
+    .await
+}
+
+/// Asks the coordinator at `url` to start a new signing session, over gRPC if `grpc`,
+/// see [`http::create_remote_session`].
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the session.
+pub async fn create_remote_session(
+    url: impl Into<String>,
+    message: impl Into<String>,
+    context: impl Into<String>,
+    signers: Vec<u32>,
+    tls: Option<ClientTls>,
+    grpc: bool,
+) -> Result<Session, Error> {
+    let (url, message, context) = (url.into(), message.into(), context.into());
+    let create = if grpc {
+        grpc::create_remote_session
+    } else {
+        http::create_remote_session
+    };
+    run(move || create(&url, &message, &context, signers, tls.as_ref())).await
+}
+
+/// Sends the public commitments in `commitments_file` to the coordinator at `url`, over
+/// gRPC if `grpc`, see [`http::submit_commitments_file`].
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or rejects the commitments.
+pub async fn submit_commitments_file(
+    url: impl Into<String>,
+    commitments_file: impl Into<String>,
+    tls: Option<ClientTls>,
+    grpc: bool,
+) -> Result<(), Error> {
+    let (url, commitments_file) = (url.into(), commitments_file.into());
+    let submit = if grpc {
+        grpc::submit_commitments_file
+    } else {
+        http::submit_commitments_file
+    };
+    run(move || submit(&url, &commitments_file, tls.as_ref())).await
+}
+
+/// Runs signing round two against the session held by the coordinator at `url` and
+/// sends the partial signature back, over gRPC if `grpc`, see
+/// [`http::partial_sign_remote`].
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures, signing fails,
+/// or the coordinator rejects the partial signature.
+#[allow(clippy::too_many_arguments)]
+pub async fn partial_sign_remote(
+    url: impl Into<String>,
+    message: impl Into<String>,
+    context: impl Into<String>,
+    share_file: impl Into<String>,
+    secret_commitments_file: impl Into<String>,
+    partial_signature_file: impl Into<String>,
+    tls: Option<ClientTls>,
+    identity: Option<IdentityFiles>,
+    echo_files: Vec<String>,
+    options: FileOptions,
+    grpc: bool,
+) -> Result<(), Error> {
+    let (url, message, context) = (url.into(), message.into(), context.into());
+    let share_file = share_file.into();
+    let (secret_commitments_file, partial_signature_file) = (
+        secret_commitments_file.into(),
+        partial_signature_file.into(),
+    );
+    let partial_sign = if grpc {
+        grpc::partial_sign_remote
+    } else {
+        http::partial_sign_remote
+    };
+    run(move || {
+        partial_sign(
+            &url,
+            &message,
+            &context,
+            &share_file,
+            &secret_commitments_file,
+            &partial_signature_file,
+            tls.as_ref(),
+            identity.as_ref(),
+            &echo_files,
+            &options,
+        )
+    })
+    .await
+}
+
+/// Downloads the threshold signature of a completed session from the coordinator at
+/// `url` and saves it, over gRPC if `grpc`, see [`http::download_signature`].
+///
+/// # Errors
+/// Returns an error if the coordinator cannot be reached or the session is not complete.
+pub async fn download_signature(
+    url: impl Into<String>,
+    session_id: impl Into<String>,
+    signature_file: impl Into<String>,
+    tls: Option<ClientTls>,
+    options: FileOptions,
+    grpc: bool,
+) -> Result<(), Error> {
+    let (url, session_id, signature_file) = (url.into(), session_id.into(), signature_file.into());
+    let download = if grpc {
+        grpc::download_signature
+    } else {
+        http::download_signature
+    };
+    run(move || download(&url, &session_id, &signature_file, tls.as_ref(), &options)).await
+}
@@ -0,0 +1,174 @@
+//! Tracking of the signing nonces a participant has used.
+//!
+//! A nonce pair that signs two different messages leaks the share it signed with. Every
+//! nonce pair [`crate::signing`] writes to a secret commitments file or commitment pool
+//! is therefore recorded as unused in a `nonce_state.json` next to that file, and
+//! partial signing marks it as used before the partial signature is written. Signing
+//! refuses a nonce pair the state already marked as used, for example from a restored
+//! copy of a secret commitments file, and one the state does not know, which means the
+//! state file was deleted or rolled back to a copy from before the pair was created.
+//!
+//! Nonce pairs are named by the SHA-256 hash of their public commitments, so the state
+//! holds nothing secret. It is only updated under an exclusive lock of
+//! `nonce_state.json.lock`, so signers sharing a directory never lose each other's
+//! updates. Signers that keep their secret commitments themselves, like the WebAssembly
+//! and mobile bindings, have to track their nonces themselves.
+
+use crate::error::FrostCliError;
+use crate::{read_json, write_json, FileOptions};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// File name of the nonce state next to the files holding the nonces.
+const STATE_FILE: &str = "nonce_state.json";
+
+/// The nonce pairs recorded in a directory, by the hash of their public commitments.
+#[derive(Serialize, Deserialize, Default)]
+pub struct NonceState {
+    /// Nonce pairs written to disk that have not signed yet.
+    pub unused: Vec<String>,
+    /// Nonce pairs that signed, and may never sign again.
+    pub used: Vec<String>,
+}
+
+/// Returns the path of the nonce state next to a file holding nonces.
+pub fn state_file(nonce_file: &str) -> PathBuf {
+    Path::new(nonce_file).with_file_name(STATE_FILE)
+}
+
+/// Loads the nonce state next to a file holding nonces.
+///
+/// # Errors
+/// Returns an error if the state file is missing or cannot be read.
+pub fn load_state(nonce_file: &str) -> Result<NonceState, Box<dyn std::error::Error>> {
+    read_json(state_file(nonce_file))
+}
+
+/// Returns the name under which a nonce pair is recorded, the hex-encoded SHA-256 hash
+/// of its hiding and binding commitments.
+pub fn commitment_id(commitment: &([u8; 32], [u8; 32])) -> String {
+    let mut h = Sha256::new();
+    h.update(commitment.0);
+    h.update(commitment.1);
+    hex::encode(h.finalize())
+}
+
+/// Records new nonce pairs as unused, creating the state if there is none yet.
+///
+/// # Errors
+/// Returns [`FrostCliError::NonceReuse`] if a pair was already used, or an error if the
+/// state cannot be locked, read or written.
+pub(crate) fn record(nonce_file: &str, ids: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    update_state(nonce_file, true, |state, state_file| {
+        for id in ids {
+            if state.used.contains(id) {
+                return Err(reuse(state_file, "Nonces were already used to sign"));
+            }
+            if !state.unused.contains(id) {
+                state.unused.push(id.clone());
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Removes an unused nonce pair from the state when it moves out of the file holding it,
+/// as from a commitment pool to a secret commitments file.
+///
+/// # Errors
+/// Returns [`FrostCliError::NonceReuse`] if the state is missing, or does not list the
+/// pair as unused.
+pub(crate) fn take(nonce_file: &str, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    update_state(nonce_file, false, |state, state_file| {
+        let position = unused_position(state, state_file, id)?;
+        state.unused.remove(position);
+        Ok(())
+    })
+}
+
+/// Marks an unused nonce pair as used, before anything it signed is released.
+///
+/// # Errors
+/// Returns [`FrostCliError::NonceReuse`] if the state is missing, or does not list the
+/// pair as unused.
+pub(crate) fn consume(nonce_file: &str, id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    update_state(nonce_file, false, |state, state_file| {
+        let position = unused_position(state, state_file, id)?;
+        let id = state.unused.remove(position);
+        state.used.push(id);
+        Ok(())
+    })
+}
+
+/// Returns the position of an unused nonce pair in the state.
+fn unused_position(
+    state: &NonceState,
+    state_file: &Path,
+    id: &str,
+) -> Result<usize, FrostCliError> {
+    if state.used.iter().any(|used| used == id) {
+        return Err(reuse(state_file, "Nonces were already used to sign"));
+    }
+    state
+        .unused
+        .iter()
+        .position(|unused| unused == id)
+        .ok_or_else(|| {
+            reuse(
+                state_file,
+                "Nonces are not recorded in the nonce state, which may have been rolled back",
+            )
+        })
+}
+
+/// Loads the nonce state next to `nonce_file` under an exclusive lock, applies `update`
+/// and saves it.
+fn update_state(
+    nonce_file: &str,
+    create: bool,
+    update: impl FnOnce(&mut NonceState, &Path) -> Result<(), FrostCliError>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Lock the state against concurrent updates until the lock file is dropped
+    let state_file = state_file(nonce_file);
+    let lock_file = state_file.with_extension("json.lock");
+    let guard = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_file)
+        .map_err(|err| FrostCliError::io(&lock_file, err))?;
+    guard
+        .lock()
+        .map_err(|err| FrostCliError::io(&lock_file, err))?;
+
+    // Step 2: Load the state, which may only be missing when new nonces are recorded
+    let mut state = if state_file.exists() {
+        read_json(&state_file)?
+    } else if create {
+        NonceState::default()
+    } else {
+        return Err(reuse(
+            &state_file,
+            "Nonce state is missing, it may have been deleted or rolled back",
+        )
+        .into());
+    };
+    update(&mut state, &state_file)?;
+
+    // Step 3: Save the state without ever leaving a half-written file behind, as JSON
+    // whatever format the commitments are written in
+    let temp_file = state_file.with_extension("json.tmp");
+    write_json(&temp_file, &state, &FileOptions::default())?;
+    fs::rename(&temp_file, &state_file).map_err(|err| FrostCliError::io(&state_file, err))?;
+    Ok(())
+}
+
+/// Returns a [`FrostCliError::NonceReuse`] error of a nonce state.
+fn reuse(state_file: &Path, reason: &str) -> FrostCliError {
+    FrostCliError::NonceReuse {
+        state: state_file.display().to_string(),
+        reason: format!("{}: {}", state_file.display(), reason),
+    }
+}
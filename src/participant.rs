@@ -0,0 +1,79 @@
+//! A participant's operations on the JSON of its files, for the WebAssembly bindings of
+//! the `wasm` feature and the mobile bindings of the `uniffi` feature.
+//!
+//! Signers on the web or on a phone keep their share and secret commitments themselves,
+//! in browser storage or the platform keystore, and pass them in with every call. They
+//! are exchanged as the JSON of the CLI's files, so what such a signer produces can be
+//! uploaded to a coordinator as is.
+
+use crate::dealer::{self, ShareBundle};
+use crate::identity::Identity;
+use crate::memlock::Locked;
+use crate::signing::{self, CommitOptions, PublicCommitments, SecretCommitments};
+use crate::{add_secret_checksum, parse_participant_share, FileOptions, ParticipantShare};
+use serde_json::Value;
+
+/// Decrypts a share bundle with the participant's identity and returns the JSON of the
+/// share file.
+pub(crate) fn decrypt_share(
+    identity: &str,
+    bundle: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let identity: Identity = serde_json::from_str(identity)?;
+    let bundle: ShareBundle = serde_json::from_str(bundle)?;
+    let share = dealer::open_share_bundle(&identity, &bundle)?;
+    share_json(&share)
+}
+
+/// Runs signing round one for the JSON of a share file, and returns the public and the
+/// secret commitments as JSON.
+pub(crate) fn commit(
+    session_id: &str,
+    share: &str,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let share = parse_share(share)?;
+    let (public_commitments, secret_commitments) =
+        signing::commit_with_share(session_id, &share, CommitOptions::default())?;
+    Ok((
+        serde_json::to_value(&public_commitments)?,
+        serde_json::to_value(&*secret_commitments)?,
+    ))
+}
+
+/// Runs signing round two for the JSON of a share file, its secret commitments and the
+/// roster, signing `message` in `context`, and returns the partial signature and the
+/// remaining secret commitments as JSON.
+pub(crate) fn partial_sign(
+    message: &str,
+    context: &str,
+    share: &str,
+    secret_commitments: &str,
+    roster: &str,
+) -> Result<(Value, Value), Box<dyn std::error::Error>> {
+    let share = parse_share(share)?;
+    let secret_commitments: Locked<SecretCommitments> =
+        Locked::new(serde_json::from_str(secret_commitments)?)?;
+    let roster: Vec<PublicCommitments> = serde_json::from_str(roster)?;
+    let (partial_signature, remaining) =
+        signing::partial_sign_with_share(message, context, &share, &secret_commitments, &roster)?;
+    Ok((
+        serde_json::to_value(&partial_signature)?,
+        serde_json::to_value(&remaining)?,
+    ))
+}
+
+/// Parses the JSON of a share file, checking its checksum.
+fn parse_share(share: &str) -> Result<Locked<ParticipantShare>, Box<dyn std::error::Error>> {
+    Locked::new(parse_participant_share(
+        share.as_bytes(),
+        "share",
+        &FileOptions::default(),
+    )?)
+}
+
+/// Returns the JSON of a share file, with its checksum.
+fn share_json(share: &ParticipantShare) -> Result<String, Box<dyn std::error::Error>> {
+    let mut value = serde_json::to_value(share)?;
+    add_secret_checksum(&mut value, None)?;
+    Ok(value.to_string())
+}
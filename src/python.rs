@@ -0,0 +1,400 @@
+//! The `frost_dalek_cli` Python module, with the `python` feature.
+//!
+//! Automation written in Python runs ceremonies through the library functions instead
+//! of running the CLI and parsing what it prints. Each function of the module calls the
+//! library function of the same name with the same arguments, and releases the GIL
+//! while it runs, so other Python threads carry on during a long key generation. The
+//! functions that sign or verify take the signing context as their last argument,
+//! [`crate::SIGNING_CONTEXT`] if it is left out.
+//!
+//! Failures are raised as `FrostCliError`, or one of its subclasses for the typed
+//! errors of [`crate::error`]. Signatures that do not verify raise
+//! `InvalidSignatureError`, and files that cannot be read or written raise `OSError`.
+
+use crate::backend::Ciphersuite;
+use crate::error;
+use crate::io::files::FileOptions;
+use crate::io::signature::SignatureFormat;
+use crate::{dkg, signing, InvalidSignature, SIGNING_CONTEXT};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyOSError};
+use pyo3::prelude::*;
+
+create_exception!(
+    frost_dalek_cli,
+    FrostCliError,
+    PyException,
+    "A failure of frost-cli."
+);
+create_exception!(
+    frost_dalek_cli,
+    InvalidParametersError,
+    FrostCliError,
+    "The threshold, participants or signers do not fit together."
+);
+create_exception!(
+    frost_dalek_cli,
+    InvalidShareError,
+    FrostCliError,
+    "A private share could not be decoded or is not a valid share."
+);
+create_exception!(
+    frost_dalek_cli,
+    DkgError,
+    FrostCliError,
+    "The key generation failed because of a participant's round message."
+);
+create_exception!(
+    frost_dalek_cli,
+    AggregationError,
+    FrostCliError,
+    "The partial signatures could not be aggregated into a valid signature."
+);
+create_exception!(
+    frost_dalek_cli,
+    InvalidSignatureError,
+    FrostCliError,
+    "The signature does not verify."
+);
+
+/// Generates keys as a trusted dealer and saves them to a key file, see
+/// [`crate::generate_keys`].
+#[pyfunction]
+#[pyo3(signature = (t, n, output_key_file, ciphersuite = "ristretto255"))]
+fn generate_keys(
+    py: Python<'_>,
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+    ciphersuite: &str,
+) -> PyResult<()> {
+    run(py, || {
+        crate::generate_keys_with_ciphersuite(t, n, output_key_file, ciphersuite.parse()?)
+    })
+}
+
+/// Signs a message with the shares at the given positions of a key file, see
+/// [`crate::sign_message`].
+#[pyfunction]
+#[pyo3(signature = (message, signers, n, key_file, signature_file, context = SIGNING_CONTEXT))]
+fn sign_message(
+    py: Python<'_>,
+    message: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        crate::sign_message(message, context, signers, n, key_file, signature_file)
+    })
+}
+
+/// Signs a message with participant shares, see [`crate::sign_message_with_shares`].
+#[pyfunction]
+#[pyo3(signature = (message, share_files, signature_file, context = SIGNING_CONTEXT))]
+fn sign_message_with_shares(
+    py: Python<'_>,
+    message: &str,
+    share_files: Vec<String>,
+    signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        crate::sign_message_with_shares(message, context, &share_files, signature_file, None)
+    })
+}
+
+/// Signs a file by its hash, see [`crate::sign_file`].
+#[pyfunction]
+#[pyo3(signature = (file, signers, n, key_file, signature_file, context = SIGNING_CONTEXT))]
+fn sign_file(
+    py: Python<'_>,
+    file: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        crate::sign_file(
+            file,
+            context,
+            signers,
+            n,
+            key_file,
+            signature_file,
+            SignatureFormat::Json,
+            &FileOptions::default(),
+            None,
+        )
+    })
+}
+
+/// Verifies a signature file against a key file, see [`crate::validate_signature`].
+#[pyfunction]
+#[pyo3(signature = (message, key_file, signature_file, context = SIGNING_CONTEXT))]
+fn validate_signature(
+    py: Python<'_>,
+    message: &str,
+    key_file: &str,
+    signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        crate::validate_signature(message, context, key_file, signature_file)
+    })
+}
+
+/// Verifies a 64-byte signature against a 32-byte group key, see
+/// [`crate::verify_with_group_key`].
+#[pyfunction]
+#[pyo3(signature = (message, ciphersuite, group_key, signature, context = SIGNING_CONTEXT))]
+fn verify_with_group_key(
+    py: Python<'_>,
+    message: &[u8],
+    ciphersuite: &str,
+    group_key: &[u8],
+    signature: &[u8],
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        let ciphersuite: Ciphersuite = ciphersuite.parse()?;
+        let group_key: [u8; 32] = group_key
+            .try_into()
+            .map_err(|_| "The group key must be 32 bytes")?;
+        let signature: [u8; 64] = signature
+            .try_into()
+            .map_err(|_| "The signature must be 64 bytes")?;
+        crate::verify_with_group_key(message, context, ciphersuite, &group_key, &signature)
+    })
+}
+
+/// Returns the group public key of a key file as hex, or as PEM, see
+/// [`crate::export_group_key`].
+#[pyfunction]
+#[pyo3(signature = (key_file, pem = false))]
+fn export_group_key(py: Python<'_>, key_file: &str, pem: bool) -> PyResult<String> {
+    run(py, || {
+        crate::export_group_key(key_file, pem, &FileOptions::default())
+    })
+}
+
+/// Runs round one of the DKG for a participant, see [`dkg::round_one`].
+#[pyfunction]
+fn dkg_round_one(
+    py: Python<'_>,
+    index: u32,
+    t: u32,
+    n: u32,
+    state_file: &str,
+    output_dir: &str,
+) -> PyResult<()> {
+    run(py, || {
+        dkg::round_one(index, t, n, state_file, output_dir, &FileOptions::default())
+    })
+}
+
+/// Runs round two of the DKG for a participant, see [`dkg::round_two`].
+#[pyfunction]
+fn dkg_round_two(
+    py: Python<'_>,
+    state_file: &str,
+    round1_dir: &str,
+    output_dir: &str,
+) -> PyResult<()> {
+    run(py, || {
+        dkg::round_two(state_file, round1_dir, output_dir, &FileOptions::default())
+    })
+}
+
+/// Derives a participant's share from the shares sent to it, see [`dkg::finish`].
+#[pyfunction]
+fn dkg_finish(
+    py: Python<'_>,
+    state_file: &str,
+    shares_dir: &str,
+    output_share_file: &str,
+) -> PyResult<()> {
+    run(py, || {
+        dkg::finish(
+            state_file,
+            shares_dir,
+            output_share_file,
+            &FileOptions::default(),
+        )
+    })
+}
+
+/// Runs signing round one for a participant, see [`signing::commit`].
+#[pyfunction]
+fn commit(
+    py: Python<'_>,
+    session_id: &str,
+    share_file: &str,
+    public_commitments_file: &str,
+    secret_commitments_file: &str,
+) -> PyResult<()> {
+    run(py, || {
+        signing::commit(
+            session_id,
+            share_file,
+            public_commitments_file,
+            secret_commitments_file,
+            signing::CommitOptions::default(),
+        )
+    })
+}
+
+/// Runs signing round two for a participant, see [`signing::partial_sign`].
+#[pyfunction]
+#[pyo3(signature = (
+    message,
+    share_file,
+    secret_commitments_file,
+    commitment_files,
+    partial_signature_file,
+    context = SIGNING_CONTEXT,
+))]
+fn partial_sign(
+    py: Python<'_>,
+    message: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    commitment_files: Vec<String>,
+    partial_signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        signing::partial_sign(
+            message,
+            context,
+            share_file,
+            secret_commitments_file,
+            &commitment_files,
+            partial_signature_file,
+            &FileOptions::default(),
+        )
+    })
+}
+
+/// Combines the partial signatures of all signers into a threshold signature, see
+/// [`signing::aggregate`].
+#[pyfunction]
+#[pyo3(signature = (
+    message,
+    key_file,
+    commitment_files,
+    partial_signatures_dir,
+    signature_file,
+    context = SIGNING_CONTEXT,
+))]
+fn aggregate(
+    py: Python<'_>,
+    message: &str,
+    key_file: &str,
+    commitment_files: Vec<String>,
+    partial_signatures_dir: &str,
+    signature_file: &str,
+    context: &str,
+) -> PyResult<()> {
+    run(py, || {
+        signing::aggregate(
+            message,
+            context,
+            key_file,
+            &commitment_files,
+            partial_signatures_dir,
+            signature_file,
+        )
+    })
+}
+
+/// The `frost_dalek_cli` module.
+#[pymodule]
+pub fn frost_dalek_cli(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("FrostCliError", py.get_type_bound::<FrostCliError>())?;
+    m.add(
+        "InvalidParametersError",
+        py.get_type_bound::<InvalidParametersError>(),
+    )?;
+    m.add(
+        "InvalidShareError",
+        py.get_type_bound::<InvalidShareError>(),
+    )?;
+    m.add("DkgError", py.get_type_bound::<DkgError>())?;
+    m.add("AggregationError", py.get_type_bound::<AggregationError>())?;
+    m.add(
+        "InvalidSignatureError",
+        py.get_type_bound::<InvalidSignatureError>(),
+    )?;
+    m.add_function(wrap_pyfunction!(generate_keys, m)?)?;
+    m.add_function(wrap_pyfunction!(sign_message, m)?)?;
+    m.add_function(wrap_pyfunction!(sign_message_with_shares, m)?)?;
+    m.add_function(wrap_pyfunction!(sign_file, m)?)?;
+    m.add_function(wrap_pyfunction!(validate_signature, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_with_group_key, m)?)?;
+    m.add_function(wrap_pyfunction!(export_group_key, m)?)?;
+    m.add_function(wrap_pyfunction!(dkg_round_one, m)?)?;
+    m.add_function(wrap_pyfunction!(dkg_round_two, m)?)?;
+    m.add_function(wrap_pyfunction!(dkg_finish, m)?)?;
+    m.add_function(wrap_pyfunction!(commit, m)?)?;
+    m.add_function(wrap_pyfunction!(partial_sign, m)?)?;
+    m.add_function(wrap_pyfunction!(aggregate, m)?)?;
+    Ok(())
+}
+
+/// The Python exception an error of the library is raised as.
+#[derive(Clone, Copy)]
+enum ErrorKind {
+    InvalidParameters,
+    InvalidShare,
+    Dkg,
+    Aggregation,
+    InvalidSignature,
+    Io,
+    Other,
+}
+
+impl ErrorKind {
+    fn of(err: &(dyn std::error::Error + 'static)) -> ErrorKind {
+        if err.is::<InvalidSignature>() {
+            return ErrorKind::InvalidSignature;
+        }
+        if error::is_io_error(err) {
+            return ErrorKind::Io;
+        }
+        match err.downcast_ref::<error::FrostCliError>() {
+            Some(error::FrostCliError::InvalidParameters(_)) => ErrorKind::InvalidParameters,
+            Some(error::FrostCliError::InvalidShare { .. }) => ErrorKind::InvalidShare,
+            Some(error::FrostCliError::Dkg { .. })
+            | Some(error::FrostCliError::Misbehaving { .. }) => ErrorKind::Dkg,
+            Some(error::FrostCliError::Aggregation { .. }) => ErrorKind::Aggregation,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    fn into_py_err(self, message: String) -> PyErr {
+        match self {
+            ErrorKind::InvalidParameters => InvalidParametersError::new_err(message),
+            ErrorKind::InvalidShare => InvalidShareError::new_err(message),
+            ErrorKind::Dkg => DkgError::new_err(message),
+            ErrorKind::Aggregation => AggregationError::new_err(message),
+            ErrorKind::InvalidSignature => InvalidSignatureError::new_err(message),
+            ErrorKind::Io => PyOSError::new_err(message),
+            ErrorKind::Other => FrostCliError::new_err(message),
+        }
+    }
+}
+
+/// Runs a library call with the GIL released and raises its error as an exception.
+fn run<T: Send>(
+    py: Python<'_>,
+    call: impl FnOnce() -> Result<T, Box<dyn std::error::Error>> + Send,
+) -> PyResult<T> {
+    py.allow_threads(|| call().map_err(|err| (ErrorKind::of(&*err), err.to_string())))
+        .map_err(|(kind, message)| kind.into_py_err(message))
+}
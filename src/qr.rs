@@ -0,0 +1,89 @@
+//! QR code transfer of round messages for air-gapped signers.
+//!
+//! Round message files are re-encoded as compact JSON, whatever format they were
+//! written in, and shown as a QR code in the terminal of the machine that produced
+//! them. On the receiving machine, a photo or screenshot of the code is decoded back
+//! into a message file in the output format, see [`crate::io::format`].
+
+use crate::io::output::report;
+use crate::{read_json, write_json, FileOptions};
+use qrcode::render::unicode;
+use qrcode::QrCode;
+use serde_json::json;
+use std::path::Path;
+
+/// Renders the contents of a message file as a terminal QR code.
+///
+/// # Errors
+/// Returns an error if the file cannot be decoded or is too large for a single QR code.
+pub fn render_file(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    // Step 1: Compact the JSON so the payload fits in as small a code as possible
+    let value: serde_json::Value = read_json(path)?;
+    let payload = serde_json::to_vec(&value)?;
+
+    // Step 2: Encode and render the QR code
+    let code = QrCode::new(&payload)
+        .map_err(|err| format!("Failed to encode {} as QR code: {}", path.display(), err))?;
+    Ok(code
+        .render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build())
+}
+
+/// Prints a message file as a QR code, labelled with its file name.
+///
+/// # Errors
+/// Returns an error if the file cannot be rendered, see [`render_file`].
+pub fn print_file(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let code = render_file(path)?;
+    report(
+        "qr_code",
+        &format!("{}:\n{}", path.display(), code),
+        json!({ "file": path, "qr_code": code }),
+    );
+    Ok(())
+}
+
+/// Decodes a QR code from an image and saves the message it contains.
+///
+/// # Arguments
+/// - `image_file`: Path to a PNG or JPEG image of the QR code.
+/// - `output_file`: Path to save the decoded message file.
+/// - `options`: How the decoded message file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if no QR code is found or its contents are not valid JSON.
+pub fn decode_image(
+    image_file: &str,
+    output_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the image as greyscale
+    let image = image::open(image_file)?.to_luma8();
+    let (width, height) = image.dimensions();
+
+    // Step 2: Locate and decode the QR code
+    let mut prepared =
+        rqrr::PreparedImage::prepare_from_greyscale(width as usize, height as usize, |x, y| {
+            image.get_pixel(x as u32, y as u32).0[0]
+        });
+    let grids = prepared.detect_grids();
+    let grid = grids
+        .first()
+        .ok_or_else(|| format!("No QR code found in {}", image_file))?;
+    let (_, content) = grid
+        .decode()
+        .map_err(|err| format!("Failed to decode QR code: {:?}", err))?;
+
+    // Step 3: Check the payload and save it as a regular message file
+    let value: serde_json::Value = serde_json::from_str(&content)?;
+    write_json(output_file, &value, options)?;
+
+    report(
+        "qr_code_decoded",
+        &format!("Decoded QR code saved to: {}", output_file),
+        json!({ "output_file": output_file }),
+    );
+    Ok(())
+}
@@ -0,0 +1,217 @@
+//! Reconstructing the full group secret key from a threshold of shares.
+//!
+//! **This ends the threshold security of the group.** Whoever holds the reconstructed
+//! key can sign alone, so it is only meant for migrating off FROST or for disaster
+//! recovery, on an offline machine, after which the shares should be considered spent.
+//! [`reconstruct`] combines participant shares and [`reconstruct_keys`] the shares of a
+//! dealer key file by Lagrange interpolation at zero, and both check the result against
+//! the group key before saving it as a [`GroupSecretKey`] file. The file is only created
+//! if it does not exist yet, and on Unix only its owner can read it.
+//!
+//! Only ristretto255 keys can be reconstructed.
+
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::files::{create_checked_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::signing::lagrange_coefficient;
+use crate::types::FORMAT_VERSION;
+use crate::{load_frost_keys, load_participant_share, scalar_from_bytes};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::scalar::Scalar;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{info_span, warn};
+use zeroize::{Zeroize, Zeroizing};
+
+/// The full secret key of a group, as saved by [`reconstruct`].
+#[derive(Serialize, Deserialize)]
+pub struct GroupSecretKey {
+    /// Format version, see [`FORMAT_VERSION`].
+    pub version: u32,
+    pub group_key: [u8; 32],
+    /// The group secret key, whose public key is `group_key`.
+    pub secret_key: [u8; 32],
+}
+
+impl Drop for GroupSecretKey {
+    fn drop(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+/// Reconstructs the group secret key from participant shares and saves it.
+///
+/// # Arguments
+/// - `share_files`: Share locations of at least the threshold of participants, see
+///   [`crate::storage`].
+/// - `output_file`: Path to save the [`GroupSecretKey`] to.
+/// - `options`: How the secret key is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the shares belong to different groups, fewer shares than the
+/// threshold or a share twice are given, the result does not match the group key, or
+/// `output_file` already exists.
+pub fn reconstruct(
+    share_files: &[String],
+    output_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the shares and check that they belong to the same group
+    let mut shares = Vec::new();
+    for share_file in share_files {
+        shares.push(Locked::new(load_participant_share(share_file, options)?)?);
+    }
+    let first = shares
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("No shares given".into()))?;
+    for (share, share_file) in shares.iter().zip(share_files) {
+        if share.group_key != first.group_key || share.threshold != first.threshold {
+            return Err(FrostCliError::InvalidShare {
+                share: share_file.clone(),
+                reason: "Share belongs to another group".into(),
+            }
+            .into());
+        }
+    }
+
+    // Step 2: Interpolate the secret key and save it
+    let private_shares: Vec<(Zeroizing<Scalar>, u32)> = shares
+        .iter()
+        .map(|share| Ok((Zeroizing::new(scalar_from_bytes(share.share)?), share.index)))
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+    let indices = save_secret_key(
+        &private_shares,
+        first.threshold,
+        first.group_key,
+        output_file,
+        options,
+    )?;
+    report_reconstructed(&indices, &first.group_key, output_file);
+    Ok(())
+}
+
+/// Reconstructs the group secret key from the shares of a dealer key file and saves it.
+///
+/// # Arguments
+/// - `key_file`: Path to the dealer key file.
+/// - `signers`: Positions of the shares in the key file to combine, at least its
+///   threshold.
+/// - `output_file`: Path to save the [`GroupSecretKey`] to.
+/// - `options`: How the secret key is written.
+///
+/// # Errors
+/// Returns an error if the keys are not ristretto255 keys, fewer signers than the
+/// threshold or an invalid position are given, the result does not match the group key,
+/// or `output_file` already exists.
+pub fn reconstruct_keys(
+    key_file: &str,
+    signers: &[u32],
+    output_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the keys and pick the signers' shares
+    let frost_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+    if !frost_keys.ciphersuite.is_ristretto255() {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Only ristretto255 keys can be reconstructed, not {} keys",
+            frost_keys.ciphersuite
+        ))
+        .into());
+    }
+    let mut private_shares = Vec::new();
+    for &signer in signers {
+        let (share, index) = frost_keys
+            .private_shares
+            .get(signer as usize)
+            .ok_or_else(|| {
+                FrostCliError::InvalidParameters(format!("Invalid signer index: {}", signer))
+            })?;
+        private_shares.push((Zeroizing::new(scalar_from_bytes(*share)?), *index));
+    }
+
+    // Step 2: Interpolate the secret key and save it
+    let indices = save_secret_key(
+        &private_shares,
+        frost_keys.threshold,
+        frost_keys.group_key,
+        output_file,
+        options,
+    )?;
+    report_reconstructed(&indices, &frost_keys.group_key, output_file);
+    Ok(())
+}
+
+/// Interpolates the secret key from `(share, index)` pairs, checks it against the group
+/// key and saves it. Returns the indices of the shares.
+fn save_secret_key(
+    private_shares: &[(Zeroizing<Scalar>, u32)],
+    threshold: u32,
+    group_key: [u8; 32],
+    output_file: &str,
+    options: &FileOptions,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let _span = info_span!("reconstruct", threshold).entered();
+    // Step 1: Check the number of shares and their indices
+    let indices: Vec<u32> = private_shares.iter().map(|(_, index)| *index).collect();
+    if indices.len() < threshold as usize {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of shares is less than the threshold".into(),
+        )
+        .into());
+    }
+    let mut sorted = indices.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != indices.len() || sorted.first() == Some(&0) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Invalid share indices: {:?}",
+            indices
+        ))
+        .into());
+    }
+
+    // Step 2: Interpolate at zero and check the result against the group key
+    let mut secret = Zeroizing::new(Scalar::zero());
+    for (share, index) in private_shares {
+        *secret += lagrange_coefficient(*index, &indices) * **share;
+    }
+    if (&RISTRETTO_BASEPOINT_TABLE * &*secret)
+        .compress()
+        .to_bytes()
+        != group_key
+    {
+        return Err("Reconstructed secret key does not match the group key".into());
+    }
+
+    // Step 3: Save the secret key
+    warn!("Reconstructed the group secret key, which can now sign without a threshold");
+    create_checked_json(
+        output_file,
+        &GroupSecretKey {
+            version: FORMAT_VERSION,
+            group_key,
+            secret_key: secret.to_bytes(),
+        },
+        options,
+    )?;
+    Ok(indices)
+}
+
+/// Reports a reconstructed secret key, leaving the key itself out of the report.
+fn report_reconstructed(indices: &[u32], group_key: &[u8; 32], output_file: &str) {
+    report(
+        "secret_key_reconstructed",
+        &format!(
+            "Group secret key reconstructed from participants {:?} and saved to: {}. Anyone \
+             holding this file can sign alone; keep it offline and delete it when done.",
+            indices, output_file
+        ),
+        json!({
+            "participants": indices,
+            "output_file": output_file,
+            "group_key_fingerprint": envelope::fingerprint(group_key),
+        }),
+    );
+}
@@ -0,0 +1,525 @@
+//! Proactive refresh of participant shares.
+//!
+//! Every participant adds a fresh sharing of zero to their share, so the refreshed
+//! shares interpolate to the same group secret and the group key stays the same, but
+//! no threshold of old shares combined with new ones can sign. A share that leaked
+//! before the refresh becomes useless once it is done.
+//!
+//! Each participant runs [`round_one`] and [`finish`] in their own process, like the
+//! rounds of [`crate::dkg`]. Round one writes a public `refresh_<index>.json` message
+//! with commitments to a random polynomial whose constant term is zero, and a secret
+//! `refresh_share_<index>_<receiver>.json` file for every other participant, which is a
+//! [`RefreshShare`] and is sealed and delivered like a DKG share. [`finish`] checks the
+//! received shares against their senders' commitments and saves the refreshed share.
+//! Every message carries the coordinator's refresh ID, so the messages of an earlier
+//! refresh of the same group cannot be replayed into a later one.
+//!
+//! All participants have to take part and finish, since a share that misses a refresh
+//! no longer fits the others. Together with the old share, the state file and the
+//! received share files give back the new one, so [`finish`] securely deletes them, and
+//! the old share if the refreshed one is saved elsewhere, once the refreshed share is
+//! saved and read back.
+
+use crate::dkg::{evaluate_polynomial, verification_shares, verify_share, Round2Message};
+use crate::error::FrostCliError;
+use crate::identity::IdentitySignature;
+use crate::io::envelope;
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::net::noise;
+use crate::{
+    load_participant_share, point_from_bytes, read_json, save_participant_share, scalar_from_bytes,
+    scalars_from_bytes, secure_delete, storage, write_json, write_secret_json, FileOptions,
+    ParticipantShare,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info_span;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Public refresh message: a participant's commitments to their zero-sharing polynomial.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RefreshMessage {
+    /// ID of the refresh announced by the coordinator.
+    pub session_id: String,
+    pub index: u32,
+    /// Group key of the shares being refreshed.
+    pub group_key: [u8; 32],
+    /// Commitments to the coefficients, the first of which is the identity point.
+    pub commitments: Vec<[u8; 32]>,
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Secret refresh share: the sender's zero-sharing polynomial evaluated at the
+/// receiver's index, tied to the refresh it was made for.
+#[derive(Serialize, Deserialize)]
+pub struct RefreshShare {
+    /// ID of the refresh announced by the coordinator.
+    pub session_id: String,
+    #[serde(flatten)]
+    pub share: Round2Message,
+}
+
+/// Participant-local state kept between the refresh rounds.
+///
+/// Holds the secret polynomial coefficients, so it must never leave the
+/// participant's machine.
+#[derive(Serialize, Deserialize)]
+pub struct RefreshState {
+    pub session_id: String,
+    pub index: u32,
+    pub threshold: u32,
+    pub participants: u32,
+    pub group_key: [u8; 32],
+    pub coefficients: Vec<[u8; 32]>,
+}
+
+impl Drop for RefreshState {
+    fn drop(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
+/// Runs round one of a share refresh for a single participant.
+///
+/// # Arguments
+/// - `session_id`: ID of the refresh announced by the coordinator, a fresh one for every
+///   refresh.
+/// - `share_file`: Share location of the participant's current share, see
+///   [`crate::storage`].
+/// - `state_file`: Path to save the participant's secret local state.
+/// - `output_dir`: Directory to write the public `refresh_<index>.json` message and the
+///   secret shares for the other participants to.
+/// - `options`: How the state and the round messages are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded, its threshold is 1, or the files
+/// cannot be written.
+pub fn round_one(
+    session_id: &str,
+    share_file: &str,
+    state_file: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    round_one_with_rng(
+        session_id, share_file, state_file, output_dir, options, &mut OsRng,
+    )
+}
+
+/// Runs round one of a share refresh like [`round_one`], drawing the secret polynomial
+/// from `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if the share cannot be loaded, its threshold is 1, or the files
+/// cannot be written.
+pub fn round_one_with_rng<R: RngCore + CryptoRng>(
+    session_id: &str,
+    share_file: &str,
+    state_file: &str,
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the share being refreshed.
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let _span = info_span!("refresh_round_one", index = share.index).entered();
+    if share.threshold < 2 {
+        return Err(FrostCliError::InvalidParameters(
+            "Shares of a group with threshold 1 are the group secret and cannot be refreshed"
+                .into(),
+        )
+        .into());
+    }
+
+    // Step 2: Sample a polynomial with a zero constant term and commit to it.
+    let coefficients: Zeroizing<Vec<Scalar>> = Zeroizing::new(
+        std::iter::once(Scalar::zero())
+            .chain((1..share.threshold).map(|_| Scalar::random(rng)))
+            .collect(),
+    );
+    let commitments: Vec<[u8; 32]> = coefficients
+        .iter()
+        .map(|coefficient| {
+            (&RISTRETTO_BASEPOINT_TABLE * coefficient)
+                .compress()
+                .to_bytes()
+        })
+        .collect();
+
+    // Step 3: Save the local state and the public refresh message.
+    let state = RefreshState {
+        session_id: session_id.to_string(),
+        index: share.index,
+        threshold: share.threshold,
+        participants: share.participants,
+        group_key: share.group_key,
+        coefficients: coefficients.iter().map(|c| c.to_bytes()).collect(),
+    };
+    write_secret_json(state_file, &state, options)?;
+
+    fs::create_dir_all(output_dir)?;
+    let output_file = message_file_path(output_dir, share.index);
+    write_json(
+        &output_file,
+        &RefreshMessage {
+            session_id: session_id.to_string(),
+            index: share.index,
+            group_key: share.group_key,
+            commitments,
+            identity_signature: None,
+        },
+        options,
+    )?;
+
+    // Step 4: Evaluate our polynomial for every other participant.
+    for receiver in (1..=share.participants).filter(|&i| i != share.index) {
+        let delta = Zeroizing::new(evaluate_polynomial(&coefficients, receiver));
+        write_secret_json(
+            share_file_path(output_dir, share.index, receiver),
+            &RefreshShare {
+                session_id: session_id.to_string(),
+                share: Round2Message {
+                    sender: share.index,
+                    receiver,
+                    share: delta.to_bytes(),
+                    identity_signature: None,
+                },
+            },
+            options,
+        )?;
+    }
+
+    report(
+        "refresh_round_one_complete",
+        &format!(
+            "Refresh round 1 complete for participant {}. Messages saved to: {}",
+            share.index, output_dir
+        ),
+        json!({
+            "session_id": session_id,
+            "index": share.index,
+            "output_dir": output_dir,
+        }),
+    );
+    Ok(())
+}
+
+/// Finishes a share refresh for a single participant.
+///
+/// Verifies every participant's refresh message and the shares sent to this
+/// participant, all of the refresh the state was made for, then adds them to the
+/// current share and saves the refreshed share. Once the refreshed share is read back,
+/// the state file and the received shares, sealed or not, are overwritten and deleted,
+/// and so is the old share if the refreshed one is saved to another share file.
+///
+/// # Arguments
+/// - `state_file`: Path to the participant's local state from round one.
+/// - `share_file`: Share location of the participant's current share.
+/// - `refresh_dir`: Directory containing the `refresh_<i>.json` messages of all
+///   participants and the `refresh_share_<sender>_<index>.json` files sent to this
+///   participant.
+/// - `output_share_file`: Share location to save the refreshed share to, which may be
+///   `share_file` itself. A backend other than files cannot delete the old share, so it
+///   is overwritten with the refreshed one.
+/// - `options`: How the shares are read and the refreshed share is written.
+///
+/// # Errors
+/// Returns an error if a message or share is missing, belongs to another group or
+/// refresh, or does not match its sender's commitments, the refreshed share does not
+/// read back, or the state file, a received share or the old share cannot be deleted.
+pub fn finish(
+    state_file: &str,
+    share_file: &str,
+    refresh_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the local state and the share it was made for.
+    let state: RefreshState = read_json(state_file)?;
+    let _span = info_span!("refresh_finish", index = state.index).entered();
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    if share.index != state.index || share.group_key != state.group_key {
+        return Err(FrostCliError::InvalidShare {
+            share: share_file.to_string(),
+            reason: format!(
+                "Share is not the share of participant {} the refresh was started with",
+                state.index
+            ),
+        }
+        .into());
+    }
+    let coefficients = scalars_from_bytes(&state.coefficients)?;
+
+    // Step 2: Check every participant's refresh message.
+    let mut messages = Vec::new();
+    for index in 1..=state.participants {
+        let message: RefreshMessage = read_json(message_file_path(refresh_dir, index))?;
+        check_message(&state, index, &message)?;
+        messages.push(message);
+    }
+    let own_commitments: Vec<[u8; 32]> = coefficients
+        .iter()
+        .map(|c| (&RISTRETTO_BASEPOINT_TABLE * c).compress().to_bytes())
+        .collect();
+    let own_message = state
+        .index
+        .checked_sub(1)
+        .and_then(|position| messages.get(position as usize));
+    if own_message.map(|message| &message.commitments) != Some(&own_commitments) {
+        return Err(FrostCliError::InvalidParameters(
+            "Refresh message for this participant does not match the state file".into(),
+        )
+        .into());
+    }
+
+    // Step 3: Verify the shares sent to us and add them to our share.
+    let mut secret = Zeroizing::new(scalar_from_bytes(share.share)?);
+    *secret += evaluate_polynomial(&coefficients, state.index);
+    let mut delta_files = Vec::new();
+    for message in messages
+        .iter()
+        .filter(|message| message.index != state.index)
+    {
+        let delta_file = share_file_path(refresh_dir, message.index, state.index);
+        delta_files.push(delta_file.clone());
+        let RefreshShare {
+            session_id,
+            share: delta_message,
+        } = read_json(&delta_file)?;
+        check_session_id(&state, message.index, &session_id)?;
+        if delta_message.sender != message.index || delta_message.receiver != state.index {
+            return Err(FrostCliError::Dkg {
+                index: message.index,
+                reason: format!("Unexpected share file: {}", delta_file.display()),
+            }
+            .into());
+        }
+        let delta = Zeroizing::new(scalar_from_bytes(delta_message.share)?);
+        if !verify_share(state.index, &delta, &message.commitments)? {
+            return Err(FrostCliError::Dkg {
+                index: message.index,
+                reason: "Sent an invalid refresh share".into(),
+            }
+            .into());
+        }
+        *secret += *delta;
+    }
+    report(
+        "shares_verified",
+        "All received refresh shares verified!",
+        json!({ "index": state.index }),
+    );
+
+    // Step 4: Save the refreshed share under the unchanged group key, with every
+    // verification share moved by the refresh polynomials evaluated at its index.
+    let deltas = verification_shares(
+        messages
+            .iter()
+            .map(|message| message.commitments.as_slice()),
+        share.verification_shares.len() as u32,
+    )?;
+    let verification_shares = share
+        .verification_shares
+        .iter()
+        .zip(&deltas)
+        .map(|(old, delta)| {
+            Ok((point_from_bytes(old)? + point_from_bytes(delta)?)
+                .compress()
+                .to_bytes())
+        })
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+    let refreshed = Locked::new(ParticipantShare {
+        index: state.index,
+        share: secret.to_bytes(),
+        group_key: state.group_key,
+        threshold: state.threshold,
+        participants: state.participants,
+        verification_shares,
+    })?;
+    save_participant_share(output_share_file, &refreshed, options)?;
+    let saved = Locked::new(load_participant_share(output_share_file, options)?)?;
+    if saved.share != refreshed.share || saved.verification_shares != refreshed.verification_shares
+    {
+        return Err(FrostCliError::InvalidShare {
+            share: output_share_file.to_string(),
+            reason: "Refreshed share does not read back as saved".into(),
+        }
+        .into());
+    }
+
+    // Step 5: Delete the state and the received shares, which give back the refreshed
+    // share together with the old one, and the old share itself.
+    secure_delete(state_file)?;
+    for delta_file in &delta_files {
+        secure_delete(delta_file)?;
+        let sealed_file = noise::sealed_path(delta_file)?;
+        if sealed_file.exists() {
+            secure_delete(sealed_file)?;
+        }
+    }
+    if !same_location(share_file, output_share_file)? {
+        if storage::is_file(share_file) {
+            secure_delete(share_file)?;
+        } else {
+            storage::open(share_file, options)?.save(&refreshed)?;
+        }
+    }
+
+    report(
+        "refresh_complete",
+        &format!(
+            "Refresh complete for participant {}. Share saved to: {}",
+            state.index, output_share_file
+        ),
+        json!({
+            "session_id": state.session_id,
+            "index": state.index,
+            "share_file": output_share_file,
+            "group_key_fingerprint": envelope::fingerprint(&state.group_key),
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the path of the refresh message [`round_one`] writes for this participant.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn own_message_file(
+    state_file: &str,
+    output_dir: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let state: RefreshState = read_json(state_file)?;
+    Ok(message_file_path(output_dir, state.index))
+}
+
+/// Returns the paths of the refresh messages [`finish`] reads from `refresh_dir`.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn message_files(
+    state_file: &str,
+    refresh_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: RefreshState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .map(|index| message_file_path(refresh_dir, index))
+        .collect())
+}
+
+/// Returns the paths of the secret shares [`round_one`] writes for the other participants.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn outgoing_share_files(
+    state_file: &str,
+    output_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: RefreshState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .filter(|&receiver| receiver != state.index)
+        .map(|receiver| share_file_path(output_dir, state.index, receiver))
+        .collect())
+}
+
+/// Returns the paths of the secret shares [`finish`] reads from `refresh_dir`.
+///
+/// # Errors
+/// Returns an error if the state file cannot be read.
+pub fn incoming_share_files(
+    state_file: &str,
+    refresh_dir: &str,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let state: RefreshState = read_json(state_file)?;
+    Ok((1..=state.participants)
+        .filter(|&sender| sender != state.index)
+        .map(|sender| share_file_path(refresh_dir, sender, state.index))
+        .collect())
+}
+
+/// Checks that a refresh message is participant `index`'s zero sharing for our group.
+fn check_message(
+    state: &RefreshState,
+    index: u32,
+    message: &RefreshMessage,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let fail = |reason: &str| -> Box<dyn std::error::Error> {
+        FrostCliError::Dkg {
+            index,
+            reason: reason.into(),
+        }
+        .into()
+    };
+    if message.index != index {
+        return Err(fail(&format!(
+            "Refresh message of participant {} claims to come from participant {}",
+            index, message.index
+        )));
+    }
+    check_session_id(state, index, &message.session_id)?;
+    if message.group_key != state.group_key {
+        return Err(fail("Refreshes the shares of another group key"));
+    }
+    if message.commitments.len() != state.threshold as usize {
+        return Err(fail(&format!(
+            "Sent {} commitments, expected {}",
+            message.commitments.len(),
+            state.threshold
+        )));
+    }
+    // A nonzero constant term would change the group secret.
+    if message.commitments.first() != Some(&RistrettoPoint::identity().compress().to_bytes()) {
+        return Err(fail("Polynomial does not share zero"));
+    }
+    Ok(())
+}
+
+/// Checks that a round message of participant `index` belongs to the refresh of `state`.
+fn check_session_id(
+    state: &RefreshState,
+    index: u32,
+    session_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if session_id != state.session_id {
+        return Err(FrostCliError::Dkg {
+            index,
+            reason: format!(
+                "Round message belongs to refresh {}, expected {}",
+                session_id, state.session_id
+            ),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Returns whether two share locations are the same, two paths to one file included.
+fn same_location(location: &str, other: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    if storage::is_file(location) && storage::is_file(other) {
+        let path = fs::canonicalize(location).map_err(|err| FrostCliError::io(location, err))?;
+        let other_path = fs::canonicalize(other).map_err(|err| FrostCliError::io(other, err))?;
+        return Ok(path == other_path);
+    }
+    Ok(location == other)
+}
+
+/// Path of participant `index`'s refresh message inside `dir`.
+fn message_file_path(dir: &str, index: u32) -> PathBuf {
+    Path::new(dir).join(format!("refresh_{}.json", index))
+}
+
+/// Path of the refresh share `sender` evaluated for `receiver` inside `dir`.
+fn share_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("refresh_share_{}_{}.json", sender, receiver))
+}
@@ -0,0 +1,131 @@
+//! Repairing the lost share of a participant.
+//!
+//! A participant who lost their share gets it back from a threshold of the other
+//! participants, the helpers, without a new key generation and without any of them
+//! learning the share or the group secret. This is the enrollment protocol of
+//! [`crate::enroll`] run for the lost index: the helpers' pieces add up to the group's
+//! polynomial at that index, which is the lost share itself, so the group key and every
+//! other share stay as they are and nobody needs to update their share.
+//!
+//! Every helper runs [`round_one`] and [`round_two`], and the participant who lost the
+//! share runs [`finish`], with the same `enroll_*` files as an enrollment. A revoked
+//! index cannot be repaired, see [`crate::revoke`].
+
+use crate::enroll::{self, EnrollMessage};
+use crate::error::FrostCliError;
+use crate::memlock::Locked;
+use crate::revoke::revoked_indices;
+use crate::{load_participant_share, read_json, FileOptions};
+use rand::rngs::OsRng;
+
+/// Runs a helper's first round of a repair, see [`crate::enroll::round_one`].
+///
+/// # Arguments
+/// - `share_file`: Share location of the helper's share, see [`crate::storage`].
+/// - `helpers`: Indices of all helpers, at least the threshold of them.
+/// - `lost_index`: Index of the participant who lost their share.
+/// - `output_dir`: Directory to write the public `enroll_<index>.json` message and the
+///   secret pieces for the other helpers to.
+/// - `options`: How the round one messages are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the lost index is not part of the group or was revoked, the
+/// helpers are invalid, or the files cannot be written.
+pub fn round_one(
+    share_file: &str,
+    helpers: &[u32],
+    lost_index: u32,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_lost_index(share_file, lost_index, options)?;
+    enroll::split_share(
+        share_file, helpers, lost_index, output_dir, options, &mut OsRng,
+    )
+}
+
+/// Runs a helper's second round of a repair, see [`crate::enroll::round_two`].
+///
+/// # Arguments
+/// - `share_file`: Share location of the helper's share.
+/// - `helpers`: Indices of all helpers.
+/// - `lost_index`: Index of the participant who lost their share.
+/// - `repair_dir`: Directory containing the pieces sent to this helper.
+/// - `output_dir`: Directory to write the secret share for the lost index to.
+/// - `options`: How the share for the lost index is written.
+///
+/// # Errors
+/// Returns an error if the lost index is not part of the group or was revoked, or a
+/// piece is missing or was not sent to this helper.
+pub fn round_two(
+    share_file: &str,
+    helpers: &[u32],
+    lost_index: u32,
+    repair_dir: &str,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_lost_index(share_file, lost_index, options)?;
+    enroll::add_pieces(
+        share_file, helpers, lost_index, repair_dir, output_dir, options,
+    )
+}
+
+/// Finishes a repair for the participant who lost their share, see
+/// [`crate::enroll::finish`].
+///
+/// # Arguments
+/// - `lost_index`: This participant's index.
+/// - `helpers`: Indices of all helpers.
+/// - `repair_dir`: Directory containing the helpers' messages and the shares they sent
+///   to this participant.
+/// - `output_share_file`: Share location to save the repaired share to.
+/// - `options`: How the repaired share is written.
+///
+/// # Errors
+/// Returns an error if the lost index is not part of the group, or the repaired share
+/// does not match the helpers' public shares.
+pub fn finish(
+    lost_index: u32,
+    helpers: &[u32],
+    repair_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let helper = helpers
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("No helpers given".into()))?;
+    let message: EnrollMessage = read_json(enroll::message_file(repair_dir, *helper))?;
+    if lost_index == 0 || lost_index > message.participants {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is not one of the {} participants, enroll it instead",
+            lost_index, message.participants
+        ))
+        .into());
+    }
+    enroll::finish_share(lost_index, helpers, repair_dir, output_share_file, options)
+}
+
+/// Checks that `lost_index` is a participant of the helper's group that was not revoked.
+fn check_lost_index(
+    share_file: &str,
+    lost_index: u32,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    if lost_index == 0 || lost_index > share.participants {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is not one of the {} participants, enroll it instead",
+            lost_index, share.participants
+        ))
+        .into());
+    }
+    if revoked_indices(share_file, options)?.contains(&lost_index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} has been revoked",
+            lost_index
+        ))
+        .into());
+    }
+    Ok(())
+}
@@ -0,0 +1,584 @@
+//! Resharing a group key to a new threshold and participant set.
+//!
+//! A threshold of the old participants, the dealers, each deal their share weighted by
+//! its Lagrange coefficient to the new participants with a fresh polynomial of the new
+//! threshold. The new participants add up what they receive, which gives them shares of
+//! the same group secret under the new threshold, so the group key stays the same. The
+//! old shares should be deleted once the new participants have their shares.
+//!
+//! [`reshare_keys`] reshares a dealer key file in one process. Otherwise every dealer
+//! runs [`round_one`], which writes a public `reshare_<index>.json` message and a secret
+//! `reshare_share_<index>_<receiver>.json` file for every new participant, a
+//! [`Round2Message`] sealed and delivered like a DKG share. Every new participant then
+//! runs [`finish`] with the messages and shares of all dealers.
+//!
+//! Only ristretto255 keys can be reshared.
+
+use crate::dkg::{evaluate_polynomial, verification_shares, verify_share, Round2Message};
+use crate::error::FrostCliError;
+use crate::identity::IdentitySignature;
+use crate::io::envelope;
+use crate::io::files::{write_checked_json, write_secret_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::signing::lagrange_coefficient;
+use crate::types::FORMAT_VERSION;
+use crate::{
+    load_frost_keys, load_participant_share, point_from_bytes, read_json, save_participant_share,
+    scalar_from_bytes, write_json, FrostKeys, ParticipantShare,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info_span;
+use zeroize::Zeroizing;
+
+/// Public reshare message: a dealer's commitments to the polynomial it deals its share
+/// with, and the parameters of the reshare.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReshareMessage {
+    pub index: u32,
+    /// Group key of the shares being reshared.
+    pub group_key: [u8; 32],
+    /// Threshold of the old participant set.
+    pub old_threshold: u32,
+    /// Indices of all dealers, in the old participant set.
+    pub dealers: Vec<u32>,
+    /// Threshold of the new participant set.
+    pub threshold: u32,
+    /// Number of new participants.
+    pub participants: u32,
+    /// Indices up to `participants` that receive no share, see [`crate::revoke`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revoked: Vec<u32>,
+    pub commitments: Vec<[u8; 32]>,
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Reshares the keys of a dealer key file to a new threshold and number of participants
+/// and saves them to a new key file with the same group key.
+///
+/// # Arguments
+/// - `key_file`: Path to the dealer key file.
+/// - `signers`: Positions of the shares in the key file that deal, at least its threshold.
+/// - `t`: Threshold of the new keys.
+/// - `n`: Number of participants of the new keys.
+/// - `output_key_file`: Path to save the new keys to.
+/// - `options`: How the new key file is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the keys are not ristretto255 keys, or the signers or new
+/// parameters are invalid.
+pub fn reshare_keys(
+    key_file: &str,
+    signers: &[u32],
+    t: u32,
+    n: u32,
+    output_key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let frost_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+    let reshared = Locked::new(reshare_frost_keys(&frost_keys, signers, t, n, &mut OsRng)?)?;
+    write_checked_json(output_key_file, &*reshared, options)?;
+
+    report(
+        "keys_reshared",
+        &format!(
+            "Reshared the keys to {} shares with threshold {}. Keys saved to: {}",
+            n, t, output_key_file
+        ),
+        json!({
+            "key_file": output_key_file,
+            "group_key_fingerprint": envelope::fingerprint(&reshared.group_key),
+            "threshold": t,
+            "participants": n,
+        }),
+    );
+    Ok(())
+}
+
+/// Reshares keys in memory like [`reshare_keys`], drawing the dealers' polynomials from
+/// `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if the keys are not ristretto255 keys, or the signers or new
+/// parameters are invalid.
+pub fn reshare_frost_keys<R: RngCore + CryptoRng>(
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+    t: u32,
+    n: u32,
+    rng: &mut R,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    reshare_frost_keys_excluding(frost_keys, signers, t, n, &[], rng)
+}
+
+/// Reshares keys in memory like [`reshare_frost_keys`], leaving out the shares of the
+/// `revoked` indices.
+pub(crate) fn reshare_frost_keys_excluding<R: RngCore + CryptoRng>(
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+    t: u32,
+    n: u32,
+    revoked: &[u32],
+    rng: &mut R,
+) -> Result<FrostKeys, Box<dyn std::error::Error>> {
+    let _span = info_span!("reshare_keys", t, n).entered();
+    // Step 1: Check the keys, the dealing shares and the new parameters.
+    if !frost_keys.ciphersuite.is_ristretto255() {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Only ristretto255 keys can be reshared, not {} keys",
+            frost_keys.ciphersuite
+        ))
+        .into());
+    }
+    let receivers = receivers(t, n, revoked)?;
+    if let Some(&signer) = signers
+        .iter()
+        .find(|&&signer| signer as usize >= frost_keys.private_shares.len())
+    {
+        return Err(
+            FrostCliError::InvalidParameters(format!("Invalid signer index: {}", signer)).into(),
+        );
+    }
+    let dealers: Vec<u32> = signers
+        .iter()
+        .map(|&signer| frost_keys.private_shares[signer as usize].1)
+        .collect();
+    check_dealers(
+        &dealers,
+        frost_keys.threshold,
+        frost_keys.private_shares.len() as u32,
+    )?;
+
+    // Step 2: Every dealer deals its weighted share to the new participants.
+    let mut shares: Zeroizing<Vec<Scalar>> = Zeroizing::new(vec![Scalar::zero(); receivers.len()]);
+    let mut group_key = RistrettoPoint::identity();
+    let mut dealt_commitments = Vec::with_capacity(signers.len());
+    for &signer in signers {
+        let (share, index) = &frost_keys.private_shares[signer as usize];
+        let share = Zeroizing::new(scalar_from_bytes(*share)?);
+        let (commitments, evaluations) = deal(*index, &share, &dealers, t, &receivers, rng);
+        group_key += point_from_bytes(&commitments[0])?;
+        for (total, evaluation) in shares.iter_mut().zip(evaluations.iter()) {
+            *total += evaluation;
+        }
+        dealt_commitments.push(commitments);
+    }
+
+    // Step 3: The dealt constant terms must add up to the group key.
+    if group_key.compress().to_bytes() != frost_keys.group_key {
+        return Err(FrostCliError::InvalidShare {
+            share: format!("of participants {:?}", dealers),
+            reason: "Reshared shares do not add up to the group key".into(),
+        }
+        .into());
+    }
+    Ok(FrostKeys {
+        version: FORMAT_VERSION,
+        ciphersuite: frost_keys.ciphersuite,
+        group_key: frost_keys.group_key,
+        private_shares: shares
+            .iter()
+            .zip(&receivers)
+            .map(|(share, &index)| (share.to_bytes(), index))
+            .collect(),
+        threshold: t,
+        verification_shares: verification_shares(dealt_commitments.iter().map(Vec::as_slice), n)?,
+    })
+}
+
+/// Runs a dealer's round of a reshare.
+///
+/// # Arguments
+/// - `share_file`: Share location of the dealer's share, see [`crate::storage`].
+/// - `dealers`: Indices of all dealers, at least the old threshold of them.
+/// - `t`: Threshold of the new participant set.
+/// - `n`: Number of new participants.
+/// - `output_dir`: Directory to write the public `reshare_<index>.json` message and the
+///   secret shares for the new participants to.
+/// - `options`: How the round messages are written.
+///
+/// # Errors
+/// Returns an error if the dealers or new parameters are invalid, or the files cannot be
+/// written.
+pub fn round_one(
+    share_file: &str,
+    dealers: &[u32],
+    t: u32,
+    n: u32,
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    round_one_with_rng(share_file, dealers, t, n, output_dir, options, &mut OsRng)
+}
+
+/// Runs a dealer's round of a reshare like [`round_one`], drawing the polynomial from
+/// `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if the dealers or new parameters are invalid, or the files cannot be
+/// written.
+pub fn round_one_with_rng<R: RngCore + CryptoRng>(
+    share_file: &str,
+    dealers: &[u32],
+    t: u32,
+    n: u32,
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    deal_share(share_file, dealers, t, n, &[], output_dir, options, rng)
+}
+
+/// Runs a dealer's round of a reshare like [`round_one_with_rng`], dealing no shares to
+/// the `revoked` indices.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn deal_share<R: RngCore + CryptoRng>(
+    share_file: &str,
+    dealers: &[u32],
+    t: u32,
+    n: u32,
+    revoked: &[u32],
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the dealer's share and check the parameters.
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let _span = info_span!("reshare_round_one", index = share.index, t, n).entered();
+    let receivers = receivers(t, n, revoked)?;
+    check_dealers(dealers, share.threshold, share.participants)?;
+    if !dealers.contains(&share.index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant {} is not one of the dealers {:?}",
+            share.index, dealers
+        ))
+        .into());
+    }
+
+    // Step 2: Deal the weighted share with a polynomial of the new threshold.
+    let secret = Zeroizing::new(scalar_from_bytes(share.share)?);
+    let (commitments, evaluations) = deal(share.index, &secret, dealers, t, &receivers, rng);
+
+    // Step 3: Save the public message and the secret shares of the new participants.
+    fs::create_dir_all(output_dir)?;
+    write_json(
+        message_file(output_dir, share.index),
+        &ReshareMessage {
+            index: share.index,
+            group_key: share.group_key,
+            old_threshold: share.threshold,
+            dealers: dealers.to_vec(),
+            threshold: t,
+            participants: n,
+            revoked: revoked.to_vec(),
+            commitments,
+            identity_signature: None,
+        },
+        options,
+    )?;
+    for (&receiver, evaluation) in receivers.iter().zip(evaluations.iter()) {
+        write_secret_json(
+            share_file_path(output_dir, share.index, receiver),
+            &Round2Message {
+                sender: share.index,
+                receiver,
+                share: evaluation.to_bytes(),
+                identity_signature: None,
+            },
+            options,
+        )?;
+    }
+
+    report(
+        "reshare_round_one_complete",
+        &format!(
+            "Reshare round 1 complete for dealer {}. Messages saved to: {}",
+            share.index, output_dir
+        ),
+        json!({ "index": share.index, "output_dir": output_dir }),
+    );
+    Ok(())
+}
+
+/// Finishes a reshare for a new participant.
+///
+/// Checks that the messages of all dealers describe the same reshare and add up to the
+/// group key, verifies the shares sent to this participant against them, and saves
+/// their sum as the participant's new share.
+///
+/// # Arguments
+/// - `index`: This participant's index in the new participant set.
+/// - `dealers`: Indices of all dealers.
+/// - `reshare_dir`: Directory containing the `reshare_<dealer>.json` messages and the
+///   `reshare_share_<dealer>_<index>.json` files sent to this participant.
+/// - `output_share_file`: Share location to save the new share to.
+/// - `options`: How the new share is written.
+///
+/// # Errors
+/// Returns an error if a message or share is missing, the messages disagree or do not
+/// add up to the group key, or a share does not match its dealer's commitments.
+pub fn finish(
+    index: u32,
+    dealers: &[u32],
+    reshare_dir: &str,
+    output_share_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = info_span!("reshare_finish", index).entered();
+    // Step 1: Load the dealers' messages and check that they describe the same reshare.
+    let messages: Vec<ReshareMessage> = dealers
+        .iter()
+        .map(|&dealer| read_json(message_file(reshare_dir, dealer)))
+        .collect::<Result<_, _>>()?;
+    let first = messages
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("No dealers given".into()))?;
+    for (&dealer, message) in dealers.iter().zip(&messages) {
+        let fail = |reason: String| -> Box<dyn std::error::Error> {
+            FrostCliError::Dkg {
+                index: dealer,
+                reason,
+            }
+            .into()
+        };
+        if message.index != dealer {
+            return Err(fail(format!(
+                "Reshare message claims to come from participant {}",
+                message.index
+            )));
+        }
+        if message.group_key != first.group_key
+            || message.old_threshold != first.old_threshold
+            || message.threshold != first.threshold
+            || message.participants != first.participants
+            || message.revoked != first.revoked
+        {
+            return Err(fail(
+                "Reshare message disagrees with the other dealers".into(),
+            ));
+        }
+        if message.dealers != dealers {
+            return Err(fail(format!(
+                "Reshare message names dealers {:?}, expected {:?}",
+                message.dealers, dealers
+            )));
+        }
+        if message.threshold == 0 {
+            return Err(fail("Reshare message has a threshold of 0".into()));
+        }
+        if message.commitments.len() != message.threshold as usize {
+            return Err(fail(format!(
+                "Sent {} commitments, expected {}",
+                message.commitments.len(),
+                message.threshold
+            )));
+        }
+    }
+    check_dealers(dealers, first.old_threshold, u32::MAX)?;
+    if index == 0 || index > first.participants || first.revoked.contains(&index) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Participant index must be between 1 and {}",
+            first.participants
+        ))
+        .into());
+    }
+
+    // Step 2: The dealt constant terms must add up to the group key.
+    let mut group_key = RistrettoPoint::identity();
+    for message in &messages {
+        group_key += point_from_bytes(&message.commitments[0])?;
+    }
+    if group_key.compress().to_bytes() != first.group_key {
+        let mut participants = dealers.to_vec();
+        participants.sort_unstable();
+        return Err(FrostCliError::Misbehaving {
+            participants,
+            reason: "Reshare messages do not add up to the group key".into(),
+        }
+        .into());
+    }
+
+    // Step 3: Verify and sum the shares the dealers sent to us.
+    let mut secret = Zeroizing::new(Scalar::zero());
+    for message in &messages {
+        let share_file = share_file_path(reshare_dir, message.index, index);
+        let share_message: Round2Message = read_json(&share_file)?;
+        if share_message.sender != message.index || share_message.receiver != index {
+            return Err(FrostCliError::Dkg {
+                index: message.index,
+                reason: format!("Unexpected share file: {}", share_file.display()),
+            }
+            .into());
+        }
+        let share = Zeroizing::new(scalar_from_bytes(share_message.share)?);
+        if !verify_share(index, &share, &message.commitments)? {
+            return Err(FrostCliError::Dkg {
+                index: message.index,
+                reason: "Sent an invalid reshare share".into(),
+            }
+            .into());
+        }
+        *secret += *share;
+    }
+    report(
+        "shares_verified",
+        "All received reshare shares verified!",
+        json!({ "index": index }),
+    );
+
+    // Step 4: Save the new share under the unchanged group key, with the verification
+    // shares of the new participants.
+    let participant_share = Locked::new(ParticipantShare {
+        index,
+        share: secret.to_bytes(),
+        group_key: first.group_key,
+        threshold: first.threshold,
+        participants: first.participants,
+        verification_shares: verification_shares(
+            messages
+                .iter()
+                .map(|message| message.commitments.as_slice()),
+            first.participants,
+        )?,
+    })?;
+    save_participant_share(output_share_file, &participant_share, options)?;
+
+    report(
+        "reshare_complete",
+        &format!(
+            "Reshare complete for participant {}. Share saved to: {}",
+            index, output_share_file
+        ),
+        json!({
+            "index": index,
+            "share_file": output_share_file,
+            "group_key_fingerprint": envelope::fingerprint(&first.group_key),
+            "threshold": first.threshold,
+            "participants": first.participants,
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the path of dealer `index`'s reshare message inside `dir`.
+pub fn message_file(dir: &str, index: u32) -> PathBuf {
+    Path::new(dir).join(format!("reshare_{}.json", index))
+}
+
+/// Returns the paths of the secret shares [`round_one`] writes for `n` new participants.
+pub fn outgoing_share_files(dir: &str, index: u32, n: u32) -> Vec<PathBuf> {
+    (1..=n)
+        .map(|receiver| share_file_path(dir, index, receiver))
+        .collect()
+}
+
+/// Returns the paths of the secret shares [`finish`] reads from `dir`.
+pub fn incoming_share_files(dir: &str, index: u32, dealers: &[u32]) -> Vec<PathBuf> {
+    dealers
+        .iter()
+        .map(|&dealer| share_file_path(dir, dealer, index))
+        .collect()
+}
+
+/// Deals `index`'s share weighted by its Lagrange coefficient over `dealers` with a
+/// random polynomial of threshold `t`. Returns the commitments to the polynomial and its
+/// evaluations for the `receivers`.
+fn deal<R: RngCore + CryptoRng>(
+    index: u32,
+    share: &Scalar,
+    dealers: &[u32],
+    t: u32,
+    receivers: &[u32],
+    rng: &mut R,
+) -> (Vec<[u8; 32]>, Zeroizing<Vec<Scalar>>) {
+    let coefficients: Zeroizing<Vec<Scalar>> = Zeroizing::new(
+        std::iter::once(lagrange_coefficient(index, dealers) * share)
+            .chain((1..t).map(|_| Scalar::random(rng)))
+            .collect(),
+    );
+    let commitments = coefficients
+        .iter()
+        .map(|coefficient| {
+            (&RISTRETTO_BASEPOINT_TABLE * coefficient)
+                .compress()
+                .to_bytes()
+        })
+        .collect();
+    let evaluations = Zeroizing::new(
+        receivers
+            .iter()
+            .map(|&receiver| evaluate_polynomial(&coefficients, receiver))
+            .collect(),
+    );
+    (commitments, evaluations)
+}
+
+/// Checks the threshold and number of participants of the new participant set, and
+/// returns the indices that receive a share: 1 to `n` except the `revoked` ones.
+pub(crate) fn receivers(
+    t: u32,
+    n: u32,
+    revoked: &[u32],
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let receivers: Vec<u32> = (1..=n).filter(|index| !revoked.contains(index)).collect();
+    check_parameters(t, receivers.len() as u32)?;
+    Ok(receivers)
+}
+
+/// Checks the threshold and number of participants of the new participant set.
+fn check_parameters(t: u32, n: u32) -> Result<(), Box<dyn std::error::Error>> {
+    if t > n {
+        return Err(FrostCliError::InvalidParameters(
+            "Threshold value cannot be greater than the total number of participants".into(),
+        )
+        .into());
+    }
+    if t == 0 {
+        return Err(
+            FrostCliError::InvalidParameters("Threshold value must be at least 1".into()).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Checks that `dealers` are at least `threshold` distinct indices up to `participants`.
+fn check_dealers(
+    dealers: &[u32],
+    threshold: u32,
+    participants: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if dealers.len() < threshold as usize {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of dealers is less than the threshold".into(),
+        )
+        .into());
+    }
+    let mut sorted = dealers.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != dealers.len()
+        || sorted.first() == Some(&0)
+        || sorted.last().is_some_and(|&last| last > participants)
+    {
+        return Err(
+            FrostCliError::InvalidParameters(format!("Invalid dealers: {:?}", dealers)).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Path of the share `sender` dealt to `receiver` inside `dir`.
+pub(crate) fn share_file_path(dir: &str, sender: u32, receiver: u32) -> PathBuf {
+    Path::new(dir).join(format!("reshare_share_{}_{}.json", sender, receiver))
+}
@@ -0,0 +1,439 @@
+//! Revoking lost or compromised participants.
+//!
+//! The remaining participants reshare the group key to the same threshold and
+//! participant set without the revoked indices, see [`crate::reshare`], so the revoked
+//! shares are no longer part of the sharing. As the group key stays the same, a revoked
+//! share would still produce valid partial signatures, so the group also signs a
+//! [`RevocationStatement`] naming the revoked indices, which is recorded next to the key
+//! file in `revocations.json`, the key's directory when it is kept in the keystore.
+//! Signing sessions started with that key file refuse the revoked indices, see
+//! [`crate::session::Session::new`].
+//!
+//! [`revoke_keys`] does all of this for a dealer key file in one process. Otherwise
+//! every dealer runs [`round_one`] and the remaining participants run
+//! [`crate::reshare::finish`] as in a reshare, the statement written by
+//! [`new_statement`] is signed like any other message, and [`record`] records it.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::files::{read_signature, write_checked_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::reshare::{self, reshare_frost_keys_excluding};
+use crate::{
+    load_frost_keys, load_group_key_bytes, load_participant_share, read_json, sign_with_keys,
+    storage, verify_with_group_key, write_json, InvalidSignature, SIGNING_CONTEXT,
+};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info_span;
+
+/// File name of the revocation records next to a key file.
+const REVOCATIONS_FILE: &str = "revocations.json";
+
+/// The statement the group signs to revoke participants.
+///
+/// It is signed as its compact JSON, which is already in canonical form, so it verifies
+/// with and without `--canonicalize jcs`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RevocationStatement {
+    pub group_key_fingerprint: String,
+    /// Indices of the revoked participants.
+    pub revoked: Vec<u32>,
+    /// Unix time the statement was written.
+    pub timestamp: u64,
+}
+
+/// A revocation statement with the group's signature, as kept in `revocations.json`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SignedRevocation {
+    /// The JSON of the signed [`RevocationStatement`].
+    pub statement: String,
+    pub signature: Vec<u8>,
+}
+
+/// Revokes participants of a dealer key file in one process: reshares the keys to the
+/// same threshold and participants without the revoked indices, saves them to a new key
+/// file and records the revocation signed by the new keys next to it.
+///
+/// # Arguments
+/// - `key_file`: Path to the dealer key file.
+/// - `signers`: Positions of the shares in the key file that deal, at least its threshold.
+/// - `revoked`: Indices of the participants to revoke.
+/// - `output_key_file`: Path to save the new keys to.
+/// - `options`: How the new key file and the revocation list are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the keys are not ristretto255 keys, a revoked participant is one
+/// of the signers, or fewer than the threshold participants would remain.
+pub fn revoke_keys(
+    key_file: &str,
+    signers: &[u32],
+    revoked: &[u32],
+    output_key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = info_span!("revoke_keys").entered();
+    // Step 1: Reshare the keys without the revoked shares, nor the ones revoked before.
+    let frost_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+    let participants = frost_keys
+        .private_shares
+        .iter()
+        .map(|(_, index)| *index)
+        .max()
+        .unwrap_or(0);
+    check_revoked(revoked, participants)?;
+    if let Some(&signer) = signers.iter().find(|&&signer| {
+        frost_keys
+            .private_shares
+            .get(signer as usize)
+            .is_some_and(|(_, index)| revoked.contains(index))
+    }) {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Signer {} holds a revoked share",
+            signer
+        ))
+        .into());
+    }
+    let mut excluded: Vec<u32> = (1..=participants)
+        .filter(|index| {
+            !frost_keys
+                .private_shares
+                .iter()
+                .any(|(_, share_index)| share_index == index)
+        })
+        .collect();
+    excluded.extend(revoked);
+    let threshold = frost_keys.threshold;
+    let revoked_keys = Locked::new(reshare_frost_keys_excluding(
+        &frost_keys,
+        signers,
+        threshold,
+        participants,
+        &excluded,
+        &mut OsRng,
+    )?)?;
+    write_checked_json(output_key_file, &*revoked_keys, options)?;
+
+    // Step 2: Sign the revocation with the new keys and record it.
+    let statement = statement_json(&revoked_keys.group_key, revoked)?;
+    let positions: Vec<u32> = (0..threshold).collect();
+    let signature = sign_with_keys(
+        statement.as_bytes(),
+        SIGNING_CONTEXT,
+        &revoked_keys,
+        &positions,
+    )?;
+    append_record(
+        output_key_file,
+        SignedRevocation {
+            statement,
+            signature: signature.to_vec(),
+        },
+        options,
+    )?;
+
+    report(
+        "participants_revoked",
+        &format!(
+            "Revoked participants {:?}. Keys saved to: {}",
+            revoked, output_key_file
+        ),
+        json!({
+            "key_file": output_key_file,
+            "group_key_fingerprint": envelope::fingerprint(&revoked_keys.group_key),
+            "revoked": revoked,
+        }),
+    );
+    Ok(())
+}
+
+/// Runs a dealer's round of a revocation: a reshare round to the dealer's threshold and
+/// participants that deals no shares to the revoked indices, see
+/// [`crate::reshare::round_one`].
+///
+/// # Arguments
+/// - `share_file`: Share location of the dealer's share, see [`crate::storage`].
+/// - `dealers`: Indices of all dealers, at least the threshold of them.
+/// - `revoked`: Indices of the participants to revoke.
+/// - `output_dir`: Directory to write the public `reshare_<index>.json` message and the
+///   secret shares for the remaining participants to.
+/// - `options`: How the round messages are written.
+///
+/// # Errors
+/// Returns an error if a revoked participant is one of the dealers, fewer than the
+/// threshold participants would remain, or the files cannot be written.
+pub fn round_one(
+    share_file: &str,
+    dealers: &[u32],
+    revoked: &[u32],
+    output_dir: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    round_one_with_rng(
+        share_file, dealers, revoked, output_dir, options, &mut OsRng,
+    )
+}
+
+/// Runs a dealer's round of a revocation like [`round_one`], drawing the polynomial from
+/// `rng`, see [`crate::SecureRng`].
+///
+/// # Errors
+/// Returns an error if a revoked participant is one of the dealers, fewer than the
+/// threshold participants would remain, or the files cannot be written.
+pub fn round_one_with_rng<R: RngCore + CryptoRng>(
+    share_file: &str,
+    dealers: &[u32],
+    revoked: &[u32],
+    output_dir: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check the revoked participants, adding the ones recorded before.
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    check_revoked(revoked, share.participants)?;
+    let mut excluded = revoked_indices(share_file, options)?;
+    excluded.extend(revoked);
+    excluded.sort_unstable();
+    excluded.dedup();
+    if let Some(dealer) = dealers.iter().find(|dealer| excluded.contains(dealer)) {
+        return Err(
+            FrostCliError::InvalidParameters(format!("Dealer {} is revoked", dealer)).into(),
+        );
+    }
+
+    // Step 2: Reshare to the same threshold and participants without them.
+    reshare::deal_share(
+        share_file,
+        dealers,
+        share.threshold,
+        share.participants,
+        &excluded,
+        output_dir,
+        options,
+        rng,
+    )
+}
+
+/// Writes the revocation statement for the group to sign.
+///
+/// # Arguments
+/// - `key_file`: Path to a key file or participant share file of the group.
+/// - `revoked`: Indices of the revoked participants.
+/// - `statement_file`: Path to save the statement to.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be read or the statement cannot be written.
+pub fn new_statement(
+    key_file: &str,
+    revoked: &[u32],
+    statement_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, group_key) = load_group_key_bytes(key_file, options)?;
+    let statement = statement_json(&group_key, revoked)?;
+    fs::write(statement_file, &statement).map_err(|err| FrostCliError::io(statement_file, err))?;
+
+    report(
+        "revocation_statement_written",
+        &format!(
+            "Revocation of participants {:?} saved to: {}. Sign its contents as the message.",
+            revoked, statement_file
+        ),
+        json!({
+            "statement_file": statement_file,
+            "statement": statement,
+            "revoked": revoked,
+        }),
+    );
+    Ok(())
+}
+
+/// Records a signed revocation statement next to the key file.
+///
+/// # Arguments
+/// - `key_file`: Path to a key file or participant share file of the group.
+/// - `statement_file`: Path to the statement written by [`new_statement`].
+/// - `signature_file`: Path to the group's signature on the statement.
+/// - `options`: How the revocation list is written.
+///
+/// # Errors
+/// Returns an error if the statement is for another group key, the signature does not
+/// verify, or the key is not kept in a file.
+pub fn record(
+    key_file: &str,
+    statement_file: &str,
+    signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check the statement and its signature against the group key.
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let statement =
+        fs::read_to_string(statement_file).map_err(|err| FrostCliError::io(statement_file, err))?;
+    let signature = read_signature(signature_file, None)?;
+    let signed = SignedRevocation {
+        statement,
+        signature: signature.to_vec(),
+    };
+    let revoked = check_record(&signed, ciphersuite, &group_key)?;
+
+    // Step 2: Append it to the records next to the key file.
+    let revocations_file = append_record(key_file, signed, options)?;
+
+    report(
+        "revocation_recorded",
+        &format!(
+            "Revocation of participants {:?} recorded in: {}",
+            revoked,
+            revocations_file.display()
+        ),
+        json!({
+            "revocations_file": revocations_file,
+            "revoked": revoked,
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the indices revoked by the records of the key's group next to a key file, or
+/// none if there are no records or the key is not kept in a file.
+///
+/// # Errors
+/// Returns an error if a record of the key's group does not verify.
+pub fn revoked_indices(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let Some(revocations_file) = revocations_file(key_file).filter(|file| file.exists()) else {
+        return Ok(Vec::new());
+    };
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let records: Vec<SignedRevocation> = read_json(&revocations_file)?;
+    let fingerprint = envelope::fingerprint(&group_key);
+    let mut revoked = Vec::new();
+    for record in &records {
+        // Records of other groups whose key files share the directory are skipped.
+        if parse_statement(record)?.group_key_fingerprint != fingerprint {
+            continue;
+        }
+        revoked.extend(check_record(record, ciphersuite, &group_key)?);
+    }
+    revoked.sort_unstable();
+    revoked.dedup();
+    Ok(revoked)
+}
+
+/// Returns the path of the revocation records next to a key file, or `None` if the key
+/// is not kept in a file.
+pub fn revocations_file(key_file: &str) -> Option<PathBuf> {
+    storage::is_file(key_file).then(|| Path::new(key_file).with_file_name(REVOCATIONS_FILE))
+}
+
+/// Returns the paths of the secret shares [`round_one`] writes for the participants that
+/// are not revoked.
+pub fn outgoing_share_files(dir: &str, index: u32, n: u32, revoked: &[u32]) -> Vec<PathBuf> {
+    (1..=n)
+        .filter(|receiver| !revoked.contains(receiver))
+        .map(|receiver| reshare::share_file_path(dir, index, receiver))
+        .collect()
+}
+
+/// Returns the statement revoking `revoked` from the group of `group_key`.
+fn statement_json(
+    group_key: &[u8; 32],
+    revoked: &[u32],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut revoked = revoked.to_vec();
+    revoked.sort_unstable();
+    revoked.dedup();
+    Ok(serde_json::to_string(&RevocationStatement {
+        group_key_fingerprint: envelope::fingerprint(group_key),
+        revoked,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    })?)
+}
+
+/// Checks a signed revocation against the group key and returns the revoked indices.
+fn check_record(
+    record: &SignedRevocation,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let statement = parse_statement(record)?;
+    if statement.group_key_fingerprint != envelope::fingerprint(group_key) {
+        return Err(InvalidSignature(format!(
+            "Revocation statement is for group key {}",
+            statement.group_key_fingerprint
+        ))
+        .into());
+    }
+    let signature: [u8; 64] = record
+        .signature
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature")?;
+    verify_with_group_key(
+        record.statement.as_bytes(),
+        SIGNING_CONTEXT,
+        ciphersuite,
+        group_key,
+        &signature,
+    )?;
+    Ok(statement.revoked)
+}
+
+/// Parses the statement of a signed revocation.
+fn parse_statement(
+    record: &SignedRevocation,
+) -> Result<RevocationStatement, Box<dyn std::error::Error>> {
+    serde_json::from_str(&record.statement)
+        .map_err(|err| format!("Invalid revocation statement: {}", err).into())
+}
+
+/// Appends a record to the revocations next to a key file and returns their path.
+fn append_record(
+    key_file: &str,
+    record: SignedRevocation,
+    options: &FileOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let revocations_file = revocations_file(key_file).ok_or_else(|| {
+        FrostCliError::InvalidParameters(format!(
+            "Revocations can only be recorded next to a key file, not {}",
+            key_file
+        ))
+    })?;
+    let mut records: Vec<SignedRevocation> = if revocations_file.exists() {
+        read_json(&revocations_file)?
+    } else {
+        Vec::new()
+    };
+    records.push(record);
+    write_json(&revocations_file, &records, options)?;
+    Ok(revocations_file)
+}
+
+/// Checks that `revoked` are distinct indices between 1 and `participants`.
+fn check_revoked(revoked: &[u32], participants: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let mut sorted = revoked.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.is_empty()
+        || sorted.len() != revoked.len()
+        || sorted.first() == Some(&0)
+        || sorted.last().is_some_and(|&last| last > participants)
+    {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Invalid revoked participants: {:?}",
+            revoked
+        ))
+        .into());
+    }
+    Ok(())
+}
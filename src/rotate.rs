@@ -0,0 +1,333 @@
+//! Rotating a group to a new group key.
+//!
+//! A new group, usually from a new DKG, takes over from an old one. The old group
+//! threshold-signs a [`RotationStatement`] binding its key to the new one, with the
+//! dates from which the new key is used and until which the old one is accepted. The
+//! signed statement is kept next to the new key file in `rotations.json`, after the
+//! statements that led to the old key, so anyone who trusts an earlier group key can
+//! follow the chain to the current one with [`history`] without trusting the new key
+//! out of band.
+//!
+//! [`rotate_keys`] generates new dealer keys and signs the statement with the old ones
+//! in one process, for example for two keys in the keystore. The two key files need
+//! directories of their own, as the keystore gives them. In a DKG group, the
+//! participants run a new DKG, write the statement with [`new_statement`], sign it with
+//! their old shares like any other message, and each records it with [`record`].
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use crate::io::files::{read_signature, write_checked_json, FileOptions};
+use crate::io::output::report;
+use crate::memlock::Locked;
+use crate::{
+    generate_frost_keys, load_frost_keys, load_group_key_bytes, read_json, sign_with_keys, storage,
+    verify_with_group_key, write_json, InvalidSignature, SIGNING_CONTEXT,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info_span;
+
+/// File name of the rotation records next to a key file.
+const ROTATIONS_FILE: &str = "rotations.json";
+
+/// The statement an old group signs to hand over to a new group key.
+///
+/// It is signed as its compact JSON, which is already in canonical form, so it verifies
+/// with and without `--canonicalize jcs`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RotationStatement {
+    /// Hex-encoded group key of the new group.
+    pub new_group_key: String,
+    /// Hex-encoded group key of the old group, which signs the statement.
+    pub old_group_key: String,
+    /// Unix time from which the new key is used.
+    pub valid_from: u64,
+    /// Unix time until which signatures by the old key are accepted, if limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<u64>,
+}
+
+/// A rotation statement with the old group's signature, as kept in `rotations.json`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SignedRotation {
+    /// The JSON of the signed [`RotationStatement`].
+    pub statement: String,
+    pub signature: Vec<u8>,
+}
+
+/// Rotates a dealer key file to new keys in one process: generates the new keys in the
+/// same ciphersuite, signs the rotation statement with the old keys and records it next
+/// to the new key file.
+///
+/// # Arguments
+/// - `key_file`: Path to the old dealer key file.
+/// - `signers`: Positions of the old shares that sign the statement.
+/// - `t`: Threshold of the new keys.
+/// - `n`: Number of participants of the new keys.
+/// - `new_key_file`: Path to save the new keys to.
+/// - `valid_until`: Unix time until which signatures by the old key are accepted.
+/// - `options`: How the new key file and the rotation history are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the new parameters or the signers are invalid, or the files cannot
+/// be written.
+pub fn rotate_keys(
+    key_file: &str,
+    signers: &[u32],
+    t: u32,
+    n: u32,
+    new_key_file: &str,
+    valid_until: Option<u64>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _span = info_span!("rotate_keys", t, n).entered();
+    // Step 1: Generate the new keys in the ciphersuite of the old ones
+    let old_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+    let new_keys = Locked::new(generate_frost_keys(t, n, old_keys.ciphersuite, &|_| {})?)?;
+    let new_group_key = new_keys.group_key;
+
+    // Step 2: Sign the statement with the old keys, then save the new keys and record it
+    let statement = statement_json(&old_keys.group_key, &new_group_key, valid_until)?;
+    let signature = sign_with_keys(statement.as_bytes(), SIGNING_CONTEXT, &old_keys, signers)?;
+    write_checked_json(new_key_file, &*new_keys, options)?;
+    let rotations_file = append_record(
+        key_file,
+        new_key_file,
+        SignedRotation {
+            statement,
+            signature: signature.to_vec(),
+        },
+        options,
+    )?;
+
+    report_recorded(&old_keys.group_key, &new_group_key, &rotations_file);
+    Ok(())
+}
+
+/// Writes the rotation statement for the old group to sign.
+///
+/// # Arguments
+/// - `key_file`: Path to a key file or participant share file of the old group.
+/// - `new_key_file`: Path to a key file or participant share file of the new group.
+/// - `valid_until`: Unix time until which signatures by the old key are accepted.
+/// - `statement_file`: Path to save the statement to.
+/// - `options`: File key the key files were written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if a key file cannot be read or the statement cannot be written.
+pub fn new_statement(
+    key_file: &str,
+    new_key_file: &str,
+    valid_until: Option<u64>,
+    statement_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, old_group_key) = load_group_key_bytes(key_file, options)?;
+    let (_, new_group_key) = load_group_key_bytes(new_key_file, options)?;
+    let statement = statement_json(&old_group_key, &new_group_key, valid_until)?;
+    fs::write(statement_file, &statement).map_err(|err| FrostCliError::io(statement_file, err))?;
+
+    report(
+        "rotation_statement_written",
+        &format!(
+            "Rotation from group key {} to {} saved to: {}. Sign its contents as the message \
+             with the old group.",
+            envelope::fingerprint(&old_group_key),
+            envelope::fingerprint(&new_group_key),
+            statement_file
+        ),
+        json!({
+            "statement_file": statement_file,
+            "statement": statement,
+        }),
+    );
+    Ok(())
+}
+
+/// Records a rotation statement signed by the old group next to the new key file, after
+/// the rotations recorded next to the old key file.
+///
+/// # Arguments
+/// - `key_file`: Path to a key file or participant share file of the old group.
+/// - `new_key_file`: Path to a key file or participant share file of the new group.
+/// - `statement_file`: Path to the statement written by [`new_statement`].
+/// - `signature_file`: Path to the old group's signature on the statement.
+/// - `options`: How the rotation history is written.
+///
+/// # Errors
+/// Returns an error if the statement names other group keys, the signature does not
+/// verify, or the new key is not kept in a file.
+pub fn record(
+    key_file: &str,
+    new_key_file: &str,
+    statement_file: &str,
+    signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Check the statement against both group keys and its signature
+    let (ciphersuite, old_group_key) = load_group_key_bytes(key_file, options)?;
+    let (_, new_group_key) = load_group_key_bytes(new_key_file, options)?;
+    let statement =
+        fs::read_to_string(statement_file).map_err(|err| FrostCliError::io(statement_file, err))?;
+    let signed = SignedRotation {
+        statement,
+        signature: read_signature(signature_file, None)?.to_vec(),
+    };
+    let parsed = check_record(&signed, ciphersuite)?;
+    if parsed.old_group_key != hex::encode(old_group_key)
+        || parsed.new_group_key != hex::encode(new_group_key)
+    {
+        return Err(InvalidSignature(
+            "Rotation statement names other group keys than the key files".into(),
+        )
+        .into());
+    }
+
+    // Step 2: Record it after the history of the old key
+    let rotations_file = append_record(key_file, new_key_file, signed, options)?;
+    report_recorded(&old_group_key, &new_group_key, &rotations_file);
+    Ok(())
+}
+
+/// Returns the rotations that led to the group key of a key file, oldest first, after
+/// checking every signature and that each one hands over to the next. The first
+/// statement's old group key is the key the history starts from.
+///
+/// # Errors
+/// Returns an error if a signature does not verify or the chain is broken.
+pub fn history(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<Vec<RotationStatement>, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let statements = check_chain(&load_records(key_file)?, ciphersuite)?;
+    if let Some(last) = statements.last() {
+        if last.new_group_key != hex::encode(group_key) {
+            return Err(InvalidSignature(format!(
+                "Rotation history ends at group key {}, not at the key file's",
+                last.new_group_key
+            ))
+            .into());
+        }
+    }
+    Ok(statements)
+}
+
+/// Returns the path of the rotation records next to a key file, or `None` if the key is
+/// not kept in a file.
+pub fn rotations_file(key_file: &str) -> Option<PathBuf> {
+    storage::is_file(key_file).then(|| Path::new(key_file).with_file_name(ROTATIONS_FILE))
+}
+
+/// Returns the statement handing over from `old_group_key` to `new_group_key`.
+fn statement_json(
+    old_group_key: &[u8; 32],
+    new_group_key: &[u8; 32],
+    valid_until: Option<u64>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(serde_json::to_string(&RotationStatement {
+        new_group_key: hex::encode(new_group_key),
+        old_group_key: hex::encode(old_group_key),
+        valid_from: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        valid_until,
+    })?)
+}
+
+/// Checks a signed rotation against the old group key it names and returns its
+/// statement.
+fn check_record(
+    record: &SignedRotation,
+    ciphersuite: Ciphersuite,
+) -> Result<RotationStatement, Box<dyn std::error::Error>> {
+    let statement: RotationStatement = serde_json::from_str(&record.statement)
+        .map_err(|err| format!("Invalid rotation statement: {}", err))?;
+    let old_group_key: [u8; 32] = hex::decode(&statement.old_group_key)?
+        .try_into()
+        .map_err(|_| "Group key must be 32 bytes")?;
+    let signature: [u8; 64] = record
+        .signature
+        .as_slice()
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature")?;
+    verify_with_group_key(
+        record.statement.as_bytes(),
+        SIGNING_CONTEXT,
+        ciphersuite,
+        &old_group_key,
+        &signature,
+    )?;
+    Ok(statement)
+}
+
+/// Checks every record of a chain and that each hands over to the next.
+fn check_chain(
+    records: &[SignedRotation],
+    ciphersuite: Ciphersuite,
+) -> Result<Vec<RotationStatement>, Box<dyn std::error::Error>> {
+    let statements = records
+        .iter()
+        .map(|record| check_record(record, ciphersuite))
+        .collect::<Result<Vec<_>, _>>()?;
+    for pair in statements.windows(2) {
+        if pair[0].new_group_key != pair[1].old_group_key {
+            return Err(InvalidSignature(format!(
+                "Rotation history is broken after group key {}",
+                pair[0].new_group_key
+            ))
+            .into());
+        }
+    }
+    Ok(statements)
+}
+
+/// Reads the rotation records next to a key file, or none if there are none.
+fn load_records(key_file: &str) -> Result<Vec<SignedRotation>, Box<dyn std::error::Error>> {
+    match rotations_file(key_file).filter(|file| file.exists()) {
+        Some(rotations_file) => read_json(&rotations_file),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Writes the history of the old key file followed by `record` next to the new key
+/// file, and returns the path it was written to.
+fn append_record(
+    key_file: &str,
+    new_key_file: &str,
+    record: SignedRotation,
+    options: &FileOptions,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let rotations_file = rotations_file(new_key_file).ok_or_else(|| {
+        FrostCliError::InvalidParameters(format!(
+            "Rotations can only be recorded next to a key file, not {}",
+            new_key_file
+        ))
+    })?;
+    let mut records = load_records(key_file)?;
+    records.push(record);
+    let (ciphersuite, _) = load_group_key_bytes(key_file, options)?;
+    check_chain(&records, ciphersuite)?;
+    write_json(&rotations_file, &records, options)?;
+    Ok(rotations_file)
+}
+
+/// Reports a recorded rotation.
+fn report_recorded(old_group_key: &[u8; 32], new_group_key: &[u8; 32], rotations_file: &Path) {
+    report(
+        "rotation_recorded",
+        &format!(
+            "Rotation from group key {} to {} recorded in: {}",
+            envelope::fingerprint(old_group_key),
+            envelope::fingerprint(new_group_key),
+            rotations_file.display()
+        ),
+        json!({
+            "old_group_key_fingerprint": envelope::fingerprint(old_group_key),
+            "new_group_key_fingerprint": envelope::fingerprint(new_group_key),
+            "rotations_file": rotations_file,
+        }),
+    );
+}
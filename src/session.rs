@@ -0,0 +1,585 @@
+//! Coordinator-side signing sessions persisted in a `session.json` manifest.
+//!
+//! Every session command loads the manifest, applies a single step and saves it
+//! again, so a coordinator that crashes half-way through a ceremony simply continues
+//! with the next command instead of starting over. Each session has a random ID that
+//! signers bind into their commitments and partial signatures, and round messages
+//! carrying any other ID are rejected.
+
+use crate::backend::Ciphersuite;
+use crate::echo::check_echoes;
+use crate::error::FrostCliError;
+use crate::identity::{sign_message_file, verify_message, IdentityFiles};
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::metrics;
+use crate::revoke::revoked_indices;
+use crate::signing::{
+    aggregate_signature, check_signer_set, partial_sign_with_roster, roster_record,
+    verify_partial_signature, PartialSignature, PublicCommitments,
+};
+use crate::transcript::{self, CeremonyEvent};
+use crate::types::SignatureMetadata;
+use crate::{load_key_file, read_json, save_signature_with_metadata, write_json, FileOptions};
+use frost_dalek::{compute_message_hash, GroupKey};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+
+/// Progress of a signing session.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    CollectingCommitments,
+    CollectingPartials,
+    Complete,
+}
+
+/// The `session.json` manifest of a signing session.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Session {
+    /// Random hex ID that every round message of this session must carry.
+    pub session_id: String,
+    pub status: SessionStatus,
+    /// The message to be signed, so signers can see what they are asked to sign.
+    #[serde(default)]
+    pub message: String,
+    pub message_hash: Vec<u8>,
+    /// Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+    pub context: String,
+    pub group_key: [u8; 32],
+    pub threshold: u32,
+    /// Verification share of every participant, which partial signatures are checked
+    /// against, see [`crate::FrostKeys::verification_shares`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_shares: Vec<[u8; 32]>,
+    /// Participant indices expected to sign, sorted.
+    pub roster: Vec<u32>,
+    pub commitments: Vec<PublicCommitments>,
+    pub partial_signatures: Vec<PartialSignature>,
+    pub signature: Option<Vec<u8>>,
+}
+
+impl Session {
+    /// Returns the roster members that have not yet sent what the current status needs.
+    pub fn missing_signers(&self) -> Vec<u32> {
+        let received: Vec<u32> = match self.status {
+            SessionStatus::CollectingCommitments => {
+                self.commitments.iter().map(|c| c.index).collect()
+            }
+            SessionStatus::CollectingPartials => {
+                self.partial_signatures.iter().map(|p| p.index).collect()
+            }
+            SessionStatus::Complete => return Vec::new(),
+        };
+        self.roster
+            .iter()
+            .copied()
+            .filter(|index| !received.contains(index))
+            .collect()
+    }
+
+    fn message_hash(&self) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+        self.message_hash
+            .as_slice()
+            .try_into()
+            .map_err(|_| "Invalid message hash in session".into())
+    }
+
+    /// Starts a new session with a random ID.
+    ///
+    /// # Arguments
+    /// - `message`: The message to be signed.
+    /// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+    /// - `key_file`: Path to a key file or participant share file of the group.
+    /// - `signers`: Participant indices expected to sign, any threshold or more of them.
+    ///   A participant given twice is only expected once.
+    /// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+    ///
+    /// # Errors
+    /// Returns an error if fewer signers than the threshold, participant 0 or a revoked
+    /// signer are given, see [`crate::revoke`].
+    pub fn new(
+        message: &str,
+        context: &str,
+        key_file: &str,
+        signers: Vec<u32>,
+        options: &FileOptions,
+    ) -> Result<Session, Box<dyn std::error::Error>> {
+        // Step 1: Load the group parameters and the revoked participants
+        let revoked = revoked_indices(key_file, options)?;
+        let key_file = load_key_file(key_file, options)?;
+
+        // Step 2: Check the roster against the threshold and the revocations
+        let mut roster = signers;
+        roster.sort_unstable();
+        roster.dedup();
+        check_signer_set(&roster, key_file.threshold())?;
+        if let Some(signer) = roster.iter().find(|signer| revoked.contains(signer)) {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Participant {} has been revoked",
+                signer
+            ))
+            .into());
+        }
+
+        // Step 3: Pick a random session ID
+        let mut session_id = [0u8; 16];
+        OsRng.fill_bytes(&mut session_id);
+        metrics::ceremony_started();
+
+        Ok(Session {
+            session_id: hex::encode(session_id),
+            status: SessionStatus::CollectingCommitments,
+            message: message.to_string(),
+            message_hash: compute_message_hash(context.as_bytes(), message.as_bytes()).to_vec(),
+            context: context.to_string(),
+            group_key: key_file.group_key(),
+            threshold: key_file.threshold(),
+            verification_shares: key_file.verification_shares().to_vec(),
+            roster,
+            commitments: Vec::new(),
+            partial_signatures: Vec::new(),
+            signature: None,
+        })
+    }
+
+    /// Adds a signer's public commitments and records them in `transcript_file`, if one
+    /// is given, see [`crate::transcript`].
+    ///
+    /// Once every signer has committed, the session moves on to collecting partial
+    /// signatures.
+    ///
+    /// # Errors
+    /// Returns an error if the session is not collecting commitments, the signer is not
+    /// part of the roster, or the signer already committed.
+    pub fn add_commitments(
+        &mut self,
+        commitments: PublicCommitments,
+        transcript_file: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.status != SessionStatus::CollectingCommitments {
+            return Err("Session is not collecting commitments".into());
+        }
+
+        // Step 1: Check the session ID and the signer
+        if commitments.session_id != self.session_id {
+            return Err(format!(
+                "Commitments belong to session {}, expected {}",
+                commitments.session_id, self.session_id
+            )
+            .into());
+        }
+        if !self.roster.contains(&commitments.index) {
+            return Err(format!(
+                "Participant {} is not part of the roster",
+                commitments.index
+            )
+            .into());
+        }
+        if self
+            .commitments
+            .iter()
+            .any(|c| c.index == commitments.index)
+        {
+            return Err(
+                format!("Participant {} already sent commitments", commitments.index).into(),
+            );
+        }
+        if commitments.commitments.is_empty() {
+            return Err(
+                format!("Participant {} published no commitments", commitments.index).into(),
+            );
+        }
+
+        // Step 2: Record the commitments and advance the session
+        transcript::append(
+            transcript_file,
+            roster_record(
+                CeremonyEvent::CommitmentsAdded,
+                &self.group_key,
+                self.threshold,
+                std::slice::from_ref(&commitments),
+                0,
+                &self.message_hash()?,
+            ),
+        )?;
+        report(
+            "commitments_added",
+            &format!(
+                "Commitments of participant {} added to the session",
+                commitments.index
+            ),
+            json!({ "session_id": self.session_id, "index": commitments.index }),
+        );
+        self.commitments.push(commitments);
+        self.commitments.sort_by_key(|c| c.index);
+        if self.missing_signers().is_empty() {
+            self.status = SessionStatus::CollectingPartials;
+            report(
+                "commitments_complete",
+                "All commitments received, the session is ready for partial signatures",
+                json!({ "session_id": self.session_id, "signers": self.roster }),
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds a signer's partial signature and records it in `transcript_file`, if one is
+    /// given, see [`crate::transcript`].
+    ///
+    /// Once every signer has sent a partial signature, they are aggregated and the
+    /// session completes with the threshold signature, which is recorded as well.
+    ///
+    /// # Errors
+    /// Returns an error if the session is not collecting partial signatures, the signer
+    /// is not part of the roster, or the aggregated signature is invalid, and a
+    /// [`FrostCliError::Aggregation`] naming the signer if their partial signature does
+    /// not verify.
+    pub fn add_partial_signature(
+        &mut self,
+        partial: PartialSignature,
+        transcript_file: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.status != SessionStatus::CollectingPartials {
+            return Err("Session is not collecting partial signatures".into());
+        }
+
+        // Step 1: Check the session ID and the signer
+        if partial.session_id != self.session_id {
+            return Err(format!(
+                "Partial signature belongs to session {}, expected {}",
+                partial.session_id, self.session_id
+            )
+            .into());
+        }
+        if !self.roster.contains(&partial.index) {
+            return Err(format!("Participant {} is not part of the roster", partial.index).into());
+        }
+        if self
+            .partial_signatures
+            .iter()
+            .any(|p| p.index == partial.index)
+        {
+            return Err(format!(
+                "Participant {} already sent a partial signature",
+                partial.index
+            )
+            .into());
+        }
+
+        // Step 2: Check the partial signature on its own, so a signer who sent an
+        // invalid one is named right away and can be left out of a new session
+        metrics::partial_signature_received();
+        let group_key =
+            GroupKey::from_bytes(self.group_key).map_err(|_| "Invalid group public key")?;
+        verify_partial_signature(
+            &self.message_hash()?,
+            &group_key,
+            &self.commitments,
+            &self.verification_shares,
+            &partial,
+            0,
+        )?;
+
+        // Step 3: Record the partial signature
+        let mut record = roster_record(
+            CeremonyEvent::PartialSignatureAdded,
+            &self.group_key,
+            self.threshold,
+            &self.commitments,
+            0,
+            &self.message_hash()?,
+        );
+        record.signers = vec![partial.index];
+        record
+            .commitments
+            .retain(|commitment| commitment.index == partial.index);
+        transcript::append(transcript_file, record)?;
+        report(
+            "partial_signature_added",
+            &format!(
+                "Partial signature of participant {} added to the session",
+                partial.index
+            ),
+            json!({ "session_id": self.session_id, "index": partial.index }),
+        );
+        self.partial_signatures.push(partial);
+        self.partial_signatures.sort_by_key(|p| p.index);
+
+        // Step 4: Aggregate once every signer has sent their partial signature, and
+        // record the outcome in the transcript
+        if self.missing_signers().is_empty() {
+            let message_hash = self.message_hash()?;
+            let outcome = aggregate_signature(
+                &message_hash,
+                &group_key,
+                &self.commitments,
+                &self.verification_shares,
+                &self.partial_signatures,
+                0,
+            )
+            .map(|signature| signature.to_bytes());
+            transcript::append_outcome(
+                transcript_file,
+                roster_record(
+                    CeremonyEvent::Signed,
+                    &self.group_key,
+                    self.threshold,
+                    &self.commitments,
+                    0,
+                    &message_hash,
+                ),
+                &outcome,
+            )?;
+            match outcome {
+                Ok(_) => metrics::ceremony_completed(),
+                Err(_) => metrics::ceremony_failed(),
+            }
+            self.signature = Some(outcome?.to_vec());
+            self.status = SessionStatus::Complete;
+        }
+        Ok(())
+    }
+}
+
+/// Creates a new signing session.
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to a key file or participant share file of the group.
+/// - `signers`: Participant indices expected to sign.
+/// - `session_file`: Path to save the session manifest.
+/// - `options`: How the key file is read and the session manifest is written, see
+///   [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if fewer signers than the threshold are given.
+pub fn new_session(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    signers: Vec<u32>,
+    session_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = Session::new(message, context, key_file, signers, options)?;
+    save_session(session_file, &session, options)?;
+
+    report(
+        "session_created",
+        &format!(
+            "Signing session {} created with signers {:?}. Session saved to: {}",
+            session.session_id, session.roster, session_file
+        ),
+        json!({
+            "session_id": session.session_id,
+            "signers": session.roster,
+            "session_file": session_file,
+        }),
+    );
+    Ok(())
+}
+
+/// Adds a signer's public commitments to a session manifest, recording them in
+/// `transcript_file` if one is given.
+///
+/// # Errors
+/// Returns an error if the commitments are rejected, see [`Session::add_commitments`].
+pub fn add_commitments(
+    session_file: &str,
+    commitments_file: &str,
+    transcript_file: Option<&str>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut session = load_session(session_file)?;
+    session.add_commitments(read_json(commitments_file)?, transcript_file)?;
+    save_session(session_file, &session, options)
+}
+
+/// Adds a signer's partial signature to a session manifest, recording it in
+/// `transcript_file` if one is given.
+///
+/// Once the session completes, the threshold signature is saved to `signature_file`.
+///
+/// # Errors
+/// Returns an error if the partial signature is rejected, see
+/// [`Session::add_partial_signature`].
+pub fn add_partial_signature(
+    session_file: &str,
+    partial_signature_file: &str,
+    signature_file: &str,
+    transcript_file: Option<&str>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut session = load_session(session_file)?;
+    session.add_partial_signature(read_json(partial_signature_file)?, transcript_file)?;
+    if let Some(signature) = &session.signature {
+        let metadata = SignatureMetadata::new(
+            Ciphersuite::Ristretto255,
+            &session.context,
+            &session.group_key,
+            session.roster.clone(),
+        )?;
+        save_signature_with_metadata(
+            signature_file,
+            signature,
+            &metadata,
+            SignatureFormat::Json,
+            options.format,
+        )?;
+        report(
+            "signature_saved",
+            &format!("Threshold signature saved to: {}", signature_file),
+            json!({
+                "session_id": session.session_id,
+                "signature_file": signature_file,
+                "signers": session.roster,
+            }),
+        );
+    }
+    save_session(session_file, &session, options)
+}
+
+/// Runs signing round two for a participant using the roster recorded in a session.
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures, or `message`
+/// and `context` are not the message and context the session was created for.
+pub fn partial_sign(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    session_file: &str,
+    partial_signature_file: &str,
+    identity: Option<&IdentityFiles>,
+    echo_files: &[String],
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = load_session(session_file)?;
+    partial_sign_with_session(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        &session,
+        partial_signature_file,
+        identity,
+        echo_files,
+        options,
+    )
+}
+
+/// Runs signing round two for a participant against an already loaded session.
+///
+/// With `identity`, every signer's commitments must carry a valid identity signature
+/// and the partial signature is signed with the participant's own identity. With
+/// `echo_files`, every other signer must have echoed the same roster, see
+/// [`check_echoes`].
+///
+/// # Errors
+/// Returns an error if the session is not collecting partial signatures, `message`
+/// and `context` are not the message and context the session was created for, a
+/// signer's commitments were not signed by their identity, or another signer saw a
+/// different roster.
+pub fn partial_sign_with_session(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    session: &Session,
+    partial_signature_file: &str,
+    identity: Option<&IdentityFiles>,
+    echo_files: &[String],
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if session.status != SessionStatus::CollectingPartials {
+        return Err("Session is not collecting partial signatures".into());
+    }
+    if session.context != context {
+        return Err(format!(
+            "Session signs in context {:?}, expected {:?}",
+            session.context, context
+        )
+        .into());
+    }
+    if compute_message_hash(context.as_bytes(), message.as_bytes()) != session.message_hash()? {
+        return Err("Message does not match the session".into());
+    }
+    if let Some(identity) = identity {
+        for commitments in &session.commitments {
+            verify_message(&identity.identities_dir, commitments)?;
+        }
+    }
+    if !echo_files.is_empty() {
+        check_echoes(
+            message,
+            context,
+            share_file,
+            &session.commitments,
+            echo_files,
+            identity.map(|identity| identity.identities_dir.as_str()),
+            options,
+        )?;
+    }
+
+    partial_sign_with_roster(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        &session.commitments,
+        0,
+        partial_signature_file,
+        options,
+    )?;
+    if let Some(identity) = identity {
+        sign_message_file(&identity.identity_file, partial_signature_file)?;
+    }
+    Ok(())
+}
+
+/// Prints the status of a session and the signers it is still waiting for.
+pub fn print_status(session_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let session = load_session(session_file)?;
+    let mut message = format!(
+        "Session: {}\nStatus: {:?}\nSigners: {:?}",
+        session.session_id, session.status, session.roster
+    );
+    let missing = session.missing_signers();
+    if session.status != SessionStatus::Complete {
+        message.push_str(&format!("\nWaiting for: {:?}", missing));
+    }
+    report(
+        "session_status",
+        &message,
+        json!({
+            "session_id": session.session_id,
+            "status": session.status,
+            "signers": session.roster,
+            "missing_signers": missing,
+        }),
+    );
+    Ok(())
+}
+
+/// Loads a session manifest.
+pub fn load_session(session_file: &str) -> Result<Session, Box<dyn std::error::Error>> {
+    read_json(session_file)
+}
+
+/// Saves a session manifest without ever leaving a half-written file behind.
+pub fn save_session(
+    session_file: &str,
+    session: &Session,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let temp_file = format!("{}.tmp", session_file);
+    write_json(&temp_file, session, options)?;
+    fs::rename(&temp_file, session_file)?;
+    Ok(())
+}
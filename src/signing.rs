@@ -0,0 +1,2001 @@
+//! Threshold signing, in one process holding the signers' shares or in two rounds run
+//! by separate participants.
+//!
+//! [`sign_message`], [`sign_file`] and [`sign_message_with_shares`] run both rounds for
+//! all signers at once, from a key file or participant share files, and
+//! [`sign_with_keys`] from keys held in memory.
+//!
+//! In two rounds, each signer runs [`commit`] to publish fresh signing commitments while keeping the
+//! matching nonces on their own machine, then [`partial_sign`] once the coordinator has
+//! announced the signer roster. The coordinator finally combines the partial signatures
+//! with [`aggregate`] into a regular `ThresholdSignature`. Every message carries the
+//! coordinator's session ID, which is also hashed into the binding factors, so round
+//! messages from one signing attempt cannot be replayed into another. Like the DKG
+//! module, this works directly over the Ristretto group because frost-dalek does not
+//! expose a way to persist secret commitment shares or partial signatures between
+//! processes.
+
+use crate::artifact::SignedFile;
+use crate::backend::{self, Ciphersuite};
+use crate::error::FrostCliError;
+use crate::identity::IdentitySignature;
+use crate::io;
+use crate::io::envelope::{self, EnvelopeOptions, OutFormat};
+use crate::io::files::{
+    load_frost_keys, load_group_key, load_group_key_bytes, load_key_file, load_participant_share,
+    read_json, save_signature_with_metadata, write_json, write_secret_json, FileOptions,
+};
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::keystore::KeyStore;
+use crate::memlock::Locked;
+use crate::metrics::{self, Verification};
+use crate::nonces;
+use crate::transcript::{self, CeremonyEvent, CeremonyRecord, CommitmentHash};
+use crate::types::{
+    point_from_bytes, scalar_from_bytes, FrostKeys, ParticipantId, ParticipantShare, SecureRng,
+    SignatureMetadata,
+};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use frost_dalek::signature::SecretKey as SignatureSecretKey;
+use frost_dalek::signature::ThresholdSignature;
+use frost_dalek::{
+    compute_message_hash, generate_commitment_share_lists, GroupKey, IndividualPublicKey,
+    Parameters, SignatureAggregator,
+};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Public signing commitments a participant publishes for the coordinator.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PublicCommitments {
+    pub session_id: String,
+    pub index: u32,
+    /// The participant's public key share, used to check their partial signature.
+    pub public_key: [u8; 32],
+    /// Hiding and binding commitment pairs.
+    pub commitments: Vec<([u8; 32], [u8; 32])>,
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Secret nonces behind a participant's published commitments.
+///
+/// Must never leave the participant's machine, and each nonce pair may only be
+/// used for a single signature.
+#[derive(Serialize, Deserialize)]
+pub struct SecretCommitments {
+    pub session_id: String,
+    pub index: u32,
+    /// Hiding and binding nonce pairs, in the same order as the public commitments.
+    pub nonces: Vec<([u8; 32], [u8; 32])>,
+}
+
+impl Drop for SecretCommitments {
+    fn drop(&mut self) {
+        for (hiding, binding) in &mut self.nonces {
+            hiding.zeroize();
+            binding.zeroize();
+        }
+    }
+}
+
+/// Secret nonces a participant precomputed with [`generate_commitments`], taken in order
+/// by [`commit_from_pool`].
+///
+/// Must never leave the participant's machine.
+#[derive(Serialize, Deserialize)]
+pub struct CommitmentPool {
+    pub index: u32,
+    /// Pool index of the first unused nonce pair. Used ones are removed from `nonces`.
+    pub next: u32,
+    /// Unused hiding and binding nonce pairs, starting at `next`.
+    pub nonces: Vec<([u8; 32], [u8; 32])>,
+}
+
+impl Drop for CommitmentPool {
+    fn drop(&mut self) {
+        for (hiding, binding) in &mut self.nonces {
+            hiding.zeroize();
+            binding.zeroize();
+        }
+    }
+}
+
+/// A signer's partial signature, sent to the coordinator for aggregation.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PartialSignature {
+    pub session_id: String,
+    pub index: u32,
+    pub z: [u8; 32],
+    /// Signature by the sender's identity, see [`crate::identity`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_signature: Option<IdentitySignature>,
+}
+
+/// Options of signing round one, see [`commit`].
+pub struct CommitOptions<'a> {
+    /// Number of nonce pairs to commit to, so that the session can sign `count`
+    /// messages, each with the commitments at its own index, see [`partial_sign_at`].
+    pub count: u32,
+    /// Generator to draw the nonces from, see [`crate::SecureRng`], or `None` for the
+    /// operating system's.
+    pub rng: Option<&'a mut dyn SecureRng>,
+    /// How the commitment files are written, ignored by [`commit_with_share`].
+    pub files: FileOptions,
+}
+
+impl Default for CommitOptions<'_> {
+    fn default() -> Self {
+        CommitOptions {
+            count: 1,
+            rng: None,
+            files: FileOptions::default(),
+        }
+    }
+}
+
+/// Runs signing round one for a single participant. The nonces are recorded as unused
+/// in the nonce state next to the secret commitments file, see [`crate::nonces`].
+///
+/// # Arguments
+/// - `session_id`: ID of the signing session announced by the coordinator.
+/// - `share_file`: Path to the participant's share file.
+/// - `public_commitments_file`: Path to save the public commitments for the coordinator.
+/// - `secret_commitments_file`: Path to save the secret nonces locally.
+/// - `options`: How many nonce pairs to commit to, where to draw them from and how the
+///   files are written.
+///
+/// # Errors
+/// Returns an error if the count is zero, the share cannot be loaded or the files cannot
+/// be written.
+pub fn commit(
+    session_id: &str,
+    share_file: &str,
+    public_commitments_file: &str,
+    secret_commitments_file: &str,
+    options: CommitOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the participant's share
+    let share = Locked::new(load_participant_share(share_file, &options.files)?)?;
+
+    // Step 2: Generate the hiding and binding nonces and commit to them
+    let count = options.count;
+    let files = options.files.clone();
+    let (public_commitments, secret_commitments) = commit_with_share(session_id, &share, options)?;
+
+    // Step 3: Record the nonces as unused, save them locally and publish the commitments
+    let ids: Vec<String> = public_commitments
+        .commitments
+        .iter()
+        .map(nonces::commitment_id)
+        .collect();
+    nonces::record(secret_commitments_file, &ids)?;
+    write_secret_json(secret_commitments_file, &*secret_commitments, &files)?;
+    write_json(public_commitments_file, &public_commitments, &files)?;
+
+    report(
+        "commitments_saved",
+        &format!(
+            "{} commitments for participant {} saved to: {}",
+            count, share.index, public_commitments_file
+        ),
+        json!({
+            "index": share.index,
+            "session_id": session_id,
+            "commitments_file": public_commitments_file,
+            "count": count,
+        }),
+    );
+    Ok(())
+}
+
+/// Generates hiding and binding nonce pairs for a share and commits to them, without
+/// touching any file, see [`commit`].
+///
+/// # Errors
+/// Returns an error if the count is zero, the share is not a valid scalar or the memory
+/// cannot be locked.
+pub fn commit_with_share(
+    session_id: &str,
+    share: &ParticipantShare,
+    options: CommitOptions,
+) -> Result<(PublicCommitments, Locked<SecretCommitments>), Box<dyn std::error::Error>> {
+    if options.count == 0 {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of commitments must be at least 1".into(),
+        )
+        .into());
+    }
+    let mut os_rng = OsRng;
+    let mut rng: &mut dyn SecureRng = match options.rng {
+        Some(rng) => rng,
+        None => &mut os_rng,
+    };
+    let mut nonce_pairs = Vec::new();
+    for _ in 0..options.count {
+        nonce_pairs.push((
+            Zeroizing::new(Scalar::random(&mut rng)),
+            Zeroizing::new(Scalar::random(&mut rng)),
+        ));
+    }
+    commit_with_nonces(session_id, share, &nonce_pairs)
+}
+
+/// Commits to the given hiding and binding nonce pairs for a share.
+fn commit_with_nonces(
+    session_id: &str,
+    share: &ParticipantShare,
+    nonce_pairs: &[(Zeroizing<Scalar>, Zeroizing<Scalar>)],
+) -> Result<(PublicCommitments, Locked<SecretCommitments>), Box<dyn std::error::Error>> {
+    let secret_key = Zeroizing::new(scalar_from_bytes(share.share)?);
+    let public_commitments = PublicCommitments {
+        session_id: session_id.to_string(),
+        index: share.index,
+        public_key: (&RISTRETTO_BASEPOINT_TABLE * &*secret_key)
+            .compress()
+            .to_bytes(),
+        commitments: nonce_pairs
+            .iter()
+            .map(|(hiding, binding)| {
+                (
+                    (&RISTRETTO_BASEPOINT_TABLE * &**hiding)
+                        .compress()
+                        .to_bytes(),
+                    (&RISTRETTO_BASEPOINT_TABLE * &**binding)
+                        .compress()
+                        .to_bytes(),
+                )
+            })
+            .collect(),
+        identity_signature: None,
+    };
+    let secret_commitments = Locked::new(SecretCommitments {
+        session_id: session_id.to_string(),
+        index: share.index,
+        nonces: nonce_pairs
+            .iter()
+            .map(|(hiding, binding)| (hiding.to_bytes(), binding.to_bytes()))
+            .collect(),
+    })?;
+    Ok((public_commitments, secret_commitments))
+}
+
+/// Precomputes a pool of nonce pairs for a participant, so that [`commit_from_pool`]
+/// only has to take the next unused one. Nonces are added to an existing pool of the
+/// same participant.
+///
+/// # Arguments
+/// - `share_file`: Path to the participant's share file.
+/// - `count`: Number of nonce pairs to add.
+/// - `pool_file`: Path to the participant's commitment pool, kept locally.
+/// - `options`: How the pool is written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the count is zero, the pool belongs to another participant, or
+/// the files cannot be read or written.
+pub fn generate_commitments(
+    share_file: &str,
+    count: u32,
+    pool_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    generate_commitments_with_rng(share_file, count, pool_file, options, &mut OsRng)
+}
+
+/// Precomputes a pool of nonce pairs like [`generate_commitments`], drawing the nonces
+/// from `rng`.
+///
+/// # Errors
+/// Returns an error if the count is zero, the pool belongs to another participant, or
+/// the files cannot be read or written.
+pub fn generate_commitments_with_rng<R: RngCore + CryptoRng>(
+    share_file: &str,
+    count: u32,
+    pool_file: &str,
+    options: &FileOptions,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the participant's share and any existing pool
+    if count == 0 {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of commitments must be at least 1".into(),
+        )
+        .into());
+    }
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let existing = if Path::new(pool_file).exists() {
+        Some(load_commitment_pool(pool_file)?)
+    } else {
+        None
+    };
+    if let Some(existing) = existing.as_ref().filter(|pool| pool.index != share.index) {
+        return Err(format!(
+            "Commitment pool belongs to participant {}, not {}",
+            existing.index, share.index
+        )
+        .into());
+    }
+
+    // Step 2: Add the new nonce pairs and save the pool
+    let mut pool_nonces = existing
+        .as_ref()
+        .map(|pool| pool.nonces.clone())
+        .unwrap_or_default();
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        let hiding = Zeroizing::new(Scalar::random(rng));
+        let binding = Zeroizing::new(Scalar::random(rng));
+        ids.push(nonces::commitment_id(&(
+            (&RISTRETTO_BASEPOINT_TABLE * &*hiding)
+                .compress()
+                .to_bytes(),
+            (&RISTRETTO_BASEPOINT_TABLE * &*binding)
+                .compress()
+                .to_bytes(),
+        )));
+        pool_nonces.push((hiding.to_bytes(), binding.to_bytes()));
+    }
+    let pool = Locked::new(CommitmentPool {
+        index: share.index,
+        next: existing.as_ref().map_or(0, |pool| pool.next),
+        nonces: pool_nonces,
+    })?;
+    nonces::record(pool_file, &ids)?;
+    write_secret_json(pool_file, &*pool, options)?;
+
+    report(
+        "commitment_pool_saved",
+        &format!(
+            "{} commitments for participant {} saved to: {}, {} unused",
+            count,
+            share.index,
+            pool_file,
+            pool.nonces.len()
+        ),
+        json!({
+            "index": share.index,
+            "pool_file": pool_file,
+            "next": pool.next,
+            "unused": pool.nonces.len(),
+        }),
+    );
+    Ok(())
+}
+
+/// Loads a commitment pool saved by [`generate_commitments`], locked in memory if
+/// enabled, see [`crate::memlock`].
+///
+/// # Errors
+/// Returns an error if the file cannot be read or the memory cannot be locked.
+pub fn load_commitment_pool(
+    pool_file: &str,
+) -> Result<Locked<CommitmentPool>, Box<dyn std::error::Error>> {
+    Locked::new(read_json(pool_file)?)
+}
+
+/// Runs signing round one like [`commit`] with the next unused nonce pair of the
+/// participant's commitment pool instead of fresh nonces.
+///
+/// The nonce pair is removed from the pool before the commitments are written, so it
+/// can never be used for a second session.
+///
+/// # Arguments
+/// - `session_id`: ID of the signing session announced by the coordinator.
+/// - `share_file`: Path to the participant's share file.
+/// - `pool_file`: Path to the pool saved by [`generate_commitments`].
+/// - `public_commitments_file`: Path to save the public commitments for the coordinator.
+/// - `secret_commitments_file`: Path to save the secret nonces locally.
+/// - `options`: How the pool and the commitments are written, see [`FileOptions`].
+///
+/// # Errors
+/// Returns an error if the pool is used up or belongs to another participant, or the
+/// files cannot be read or written.
+pub fn commit_from_pool(
+    session_id: &str,
+    share_file: &str,
+    pool_file: &str,
+    public_commitments_file: &str,
+    secret_commitments_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the participant's share and take the next unused nonce pair
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let pool = load_commitment_pool(pool_file)?;
+    if pool.index != share.index {
+        return Err(format!(
+            "Commitment pool belongs to participant {}, not {}",
+            pool.index, share.index
+        )
+        .into());
+    }
+    let (hiding_bytes, binding_bytes) = pool
+        .nonces
+        .first()
+        .ok_or("No unused commitments left, run commitments generate again")?;
+    let nonce_pair = (
+        Zeroizing::new(scalar_from_bytes(*hiding_bytes)?),
+        Zeroizing::new(scalar_from_bytes(*binding_bytes)?),
+    );
+    let (public_commitments, secret_commitments) =
+        commit_with_nonces(session_id, &share, &[nonce_pair])?;
+
+    // Step 2: Burn the nonce pair in the pool before anything is released
+    let id = nonces::commitment_id(&public_commitments.commitments[0]);
+    nonces::take(pool_file, &id)?;
+    let remaining = Locked::new(CommitmentPool {
+        index: pool.index,
+        next: pool.next + 1,
+        nonces: pool.nonces[1..].to_vec(),
+    })?;
+    write_secret_json(pool_file, &*remaining, options)?;
+
+    // Step 3: Record the nonces as unused, save them locally and publish the commitments
+    nonces::record(secret_commitments_file, &[id])?;
+    write_secret_json(secret_commitments_file, &*secret_commitments, options)?;
+    write_json(public_commitments_file, &public_commitments, options)?;
+
+    report(
+        "commitments_saved",
+        &format!(
+            "Commitments {} of participant {} saved to: {}, {} unused left in the pool",
+            pool.next,
+            share.index,
+            public_commitments_file,
+            remaining.nonces.len()
+        ),
+        json!({
+            "index": share.index,
+            "session_id": session_id,
+            "commitments_file": public_commitments_file,
+            "pool_index": pool.next,
+            "unused": remaining.nonces.len(),
+        }),
+    );
+    Ok(())
+}
+
+/// Loads the secret nonces saved by [`commit`], locked in memory if enabled, see
+/// [`crate::memlock`].
+///
+/// # Errors
+/// Returns an error if the file cannot be read or the memory cannot be locked.
+pub fn load_secret_commitments(
+    secret_commitments_file: &str,
+) -> Result<Locked<SecretCommitments>, Box<dyn std::error::Error>> {
+    Locked::new(read_json(secret_commitments_file)?)
+}
+
+/// Runs signing round two for a single participant.
+///
+/// The used nonces are removed from the secret commitments file and marked as used in
+/// its nonce state before the partial signature is written, so they can never be used
+/// for a second message, see [`crate::nonces`].
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `share_file`: Path to the participant's share file.
+/// - `secret_commitments_file`: Path to the secret nonces saved by [`commit`].
+/// - `commitment_files`: Paths to the public commitments of every signer, including this one.
+/// - `partial_signature_file`: Path to save the partial signature for the coordinator.
+/// - `options`: How the partial signature and the remaining nonces are written.
+///
+/// # Errors
+/// Returns an error if this participant is not part of the roster, the roster is smaller
+/// than the threshold, or the secret commitments do not match the published ones.
+pub fn partial_sign(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    commitment_files: &[String],
+    partial_signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    partial_sign_at(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        commitment_files,
+        0,
+        partial_signature_file,
+        options,
+    )
+}
+
+/// Runs signing round two like [`partial_sign`] with the commitments at
+/// `commitment_index` of every signer, for sessions committed with
+/// [`CommitOptions::count`]. Every signer and the coordinator must use the same index.
+///
+/// # Errors
+/// Returns an error if a signer published no commitments at the index, this
+/// participant's nonces at the index were already used, or [`partial_sign`] fails.
+#[allow(clippy::too_many_arguments)]
+pub fn partial_sign_at(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    commitment_files: &[String],
+    commitment_index: u32,
+    partial_signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let roster = load_roster(commitment_files)?;
+    partial_sign_with_roster(
+        message,
+        context,
+        share_file,
+        secret_commitments_file,
+        &roster,
+        commitment_index,
+        partial_signature_file,
+        options,
+    )
+}
+
+/// Runs signing round two against an already loaded signer roster.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn partial_sign_with_roster(
+    message: &str,
+    context: &str,
+    share_file: &str,
+    secret_commitments_file: &str,
+    roster: &[PublicCommitments],
+    commitment_index: u32,
+    partial_signature_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the participant's share and secret nonces
+    let share = Locked::new(load_participant_share(share_file, options)?)?;
+    let secret_commitments = load_secret_commitments(secret_commitments_file)?;
+
+    // Step 2: Compute the partial signature with the nonces at the index
+    let (partial_signature, remaining) = partial_sign_with_share_at(
+        message,
+        context,
+        &share,
+        &secret_commitments,
+        roster,
+        commitment_index,
+    )?;
+
+    // Step 3: Burn the nonces in the nonce state and the file before anything is released
+    let commitment = roster
+        .iter()
+        .find(|signer| signer.index == share.index)
+        .and_then(|signer| signer.commitments.get(commitment_index as usize))
+        .ok_or("This participant is not part of the signer roster")?;
+    nonces::consume(secret_commitments_file, &nonces::commitment_id(commitment))?;
+    write_secret_json(secret_commitments_file, &remaining, options)?;
+
+    // Step 4: Save the partial signature
+    write_json(partial_signature_file, &partial_signature, options)?;
+
+    report(
+        "partial_signature_saved",
+        &format!(
+            "Partial signature of participant {} saved to: {}",
+            share.index, partial_signature_file
+        ),
+        json!({
+            "index": share.index,
+            "session_id": secret_commitments.session_id,
+            "partial_signature_file": partial_signature_file,
+        }),
+    );
+    Ok(())
+}
+
+/// Runs signing round two for a share against a loaded signer roster, without touching
+/// any file, see [`partial_sign`].
+///
+/// Returns the partial signature and the secret commitments without the nonces it used,
+/// which must replace the caller's secret commitments before the partial signature is
+/// released.
+///
+/// # Errors
+/// Returns an error if this participant is not part of the roster, the roster is smaller
+/// than the threshold or names a participant twice, or the secret commitments do not
+/// match the published ones.
+pub fn partial_sign_with_share(
+    message: &str,
+    context: &str,
+    share: &ParticipantShare,
+    secret_commitments: &SecretCommitments,
+    roster: &[PublicCommitments],
+) -> Result<(PartialSignature, SecretCommitments), Box<dyn std::error::Error>> {
+    partial_sign_with_share_at(message, context, share, secret_commitments, roster, 0)
+}
+
+/// Runs signing round two for a share like [`partial_sign_with_share`] with the
+/// commitments at `commitment_index`, see [`partial_sign_at`].
+///
+/// The used nonces are wiped in the returned secret commitments, so the other indices
+/// keep their place, and wiped nonces at the end are dropped.
+///
+/// # Errors
+/// Returns an error if the nonces at the index are missing or were already used, or
+/// [`partial_sign_with_share`] fails.
+pub fn partial_sign_with_share_at(
+    message: &str,
+    context: &str,
+    share: &ParticipantShare,
+    secret_commitments: &SecretCommitments,
+    roster: &[PublicCommitments],
+    commitment_index: u32,
+) -> Result<(PartialSignature, SecretCommitments), Box<dyn std::error::Error>> {
+    // Step 1: Take the unused nonces at the index
+    let secret_key = Zeroizing::new(scalar_from_bytes(share.share)?);
+    if secret_commitments.index != share.index {
+        return Err("Secret commitments belong to a different participant".into());
+    }
+    let (hiding_bytes, binding_bytes) = secret_commitments
+        .nonces
+        .get(commitment_index as usize)
+        .ok_or("No unused commitments left, run commit again")?;
+    if *hiding_bytes == [0; 32] && *binding_bytes == [0; 32] {
+        return Err(format!(
+            "Commitments at index {} were already used",
+            commitment_index
+        )
+        .into());
+    }
+    let hiding = Zeroizing::new(scalar_from_bytes(*hiding_bytes)?);
+    let binding = Zeroizing::new(scalar_from_bytes(*binding_bytes)?);
+
+    // Step 2: Find ourselves in the signer roster
+    let indices: Vec<u32> = roster.iter().map(|signer| signer.index).collect();
+    check_signer_set(&indices, share.threshold)?;
+    let position = roster
+        .iter()
+        .position(|signer| signer.index == share.index)
+        .ok_or("This participant is not part of the signer roster")?;
+    let expected = (
+        (&RISTRETTO_BASEPOINT_TABLE * &*hiding)
+            .compress()
+            .to_bytes(),
+        (&RISTRETTO_BASEPOINT_TABLE * &*binding)
+            .compress()
+            .to_bytes(),
+    );
+    if roster[position].commitments.get(commitment_index as usize) != Some(&expected) {
+        return Err("Published commitments do not match the secret commitments file".into());
+    }
+    check_session_id(&secret_commitments.session_id, roster, &[])?;
+
+    // Step 3: Hash the message and derive the group commitment and challenge
+    let message_hash = compute_message_hash(context.as_bytes(), message.as_bytes());
+    let (binding_factors, group_commitment) = binding_factors_and_group_commitment(
+        &secret_commitments.session_id,
+        &message_hash,
+        roster,
+        commitment_index,
+    )?;
+    let challenge = compute_challenge(&message_hash, &share.group_key, &group_commitment);
+    let lambda = lagrange_coefficient(share.index, &indices);
+
+    // Step 4: Compute the partial signature, wiping the used nonces in the remaining ones
+    let mut nonce_pairs = secret_commitments.nonces.clone();
+    nonce_pairs[commitment_index as usize] = ([0; 32], [0; 32]);
+    while nonce_pairs.last() == Some(&([0; 32], [0; 32])) {
+        nonce_pairs.pop();
+    }
+    let remaining = SecretCommitments {
+        session_id: secret_commitments.session_id.clone(),
+        index: secret_commitments.index,
+        nonces: nonce_pairs,
+    };
+    let z = *hiding + *binding * binding_factors[position] + lambda * *secret_key * challenge;
+    let partial_signature = PartialSignature {
+        session_id: secret_commitments.session_id.clone(),
+        index: share.index,
+        z: z.to_bytes(),
+        identity_signature: None,
+    };
+    Ok((partial_signature, remaining))
+}
+
+/// Combines the partial signatures of all signers into a threshold signature, without
+/// recording it in a transcript, see [`aggregate_at`].
+///
+/// # Arguments
+/// - `message`: The message that was signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to a key file or participant share file containing the group key.
+/// - `commitment_files`: Paths to the public commitments of every signer.
+/// - `partial_signatures_dir`: Directory containing one partial signature file per signer.
+/// - `signature_file`: Path to save the threshold signature.
+///
+/// # Errors
+/// Returns an error if a signer's partial signature is missing or the aggregated
+/// signature does not verify under the group key, and a [`FrostCliError::Aggregation`]
+/// naming every signer whose partial signature does not verify against their
+/// verification share and commitments.
+pub fn aggregate(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    commitment_files: &[String],
+    partial_signatures_dir: &str,
+    signature_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    aggregate_at(
+        message,
+        context,
+        key_file,
+        commitment_files,
+        partial_signatures_dir,
+        0,
+        signature_file,
+        SignatureFormat::Json,
+        &FileOptions::default(),
+        None,
+    )
+}
+
+/// Combines the partial signatures of all signers like [`aggregate`], for partial
+/// signatures made with the commitments at `commitment_index`, see [`partial_sign_at`],
+/// saves the signature in `sig_format`, see [`io::signature`], a `json` one written as
+/// `options` says, and records the outcome in `transcript_file`, see
+/// [`crate::transcript`].
+///
+/// # Errors
+/// Returns an error if a signer published no commitments at the index, or [`aggregate`]
+/// fails.
+#[allow(clippy::too_many_arguments)]
+pub fn aggregate_at(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    commitment_files: &[String],
+    partial_signatures_dir: &str,
+    commitment_index: u32,
+    signature_file: &str,
+    sig_format: SignatureFormat,
+    options: &FileOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the group key, the verification shares, the roster, and the partial
+    // signatures
+    let group_key = load_group_key(key_file, options)?;
+    let keys = load_key_file(key_file, options)?;
+    let roster = load_roster(commitment_files)?;
+    let mut partial_signatures: Vec<PartialSignature> = Vec::new();
+    for entry in fs::read_dir(partial_signatures_dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            partial_signatures.push(read_json(&path)?);
+        }
+    }
+    partial_signatures.sort_by_key(|partial| partial.index);
+
+    // Step 2: Combine the partial signatures and record the outcome in the transcript
+    let message_hash = compute_message_hash(context.as_bytes(), message.as_bytes());
+    let outcome = aggregate_signature(
+        &message_hash,
+        &group_key,
+        &roster,
+        keys.verification_shares(),
+        &partial_signatures,
+        commitment_index,
+    )
+    .map(|signature| signature.to_bytes());
+    transcript::append_outcome(
+        transcript_file,
+        roster_record(
+            CeremonyEvent::Signed,
+            &group_key.to_bytes(),
+            keys.threshold(),
+            &roster,
+            commitment_index,
+            &message_hash,
+        ),
+        &outcome,
+    )?;
+    let signature = outcome?;
+
+    // Step 3: Save the signature as a JSON file with the signers that took part
+    let metadata = SignatureMetadata::new(
+        Ciphersuite::Ristretto255,
+        context,
+        &group_key.to_bytes(),
+        partial_signatures
+            .iter()
+            .map(|partial| partial.index)
+            .collect(),
+    )?;
+    save_signature_with_metadata(
+        signature_file,
+        &signature,
+        &metadata,
+        sig_format,
+        options.format,
+    )?;
+
+    report(
+        "signature_saved",
+        &format!("Threshold signature saved to: {}", signature_file),
+        json!({
+            "signature_file": signature_file,
+            "group_key_fingerprint": metadata.group_key_fingerprint,
+            "signers": metadata.signers,
+        }),
+    );
+    Ok(())
+}
+
+/// Combines partial signatures into a threshold signature and verifies the result.
+///
+/// `partial_signatures` must be sorted by participant index, and are checked against
+/// the group's `verification_shares`, see [`FrostKeys::verification_shares`].
+pub(crate) fn aggregate_signature(
+    message_hash: &[u8; 64],
+    group_key: &GroupKey,
+    roster: &[PublicCommitments],
+    verification_shares: &[[u8; 32]],
+    partial_signatures: &[PartialSignature],
+    commitment_index: u32,
+) -> Result<ThresholdSignature, Box<dyn std::error::Error>> {
+    // Step 1: Check that every signer in the roster sent exactly one partial signature
+    let roster_indices: Vec<u32> = roster.iter().map(|signer| signer.index).collect();
+    let partial_indices: Vec<u32> = partial_signatures.iter().map(|p| p.index).collect();
+    if let Some(pair) = partial_indices.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(FrostCliError::Aggregation {
+            signers: vec![pair[0]],
+            reason: format!(
+                "Participant {} sent more than one partial signature",
+                pair[0]
+            ),
+        }
+        .into());
+    }
+    if roster_indices != partial_indices {
+        let mut signers: Vec<u32> = roster_indices
+            .iter()
+            .filter(|index| !partial_indices.contains(index))
+            .chain(
+                partial_indices
+                    .iter()
+                    .filter(|index| !roster_indices.contains(index)),
+            )
+            .copied()
+            .collect();
+        signers.sort_unstable();
+        signers.dedup();
+        return Err(FrostCliError::Aggregation {
+            signers,
+            reason: format!(
+                "Expected partial signatures from signers {:?}, found {:?}",
+                roster_indices, partial_indices
+            ),
+        }
+        .into());
+    }
+
+    // Step 2: Reject round messages from any other session
+    let session_id = &roster
+        .first()
+        .ok_or("The signer roster is empty")?
+        .session_id;
+    check_session_id(session_id, roster, partial_signatures)?;
+
+    // Step 3: Recompute the group commitment and check every partial signature on its
+    // own, so all signers who sent an invalid one are named
+    let (binding_factors, group_commitment) =
+        binding_factors_and_group_commitment(session_id, message_hash, roster, commitment_index)?;
+    let challenge = compute_challenge(message_hash, &group_key.to_bytes(), &group_commitment);
+    let mut invalid = Vec::new();
+    for ((signer, partial), binding_factor) in
+        roster.iter().zip(partial_signatures).zip(&binding_factors)
+    {
+        let lambda = lagrange_coefficient(signer.index, &roster_indices);
+        if let Err(reason) = check_partial_signature(
+            signer,
+            verification_shares,
+            partial,
+            commitment_index,
+            binding_factor,
+            &(lambda * challenge),
+        ) {
+            invalid.push((signer.index, reason));
+        }
+    }
+    if !invalid.is_empty() {
+        return Err(invalid_partial_signatures(&invalid).into());
+    }
+
+    // Step 4: Sum the partial signatures
+    let mut z = Scalar::zero();
+    for partial in partial_signatures {
+        z += scalar_from_bytes(partial.z)?;
+    }
+
+    // Step 5: Assemble and verify the threshold signature
+    let mut signature_bytes = [0u8; 64];
+    signature_bytes[..32].copy_from_slice(group_commitment.compress().as_bytes());
+    signature_bytes[32..].copy_from_slice(z.as_bytes());
+    let threshold_signature = ThresholdSignature::from_bytes(signature_bytes)
+        .map_err(|_| "Failed to deserialize ThresholdSignature")?;
+    threshold_signature
+        .verify(group_key, message_hash)
+        .map_err(|_| FrostCliError::Aggregation {
+            signers: roster_indices,
+            reason: "Aggregated signature is invalid although every partial signature \
+                     verifies, so the public keys they were checked against do not match the \
+                     group key"
+                .into(),
+        })?;
+
+    Ok(threshold_signature)
+}
+
+/// Returns the transcript record of a ceremony step over the commitments of `roster` at
+/// `commitment_index`, see [`crate::transcript`].
+pub(crate) fn roster_record(
+    event: CeremonyEvent,
+    group_key: &[u8; 32],
+    threshold: u32,
+    roster: &[PublicCommitments],
+    commitment_index: u32,
+    message_hash: &[u8; 64],
+) -> CeremonyRecord {
+    let mut record = CeremonyRecord::new(
+        event,
+        Ciphersuite::Ristretto255,
+        group_key,
+        threshold,
+        roster.iter().map(|signer| signer.index).collect(),
+        message_hash,
+    );
+    record.session_id = roster.first().map(|signer| signer.session_id.clone());
+    record.commitments = roster
+        .iter()
+        .filter_map(|signer| {
+            let commitment = signer.commitments.get(commitment_index as usize)?;
+            Some(CommitmentHash {
+                index: signer.index,
+                hash: nonces::commitment_id(commitment),
+            })
+        })
+        .collect();
+    record
+}
+
+/// Checks a single partial signature against its signer's verification share and
+/// commitments, as soon as it arrives and before the other signers have sent theirs.
+///
+/// # Arguments
+/// - `message_hash`: Hash of the signed message in the signing context.
+/// - `group_key`: The group public key.
+/// - `roster`: Public commitments of every signer, sorted by participant index.
+/// - `verification_shares`: Verification share of every participant, see
+///   [`FrostKeys::verification_shares`].
+/// - `partial`: The partial signature to check.
+/// - `commitment_index`: Index of the commitments the signers signed with.
+///
+/// # Errors
+/// Returns a [`FrostCliError::Aggregation`] naming the signer if the partial signature
+/// is malformed or does not verify, or an error if the signer is not in the roster.
+pub fn verify_partial_signature(
+    message_hash: &[u8; 64],
+    group_key: &GroupKey,
+    roster: &[PublicCommitments],
+    verification_shares: &[[u8; 32]],
+    partial: &PartialSignature,
+    commitment_index: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let position = roster
+        .iter()
+        .position(|signer| signer.index == partial.index)
+        .ok_or_else(|| format!("Participant {} is not part of the roster", partial.index))?;
+    let session_id = &roster[position].session_id;
+    let (binding_factors, group_commitment) =
+        binding_factors_and_group_commitment(session_id, message_hash, roster, commitment_index)?;
+    let challenge = compute_challenge(message_hash, &group_key.to_bytes(), &group_commitment);
+    let indices: Vec<u32> = roster.iter().map(|signer| signer.index).collect();
+    let lambda = lagrange_coefficient(partial.index, &indices);
+    let checked = check_partial_signature(
+        &roster[position],
+        verification_shares,
+        partial,
+        commitment_index,
+        &binding_factors[position],
+        &(lambda * challenge),
+    );
+    metrics::verification(Verification::PartialSignature, checked.is_ok());
+    checked.map_err(|reason| invalid_partial_signatures(&[(partial.index, reason)]))?;
+    Ok(())
+}
+
+/// Checks `g^z = D + E * binding_factor + Y * lambda_challenge` for a signer's partial
+/// signature `z`, hiding and binding commitments `D` and `E` and verification share `Y`,
+/// and returns what is wrong with it otherwise.
+fn check_partial_signature(
+    signer: &PublicCommitments,
+    verification_shares: &[[u8; 32]],
+    partial: &PartialSignature,
+    commitment_index: u32,
+    binding_factor: &Scalar,
+    lambda_challenge: &Scalar,
+) -> Result<(), String> {
+    let z = scalar_from_bytes(partial.z).map_err(|_| "sent a malformed partial signature")?;
+    let public_key = match verification_share(verification_shares, signer.index)? {
+        Some(public_key) => public_key,
+        None => {
+            warn!(
+                index = signer.index,
+                "No verification shares stored with the keys, checking the partial signature \
+                 against the public key the signer published"
+            );
+            point_from_bytes(&signer.public_key).map_err(|_| "published an invalid public key")?
+        }
+    };
+    let (hiding, binding) = signer
+        .commitments
+        .get(commitment_index as usize)
+        .ok_or("published no commitments at the index")?;
+    let hiding = point_from_bytes(hiding).map_err(|_| "published invalid commitments")?;
+    let binding = point_from_bytes(binding).map_err(|_| "published invalid commitments")?;
+    if &RISTRETTO_BASEPOINT_TABLE * &z
+        != hiding + binding * binding_factor + public_key * lambda_challenge
+    {
+        return Err("sent a partial signature that does not verify".into());
+    }
+    Ok(())
+}
+
+/// Returns participant `index`'s verification share, see
+/// [`FrostKeys::verification_shares`], or `None` for keys saved before verification
+/// shares were stored. The verification share of a participant enrolled after the keys
+/// were saved is interpolated from the stored ones, which all lie on one polynomial.
+fn verification_share(
+    verification_shares: &[[u8; 32]],
+    index: u32,
+) -> Result<Option<RistrettoPoint>, String> {
+    let invalid = |_| "has an invalid verification share".to_string();
+    if verification_shares.is_empty() {
+        return Ok(None);
+    }
+    if index == 0 {
+        return Err("is not a participant of the group".into());
+    }
+    if let Some(share) = verification_shares.get(index as usize - 1) {
+        return point_from_bytes(share).map(Some).map_err(invalid);
+    }
+    let base: Vec<u32> = (1..=verification_shares.len() as u32).collect();
+    let mut share = RistrettoPoint::identity();
+    for (&other, other_share) in base.iter().zip(verification_shares) {
+        share += point_from_bytes(other_share).map_err(invalid)? * lagrange_at(other, &base, index);
+    }
+    Ok(Some(share))
+}
+
+/// Returns a [`FrostCliError::Aggregation`] error naming every signer whose partial
+/// signature is invalid, and what is wrong with each.
+fn invalid_partial_signatures(invalid: &[(u32, String)]) -> FrostCliError {
+    FrostCliError::Aggregation {
+        signers: invalid.iter().map(|(index, _)| *index).collect(),
+        reason: invalid
+            .iter()
+            .map(|(index, reason)| format!("participant {} {}", index, reason))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+/// Loads the public commitments of every signer, sorted by participant index.
+///
+/// # Errors
+/// Returns an error if a commitment file cannot be loaded or has no commitments, or two
+/// files hold commitments of the same participant.
+pub fn load_roster(
+    commitment_files: &[String],
+) -> Result<Vec<PublicCommitments>, Box<dyn std::error::Error>> {
+    let mut roster = Vec::new();
+    for commitment_file in commitment_files {
+        let signer: PublicCommitments = read_json(commitment_file)?;
+        if signer.commitments.is_empty() {
+            return Err(format!("Participant {} published no commitments", signer.index).into());
+        }
+        if roster
+            .iter()
+            .any(|other: &PublicCommitments| other.index == signer.index)
+        {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Participant {} published commitments more than once",
+                signer.index
+            ))
+            .into());
+        }
+        roster.push(signer);
+    }
+    roster.sort_by_key(|signer| signer.index);
+    Ok(roster)
+}
+
+/// Returns the participant indices of the signers in the given commitment files, sorted.
+///
+/// # Errors
+/// Returns an error if a commitment file cannot be loaded.
+pub fn roster_indices(commitment_files: &[String]) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    Ok(load_roster(commitment_files)?
+        .iter()
+        .map(|signer| signer.index)
+        .collect())
+}
+
+/// Checks that all commitments and partial signatures belong to `session_id`.
+fn check_session_id(
+    session_id: &str,
+    roster: &[PublicCommitments],
+    partial_signatures: &[PartialSignature],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let foreign = roster
+        .iter()
+        .map(|signer| (signer.index, &signer.session_id))
+        .chain(partial_signatures.iter().map(|p| (p.index, &p.session_id)))
+        .find(|(_, id)| id.as_str() != session_id);
+    match foreign {
+        Some((index, id)) => Err(format!(
+            "Round message of participant {} belongs to session {}, expected {}",
+            index, id, session_id
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// Computes every signer's binding factor and the group commitment `R` from the
+/// commitments at `commitment_index`.
+///
+/// The binding factors are returned in roster order.
+fn binding_factors_and_group_commitment(
+    session_id: &str,
+    message_hash: &[u8; 64],
+    roster: &[PublicCommitments],
+    commitment_index: u32,
+) -> Result<(Vec<Scalar>, RistrettoPoint), Box<dyn std::error::Error>> {
+    let commitments = roster
+        .iter()
+        .map(|signer| {
+            signer
+                .commitments
+                .get(commitment_index as usize)
+                .copied()
+                .ok_or_else(|| {
+                    format!(
+                        "Participant {} published no commitments at index {}",
+                        signer.index, commitment_index
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut h = Sha512::new();
+    h.update(b"FROST-SHA512");
+    h.update(session_id.as_bytes());
+    h.update(&message_hash[..]);
+    for (signer, &(hiding, binding)) in roster.iter().zip(&commitments) {
+        h.update(signer.index.to_be_bytes());
+        h.update(hiding);
+        h.update(binding);
+    }
+
+    let mut binding_factors = Vec::with_capacity(roster.len());
+    let mut group_commitment = RistrettoPoint::identity();
+    for (signer, &(hiding, binding)) in roster.iter().zip(&commitments) {
+        let mut h1 = h.clone();
+        h1.update(b"FROST-SHA512");
+        h1.update(signer.index.to_be_bytes());
+        let binding_factor = Scalar::from_hash(h1);
+
+        group_commitment +=
+            point_from_bytes(&hiding)? + point_from_bytes(&binding)? * binding_factor;
+        binding_factors.push(binding_factor);
+    }
+    Ok((binding_factors, group_commitment))
+}
+
+/// Computes the Schnorr challenge the same way frost-dalek does, so that the aggregated
+/// signature verifies with `ThresholdSignature::verify`.
+fn compute_challenge(
+    message_hash: &[u8; 64],
+    group_key: &[u8; 32],
+    group_commitment: &RistrettoPoint,
+) -> Scalar {
+    let mut h = Sha512::new();
+    h.update(b"FROST-SHA512");
+    h.update(group_commitment.compress().as_bytes());
+    h.update(group_key);
+    h.update(&message_hash[..]);
+    Scalar::from_hash(h)
+}
+
+/// Computes the Lagrange coefficient of `index` for interpolating at zero over `indices`.
+pub(crate) fn lagrange_coefficient(index: u32, indices: &[u32]) -> Scalar {
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for &other in indices.iter().filter(|&&other| other != index) {
+        numerator *= Scalar::from(other);
+        denominator *= Scalar::from(other) - Scalar::from(index);
+    }
+    numerator * denominator.invert()
+}
+
+/// Computes the Lagrange coefficient of `index` for interpolating at `x` over `indices`.
+pub(crate) fn lagrange_at(index: u32, indices: &[u32], x: u32) -> Scalar {
+    let x = Scalar::from(x);
+    let mut numerator = Scalar::one();
+    let mut denominator = Scalar::one();
+    for &other in indices.iter().filter(|&&other| other != index) {
+        numerator *= x - Scalar::from(other);
+        denominator *= Scalar::from(index) - Scalar::from(other);
+    }
+    numerator * denominator.invert()
+}
+
+/// Signs a message using threshold signing, without recording it in a transcript, see
+/// [`sign_message_as`].
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `signers`: Positions of the signers' shares in the key file, from 0, see
+///   [`signer_positions`] to translate participant IDs. Any threshold or more of the
+///   participants can sign, each of them once.
+/// - `n`: The total number of participants.
+/// - `key_file`: Path to the file containing the generated keys.
+/// - `signature_file`: Path to save the generated signature.
+///
+/// # Errors
+/// Returns an error if loading keys, generating commitment shares, or signing fails.
+pub fn sign_message(
+    message: &str,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sign_message_as(
+        message,
+        context,
+        signers,
+        n,
+        key_file,
+        signature_file,
+        OutFormat::Signature,
+        &EnvelopeOptions::default(),
+        None,
+    )
+}
+
+/// Signs a message using threshold signing and saves it in the given output format,
+/// see [`sign_message`] and [`io::envelope`]. `options` are the envelope's parameters,
+/// such as the namespace of an SSH signature, and the ceremony is recorded in
+/// `transcript_file`, see [`crate::transcript`].
+///
+/// # Errors
+/// Returns an error if the output format cannot carry signatures of the key file's
+/// ciphersuite, or signing fails.
+pub fn sign_message_as(
+    message: &str,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+    out_format: OutFormat,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sign_message_as_with_rng(
+        message,
+        context,
+        signers,
+        n,
+        key_file,
+        signature_file,
+        out_format,
+        options,
+        transcript_file,
+        &mut OsRng,
+    )
+}
+
+/// Signs a message like [`sign_message_as`], drawing the signing nonces from `rng`, see
+/// [`crate::SecureRng`].
+///
+/// **Never sign two messages with nonces drawn from generators seeded the same way,**
+/// such as [`crate::seeded_rng`] with the same seed: anyone who sees both signatures can
+/// compute the signers' shares.
+///
+/// # Errors
+/// Returns an error if the output format cannot carry signatures of the key file's
+/// ciphersuite, or signing fails.
+pub fn sign_message_as_with_rng(
+    message: &str,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+    out_format: OutFormat,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+    rng: &mut dyn SecureRng,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let message = io::jcs::canonicalize_as(message, options.canonicalization)?;
+    let (_, group_key) = load_group_key_bytes(key_file, &options.files)?;
+    let bytes = seal_message(
+        &message,
+        context,
+        signers.clone(),
+        n,
+        key_file,
+        out_format,
+        options,
+        transcript_file,
+        rng,
+    )?;
+    fs::write(signature_file, bytes)?;
+
+    report(
+        "signature_saved",
+        &format!("Threshold signature saved to: {}", signature_file),
+        json!({
+            "signature_file": signature_file,
+            "group_key_fingerprint": envelope::fingerprint(&group_key),
+            "signers": signers,
+        }),
+    );
+    Ok(())
+}
+
+/// Signs a message using threshold signing and returns it in the given output format,
+/// drawing the signing nonces from `rng`, see [`sign_message_as_with_rng`].
+pub(crate) fn seal_message(
+    message: &[u8],
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    out_format: OutFormat,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+    mut rng: &mut dyn SecureRng,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let frost_keys = load_signing_keys(&signers, n, key_file, &options.files)?;
+    let backend = backend::open(frost_keys.ciphersuite)?;
+
+    // A plain signature file records which participants signed
+    if out_format == OutFormat::Signature {
+        let metadata = SignatureMetadata::new(
+            frost_keys.ciphersuite,
+            context,
+            &frost_keys.group_key,
+            signers
+                .iter()
+                .map(|&signer| frost_keys.private_shares[signer as usize].1)
+                .collect(),
+        )?;
+        let signature = sign_with_keys_and_rng(
+            message,
+            context,
+            &frost_keys,
+            &signers,
+            transcript_file,
+            &mut rng,
+        )?;
+        return io::signature::encode_with_metadata(
+            &signature,
+            Some(&metadata),
+            options.signature_format,
+            options.files.format,
+        );
+    }
+
+    // Sign the message in its envelope with the backend of the key file's ciphersuite
+    io::envelope::seal(
+        out_format,
+        frost_keys.ciphersuite,
+        &frost_keys.group_key,
+        message,
+        options,
+        |signing_input| {
+            backend.sign(
+                &frost_keys,
+                &signers,
+                signing_input,
+                context,
+                transcript_file,
+                rng,
+            )
+        },
+    )
+}
+
+/// Loads the keys of a key file and checks that `signers` can sign with them.
+pub(crate) fn load_signing_keys(
+    signers: &[u32],
+    n: u32,
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<Locked<FrostKeys>, Box<dyn std::error::Error>> {
+    // Step 1: Load keys from file
+    let frost_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+
+    // Step 2: Check if the number of participants matches the key file
+    if frost_keys.private_shares.len() != n as usize {
+        return Err(FrostCliError::InvalidParameters(
+            "Number of participants does not match the key file".into(),
+        )
+        .into());
+    }
+
+    // Step 3: Check that the signers can sign with the keys
+    check_signers(&frost_keys, signers)?;
+    Ok(frost_keys)
+}
+
+/// Loads the keys of a key file and returns the positions of the given participants'
+/// shares in it, which the signing functions take. `options` holds the file key the key
+/// file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded, or a participant has no share in
+/// it or is given twice.
+pub fn signer_positions(
+    key_file: &str,
+    signers: &[ParticipantId],
+    options: &FileOptions,
+) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let frost_keys = Locked::new(load_frost_keys(key_file, options)?)?;
+    Ok(frost_keys.positions(signers)?)
+}
+
+/// Checks that `signers` are at least a threshold of valid positions in the keys.
+fn check_signers(
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Ensure all specified signers are valid positions, each given once
+    for (i, &signer) in signers.iter().enumerate() {
+        if signer as usize >= frost_keys.private_shares.len() {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Invalid signer position: {}, positions start at 0 and the key file holds {} \
+                 shares",
+                signer,
+                frost_keys.private_shares.len()
+            ))
+            .into());
+        }
+        if signers[..i].contains(&signer) {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Signer position {} is given twice",
+                signer
+            ))
+            .into());
+        }
+    }
+
+    // Step 2: Check the participants at those positions as a signer set
+    let indices: Vec<u32> = signers
+        .iter()
+        .map(|&signer| frost_keys.private_shares[signer as usize].1)
+        .collect();
+    Ok(check_signer_set(&indices, frost_keys.threshold)?)
+}
+
+/// Checks a set of signers by participant index before any signing starts: any `threshold`
+/// or more distinct participants can sign together, but none of them twice.
+pub(crate) fn check_signer_set(indices: &[u32], threshold: u32) -> Result<(), FrostCliError> {
+    for (i, index) in indices.iter().enumerate() {
+        if *index == 0 {
+            return Err(FrostCliError::InvalidParameters(
+                "Participant IDs start at 1, there is no participant 0".into(),
+            ));
+        }
+        if indices[..i].contains(index) {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Participant {} is among the signers more than once",
+                index
+            )));
+        }
+    }
+    if indices.len() < threshold as usize {
+        return Err(FrostCliError::InvalidParameters(format!(
+            "Number of signers is less than the threshold: {} signers for a threshold of {}",
+            indices.len(),
+            threshold
+        )));
+    }
+    Ok(())
+}
+
+/// Signs a message with the shares at the given positions of `frost_keys` and returns
+/// the 64-byte threshold signature, without reading or writing any file. The signing
+/// nonces are drawn from the operating system and the ceremony is not recorded in a
+/// transcript, see [`sign_with_keys_and_rng`].
+///
+/// The message is signed as it is, not canonicalized as by [`sign_message`]. A
+/// Ristretto255 signature is the encoding of a [`ThresholdSignature`], see
+/// [`ThresholdSignature::from_bytes`].
+///
+/// # Arguments
+/// - `message`: The bytes to sign.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `frost_keys`: The keys, for example from [`crate::generate_frost_keys`].
+/// - `signers`: Positions of the signers' shares in `frost_keys`.
+///
+/// # Errors
+/// Returns an error if fewer signers than the threshold or an invalid position are
+/// given, the ciphersuite is not included in this build, or signing fails.
+pub fn sign_with_keys(
+    message: &[u8],
+    context: &str,
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+    sign_with_keys_and_rng(message, context, frost_keys, signers, None, &mut OsRng)
+}
+
+/// Signs a message like [`sign_with_keys`], drawing the signing nonces from `rng` and
+/// recording the ceremony in `transcript_file`, see [`crate::transcript`].
+///
+/// # Errors
+/// Returns an error if fewer signers than the threshold or an invalid position are
+/// given, the ciphersuite is not included in this build, or signing fails.
+pub fn sign_with_keys_and_rng<R: RngCore + CryptoRng>(
+    message: &[u8],
+    context: &str,
+    frost_keys: &FrostKeys,
+    signers: &[u32],
+    transcript_file: Option<&str>,
+    rng: &mut R,
+) -> Result<[u8; 64], Box<dyn std::error::Error>> {
+    check_signers(frost_keys, signers)?;
+    backend::open(frost_keys.ciphersuite)?
+        .sign(frost_keys, signers, message, context, transcript_file, rng)?
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature".into())
+}
+
+/// Signs a message using the participant share files of the chosen signers.
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `share_files`: Paths to the `ParticipantShare` files of the signers.
+/// - `signature_file`: Path to save the generated signature.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+///
+/// # Errors
+/// Returns an error if the shares belong to different groups, fewer shares than the
+/// threshold are given, or signing fails.
+pub fn sign_message_with_shares(
+    message: &str,
+    context: &str,
+    share_files: &[String],
+    signature_file: &str,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    sign_message_with_shares_as(
+        message,
+        context,
+        share_files,
+        signature_file,
+        &EnvelopeOptions::default(),
+        transcript_file,
+    )
+}
+
+/// Signs a message using participant share files like [`sign_message_with_shares`],
+/// saving the signature as `options` say, see [`EnvelopeOptions::signature_format`].
+///
+/// # Errors
+/// Returns an error if [`sign_message_with_shares`] fails.
+pub fn sign_message_with_shares_as(
+    message: &str,
+    context: &str,
+    share_files: &[String],
+    signature_file: &str,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut shares = Vec::new();
+    for share_file in share_files {
+        shares.push(Locked::new(load_participant_share(
+            share_file,
+            &options.files,
+        )?)?);
+    }
+    sign_message_with_loaded_shares(
+        message,
+        context,
+        &shares,
+        signature_file,
+        options,
+        transcript_file,
+    )
+}
+
+/// Signs a message using the participant shares kept under the given names in a
+/// keystore, see [`sign_message_with_shares`].
+///
+/// # Arguments
+/// - `message`: The message to be signed.
+/// - `context`: Context string the message is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `keystore`: The keystore holding the signers' shares, see [`KeyStore`].
+/// - `names`: Names of the signers' shares in the keystore.
+/// - `signature_file`: Path to save the generated signature.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+///
+/// # Errors
+/// Returns an error if a share cannot be loaded from the keystore, the shares belong to
+/// different groups, fewer shares than the threshold are given, or signing fails.
+pub fn sign_message_with_keystore(
+    message: &str,
+    context: &str,
+    keystore: &dyn KeyStore,
+    names: &[String],
+    signature_file: &str,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut shares = Vec::new();
+    for name in names {
+        shares.push(Locked::new(keystore.load_share(name)?)?);
+    }
+    sign_message_with_loaded_shares(
+        message,
+        context,
+        &shares,
+        signature_file,
+        &EnvelopeOptions::default(),
+        transcript_file,
+    )
+}
+
+/// Signs a message with loaded participant shares and saves the signature, see
+/// [`sign_message_with_shares_as`].
+fn sign_message_with_loaded_shares(
+    message: &str,
+    context: &str,
+    shares: &[Locked<ParticipantShare>],
+    signature_file: &str,
+    options: &EnvelopeOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let message = io::jcs::canonicalize_as(message, options.canonicalization)?;
+    let (signature, metadata) = sign_with_shares(&message, context, shares, transcript_file)?;
+    save_signature_with_metadata(
+        signature_file,
+        &signature,
+        &metadata,
+        options.signature_format,
+        options.files.format,
+    )?;
+
+    report(
+        "signature_saved",
+        &format!("Threshold signature saved to: {}", signature_file),
+        json!({
+            "signature_file": signature_file,
+            "group_key_fingerprint": metadata.group_key_fingerprint,
+            "signers": metadata.signers,
+        }),
+    );
+    Ok(())
+}
+
+/// Signs a message with loaded participant shares and returns the signature and its
+/// metadata, see [`sign_message_with_shares`].
+pub(crate) fn sign_with_shares(
+    message: &[u8],
+    context: &str,
+    shares: &[Locked<ParticipantShare>],
+    transcript_file: Option<&str>,
+) -> Result<(Vec<u8>, SignatureMetadata), Box<dyn std::error::Error>> {
+    // Step 1: Check that all shares belong to the same group
+    let first = shares
+        .first()
+        .ok_or_else(|| FrostCliError::InvalidParameters("At least one share is required".into()))?;
+    for share in shares {
+        if share.group_key != first.group_key
+            || share.threshold != first.threshold
+            || share.participants != first.participants
+        {
+            return Err(FrostCliError::InvalidParameters(format!(
+                "Share of participant {} belongs to a different group",
+                share.index
+            ))
+            .into());
+        }
+    }
+
+    // Step 2: Check that at least the threshold of distinct participants sign
+    let indices: Vec<u32> = shares.iter().map(|share| share.index).collect();
+    check_signer_set(&indices, first.threshold)?;
+
+    // Step 3: Load the group public key
+    let group_key =
+        GroupKey::from_bytes(first.group_key).map_err(|_| "Invalid group public key")?;
+
+    // Step 4: Reconstruct the secret keys of the signers
+    let mut secret_keys = Vec::new();
+    for share in shares {
+        let secret_key =
+            SignatureSecretKey::from_bytes(share.index, share.share).map_err(|_| {
+                FrostCliError::InvalidShare {
+                    share: format!("of participant {}", share.index),
+                    reason: format!("Invalid private key bytes of participant {}", share.index),
+                }
+            })?;
+        secret_keys.push(secret_key);
+    }
+
+    let signature = sign_with_secret_keys(
+        message,
+        context,
+        &secret_keys,
+        &first.verification_shares,
+        group_key,
+        first.threshold,
+        first.participants,
+        transcript_file,
+        &mut OsRng,
+    )?;
+
+    // Step 5: Record who signed
+    let metadata = SignatureMetadata::new(
+        Ciphersuite::Ristretto255,
+        context,
+        &first.group_key,
+        shares.iter().map(|share| share.index).collect(),
+    )?;
+    Ok((signature, metadata))
+}
+
+/// Runs both signing rounds for the given signers and returns the threshold signature.
+pub(crate) fn sign_with_secret_keys(
+    message: &[u8],
+    context: &str,
+    secret_keys: &[SignatureSecretKey],
+    verification_shares: &[[u8; 32]],
+    group_key: GroupKey,
+    t: u32,
+    n: u32,
+    transcript_file: Option<&str>,
+    rng: &mut dyn SecureRng,
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut signatures = sign_batch_with_secret_keys(
+        &[message.to_vec()],
+        context,
+        secret_keys,
+        verification_shares,
+        group_key,
+        t,
+        n,
+        transcript_file,
+        rng,
+    )?;
+    Ok(signatures.remove(0))
+}
+
+/// Runs both signing rounds for several messages at once and returns their threshold
+/// signatures in order. Every signer commits to one nonce pair per message up front,
+/// and message `i` is signed with the `i`-th commitment of each signer. The partial
+/// signatures are checked against the group's `verification_shares`, see
+/// [`FrostKeys::verification_shares`], so a share of another group is caught.
+pub(crate) fn sign_batch_with_secret_keys(
+    messages: &[Vec<u8>],
+    context: &str,
+    secret_keys: &[SignatureSecretKey],
+    verification_shares: &[[u8; 32]],
+    group_key: GroupKey,
+    t: u32,
+    n: u32,
+    transcript_file: Option<&str>,
+    rng: &mut dyn SecureRng,
+) -> Result<Vec<Vec<u8>>, Box<dyn std::error::Error>> {
+    // Step 1: Look up the public key of each of the chosen signers, which keys saved
+    // before verification shares were stored derive from the secret keys
+    let mut public_keys = Vec::with_capacity(secret_keys.len());
+    for signer in secret_keys {
+        let index = signer.get_index();
+        let public_key = match verification_share(verification_shares, index)
+            .map_err(|reason| invalid_partial_signatures(&[(index, reason)]))?
+        {
+            Some(share) => IndividualPublicKey { index, share },
+            None => signer.to_public(),
+        };
+        public_keys.push(public_key);
+    }
+
+    // Step 2: Generate a commitment share per message for each of the chosen signers
+    let mut public_comshares = Vec::new();
+    let mut secret_comshares = Vec::new();
+    for signer in secret_keys {
+        let (pub_com, sec_com) =
+            generate_commitment_share_lists(&mut *rng, signer.get_index(), messages.len());
+        public_comshares.push((signer.get_index(), pub_com));
+        secret_comshares.push((signer.get_index(), sec_com));
+    }
+
+    let context = context.as_bytes();
+    let mut signatures = Vec::with_capacity(messages.len());
+    for (i, message) in messages.iter().enumerate() {
+        // Step 3: Hash the message to create a signing context
+        let message_hash = compute_message_hash(context, message);
+
+        // Step 4: Initialize a signature aggregator
+        let mut aggregator =
+            SignatureAggregator::new(Parameters { t, n }, group_key, context, message);
+
+        // Step 5: Include signers and their commitment shares for this message
+        for (public_key, (index, pub_com)) in public_keys.iter().zip(public_comshares.iter()) {
+            aggregator.include_signer(*index, pub_com.commitments[i], public_key.clone());
+        }
+
+        // Step 6: Get the list of participating signers
+        let signers = aggregator.get_signers().clone();
+
+        // Step 7: Create and include partial signatures. Signing drops the commitment
+        // it used from the secret list, so the one for this message is always first.
+        for (secret_key, (index, sec_com)) in secret_keys.iter().zip(secret_comshares.iter_mut()) {
+            if sec_com.commitments.len() != messages.len() - i {
+                return Err(format!("Commitment of participant {} was not used up", index).into());
+            }
+            let partial_sig = secret_key.sign(&message_hash, &group_key, sec_com, 0, &signers)?;
+            aggregator.include_partial_signature(partial_sig);
+        }
+
+        // Step 8: Finalize and aggregate the threshold signature, and record the
+        // outcome in the transcript
+        let outcome = aggregator
+            .finalize()
+            .map_err(|err| aggregation_error("Failed to finalize aggregator", &err))
+            .and_then(|aggregator| {
+                aggregator
+                    .aggregate()
+                    .map_err(|err| aggregation_error("Failed to aggregate signature", &err))
+            })
+            .map(|signature| signature.to_bytes());
+        let mut record = CeremonyRecord::new(
+            CeremonyEvent::Signed,
+            Ciphersuite::Ristretto255,
+            &group_key.to_bytes(),
+            t,
+            public_comshares.iter().map(|(index, _)| *index).collect(),
+            &message_hash,
+        );
+        record.commitments = public_comshares
+            .iter()
+            .map(|(index, pub_com)| {
+                let (hiding, binding) = pub_com.commitments[i];
+                CommitmentHash {
+                    index: *index,
+                    hash: nonces::commitment_id(&(
+                        hiding.compress().to_bytes(),
+                        binding.compress().to_bytes(),
+                    )),
+                }
+            })
+            .collect();
+        transcript::append_outcome(transcript_file, record, &outcome)?;
+
+        signatures.push(outcome?.to_vec());
+    }
+    Ok(signatures)
+}
+
+/// Returns the error of an aggregation that failed, naming the signers frost-dalek
+/// reports as misbehaving.
+fn aggregation_error<R: fmt::Debug>(reason: &str, misbehaving: &HashMap<u32, R>) -> FrostCliError {
+    let mut signers: Vec<u32> = misbehaving.keys().copied().collect();
+    signers.sort_unstable();
+    FrostCliError::Aggregation {
+        signers,
+        reason: format!("{}: {:?}", reason, misbehaving),
+    }
+}
+
+/// Signs a file of any size and content using threshold signing, see
+/// [`crate::artifact`].
+///
+/// # Arguments
+/// - `file`: Path to the file to be signed, hashed in chunks rather than read at once.
+/// - `context`: Context string the file is signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `signers`: Positions of the signers' shares in the key file.
+/// - `n`: The total number of participants.
+/// - `key_file`: Path to the file containing the generated keys.
+/// - `signature_file`: Path to save the signature and the record of the signed file.
+/// - `sig_format`: Format to save the signature in, see [`io::signature`]. Only `json`
+///   keeps the record of the signed file.
+/// - `options`: How the key file is read and a `json` signature file is written.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+///
+/// # Errors
+/// Returns an error if the file cannot be read, or loading keys or signing fails.
+#[allow(clippy::too_many_arguments)]
+pub fn sign_file(
+    file: &str,
+    context: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    signature_file: &str,
+    sig_format: SignatureFormat,
+    options: &FileOptions,
+    transcript_file: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Hash the file
+    let signed_file = SignedFile::hash(file)?;
+
+    // Step 2: Sign the hash with the backend of the key file's ciphersuite
+    let frost_keys = load_signing_keys(&signers, n, key_file, options)?;
+    let signature = backend::open(frost_keys.ciphersuite)?.sign(
+        &frost_keys,
+        &signers,
+        &signed_file.signing_input()?,
+        context,
+        transcript_file,
+        &mut OsRng,
+    )?;
+
+    // Step 3: Save the signature with the record of the signed file
+    let metadata = SignatureMetadata::new(
+        frost_keys.ciphersuite,
+        context,
+        &frost_keys.group_key,
+        signers
+            .iter()
+            .map(|&signer| frost_keys.private_shares[signer as usize].1)
+            .collect(),
+    )?;
+    let bytes = io::signature::encode_file_signature(
+        &signature,
+        &metadata,
+        &signed_file,
+        sig_format,
+        options.format,
+    )?;
+    fs::write(signature_file, bytes)?;
+
+    report(
+        "signature_saved",
+        &format!(
+            "Threshold signature of {} ({} bytes, SHA-256 {}) saved to: {}",
+            signed_file.name, signed_file.size, signed_file.sha256, signature_file
+        ),
+        json!({
+            "signature_file": signature_file,
+            "group_key_fingerprint": metadata.group_key_fingerprint,
+            "signers": metadata.signers,
+            "file": signed_file,
+        }),
+    );
+    Ok(())
+}
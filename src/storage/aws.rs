@@ -0,0 +1,188 @@
+//! Shares kept in AWS Secrets Manager, envelope-encrypted with AWS KMS.
+//!
+//! A location `aws:<secret-id>` is the Secrets Manager secret with that name or ARN.
+//! Every time a share is saved, a fresh data key is generated under a KMS key, the
+//! share is encrypted with it locally using AES-256-GCM, and only the encrypted data key
+//! is stored next to the ciphertext. Reading the share back therefore needs both read
+//! access to the secret and decrypt permission on the KMS key.
+//!
+//! The KMS key is given as `aws:<secret-id>?kms-key-id=<key>` or in
+//! `FROST_AWS_KMS_KEY_ID`, and is only needed to save a share. Credentials and region
+//! come from the usual AWS sources, such as the environment, `~/.aws` or the instance
+//! profile of an EC2 signer.
+
+use crate::config;
+use crate::storage::ShareStorage;
+use crate::ParticipantShare;
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use aws_config::BehaviorVersion;
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Encryption context key binding a data key to its secret.
+const CONTEXT_KEY: &str = "frost-cli-share";
+
+/// A participant share kept in AWS Secrets Manager.
+pub struct AwsStorage {
+    secret_id: String,
+    kms_key_id: Option<String>,
+}
+
+/// The secret string: a share encrypted under a KMS data key.
+#[derive(Serialize, Deserialize)]
+struct EncryptedShare {
+    kms_key_id: String,
+    /// Hex-encoded data key, encrypted by KMS.
+    encrypted_key: String,
+    nonce: [u8; 12],
+    /// Hex-encoded AES-256-GCM encryption of the [`ParticipantShare`].
+    ciphertext: String,
+}
+
+impl AwsStorage {
+    /// Parses the part of a location after `aws:`.
+    pub fn new(location: &str) -> AwsStorage {
+        let (secret_id, query) = location.split_once('?').unwrap_or((location, ""));
+        let kms_key_id = query
+            .split('&')
+            .find_map(|attribute| attribute.strip_prefix("kms-key-id="))
+            .map(str::to_string)
+            .or_else(|| config::var(config::AWS_KMS_KEY_ID).ok().flatten());
+        AwsStorage {
+            secret_id: secret_id.to_string(),
+            kms_key_id,
+        }
+    }
+}
+
+impl ShareStorage for AwsStorage {
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        tokio::runtime::Runtime::new()?.block_on(async {
+            let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+
+            // Step 1: Fetch the encrypted share
+            let secret = aws_sdk_secretsmanager::Client::new(&config)
+                .get_secret_value()
+                .secret_id(&self.secret_id)
+                .send()
+                .await
+                .map_err(|err| format!("Failed to read secret {}: {}", self.secret_id, err))?;
+            let encrypted: EncryptedShare = serde_json::from_str(
+                secret
+                    .secret_string()
+                    .ok_or_else(|| format!("Secret {} has no string value", self.secret_id))?,
+            )?;
+
+            // Step 2: Decrypt the data key with KMS
+            let data_key = aws_sdk_kms::Client::new(&config)
+                .decrypt()
+                .key_id(&encrypted.kms_key_id)
+                .ciphertext_blob(Blob::new(hex::decode(&encrypted.encrypted_key)?))
+                .encryption_context(CONTEXT_KEY, &self.secret_id)
+                .send()
+                .await
+                .map_err(|err| {
+                    format!(
+                        "Failed to decrypt the data key of {}: {}",
+                        self.secret_id, err
+                    )
+                })?;
+            let data_key = data_key.plaintext().ok_or("KMS returned no data key")?;
+
+            // Step 3: Decrypt the share
+            let payload = Zeroizing::new(
+                Aes256Gcm::new_from_slice(data_key.as_ref())
+                    .map_err(|_| "Invalid data key")?
+                    .decrypt(
+                        Nonce::from_slice(&encrypted.nonce),
+                        Payload {
+                            msg: &hex::decode(&encrypted.ciphertext)?,
+                            aad: self.secret_id.as_bytes(),
+                        },
+                    )
+                    .map_err(|_| format!("Failed to decrypt share {}", self.secret_id))?,
+            );
+            Ok(serde_json::from_slice(&payload)?)
+        })
+    }
+
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>> {
+        let kms_key_id = self
+            .kms_key_id
+            .as_ref()
+            .ok_or("No KMS key, set kms-key-id or FROST_AWS_KMS_KEY_ID")?;
+        tokio::runtime::Runtime::new()?.block_on(async {
+            let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+
+            // Step 1: Generate a fresh data key bound to this secret
+            let data_key = aws_sdk_kms::Client::new(&config)
+                .generate_data_key()
+                .key_id(kms_key_id)
+                .key_spec(DataKeySpec::Aes256)
+                .encryption_context(CONTEXT_KEY, &self.secret_id)
+                .send()
+                .await
+                .map_err(|err| format!("Failed to generate a data key: {}", err))?;
+            let plaintext_key = data_key.plaintext().ok_or("KMS returned no data key")?;
+            let encrypted_key = data_key
+                .ciphertext_blob()
+                .ok_or("KMS returned no encrypted data key")?;
+
+            // Step 2: Encrypt the share locally
+            let mut nonce = [0u8; 12];
+            OsRng.fill_bytes(&mut nonce);
+            let ciphertext = Aes256Gcm::new_from_slice(plaintext_key.as_ref())
+                .map_err(|_| "Invalid data key")?
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    Payload {
+                        msg: &Zeroizing::new(serde_json::to_vec(share)?),
+                        aad: self.secret_id.as_bytes(),
+                    },
+                )
+                .map_err(|_| "Failed to encrypt share")?;
+            let secret_string = serde_json::to_string(&EncryptedShare {
+                kms_key_id: kms_key_id.clone(),
+                encrypted_key: hex::encode(encrypted_key.as_ref()),
+                nonce,
+                ciphertext: hex::encode(ciphertext),
+            })?;
+
+            // Step 3: Store it as a new version of the secret, creating it if needed
+            let client = aws_sdk_secretsmanager::Client::new(&config);
+            let put = client
+                .put_secret_value()
+                .secret_id(&self.secret_id)
+                .secret_string(&secret_string)
+                .send()
+                .await;
+            match put {
+                Ok(_) => Ok(()),
+                Err(err)
+                    if err
+                        .as_service_error()
+                        .map_or(false, |err| err.is_resource_not_found_exception()) =>
+                {
+                    client
+                        .create_secret()
+                        .name(&self.secret_id)
+                        .secret_string(secret_string)
+                        .send()
+                        .await
+                        .map_err(|err| {
+                            format!("Failed to create secret {}: {}", self.secret_id, err)
+                        })?;
+                    Ok(())
+                }
+                Err(err) => {
+                    Err(format!("Failed to save share to {}: {}", self.secret_id, err).into())
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,34 @@
+//! Share files on disk, the default backend.
+//!
+//! Share files carry a checksum, see [`crate::write_checked_json`].
+
+use crate::error::invalid_share;
+use crate::io::files::FileOptions;
+use crate::storage::ShareStorage;
+use crate::{read_checked_json, write_checked_json, ParticipantShare};
+
+/// A participant share kept as a file, written and read as `options` say.
+pub struct FileStorage {
+    path: String,
+    options: FileOptions,
+}
+
+impl FileStorage {
+    pub fn new(path: &str, options: &FileOptions) -> FileStorage {
+        FileStorage {
+            path: path.to_string(),
+            options: options.clone(),
+        }
+    }
+}
+
+impl ShareStorage for FileStorage {
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        read_checked_json(&self.path, "share", self.options.file_key.as_ref())
+            .map_err(|err| invalid_share(&self.path, err))
+    }
+
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>> {
+        write_checked_json(&self.path, share, &self.options)
+    }
+}
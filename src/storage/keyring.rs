@@ -0,0 +1,52 @@
+//! Shares kept in the operating system's keyring.
+//!
+//! Uses the macOS Keychain, the Windows Credential Manager or the Secret Service on
+//! Linux. A share is stored as a JSON secret under the `frost-cli` service, with the
+//! name from its `keyring:<name>` location as the account, so it never touches the
+//! disk unencrypted.
+
+use crate::storage::ShareStorage;
+use crate::ParticipantShare;
+use ::keyring::Entry;
+use zeroize::Zeroizing;
+
+/// Keyring service all shares are stored under.
+const SERVICE: &str = "frost-cli";
+
+/// A participant share kept in the operating system's keyring.
+pub struct KeyringStorage {
+    name: String,
+    entry: Entry,
+}
+
+impl KeyringStorage {
+    /// Opens the keyring entry for the share called `name`.
+    ///
+    /// # Errors
+    /// Returns an error if no keyring is available.
+    pub fn new(name: &str) -> Result<KeyringStorage, Box<dyn std::error::Error>> {
+        Ok(KeyringStorage {
+            name: name.to_string(),
+            entry: Entry::new(SERVICE, name)?,
+        })
+    }
+}
+
+impl ShareStorage for KeyringStorage {
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        let secret = Zeroizing::new(self.entry.get_password().map_err(|err| {
+            format!(
+                "Failed to read share {} from the keyring: {}",
+                self.name, err
+            )
+        })?);
+        Ok(serde_json::from_str(&secret)?)
+    }
+
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>> {
+        self.entry
+            .set_password(&Zeroizing::new(serde_json::to_string(share)?))
+            .map_err(|err| format!("Failed to save share {} to the keyring: {}", self.name, err))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Backends that keep a participant's private share.
+//!
+//! Every command that needs a share takes a share location. A plain path is a JSON
+//! share file on disk, `keyring:<name>` is an entry in the operating system's keyring,
+//! `pkcs11:...` is an object on a PKCS#11 token, `vault://<mount>/<path>` is a secret
+//! in HashiCorp Vault and `aws:<secret-id>` is a KMS-encrypted secret in AWS Secrets
+//! Manager. [`open`] returns the backend for
+//! a location, so the protocol code never needs to know where a share is kept.
+
+#[cfg(feature = "aws")]
+pub mod aws;
+pub mod file;
+#[cfg(feature = "keyring")]
+pub mod keyring;
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+#[cfg(native)]
+pub mod vault;
+
+use crate::io::files::FileOptions;
+use crate::ParticipantShare;
+
+/// Prefix of share locations kept in the operating system's keyring.
+pub const KEYRING_PREFIX: &str = "keyring:";
+
+/// Prefix of share locations kept on a PKCS#11 token, see [RFC 7512].
+///
+/// [RFC 7512]: https://www.rfc-editor.org/rfc/rfc7512
+pub const PKCS11_PREFIX: &str = "pkcs11:";
+
+/// Prefix of share locations kept in a HashiCorp Vault KV version 2 engine.
+pub const VAULT_PREFIX: &str = "vault://";
+
+/// Prefix of share locations kept in AWS Secrets Manager.
+pub const AWS_PREFIX: &str = "aws:";
+
+/// A place a participant share is loaded from and saved to.
+pub trait ShareStorage {
+    /// Loads the share.
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>>;
+
+    /// Saves the share, replacing any share kept there before.
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Returns the backend for a share location, writing share files as `options` say.
+///
+/// # Errors
+/// Returns an error if the location needs a backend this build does not include.
+pub fn open(
+    location: &str,
+    options: &FileOptions,
+) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    if let Some(name) = location.strip_prefix(KEYRING_PREFIX) {
+        return open_keyring(name);
+    }
+    if let Some(uri) = location.strip_prefix(PKCS11_PREFIX) {
+        return open_pkcs11(uri);
+    }
+    if let Some(path) = location.strip_prefix(VAULT_PREFIX) {
+        return open_vault(path);
+    }
+    if let Some(secret_id) = location.strip_prefix(AWS_PREFIX) {
+        return open_aws(secret_id);
+    }
+    Ok(Box::new(file::FileStorage::new(location, options)))
+}
+
+/// Returns whether a share location is a plain file.
+pub(crate) fn is_file(location: &str) -> bool {
+    ![KEYRING_PREFIX, PKCS11_PREFIX, VAULT_PREFIX, AWS_PREFIX]
+        .iter()
+        .any(|prefix| location.starts_with(prefix))
+}
+
+#[cfg(feature = "keyring")]
+fn open_keyring(name: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Ok(Box::new(keyring::KeyringStorage::new(name)?))
+}
+
+#[cfg(not(feature = "keyring"))]
+fn open_keyring(_name: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the keyring feature".into())
+}
+
+#[cfg(feature = "pkcs11")]
+fn open_pkcs11(uri: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Ok(Box::new(pkcs11::Pkcs11Storage::new(uri)?))
+}
+
+#[cfg(not(feature = "pkcs11"))]
+fn open_pkcs11(_uri: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the pkcs11 feature".into())
+}
+
+#[cfg(native)]
+fn open_vault(path: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Ok(Box::new(vault::VaultStorage::new(path)?))
+}
+
+#[cfg(not(native))]
+fn open_vault(_path: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the native feature, which Vault needs".into())
+}
+
+#[cfg(feature = "aws")]
+fn open_aws(secret_id: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Ok(Box::new(aws::AwsStorage::new(secret_id)))
+}
+
+#[cfg(not(feature = "aws"))]
+fn open_aws(_secret_id: &str) -> Result<Box<dyn ShareStorage>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the aws feature".into())
+}
@@ -0,0 +1,147 @@
+//! Shares kept on a PKCS#11 token such as an HSM or a smart card.
+//!
+//! A share is stored as a private data object on the token, so it can only be read
+//! after logging in with the user PIN and never touches the disk. Locations follow
+//! the PKCS#11 URI scheme of RFC 7512, for example
+//! `pkcs11:token=frost;object=board-keys-1?module-path=/usr/lib/softhsm/libsofthsm2.so`.
+//! The `module-path` and `pin-value` query attributes fall back to the
+//! `FROST_PKCS11_MODULE` and `FROST_PKCS11_PIN` environment variables, the PIN also to
+//! a file named by `FROST_PKCS11_PIN_FILE`, see [`crate::config`]. Without a `token`
+//! attribute the first slot with a token is used.
+//!
+//! PKCS#11 has no mechanisms for the Ristretto group, so the scalar operations of a
+//! partial signature cannot run on the token. The share is read from the token for
+//! each signing command and only kept in memory while the command runs.
+
+use crate::config;
+use crate::storage::ShareStorage;
+use crate::ParticipantShare;
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::object::{Attribute, AttributeType, ObjectClass};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use zeroize::Zeroizing;
+
+/// Application attribute set on every share object, to tell them from other data.
+const APPLICATION: &[u8] = b"frost-cli";
+
+/// A participant share kept on a PKCS#11 token.
+pub struct Pkcs11Storage {
+    module_path: String,
+    token: Option<String>,
+    object: String,
+    pin: String,
+}
+
+impl Pkcs11Storage {
+    /// Parses the part of a PKCS#11 URI after `pkcs11:`.
+    ///
+    /// # Errors
+    /// Returns an error if the URI names no object, or no module or PIN is configured.
+    pub fn new(uri: &str) -> Result<Pkcs11Storage, Box<dyn std::error::Error>> {
+        let (path, query) = uri.split_once('?').unwrap_or((uri, ""));
+        let path = attributes(path, ';');
+        let query = attributes(query, '&');
+        let find = |attributes: &[(String, String)], name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        Ok(Pkcs11Storage {
+            module_path: find(&query, "module-path")
+                .or(config::var(config::PKCS11_MODULE)?)
+                .ok_or("No PKCS#11 module, set module-path or FROST_PKCS11_MODULE")?,
+            token: find(&path, "token"),
+            object: find(&path, "object").ok_or("PKCS#11 URI names no object")?,
+            pin: find(&query, "pin-value")
+                .or(config::var(config::PKCS11_PIN)?)
+                .ok_or(
+                    "No PKCS#11 PIN, set pin-value, FROST_PKCS11_PIN or FROST_PKCS11_PIN_FILE",
+                )?,
+        })
+    }
+
+    /// Logs in to the token and runs `action` in a read-write session.
+    fn with_session<T>(
+        &self,
+        action: impl FnOnce(&Session) -> Result<T, Box<dyn std::error::Error>>,
+    ) -> Result<T, Box<dyn std::error::Error>> {
+        // Step 1: Load the module and find the token
+        let pkcs11 = Pkcs11::new(&self.module_path)?;
+        pkcs11.initialize(CInitializeArgs::OsThreads)?;
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_token()? {
+            let label = pkcs11.get_token_info(candidate)?.label().trim().to_string();
+            if self.token.as_deref().map_or(true, |token| token == label) {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot = slot.ok_or_else(|| match &self.token {
+            Some(token) => format!("No PKCS#11 token labelled {}", token),
+            None => "No PKCS#11 token found".to_string(),
+        })?;
+
+        // Step 2: Log in as the user and run the action
+        let session = pkcs11.open_rw_session(slot)?;
+        session.login(UserType::User, Some(&AuthPin::new(self.pin.clone())))?;
+        let result = action(&session);
+        session.logout()?;
+        result
+    }
+
+    fn template(&self) -> Vec<Attribute> {
+        vec![
+            Attribute::Class(ObjectClass::DATA),
+            Attribute::Application(APPLICATION.to_vec()),
+            Attribute::Label(self.object.as_bytes().to_vec()),
+        ]
+    }
+}
+
+impl ShareStorage for Pkcs11Storage {
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        let value = self.with_session(|session| {
+            let object = session
+                .find_objects(&self.template())?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("No share {} on the PKCS#11 token", self.object))?;
+            match session
+                .get_attributes(object, &[AttributeType::Value])?
+                .into_iter()
+                .next()
+            {
+                Some(Attribute::Value(value)) => Ok(value),
+                _ => Err(format!("Share {} on the PKCS#11 token has no value", self.object).into()),
+            }
+        })?;
+        Ok(serde_json::from_slice(&Zeroizing::new(value))?)
+    }
+
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>> {
+        let value = serde_json::to_vec(share)?;
+        self.with_session(|session| {
+            for object in session.find_objects(&self.template())? {
+                session.destroy_object(object)?;
+            }
+            let mut template = self.template();
+            template.extend([
+                Attribute::Token(true),
+                Attribute::Private(true),
+                Attribute::Value(value),
+            ]);
+            session.create_object(&template)?;
+            Ok(())
+        })
+    }
+}
+
+/// Splits `key=value` attributes separated by `separator`.
+fn attributes(part: &str, separator: char) -> Vec<(String, String)> {
+    part.split(separator)
+        .filter_map(|attribute| attribute.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
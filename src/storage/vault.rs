@@ -0,0 +1,140 @@
+//! Shares kept in a HashiCorp Vault KV version 2 secrets engine.
+//!
+//! A location `vault://<mount>/<path>` is the secret at `<path>` in the KV engine
+//! mounted at `<mount>`, for example `vault://secret/frost/board-keys-1`. The share is
+//! stored as the secret's data, so it never touches the local disk.
+//!
+//! The server is taken from `VAULT_ADDR`, like the Vault CLI does. Requests are
+//! authenticated with `VAULT_TOKEN`, or by logging in with the AppRole in
+//! `VAULT_ROLE_ID` and `VAULT_SECRET_ID`. Without either, requests are sent without a
+//! token, which is what a Vault agent with `use_auto_auth_token` expects. The token and
+//! secret ID can also be read from the files named by `VAULT_TOKEN_FILE` and
+//! `VAULT_SECRET_ID_FILE`, see [`crate::config`].
+
+use crate::config;
+use crate::storage::ShareStorage;
+use crate::ParticipantShare;
+use serde::{Deserialize, Serialize};
+
+/// Server used when `VAULT_ADDR` is not set, the Vault CLI's default.
+const DEFAULT_ADDR: &str = "http://127.0.0.1:8200";
+
+/// A participant share kept in Vault.
+pub struct VaultStorage {
+    addr: String,
+    mount: String,
+    path: String,
+}
+
+/// The body of a KV version 2 read or write.
+#[derive(Serialize, Deserialize)]
+struct SecretData<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: String,
+}
+
+#[derive(Serialize)]
+struct AppRoleLogin {
+    role_id: String,
+    secret_id: String,
+}
+
+impl VaultStorage {
+    /// Parses the part of a location after `vault://`.
+    ///
+    /// # Errors
+    /// Returns an error if the location has no secret path after the mount.
+    pub fn new(location: &str) -> Result<VaultStorage, Box<dyn std::error::Error>> {
+        let (mount, path) = location
+            .trim_matches('/')
+            .split_once('/')
+            .ok_or_else(|| format!("Expected vault://<mount>/<path>, got: {}", location))?;
+        Ok(VaultStorage {
+            addr: config::var(config::VAULT_ADDR)?.unwrap_or_else(|| DEFAULT_ADDR.to_string()),
+            mount: mount.to_string(),
+            path: path.to_string(),
+        })
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}/v1/{}/data/{}",
+            self.addr.trim_end_matches('/'),
+            self.mount,
+            self.path
+        )
+    }
+
+    /// Returns the token to authenticate with, logging in with the AppRole if needed.
+    fn token(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Some(token) = config::var(config::VAULT_TOKEN)? {
+            return Ok(Some(token));
+        }
+        let (Some(role_id), Some(secret_id)) = (
+            config::var(config::VAULT_ROLE_ID)?,
+            config::var(config::VAULT_SECRET_ID)?,
+        ) else {
+            return Ok(None);
+        };
+        let login: LoginResponse = ureq::post(&format!(
+            "{}/v1/auth/approle/login",
+            self.addr.trim_end_matches('/')
+        ))
+        .send_json(&AppRoleLogin { role_id, secret_id })
+        .map_err(|err| format!("Failed to log in to Vault with the AppRole: {}", err))?
+        .into_json()?;
+        Ok(Some(login.auth.client_token))
+    }
+
+    /// Adds the token and namespace headers to a request.
+    fn authorize(
+        &self,
+        request: ureq::Request,
+    ) -> Result<ureq::Request, Box<dyn std::error::Error>> {
+        let mut request = request;
+        if let Some(token) = self.token()? {
+            request = request.set("X-Vault-Token", &token);
+        }
+        if let Some(namespace) = config::var(config::VAULT_NAMESPACE)? {
+            request = request.set("X-Vault-Namespace", &namespace);
+        }
+        Ok(request)
+    }
+}
+
+impl ShareStorage for VaultStorage {
+    fn load(&self) -> Result<ParticipantShare, Box<dyn std::error::Error>> {
+        let secret: SecretData<SecretData<ParticipantShare>> = self
+            .authorize(ureq::get(&self.url()))?
+            .call()
+            .map_err(|err| {
+                format!(
+                    "Failed to read share {}/{} from Vault: {}",
+                    self.mount, self.path, err
+                )
+            })?
+            .into_json()?;
+        Ok(secret.data.data)
+    }
+
+    fn save(&self, share: &ParticipantShare) -> Result<(), Box<dyn std::error::Error>> {
+        self.authorize(ureq::post(&self.url()))?
+            .send_json(&SecretData { data: share })
+            .map_err(|err| {
+                format!(
+                    "Failed to save share {}/{} to Vault: {}",
+                    self.mount, self.path, err
+                )
+            })?;
+        Ok(())
+    }
+}
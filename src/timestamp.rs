@@ -0,0 +1,618 @@
+//! RFC 3161 timestamps of threshold signatures.
+//!
+//! The time recorded in a signature file's [`crate::SignatureMetadata`] is whatever the
+//! signing machine's clock said. To prove when a signature existed,
+//! [`timestamp_signature`] sends the SHA-256 hash of the final signature to a time
+//! stamping authority (TSA) and embeds the signed timestamp token it answers with in
+//! the signature file.
+//!
+//! [`verify_signature_timestamp`] checks the token against the signature and the TSA's
+//! certificate, which has to be given: the certificate is pinned rather than checked
+//! against a chain of trust. Tokens signed with RSA PKCS #1 v1.5 or ECDSA over P-256 or
+//! P-384 are supported.
+
+use crate::io::format;
+use crate::io::output::report;
+use crate::io::pem;
+use crate::{add_checksum, parse_versioned, InvalidSignature, SignatureFile};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::signature::{self as ring_signature, UnparsedPublicKey, VerificationAlgorithm};
+use serde_json::json;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fs;
+use std::io::Read;
+
+/// Media type of a timestamp request.
+const QUERY_CONTENT_TYPE: &str = "application/timestamp-query";
+
+/// Largest timestamp response read from a TSA.
+const MAX_RESPONSE_LEN: u64 = 1 << 20;
+
+/// PEM label of a TSA certificate.
+const CERTIFICATE_LABEL: &str = "CERTIFICATE";
+
+// DER tags
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const OID: u8 = 0x06;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const CONTEXT_0: u8 = 0xa0;
+
+// Contents of the object identifiers a timestamp token uses
+const OID_SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+const OID_TST_INFO: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+const OID_CONTENT_TYPE: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+/// A verified timestamp token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp {
+    /// Time the TSA signed the token, as `YYYY-MM-DD hh:mm:ss UTC`.
+    pub time: String,
+    /// Hex-encoded serial number the TSA gave the token.
+    pub serial_number: String,
+}
+
+/// Obtains a timestamp token over the signature in a signature file from a TSA and
+/// embeds it in the file.
+///
+/// # Arguments
+/// - `signature_file`: Path to a signature file in the `json` signature format.
+/// - `tsa_url`: URL of the TSA to post the timestamp request to.
+///
+/// # Errors
+/// Returns an error if the file is not a versioned signature file, the TSA cannot be
+/// reached or refuses the request, or its token is not over the signature.
+pub fn timestamp_signature(
+    signature_file: &str,
+    tsa_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the signature file
+    let (mut file, file_format) = load_signature_file(signature_file)?;
+
+    // Step 2: Ask the TSA for a token over the signature
+    let nonce = OsRng.next_u64();
+    let response = ureq::post(tsa_url)
+        .set("Content-Type", QUERY_CONTENT_TYPE)
+        .send_bytes(&request(&file.signature, nonce))
+        .map_err(|err| format!("Timestamp request to {} failed: {}", tsa_url, err))?;
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_LEN)
+        .read_to_end(&mut bytes)?;
+    let token = response_token(&bytes)?;
+
+    // Step 3: Check that the token is over this signature and answers this request
+    let tst_info = TstInfo::parse(signed_content(&token)?.content)?;
+    tst_info.check_imprint(&file.signature)?;
+    if tst_info.nonce.map(unsigned) != Some(unsigned(&nonce.to_be_bytes())) {
+        return Err("Timestamp token does not answer the request, its nonce differs".into());
+    }
+
+    // Step 4: Embed the token in the signature file
+    file.timestamp_token = Some(token.clone());
+    let mut value = serde_json::to_value(&file)?;
+    add_checksum(&mut value)?;
+    fs::write(signature_file, format::encode_as(&value, file_format)?)?;
+
+    report(
+        "signature_timestamped",
+        &format!(
+            "Signature timestamped by {} at {}",
+            tsa_url,
+            tst_info.time()
+        ),
+        json!({
+            "signature_file": signature_file,
+            "tsa_url": tsa_url,
+            "time": tst_info.time(),
+            "serial_number": hex::encode(unsigned(tst_info.serial_number)),
+        }),
+    );
+    Ok(())
+}
+
+/// Verifies the timestamp token embedded in a signature file.
+///
+/// # Arguments
+/// - `signature_file`: Path to the timestamped signature file.
+/// - `tsa_cert_file`: Path to the TSA's certificate, as PEM or DER.
+///
+/// # Errors
+/// Returns an error if the file carries no token, or the token is not over the
+/// signature, not signed with the certificate's key, or signed outside its validity.
+pub fn verify_signature_timestamp(
+    signature_file: &str,
+    tsa_cert_file: &str,
+) -> Result<Timestamp, Box<dyn std::error::Error>> {
+    let (file, _) = load_signature_file(signature_file)?;
+    let token = file
+        .timestamp_token
+        .ok_or_else(|| format!("{} carries no timestamp token", signature_file))?;
+    let certificate = fs::read(tsa_cert_file)?;
+    let certificate = if pem::is_pem(&certificate) {
+        pem::decode(CERTIFICATE_LABEL, &String::from_utf8_lossy(&certificate))?
+    } else {
+        certificate
+    };
+    let timestamp = verify(&token, &file.signature, &certificate)?;
+
+    report(
+        "timestamp_verified",
+        &format!(
+            "Timestamp is valid! The signature existed at {} (token serial number {})",
+            timestamp.time, timestamp.serial_number
+        ),
+        json!({
+            "valid": true,
+            "signature_file": signature_file,
+            "time": timestamp.time,
+            "serial_number": timestamp.serial_number,
+        }),
+    );
+    Ok(timestamp)
+}
+
+/// Returns a DER timestamp request for the SHA-256 hash of `signature`.
+pub fn request(signature: &[u8], nonce: u64) -> Vec<u8> {
+    der(
+        SEQUENCE,
+        &[
+            der(INTEGER, &[1]),
+            message_imprint(signature),
+            der(INTEGER, &integer(&nonce.to_be_bytes())),
+            // Ask the TSA to include its certificate in the token
+            der(BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+/// Returns the timestamp token of a DER timestamp response.
+///
+/// # Errors
+/// Returns an error if the TSA did not grant the request.
+pub fn response_token(response: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut response = Reader::new(Reader::new(response).expect(SEQUENCE)?);
+    let mut status = Reader::new(response.expect(SEQUENCE)?);
+    // 0 is granted, 1 granted with modifications
+    match unsigned(status.expect(INTEGER)?) {
+        [] | [1] => Ok(response.next()?.raw.to_vec()),
+        code => Err(format!(
+            "TSA refused the timestamp request with status {}",
+            hex::encode(code)
+        )
+        .into()),
+    }
+}
+
+/// Verifies a DER timestamp token over `signature` with the TSA's DER certificate.
+///
+/// # Errors
+/// Returns an error if the token is not over the signature, not signed with the
+/// certificate's key, or signed outside the certificate's validity.
+pub fn verify(
+    token: &[u8],
+    signature: &[u8],
+    certificate: &[u8],
+) -> Result<Timestamp, Box<dyn std::error::Error>> {
+    // Step 1: Check that the token is over the signature
+    let signed = signed_content(token)?;
+    let tst_info = TstInfo::parse(signed.content)?;
+    tst_info.check_imprint(signature)?;
+
+    // Step 2: Check the signed attributes, which bind the token's content
+    let mut content_type = None;
+    let mut message_digest = None;
+    let mut attributes = Reader::new(signed.attributes);
+    while !attributes.is_empty() {
+        let mut attribute = Reader::new(attributes.expect(SEQUENCE)?);
+        let oid = attribute.expect(OID)?;
+        let mut values = Reader::new(attribute.expect(SET)?);
+        if oid == OID_CONTENT_TYPE {
+            content_type = Some(values.expect(OID)?);
+        } else if oid == OID_MESSAGE_DIGEST {
+            message_digest = Some(values.expect(OCTET_STRING)?);
+        }
+    }
+    if content_type != Some(OID_TST_INFO) {
+        return Err("Timestamp token does not sign a TSTInfo".into());
+    }
+    if message_digest != Some(digest(signed.digest_algorithm, signed.content)?.as_slice()) {
+        return Err(InvalidSignature(
+            "Timestamp token content does not match its signed digest".into(),
+        )
+        .into());
+    }
+
+    // Step 3: Verify the TSA's signature over the signed attributes, which are signed
+    // with the SET tag instead of their implicit [0] tag
+    let tsa = Certificate::parse(certificate)?;
+    let signed_attributes = der(SET, signed.attributes);
+    UnparsedPublicKey::new(tsa.algorithm(signed.digest_algorithm)?, tsa.public_key)
+        .verify(&signed_attributes, signed.signature)
+        .map_err(|_| {
+            InvalidSignature("Timestamp token is not signed by the TSA certificate".into())
+        })?;
+
+    // Step 4: Check that the token was made while the certificate was valid
+    let time = tst_info.time.to_string();
+    if time < tsa.not_before || time > tsa.not_after {
+        return Err(InvalidSignature(
+            "Timestamp token was made outside the validity of the TSA certificate".into(),
+        )
+        .into());
+    }
+
+    Ok(Timestamp {
+        time: tst_info.time(),
+        serial_number: hex::encode(unsigned(tst_info.serial_number)),
+    })
+}
+
+/// Reads a signature file in any file format, returning the format to write it back in.
+fn load_signature_file(
+    signature_file: &str,
+) -> Result<(SignatureFile, format::Format), Box<dyn std::error::Error>> {
+    let bytes = fs::read(signature_file)?;
+    let file = format::decode(&bytes)
+        .and_then(|value| parse_versioned(value, signature_file, "signature", None))
+        .map_err(|err| format!("Only json signature files can be timestamped: {}", err))?;
+    Ok((file, format::detect(&bytes)))
+}
+
+/// Returns the DER `MessageImprint` of the SHA-256 hash of `signature`.
+fn message_imprint(signature: &[u8]) -> Vec<u8> {
+    der(
+        SEQUENCE,
+        &[
+            der(SEQUENCE, &[der(OID, OID_SHA256), der(NULL, &[])].concat()),
+            der(OCTET_STRING, &Sha256::digest(signature)),
+        ]
+        .concat(),
+    )
+}
+
+/// Returns the hash of `content` with the hash algorithm of the given OID.
+fn digest(algorithm: &[u8], content: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    match algorithm {
+        OID_SHA256 => Ok(Sha256::digest(content).to_vec()),
+        OID_SHA384 => Ok(Sha384::digest(content).to_vec()),
+        OID_SHA512 => Ok(Sha512::digest(content).to_vec()),
+        _ => Err("Unsupported digest algorithm in timestamp token".into()),
+    }
+}
+
+/// The parts of a timestamp token's CMS `SignedData` its verification needs.
+struct SignedContent<'a> {
+    /// DER `TSTInfo` the TSA signed.
+    content: &'a [u8],
+    /// OID of the hash algorithm of the signer.
+    digest_algorithm: &'a [u8],
+    /// Contents of the signed attributes.
+    attributes: &'a [u8],
+    signature: &'a [u8],
+}
+
+/// Returns the signed content of a timestamp token, a CMS `ContentInfo` of `SignedData`.
+fn signed_content(token: &[u8]) -> Result<SignedContent<'_>, Box<dyn std::error::Error>> {
+    let mut content_info = Reader::new(Reader::new(token).expect(SEQUENCE)?);
+    if content_info.expect(OID)? != OID_SIGNED_DATA {
+        return Err("Timestamp token is not CMS signed data".into());
+    }
+    let mut signed_data =
+        Reader::new(Reader::new(content_info.expect(CONTEXT_0)?).expect(SEQUENCE)?);
+    signed_data.expect(INTEGER)?;
+    signed_data.expect(SET)?;
+    let mut encapsulated = Reader::new(signed_data.expect(SEQUENCE)?);
+    if encapsulated.expect(OID)? != OID_TST_INFO {
+        return Err("Timestamp token does not carry a TSTInfo".into());
+    }
+    let content = Reader::new(encapsulated.expect(CONTEXT_0)?).expect(OCTET_STRING)?;
+
+    // Skip the certificates and revocation lists to the signer infos
+    let mut signer_infos = loop {
+        let element = signed_data.next()?;
+        if element.tag == SET {
+            break Reader::new(element.contents);
+        }
+    };
+    let mut signer_info = Reader::new(signer_infos.expect(SEQUENCE)?);
+    if !signer_infos.is_empty() {
+        return Err("Timestamp token must have exactly one signer".into());
+    }
+    signer_info.expect(INTEGER)?;
+    signer_info.next()?;
+    let digest_algorithm = Reader::new(signer_info.expect(SEQUENCE)?).expect(OID)?;
+    let attributes = signer_info
+        .optional(CONTEXT_0)?
+        .ok_or("Timestamp token has no signed attributes")?;
+    signer_info.expect(SEQUENCE)?;
+    let signature = signer_info.expect(OCTET_STRING)?;
+
+    Ok(SignedContent {
+        content,
+        digest_algorithm,
+        attributes,
+        signature,
+    })
+}
+
+/// The fields of a `TSTInfo` a timestamp is checked with.
+struct TstInfo<'a> {
+    hash_algorithm: &'a [u8],
+    hashed_message: &'a [u8],
+    serial_number: &'a [u8],
+    /// `genTime` as `YYYYMMDDhhmmss`.
+    time: &'a str,
+    nonce: Option<&'a [u8]>,
+}
+
+impl<'a> TstInfo<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut tst_info = Reader::new(Reader::new(bytes).expect(SEQUENCE)?);
+        tst_info.expect(INTEGER)?;
+        tst_info.expect(OID)?;
+        let mut imprint = Reader::new(tst_info.expect(SEQUENCE)?);
+        let hash_algorithm = Reader::new(imprint.expect(SEQUENCE)?).expect(OID)?;
+        let hashed_message = imprint.expect(OCTET_STRING)?;
+        let serial_number = tst_info.expect(INTEGER)?;
+        let time = std::str::from_utf8(tst_info.expect(GENERALIZED_TIME)?)?;
+        // Fractions of a second are dropped, the accuracy is what the TSA promises
+        let time = time
+            .get(..14)
+            .filter(|time| time.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or("Invalid genTime in timestamp token")?;
+        tst_info.optional(SEQUENCE)?;
+        tst_info.optional(BOOLEAN)?;
+        let nonce = tst_info.optional(INTEGER)?;
+        Ok(TstInfo {
+            hash_algorithm,
+            hashed_message,
+            serial_number,
+            time,
+            nonce,
+        })
+    }
+
+    /// Checks that the token is over the SHA-256 hash of `signature`.
+    fn check_imprint(&self, signature: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        if self.hash_algorithm != OID_SHA256
+            || self.hashed_message != Sha256::digest(signature).as_slice()
+        {
+            return Err(
+                InvalidSignature("Timestamp token is not over this signature".into()).into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the time of the token as `YYYY-MM-DD hh:mm:ss UTC`.
+    fn time(&self) -> String {
+        let t = self.time;
+        format!(
+            "{}-{}-{} {}:{}:{} UTC",
+            &t[..4],
+            &t[4..6],
+            &t[6..8],
+            &t[8..10],
+            &t[10..12],
+            &t[12..14]
+        )
+    }
+}
+
+/// The fields of an X.509 certificate a timestamp is checked with.
+struct Certificate<'a> {
+    key_algorithm: &'a [u8],
+    curve: Option<&'a [u8]>,
+    /// Contents of the `subjectPublicKey` bit string, without its unused bits byte.
+    public_key: &'a [u8],
+    /// Validity as `YYYYMMDDhhmmss`.
+    not_before: String,
+    not_after: String,
+}
+
+impl<'a> Certificate<'a> {
+    fn parse(bytes: &'a [u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut certificate = Reader::new(Reader::new(bytes).expect(SEQUENCE)?);
+        let mut tbs = Reader::new(certificate.expect(SEQUENCE)?);
+        tbs.optional(CONTEXT_0)?;
+        tbs.expect(INTEGER)?;
+        tbs.expect(SEQUENCE)?;
+        tbs.expect(SEQUENCE)?;
+        let mut validity = Reader::new(tbs.expect(SEQUENCE)?);
+        let not_before = certificate_time(validity.next()?)?;
+        let not_after = certificate_time(validity.next()?)?;
+        tbs.expect(SEQUENCE)?;
+        let mut key_info = Reader::new(tbs.expect(SEQUENCE)?);
+        let mut algorithm = Reader::new(key_info.expect(SEQUENCE)?);
+        let key_algorithm = algorithm.expect(OID)?;
+        let curve = algorithm.optional(OID)?;
+        let public_key = match key_info.expect(BIT_STRING)? {
+            [0, public_key @ ..] => public_key,
+            _ => return Err("Invalid public key in TSA certificate".into()),
+        };
+        Ok(Certificate {
+            key_algorithm,
+            curve,
+            public_key,
+            not_before,
+            not_after,
+        })
+    }
+
+    /// Returns the algorithm to verify signatures of the certificate's key with, for
+    /// the given digest algorithm.
+    fn algorithm(
+        &self,
+        digest_algorithm: &[u8],
+    ) -> Result<&'static dyn VerificationAlgorithm, Box<dyn std::error::Error>> {
+        Ok(match (self.key_algorithm, self.curve, digest_algorithm) {
+            (OID_RSA, _, OID_SHA256) => &ring_signature::RSA_PKCS1_2048_8192_SHA256,
+            (OID_RSA, _, OID_SHA384) => &ring_signature::RSA_PKCS1_2048_8192_SHA384,
+            (OID_RSA, _, OID_SHA512) => &ring_signature::RSA_PKCS1_2048_8192_SHA512,
+            (OID_EC_PUBLIC_KEY, Some(OID_P256), OID_SHA256) => {
+                &ring_signature::ECDSA_P256_SHA256_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P256), OID_SHA384) => {
+                &ring_signature::ECDSA_P256_SHA384_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P384), OID_SHA256) => {
+                &ring_signature::ECDSA_P384_SHA256_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P384), OID_SHA384) => {
+                &ring_signature::ECDSA_P384_SHA384_ASN1
+            }
+            _ => return Err("Unsupported key or digest algorithm of the TSA".into()),
+        })
+    }
+}
+
+/// Returns a certificate's `UTCTime` or `GeneralizedTime` as `YYYYMMDDhhmmss`.
+fn certificate_time(element: Element<'_>) -> Result<String, Box<dyn std::error::Error>> {
+    let time = std::str::from_utf8(element.contents)?;
+    if !time.bytes().take(12).all(|b| b.is_ascii_digit()) {
+        return Err("Invalid validity in TSA certificate".into());
+    }
+    let time = match element.tag {
+        // Two-digit years from 50 on are in the 20th century, see RFC 5280
+        UTC_TIME if time.len() >= 12 => {
+            let century = if &time[..2] < "50" { "20" } else { "19" };
+            format!("{}{}", century, &time[..12])
+        }
+        GENERALIZED_TIME
+            if time
+                .get(12..14)
+                .is_some_and(|seconds| seconds.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            time[..14].to_string()
+        }
+        _ => return Err("Invalid validity in TSA certificate".into()),
+    };
+    Ok(time)
+}
+
+/// Encodes a DER element.
+fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![tag];
+    let len = contents.len().to_be_bytes();
+    match unsigned(&len) {
+        [short] if *short < 0x80 => bytes.push(*short),
+        long => {
+            bytes.push(0x80 | long.len() as u8);
+            bytes.extend_from_slice(long);
+        }
+    }
+    bytes.extend_from_slice(contents);
+    bytes
+}
+
+/// Returns the contents of a DER integer with the unsigned big-endian value `bytes`.
+fn integer(bytes: &[u8]) -> Vec<u8> {
+    let bytes = unsigned(bytes);
+    match bytes.first() {
+        None => vec![0],
+        Some(first) if first & 0x80 != 0 => [&[0], bytes].concat(),
+        Some(_) => bytes.to_vec(),
+    }
+}
+
+/// Strips the leading zero bytes of a big-endian integer.
+fn unsigned(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// A DER element.
+struct Element<'a> {
+    tag: u8,
+    contents: &'a [u8],
+    /// The whole element, tag and length included.
+    raw: &'a [u8],
+}
+
+/// Reads DER elements one after another.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn next(&mut self) -> Result<Element<'a>, Box<dyn std::error::Error>> {
+        let truncated = "Truncated DER in timestamp";
+        let (&tag, rest) = self.bytes.split_first().ok_or(truncated)?;
+        let (&first, rest) = rest.split_first().ok_or(truncated)?;
+        let (len, rest) = match first {
+            0..=0x7f => (usize::from(first), rest),
+            0x81..=0x84 => {
+                let count = usize::from(first & 0x7f);
+                if rest.len() < count {
+                    return Err(truncated.into());
+                }
+                let len = rest[..count]
+                    .iter()
+                    .fold(0usize, |len, &b| (len << 8) | usize::from(b));
+                (len, &rest[count..])
+            }
+            _ => return Err("Unsupported DER length in timestamp".into()),
+        };
+        if rest.len() < len {
+            return Err(truncated.into());
+        }
+        let header = self.bytes.len() - rest.len();
+        let element = Element {
+            tag,
+            contents: &rest[..len],
+            raw: &self.bytes[..header + len],
+        };
+        self.bytes = &rest[len..];
+        Ok(element)
+    }
+
+    /// Reads the next element, which must have the given tag, and returns its contents.
+    fn expect(&mut self, tag: u8) -> Result<&'a [u8], Box<dyn std::error::Error>> {
+        let element = self.next()?;
+        if element.tag != tag {
+            return Err(format!(
+                "Unexpected DER tag {:#04x} in timestamp, expected {:#04x}",
+                element.tag, tag
+            )
+            .into());
+        }
+        Ok(element.contents)
+    }
+
+    /// Reads the next element if it has the given tag.
+    fn optional(&mut self, tag: u8) -> Result<Option<&'a [u8]>, Box<dyn std::error::Error>> {
+        if self.bytes.first() == Some(&tag) {
+            self.expect(tag).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
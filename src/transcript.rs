@@ -0,0 +1,304 @@
+//! Append-only transcripts of signing ceremonies, as evidence of who signed what.
+//!
+//! The signing functions that take a `transcript_file` append what their ceremony did
+//! to it, one JSON object per line: the group key and threshold, the session, the
+//! signers' participant indices, the SHA-256 hashes of the commitments they signed
+//! with, the hash of the message, the time and the threshold signature. With `None`
+//! nothing is recorded. Coordinator sessions also record every commitment and partial signature
+//! as it arrives, and a failed aggregation is recorded with its error.
+//!
+//! Entries are only ever appended, under an exclusive lock of the transcript, so
+//! ceremonies sharing one never lose each other's entries. Each entry holds the
+//! SHA-256 hash of the line before it, so an entry removed, changed or inserted later
+//! breaks the chain, which [`read_transcript`] checks along with every ristretto255
+//! signature. The transcript is not signed: whoever can write it can rewrite it from
+//! the altered entry on, so keep the hash of its last entry somewhere else to tell.
+
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::io::output::report;
+use frost_dalek::signature::ThresholdSignature;
+use frost_dalek::GroupKey;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What a transcript entry records.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CeremonyEvent {
+    /// A signer's commitments were added to a session.
+    CommitmentsAdded,
+    /// A signer's partial signature was added to a session.
+    PartialSignatureAdded,
+    /// The signers made the threshold signature.
+    Signed,
+    /// The partial signatures could not be aggregated.
+    AggregationFailed,
+}
+
+/// The commitments a signer signed with.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CommitmentHash {
+    /// Participant index of the signer.
+    pub index: u32,
+    /// Hex-encoded SHA-256 hash of the commitments, see [`crate::nonces::commitment_id`]
+    /// for ristretto255.
+    pub hash: String,
+}
+
+/// One step of a signing ceremony.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct CeremonyRecord {
+    pub event: CeremonyEvent,
+    pub ciphersuite: Ciphersuite,
+    /// Hex-encoded group key.
+    pub group_key: String,
+    pub threshold: u32,
+    /// Session ID of the signers' round messages, left out of ceremonies run in one
+    /// process.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Participant indices of the signers the step is about.
+    pub signers: Vec<u32>,
+    /// The commitments of those signers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commitments: Vec<CommitmentHash>,
+    /// Hex-encoded hash of the message: for ristretto255 the hash of the message in the
+    /// signing context the signature is made over, otherwise its SHA-512 hash.
+    pub message_hash: String,
+    /// Hex-encoded threshold signature, once made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Why the step failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A line of a transcript.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// Position of the entry in the transcript, from 1.
+    pub sequence: u64,
+    /// Unix time the entry was recorded at.
+    pub timestamp: u64,
+    /// Hex-encoded SHA-256 hash of the line before, without its newline, or of nothing
+    /// for the first entry.
+    pub previous: String,
+    #[serde(flatten)]
+    pub record: CeremonyRecord,
+}
+
+impl CeremonyRecord {
+    /// Returns the record of a step without a session, commitments or outcome.
+    pub(crate) fn new(
+        event: CeremonyEvent,
+        ciphersuite: Ciphersuite,
+        group_key: &[u8; 32],
+        threshold: u32,
+        signers: Vec<u32>,
+        message_hash: &[u8],
+    ) -> Self {
+        CeremonyRecord {
+            event,
+            ciphersuite,
+            group_key: hex::encode(group_key),
+            threshold,
+            session_id: None,
+            signers,
+            commitments: Vec::new(),
+            message_hash: hex::encode(message_hash),
+            signature: None,
+            error: None,
+        }
+    }
+}
+
+/// Appends a ceremony step to `transcript_file`, or does nothing without one.
+///
+/// # Errors
+/// Returns an error if the transcript cannot be locked, read or written, or its last
+/// line is not an entry.
+pub(crate) fn append(
+    transcript_file: Option<&str>,
+    record: CeremonyRecord,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(transcript_file) = transcript_file else {
+        return Ok(());
+    };
+
+    // Step 1: Lock the transcript against concurrent ceremonies until it is dropped
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(transcript_file)
+        .map_err(|err| FrostCliError::io(transcript_file, err))?;
+    file.lock()
+        .map_err(|err| FrostCliError::io(transcript_file, err))?;
+
+    // Step 2: Chain the entry to the last line
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|err| FrostCliError::io(transcript_file, err))?;
+    let (sequence, previous) = match contents.lines().last() {
+        Some(line) => {
+            let last: TranscriptEntry = serde_json::from_str(line).map_err(|err| {
+                format!(
+                    "Last line of transcript {} is not an entry: {}",
+                    transcript_file, err
+                )
+            })?;
+            (last.sequence + 1, line_hash(line))
+        }
+        None => (1, line_hash("")),
+    };
+    let entry = TranscriptEntry {
+        sequence,
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        previous,
+        record,
+    };
+
+    // Step 3: Append it and make sure it is on disk before the ceremony goes on
+    let mut line = serde_json::to_string(&entry)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|err| FrostCliError::io(transcript_file, err))?;
+    Ok(())
+}
+
+/// Appends the outcome of an aggregation to `transcript_file`, if one is given: the
+/// signature it made, or why it failed.
+///
+/// # Errors
+/// Returns an error if the transcript cannot be written, see [`append`].
+pub(crate) fn append_outcome<S: AsRef<[u8]>, E: fmt::Display>(
+    transcript_file: Option<&str>,
+    mut record: CeremonyRecord,
+    outcome: &Result<S, E>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match outcome {
+        Ok(signature) => record.signature = Some(hex::encode(signature)),
+        Err(err) => {
+            record.event = CeremonyEvent::AggregationFailed;
+            record.error = Some(err.to_string());
+        }
+    }
+    append(transcript_file, record)
+}
+
+/// Reads a transcript and checks that every entry follows the one before it and that
+/// every ristretto255 signature it records verifies.
+///
+/// # Errors
+/// Returns an error if the transcript cannot be read, a line is not an entry, the chain
+/// is broken, or a recorded signature does not verify.
+pub fn read_transcript(
+    transcript_file: &str,
+) -> Result<Vec<TranscriptEntry>, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(transcript_file)
+        .map_err(|err| FrostCliError::io(transcript_file, err))?;
+    let mut entries: Vec<TranscriptEntry> = Vec::new();
+    let mut previous = line_hash("");
+    for (number, line) in contents.lines().enumerate() {
+        let entry: TranscriptEntry = serde_json::from_str(line).map_err(|err| {
+            format!(
+                "Line {} of transcript {} is not an entry: {}",
+                number + 1,
+                transcript_file,
+                err
+            )
+        })?;
+        if entry.sequence != number as u64 + 1 || entry.previous != previous {
+            return Err(format!(
+                "Line {} of transcript {} does not follow the line before it, the \
+                 transcript was altered",
+                number + 1,
+                transcript_file
+            )
+            .into());
+        }
+        check_signature(&entry.record).map_err(|err| {
+            format!(
+                "Signature on line {} of transcript {} does not verify: {}",
+                number + 1,
+                transcript_file,
+                err
+            )
+        })?;
+        previous = line_hash(line);
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Checks a transcript and reports how many entries and signatures it holds, and the
+/// hash of its last line to compare against a copy kept elsewhere.
+///
+/// # Errors
+/// Returns an error if the transcript does not check, see [`read_transcript`].
+pub fn check(transcript_file: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = read_transcript(transcript_file)?;
+    let signatures = entries
+        .iter()
+        .filter(|entry| entry.record.event == CeremonyEvent::Signed)
+        .count();
+    let last_hash = fs::read_to_string(transcript_file)
+        .map_err(|err| FrostCliError::io(transcript_file, err))?
+        .lines()
+        .last()
+        .map(line_hash)
+        .unwrap_or_else(|| line_hash(""));
+    report(
+        "transcript_checked",
+        &format!(
+            "Transcript {} holds {} entries and {} signatures in an unbroken chain, the \
+             last entry hashing to {}",
+            transcript_file,
+            entries.len(),
+            signatures,
+            last_hash
+        ),
+        json!({
+            "transcript_file": transcript_file,
+            "entries": entries.len(),
+            "signatures": signatures,
+            "last_hash": last_hash,
+        }),
+    );
+    Ok(())
+}
+
+/// Returns the hex-encoded SHA-256 hash of a transcript line.
+fn line_hash(line: &str) -> String {
+    hex::encode(Sha256::digest(line.as_bytes()))
+}
+
+/// Verifies the signature of a `signed` ristretto255 entry over its message hash. The
+/// message hash of other ciphersuites is not what they sign, so they are not checked.
+fn check_signature(record: &CeremonyRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(signature), true) = (&record.signature, record.ciphersuite.is_ristretto255()) else {
+        return Ok(());
+    };
+    let group_key: [u8; 32] = hex::decode(&record.group_key)?
+        .try_into()
+        .map_err(|_| "Group key must be 32 bytes")?;
+    let group_key = GroupKey::from_bytes(group_key).map_err(|_| "Invalid group public key")?;
+    let message_hash: [u8; 64] = hex::decode(&record.message_hash)?
+        .try_into()
+        .map_err(|_| "Message hash must be 64 bytes")?;
+    let signature: [u8; 64] = hex::decode(signature)?
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature")?;
+    ThresholdSignature::from_bytes(signature)
+        .map_err(|_| "Failed to deserialize ThresholdSignature")?
+        .verify(&group_key, &message_hash)
+        .map_err(|_| "Signature verification failed")?;
+    Ok(())
+}
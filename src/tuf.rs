@@ -0,0 +1,250 @@
+//! Threshold signatures on TUF metadata, see the [TUF specification].
+//!
+//! TUF signs the canonical JSON of the `signed` object of a metadata file, and lists the
+//! signatures next to it as objects with the signing key's ID and the hex signature.
+//! The key ID is the SHA-256 hash of the canonical JSON of the key, so an Ed25519 group
+//! key added to the root metadata with [`key`] is recognised like any other `ed25519`
+//! key, and a threshold of participants can stand in for one of a role's keys.
+//!
+//! Canonical JSON here is the OLPC form TUF implementations use: sorted keys, no
+//! whitespace, only `\` and `"` escaped, and integers as the only numbers.
+//!
+//! [TUF specification]: https://theupdateframework.github.io/specification/latest/
+
+use crate::backend::{self, Ciphersuite};
+use crate::io::files::FileOptions;
+use crate::io::output::report;
+use crate::{load_group_key_bytes, load_signing_keys, SIGNING_CONTEXT};
+use rand::rngs::OsRng;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Returns the canonical JSON encoding of a value.
+///
+/// # Errors
+/// Returns an error if the value holds a number that is not an integer.
+pub fn canonical_json(value: &Value) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut bytes = Vec::new();
+    write_canonical(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn write_canonical(value: &Value, out: &mut Vec<u8>) -> Result<(), Box<dyn std::error::Error>> {
+    match value {
+        Value::Null => out.extend(b"null"),
+        Value::Bool(b) => out.extend(if *b { &b"true"[..] } else { &b"false"[..] }),
+        Value::Number(n) if n.is_i64() || n.is_u64() => out.extend(n.to_string().into_bytes()),
+        Value::Number(n) => {
+            return Err(format!("Canonical JSON has no floating point numbers: {}", n).into())
+        }
+        Value::String(s) => write_string(s, out),
+        Value::Array(values) => {
+            out.push(b'[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(value, out)?;
+            }
+            out.push(b']');
+        }
+        Value::Object(object) => {
+            let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push(b'{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_string(key, out);
+                out.push(b':');
+                write_canonical(value, out)?;
+            }
+            out.push(b'}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(s: &str, out: &mut Vec<u8>) {
+    out.push(b'"');
+    for c in s.chars() {
+        if c == '\\' || c == '"' {
+            out.push(b'\\');
+        }
+        let mut buf = [0; 4];
+        out.extend(c.encode_utf8(&mut buf).as_bytes());
+    }
+    out.push(b'"');
+}
+
+/// Returns the TUF key object of an Ed25519 group key.
+///
+/// # Errors
+/// Returns an error if the ciphersuite has no TUF key type.
+pub fn key(
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+) -> Result<Value, Box<dyn std::error::Error>> {
+    if ciphersuite != Ciphersuite::Ed25519 {
+        return Err(format!(
+            "TUF has no key type for {} signatures, generate the keys with --ciphersuite ed25519",
+            ciphersuite
+        )
+        .into());
+    }
+    Ok(json!({
+        "keytype": "ed25519",
+        "scheme": "ed25519",
+        "keyval": { "public": hex::encode(group_key) },
+    }))
+}
+
+/// Returns the key ID of a TUF key object.
+///
+/// # Errors
+/// Returns an error if the key cannot be canonicalized.
+pub fn key_id(key: &Value) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(hex::encode(Sha256::digest(&canonical_json(key)?)))
+}
+
+/// Returns the group key of a key file as a `keys` entry of TUF root metadata, mapping
+/// its key ID to the key object.
+///
+/// # Errors
+/// Returns an error if the key file cannot be loaded or its ciphersuite has no TUF key
+/// type.
+pub fn export_key(
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let key = key(ciphersuite, &group_key)?;
+    let mut entry = serde_json::Map::new();
+    entry.insert(key_id(&key)?, key);
+    Ok(format!("{}\n", serde_json::to_string_pretty(&entry)?))
+}
+
+/// Threshold-signs a TUF metadata file and adds the signature to its `signatures`.
+///
+/// A signature by the same key ID already in the file is replaced.
+///
+/// # Arguments
+/// - `metadata_file`: Path to the TUF metadata, such as `root.json` or `targets.json`.
+/// - `signers`: Indices of the participants signing.
+/// - `n`: Total number of participants.
+/// - `key_file`: Path to the key file of an Ed25519 group key.
+/// - `output_file`: Path to save the signed metadata to, the metadata file if `None`.
+/// - `transcript_file`: Transcript to record the ceremony in, see [`crate::transcript`],
+///   or `None`.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the file is not TUF metadata, the key has no TUF key type, or
+/// signing fails.
+pub fn sign_metadata(
+    metadata_file: &str,
+    signers: Vec<u32>,
+    n: u32,
+    key_file: &str,
+    output_file: Option<&str>,
+    transcript_file: Option<&str>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Load the metadata and canonicalize what is signed
+    let mut metadata: Value = serde_json::from_slice(&fs::read(metadata_file)?)?;
+    let signed = metadata.get("signed").ok_or_else(|| {
+        format!(
+            "{} is not TUF metadata, it has no signed object",
+            metadata_file
+        )
+    })?;
+    let canonical = canonical_json(signed)?;
+
+    // Step 2: Sign with the group key
+    let frost_keys = load_signing_keys(&signers, n, key_file, options)?;
+    let key_id = key_id(&key(frost_keys.ciphersuite, &frost_keys.group_key)?)?;
+    let signature = backend::open(frost_keys.ciphersuite)?.sign(
+        &frost_keys,
+        &signers,
+        &canonical,
+        SIGNING_CONTEXT,
+        transcript_file,
+        &mut OsRng,
+    )?;
+
+    // Step 3: Replace the group's signature in the metadata
+    let signatures = metadata
+        .as_object_mut()
+        .ok_or("TUF metadata is not a JSON object")?
+        .entry("signatures")
+        .or_insert_with(|| json!([]))
+        .as_array_mut()
+        .ok_or("TUF signatures are not a list")?;
+    signatures.retain(|signature| {
+        signature.get("keyid").and_then(Value::as_str) != Some(key_id.as_str())
+    });
+    signatures.push(json!({ "keyid": key_id, "sig": hex::encode(signature) }));
+
+    // Step 4: Save the metadata
+    let output_file = output_file.unwrap_or(metadata_file);
+    fs::write(output_file, serde_json::to_vec_pretty(&metadata)?)?;
+    report(
+        "tuf_metadata_signed",
+        &format!(
+            "TUF metadata signed by key ID {} saved to: {}",
+            key_id, output_file
+        ),
+        json!({ "key_id": key_id, "metadata_file": output_file }),
+    );
+    Ok(())
+}
+
+/// Checks the group's signature on a TUF metadata file.
+///
+/// # Errors
+/// Returns an error if the metadata has no signature with the group key's ID or the
+/// signature is invalid.
+pub fn verify_metadata(
+    metadata_file: &str,
+    key_file: &str,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Step 1: Find the group's signature
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+    let key_id = key_id(&key(ciphersuite, &group_key)?)?;
+    let metadata: Value = serde_json::from_slice(&fs::read(metadata_file)?)?;
+    let signed = metadata.get("signed").ok_or_else(|| {
+        format!(
+            "{} is not TUF metadata, it has no signed object",
+            metadata_file
+        )
+    })?;
+    let signature = metadata
+        .get("signatures")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .find(|signature| signature.get("keyid").and_then(Value::as_str) == Some(key_id.as_str()))
+        .and_then(|signature| signature.get("sig").and_then(Value::as_str))
+        .ok_or_else(|| format!("TUF metadata has no signature by key ID {}", key_id))?;
+
+    // Step 2: Verify it over the canonical signed object
+    let signature: [u8; 64] = hex::decode(signature)?
+        .try_into()
+        .map_err(|_| "Invalid length for threshold signature")?;
+    backend::open(ciphersuite)?.verify(
+        &group_key,
+        &canonical_json(signed)?,
+        SIGNING_CONTEXT,
+        &signature,
+    )?;
+
+    report(
+        "tuf_signature_verified",
+        &format!("TUF signature by key ID {} is valid!", key_id),
+        json!({ "valid": true, "key_id": key_id, "metadata_file": metadata_file }),
+    );
+    Ok(())
+}
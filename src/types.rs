@@ -0,0 +1,495 @@
+//! Types and settings shared by key generation, signing and verification.
+//!
+//! The key files, participant shares and signature files the other modules read and
+//! write, the error of a signature that does not verify, the progress of a key
+//! generation, and the default context string messages are signed in. The public items
+//! are re-exported at the crate root.
+
+use crate::artifact::SignedFile;
+use crate::backend::Ciphersuite;
+use crate::error::FrostCliError;
+use crate::io::envelope;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Context string hashed together with a signed message, the one the CLI signs and
+/// verifies in unless `--context` names another.
+///
+/// Every function that signs or verifies takes the context as an argument. Signatures
+/// made in one context never verify in another, so applications sharing a group key can
+/// each sign in their own context without a signature for one being accepted by the
+/// other. Only the ristretto255 ciphersuite hashes messages with a context, see
+/// [`Ciphersuite::signing_context`].
+pub const SIGNING_CONTEXT: &str = "THRESHOLD SIGNING CONTEXT";
+
+/// Returns [`SIGNING_CONTEXT`], for requests that leave their context out.
+pub(crate) fn default_signing_context() -> String {
+    SIGNING_CONTEXT.to_string()
+}
+
+/// A cryptographically secure random number generator that signing nonces and key
+/// material are drawn from.
+///
+/// Library functions that take one are generic over `RngCore + CryptoRng` and default
+/// to the operating system's generator in their variants without one. Supplying a
+/// seeded DRBG makes tests deterministic, and a hardware RNG can be used in
+/// production. The [`crate::backend::Backend`] trait takes it as `&mut dyn SecureRng`, which
+/// any such generator is.
+pub trait SecureRng: RngCore + CryptoRng {}
+
+impl<R: RngCore + CryptoRng> SecureRng for R {}
+
+/// Returns a ChaCha20 generator seeded with the SHA-256 hash of `seed`, which draws the
+/// same numbers for the same seed on every run.
+///
+/// **Anything drawn from it is known to whoever knows the seed.** It is only meant for
+/// tests and reproducible demos, and needs the `insecure-deterministic` feature.
+///
+/// # Errors
+/// Returns an error if frost-cli was built without the `insecure-deterministic` feature.
+#[cfg(feature = "insecure-deterministic")]
+pub fn seeded_rng(seed: &[u8]) -> Result<Box<dyn SecureRng>, Box<dyn std::error::Error>> {
+    use rand::SeedableRng;
+    use sha2::{Digest, Sha256};
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&Sha256::digest(seed));
+    Ok(Box::new(rand_chacha::ChaCha20Rng::from_seed(key)))
+}
+
+#[cfg(not(feature = "insecure-deterministic"))]
+pub fn seeded_rng(_seed: &[u8]) -> Result<Box<dyn SecureRng>, Box<dyn std::error::Error>> {
+    Err("frost-cli was built without the insecure-deterministic feature".into())
+}
+
+/// Version of the key file and signature file formats written by this release.
+///
+/// Files without a version were written before formats were versioned, and version 1
+/// files have no checksum. Both can be upgraded with [`crate::migrate::migrate`].
+pub const FORMAT_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FrostKeys {
+    /// Format version, see [`FORMAT_VERSION`].
+    pub version: u32,
+    /// Ciphersuite of the keys, left out of Ristretto key files, see [`crate::backend`].
+    #[serde(default, skip_serializing_if = "Ciphersuite::is_ristretto255")]
+    pub ciphersuite: Ciphersuite,
+    pub group_key: [u8; 32],
+    pub private_shares: Vec<([u8; 32], u32)>,
+    pub threshold: u32,
+    /// Public verification share of every participant, that of participant `i` at
+    /// position `i - 1`, which their partial signatures are checked against. Empty in
+    /// key files saved before verification shares were stored, and for ciphersuites
+    /// other than ristretto255, whose backends derive them from the shares.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_shares: Vec<[u8; 32]>,
+}
+
+impl Drop for FrostKeys {
+    fn drop(&mut self) {
+        for (share, _) in &mut self.private_shares {
+            share.zeroize();
+        }
+    }
+}
+
+impl FrostKeys {
+    /// Checks that the threshold is at least 1 and that every share belongs to a
+    /// different participant, whose index starts at 1.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.threshold == 0 {
+            return Err("Threshold must be at least 1".into());
+        }
+        for (i, (_, index)) in self.private_shares.iter().enumerate() {
+            if *index == 0 {
+                return Err("Participant IDs start at 1, there is no participant 0".into());
+            }
+            if self.private_shares[..i]
+                .iter()
+                .any(|(_, other)| other == index)
+            {
+                return Err(format!("Participant {} has two shares", index));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the positions in `private_shares` of the shares of the given participants,
+    /// which the signing functions take.
+    ///
+    /// # Errors
+    /// Returns [`FrostCliError::InvalidParameters`] if a participant has no share in the
+    /// keys or is given twice.
+    pub fn positions(&self, participants: &[ParticipantId]) -> Result<Vec<u32>, FrostCliError> {
+        let mut positions = Vec::with_capacity(participants.len());
+        for (i, participant) in participants.iter().enumerate() {
+            if participants[..i].contains(participant) {
+                return Err(FrostCliError::InvalidParameters(format!(
+                    "Participant {} is given twice",
+                    participant
+                )));
+            }
+            let position = self
+                .private_shares
+                .iter()
+                .position(|(_, index)| *index == participant.get())
+                .ok_or_else(|| {
+                    let indices: Vec<u32> = self
+                        .private_shares
+                        .iter()
+                        .map(|(_, index)| *index)
+                        .collect();
+                    FrostCliError::InvalidParameters(format!(
+                        "Participant {} has no share in the key file, which holds participants \
+                         {:?}",
+                        participant, indices
+                    ))
+                })?;
+            positions.push(position as u32);
+        }
+        Ok(positions)
+    }
+}
+
+/// The index of a participant in its group, which FROST numbers from 1.
+///
+/// The shares of a dealer key file are stored with their participants' indices, and
+/// their positions in the file, from 0, only match the indices minus one until
+/// participants are revoked. [`FrostKeys::positions`] translates participant IDs into
+/// the positions the signing functions take.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParticipantId(u32);
+
+impl ParticipantId {
+    /// Returns the ID of participant `index`.
+    ///
+    /// # Errors
+    /// Returns [`FrostCliError::InvalidParameters`] if `index` is 0.
+    pub fn new(index: u32) -> Result<Self, FrostCliError> {
+        if index == 0 {
+            return Err(FrostCliError::InvalidParameters(
+                "Participant IDs start at 1, there is no participant 0".into(),
+            ));
+        }
+        Ok(ParticipantId(index))
+    }
+
+    /// Returns the participant's index.
+    pub fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for ParticipantId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ParticipantId {
+    type Err = FrostCliError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index = s.trim().parse().map_err(|_| {
+            FrostCliError::InvalidParameters(format!("Invalid participant ID: {}", s))
+        })?;
+        ParticipantId::new(index)
+    }
+}
+
+/// A single participant's private share and the public parameters of its group.
+///
+/// Unlike [`FrostKeys`], a share file only ever contains its owner's share.
+#[derive(Serialize, Deserialize)]
+pub struct ParticipantShare {
+    pub index: u32,
+    pub share: [u8; 32],
+    pub group_key: [u8; 32],
+    pub threshold: u32,
+    pub participants: u32,
+    /// Public verification share of every participant, see
+    /// [`FrostKeys::verification_shares`]. Empty in shares restored from a mnemonic or
+    /// paper backup, which only hold the share itself.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verification_shares: Vec<[u8; 32]>,
+}
+
+impl ParticipantShare {
+    /// Checks that the share's index and threshold are between 1 and its number of
+    /// participants.
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.index == 0 || self.index > self.participants {
+            return Err(format!(
+                "Participant {} is not one of the {} participants",
+                self.index, self.participants
+            ));
+        }
+        if self.threshold == 0 || self.threshold > self.participants {
+            return Err(format!(
+                "Threshold {} is not between 1 and the {} participants",
+                self.threshold, self.participants
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ParticipantShare {
+    fn drop(&mut self) {
+        self.share.zeroize();
+    }
+}
+
+/// A threshold signature file.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureFile {
+    /// Format version, see [`FORMAT_VERSION`].
+    pub version: u32,
+    /// The 64-byte threshold signature.
+    pub signature: Vec<u8>,
+    /// How the signature was made, left out of signatures saved without it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SignatureMetadata>,
+    /// The file the signature was made over, left out of message signatures, see
+    /// [`crate::artifact`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<SignedFile>,
+    /// DER RFC 3161 timestamp token over the signature, see [`crate::timestamp`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_token: Option<Vec<u8>>,
+}
+
+/// What a signature file records about how its signature was made.
+///
+/// The metadata is not covered by the signature. It tells which group and signers made
+/// a signature and when, but anyone holding the file can change it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct SignatureMetadata {
+    /// Hex-encoded SHA-256 hash of the group key, see [`crate::io::envelope::key_id`].
+    pub group_key_fingerprint: String,
+    pub ciphersuite: Ciphersuite,
+    /// Context string the message was hashed with, left out for ciphersuites that sign
+    /// messages as they are, see [`Ciphersuite::signing_context`].
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub context: String,
+    /// Participant indices of the signers.
+    pub signers: Vec<u32>,
+    /// Unix time the signature was made at.
+    pub timestamp: u64,
+}
+
+impl SignatureMetadata {
+    /// Returns the metadata of a signature the signers are making now in `context`.
+    ///
+    /// # Errors
+    /// Returns an error if the system clock is before the Unix epoch.
+    pub fn new(
+        ciphersuite: Ciphersuite,
+        context: &str,
+        group_key: &[u8; 32],
+        signers: Vec<u32>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(SignatureMetadata {
+            group_key_fingerprint: envelope::fingerprint(group_key),
+            ciphersuite,
+            context: ciphersuite.signing_context(context).to_string(),
+            signers,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        })
+    }
+
+    /// Checks that the metadata describes a signature by the given group key in
+    /// `context`.
+    ///
+    /// # Errors
+    /// Returns an error if the metadata names another group key, ciphersuite or context,
+    /// or its signers are empty or repeated.
+    pub fn check(
+        &self,
+        ciphersuite: Ciphersuite,
+        context: &str,
+        group_key: &[u8; 32],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let fingerprint = envelope::fingerprint(group_key);
+        if self.group_key_fingerprint != fingerprint {
+            return Err(format!(
+                "Signature file names group key {}, expected {}",
+                self.group_key_fingerprint, fingerprint
+            )
+            .into());
+        }
+        if self.ciphersuite != ciphersuite {
+            return Err(format!(
+                "Signature file names ciphersuite {}, expected {}",
+                self.ciphersuite, ciphersuite
+            )
+            .into());
+        }
+        let context = ciphersuite.signing_context(context);
+        if self.context != context {
+            return Err(format!(
+                "Signature file names signing context {:?}, expected {:?}",
+                self.context, context
+            )
+            .into());
+        }
+        let mut signers = self.signers.clone();
+        signers.sort_unstable();
+        signers.dedup();
+        if signers.is_empty() || signers.len() != self.signers.len() {
+            return Err(format!("Invalid signers in signature file: {:?}", self.signers).into());
+        }
+        Ok(())
+    }
+}
+
+/// Error of a signature that was read but is not valid for the message, file or group
+/// key it was checked against.
+///
+/// It tells a signature that does not verify apart from input that could not be read or
+/// parsed, so `verify` can exit with a code for each, see [`crate::validate_signature`].
+#[derive(Debug)]
+pub struct InvalidSignature(pub String);
+
+impl fmt::Display for InvalidSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidSignature {}
+
+/// Stage of a key generation, see [`KeygenProgress`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeygenStage {
+    /// Participants commit to their secret polynomials.
+    Commitments,
+    /// The participants' proofs of secret keys are verified.
+    Proofs,
+    /// Round 1 of the DKG, each participant creates the secret shares of the others.
+    RoundOne,
+    /// Round 2 of the DKG, each participant checks the secret shares it received.
+    RoundTwo,
+    /// Each participant derives its private share and the group key.
+    Finish,
+}
+
+impl fmt::Display for KeygenStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeygenStage::Commitments => write!(f, "Committing"),
+            KeygenStage::Proofs => write!(f, "Verifying proofs"),
+            KeygenStage::RoundOne => write!(f, "DKG round 1"),
+            KeygenStage::RoundTwo => write!(f, "DKG round 2"),
+            KeygenStage::Finish => write!(f, "Finishing DKG"),
+        }
+    }
+}
+
+/// Progress of a key generation, passed to the callback of
+/// [`crate::generate_keys_with_progress`] each time a participant gets through a stage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeygenProgress {
+    pub stage: KeygenStage,
+    /// Number of participants done with the stage.
+    pub done: u32,
+    /// Number of participants.
+    pub total: u32,
+}
+
+impl KeygenProgress {
+    pub(crate) fn new(stage: KeygenStage, done: u32, total: u32) -> Self {
+        KeygenProgress { stage, done, total }
+    }
+}
+
+/// Either kind of file that carries a group public key.
+pub(crate) enum KeyFile {
+    Keys(FrostKeys),
+    Share(ParticipantShare),
+}
+
+impl KeyFile {
+    /// Returns the compressed group public key.
+    pub(crate) fn group_key(&self) -> [u8; 32] {
+        match self {
+            KeyFile::Keys(frost_keys) => frost_keys.group_key,
+            KeyFile::Share(share) => share.group_key,
+        }
+    }
+
+    /// Returns the ciphersuite of the group. Participant shares are always Ristretto.
+    pub(crate) fn ciphersuite(&self) -> Ciphersuite {
+        match self {
+            KeyFile::Keys(frost_keys) => frost_keys.ciphersuite,
+            KeyFile::Share(_) => Ciphersuite::Ristretto255,
+        }
+    }
+
+    /// Returns the signing threshold of the group.
+    pub(crate) fn threshold(&self) -> u32 {
+        match self {
+            KeyFile::Keys(frost_keys) => frost_keys.threshold,
+            KeyFile::Share(share) => share.threshold,
+        }
+    }
+
+    /// Returns the public verification share of every participant, which is empty for
+    /// files saved before verification shares were stored.
+    pub(crate) fn verification_shares(&self) -> &[[u8; 32]] {
+        match self {
+            KeyFile::Keys(frost_keys) => &frost_keys.verification_shares,
+            KeyFile::Share(share) => &share.verification_shares,
+        }
+    }
+}
+
+/// Returns the UTC date of a Unix timestamp as `YYYY-MM-DD`.
+pub(crate) fn utc_date(timestamp: u64) -> String {
+    // Civil date from days since 1970-01-01, see Howard Hinnant's `civil_from_days`.
+    let days = (timestamp / 86400) as i64 + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub(crate) fn scalar_from_bytes(bytes: [u8; 32]) -> Result<Scalar, Box<dyn std::error::Error>> {
+    Scalar::from_canonical_bytes(bytes).ok_or_else(|| "Invalid scalar bytes".into())
+}
+
+/// Decodes secret scalars, which are wiped again once dropped.
+pub(crate) fn scalars_from_bytes(
+    bytes: &[[u8; 32]],
+) -> Result<Zeroizing<Vec<Scalar>>, Box<dyn std::error::Error>> {
+    Ok(Zeroizing::new(
+        bytes
+            .iter()
+            .map(|b| scalar_from_bytes(*b))
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+pub(crate) fn point_from_bytes(
+    bytes: &[u8; 32],
+) -> Result<RistrettoPoint, Box<dyn std::error::Error>> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or_else(|| "Invalid Ristretto point bytes".into())
+}
@@ -0,0 +1,336 @@
+//! Verification of threshold signatures.
+//!
+//! [`verify_with_group_key`] checks a signature on bytes held in memory in any
+//! ciphersuite. [`validate_signature`] and [`validate_file_signature`] check a signature
+//! file or envelope on a message or file against the group key of a key file, and
+//! report what the signature file records about the signing.
+
+use crate::artifact::SignedFile;
+use crate::backend::{self, Ciphersuite};
+use crate::error::FrostCliError;
+use crate::io;
+use crate::io::envelope::{self, EnvelopeOptions, OutFormat};
+use crate::io::files::{load_group_key_bytes, read_signature, FileOptions};
+use crate::io::output::report;
+use crate::io::signature::SignatureFormat;
+use crate::keystore::KeyStore;
+use crate::metrics::{self, Verification};
+use crate::types::{utc_date, InvalidSignature};
+use serde_json::json;
+use std::fs;
+
+/// Verifies a threshold signature on a message against a group key, without reading
+/// any file.
+///
+/// # Arguments
+/// - `message`: The bytes that were signed, see [`crate::sign_with_keys`].
+/// - `context`: Context string the message was signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `ciphersuite`: Ciphersuite of the group key.
+/// - `group_key`: The group public key, as in [`crate::FrostKeys::group_key`].
+/// - `signature`: The 64-byte threshold signature.
+///
+/// # Errors
+/// Returns an [`InvalidSignature`] error if the signature is not valid, or another error
+/// if the ciphersuite is not included in this build.
+pub fn verify_with_group_key(
+    message: &[u8],
+    context: &str,
+    ciphersuite: Ciphersuite,
+    group_key: &[u8; 32],
+    signature: &[u8; 64],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let verified = backend::open(ciphersuite)?.verify(group_key, message, context, signature);
+    metrics::verification(Verification::Signature, verified.is_ok());
+    verified.map_err(|err| InvalidSignature(err.to_string()).into())
+}
+
+/// Validates a threshold signature for a given message.
+///
+/// This function ensures that a provided signature matches the expected
+/// group public key and is valid for the provided message.
+///
+/// # Arguments
+///
+/// - `message`: The message whose signature needs validation.
+/// - `context`: Context string the message was signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to the JSON key file or participant share file containing the group public key.
+/// - `signature_file`: Path to the JSON file containing the threshold signature.
+///
+/// # Returns
+///
+/// - `Ok(())` if the signature is valid.
+/// - An [`InvalidSignature`] error if the signature is read but invalid, an error for
+///   which [`crate::error::is_io_error`] holds if a file cannot be read, or another error if
+///   any other validation step fails.
+pub fn validate_signature(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    signature_file: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    validate_signature_as(
+        message,
+        context,
+        key_file,
+        signature_file,
+        None,
+        &EnvelopeOptions::default(),
+    )
+}
+
+/// Validates a threshold signature saved in the given format, see [`validate_signature`].
+///
+/// # Arguments
+/// - `message`: The message whose signature needs validation.
+/// - `context`: Context string the message was signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to the key file or participant share file containing the group public key.
+/// - `signature_file`: Path to the file containing the threshold signature.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+/// - `options`: Parameters an envelope must have been made with, such as the namespace of
+///   an SSH signature.
+///
+/// # Errors
+/// Returns an error if the signature is not in the given format or is invalid.
+pub fn validate_signature_as(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &EnvelopeOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (message, fields) = check_signature(
+        message,
+        context,
+        key_file,
+        signature_file,
+        sig_format,
+        options,
+    )?;
+    report("signature_verified", &message, fields);
+    Ok(())
+}
+
+/// Validates a threshold signature against the group key of a key kept in a keystore,
+/// see [`validate_signature_as`].
+///
+/// # Arguments
+/// - `message`: The message whose signature needs validation.
+/// - `context`: Context string the message was signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `keystore`: The keystore holding the key, see [`KeyStore`].
+/// - `name`: Name of the key in the keystore.
+/// - `signature_file`: Path to the file containing the threshold signature.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+///
+/// # Errors
+/// Returns an error if the keystore has no key called `name`, or an [`InvalidSignature`]
+/// error if the signature is invalid.
+pub fn validate_signature_with_keystore(
+    message: &str,
+    context: &str,
+    keystore: &dyn KeyStore,
+    name: &str,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = keystore.load_group_key(name)?;
+    let (message, fields) = check_signature_with_group_key(
+        message,
+        context,
+        ciphersuite,
+        group_key,
+        signature_file,
+        sig_format,
+        &EnvelopeOptions::default(),
+    )?;
+    report("signature_verified", &message, fields);
+    Ok(())
+}
+
+/// Validates a threshold signature like [`validate_signature_as`] and returns what it
+/// reports, the result as a sentence and its fields, see [`io::output::report`].
+pub(crate) fn check_signature(
+    message: &str,
+    context: &str,
+    key_file: &str,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &EnvelopeOptions,
+) -> Result<(String, serde_json::Value), Box<dyn std::error::Error>> {
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, &options.files)?;
+    check_signature_with_group_key(
+        message,
+        context,
+        ciphersuite,
+        group_key,
+        signature_file,
+        sig_format,
+        options,
+    )
+}
+
+/// Validates a threshold signature like [`check_signature`], against a group key that
+/// is already loaded.
+fn check_signature_with_group_key(
+    message: &str,
+    context: &str,
+    ciphersuite: Ciphersuite,
+    group_key: [u8; 32],
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &EnvelopeOptions,
+) -> Result<(String, serde_json::Value), Box<dyn std::error::Error>> {
+    // Step 1: Load the signature and the bytes it was made over, from an envelope
+    // unless a signature format is given
+    let message = io::jcs::canonicalize_as(message, options.canonicalization)?;
+    let bytes = fs::read(signature_file).map_err(|err| FrostCliError::io(signature_file, err))?;
+    let mut metadata = None;
+    let (signing_input, signature) = match (io::envelope::detect(&bytes), sig_format) {
+        (OutFormat::Signature, _) | (_, Some(_)) => {
+            metadata = io::signature::decode_metadata(&bytes, signature_file)?;
+            (message, read_signature(signature_file, sig_format)?)
+        }
+        (out_format, None) => {
+            let opened = io::envelope::open(
+                out_format,
+                &bytes,
+                ciphersuite,
+                &group_key,
+                &message,
+                options,
+            )?;
+            (opened.signing_input, opened.signature)
+        }
+    };
+
+    // Step 2: Check what the signature file records against the group key and the
+    // signing context, so a signature from another context is reported as such
+    if let Some(metadata) = &metadata {
+        metadata
+            .check(ciphersuite, context, &group_key)
+            .map_err(|err| InvalidSignature(err.to_string()))?;
+    }
+
+    // Step 3: Verify the signature in the ciphersuite of the group key
+    verify_with_group_key(&signing_input, context, ciphersuite, &group_key, &signature)?;
+
+    // Step 4: Report what the signature file records about the signing
+    let mut message = String::from("Signature is valid!");
+    if let Some(metadata) = &metadata {
+        message.push_str(&format!(
+            "\nSigned by participants {:?} of group key {} ({}) on {} (Unix time {})",
+            metadata.signers,
+            metadata.group_key_fingerprint,
+            metadata.ciphersuite,
+            utc_date(metadata.timestamp),
+            metadata.timestamp
+        ));
+    }
+    let fields = json!({
+        "valid": true,
+        "signature_file": signature_file,
+        "group_key_fingerprint": envelope::fingerprint(&group_key),
+        "ciphersuite": ciphersuite,
+        "metadata": metadata,
+    });
+    Ok((message, fields))
+}
+
+/// Validates a threshold signature on a file, see [`crate::sign_file`].
+///
+/// # Arguments
+/// - `file`: Path to the file whose signature needs validation.
+/// - `context`: Context string the file was signed in, see [`crate::SIGNING_CONTEXT`].
+/// - `key_file`: Path to the key file or participant share file containing the group public key.
+/// - `signature_file`: Path to the file containing the threshold signature.
+/// - `sig_format`: Format the signature was saved in, detected from the file if `None`.
+/// - `options`: File key the key file was written with, see [`FileOptions::file_key`].
+///
+/// # Errors
+/// Returns an error if the signature file is an envelope, records another file, or the
+/// signature is invalid.
+pub fn validate_file_signature(
+    file: &str,
+    context: &str,
+    key_file: &str,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &FileOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (message, fields) =
+        check_file_signature(file, context, key_file, signature_file, sig_format, options)?;
+    report("signature_verified", &message, fields);
+    Ok(())
+}
+
+/// Validates a threshold signature on a file like [`validate_file_signature`] and
+/// returns what it reports, see [`check_signature`].
+pub(crate) fn check_file_signature(
+    file: &str,
+    context: &str,
+    key_file: &str,
+    signature_file: &str,
+    sig_format: Option<SignatureFormat>,
+    options: &FileOptions,
+) -> Result<(String, serde_json::Value), Box<dyn std::error::Error>> {
+    // Step 1: Load the public group key from the key file
+    let (ciphersuite, group_key) = load_group_key_bytes(key_file, options)?;
+
+    // Step 2: Load the signature and what the signature file records
+    let bytes = fs::read(signature_file).map_err(|err| FrostCliError::io(signature_file, err))?;
+    if sig_format.is_none() && io::envelope::detect(&bytes) != OutFormat::Signature {
+        return Err("Files are only signed as plain signature files".into());
+    }
+    let signature = read_signature(signature_file, sig_format)?;
+    let metadata = io::signature::decode_metadata(&bytes, signature_file)?;
+    let recorded = io::signature::decode_signed_file(&bytes, signature_file)?;
+
+    // Step 3: Hash the file and check it against the recorded one
+    let signed_file = SignedFile::hash(file)?;
+    if let Some(recorded) = &recorded {
+        recorded.check(&signed_file)?;
+    }
+
+    // Step 4: Check what the signature file records against the group key and the
+    // signing context, then verify the signature on the hash
+    if let Some(metadata) = &metadata {
+        metadata
+            .check(ciphersuite, context, &group_key)
+            .map_err(|err| InvalidSignature(err.to_string()))?;
+    }
+    verify_with_group_key(
+        &signed_file.signing_input()?,
+        context,
+        ciphersuite,
+        &group_key,
+        &signature,
+    )?;
+
+    // Step 5: Report what the signature file records about the signing
+    let mut message = format!(
+        "Signature is valid for {} ({} bytes, SHA-256 {})!",
+        signed_file.name, signed_file.size, signed_file.sha256
+    );
+    if let Some(recorded) = recorded.filter(|recorded| recorded.name != signed_file.name) {
+        message.push_str(&format!("\nThe file was signed as {}", recorded.name));
+    }
+    if let Some(metadata) = &metadata {
+        message.push_str(&format!(
+            "\nSigned by participants {:?} of group key {} ({}) on {} (Unix time {})",
+            metadata.signers,
+            metadata.group_key_fingerprint,
+            metadata.ciphersuite,
+            utc_date(metadata.timestamp),
+            metadata.timestamp
+        ));
+    }
+    let fields = json!({
+        "valid": true,
+        "signature_file": signature_file,
+        "group_key_fingerprint": envelope::fingerprint(&group_key),
+        "ciphersuite": ciphersuite,
+        "metadata": metadata,
+        "file": signed_file,
+    });
+    Ok((message, fields))
+}
@@ -0,0 +1,69 @@
+//! WebAssembly bindings for a participant's side of a ceremony, with the `wasm` feature.
+//!
+//! A signer running in a browser has no files and no CLI, but takes part in the same
+//! ceremonies as everyone else: it decrypts the share bundle a dealer sealed to it, see
+//! [`crate::dealer`], commits to nonces, and signs once the coordinator has the roster.
+//! Every function takes and returns JSON strings in the formats of the CLI's files, so
+//! what the browser produces can be uploaded to a coordinator as is, see
+//! [`crate::messages`]. Keeping the share and the secret commitments between calls, for
+//! example in IndexedDB, is left to the page.
+
+use crate::{participant, SIGNING_CONTEXT};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+/// Decrypts a share bundle with the participant's identity.
+///
+/// Takes the JSON of the identity file and of the share bundle, and returns the JSON of
+/// the share file, which the CLI reads as any other share file.
+#[wasm_bindgen(js_name = decryptShare)]
+pub fn decrypt_share(identity: &str, bundle: &str) -> Result<String, JsError> {
+    participant::decrypt_share(identity, bundle).map_err(js_error)
+}
+
+/// Runs signing round one for a share.
+///
+/// Takes the session ID and the JSON of the share file, and returns a JSON object with
+/// the `public_commitments` to upload and the `secret_commitments` to keep for
+/// [`partial_sign`].
+#[wasm_bindgen(js_name = commit)]
+pub fn commit(session_id: &str, share: &str) -> Result<String, JsError> {
+    let (public_commitments, secret_commitments) =
+        participant::commit(session_id, share).map_err(js_error)?;
+    Ok(json!({
+        "public_commitments": public_commitments,
+        "secret_commitments": secret_commitments,
+    })
+    .to_string())
+}
+
+/// Runs signing round two for a share.
+///
+/// Takes the message, the JSON of the share file, the secret commitments returned by
+/// [`commit`], the roster, a JSON array of every signer's public commitments, and
+/// optionally the context string the message is signed in, [`crate::SIGNING_CONTEXT`]
+/// if left out. Returns a JSON object with the `partial_signature` to upload and the
+/// remaining `secret_commitments`, which must replace the ones given before the partial
+/// signature is uploaded.
+#[wasm_bindgen(js_name = partialSign)]
+pub fn partial_sign(
+    message: &str,
+    share: &str,
+    secret_commitments: &str,
+    roster: &str,
+    context: Option<String>,
+) -> Result<String, JsError> {
+    let context = context.as_deref().unwrap_or(SIGNING_CONTEXT);
+    let (partial_signature, remaining) =
+        participant::partial_sign(message, context, share, secret_commitments, roster)
+            .map_err(js_error)?;
+    Ok(json!({
+        "partial_signature": partial_signature,
+        "secret_commitments": remaining,
+    })
+    .to_string())
+}
+
+fn js_error(err: Box<dyn std::error::Error>) -> JsError {
+    JsError::new(&err.to_string())
+}
@@ -0,0 +1,37 @@
+// Test module for locking secrets in memory, in its own binary because it enables locking
+// for the whole process
+#[cfg(test)]
+mod tests {
+    use frost_cli::{dkg, load_participant_share, memlock::{self, Locked}, sign_message_with_shares, validate_signature, SIGNING_CONTEXT};
+    use std::fs::{remove_dir_all, remove_file};
+
+    #[test]
+    fn test_mlock() {
+        let dkg_dir = "./results/test_mlock_dkg";
+        let state_files: Vec<String> = (1..=2).map(|i| format!("./results/test_mlock_state_{}.json", i)).collect();
+        let share_files: Vec<String> = (1..=2).map(|i| format!("./results/test_mlock_share_{}.json", i)).collect();
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, 2, 2, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+        for (state_file, share_file) in state_files.iter().zip(&share_files) {
+            dkg::finish(state_file, dkg_dir, share_file, &Default::default()).unwrap();
+            remove_file(state_file).unwrap();
+        }
+        remove_dir_all(dkg_dir).unwrap();
+
+        let signature_file = "./results/test_mlock_signature.json";
+        memlock::enable();
+        let share = Locked::new(load_participant_share(&share_files[0], &Default::default()).unwrap()).unwrap();
+        assert_eq!(share.index, 1);
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature with locked shares: {:?}", result.err());
+        for file in share_files.iter().map(String::as_str).chain([signature_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+}
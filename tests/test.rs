@@ -1,8 +1,86 @@
 // Test module for frost_cli
 #[cfg(test)]
 mod tests {
-    use frost_cli::{generate_keys, sign_message, validate_signature};
-    use std::fs::{self, remove_file};
+    use frost_cli::{
+        backup, dealer, dkg, echo, export_group_key, generate_keys, identity::{self, IdentityFiles}, io::format::{self, Format}, io::signature::{self, SignatureFormat}, keystore, load_participant_share, migrate, net::grpc, net::http, net::noise, net::p2p, net::relay, net::tcp, net::tls::{ClientTls, ServerTls}, net::ws, qr, session, save_participant_share, sign_message, transcript,
+        sign_message_with_shares, signing, validate_signature, validate_signature_as, SIGNING_CONTEXT,
+    };
+    use std::fs::{self, remove_dir_all, remove_file};
+    use std::path::Path;
+    use std::thread;
+
+    const SESSION_ID: &str = "00112233445566778899aabbccddeeff";
+
+    /// Runs all DKG rounds for `n` participants and returns their share files.
+    fn run_dkg(name: &str, t: u32, n: u32) -> Vec<String> {
+        let dkg_dir = format!("./results/{}_dkg", name);
+        let state_files: Vec<String> = (1..=n)
+            .map(|i| format!("./results/{}_state_{}.json", name, i))
+            .collect();
+        let share_files: Vec<String> = (1..=n)
+            .map(|i| format!("./results/{}_share_{}.json", name, i))
+            .collect();
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, t, n, state_file, &dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, &dkg_dir, &dkg_dir, &Default::default()).unwrap();
+        }
+        for (state_file, share_file) in state_files.iter().zip(&share_files) {
+            dkg::finish(state_file, &dkg_dir, share_file, &Default::default()).unwrap();
+            remove_file(state_file).unwrap();
+        }
+        remove_dir_all(&dkg_dir).unwrap();
+        share_files
+    }
+
+    /// Writes a CA, a server certificate for `localhost` and client certificates for
+    /// participants 1 to `n` into `dir`.
+    fn write_tls_certs(dir: &str, n: u32) -> (ServerTls, Vec<ClientTls>) {
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair, KeyUsagePurpose};
+        let participants_dir = format!("{}/participants", dir);
+        fs::create_dir_all(&participants_dir).unwrap();
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::DigitalSignature];
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let ca_file = format!("{}/ca.pem", dir);
+        fs::write(&ca_file, ca.pem()).unwrap();
+        let write_cert = |name: &str, cert_file: String| {
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(vec![name.to_string()])
+                .unwrap()
+                .signed_by(&key, &ca, &ca_key)
+                .unwrap();
+            let key_file = cert_file.replace(".pem", "_key.pem");
+            fs::write(&cert_file, cert.pem()).unwrap();
+            fs::write(&key_file, key.serialize_pem()).unwrap();
+            (cert_file, key_file)
+        };
+        let (cert_file, key_file) = write_cert("localhost", format!("{}/server.pem", dir));
+        let server = ServerTls {
+            cert_file,
+            key_file,
+            client_ca_file: ca_file.clone(),
+            participants_dir: participants_dir.clone(),
+        };
+        let clients = (1..=n)
+            .map(|i| {
+                let (cert_file, key_file) = write_cert(
+                    &format!("participant-{}", i),
+                    format!("{}/participant_{}.pem", dir, i),
+                );
+                fs::copy(&cert_file, format!("{}/participant_{}.pem", participants_dir, i)).unwrap();
+                ClientTls {
+                    ca_file: ca_file.clone(),
+                    cert_file,
+                    key_file,
+                }
+            })
+            .collect();
+        (server, clients)
+    }
 
     #[test]
     fn test_generate_keys() {
@@ -32,7 +110,7 @@ mod tests {
             result.err()
         );
         let message = "hi, this is a test";
-        let result = sign_message(message, vec![1, 2, 3], 5, &keys_file, &signature_file);
+        let result = sign_message(message, SIGNING_CONTEXT, vec![1, 2, 3], 5, &keys_file, &signature_file);
         assert!(
             result.is_ok(),
             "Failed to sign message with t=3, n=5: {:?}",
@@ -58,7 +136,7 @@ mod tests {
             result.err()
         );
         let message = "hi, this is a test";
-        let result = sign_message(message, vec![1,2,3,4], 5, &keys_file, &signature_file);
+        let result = sign_message(message, SIGNING_CONTEXT, vec![1,2,3,4], 5, &keys_file, &signature_file);
         assert!(
             result.is_ok(),
             "Signing should succeed with t=4, n=5 when keys were generated with t=3, n=5"
@@ -83,13 +161,13 @@ mod tests {
             result.err()
         );
         let message = "hi, this is a test";
-        let result = sign_message(message, vec![1,2,3], 5, &keys_file, &signature_file);
+        let result = sign_message(message, SIGNING_CONTEXT, vec![1,2,3], 5, &keys_file, &signature_file);
         assert!(
             result.is_ok(),
             "Failed to sign message with t=3, n=5: {:?}",
             result.err()
         );
-        let result = validate_signature(message, &keys_file, &signature_file);
+        let result = validate_signature(message, SIGNING_CONTEXT, &keys_file, &signature_file);
         assert!(
             result.is_ok(),
             "Failed to verify signature for message: {}",
@@ -99,49 +177,5328 @@ mod tests {
         remove_file(signature_file).unwrap();
     }
 
-    // Fail tests
     #[test]
-    fn test_sign_message_fail() {
-        let keys_file = "./results/test_sign_message_fail_frost_keys.json";
-        let signature_file = "./results/test_sign_message_fail_signature.json";
-        let result = generate_keys(2, 5, keys_file);
+    fn test_dkg() {
+        let dkg_dir = "./results/test_dkg";
+        let state_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_dkg_state_{}.json", i))
+            .collect();
+        let share_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_dkg_share_{}.json", i))
+            .collect();
+        let signature_file = "./results/test_dkg_signature.json";
+        for (i, state_file) in state_files.iter().enumerate() {
+            let result = dkg::round_one(i as u32 + 1, 2, 3, state_file, dkg_dir, &Default::default());
+            assert!(
+                result.is_ok(),
+                "DKG round 1 failed for participant {}: {:?}",
+                i + 1,
+                result.err()
+            );
+        }
+        for state_file in &state_files {
+            let result = dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default());
+            assert!(result.is_ok(), "DKG round 2 failed: {:?}", result.err());
+        }
+        for (state_file, share_file) in state_files.iter().zip(&share_files) {
+            let result = dkg::finish(state_file, dkg_dir, share_file, &Default::default());
+            assert!(result.is_ok(), "DKG finish failed: {:?}", result.err());
+        }
+        let group_keys: Vec<[u8; 32]> = share_files
+            .iter()
+            .map(|share_file| load_participant_share(share_file, &Default::default()).unwrap().group_key)
+            .collect();
+        assert!(
+            group_keys.iter().all(|key| *key == group_keys[0]),
+            "Participants derived different group keys"
+        );
+
+        // Any two of the three shares must produce a valid signature.
+        let message = "hi, this is a test";
+        let result = sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[1..], signature_file, None);
         assert!(
             result.is_ok(),
-            "Failed to generate keys with t=2, n=5: {:?}",
+            "Failed to sign message with DKG shares: {:?}",
+            result.err()
+        );
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature made with DKG shares: {:?}",
+            result.err()
+        );
+
+        for file in state_files.iter().chain(&share_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(dkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_commit() {
+        let share_files = run_dkg("test_commit", 2, 3);
+        let public_commitments_file = "./results/test_commit_public_commitments.json";
+        let secret_commitments_file = "./results/test_commit_secret_commitments.json";
+        let result = signing::commit(
+            SESSION_ID,
+            &share_files[0],
+            public_commitments_file,
+            secret_commitments_file,
+            signing::CommitOptions::default(),
+        );
+        assert!(
+            result.is_ok(),
+            "Failed to create commitments: {:?}",
             result.err()
         );
+        let file = fs::File::open(public_commitments_file).unwrap();
+        let public_commitments: signing::PublicCommitments =
+            serde_json::from_reader(file).unwrap();
+        assert_eq!(public_commitments.index, 1);
+        assert_eq!(public_commitments.commitments.len(), 1);
+        assert!(
+            fs::metadata(secret_commitments_file).is_ok(),
+            "Secret commitments file not found: {}",
+            secret_commitments_file
+        );
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_file(public_commitments_file).unwrap();
+        remove_file(secret_commitments_file).unwrap();
+    }
+
+    #[test]
+    fn test_partial_sign() {
+        let share_files = run_dkg("test_partial_sign", 2, 3);
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_partial_sign_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_partial_sign_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_partial_sign_partial_signature_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            share_files.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        let message = "hi, this is a test";
+        for ((share_file, secret_file), partial_file) in
+            share_files.iter().zip(&secret_files).zip(&partial_files)
+        {
+            let result = signing::partial_sign(
+                message,
+                SIGNING_CONTEXT,
+                share_file,
+                secret_file,
+                &public_files,
+                partial_file,
+                &Default::default(),
+            );
+            assert!(
+                result.is_ok(),
+                "Failed to create partial signature: {:?}",
+                result.err()
+            );
+            assert!(
+                fs::metadata(partial_file).is_ok(),
+                "Partial signature file not found: {}",
+                partial_file
+            );
+        }
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let share_files = run_dkg("test_aggregate", 2, 3);
+        let partials_dir = "./results/test_aggregate_partials";
+        let signature_file = "./results/test_aggregate_signature.json";
+        fs::create_dir_all(partials_dir).unwrap();
+        // Participants 1 and 3 sign.
+        let signer_shares = [&share_files[0], &share_files[2]];
+        let public_files: Vec<String> = (0..2)
+            .map(|i| format!("./results/test_aggregate_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (0..2)
+            .map(|i| format!("./results/test_aggregate_secret_commitments_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            signer_shares.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
         let message = "hi, this is a test";
-        let result = sign_message(message, vec![1], 5, &keys_file, &signature_file);
+        for (i, (share_file, secret_file)) in signer_shares.iter().zip(&secret_files).enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i);
+            signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, &partial_file, &Default::default())
+                .unwrap();
+        }
+        let result = signing::aggregate(
+            message,
+            SIGNING_CONTEXT,
+            &share_files[1],
+            &public_files,
+            partials_dir,
+            signature_file,
+        );
         assert!(
-            result.is_err(),
-            "Signing should fail with t=1, n=5 when keys were generated with t=2, n=5"
+            result.is_ok(),
+            "Failed to aggregate partial signatures: {:?}",
+            result.err()
         );
-        remove_file(keys_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[1], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify aggregated signature: {:?}",
+            result.err()
+        );
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(partials_dir).unwrap();
     }
 
     #[test]
-    fn test_verify_signature_fail() {
-        let keys_file = "./results/test_verify_signature_fail_frost_keys.json";
-        let signature_file = "./results/test_verify_signature_fail_signature.json";
-        let result = generate_keys(3, 5, keys_file);
+    fn test_session() {
+        let share_files = run_dkg("test_session", 2, 3);
+        let session_file = "./results/test_session_session.json";
+        let signature_file = "./results/test_session_signature.json";
+        let message = "hi, this is a test";
+        let result = session::new_session(message, SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default());
+        assert!(result.is_ok(), "Failed to create session: {:?}", result.err());
+        let session_id = session::load_session(session_file).unwrap().session_id;
+
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_session_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_session_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_session_partial_signature_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            share_files.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(&session_id, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+            let result = session::add_commitments(session_file, public_file, None, &Default::default());
+            assert!(result.is_ok(), "Failed to add commitments: {:?}", result.err());
+        }
+        for ((share_file, secret_file), partial_file) in
+            share_files.iter().zip(&secret_files).zip(&partial_files)
+        {
+            session::partial_sign(
+                message,
+                SIGNING_CONTEXT,
+                share_file,
+                secret_file,
+                session_file,
+                partial_file,
+                None,
+                &[],
+                &Default::default(),
+            )
+            .unwrap();
+            let result = session::add_partial_signature(session_file, partial_file, signature_file, None, &Default::default());
+            assert!(
+                result.is_ok(),
+                "Failed to add partial signature: {:?}",
+                result.err()
+            );
+        }
+
+        let session = session::load_session(session_file).unwrap();
+        assert_eq!(session.status, session::SessionStatus::Complete);
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
         assert!(
             result.is_ok(),
-            "Failed to generate keys with t=3, n=5: {:?}",
+            "Failed to verify session signature: {:?}",
+            result.err()
+        );
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(session_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_qr_render() {
+        let share_files = run_dkg("test_qr_render", 2, 3);
+        let public_file = "./results/test_qr_render_public_commitments.json";
+        let secret_file = "./results/test_qr_render_secret_commitments.json";
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        let result = qr::render_file(Path::new(public_file));
+        assert!(
+            result.is_ok(),
+            "Failed to render commitments as QR code: {:?}",
+            result.err()
+        );
+        assert!(!result.unwrap().is_empty(), "Rendered QR code is empty");
+        for file in share_files.iter().map(String::as_str).chain([public_file, secret_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_tcp_mailbox() {
+        let listen = "127.0.0.1:47011";
+        let mailbox_dir = "./results/test_tcp_mailbox";
+        thread::spawn(move || tcp::serve(listen, mailbox_dir, &Default::default()).unwrap());
+        let share_files = run_dkg("test_tcp_mailbox", 2, 3);
+        let public_file = "./results/test_tcp_mailbox_public_commitments.json";
+        let secret_file = "./results/test_tcp_mailbox_secret_commitments.json";
+        let downloaded_file =
+            "./results/test_tcp_mailbox_downloaded/test_tcp_mailbox_public_commitments.json";
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        // Give the mailbox time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(200));
+        let result = tcp::upload(listen, public_file);
+        assert!(
+            result.is_ok(),
+            "Failed to upload commitments: {:?}",
+            result.err()
+        );
+        let result = tcp::download(listen, downloaded_file, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to download commitments: {:?}",
+            result.err()
+        );
+        // The message arrives as JSON and is saved again, so only its contents match.
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(public_file).unwrap()).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(downloaded_file).unwrap()).unwrap()
+        );
+        for file in share_files.iter().map(String::as_str).chain([public_file, secret_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all("./results/test_tcp_mailbox_downloaded").unwrap();
+        remove_dir_all(mailbox_dir).unwrap();
+    }
+
+    #[test]
+    fn test_http_coordinator() {
+        let share_files = run_dkg("test_http_coordinator", 2, 3);
+        let sessions_dir = "./results/test_http_coordinator_sessions";
+        let signature_file = "./results/test_http_coordinator_signature.json";
+        let url = "http://127.0.0.1:47012";
+        let key_file = share_files[0].clone();
+        thread::spawn(move || http::serve("127.0.0.1:47012", &key_file, sessions_dir, None, None, &Default::default()).unwrap());
+        // Give the coordinator time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(200));
+        let message = "hi, this is a test";
+        let session = http::create_remote_session(url, message, SIGNING_CONTEXT, vec![1, 2], None).unwrap();
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_http_coordinator_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_http_coordinator_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_http_coordinator_partial_signature_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            share_files.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(&session.session_id, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+            let result = http::submit_commitments_file(url, public_file, None);
+            assert!(
+                result.is_ok(),
+                "Failed to send commitments: {:?}",
+                result.err()
+            );
+        }
+        for ((share_file, secret_file), partial_file) in
+            share_files.iter().zip(&secret_files).zip(&partial_files)
+        {
+            let result =
+                http::partial_sign_remote(
+                    url, message, SIGNING_CONTEXT, share_file, secret_file, partial_file, None, None, &[],
+                    &Default::default(),
+                );
+            assert!(
+                result.is_ok(),
+                "Failed to send partial signature: {:?}",
+                result.err()
+            );
+        }
+        let result = http::download_signature(url, &session.session_id, signature_file, None, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to download signature: {:?}",
+            result.err()
+        );
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify coordinator signature: {:?}",
             result.err()
         );
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(sessions_dir).unwrap();
+    }
+
+    #[test]
+    fn test_grpc_coordinator() {
+        let share_files = run_dkg("test_grpc_coordinator", 2, 3);
+        let sessions_dir = "./results/test_grpc_coordinator_sessions";
+        let signature_file = "./results/test_grpc_coordinator_signature.json";
+        let url = "http://127.0.0.1:47013";
+        let key_file = share_files[0].clone();
+        thread::spawn(move || grpc::serve("127.0.0.1:47013", &key_file, sessions_dir, None, None, &Default::default()).unwrap());
+        // Give the coordinator time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(200));
         let message = "hi, this is a test";
-        let result = sign_message(message, vec![1,2,3], 5, &keys_file, &signature_file);
+        let session = grpc::create_remote_session(url, message, SIGNING_CONTEXT, vec![1, 2], None).unwrap();
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_grpc_coordinator_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_grpc_coordinator_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_grpc_coordinator_partial_signature_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            share_files.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(&session.session_id, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+            grpc::submit_commitments_file(url, public_file, None).unwrap();
+        }
+        for ((share_file, secret_file), partial_file) in
+            share_files.iter().zip(&secret_files).zip(&partial_files)
+        {
+            let result =
+                grpc::partial_sign_remote(
+                    url, message, SIGNING_CONTEXT, share_file, secret_file, partial_file, None, None, &[],
+                    &Default::default(),
+                );
+            assert!(
+                result.is_ok(),
+                "Failed to send partial signature: {:?}",
+                result.err()
+            );
+        }
+        grpc::download_signature(url, &session.session_id, signature_file, None, &Default::default()).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
         assert!(
             result.is_ok(),
-            "Failed to sign message with t=3, n=5: {:?}",
+            "Failed to verify coordinator signature: {:?}",
+            result.err()
+        );
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(sessions_dir).unwrap();
+    }
+
+    #[test]
+    fn test_p2p_exchange() {
+        let share_files = run_dkg("test_p2p_exchange", 2, 3);
+        let public_file = "./results/test_p2p_exchange_public_commitments.json";
+        let secret_file = "./results/test_p2p_exchange_secret_commitments.json";
+        let received_file =
+            "./results/test_p2p_exchange_received/test_p2p_exchange_public_commitments.json";
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        let sender =
+            p2p::Node::start("test_p2p_exchange", "/ip4/127.0.0.1/tcp/47014", &[]).unwrap();
+        let receiver = p2p::Node::start(
+            "test_p2p_exchange",
+            "/ip4/127.0.0.1/tcp/0",
+            &["/ip4/127.0.0.1/tcp/47014".to_string()],
+        )
+        .unwrap();
+        sender.publish(public_file).unwrap();
+        let result = receiver.receive(received_file, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to receive commitments: {:?}",
+            result.err()
+        );
+        // The message arrives as JSON and is saved again, so only its contents match.
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(public_file).unwrap()).unwrap(),
+            serde_json::from_str::<serde_json::Value>(&fs::read_to_string(received_file).unwrap()).unwrap()
+        );
+        for file in share_files.iter().map(String::as_str).chain([public_file, secret_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all("./results/test_p2p_exchange_received").unwrap();
+    }
+
+    #[test]
+    fn test_ws_participants() {
+        let share_files = run_dkg("test_ws_participants", 2, 3);
+        let sessions_dir = "./results/test_ws_participants_sessions";
+        let state_dir = "./results/test_ws_participants_state";
+        let signature_file = "./results/test_ws_participants_signature.json";
+        let url = "http://127.0.0.1:47015";
+        let key_file = share_files[0].clone();
+        thread::spawn(move || http::serve("127.0.0.1:47015", &key_file, sessions_dir, None, None, &Default::default()).unwrap());
+        // Give the coordinator time to bind before the participants connect.
+        thread::sleep(std::time::Duration::from_millis(200));
+        for share_file in share_files.iter().take(2).cloned() {
+            thread::spawn(move || {
+                ws::run_participant("ws://127.0.0.1:47015", &share_file, SIGNING_CONTEXT, state_dir, None, None, &Default::default()).unwrap()
+            });
+        }
+        thread::sleep(std::time::Duration::from_millis(200));
+        let message = "hi, this is a test";
+        let session = http::create_remote_session(url, message, SIGNING_CONTEXT, vec![1, 2], None).unwrap();
+        let mut result = http::download_signature(url, &session.session_id, signature_file, None, &Default::default());
+        for _ in 0..50 {
+            if result.is_ok() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(100));
+            result = http::download_signature(url, &session.session_id, signature_file, None, &Default::default());
+        }
+        assert!(
+            result.is_ok(),
+            "Participants did not complete the session: {:?}",
+            result.err()
+        );
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify pushed session signature: {:?}",
             result.err()
         );
-        let result = validate_signature("different message", &keys_file, &signature_file);
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(sessions_dir).unwrap();
+        remove_dir_all(state_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mtls_coordinator() {
+        let share_files = run_dkg("test_mtls_coordinator", 2, 3);
+        let tls_dir = "./results/test_mtls_coordinator_tls";
+        let sessions_dir = "./results/test_mtls_coordinator_sessions";
+        let signature_file = "./results/test_mtls_coordinator_signature.json";
+        let url = "https://localhost:47016";
+        let (server_tls, client_tls) = write_tls_certs(tls_dir, 2);
+        let key_file = share_files[0].clone();
+        thread::spawn(move || {
+            http::serve("127.0.0.1:47016", &key_file, sessions_dir, None, Some(&server_tls), &Default::default()).unwrap()
+        });
+        // Give the coordinator time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(200));
+        let message = "hi, this is a test";
+        let session =
+            http::create_remote_session(url, message, SIGNING_CONTEXT, vec![1, 2], Some(&client_tls[0])).unwrap();
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_mtls_coordinator_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_mtls_coordinator_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_mtls_coordinator_partial_signature_{}.json", i))
+            .collect();
+        for ((share_file, public_file), secret_file) in
+            share_files.iter().zip(&public_files).zip(&secret_files)
+        {
+            signing::commit(&session.session_id, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        let result = http::submit_commitments_file(url, &public_files[1], Some(&client_tls[0]));
         assert!(
             result.is_err(),
-            "Verification should fail for a different message"
+            "Participant 1 should not be able to submit the commitments of participant 2"
+        );
+        for (public_file, tls) in public_files.iter().zip(&client_tls) {
+            let result = http::submit_commitments_file(url, public_file, Some(tls));
+            assert!(
+                result.is_ok(),
+                "Failed to send commitments: {:?}",
+                result.err()
+            );
+        }
+        for (((share_file, secret_file), partial_file), tls) in share_files
+            .iter()
+            .zip(&secret_files)
+            .zip(&partial_files)
+            .zip(&client_tls)
+        {
+            let result = http::partial_sign_remote(
+                url,
+                message,
+                SIGNING_CONTEXT,
+                share_file,
+                secret_file,
+                partial_file,
+                Some(tls),
+                None,
+                &[],
+                &Default::default(),
+            );
+            assert!(
+                result.is_ok(),
+                "Failed to send partial signature: {:?}",
+                result.err()
+            );
+        }
+        http::download_signature(url, &session.session_id, signature_file, Some(&client_tls[0]), &Default::default())
+            .unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify coordinator signature: {:?}",
+            result.err()
+        );
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(sessions_dir).unwrap();
+        remove_dir_all(tls_dir).unwrap();
+    }
+
+    #[test]
+    fn test_relay() {
+        let identities_dir = "./results/test_relay_identities";
+        let identity_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_relay_identity_{}.json", i))
+            .collect();
+        let share_files = run_dkg("test_relay", 2, 2);
+        let public_file = "./results/test_relay_public_commitments.json";
+        let secret_file = "./results/test_relay_secret_commitments.json";
+        let received_file = "./results/test_relay_received/test_relay_public_commitments.json";
+        for (i, identity_file) in identity_files.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, identities_dir, &Default::default()).unwrap();
+        }
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        thread::spawn(|| relay::serve("127.0.0.1:47017").unwrap());
+        // Give the relay time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(200));
+        relay::send(
+            "127.0.0.1:47017",
+            SESSION_ID,
+            &identity_files[0],
+            identities_dir,
+            2,
+            public_file,
+        )
+        .unwrap();
+        let result = relay::receive(
+            "127.0.0.1:47017",
+            SESSION_ID,
+            &identity_files[1],
+            identities_dir,
+            1,
+            received_file,
+        );
+        assert!(
+            result.is_ok(),
+            "Failed to receive commitments through the relay: {:?}",
+            result.err()
+        );
+        assert_eq!(
+            fs::read_to_string(public_file).unwrap(),
+            fs::read_to_string(received_file).unwrap()
+        );
+
+        // The relay refuses to hold more than its limit for a session.
+        let mut stream = std::net::TcpStream::connect("127.0.0.1:47017").unwrap();
+        let request = relay::RelayRequest::Forward {
+            session_id: SESSION_ID.to_string(),
+            tag: "oversized".to_string(),
+            payload: "00".repeat(relay::MAX_SESSION_BYTES / 2 + 1),
+        };
+        tcp::send_message(&mut stream, &request).unwrap();
+        let response: relay::RelayResponse = tcp::receive_message(&mut stream).unwrap().unwrap();
+        assert!(matches!(response, relay::RelayResponse::Error(_)), "Expected an oversized message to be refused");
+        for file in share_files
+            .iter()
+            .chain(&identity_files)
+            .map(String::as_str)
+            .chain([public_file, secret_file])
+        {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all("./results/test_relay_received").unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_noise_sealed_shares() {
+        let dkg_dir = "./results/test_noise_sealed_shares_dkg";
+        let identities_dir = "./results/test_noise_sealed_shares_identities";
+        let identity_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_noise_sealed_shares_identity_{}.json", i))
+            .collect();
+        let state_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_noise_sealed_shares_state_{}.json", i))
+            .collect();
+        let share_file = "./results/test_noise_sealed_shares_share_2.json";
+        for (i, (identity_file, state_file)) in identity_files.iter().zip(&state_files).enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, identities_dir, &Default::default()).unwrap();
+            dkg::round_one(i as u32 + 1, 2, 2, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+        let plain_file = Path::new(dkg_dir).join("share_1_2.json");
+        let plain = fs::read_to_string(&plain_file).unwrap();
+        let sealed_file = noise::seal_share(&identity_files[0], identities_dir, &plain_file, &Default::default()).unwrap();
+        assert!(
+            !fs::read_to_string(&sealed_file).unwrap().contains(&plain),
+            "Sealed share should not contain the plaintext share"
+        );
+        remove_file(&plain_file).unwrap();
+        let result = noise::open_share(&identity_files[1], identities_dir, &plain_file, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to open sealed share: {:?}",
+            result.err()
+        );
+        assert_eq!(plain, fs::read_to_string(&plain_file).unwrap());
+        let result = dkg::finish(&state_files[1], dkg_dir, share_file, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to finish DKG with an opened share: {:?}",
+            result.err()
+        );
+        for file in identity_files.iter().chain(&state_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(share_file).unwrap();
+        remove_dir_all(dkg_dir).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_signed_round_messages() {
+        let share_files = run_dkg("test_signed_round_messages", 2, 3);
+        let identities_dir = "./results/test_signed_round_messages_identities";
+        let session_file = "./results/test_signed_round_messages_session.json";
+        let message = "hi, this is a test";
+        let identities: Vec<IdentityFiles> = (1..=2)
+            .map(|i| IdentityFiles {
+                identity_file: format!("./results/test_signed_round_messages_identity_{}.json", i),
+                identities_dir: identities_dir.to_string(),
+            })
+            .collect();
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_signed_round_messages_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_signed_round_messages_secret_commitments_{}.json", i))
+            .collect();
+        let partial_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_signed_round_messages_partial_signature_{}.json", i))
+            .collect();
+        session::new_session(message, SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default()).unwrap();
+        let session_id = session::load_session(session_file).unwrap().session_id;
+        for (i, identity) in identities.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, &identity.identity_file, identities_dir, &Default::default())
+                .unwrap();
+            signing::commit(&session_id, &share_files[i], &public_files[i], &secret_files[i], signing::CommitOptions::default())
+                .unwrap();
+            identity::sign_message_file(&identity.identity_file, &public_files[i]).unwrap();
+            session::add_commitments(session_file, &public_files[i], None, &Default::default()).unwrap();
+        }
+        for (i, identity) in identities.iter().enumerate() {
+            let result = session::partial_sign(
+                message,
+                SIGNING_CONTEXT,
+                &share_files[i],
+                &secret_files[i],
+                session_file,
+                &partial_files[i],
+                Some(identity),
+                &[],
+                &Default::default(),
+            );
+            assert!(
+                result.is_ok(),
+                "Failed to sign with verified commitments: {:?}",
+                result.err()
+            );
+            let result = identity::verify_message_file(identities_dir, &partial_files[i]);
+            assert_eq!(result.ok(), Some(i as u32 + 1));
+        }
+        // A round message changed after it was signed is rejected, naming its sender.
+        let mut partial: serde_json::Value = serde_json::from_str(&fs::read_to_string(&partial_files[0]).unwrap()).unwrap();
+        partial["session_id"] = "ffeeddccbbaa99887766554433221100".into();
+        fs::write(&partial_files[0], partial.to_string()).unwrap();
+        let err = identity::verify_message_file(identities_dir, &partial_files[0]).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(frost_cli::error::FrostCliError::InvalidMessage { index: 1, .. })));
+        for file in share_files
+            .iter()
+            .chain(identities.iter().map(|identity| &identity.identity_file))
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(session_file).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_echo_broadcast() {
+        let share_files = run_dkg("test_echo_broadcast", 2, 3);
+        let session_file = "./results/test_echo_broadcast_session.json";
+        let message = "hi, this is a test";
+        let files = |kind: &str| -> Vec<String> {
+            (1..=2)
+                .map(|i| format!("./results/test_echo_broadcast_{}_{}.json", kind, i))
+                .collect()
+        };
+        let (public_files, secret_files) = (files("public_commitments"), files("secret_commitments"));
+        let (echo_files, partial_files) = (files("echo"), files("partial_signature"));
+        session::new_session(message, SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default()).unwrap();
+        let session_id = session::load_session(session_file).unwrap().session_id;
+        for i in 0..2 {
+            signing::commit(&session_id, &share_files[i], &public_files[i], &secret_files[i], signing::CommitOptions::default())
+                .unwrap();
+            session::add_commitments(session_file, &public_files[i], None, &Default::default()).unwrap();
+        }
+        let roster = session::load_session(session_file).unwrap().commitments;
+        for i in 0..2 {
+            let result = echo::write_echo(message, SIGNING_CONTEXT, &share_files[i], &roster, &echo_files[i], &Default::default());
+            assert!(result.is_ok(), "Failed to write echo: {:?}", result.err());
+        }
+        for i in 0..2 {
+            let result = session::partial_sign(
+                message,
+                SIGNING_CONTEXT,
+                &share_files[i],
+                &secret_files[i],
+                session_file,
+                &partial_files[i],
+                None,
+                &[echo_files[1 - i].clone()],
+                &Default::default(),
+            );
+            assert!(
+                result.is_ok(),
+                "Failed to sign after a matching echo: {:?}",
+                result.err()
+            );
+        }
+        for file in share_files
+            .iter()
+            .chain(&public_files)
+            .chain(&secret_files)
+            .chain(&echo_files)
+            .chain(&partial_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(session_file).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_share_bundles() {
+        let identities_dir = "./results/test_encrypted_share_bundles_identities";
+        let roster_file = "./results/test_encrypted_share_bundles_roster.json";
+        let bundles_dir = "./results/test_encrypted_share_bundles_bundles";
+        let signature_file = "./results/test_encrypted_share_bundles_signature.json";
+        let identity_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_encrypted_share_bundles_identity_{}.json", i))
+            .collect();
+        let share_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_encrypted_share_bundles_share_{}.json", i))
+            .collect();
+        for (i, identity_file) in identity_files.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, identities_dir, &Default::default()).unwrap();
+        }
+        dealer::write_roster(identities_dir, 3, roster_file, &Default::default()).unwrap();
+        let result = dealer::generate_encrypted_shares(2, 3, roster_file, bundles_dir, &Default::default());
+        assert!(
+            result.is_ok(),
+            "Failed to generate encrypted shares: {:?}",
+            result.err()
+        );
+        for (i, (identity_file, share_file)) in identity_files.iter().zip(&share_files).enumerate() {
+            let bundle_file = format!("{}/share_bundle_{}.json", bundles_dir, i + 1);
+            let result = dealer::decrypt_share(identity_file, &bundle_file, share_file, &Default::default());
+            assert!(result.is_ok(), "Failed to decrypt share: {:?}", result.err());
+        }
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[1..], signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature of decrypted shares: {:?}",
+            result.err()
+        );
+        for file in identity_files.iter().chain(&share_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(roster_file).unwrap();
+        remove_file(signature_file).unwrap();
+        remove_dir_all(bundles_dir).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mnemonic_backup() {
+        let share_files = run_dkg("test_mnemonic_backup", 2, 2);
+        let restored_file = "./results/test_mnemonic_backup_restored_share.json";
+        let signature_file = "./results/test_mnemonic_backup_signature.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let mnemonic = backup::export_mnemonic(&share_files[0], &Default::default()).unwrap();
+        assert_eq!(mnemonic.split_whitespace().count(), 24);
+        let result = backup::import_mnemonic(
+            &mnemonic,
+            share.index,
+            &hex::encode(share.group_key),
+            share.threshold,
+            share.participants,
+            restored_file,
+            &Default::default(),
+        );
+        assert!(result.is_ok(), "Failed to import share: {:?}", result.err());
+        let signers = vec![restored_file.to_string(), share_files[1].clone()];
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, restored_file, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature of a restored share: {:?}",
+            result.err()
+        );
+        for file in share_files.iter().map(String::as_str).chain([restored_file, signature_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_paper_backup() {
+        let share_files = run_dkg("test_paper_backup", 2, 2);
+        let backup_file = "./results/test_paper_backup.txt";
+        let restored_file = "./results/test_paper_backup_restored_share.json";
+        let backup = backup::export_paper(&share_files[0], &Default::default()).unwrap();
+        // Type the backup back in lowercase, with one mistyped character on line 3.
+        let typed: Vec<String> = backup
+            .lines()
+            .map(|line| {
+                let mut line = line.to_lowercase();
+                if line.starts_with("03:") {
+                    let typo = if &line[4..5] == "x" { "y" } else { "x" };
+                    line.replace_range(4..5, typo);
+                }
+                line
+            })
+            .collect();
+        fs::write(backup_file, typed.join("\n")).unwrap();
+        let result = backup::recover_paper(backup_file, restored_file, &Default::default());
+        assert!(result.is_ok(), "Failed to recover share: {:?}", result.err());
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let restored = load_participant_share(restored_file, &Default::default()).unwrap();
+        assert_eq!(restored.index, share.index);
+        assert_eq!(restored.share, share.share);
+        assert_eq!(restored.group_key, share.group_key);
+        for file in share_files.iter().map(String::as_str).chain([backup_file, restored_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_keystore() {
+        let keystore_dir = Path::new("./results/test_keystore");
+        let share_files = run_dkg("test_keystore", 2, 2);
+        let signature_file = "./results/test_keystore_signature.json";
+        let key_file = keystore::new_key_file(keystore_dir, "dealer").unwrap();
+        generate_keys(2, 3, &key_file).unwrap();
+        let result = keystore::import_key(keystore_dir, "board", &share_files[0], &Default::default());
+        assert!(result.is_ok(), "Failed to import share: {:?}", result.err());
+        let keys = keystore::list_keys(keystore_dir, &Default::default()).unwrap();
+        let names: Vec<&str> = keys.iter().map(|key| key.name.as_str()).collect();
+        assert_eq!(names, vec!["board", "dealer"]);
+        assert_eq!(keys[0].kind, keystore::KeyKind::Share { index: 1 });
+        assert_eq!(keys[1].kind, keystore::KeyKind::Keys);
+        let message = "hi, this is a test";
+        let key_file = keystore::dealer_key_file(keystore_dir, "dealer").unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, &key_file, signature_file).unwrap();
+        let key_file = keystore::key_file(keystore_dir, "dealer").unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &key_file, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature with a keystore key: {:?}",
+            result.err()
+        );
+        keystore::delete_key(keystore_dir, "dealer").unwrap();
+        assert_eq!(keystore::list_keys(keystore_dir, &Default::default()).unwrap().len(), 1);
+        for file in share_files.iter().map(String::as_str).chain([signature_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(keystore_dir).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_unversioned_files() {
+        let keys_file = "./results/test_migrate_unversioned_files_frost_keys.json";
+        let signature_file = "./results/test_migrate_unversioned_files_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let message = "hi, this is a test";
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        // Strip both files back to the formats written before versioning.
+        let mut keys: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(keys_file).unwrap()).unwrap();
+        keys.as_object_mut().unwrap().remove("version");
+        keys.as_object_mut().unwrap().remove("checksum");
+        fs::write(keys_file, keys.to_string()).unwrap();
+        let signature: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(signature_file).unwrap()).unwrap();
+        fs::write(signature_file, signature["signature"].to_string()).unwrap();
+        for file in [keys_file, signature_file] {
+            let result = migrate::migrate(file, None, &Default::default());
+            assert!(result.is_ok(), "Failed to migrate {}: {:?}", file, result.err());
+        }
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature with migrated files: {:?}",
+            result.err()
+        );
+        for file in [keys_file, signature_file] {
+            remove_file(file).unwrap();
+            remove_file(format!("{}.bak", file)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_checksums() {
+        let share_files = run_dkg("test_file_checksums", 2, 2);
+        let keys_file = "./results/test_file_checksums_frost_keys.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        for file in share_files.iter().map(String::as_str).chain([keys_file]) {
+            let value: serde_json::Value =
+                serde_json::from_str(&fs::read_to_string(file).unwrap()).unwrap();
+            assert!(value["checksum"].is_string(), "{} has no checksum", file);
+        }
+        // Share files written before checksums are upgraded by migrate.
+        let mut share: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&share_files[0]).unwrap()).unwrap();
+        share.as_object_mut().unwrap().remove("checksum");
+        fs::write(&share_files[0], share.to_string()).unwrap();
+        assert!(load_participant_share(&share_files[0], &Default::default()).is_err());
+        migrate::migrate(&share_files[0], None, &Default::default()).unwrap();
+        let result = load_participant_share(&share_files[0], &Default::default());
+        assert!(result.is_ok(), "Failed to load migrated share: {:?}", result.err());
+        for file in share_files.iter().map(String::as_str).chain([keys_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_file(format!("{}.bak", share_files[0])).unwrap();
+    }
+
+    #[test]
+    fn test_binary_formats() {
+        let keys_file = "./results/test_binary_formats_frost_keys.json";
+        let cbor_file = "./results/test_binary_formats_frost_keys.cbor";
+        let bincode_file = "./results/test_binary_formats_frost_keys.bin";
+        let signature_file = "./results/test_binary_formats_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let keys: serde_json::Value = format::decode(&fs::read(keys_file).unwrap()).unwrap();
+        for (file, target) in [(cbor_file, Format::Cbor), (bincode_file, Format::Bincode)] {
+            let bytes = format::encode_as(&keys, target).unwrap();
+            assert_eq!(format::detect(&bytes), target);
+            assert!(bytes.len() < fs::metadata(keys_file).unwrap().len() as usize);
+            fs::write(file, bytes).unwrap();
+        }
+        let message = "hi, this is a test";
+        let result = sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, cbor_file, signature_file);
+        assert!(result.is_ok(), "Failed to sign with a CBOR key file: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, bincode_file, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature with a bincode key file: {:?}",
+            result.err()
+        );
+        for file in [keys_file, cbor_file, bincode_file, signature_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_options_per_call() {
+        use frost_cli::{backend::Ciphersuite, generate_keys_with_progress, FileOptions};
+        let cbor_file = "./results/test_file_options_per_call_frost_keys.cbor";
+        let json_file = "./results/test_file_options_per_call_frost_keys.json";
+        let signature_file = "./results/test_file_options_per_call_signature.json";
+        let message = "hi, this is a test";
+        // Two callers in one process write in their own format, whatever the other chose.
+        let cbor = FileOptions { format: Format::Cbor, ..Default::default() };
+        generate_keys_with_progress(2, 3, cbor_file, Ciphersuite::Ristretto255, &|_| {}, &cbor).unwrap();
+        generate_keys_with_progress(2, 3, json_file, Ciphersuite::Ristretto255, &|_| {}, &FileOptions::default()).unwrap();
+        assert_eq!(format::detect(&fs::read(cbor_file).unwrap()), Format::Cbor);
+        assert_eq!(format::detect(&fs::read(json_file).unwrap()), Format::Json);
+        let signature = frost_cli::io::envelope::EnvelopeOptions { files: FileOptions { format: Format::Bincode, ..Default::default() }, ..Default::default() };
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 1], 3, cbor_file, signature_file, frost_cli::io::envelope::OutFormat::Signature, &signature, None).unwrap();
+        assert_eq!(format::detect(&fs::read(signature_file).unwrap()), Format::Bincode);
+        let result = validate_signature(message, SIGNING_CONTEXT, json_file, signature_file);
+        assert!(result.is_err(), "Expected a signature of another group to fail");
+        let result = validate_signature(message, SIGNING_CONTEXT, cbor_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify a bincode signature: {:?}", result.err());
+        for file in [cbor_file, json_file, signature_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_key() {
+        use frost_cli::{backend::Ciphersuite, generate_keys_with_progress, load_frost_keys, migrate, FileKey, FileOptions};
+        let keys_file = "./results/test_file_key_frost_keys.json";
+        let unkeyed_file = "./results/test_file_key_unkeyed_frost_keys.json";
+        let keyed = FileOptions { file_key: Some(FileKey::new("correct horse battery staple")), ..Default::default() };
+        assert!(!format!("{:?}", keyed).contains("horse"), "Expected the file key to be left out of debug output");
+        generate_keys(2, 3, unkeyed_file).unwrap();
+        generate_keys_with_progress(2, 3, keys_file, Ciphersuite::Ristretto255, &|_| {}, &keyed).unwrap();
+        let keys: serde_json::Value = serde_json::from_str(&fs::read_to_string(keys_file).unwrap()).unwrap();
+        let checksum = keys["checksum"].as_str().unwrap();
+        let salt = checksum.strip_prefix("argon2id-hmac-sha256:").expect("Expected a keyed checksum").split(':').next().unwrap();
+        assert_eq!(salt.len(), 32, "Expected the salt to be stored with the checksum");
+        assert!(load_frost_keys(keys_file, &keyed).is_ok(), "Failed to load a key file with a keyed checksum");
+
+        // A file with a digest is refused until it is keyed, each time with a fresh salt.
+        let result = load_frost_keys(unkeyed_file, &keyed);
+        assert!(result.err().expect("Expected an unkeyed checksum to fail").to_string().contains("unkeyed checksum"));
+        migrate::migrate(unkeyed_file, Some(unkeyed_file), &keyed).unwrap();
+        assert!(load_frost_keys(unkeyed_file, &keyed).is_ok(), "Failed to load a migrated key file");
+        let migrated: serde_json::Value = serde_json::from_str(&fs::read_to_string(unkeyed_file).unwrap()).unwrap();
+        assert!(!migrated["checksum"].as_str().unwrap().contains(salt), "Expected every file to get its own salt");
+
+        // An edited file fails its keyed checksum.
+        let mut edited = keys.clone();
+        edited["threshold"] = 1.into();
+        fs::write(keys_file, edited.to_string()).unwrap();
+        let result = load_frost_keys(keys_file, &keyed);
+        assert!(result.err().expect("Expected an edited key file to fail").to_string().contains("tampered"));
+
+        // A wrong passphrase is rejected, and without one a keyed file cannot be checked.
+        fs::write(keys_file, keys.to_string()).unwrap();
+        let wrong = FileOptions { file_key: Some(FileKey::new("wrong horse battery staple")), ..Default::default() };
+        let result = load_frost_keys(keys_file, &wrong);
+        assert!(result.err().expect("Expected a wrong passphrase to fail").to_string().contains("tampered"));
+        let result = load_frost_keys(keys_file, &FileOptions::default());
+        assert!(result.err().expect("Expected a missing key to fail").to_string().contains("keyed checksum"));
+
+        remove_file(keys_file).unwrap();
+        remove_file(unkeyed_file).unwrap();
+    }
+
+    #[test]
+    fn test_signature_formats() {
+        let keys_file = "./results/test_signature_formats_frost_keys.json";
+        let signature_file = "./results/test_signature_formats_signature.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        for target in [SignatureFormat::Raw, SignatureFormat::Hex, SignatureFormat::Base64] {
+            let file = format!("./results/test_signature_formats_signature.{:?}", target);
+            fs::write(&file, signature::encode(&raw, target, Format::Json).unwrap()).unwrap();
+            assert_eq!(signature::detect(&fs::read(&file).unwrap()), target);
+            let result = validate_signature(message, SIGNING_CONTEXT, keys_file, &file);
+            assert!(result.is_ok(), "Failed to verify {:?} signature: {:?}", target, result.err());
+            let result = validate_signature_as(message, SIGNING_CONTEXT, keys_file, &file, Some(target), &frost_cli::io::envelope::EnvelopeOptions::default());
+            assert!(result.is_ok(), "Failed to verify {:?} signature: {:?}", target, result.err());
+            remove_file(file).unwrap();
+        }
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_sign_in_every_signature_format() {
+        use frost_cli::io::envelope::{EnvelopeOptions, OutFormat};
+        let keys_file = "./results/test_sign_in_every_signature_format_frost_keys.json";
+        let data_file = "./results/test_sign_in_every_signature_format_data.bin";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(data_file, b"release artifact").unwrap();
+        let share_files = run_dkg("test_sign_in_every_signature_format", 2, 3);
+        for format in [SignatureFormat::Raw, SignatureFormat::Hex, SignatureFormat::Base64, SignatureFormat::Pem, SignatureFormat::Json] {
+            let signature_file = format!("./results/test_sign_in_every_signature_format_signature.{:?}", format);
+            let options = EnvelopeOptions { signature_format: format, ..Default::default() };
+
+            // Signing with a key file, with share files and of a file all save the signature in the format.
+            frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, &signature_file, OutFormat::Signature, &options, None).unwrap();
+            assert_eq!(signature::detect(&fs::read(&signature_file).unwrap()), format);
+            let result = validate_signature(message, SIGNING_CONTEXT, keys_file, &signature_file);
+            assert!(result.is_ok(), "Failed to verify {:?} signature: {:?}", format, result.err());
+
+            frost_cli::sign_message_with_shares_as(message, SIGNING_CONTEXT, &share_files[..2], &signature_file, &options, None).unwrap();
+            assert_eq!(signature::detect(&fs::read(&signature_file).unwrap()), format);
+            let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], &signature_file);
+            assert!(result.is_ok(), "Failed to verify {:?} signature made with shares: {:?}", format, result.err());
+
+            frost_cli::sign_file(data_file, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, &signature_file, format, &Default::default(), None).unwrap();
+            assert_eq!(signature::detect(&fs::read(&signature_file).unwrap()), format);
+            let result = frost_cli::validate_file_signature(data_file, SIGNING_CONTEXT, keys_file, &signature_file, None, &Default::default());
+            assert!(result.is_ok(), "Failed to verify {:?} file signature: {:?}", format, result.err());
+            remove_file(signature_file).unwrap();
+        }
+        for file in share_files.iter().map(String::as_str).chain([keys_file, data_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pem_armor() {
+        let keys_file = "./results/test_pem_armor_frost_keys.json";
+        let signature_file = "./results/test_pem_armor_signature.json";
+        let group_key_file = "./results/test_pem_armor_group_key.pem";
+        let pem_signature_file = "./results/test_pem_armor_signature.pem";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let group_key = export_group_key(keys_file, true, &Default::default()).unwrap();
+        assert!(group_key.starts_with("-----BEGIN FROST GROUP KEY-----"));
+        fs::write(group_key_file, group_key).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        fs::write(pem_signature_file, signature::encode(&raw, SignatureFormat::Pem, Format::Json).unwrap()).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, group_key_file, pem_signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify a PEM signature with a PEM group key: {:?}",
+            result.err()
         );
+        for file in [keys_file, signature_file, group_key_file, pem_signature_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_ed25519_ciphersuite() {
+        let keys_file = "./results/test_ed25519_ciphersuite_frost_keys.json";
+        let signature_file = "./results/test_ed25519_ciphersuite_signature.json";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify an Ed25519 signature: {:?}", result.err());
+        let group_key: [u8; 32] = hex::decode(export_group_key(keys_file, false, &Default::default()).unwrap().trim()).unwrap().try_into().unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw: [u8; 64] = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap().try_into().unwrap();
+        let result = ed25519_dalek::VerifyingKey::from_bytes(&group_key)
+            .unwrap()
+            .verify_strict(message.as_bytes(), &ed25519_dalek::Signature::from_bytes(&raw));
+        assert!(result.is_ok(), "ed25519-dalek rejected the signature: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn test_secp256k1_ciphersuite() {
+        let keys_file = "./results/test_secp256k1_ciphersuite_frost_keys.json";
+        let signature_file = "./results/test_secp256k1_ciphersuite_signature.json";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Secp256k1).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![1, 2], 3, keys_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify a secp256k1 signature: {:?}", result.err());
+        let group_key = hex::decode(export_group_key(keys_file, false, &Default::default()).unwrap().trim()).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        let result = k256::schnorr::VerifyingKey::from_bytes(&group_key)
+            .unwrap()
+            .verify_raw(message.as_bytes(), &k256::schnorr::Signature::try_from(&raw[..]).unwrap());
+        assert!(result.is_ok(), "BIP340 verification rejected the signature: {:?}", result.err());
         remove_file(keys_file).unwrap();
         remove_file(signature_file).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_rfc9591_test_vectors() {
+        let vectors_file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/vectors/rfc9591_ed25519.json");
+        let result = frost_cli::backend::check_test_vectors(frost_cli::backend::Ciphersuite::Ed25519, vectors_file);
+        assert!(result.is_ok(), "The ed25519 backend failed the test vectors: {:?}", result.err());
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_jws_output() {
+        let keys_file = "./results/test_jws_output_frost_keys.json";
+        let jws_file = "./results/test_jws_output_signature.jws";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, jws_file, frost_cli::io::envelope::OutFormat::Jws, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        let jws = fs::read_to_string(jws_file).unwrap();
+        let header = jws.split('.').next().unwrap();
+        assert!(header.starts_with("eyJhbGciOiJFZERTQSI"), "JWS header should start with alg EdDSA: {}", header);
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, jws_file);
+        assert!(result.is_ok(), "Failed to verify a JWS: {:?}", result.err());
+        let result = validate_signature("hi, this is another test", SIGNING_CONTEXT, keys_file, jws_file);
+        assert!(result.is_err(), "A JWS should not verify for another message");
+        remove_file(keys_file).unwrap();
+        remove_file(jws_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_sshsig_output() {
+        let keys_file = "./results/test_sshsig_output_frost_keys.json";
+        let sshsig_file = "./results/test_sshsig_output_signature.sig";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![1, 2], 3, keys_file, sshsig_file, frost_cli::io::envelope::OutFormat::Sshsig, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        assert!(fs::read_to_string(sshsig_file).unwrap().starts_with("-----BEGIN SSH SIGNATURE-----"));
+        let public_key = frost_cli::export_ssh_public_key(keys_file, &Default::default()).unwrap();
+        assert!(public_key.starts_with("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAI"), "Unexpected OpenSSH public key: {}", public_key);
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, sshsig_file);
+        assert!(result.is_ok(), "Failed to verify an SSH signature: {:?}", result.err());
+        let result = validate_signature("hi, this is another test", SIGNING_CONTEXT, keys_file, sshsig_file);
+        assert!(result.is_err(), "An SSH signature should not verify for another message");
+        remove_file(keys_file).unwrap();
+        remove_file(sshsig_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_sshsig_namespace() {
+        use frost_cli::io::envelope::{EnvelopeOptions, OutFormat};
+        let keys_file = "./results/test_sshsig_namespace_frost_keys.json";
+        let sshsig_file = "./results/test_sshsig_namespace_signature.sig";
+        let message = "hi, this is a test";
+        let git = EnvelopeOptions { namespace: "git".to_string(), ..Default::default() };
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, sshsig_file, OutFormat::Sshsig, &git, None).unwrap();
+        let result = validate_signature_as(message, SIGNING_CONTEXT, keys_file, sshsig_file, None, &git);
+        assert!(result.is_ok(), "Failed to verify an SSH signature in its namespace: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, sshsig_file);
+        assert!(
+            result.err().expect("Expected an SSH signature of another namespace to fail").to_string().contains("namespace git"),
+            "Expected the error to name the signature's namespace"
+        );
+        remove_file(keys_file).unwrap();
+        remove_file(sshsig_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_openpgp_output() {
+        let keys_file = "./results/test_openpgp_output_frost_keys.json";
+        let signature_file = "./results/test_openpgp_output_signature.asc";
+        let public_key_file = "./results/test_openpgp_output_public_key.asc";
+        let message = "hi, this is a test";
+        let user_id = "Release Team <release@example.com>";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, signature_file, frost_cli::io::envelope::OutFormat::Openpgp, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        frost_cli::sign_message_as(user_id, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, public_key_file, frost_cli::io::envelope::OutFormat::OpenpgpKey, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        assert!(fs::read_to_string(signature_file).unwrap().starts_with("-----BEGIN PGP SIGNATURE-----"));
+        assert!(fs::read_to_string(public_key_file).unwrap().starts_with("-----BEGIN PGP PUBLIC KEY BLOCK-----"));
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify an OpenPGP signature: {:?}", result.err());
+        let result = validate_signature(user_id, SIGNING_CONTEXT, keys_file, public_key_file);
+        assert!(result.is_ok(), "Failed to verify the OpenPGP key certification: {:?}", result.err());
+        for file in [keys_file, signature_file, public_key_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_cosign_bundle_output() {
+        let keys_file = "./results/test_cosign_bundle_output_frost_keys.json";
+        let bundle_file = "./results/test_cosign_bundle_output.bundle";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, bundle_file, frost_cli::io::envelope::OutFormat::CosignBundle, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(&fs::read_to_string(bundle_file).unwrap()).unwrap();
+        assert!(bundle["base64Signature"].is_string());
+        assert!(frost_cli::export_pkix_public_key(keys_file, &Default::default()).unwrap().starts_with("-----BEGIN PUBLIC KEY-----"));
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, bundle_file);
+        assert!(result.is_ok(), "Failed to verify a cosign bundle: {:?}", result.err());
+        assert!(validate_signature("another message", SIGNING_CONTEXT, keys_file, bundle_file).is_err());
+        remove_file(keys_file).unwrap();
+        remove_file(bundle_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_cose_output() {
+        let keys_file = "./results/test_cose_output_frost_keys.json";
+        let cose_file = "./results/test_cose_output.cose";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![1, 2], 3, keys_file, cose_file, frost_cli::io::envelope::OutFormat::Cose, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        assert_eq!(frost_cli::io::envelope::detect(&fs::read(cose_file).unwrap()), frost_cli::io::envelope::OutFormat::Cose);
+        assert!(frost_cli::export_cose_key(keys_file, &Default::default()).is_ok());
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, cose_file);
+        assert!(result.is_ok(), "Failed to verify a COSE_Sign1: {:?}", result.err());
+        assert!(validate_signature("another message", SIGNING_CONTEXT, keys_file, cose_file).is_err());
+        remove_file(keys_file).unwrap();
+        remove_file(cose_file).unwrap();
+    }
+
+    #[test]
+    fn test_dsse_output() {
+        let keys_file = "./results/test_dsse_output_frost_keys.json";
+        let envelope_file = "./results/test_dsse_output.dsse.json";
+        let message = r#"{"_type":"https://in-toto.io/Statement/v1"}"#;
+        generate_keys(2, 3, keys_file).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, envelope_file, frost_cli::io::envelope::OutFormat::Dsse, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        let envelope: serde_json::Value = serde_json::from_str(&fs::read_to_string(envelope_file).unwrap()).unwrap();
+        assert_eq!(envelope["payloadType"], "application/vnd.in-toto+json");
+        assert_eq!(envelope["signatures"].as_array().unwrap().len(), 1);
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, envelope_file);
+        assert!(result.is_ok(), "Failed to verify a DSSE envelope: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(envelope_file).unwrap();
+    }
+
+    #[test]
+    fn test_attest_slsa_provenance() {
+        let keys_file = "./results/test_attest_slsa_provenance_frost_keys.json";
+        let subject_file = "./results/test_attest_slsa_provenance_artifact.txt";
+        let envelope_file = "./results/test_attest_slsa_provenance.dsse.json";
+        let statement_file = "./results/test_attest_slsa_provenance_statement.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(subject_file, "hi, this is a test").unwrap();
+        let predicate = frost_cli::attest::Predicate::SlsaProvenance {
+            builder_id: "https://ci.example.com/runners/release".to_string(),
+            build_type: "https://ci.example.com/release@v1".to_string(),
+        };
+        frost_cli::attest::attest(&[subject_file.to_string()], &predicate, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, envelope_file, Some(statement_file), None).unwrap();
+        let statement_text = fs::read_to_string(statement_file).unwrap();
+        let statement: serde_json::Value = serde_json::from_str(&statement_text).unwrap();
+        assert_eq!(statement["predicateType"], frost_cli::attest::SLSA_PROVENANCE_TYPE);
+        assert_eq!(statement["subject"][0]["name"], "test_attest_slsa_provenance_artifact.txt");
+        assert_eq!(statement["subject"][0]["digest"]["sha256"], "bd63809a5dc18fbc229c3a454eaee6efef9bcf40e3f2e0fa63325ed86656d5bb");
+        let result = validate_signature(&statement_text, SIGNING_CONTEXT, keys_file, envelope_file);
+        assert!(result.is_ok(), "Failed to verify an attestation: {:?}", result.err());
+        for file in [keys_file, subject_file, envelope_file, statement_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_git_sign() {
+        let keys_file = "./results/test_git_sign_frost_keys.json";
+        let signature_file = "./results/test_git_sign_signature.asc";
+        let commit = b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\nThreshold-signed commit\n";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>();
+        let (mut signature, mut status) = (Vec::new(), Vec::new());
+        frost_cli::git::run(&args(&["--status-fd=2", "-bsau", "Release Team"]), keys_file, vec![0, 1], 3, None, &commit[..], &mut signature, &mut status, &Default::default()).unwrap();
+        assert!(String::from_utf8(status).unwrap().contains("\n[GNUPG:] SIG_CREATED "));
+        fs::write(signature_file, &signature).unwrap();
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        let verify_args = args(&["--keyid-format=long", "--status-fd=1", "--verify", signature_file, "-"]);
+        let result = frost_cli::git::run(&verify_args, keys_file, vec![], 3, None, &commit[..], &mut output, &mut errors, &Default::default());
+        assert!(result.is_ok(), "Failed to verify a git signature: {:?}", result.err());
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[GNUPG:] GOODSIG "));
+        assert!(output.contains("[GNUPG:] VALIDSIG "));
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        assert!(frost_cli::git::run(&verify_args, keys_file, vec![], 3, None, &b"another commit"[..], &mut output, &mut errors, &Default::default()).is_err());
+        assert!(String::from_utf8(output).unwrap().contains("[GNUPG:] BADSIG "));
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_tuf_sign_metadata() {
+        let keys_file = "./results/test_tuf_sign_metadata_frost_keys.json";
+        let metadata_file = "./results/test_tuf_sign_metadata_root.json";
+        let metadata = r#"{"signed": {"_type": "root", "spec_version": "1.0.31", "version": 1, "consistent_snapshot": true, "keys": {}, "roles": {}}, "signatures": [{"keyid": "ab", "sig": "cd"}]}"#;
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        fs::write(metadata_file, metadata).unwrap();
+        frost_cli::tuf::sign_metadata(metadata_file, vec![0, 1], 3, keys_file, None, None, &Default::default()).unwrap();
+        frost_cli::tuf::sign_metadata(metadata_file, vec![1, 2], 3, keys_file, None, None, &Default::default()).unwrap();
+        let signed: serde_json::Value = serde_json::from_str(&fs::read_to_string(metadata_file).unwrap()).unwrap();
+        assert_eq!(signed["signatures"].as_array().unwrap().len(), 2, "Signing twice should replace the group's signature");
+        let key_entry: serde_json::Value = serde_json::from_str(&frost_cli::tuf::export_key(keys_file, &Default::default()).unwrap()).unwrap();
+        let key_id = key_entry.as_object().unwrap().keys().next().unwrap();
+        assert_eq!(&signed["signatures"][1]["keyid"], key_id.as_str());
+        let result = frost_cli::tuf::verify_metadata(metadata_file, keys_file, &Default::default());
+        assert!(result.is_ok(), "Failed to verify TUF metadata: {:?}", result.err());
+        assert_eq!(
+            frost_cli::tuf::canonical_json(&serde_json::json!({"b": 1, "a": "x\"y\\z", "c": [true, null]})).unwrap(),
+            br#"{"a":"x\"y\\z","b":1,"c":[true,null]}"#.to_vec()
+        );
+        remove_file(keys_file).unwrap();
+        remove_file(metadata_file).unwrap();
+    }
+
+    #[test]
+    fn test_jcs_canonicalization() {
+        let keys_file = "./results/test_jcs_canonicalization_frost_keys.json";
+        let signature_file = "./results/test_jcs_canonicalization_signature.json";
+        let jcs = frost_cli::io::jcs::Canonicalization::Jcs;
+        // Example of RFC 8785, section 3.2.2
+        let document = r#"{"numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001], "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/", "literals": [null, true, false]}"#;
+        let canonical = r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#;
+        assert_eq!(frost_cli::io::jcs::canonicalize_as(document, jcs).unwrap(), canonical.as_bytes());
+        generate_keys(2, 3, keys_file).unwrap();
+        let options = frost_cli::io::envelope::EnvelopeOptions { canonicalization: jcs, ..Default::default() };
+        frost_cli::sign_message_as(r#"{"b": 2, "a": 1.50}"#, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file, frost_cli::io::envelope::OutFormat::Signature, &options, None).unwrap();
+        let result = validate_signature_as("{\"a\":1.5,\n \"b\":2}", SIGNING_CONTEXT, keys_file, signature_file, None, &options);
+        assert!(result.is_ok(), "Failed to verify a canonicalized message: {:?}", result.err());
+        let result = validate_signature_as("{\"a\":1.5,\n \"b\":2}", SIGNING_CONTEXT, keys_file, signature_file, None, &Default::default());
+        assert!(result.is_err(), "Verified a reformatted message without canonicalizing it");
+        assert!(validate_signature(r#"{"a":1.5,"b":2}"#, SIGNING_CONTEXT, keys_file, signature_file).is_ok());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_signature_metadata() {
+        let keys_file = "./results/test_signature_metadata_frost_keys.json";
+        let signature_file = "./results/test_signature_metadata_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let message = "hi, this is a test";
+        sign_message(message, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, signature_file).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let metadata = signature::decode_metadata(&bytes, signature_file).unwrap().expect("Signature file has no metadata");
+        assert_eq!(metadata.signers, vec![1, 3]);
+        assert_eq!(metadata.ciphersuite, frost_cli::backend::Ciphersuite::Ristretto255);
+        assert_eq!(metadata.context, "THRESHOLD SIGNING CONTEXT");
+        assert_eq!(metadata.group_key_fingerprint.len(), 64);
+        assert!(metadata.timestamp > 0);
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature with metadata: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_rfc3161_timestamp() {
+        use sha2::{Digest, Sha256};
+        use std::io::{Read, Write};
+        /// Encodes a DER element for the test TSA.
+        fn der(tag: u8, contents: &[u8]) -> Vec<u8> {
+            let mut bytes = vec![tag];
+            match contents.len() {
+                len if len < 0x80 => bytes.push(len as u8),
+                len if len < 0x100 => bytes.extend([0x81, len as u8]),
+                len => bytes.extend([0x82, (len >> 8) as u8, len as u8]),
+            }
+            bytes.extend_from_slice(contents);
+            bytes
+        }
+        const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+        const OID_TST_INFO: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04];
+        let keys_file = "./results/test_rfc3161_timestamp_frost_keys.json";
+        let signature_file = "./results/test_rfc3161_timestamp_signature.json";
+        let tsa_cert_file = "./results/test_rfc3161_timestamp_tsa.pem";
+        generate_keys(2, 3, keys_file).unwrap();
+        let message = "hi, this is a test";
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+
+        // A TSA with a self-signed P-256 certificate that answers a single request
+        let tsa_key = rcgen::KeyPair::generate().unwrap();
+        let tsa_cert = rcgen::CertificateParams::new(vec!["tsa.test".to_string()]).unwrap().self_signed(&tsa_key).unwrap();
+        fs::write(tsa_cert_file, tsa_cert.pem()).unwrap();
+        let rng = ring::rand::SystemRandom::new();
+        let signing_key = ring::signature::EcdsaKeyPair::from_pkcs8(&ring::signature::ECDSA_P256_SHA256_ASN1_SIGNING, &tsa_key.serialize_der(), &rng).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let tsa_url = format!("http://{}/", listener.local_addr().unwrap());
+        let tsa = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 1024];
+            let body_start = loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+            let content_length: usize = headers.split("content-length:").nth(1).unwrap().lines().next().unwrap().trim().parse().unwrap();
+            while request.len() < body_start + content_length {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            // The request is SEQUENCE { version, messageImprint, nonce, certReq }
+            let query = &request[body_start..];
+            let imprint = &query[5..56];
+            let nonce = &query[56..query.len() - 3];
+            let tst_info = der(0x30, &[der(0x02, &[1]), der(0x06, &[0x2a, 0x03, 0x04]), imprint.to_vec(), der(0x02, &[0x2a]), der(0x18, b"20251015120000Z"), nonce.to_vec()].concat());
+            let attributes = [
+                der(0x30, &[der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x03]), der(0x31, &der(0x06, OID_TST_INFO))].concat()),
+                der(0x30, &[der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x04]), der(0x31, &der(0x04, &Sha256::digest(&tst_info)))].concat()),
+            ]
+            .concat();
+            let signature = signing_key.sign(&rng, &der(0x31, &attributes)).unwrap();
+            let signer_info = der(0x30, &[
+                der(0x02, &[1]),
+                der(0x30, &[der(0x30, &[]), der(0x02, &[1])].concat()),
+                der(0x30, &der(0x06, OID_SHA256)),
+                der(0xa0, &attributes),
+                der(0x30, &der(0x06, &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02])),
+                der(0x04, signature.as_ref()),
+            ].concat());
+            let signed_data = der(0x30, &[
+                der(0x02, &[3]),
+                der(0x31, &der(0x30, &der(0x06, OID_SHA256))),
+                der(0x30, &[der(0x06, OID_TST_INFO), der(0xa0, &der(0x04, &tst_info))].concat()),
+                der(0x31, &signer_info),
+            ].concat());
+            let token = der(0x30, &[der(0x06, &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02]), der(0xa0, &signed_data)].concat());
+            let response = der(0x30, &[der(0x30, &der(0x02, &[0])), token].concat());
+            write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/timestamp-reply\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", response.len()).unwrap();
+            stream.write_all(&response).unwrap();
+        });
+
+        let result = frost_cli::timestamp::timestamp_signature(signature_file, &tsa_url);
+        assert!(result.is_ok(), "Failed to timestamp signature: {:?}", result.err());
+        tsa.join().unwrap();
+        let result = frost_cli::timestamp::verify_signature_timestamp(signature_file, tsa_cert_file);
+        assert!(result.is_ok(), "Failed to verify timestamp: {:?}", result.err());
+        assert_eq!(result.unwrap().time, "2025-10-15 12:00:00 UTC");
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify timestamped signature: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+        remove_file(tsa_cert_file).unwrap();
+    }
+
+    #[test]
+    fn test_json_output_line() {
+        use frost_cli::io::output::{json_line, OutputMode};
+        assert_eq!("json".parse::<OutputMode>().unwrap(), OutputMode::Json);
+        let line = json_line(
+            "signature_saved",
+            "Threshold signature saved to: signature.json",
+            serde_json::json!({ "signature_file": "signature.json", "signers": [1, 2], "metadata": null }),
+        );
+        assert_eq!(line["event"], "signature_saved");
+        assert_eq!(line["message"], "Threshold signature saved to: signature.json");
+        assert_eq!(line["signature_file"], "signature.json");
+        assert_eq!(line["signers"], serde_json::json!([1, 2]));
+        assert!(line["metadata"].is_null());
+        assert!(serde_json::from_str::<serde_json::Value>(&line.to_string()).is_ok());
+    }
+
+    #[test]
+    fn test_sign_binary_file() {
+        use sha2::{Digest, Sha256};
+        let keys_file = "./results/test_sign_binary_file_frost_keys.json";
+        let data_file = "./results/test_sign_binary_file_data.bin";
+        let signature_file = "./results/test_sign_binary_file_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        // Not valid UTF-8 and larger than a single chunk
+        let data: Vec<u8> = (0..=255u8).cycle().take(3 * frost_cli::artifact::CHUNK_SIZE + 17).collect();
+        fs::write(data_file, &data).unwrap();
+        frost_cli::sign_file(data_file, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file, SignatureFormat::Json, &Default::default(), None).unwrap();
+        frost_cli::validate_file_signature(data_file, SIGNING_CONTEXT, keys_file, signature_file, None, &Default::default()).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let recorded = signature::decode_signed_file(&bytes, signature_file).unwrap().unwrap();
+        assert_eq!(recorded.name, "test_sign_binary_file_data.bin");
+        assert_eq!(recorded.size, data.len() as u64);
+        assert_eq!(recorded.sha256, hex::encode(Sha256::digest(&data)));
+        remove_file(keys_file).unwrap();
+        remove_file(data_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_sign_manifest() {
+        let dir = "./results/test_sign_manifest";
+        let keys_file = "./results/test_sign_manifest_frost_keys.json";
+        fs::create_dir_all(dir).unwrap();
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(format!("{}/artifact.bin", dir), [0u8, 1, 2, 255, 254]).unwrap();
+        fs::write(
+            format!("{}/manifest.json", dir),
+            r#"{"entries": [
+                {"message": "release v1", "signature_file": "release.sig"},
+                {"file": "artifact.bin", "signature_file": "artifact.bin.sig"},
+                {"message": "release v1", "signature_file": "release-again.sig"}
+            ]}"#,
+        )
+        .unwrap();
+        frost_cli::manifest::sign_manifest(&format!("{}/manifest.json", dir), SIGNING_CONTEXT, vec![0, 1], 3, keys_file, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        let release = format!("{}/release.sig", dir);
+        let release_again = format!("{}/release-again.sig", dir);
+        validate_signature("release v1", SIGNING_CONTEXT, keys_file, &release).unwrap();
+        validate_signature("release v1", SIGNING_CONTEXT, keys_file, &release_again).unwrap();
+        frost_cli::validate_file_signature(&format!("{}/artifact.bin", dir), SIGNING_CONTEXT, keys_file, &format!("{}/artifact.bin.sig", dir), None, &Default::default()).unwrap();
+        // Every entry was signed with a fresh nonce commitment
+        let first = signature::decode(&fs::read(&release).unwrap(), &release, SignatureFormat::Json).unwrap();
+        let second = signature::decode(&fs::read(&release_again).unwrap(), &release_again, SignatureFormat::Json).unwrap();
+        assert_ne!(first[..32], second[..32]);
+        remove_file(keys_file).unwrap();
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_manifest() {
+        let dir = "./results/test_verify_manifest";
+        let keys_file = "./results/test_verify_manifest_frost_keys.json";
+        let manifest_file = format!("{}/manifest.json", dir);
+        fs::create_dir_all(dir).unwrap();
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(format!("{}/artifact.bin", dir), [7u8; 1000]).unwrap();
+        fs::write(
+            &manifest_file,
+            r#"{"entries": [
+                {"message": "release v1", "signature_file": "release.sig"},
+                {"file": "artifact.bin", "signature_file": "artifact.bin.sig"}
+            ]}"#,
+        )
+        .unwrap();
+        frost_cli::manifest::sign_manifest(&manifest_file, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        frost_cli::manifest::verify_manifest(&manifest_file, SIGNING_CONTEXT, keys_file, None, &Default::default()).unwrap();
+        remove_file(keys_file).unwrap();
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_keys_progress() {
+        use frost_cli::{KeygenProgress, KeygenStage};
+        use std::cell::RefCell;
+        let keys_file = "./results/test_generate_keys_progress_frost_keys.json";
+        let updates: RefCell<Vec<KeygenProgress>> = RefCell::new(Vec::new());
+        frost_cli::generate_keys_with_progress(2, 4, keys_file, frost_cli::backend::Ciphersuite::Ristretto255, &|progress| {
+            updates.borrow_mut().push(progress)
+        },
+            &Default::default(),)
+        .unwrap();
+        let updates = updates.into_inner();
+        for stage in [KeygenStage::Commitments, KeygenStage::Proofs, KeygenStage::RoundOne, KeygenStage::RoundTwo, KeygenStage::Finish] {
+            let done: Vec<u32> = updates.iter().filter(|p| p.stage == stage).map(|p| p.done).collect();
+            assert_eq!(done, vec![1, 2, 3, 4], "Unexpected progress of {:?}", stage);
+        }
+        assert!(updates.iter().all(|p| p.total == 4));
+        assert_eq!(updates.last().unwrap().stage, KeygenStage::Finish);
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_library_output_silent_by_default() {
+        use frost_cli::io::output::{output_mode, OutputMode};
+        // Tests never set an output mode, so results stay off stdout like in any application
+        assert_eq!(output_mode(), OutputMode::None);
+        assert_eq!("none".parse::<OutputMode>().unwrap(), OutputMode::None);
+        assert_eq!("text".parse::<OutputMode>().unwrap(), OutputMode::Text);
+    }
+
+    #[test]
+    fn test_verify_error_kinds() {
+        let keys_file = "./results/test_verify_error_kinds_frost_keys.json";
+        let signature_file = "./results/test_verify_error_kinds_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        validate_signature("hi, this is a test", SIGNING_CONTEXT, keys_file, signature_file).unwrap();
+        // A missing signature file is an I/O error, which verify exits with 3 on
+        let result = validate_signature("hi, this is a test", SIGNING_CONTEXT, keys_file, "./results/test_verify_error_kinds_missing.json");
+        let err = result.unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(frost_cli::error::FrostCliError::Io { .. })));
+        assert!(frost_cli::error::is_io_error(err.as_ref()));
+        // A malformed signature file is bad input, which verify exits with 2 on
+        fs::write(signature_file, "{ not a signature").unwrap();
+        let err = validate_signature("hi, this is a test", SIGNING_CONTEXT, keys_file, signature_file).unwrap_err();
+        assert!(!frost_cli::error::is_io_error(err.as_ref()) && !err.is::<frost_cli::InvalidSignature>());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_keystore_key_names() {
+        let keystore_dir = Path::new("./results/test_keystore_key_names");
+        assert!(keystore::key_names(keystore_dir).unwrap().is_empty());
+        for name in ["treasury", "release"] {
+            let key_file = keystore::new_key_file(keystore_dir, name).unwrap();
+            generate_keys(2, 3, &key_file).unwrap();
+        }
+        // A directory without a key file is not a key
+        fs::create_dir_all(keystore_dir.join("empty")).unwrap();
+        assert_eq!(keystore::key_names(keystore_dir).unwrap(), vec!["release", "treasury"]);
+        remove_dir_all(keystore_dir).unwrap();
+    }
+
+    #[test]
+    fn test_config_var_from_file() {
+        let secret_file = "./results/test_config_var_from_file_secret";
+        fs::write(secret_file, "s3cret\n").unwrap();
+        // Variables only used by this test, so no other test sees them
+        std::env::set_var("FROST_TEST_CONFIG_VAR_FILE", secret_file);
+        assert_eq!(frost_cli::config::var("FROST_TEST_CONFIG_VAR").unwrap().as_deref(), Some("s3cret"));
+        std::env::set_var("FROST_TEST_CONFIG_VAR", "from-env");
+        assert_eq!(frost_cli::config::var("FROST_TEST_CONFIG_VAR").unwrap().as_deref(), Some("from-env"));
+        assert_eq!(frost_cli::config::var("FROST_TEST_CONFIG_UNSET").unwrap(), None);
+        std::env::remove_var("FROST_TEST_CONFIG_VAR");
+        std::env::remove_var("FROST_TEST_CONFIG_VAR_FILE");
+        remove_file(secret_file).unwrap();
+    }
+
+    #[test]
+    fn test_signing_context() {
+        use frost_cli::backend::Ciphersuite;
+        assert_eq!(Ciphersuite::Ristretto255.signing_context(SIGNING_CONTEXT), SIGNING_CONTEXT);
+        assert_eq!(Ciphersuite::Ristretto255.signing_context("release-signing"), "release-signing");
+        assert!(Ciphersuite::Ed25519.signing_context(SIGNING_CONTEXT).is_empty());
+        assert!(Ciphersuite::Ed25519.check_signing_context(SIGNING_CONTEXT).is_ok());
+        assert!(Ciphersuite::Ed25519.check_signing_context("release-signing").is_err());
+    }
+
+    #[test]
+    fn test_sign_in_two_contexts() {
+        let keys_file = "./results/test_sign_in_two_contexts_frost_keys.json";
+        let release_file = "./results/test_sign_in_two_contexts_release.json";
+        let audit_file = "./results/test_sign_in_two_contexts_audit.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        // Signatures for two applications are made side by side in one process
+        let release = thread::spawn(move || sign_message(message, "release-signing", vec![0, 1], 3, keys_file, release_file).map_err(|err| err.to_string()));
+        let audit = thread::spawn(move || sign_message(message, "audit-log", vec![1, 2], 3, keys_file, audit_file).map_err(|err| err.to_string()));
+        release.join().unwrap().unwrap();
+        audit.join().unwrap().unwrap();
+        assert!(validate_signature(message, "release-signing", keys_file, release_file).is_ok());
+        assert!(validate_signature(message, "audit-log", keys_file, audit_file).is_ok());
+        let result = validate_signature(message, "audit-log", keys_file, release_file);
+        assert!(
+            result.err().expect("Expected a signature of another context to fail").to_string().contains("release-signing"),
+            "Expected the error to name the signature's context"
+        );
+        for file in [keys_file, release_file, audit_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_inspect_key() {
+        use frost_cli::inspect::{inspect_key, KeyFileKind, Shares};
+        let keys_file = "./results/test_inspect_key_frost_keys.json";
+        generate_keys(3, 5, keys_file).unwrap();
+        let inspection = inspect_key(keys_file, &Default::default()).unwrap();
+        assert_eq!(inspection.kind, KeyFileKind::Keys);
+        assert_eq!(inspection.threshold, Some(3));
+        assert_eq!(inspection.participants, Some(5));
+        assert_eq!(inspection.indices, vec![1, 2, 3, 4, 5]);
+        assert_eq!(inspection.shares, Shares::Plaintext);
+        assert_eq!(inspection.group_key, export_group_key(keys_file, false, &Default::default()).unwrap().trim());
+        // No private share ends up in the text or JSON output
+        let keys: serde_json::Value = serde_json::from_str(&fs::read_to_string(keys_file).unwrap()).unwrap();
+        let text = format!("{}{}", inspection, serde_json::to_string(&inspection).unwrap());
+        for share in keys["private_shares"].as_array().unwrap() {
+            let bytes: Vec<u8> = serde_json::from_value(share[0].clone()).unwrap();
+            assert!(!text.contains(&hex::encode(&bytes)));
+        }
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_inspect_signature() {
+        use frost_cli::inspect::inspect_signature;
+        let keys_file = "./results/test_inspect_signature_frost_keys.json";
+        let signature_file = "./results/test_inspect_signature_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let inspection = inspect_signature(signature_file, None).unwrap();
+        assert_eq!(inspection.format, "json");
+        assert_eq!(inspection.threshold_signature, Some(true));
+        assert!(!inspection.timestamped);
+        let metadata = inspection.metadata.unwrap();
+        assert_eq!(metadata.signers, vec![1, 2]);
+        let file: serde_json::Value = serde_json::from_str(&fs::read_to_string(signature_file).unwrap()).unwrap();
+        let signature: Vec<u8> = serde_json::from_value(file["signature"].clone()).unwrap();
+        assert_eq!(inspection.r.unwrap(), hex::encode(&signature[..32]));
+        assert_eq!(inspection.s.unwrap(), hex::encode(&signature[32..]));
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        use frost_cli::check::check_compatibility;
+        let keys_file = "./results/test_check_compatibility_frost_keys.json";
+        let signature_file = "./results/test_check_compatibility_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let report = check_compatibility(keys_file, Some(signature_file), None, SIGNING_CONTEXT, &Default::default());
+        assert!(report.is_compatible(), "Expected compatible files: {}", report);
+        assert_eq!(report.format_version, frost_cli::FORMAT_VERSION);
+        assert!(report.ciphersuites.contains(&frost_cli::backend::Ciphersuite::Ristretto255));
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_sign_and_verify_in_memory() {
+        use frost_cli::backend::Ciphersuite;
+        use frost_cli::{generate_frost_keys, sign_with_keys, verify_with_group_key};
+        let message = b"hi, this is a test";
+        let frost_keys = generate_frost_keys(2, 3, Ciphersuite::Ristretto255, &|_| {}).unwrap();
+        assert_eq!(frost_keys.private_shares.len(), 3);
+        let signature = sign_with_keys(message, SIGNING_CONTEXT, &frost_keys, &[0, 2]).unwrap();
+        assert!(frost_dalek::signature::ThresholdSignature::from_bytes(signature).is_ok());
+        let result = verify_with_group_key(message, SIGNING_CONTEXT, frost_keys.ciphersuite, &frost_keys.group_key, &signature);
+        assert!(result.is_ok(), "Failed to verify signature in memory: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        use frost_cli::error::FrostCliError;
+        let keys_file = "./results/test_error_kinds_frost_keys.json";
+        let share_file = "./results/test_error_kinds_share.json";
+        let err = generate_keys(4, 3, keys_file).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidParameters(_))));
+        let err = frost_cli::load_frost_keys(keys_file, &Default::default()).err().unwrap();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::Io { path, .. }) if path == keys_file));
+        fs::write(share_file, "{ not a share").unwrap();
+        let err = load_participant_share(share_file, &Default::default()).err().unwrap();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidShare { share, .. }) if share == share_file));
+        remove_file(share_file).unwrap();
+    }
+
+    #[test]
+    fn test_sign_with_seeded_rng() {
+        use frost_cli::backend::Ciphersuite;
+        use frost_cli::{generate_frost_keys, sign_with_keys_and_rng, verify_with_group_key};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let message = b"hi, this is a test";
+        let frost_keys = generate_frost_keys(2, 3, Ciphersuite::Ristretto255, &|_| {}).unwrap();
+        let first = sign_with_keys_and_rng(message, SIGNING_CONTEXT, &frost_keys, &[0, 1], None, &mut StdRng::seed_from_u64(7)).unwrap();
+        let second = sign_with_keys_and_rng(message, SIGNING_CONTEXT, &frost_keys, &[0, 1], None, &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(first, second, "Expected the same nonces from the same seed");
+        let other = sign_with_keys_and_rng(message, SIGNING_CONTEXT, &frost_keys, &[0, 1], None, &mut StdRng::seed_from_u64(8)).unwrap();
+        assert_ne!(first, other);
+        verify_with_group_key(message, SIGNING_CONTEXT, frost_keys.ciphersuite, &frost_keys.group_key, &first).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_sign_and_verify() {
+        use frost_cli::backend::Ciphersuite;
+        use frost_cli::nonblocking;
+        let keys_file = "./results/test_async_sign_and_verify_frost_keys.json";
+        let signature_files = [
+            "./results/test_async_sign_and_verify_signature_1.json",
+            "./results/test_async_sign_and_verify_signature_2.json",
+        ];
+        nonblocking::generate_keys(2, 3, keys_file, Ciphersuite::Ristretto255).await.unwrap();
+        let (first, second) = tokio::join!(
+            nonblocking::sign_message("first message", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_files[0]),
+            nonblocking::sign_message("second message", SIGNING_CONTEXT, vec![1, 2], 3, keys_file, signature_files[1]),
+        );
+        first.unwrap();
+        second.unwrap();
+        let (first, second) = tokio::join!(
+            nonblocking::validate_signature("first message", SIGNING_CONTEXT, keys_file, signature_files[0]),
+            nonblocking::validate_signature("second message", SIGNING_CONTEXT, keys_file, signature_files[1]),
+        );
+        assert!(first.is_ok(), "Failed to verify the first signature: {:?}", first.err());
+        assert!(second.is_ok(), "Failed to verify the second signature: {:?}", second.err());
+
+        let frost_keys = std::sync::Arc::new(nonblocking::generate_frost_keys(2, 3, Ciphersuite::Ristretto255).await.unwrap());
+        let task = tokio::spawn(nonblocking::sign_with_keys(b"in memory".to_vec(), SIGNING_CONTEXT, frost_keys.clone(), vec![0, 2]));
+        let signature = task.await.unwrap().unwrap();
+        let result = nonblocking::verify_with_group_key(b"in memory".to_vec(), SIGNING_CONTEXT, frost_keys.ciphersuite, frost_keys.group_key, signature).await;
+        assert!(result.is_ok(), "Failed to verify the in-memory signature: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        for signature_file in signature_files {
+            remove_file(signature_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_file_keystore_trait() {
+        use frost_cli::keystore::{FileKeyStore, KeyStore};
+        let keystore_dir = "./results/test_file_keystore_trait";
+        let share_files = run_dkg("test_file_keystore_trait", 2, 3);
+        let signature_file = "./results/test_file_keystore_trait_signature.json";
+        let keystore = FileKeyStore::new(keystore_dir);
+        let names = vec!["alice".to_string(), "bob".to_string()];
+        for (name, share_file) in names.iter().zip(&share_files) {
+            keystore.store_share(name, &load_participant_share(share_file, &Default::default()).unwrap()).unwrap();
+        }
+        let keys = keystore.list_keys().unwrap();
+        assert_eq!(keys.iter().map(|key| key.name.as_str()).collect::<Vec<_>>(), vec!["alice", "bob"]);
+        assert_eq!(keystore.load_share("bob").unwrap().index, 2);
+        let message = "hi, this is a test";
+        frost_cli::sign_message_with_keystore(message, SIGNING_CONTEXT, &keystore, &names, signature_file, None).unwrap();
+        let result = frost_cli::validate_signature_with_keystore(message, SIGNING_CONTEXT, &keystore, "alice", signature_file, None);
+        assert!(result.is_ok(), "Failed to verify signature with a keystore group key: {:?}", result.err());
+        for file in share_files.iter().map(String::as_str).chain([signature_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(keystore_dir).unwrap();
+    }
+
+    #[test]
+    fn test_transport_dkg_and_signing() {
+        use frost_cli::net::transport::{self, FileTransport, MemoryTransport, Transport};
+        let work_dir = "./results/test_transport_dkg_and_signing";
+        let share_files: Vec<String> = (1..=3).map(|i| format!("{}/share_{}.json", work_dir, i)).collect();
+        thread::scope(|scope| {
+            for (transport, share_file) in MemoryTransport::network(3).into_iter().zip(&share_files) {
+                let dkg_dir = format!("{}/dkg_{}", work_dir, transport.index());
+                scope.spawn(move || transport::run_dkg(&transport, 2, 3, &dkg_dir, share_file, &Default::default()).unwrap());
+            }
+        });
+        let signature_files: Vec<String> = (1..=3).map(|i| format!("{}/signature_{}.json", work_dir, i)).collect();
+        thread::scope(|scope| {
+            for signer in [1, 3] {
+                let transport = FileTransport::new(format!("{}/mailbox", work_dir), signer).unwrap();
+                let share_file = &share_files[signer as usize - 1];
+                let signature_file = &signature_files[signer as usize - 1];
+                let sign_dir = format!("{}/sign_{}", work_dir, signer);
+                scope.spawn(move || {
+                    transport::run_signing(&transport, "hi, this is a test", SIGNING_CONTEXT, "transport", share_file, &[1, 3], &sign_dir, signature_file, &Default::default()).unwrap()
+                });
+            }
+        });
+        validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_files[1], &signature_files[0]).unwrap();
+        validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_files[1], &signature_files[2]).unwrap();
+        remove_dir_all(work_dir).unwrap();
+    }
+
+    #[test]
+    fn test_protocol_message_schema() {
+        use frost_cli::messages::{self, MessageKind, PartialSignature, RoundMessage};
+        let partial = PartialSignature { session_id: "session".to_string(), index: 2, z: [7; 32], identity_signature: None };
+        let bytes = messages::encode(&RoundMessage::PartialSignature(partial)).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let expected = serde_json::json!({
+            "version": 1,
+            "message": { "partial_signature": { "session_id": "session", "index": 2, "z": vec![7u8; 32] } },
+        });
+        assert_eq!(value, expected);
+        let message = messages::decode(&bytes).unwrap();
+        assert_eq!(message.kind(), MessageKind::PartialSignature);
+        let RoundMessage::PartialSignature(partial) = message else { panic!("Expected a partial signature") };
+        assert_eq!((partial.session_id.as_str(), partial.index, partial.z), ("session", 2, [7; 32]));
+    }
+
+    #[test]
+    fn test_participant_operations_in_memory() {
+        use frost_cli::identity::load_identity;
+        let dir = "./results/test_participant_operations_in_memory";
+        let identities_dir = format!("{}/identities", dir);
+        let bundles_dir = format!("{}/bundles", dir);
+        let roster_file = format!("{}/roster.json", dir);
+        fs::create_dir_all(dir).unwrap();
+        let identity_files: Vec<String> = (1..=3).map(|i| format!("{}/identity_{}.json", dir, i)).collect();
+        for (i, identity_file) in identity_files.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, &identities_dir, &Default::default()).unwrap();
+        }
+        dealer::write_roster(&identities_dir, 3, &roster_file, &Default::default()).unwrap();
+        dealer::generate_encrypted_shares(2, 3, &roster_file, &bundles_dir, &Default::default()).unwrap();
+        let shares: Vec<_> = (1..=2)
+            .map(|i| {
+                let identity = load_identity(&identity_files[i - 1]).unwrap();
+                let bundle: dealer::ShareBundle = serde_json::from_slice(&fs::read(format!("{}/share_bundle_{}.json", bundles_dir, i)).unwrap()).unwrap();
+                dealer::open_share_bundle(&identity, &bundle).unwrap()
+            })
+            .collect();
+        let commitments: Vec<_> = shares.iter().map(|share| signing::commit_with_share(SESSION_ID, share, signing::CommitOptions::default()).unwrap()).collect();
+        let roster: Vec<signing::PublicCommitments> = commitments.iter().map(|(public, _)| public.clone()).collect();
+        let partials_dir = format!("{}/partials", dir);
+        fs::create_dir_all(&partials_dir).unwrap();
+        let mut commitment_files = Vec::new();
+        for (share, (public, secret)) in shares.iter().zip(&commitments) {
+            let (partial, remaining) = signing::partial_sign_with_share("hi, this is a test", SIGNING_CONTEXT, share, secret, &roster).unwrap();
+            assert!(remaining.nonces.is_empty(), "Expected the used nonces to be burnt");
+            fs::write(format!("{}/partial_{}.json", partials_dir, share.index), serde_json::to_vec(&partial).unwrap()).unwrap();
+            let commitment_file = format!("{}/commitments_{}.json", dir, share.index);
+            fs::write(&commitment_file, serde_json::to_vec(public).unwrap()).unwrap();
+            commitment_files.push(commitment_file);
+        }
+        let share_file = format!("{}/share_1.json", dir);
+        save_participant_share(&share_file, &shares[0], &Default::default()).unwrap();
+        let signature_file = format!("{}/signature.json", dir);
+        signing::aggregate("hi, this is a test", SIGNING_CONTEXT, &share_file, &commitment_files, &partials_dir, &signature_file).unwrap();
+        validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_file, &signature_file).unwrap();
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "cdylib")]
+    fn test_ffi_signing_rounds() {
+        use frost_cli::ffi;
+        use std::ffi::CString;
+        let c = |value: &str| CString::new(value).unwrap();
+        let dir = "./results/test_ffi_signing_rounds";
+        let partials_dir = format!("{}/partials", dir);
+        fs::create_dir_all(&partials_dir).unwrap();
+        let key_file = format!("{}/keys.json", dir);
+        assert_eq!(unsafe { ffi::frost_generate_keys(2, 3, c(&key_file).as_ptr(), std::ptr::null()) }, ffi::FROST_OK);
+        let share_files = run_dkg("test_ffi_signing_rounds", 2, 3);
+        let commitment_files: Vec<CString> = (1..=2).map(|i| c(&format!("{}/commitments_{}.json", dir, i))).collect();
+        let commitment_ptrs: Vec<_> = commitment_files.iter().map(|file| file.as_ptr()).collect();
+        let secret_files: Vec<CString> = (1..=2).map(|i| c(&format!("{}/secret_commitments_{}.json", dir, i))).collect();
+        let message = c("hi, this is a test");
+        for i in 0..2 {
+            let code = unsafe { ffi::frost_commit(c(SESSION_ID).as_ptr(), c(&share_files[i]).as_ptr(), commitment_ptrs[i], secret_files[i].as_ptr()) };
+            assert_eq!(code, ffi::FROST_OK);
+        }
+        for i in 0..2 {
+            let partial_file = c(&format!("{}/partial_{}.json", partials_dir, i + 1));
+            let code = unsafe {
+                ffi::frost_partial_sign(message.as_ptr(), std::ptr::null(), c(&share_files[i]).as_ptr(), secret_files[i].as_ptr(), commitment_ptrs.as_ptr(), commitment_ptrs.len(), partial_file.as_ptr())
+            };
+            assert_eq!(code, ffi::FROST_OK);
+            assert!(ffi::frost_last_error().is_null());
+        }
+        let signature_file = c(&format!("{}/signature.json", dir));
+        let share_file = c(&share_files[2]);
+        let code = unsafe { ffi::frost_aggregate(message.as_ptr(), std::ptr::null(), share_file.as_ptr(), commitment_ptrs.as_ptr(), commitment_ptrs.len(), c(&partials_dir).as_ptr(), signature_file.as_ptr()) };
+        assert_eq!(code, ffi::FROST_OK);
+        assert_eq!(unsafe { ffi::frost_verify(message.as_ptr(), std::ptr::null(), share_file.as_ptr(), signature_file.as_ptr()) }, ffi::FROST_OK);
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn test_python_module() {
+        use pyo3::prelude::*;
+        use pyo3::types::IntoPyDict;
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(frost_cli::python::frost_dalek_cli)(py);
+            let locals = [("frost", module)].into_py_dict_bound(py);
+            let script = r#"
+keys = "./results/test_python_module_keys.json"
+signature = "./results/test_python_module_signature.json"
+frost.generate_keys(2, 3, keys)
+frost.sign_message("hi, this is a test", [0, 2], 3, keys, signature)
+frost.validate_signature("hi, this is a test", keys, signature)
+assert len(frost.export_group_key(keys)) == 64
+"#;
+            py.run_bound(script, None, Some(&locals)).unwrap();
+        });
+        remove_file("./results/test_python_module_keys.json").unwrap();
+        remove_file("./results/test_python_module_signature.json").unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "uniffi")]
+    fn test_mobile_signer() {
+        use frost_cli::mobile;
+        let dir = "./results/test_mobile_signer";
+        let identities_dir = format!("{}/identities", dir);
+        let bundles_dir = format!("{}/bundles", dir);
+        let roster_file = format!("{}/roster.json", dir);
+        fs::create_dir_all(dir).unwrap();
+        let identity_files: Vec<String> = (1..=3).map(|i| format!("{}/identity_{}.json", dir, i)).collect();
+        for (i, identity_file) in identity_files.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, &identities_dir, &Default::default()).unwrap();
+        }
+        dealer::write_roster(&identities_dir, 3, &roster_file, &Default::default()).unwrap();
+        dealer::generate_encrypted_shares(2, 3, &roster_file, &bundles_dir, &Default::default()).unwrap();
+        let shares: Vec<String> = (1..=2)
+            .map(|i| {
+                let identity = fs::read_to_string(&identity_files[i - 1]).unwrap();
+                let bundle = fs::read_to_string(format!("{}/share_bundle_{}.json", bundles_dir, i)).unwrap();
+                mobile::decrypt_share(identity, bundle).unwrap()
+            })
+            .collect();
+        let commitments: Vec<mobile::Commitments> = shares.iter().map(|share| mobile::commit(SESSION_ID.to_string(), share.clone()).unwrap()).collect();
+        let roster = format!("[{}]", commitments.iter().map(|c| c.public_commitments.as_str()).collect::<Vec<_>>().join(","));
+        let partials_dir = format!("{}/partials", dir);
+        fs::create_dir_all(&partials_dir).unwrap();
+        let mut commitment_files = Vec::new();
+        for (i, (share, commitments)) in shares.iter().zip(&commitments).enumerate() {
+            let result = mobile::partial_sign("hi, this is a test".to_string(), share.clone(), commitments.secret_commitments.clone(), roster.clone(), None).unwrap();
+            fs::write(format!("{}/partial_{}.json", partials_dir, i + 1), &result.partial_signature).unwrap();
+            let commitment_file = format!("{}/commitments_{}.json", dir, i + 1);
+            fs::write(&commitment_file, &commitments.public_commitments).unwrap();
+            commitment_files.push(commitment_file);
+        }
+        let share_file = format!("{}/share_1.json", dir);
+        fs::write(&share_file, &shares[0]).unwrap();
+        let signature_file = format!("{}/signature.json", dir);
+        signing::aggregate("hi, this is a test", SIGNING_CONTEXT, &share_file, &commitment_files, &partials_dir, &signature_file).unwrap();
+        validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_file, &signature_file).unwrap();
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_refresh() {
+        let share_files = run_dkg("test_refresh", 2, 3);
+        let dir = "./results/test_refresh";
+        let old_share_file = "./results/test_refresh_old_share_1.json";
+        let signature_file = "./results/test_refresh_signature.json";
+        fs::copy(&share_files[0], old_share_file).unwrap();
+        let old_shares: Vec<_> = share_files.iter().map(|file| load_participant_share(file, &Default::default()).unwrap()).collect();
+        let state_files: Vec<String> = (1..=3).map(|i| format!("./results/test_refresh_state_{}.json", i)).collect();
+        for (share_file, state_file) in share_files.iter().zip(&state_files) {
+            frost_cli::refresh::round_one("refresh-1", share_file, state_file, dir, &Default::default()).unwrap();
+        }
+        // The last participant saves the refreshed share elsewhere, which deletes the old one.
+        let refreshed_file = "./results/test_refresh_refreshed_share_3.json";
+        for (i, (share_file, state_file)) in share_files.iter().zip(&state_files).enumerate() {
+            let output_file = if i == 2 { refreshed_file } else { share_file.as_str() };
+            let result = frost_cli::refresh::finish(state_file, share_file, dir, output_file, &Default::default());
+            assert!(result.is_ok(), "Refresh failed: {:?}", result.err());
+            assert!(!Path::new(state_file).exists(), "The refresh state should be deleted");
+        }
+        assert!(!Path::new(&format!("{}/refresh_share_2_1.json", dir)).exists(), "Received refresh shares should be deleted");
+        assert!(!Path::new(&share_files[2]).exists(), "The old share should be deleted");
+        fs::rename(refreshed_file, &share_files[2]).unwrap();
+        for (share_file, old_share) in share_files.iter().zip(&old_shares) {
+            let share = load_participant_share(share_file, &Default::default()).unwrap();
+            assert_eq!(share.group_key, old_share.group_key, "Refresh changed the group key");
+            assert_ne!(share.share, old_share.share, "Refresh left the share unchanged");
+            assert_eq!(share.verification_shares, load_participant_share(&share_files[0], &Default::default()).unwrap().verification_shares, "Refreshed verification shares differ");
+            assert!(backup::verify_share(share_file, &[], &Default::default()).is_ok(), "Refreshed share does not match its verification share");
+        }
+
+        // Any two refreshed shares sign, but an old share no longer signs with a new one.
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[..2], signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, old_share_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made with refreshed shares: {:?}", result.err());
+        let mixed = vec![old_share_file.to_string(), share_files[1].clone()];
+        let result = sign_message_with_shares(message, SIGNING_CONTEXT, &mixed, signature_file, None);
+        assert!(result.is_err(), "An old share should not sign with a refreshed share");
+
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(old_share_file).unwrap();
+        let _ = remove_file(signature_file);
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reshare() {
+        // Reshare a 2-of-3 dealer key file to 3-of-4 in one process.
+        let keys_file = "./results/test_reshare_keys.json";
+        let reshared_file = "./results/test_reshare_keys_reshared.json";
+        let signature_file = "./results/test_reshare_signature.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::reshare::reshare_keys(keys_file, &[0, 2], 3, 4, reshared_file, &Default::default());
+        assert!(result.is_ok(), "Failed to reshare keys: {:?}", result.err());
+        let reshared = frost_cli::load_frost_keys(reshared_file, &Default::default()).unwrap();
+        assert_eq!(reshared.group_key, frost_cli::load_frost_keys(keys_file, &Default::default()).unwrap().group_key, "Reshare changed the group key");
+        assert_eq!((reshared.threshold, reshared.private_shares.len()), (3, 4));
+        sign_message(message, SIGNING_CONTEXT, vec![1, 2, 3], 4, reshared_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made with reshared keys: {:?}", result.err());
+
+        // Reshare 2-of-3 DKG shares to 3-of-4 participants with the per-participant rounds.
+        let share_files = run_dkg("test_reshare", 2, 3);
+        let dir = "./results/test_reshare";
+        for share_file in &share_files[1..] {
+            frost_cli::reshare::round_one(share_file, &[2, 3], 3, 4, dir, &Default::default()).unwrap();
+        }
+        let new_share_files: Vec<String> = (1..=4).map(|i| format!("./results/test_reshare_new_share_{}.json", i)).collect();
+        for (i, new_share_file) in new_share_files.iter().enumerate() {
+            let result = frost_cli::reshare::finish(i as u32 + 1, &[2, 3], dir, new_share_file, &Default::default());
+            assert!(result.is_ok(), "Reshare failed for participant {}: {:?}", i + 1, result.err());
+        }
+        let new_share = load_participant_share(&new_share_files[0], &Default::default()).unwrap();
+        assert_eq!(new_share.group_key, load_participant_share(&share_files[0], &Default::default()).unwrap().group_key, "Reshare changed the group key");
+        assert_eq!((new_share.threshold, new_share.participants), (3, 4));
+        for new_share_file in &new_share_files {
+            assert_eq!(load_participant_share(new_share_file, &Default::default()).unwrap().verification_shares, new_share.verification_shares, "Reshared verification shares differ");
+            assert!(backup::verify_share(new_share_file, &[], &Default::default()).is_ok(), "Reshared share does not match its verification share");
+        }
+        sign_message_with_shares(message, SIGNING_CONTEXT, &new_share_files[1..], signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made with reshared shares: {:?}", result.err());
+
+        for file in share_files.iter().chain(&new_share_files) {
+            remove_file(file).unwrap();
+        }
+        for file in [keys_file, reshared_file, signature_file] {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_enroll() {
+        // Enroll a fourth participant into a 2-of-3 dealer key file in one process.
+        let keys_file = "./results/test_enroll_keys.json";
+        let enrolled_file = "./results/test_enroll_keys_enrolled.json";
+        let signature_file = "./results/test_enroll_signature.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::enroll::enroll_keys(keys_file, &[0, 1], enrolled_file, &Default::default());
+        assert!(result.is_ok(), "Failed to enroll participant: {:?}", result.err());
+        assert_eq!(frost_cli::load_frost_keys(enrolled_file, &Default::default()).unwrap().private_shares.len(), 4);
+        sign_message(message, SIGNING_CONTEXT, vec![2, 3], 4, enrolled_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made with the enrolled share: {:?}", result.err());
+
+        // Enroll a fourth participant into a DKG group with helpers 1 and 2.
+        let share_files = run_dkg("test_enroll", 2, 3);
+        let dir = "./results/test_enroll";
+        let new_share_file = "./results/test_enroll_new_share.json";
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_one(share_file, &[1, 2], 4, dir, &Default::default()).unwrap();
+        }
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_two(share_file, &[1, 2], 4, dir, dir, &Default::default()).unwrap();
+        }
+        let result = frost_cli::enroll::finish(4, &[1, 2], dir, new_share_file, &Default::default());
+        assert!(result.is_ok(), "Enrollment failed: {:?}", result.err());
+        assert!(backup::verify_share(new_share_file, &[], &Default::default()).is_ok(), "Enrolled share does not match its verification share");
+        for share_file in &share_files {
+            frost_cli::enroll::update_share(share_file, 4, &Default::default()).unwrap();
+        }
+        let signers = vec![share_files[2].clone(), new_share_file.to_string()];
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made with the enrolled share: {:?}", result.err());
+
+        for file in share_files.iter().map(String::as_str).chain([keys_file, enrolled_file, signature_file, new_share_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_revoke() {
+        // Revoke participant 2 of a 2-of-4 dealer key file in one process.
+        let dir = "./results/test_revoke";
+        fs::create_dir_all(dir).unwrap();
+        let keys_file = "./results/test_revoke/frost_keys.json";
+        let revoked_file = "./results/test_revoke/frost_keys_revoked.json";
+        let signature_file = "./results/test_revoke/signature.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 4, keys_file).unwrap();
+        let result = frost_cli::revoke::revoke_keys(keys_file, &[0, 2], &[2], revoked_file, &Default::default());
+        assert!(result.is_ok(), "Failed to revoke participant: {:?}", result.err());
+        let indices: Vec<u32> = frost_cli::load_frost_keys(revoked_file, &Default::default()).unwrap().private_shares.iter().map(|(_, index)| *index).collect();
+        assert_eq!(indices, vec![1, 3, 4]);
+        assert_eq!(frost_cli::revoke::revoked_indices(revoked_file, &Default::default()).unwrap(), vec![2]);
+        sign_message(message, SIGNING_CONTEXT, vec![1, 2], 3, revoked_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature made after the revocation: {:?}", result.err());
+        remove_dir_all(dir).unwrap();
+
+        // Revoke participant 3 of a DKG group with dealers 1 and 2.
+        let share_files = run_dkg("test_revoke", 2, 4);
+        let reshare_dir = "./results/test_revoke_reshare";
+        let new_share_files: Vec<String> = [1, 2, 4].iter().map(|i| format!("{}/share_{}.json", reshare_dir, i)).collect();
+        let statement_file = "./results/test_revoke_reshare/revocation.json";
+        let signature_file = "./results/test_revoke_reshare/signature.json";
+        let session_file = "./results/test_revoke_reshare/session.json";
+        for share_file in &share_files[..2] {
+            frost_cli::revoke::round_one(share_file, &[1, 2], &[3], reshare_dir, &Default::default()).unwrap();
+        }
+        assert!(!Path::new(&format!("{}/reshare_share_1_3.json", reshare_dir)).exists(), "No share should be dealt to the revoked participant");
+        for (index, new_share_file) in [1, 2, 4].iter().zip(&new_share_files) {
+            let result = frost_cli::reshare::finish(*index, &[1, 2], reshare_dir, new_share_file, &Default::default());
+            assert!(result.is_ok(), "Reshare without the revoked participant failed: {:?}", result.err());
+        }
+        let result = frost_cli::reshare::finish(3, &[1, 2], reshare_dir, &format!("{}/share_3.json", reshare_dir), &Default::default());
+        assert!(result.is_err(), "Expected the revoked participant not to receive a share");
+
+        // The remaining participants sign the statement and record it.
+        frost_cli::revoke::new_statement(&new_share_files[0], &[3], statement_file, &Default::default()).unwrap();
+        let statement = fs::read_to_string(statement_file).unwrap();
+        sign_message_with_shares(&statement, SIGNING_CONTEXT, &new_share_files[..2], signature_file, None).unwrap();
+        let result = frost_cli::revoke::record(&new_share_files[0], statement_file, signature_file, &Default::default());
+        assert!(result.is_ok(), "Failed to record revocation: {:?}", result.err());
+        assert_eq!(frost_cli::revoke::revoked_indices(&new_share_files[0], &Default::default()).unwrap(), vec![3]);
+        let result = session::new_session(message, SIGNING_CONTEXT, &new_share_files[0], vec![1, 3], session_file, &Default::default());
+        assert!(result.is_err(), "Expected a session with the revoked participant to fail");
+        let result = session::new_session(message, SIGNING_CONTEXT, &new_share_files[0], vec![1, 4], session_file, &Default::default());
+        assert!(result.is_ok(), "Failed to start a session without the revoked participant: {:?}", result.err());
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(reshare_dir).unwrap();
+    }
+
+    #[test]
+    fn test_share_verify() {
+        let share_files = run_dkg("test_share_verify", 2, 3);
+        let result = backup::verify_share(&share_files[0], &[], &Default::default());
+        assert!(result.is_ok(), "Failed to check the share's parameters: {:?}", result.err());
+        let result = backup::verify_share(&share_files[0], &share_files[1..2], &Default::default());
+        assert!(result.is_ok(), "Failed to verify share against the group key: {:?}", result.err());
+        let result = backup::verify_share(&share_files[2], &share_files[..2], &Default::default());
+        assert!(result.is_ok(), "Failed to verify share with more than the threshold: {:?}", result.err());
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reconstruct() {
+        let keys_file = "./results/test_reconstruct_keys.json";
+        let secret_file = "./results/test_reconstruct_secret.json";
+        let other_secret_file = "./results/test_reconstruct_secret_other.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::reconstruct::reconstruct_keys(keys_file, &[0, 2], secret_file, &Default::default());
+        assert!(result.is_ok(), "Failed to reconstruct from a key file: {:?}", result.err());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(secret_file).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        let result = frost_cli::reconstruct::reconstruct_keys(keys_file, &[0, 1], secret_file, &Default::default());
+        assert!(result.is_err(), "Expected an existing secret key file not to be overwritten");
+        remove_file(secret_file).unwrap();
+
+        // Any threshold of shares gives the same secret key.
+        let share_files = run_dkg("test_reconstruct", 2, 3);
+        frost_cli::reconstruct::reconstruct(&share_files[..2], secret_file, &Default::default()).unwrap();
+        let result = frost_cli::reconstruct::reconstruct(&share_files[1..], other_secret_file, &Default::default());
+        assert!(result.is_ok(), "Failed to reconstruct from shares: {:?}", result.err());
+        let secret: serde_json::Value = serde_json::from_str(&fs::read_to_string(secret_file).unwrap()).unwrap();
+        let other_secret: serde_json::Value = serde_json::from_str(&fs::read_to_string(other_secret_file).unwrap()).unwrap();
+        assert_eq!(secret["secret_key"], other_secret["secret_key"]);
+
+        for file in share_files.iter().map(String::as_str).chain([keys_file, secret_file, other_secret_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_repair() {
+        // Participant 3 of a 2-of-3 group lost their share, helpers 1 and 2 repair it.
+        let share_files = run_dkg("test_repair", 2, 3);
+        let dir = "./results/test_repair";
+        let repaired_file = "./results/test_repair_repaired.json";
+        for share_file in &share_files[..2] {
+            frost_cli::repair::round_one(share_file, &[1, 2], 3, dir, &Default::default()).unwrap();
+        }
+        for share_file in &share_files[..2] {
+            frost_cli::repair::round_two(share_file, &[1, 2], 3, dir, dir, &Default::default()).unwrap();
+        }
+        let result = frost_cli::repair::finish(3, &[1, 2], dir, repaired_file, &Default::default());
+        assert!(result.is_ok(), "Repair failed: {:?}", result.err());
+        let lost = load_participant_share(&share_files[2], &Default::default()).unwrap();
+        let repaired = load_participant_share(repaired_file, &Default::default()).unwrap();
+        assert_eq!(repaired.share, lost.share, "The repaired share should be the lost one");
+        assert_eq!(repaired.participants, 3);
+
+        for file in share_files.iter().map(String::as_str).chain([repaired_file]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate() {
+        // Rotate a dealer key file in one process.
+        let dir = "./results/test_rotate";
+        let old_file = "./results/test_rotate/old/frost_keys.json";
+        let new_file = "./results/test_rotate/new/frost_keys.json";
+        let newer_file = "./results/test_rotate/newer/frost_keys.json";
+        let statement_file = "./results/test_rotate/rotation.json";
+        let signature_file = "./results/test_rotate/signature.json";
+        for sub_dir in ["old", "new", "newer"] {
+            fs::create_dir_all(format!("{}/{}", dir, sub_dir)).unwrap();
+        }
+        generate_keys(2, 3, old_file).unwrap();
+        let result = frost_cli::rotate::rotate_keys(old_file, &[0, 1], 2, 3, new_file, Some(2_000_000_000), &Default::default());
+        assert!(result.is_ok(), "Failed to rotate keys: {:?}", result.err());
+        assert!(frost_cli::rotate::history(old_file, &Default::default()).unwrap().is_empty());
+        let history = frost_cli::rotate::history(new_file, &Default::default()).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].valid_until, Some(2_000_000_000));
+
+        // Rotate again with a statement signed by the current keys.
+        generate_keys(2, 3, newer_file).unwrap();
+        frost_cli::rotate::new_statement(new_file, newer_file, None, statement_file, &Default::default()).unwrap();
+        let statement = fs::read_to_string(statement_file).unwrap();
+        sign_message(&statement, SIGNING_CONTEXT, vec![1, 2], 3, new_file, signature_file).unwrap();
+        let result = frost_cli::rotate::record(new_file, newer_file, statement_file, signature_file, &Default::default());
+        assert!(result.is_ok(), "Failed to record rotation: {:?}", result.err());
+        let history = frost_cli::rotate::history(newer_file, &Default::default()).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].new_group_key, history[1].old_group_key);
+        assert_eq!(history[1].valid_until, None);
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_commitment_pool() {
+        let share_files = run_dkg("test_commitment_pool", 2, 3);
+        let dir = "./results/test_commitment_pool";
+        fs::create_dir_all("./results/test_commitment_pool/partials").unwrap();
+        let message = "hi, this is a test";
+        let signature_file = "./results/test_commitment_pool/signature.json";
+        let pool_files: Vec<String> = (1..=2).map(|i| format!("{}/pool_{}.json", dir, i)).collect();
+        let public_files: Vec<String> = (1..=2).map(|i| format!("{}/public_{}.json", dir, i)).collect();
+        let secret_files: Vec<String> = (1..=2).map(|i| format!("{}/secret_{}.json", dir, i)).collect();
+        let partials_dir = "./results/test_commitment_pool/partials";
+        let partial_files: Vec<String> = (1..=2).map(|i| format!("{}/partial_{}.json", partials_dir, i)).collect();
+        for (share_file, pool_file) in share_files.iter().zip(&pool_files) {
+            let result = signing::generate_commitments(share_file, 2, pool_file, &Default::default());
+            assert!(result.is_ok(), "Failed to generate commitments: {:?}", result.err());
+        }
+        signing::generate_commitments(&share_files[0], 1, &pool_files[0], &Default::default()).unwrap();
+        assert_eq!(signing::load_commitment_pool(&pool_files[0]).unwrap().nonces.len(), 3);
+
+        // Every session takes the next unused commitments of the pool.
+        for session in 0..2 {
+            let session_id = format!("{}{:02}", &SESSION_ID[..30], session);
+            for (((share_file, pool_file), public_file), secret_file) in share_files.iter().zip(&pool_files).zip(&public_files).zip(&secret_files) {
+                signing::commit_from_pool(&session_id, share_file, pool_file, public_file, secret_file, &Default::default()).unwrap();
+            }
+            for ((share_file, secret_file), partial_file) in share_files.iter().zip(&secret_files).zip(&partial_files) {
+                signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, partial_file, &Default::default()).unwrap();
+            }
+            let result = signing::aggregate(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, signature_file);
+            assert!(result.is_ok(), "Failed to aggregate with pooled commitments: {:?}", result.err());
+            assert!(validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file).is_ok());
+        }
+        let pool = signing::load_commitment_pool(&pool_files[0]).unwrap();
+        assert_eq!((pool.next, pool.nonces.len()), (2, 1));
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_nonce_state() {
+        let share_files = run_dkg("test_nonce_state", 2, 3);
+        let dir = "./results/test_nonce_state";
+        fs::create_dir_all(dir).unwrap();
+        let public_files: Vec<String> = (1..=2).map(|i| format!("{}/public_{}.json", dir, i)).collect();
+        let secret_files: Vec<String> = (1..=2).map(|i| format!("{}/secret_{}.json", dir, i)).collect();
+        let partial_file = "./results/test_nonce_state/partial.json";
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        let state = frost_cli::nonces::load_state(&secret_files[0]).unwrap();
+        assert_eq!((state.unused.len(), state.used.len()), (2, 0));
+
+        // Partial signing marks the nonces as used.
+        let result = signing::partial_sign("hi, this is a test", SIGNING_CONTEXT, &share_files[0], &secret_files[0], &public_files, partial_file, &Default::default());
+        assert!(result.is_ok(), "Failed to partial sign with tracked nonces: {:?}", result.err());
+        let public: signing::PublicCommitments = serde_json::from_slice(&fs::read(&public_files[0]).unwrap()).unwrap();
+        let state = frost_cli::nonces::load_state(&secret_files[0]).unwrap();
+        assert_eq!(state.used, vec![frost_cli::nonces::commitment_id(&public.commitments[0])]);
+        assert_eq!(state.unused.len(), 1);
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_commit_with_count() {
+        let share_files = run_dkg("test_commit_with_count", 2, 3);
+        let dir = "./results/test_commit_with_count";
+        let partials_dir = "./results/test_commit_with_count/partials";
+        fs::create_dir_all(partials_dir).unwrap();
+        let signature_file = "./results/test_commit_with_count/signature.json";
+        let public_files: Vec<String> = (1..=2).map(|i| format!("{}/public_{}.json", dir, i)).collect();
+        let secret_files: Vec<String> = (1..=2).map(|i| format!("{}/secret_{}.json", dir, i)).collect();
+        let partial_files: Vec<String> = (1..=2).map(|i| format!("{}/partial_{}.json", partials_dir, i)).collect();
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            let result = signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions { count: 3, ..Default::default() });
+            assert!(result.is_ok(), "Failed to commit to several nonces: {:?}", result.err());
+        }
+        let public: signing::PublicCommitments = serde_json::from_slice(&fs::read(&public_files[0]).unwrap()).unwrap();
+        assert_eq!(public.commitments.len(), 3);
+
+        // Every message is signed with the commitments at its own index, in any order.
+        for (commitment_index, message) in [(2, "third message"), (0, "first message"), (1, "second message")] {
+            for ((share_file, secret_file), partial_file) in share_files.iter().zip(&secret_files).zip(&partial_files) {
+                signing::partial_sign_at(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, commitment_index, partial_file, &Default::default()).unwrap();
+            }
+            let result = signing::aggregate_at(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, commitment_index, signature_file, SignatureFormat::Json, &Default::default(), None);
+            assert!(result.is_ok(), "Failed to aggregate at index {}: {:?}", commitment_index, result.err());
+            assert!(validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file).is_ok());
+            if commitment_index == 2 {
+                assert_eq!(signing::load_secret_commitments(&secret_files[0]).unwrap().nonces.len(), 2);
+            }
+        }
+        assert!(signing::load_secret_commitments(&secret_files[0]).unwrap().nonces.is_empty());
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ws_commitment_refill() {
+        let share_files = run_dkg("test_ws_commitment_refill", 2, 3);
+        let sessions_dir = "./results/test_ws_commitment_refill_sessions";
+        let state_dir = "./results/test_ws_commitment_refill_state";
+        let signature_file = "./results/test_ws_commitment_refill_signature.json";
+        let url = "http://127.0.0.1:47018";
+        let key_file = share_files[0].clone();
+        thread::spawn(move || http::serve("127.0.0.1:47018", &key_file, sessions_dir, None, None, &Default::default()).unwrap());
+        // Give the coordinator time to bind before the participants connect.
+        thread::sleep(std::time::Duration::from_millis(200));
+        let refill = ws::PoolRefill { min_commitments: 2, batch_size: 2 };
+        for share_file in share_files.iter().take(2).cloned() {
+            thread::spawn(move || {
+                ws::run_participant_with_pool("ws://127.0.0.1:47018", &share_file, SIGNING_CONTEXT, state_dir, Some(&refill), None, None, &Default::default()).unwrap()
+            });
+        }
+        thread::sleep(std::time::Duration::from_millis(200));
+        let message = "hi, this is a test";
+        let session = http::create_remote_session(url, message, SIGNING_CONTEXT, vec![1, 2], None).unwrap();
+        let mut result = http::download_signature(url, &session.session_id, signature_file, None, &Default::default());
+        for _ in 0..50 {
+            if result.is_ok() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(100));
+            result = http::download_signature(url, &session.session_id, signature_file, None, &Default::default());
+        }
+        assert!(
+            result.is_ok(),
+            "Participants did not complete the session: {:?}",
+            result.err()
+        );
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature from pooled commitments: {:?}",
+            result.err()
+        );
+
+        // Each pool was filled with 2, gave 1 to the session and was refilled with 2 more.
+        for index in 1..=2 {
+            let pool_file = format!("{}/commitment_pool_{}.json", state_dir, index);
+            let pool = signing::load_commitment_pool(&pool_file).unwrap();
+            assert_eq!(pool.next, 1);
+            assert_eq!(pool.nonces.len(), 3);
+        }
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(sessions_dir).unwrap();
+        remove_dir_all(state_dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_keys_many_participants() {
+        // Runs the participants' steps on all cores with the parallel feature.
+        let keys_file = "./results/test_generate_keys_many_participants_frost_keys.json";
+        let signature_file = "./results/test_generate_keys_many_participants_signature.json";
+        generate_keys(20, 40, keys_file).unwrap();
+        let signers: Vec<u32> = (10..30).collect();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, signers, 40, keys_file, signature_file).unwrap();
+        let result = validate_signature("hi, this is a test", SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature of many participants: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_bench() {
+        let results = frost_cli::bench::run(&[2, 3, 4], &[3, 5], 2).unwrap();
+        let pairs: Vec<(u32, u32)> = results.iter().map(|r| (r.threshold, r.participants)).collect();
+        // The threshold 4 of 3 participants is skipped.
+        assert_eq!(pairs, vec![(2, 3), (3, 3), (2, 5), (3, 5), (4, 5)]);
+        for result in &results {
+            assert!(!result.keygen.is_zero() && !result.partial_sign.is_zero() && !result.verify.is_zero());
+        }
+    }
+
+    #[test]
+    fn test_generate_frost_keys_large_group() {
+        let frost_keys = frost_cli::generate_frost_keys(40, 60, frost_cli::backend::Ciphersuite::Ristretto255, &|_| {}).unwrap();
+        let indices: Vec<u32> = frost_keys.private_shares.iter().map(|(_, index)| *index).collect();
+        assert_eq!(indices, (1..=60).collect::<Vec<u32>>());
+        // The last participants get their shares from every sender's list, like the first.
+        let signers: Vec<u32> = (20..60).collect();
+        let signature = frost_cli::sign_with_keys(b"hi, this is a test", SIGNING_CONTEXT, &frost_keys, &signers).unwrap();
+        let result = frost_cli::verify_with_group_key(b"hi, this is a test", SIGNING_CONTEXT, frost_keys.ciphersuite, &frost_keys.group_key, &signature);
+        assert!(result.is_ok(), "Failed to verify signature of a large group: {:?}", result.err());
+    }
+
+    #[test]
+    fn test_generate_share_files() {
+        let shares_dir = "./results/test_generate_share_files";
+        frost_cli::generate_share_files(3, 5, shares_dir, &|_| {}, &Default::default()).unwrap();
+        let share_files: Vec<String> = (1..=5)
+            .map(|i| frost_cli::keygen::share_file(shares_dir, i).to_string_lossy().into_owned())
+            .collect();
+        for (i, share_file) in share_files.iter().enumerate() {
+            let share = load_participant_share(share_file, &Default::default()).unwrap();
+            assert_eq!((share.index, share.threshold, share.participants), (i as u32 + 1, 3, 5));
+        }
+
+        // The streamed shares sign together like shares from a DKG.
+        let signature_file = "./results/test_generate_share_files_signature.json";
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[1..4], signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature of streamed shares: {:?}", result.err());
+        remove_file(signature_file).unwrap();
+        remove_dir_all(shares_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_blame() {
+        let dkg_dir = "./results/test_dkg_blame";
+        let state_files: Vec<String> = (1..=4).map(|i| format!("./results/test_dkg_blame_state_{}.json", i)).collect();
+        let share_file = "./results/test_dkg_blame_share_1.json";
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, 2, 4, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+
+        // Participant 2 sends participant 1 a wrong share and participant 4 sends none.
+        let tampered_file = format!("{}/share_2_1.json", dkg_dir);
+        let mut tampered: serde_json::Value = serde_json::from_str(&fs::read_to_string(&tampered_file).unwrap()).unwrap();
+        tampered["share"] = serde_json::from_str(&fs::read_to_string(format!("{}/share_3_1.json", dkg_dir)).unwrap()).map(|v: serde_json::Value| v["share"].clone()).unwrap();
+        fs::write(&tampered_file, tampered.to_string()).unwrap();
+        remove_file(format!("{}/share_4_1.json", dkg_dir)).unwrap();
+        let err = dkg::finish(&state_files[0], dkg_dir, share_file, &Default::default()).err().expect("Expected invalid shares to fail");
+        match err.downcast_ref::<frost_cli::error::FrostCliError>() {
+            Some(frost_cli::error::FrostCliError::Misbehaving { participants, .. }) => assert_eq!(participants, &vec![2, 4]),
+            _ => panic!("Expected participants 2 and 4 to be blamed: {}", err),
+        }
+        assert!(!Path::new(share_file).exists(), "No share should be saved");
+
+        // The honest participants still finish with each other's shares.
+        let result = dkg::finish(&state_files[2], dkg_dir, share_file, &Default::default());
+        assert!(result.is_ok(), "Failed to finish DKG with valid shares: {:?}", result.err());
+        for file in &state_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(share_file).unwrap();
+        remove_dir_all(dkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_complaints() {
+        let dkg_dir = "./results/test_dkg_complaints";
+        let evidence_file = "./results/test_dkg_complaints_evidence.json";
+        let signature_file = "./results/test_dkg_complaints_signature.json";
+        let state_files: Vec<String> = (1..=4)
+            .map(|i| format!("./results/test_dkg_complaints_state_{}.json", i))
+            .collect();
+        let share_files: Vec<String> = (1..=4)
+            .map(|i| format!("./results/test_dkg_complaints_share_{}.json", i))
+            .collect();
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, 2, 4, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+
+        // Participant 2's share to participant 1 is lost and participant 4 cheats participant 3.
+        remove_file(format!("{}/share_2_1.json", dkg_dir)).unwrap();
+        let tampered_file = format!("{}/share_4_3.json", dkg_dir);
+        let mut tampered: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&tampered_file).unwrap()).unwrap();
+        let other: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(format!("{}/share_1_3.json", dkg_dir)).unwrap(),
+        )
+        .unwrap();
+        tampered["share"] = other["share"].clone();
+        fs::write(&tampered_file, tampered.to_string()).unwrap();
+
+        let mut complaints = Vec::new();
+        for state_file in &state_files {
+            complaints.extend(dkg::complain(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap());
+        }
+        assert_eq!(complaints.len(), 2, "Expected two complaints: {:?}", complaints);
+        for state_file in &state_files {
+            dkg::answer(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+        // Participant 4 keeps cheating in its answer.
+        let reveal_file = format!("{}/reveal_4_3.json", dkg_dir);
+        let mut reveal: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&reveal_file).unwrap()).unwrap();
+        reveal["share"] = other["share"].clone();
+        fs::write(&reveal_file, reveal.to_string()).unwrap();
+
+        for (state_file, share_file) in state_files.iter().zip(&share_files).take(3) {
+            let result = dkg::resolve(state_file, dkg_dir, share_file, true, evidence_file, &Default::default());
+            assert!(result.is_ok(), "Failed to resolve complaints: {:?}", result.err());
+            assert_eq!(result.unwrap(), vec![4]);
+        }
+        let result = dkg::resolve(&state_files[3], dkg_dir, &share_files[3], true, evidence_file, &Default::default());
+        assert!(result.is_err(), "The disqualified participant should not finish");
+        let evidence: dkg::DkgEvidence = serde_json::from_str(&fs::read_to_string(evidence_file).unwrap()).unwrap();
+        assert_eq!(evidence.disqualified, vec![4]);
+        assert_eq!(evidence.reveals.len(), 1);
+
+        // The remaining participants derived the same group key and can sign with it.
+        let group_keys: Vec<[u8; 32]> = share_files[..3]
+            .iter()
+            .map(|share_file| load_participant_share(share_file, &Default::default()).unwrap().group_key)
+            .collect();
+        assert!(group_keys.iter().all(|key| *key == group_keys[0]), "Participants derived different group keys");
+        let message = "hi, this is a test";
+        let signers = vec![share_files[0].clone(), share_files[2].clone()];
+        let result = sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None);
+        assert!(result.is_ok(), "Failed to sign with the remaining shares: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[1], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature: {:?}", result.err());
+
+        for file in state_files.iter().chain(&share_files[..3]) {
+            remove_file(file).unwrap();
+        }
+        remove_file(evidence_file).unwrap();
+        remove_file(signature_file).unwrap();
+        remove_dir_all(dkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_participant_ids() {
+        let keys_file = "./results/test_participant_ids_frost_keys.json";
+        let signature_file = "./results/test_participant_ids_signature.json";
+        generate_keys(3, 5, keys_file).unwrap();
+        let ids: Vec<frost_cli::ParticipantId> = "1,3,5".split(',').map(|s| s.parse().unwrap()).collect();
+        let positions = frost_cli::signer_positions(keys_file, &ids, &Default::default()).unwrap();
+        assert_eq!(positions, vec![0, 2, 4]);
+
+        let message = "hi, this is a test";
+        let result = sign_message(message, SIGNING_CONTEXT, positions, 5, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to sign with participants 1, 3 and 5: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature: {:?}", result.err());
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_more_than_t_signers() {
+        let keys_file = "./results/test_more_than_t_signers_frost_keys.json";
+        let signature_file = "./results/test_more_than_t_signers_signature.json";
+        let message = "hi, this is a test";
+        generate_keys(3, 5, keys_file).unwrap();
+        let result = sign_message(message, SIGNING_CONTEXT, vec![0, 1, 2, 3, 4], 5, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to sign with all 5 participants: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_ok(), "Failed to verify signature of all participants: {:?}", result.err());
+
+        // All three DKG shares sign with a threshold of 2 as well.
+        let share_files = run_dkg("test_more_than_t_signers", 2, 3);
+        let result = sign_message_with_shares(message, SIGNING_CONTEXT, &share_files, signature_file, None);
+        assert!(result.is_ok(), "Failed to sign with 3 shares for a threshold of 2: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file);
+        assert!(result.is_ok(), "Failed to verify signature of 3 shares: {:?}", result.err());
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_verify_partial_signature() {
+        let share_files = run_dkg("test_verify_partial_signature", 2, 3);
+        let partials_dir = "./results/test_verify_partial_signature_partials";
+        let signature_file = "./results/test_verify_partial_signature_signature.json";
+        fs::create_dir_all(partials_dir).unwrap();
+        let public_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_verify_partial_signature_public_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_verify_partial_signature_secret_{}.json", i))
+            .collect();
+        let message = "hi, this is a test";
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        for (i, (share_file, secret_file)) in share_files.iter().zip(&secret_files).enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i + 1);
+            signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, &partial_file, &Default::default()).unwrap();
+        }
+
+        // Every partial signature verifies on its own before aggregation.
+        let roster = signing::load_roster(&public_files).unwrap();
+        let group_key = frost_dalek::GroupKey::from_bytes(load_participant_share(&share_files[0], &Default::default()).unwrap().group_key).unwrap();
+        let message_hash = frost_dalek::compute_message_hash(SIGNING_CONTEXT.as_bytes(), message.as_bytes());
+        for i in 1..=3 {
+            let partial: signing::PartialSignature = serde_json::from_str(&fs::read_to_string(format!("{}/partial_{}.json", partials_dir, i)).unwrap()).unwrap();
+            let verification_shares = load_participant_share(&share_files[0], &Default::default()).unwrap().verification_shares.clone();
+            let result = signing::verify_partial_signature(&message_hash, &group_key, &roster, &verification_shares, &partial, 0);
+            assert!(result.is_ok(), "Partial signature of participant {} should verify: {:?}", i, result.err());
+        }
+        let result = signing::aggregate(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, signature_file);
+        assert!(result.is_ok(), "Failed to aggregate verified partial signatures: {:?}", result.err());
+
+        for file in share_files.iter().chain(&public_files).chain(&secret_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_dir_all(partials_dir).unwrap();
+    }
+
+    #[test]
+    fn test_transcript() {
+        let share_files = run_dkg("test_transcript", 2, 3);
+        let transcript_file = "./results/test_transcript.jsonl";
+        let partials_dir = "./results/test_transcript_partials";
+        let signature_file = "./results/test_transcript_signature.json";
+        let shares_signature_file = "./results/test_transcript_shares_signature.json";
+        let _ = remove_file(transcript_file);
+        fs::create_dir_all(partials_dir).unwrap();
+        let public_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_transcript_public_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_transcript_secret_{}.json", i))
+            .collect();
+        let message = "hi, this is a test";
+
+        // Sign once in two rounds and once in one process, recording both in the transcript.
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        for (i, (share_file, secret_file)) in share_files.iter().zip(&secret_files).enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i + 1);
+            signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, &partial_file, &Default::default()).unwrap();
+        }
+        signing::aggregate_at(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, 0, signature_file, SignatureFormat::Json, &Default::default(), Some(transcript_file)).unwrap();
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[..2], shares_signature_file, Some(transcript_file)).unwrap();
+
+        // Both ceremonies are recorded with their signers, commitments and signatures.
+        let entries = transcript::read_transcript(transcript_file).unwrap();
+        let group_key = hex::encode(load_participant_share(&share_files[0], &Default::default()).unwrap().group_key);
+        let signed: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.record.group_key == group_key && entry.record.event == transcript::CeremonyEvent::Signed)
+            .collect();
+        assert_eq!(signed.len(), 2, "Expected both signatures in the transcript");
+        let aggregated = &signed[0].record;
+        assert_eq!(aggregated.session_id.as_deref(), Some(SESSION_ID));
+        assert_eq!(aggregated.signers, vec![1, 2, 3]);
+        assert_eq!(aggregated.threshold, 2);
+        let roster = signing::load_roster(&public_files).unwrap();
+        for (commitment, signer) in aggregated.commitments.iter().zip(&roster) {
+            assert_eq!(commitment.index, signer.index);
+            assert_eq!(commitment.hash, frost_cli::nonces::commitment_id(&signer.commitments[0]));
+        }
+        let signature = frost_cli::load_signature(signature_file, None).unwrap().to_bytes();
+        assert_eq!(aggregated.signature.as_deref(), Some(hex::encode(signature).as_str()));
+        assert_eq!(signed[1].record.signers, vec![1, 2]);
+        assert_eq!(signed[1].record.session_id, None);
+        assert!(signed[0].sequence < signed[1].sequence);
+        assert!(transcript::check(transcript_file).is_ok());
+
+        for file in share_files.iter().chain(&public_files).chain(&secret_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_file(shares_signature_file).unwrap();
+        remove_file(transcript_file).unwrap();
+        remove_dir_all(partials_dir).unwrap();
+    }
+
+    #[test]
+    fn test_deterministic_keys() {
+        use frost_cli::backend::Ciphersuite;
+        use frost_cli::{generate_frost_keys_with_rng, sign_with_keys, verify_with_group_key};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let message = b"hi, this is a test";
+
+        // The same generator gives the same keys, and any threshold of them signs.
+        let first = generate_frost_keys_with_rng(2, 3, Ciphersuite::Ristretto255, &|_| {}, &mut StdRng::seed_from_u64(7)).unwrap();
+        let second = generate_frost_keys_with_rng(2, 3, Ciphersuite::Ristretto255, &|_| {}, &mut StdRng::seed_from_u64(7)).unwrap();
+        let other = generate_frost_keys_with_rng(2, 3, Ciphersuite::Ristretto255, &|_| {}, &mut StdRng::seed_from_u64(8)).unwrap();
+        assert_eq!(first.group_key, second.group_key, "Expected the same group key from the same seed");
+        assert_eq!(first.private_shares, second.private_shares);
+        assert_ne!(first.group_key, other.group_key);
+        for signers in [[0, 1], [0, 2], [1, 2]] {
+            let signature = sign_with_keys(message, SIGNING_CONTEXT, &first, &signers).unwrap();
+            verify_with_group_key(message, SIGNING_CONTEXT, first.ciphersuite, &first.group_key, &signature).unwrap();
+        }
+
+        // With the feature, the same seed gives the same key file.
+        #[cfg(feature = "insecure-deterministic")]
+        {
+            use frost_cli::generate_keys_from_seed;
+            let keys_files = [
+                "./results/test_deterministic_keys_1.json",
+                "./results/test_deterministic_keys_2.json",
+                "./results/test_deterministic_keys_3.json",
+            ];
+            generate_keys_from_seed(2, 3, keys_files[0], Ciphersuite::Ristretto255, b"demo", &|_| {}, &Default::default()).unwrap();
+            generate_keys_from_seed(2, 3, keys_files[1], Ciphersuite::Ristretto255, b"demo", &|_| {}, &Default::default()).unwrap();
+            generate_keys_from_seed(2, 3, keys_files[2], Ciphersuite::Ristretto255, b"other", &|_| {}, &Default::default()).unwrap();
+            assert_eq!(fs::read(keys_files[0]).unwrap(), fs::read(keys_files[1]).unwrap());
+            assert_ne!(fs::read(keys_files[0]).unwrap(), fs::read(keys_files[2]).unwrap());
+            let keys = frost_cli::load_frost_keys(keys_files[0], &Default::default()).unwrap();
+            let signature = sign_with_keys(message, SIGNING_CONTEXT, &keys, &[1, 2]).unwrap();
+            verify_with_group_key(message, SIGNING_CONTEXT, keys.ciphersuite, &keys.group_key, &signature).unwrap();
+            for keys_file in keys_files {
+                remove_file(keys_file).unwrap();
+            }
+
+            // A fixed seed gives fixed keys and, with seeded nonces, a fixed signature.
+            use frost_cli::{seeded_rng, sign_with_keys_and_rng};
+            let keys = generate_frost_keys_with_rng(2, 3, Ciphersuite::Ristretto255, &|_| {}, &mut *seeded_rng(b"demo").unwrap()).unwrap();
+            let signature = sign_with_keys_and_rng(message, SIGNING_CONTEXT, &keys, &[0, 2], None, &mut seeded_rng(b"nonces").unwrap()).unwrap();
+            verify_with_group_key(message, SIGNING_CONTEXT, keys.ciphersuite, &keys.group_key, &signature).unwrap();
+            assert_eq!(hex::encode(keys.group_key), "72f704d6034f87c2393d831599cba8e63ca3130889755ae9ecb3425020041736");
+            assert_eq!(hex::encode(signature), "3c165bd1445f3f15cd30bb382a07226a7f0b68498456ae0f4da0f1a4d7ac3f7cce7df0321b16125df98211026d3dc627c55b35938f80ecf2cf21642e1db68402");
+            let share = frost_cli::ParticipantShare {
+                index: keys.private_shares[0].1,
+                share: keys.private_shares[0].0,
+                group_key: keys.group_key,
+                threshold: keys.threshold,
+                participants: 3,
+                verification_shares: keys.verification_shares.clone(),
+            };
+            let (commitments, _) = frost_cli::signing::commit_with_share("session", &share, frost_cli::signing::CommitOptions { rng: Some(&mut *seeded_rng(b"nonces").unwrap()), ..Default::default() }).unwrap();
+            let (again, _) = frost_cli::signing::commit_with_share("session", &share, frost_cli::signing::CommitOptions { rng: Some(&mut *seeded_rng(b"nonces").unwrap()), ..Default::default() }).unwrap();
+            assert_eq!(commitments.commitments, again.commitments, "Expected the same commitments from the same seed");
+        }
+    }
+
+    #[test]
+    fn test_parse_files() {
+        use frost_cli::{parse_frost_keys, parse_participant_share, parse_signature};
+        let keys_file = "./results/test_parse_files_frost_keys.json";
+        let share_file = "./results/test_parse_files_share.json";
+        let signature_file = "./results/test_parse_files_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let frost_keys = frost_cli::load_frost_keys(keys_file, &Default::default()).unwrap();
+        let (share, index) = frost_keys.private_shares[0];
+        save_participant_share(share_file, &frost_cli::ParticipantShare { index, share, group_key: frost_keys.group_key, threshold: 2, participants: 3, verification_shares: frost_keys.verification_shares.clone() }, &Default::default()).unwrap();
+
+        // The parsers accept what the loaders accept.
+        let parsed = parse_frost_keys(&fs::read(keys_file).unwrap(), keys_file, &Default::default()).unwrap();
+        assert_eq!(parsed.private_shares, frost_keys.private_shares);
+        let parsed = parse_participant_share(&fs::read(share_file).unwrap(), share_file, &Default::default()).unwrap();
+        assert_eq!(parsed.share, load_participant_share(share_file, &Default::default()).unwrap().share);
+        let signature = parse_signature(&fs::read(signature_file).unwrap(), signature_file, None).unwrap();
+        assert_eq!(signature, frost_cli::load_signature(signature_file, None).unwrap().to_bytes());
+
+        // So do they in the binary file formats.
+        for format in [Format::Cbor, Format::Bincode] {
+            let value: serde_json::Value = format::decode(&fs::read(keys_file).unwrap()).unwrap();
+            let bytes = format::encode_as(&value, format).unwrap();
+            assert_eq!(parse_frost_keys(&bytes, keys_file, &Default::default()).unwrap().group_key, frost_keys.group_key);
+        }
+        remove_file(keys_file).unwrap();
+        remove_file(share_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_daemon() {
+        use frost_cli::daemon;
+        let share_files = run_dkg("test_daemon", 2, 3);
+        let dir = "./results/test_daemon";
+        let partials_dir = format!("{}/partials", dir);
+        fs::create_dir_all(&partials_dir).unwrap();
+        let sockets: Vec<String> = (1..=2).map(|i| format!("{}/daemon_{}.sock", dir, i)).collect();
+        for (socket, share_file) in sockets.iter().zip(&share_files) {
+            let (socket, share_file) = (socket.clone(), share_file.clone());
+            thread::spawn(move || daemon::serve(&socket, &share_file, &Default::default()).unwrap());
+        }
+        // Give the daemons time to bind before connecting.
+        while !sockets.iter().all(|socket| Path::new(socket).exists()) {
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+
+        let status = daemon::call(&sockets[1], "status", serde_json::Value::Null).unwrap();
+        assert_eq!(status["index"], 2);
+        assert_eq!(status["threshold"], 2);
+        let commitment_files: Vec<String> = (1..=2).map(|i| format!("{}/commitments_{}.json", dir, i)).collect();
+        for (socket, commitment_file) in sockets.iter().zip(&commitment_files) {
+            daemon::commit(socket, SESSION_ID, commitment_file, &Default::default()).unwrap();
+        }
+        for (i, socket) in sockets.iter().enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i + 1);
+            let result = daemon::partial_sign(socket, "hi, this is a test", SIGNING_CONTEXT, &commitment_files, &partial_file, &Default::default());
+            assert!(result.is_ok(), "Failed to sign through the daemon: {:?}", result.err());
+        }
+        let signature_file = format!("{}/signature.json", dir);
+        signing::aggregate("hi, this is a test", SIGNING_CONTEXT, &share_files[0], &commitment_files, &partials_dir, &signature_file).unwrap();
+        let result = validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_files[0], &signature_file);
+        assert!(result.is_ok(), "Failed to verify a daemon signature: {:?}", result.err());
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_http_api() {
+        use frost_cli::net::api;
+        let share_files = run_dkg("test_http_api", 2, 3);
+        let dir = "./results/test_http_api";
+        let keystore_dir = Path::new(dir).join("keys");
+        let clients_file = format!("{}/clients.json", dir);
+        let sessions_dir = format!("{}/sessions", dir);
+        let signature_file = format!("{}/signature.json", dir);
+        let url = "http://127.0.0.1:47019";
+        fs::create_dir_all(dir).unwrap();
+        keystore::import_key(&keystore_dir, "alice", &share_files[0], &Default::default()).unwrap();
+        keystore::import_key(&keystore_dir, "bob", &share_files[1], &Default::default()).unwrap();
+        let bot = api::add_client(&clients_file, "release-bot", true, None, &Default::default()).unwrap();
+        let alice = api::add_client(&clients_file, "alice", false, Some("alice"), &Default::default()).unwrap();
+        let bob = api::add_client(&clients_file, "bob", false, Some("bob"), &Default::default()).unwrap();
+        let (serve_keystore, serve_clients, serve_sessions) = (keystore_dir.clone(), clients_file.clone(), sessions_dir.clone());
+        thread::spawn(move || api::serve("127.0.0.1:47019", &serve_keystore, &serve_clients, &serve_sessions, &Default::default()).unwrap());
+        // Give the server time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(500));
+
+        let bearer = |token: &str| format!("Bearer {}", token);
+        let session: api::ApprovalSession = ureq::post(&format!("{}/sessions", url))
+            .set("Authorization", &bearer(&bot))
+            .send_json(serde_json::json!({ "key": "alice", "message": "hi, this is a test" }))
+            .unwrap()
+            .into_json()
+            .unwrap();
+        assert_eq!(session.status, api::ApprovalStatus::CollectingApprovals);
+        let approvals_url = format!("{}/sessions/{}/approvals", url, session.session_id);
+        for token in [&alice, &bob] {
+            ureq::post(&approvals_url).set("Authorization", &bearer(token)).call().unwrap();
+        }
+        let session: api::ApprovalSession = ureq::get(&format!("{}/sessions/{}", url, session.session_id))
+            .set("Authorization", &bearer(&bot))
+            .call()
+            .unwrap()
+            .into_json()
+            .unwrap();
+        assert_eq!(session.status, api::ApprovalStatus::Complete);
+        assert_eq!(session.approvals.iter().map(|approval| approval.index).collect::<Vec<_>>(), vec![1, 2]);
+        let signature = ureq::get(&format!("{}/sessions/{}/signature", url, session.session_id))
+            .set("Authorization", &bearer(&bot))
+            .call()
+            .unwrap()
+            .into_string()
+            .unwrap();
+        fs::write(&signature_file, signature).unwrap();
+        let result = validate_signature("hi, this is a test", SIGNING_CONTEXT, &share_files[2], &signature_file);
+        assert!(result.is_ok(), "Failed to verify an API signature: {:?}", result.err());
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_metrics() {
+        use frost_cli::metrics;
+        let share_files = run_dkg("test_metrics", 2, 2);
+        let signature_file = "./results/test_metrics_signature.json";
+        let message = "hi, this is a test";
+        let valid = "frost_verifications_total{kind=\"signature\",result=\"valid\"}";
+        let count = |metrics: &str, name: &str| -> u64 {
+            metrics.lines().find_map(|line| line.strip_prefix(name)?.trim().parse().ok()).unwrap()
+        };
+        let before = count(&metrics::render(), valid);
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files, signature_file, None).unwrap();
+        validate_signature(message, SIGNING_CONTEXT, &share_files[0], signature_file).unwrap();
+        thread::spawn(|| metrics::serve("127.0.0.1:47021").unwrap());
+        // Give the server time to bind before connecting.
+        thread::sleep(std::time::Duration::from_millis(500));
+
+        let response = ureq::get("http://127.0.0.1:47021/metrics").call().unwrap();
+        assert_eq!(response.header("Content-Type"), Some("text/plain; version=0.0.4"));
+        let scraped = response.into_string().unwrap();
+        assert!(count(&scraped, valid) > before, "Valid signature was not counted");
+        for name in ["frost_ceremonies_started_total", "frost_ceremonies_completed_total", "frost_partial_signatures_received_total"] {
+            assert!(scraped.contains(&format!("# TYPE {} counter", name)), "Missing {}", name);
+        }
+        let largest = count(&scraped, "frost_round_duration_seconds_bucket{round=\"approvals\",le=\"86400\"}");
+        assert!(largest <= count(&scraped, "frost_round_duration_seconds_count{round=\"approvals\"}"));
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "keyring")]
+    #[ignore = "needs an unlocked OS keyring"]
+    fn test_keyring_storage() {
+        let share_files = run_dkg("test_keyring_storage", 2, 2);
+        let location = "keyring:test_keyring_storage";
+        let signature_file = "./results/test_keyring_storage_signature.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let result = save_participant_share(location, &share, &Default::default());
+        assert!(result.is_ok(), "Failed to save share to the keyring: {:?}", result.err());
+        let signers = vec![location.to_string(), share_files[1].clone()];
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, location, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature of a keyring share: {:?}",
+            result.err()
+        );
+        keyring::Entry::new("frost-cli", "test_keyring_storage")
+            .unwrap()
+            .delete_credential()
+            .unwrap();
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "pkcs11")]
+    #[ignore = "needs a PKCS#11 module such as SoftHSM, see FROST_PKCS11_MODULE"]
+    fn test_pkcs11_storage() {
+        let share_files = run_dkg("test_pkcs11_storage", 2, 2);
+        let location = "pkcs11:object=test_pkcs11_storage";
+        let signature_file = "./results/test_pkcs11_storage_signature.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let result = save_participant_share(location, &share, &Default::default());
+        assert!(result.is_ok(), "Failed to save share to the token: {:?}", result.err());
+        let signers = vec![location.to_string(), share_files[1].clone()];
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, location, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature of a PKCS#11 share: {:?}",
+            result.err()
+        );
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[ignore = "needs a Vault server with a KV version 2 engine at secret/, see VAULT_ADDR"]
+    fn test_vault_storage() {
+        let share_files = run_dkg("test_vault_storage", 2, 2);
+        let location = "vault://secret/frost-cli/test_vault_storage";
+        let signature_file = "./results/test_vault_storage_signature.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let result = save_participant_share(location, &share, &Default::default());
+        assert!(result.is_ok(), "Failed to save share to Vault: {:?}", result.err());
+        let signers = vec![location.to_string(), share_files[1].clone()];
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, location, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature with the group key in Vault: {:?}",
+            result.err()
+        );
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "aws")]
+    #[ignore = "needs AWS credentials and a KMS key, see FROST_AWS_KMS_KEY_ID"]
+    fn test_aws_storage() {
+        let share_files = run_dkg("test_aws_storage", 2, 2);
+        let location = "aws:frost-cli/test_aws_storage";
+        let signature_file = "./results/test_aws_storage_signature.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let result = save_participant_share(location, &share, &Default::default());
+        assert!(result.is_ok(), "Failed to save share to AWS: {:?}", result.err());
+        let signers = vec![location.to_string(), share_files[1].clone()];
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &signers, signature_file, None).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, location, signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to verify signature of a share in AWS: {:?}",
+            result.err()
+        );
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
+
+    // Fail tests
+    #[test]
+    fn test_sign_message_fail() {
+        let keys_file = "./results/test_sign_message_fail_frost_keys.json";
+        let signature_file = "./results/test_sign_message_fail_signature.json";
+        let result = generate_keys(2, 5, keys_file);
+        assert!(
+            result.is_ok(),
+            "Failed to generate keys with t=2, n=5: {:?}",
+            result.err()
+        );
+        let message = "hi, this is a test";
+        let result = sign_message(message, SIGNING_CONTEXT, vec![1], 5, &keys_file, &signature_file);
+        assert!(
+            result.is_err(),
+            "Signing should fail with t=1, n=5 when keys were generated with t=2, n=5"
+        );
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_verify_signature_fail() {
+        let keys_file = "./results/test_verify_signature_fail_frost_keys.json";
+        let signature_file = "./results/test_verify_signature_fail_signature.json";
+        let result = generate_keys(3, 5, keys_file);
+        assert!(
+            result.is_ok(),
+            "Failed to generate keys with t=3, n=5: {:?}",
+            result.err()
+        );
+        let message = "hi, this is a test";
+        let result = sign_message(message, SIGNING_CONTEXT, vec![1,2,3], 5, &keys_file, &signature_file);
+        assert!(
+            result.is_ok(),
+            "Failed to sign message with t=3, n=5: {:?}",
+            result.err()
+        );
+        let result = validate_signature("different message", SIGNING_CONTEXT, &keys_file, &signature_file);
+        assert!(
+            result.is_err(),
+            "Verification should fail for a different message"
+        );
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_invalid_index_fail() {
+        let result = dkg::round_one(
+            4,
+            2,
+            3,
+            "./results/test_dkg_invalid_index_state.json",
+            "./results/test_dkg_invalid_index",
+            &Default::default(),
+        );
+        assert!(
+            result.is_err(),
+            "DKG round 1 should fail for an index greater than n"
+        );
+    }
+
+    #[test]
+    fn test_sign_message_with_shares_fail() {
+        let share_files = run_dkg("test_sign_message_with_shares_fail", 2, 3);
+        let result = sign_message_with_shares(
+            "hi, this is a test",
+            SIGNING_CONTEXT,
+            &share_files[..1],
+            "./results/test_sign_message_with_shares_fail_signature.json",
+            None,
+        );
+        assert!(
+            result.is_err(),
+            "Signing should fail with fewer shares than the threshold"
+        );
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_partial_sign_nonce_reuse_fail() {
+        let share_files = run_dkg("test_partial_sign_nonce_reuse_fail", 2, 3);
+        let public_file = "./results/test_partial_sign_nonce_reuse_fail_public_commitments.json";
+        let secret_file = "./results/test_partial_sign_nonce_reuse_fail_secret_commitments.json";
+        let other_public_file =
+            "./results/test_partial_sign_nonce_reuse_fail_other_public_commitments.json";
+        let other_secret_file =
+            "./results/test_partial_sign_nonce_reuse_fail_other_secret_commitments.json";
+        let partial_file = "./results/test_partial_sign_nonce_reuse_fail_partial_signature.json";
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        signing::commit(
+            SESSION_ID,
+            &share_files[1],
+            other_public_file,
+            other_secret_file,
+            signing::CommitOptions::default(),
+        )
+        .unwrap();
+        let roster = vec![public_file.to_string(), other_public_file.to_string()];
+        signing::partial_sign("first message", SIGNING_CONTEXT, &share_files[0], secret_file, &roster, partial_file, &Default::default())
+            .unwrap();
+        let result = signing::partial_sign(
+            "second message",
+            SIGNING_CONTEXT,
+            &share_files[0],
+            secret_file,
+            &roster,
+            partial_file,
+            &Default::default(),
+        );
+        assert!(
+            result.is_err(),
+            "Partial signing should fail when the nonces were already used"
+        );
+        for file in share_files.iter().map(String::as_str).chain([
+            public_file,
+            secret_file,
+            other_public_file,
+            other_secret_file,
+            partial_file,
+        ]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_session_unknown_signer_fail() {
+        let share_files = run_dkg("test_session_unknown_signer_fail", 2, 3);
+        let session_file = "./results/test_session_unknown_signer_fail_session.json";
+        let public_file = "./results/test_session_unknown_signer_fail_public_commitments.json";
+        let secret_file = "./results/test_session_unknown_signer_fail_secret_commitments.json";
+        session::new_session("hi, this is a test", SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default())
+            .unwrap();
+        let session_id = session::load_session(session_file).unwrap().session_id;
+        signing::commit(&session_id, &share_files[2], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        let result = session::add_commitments(session_file, public_file, None, &Default::default());
+        assert!(
+            result.is_err(),
+            "Adding commitments should fail for a participant outside the roster"
+        );
+        for file in share_files.iter().map(String::as_str).chain([
+            session_file,
+            public_file,
+            secret_file,
+        ]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_session_replay_fail() {
+        let share_files = run_dkg("test_session_replay_fail", 2, 3);
+        let session_file = "./results/test_session_replay_fail_session.json";
+        let public_file = "./results/test_session_replay_fail_public_commitments.json";
+        let secret_file = "./results/test_session_replay_fail_secret_commitments.json";
+        session::new_session("hi, this is a test", SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default())
+            .unwrap();
+        // Commitments made for a different session must be rejected.
+        signing::commit(SESSION_ID, &share_files[0], public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        let result = session::add_commitments(session_file, public_file, None, &Default::default());
+        assert!(
+            result.is_err(),
+            "Adding commitments should fail for a different session ID"
+        );
+        for file in share_files.iter().map(String::as_str).chain([
+            session_file,
+            public_file,
+            secret_file,
+        ]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_qr_decode_image_fail() {
+        let result = qr::decode_image(
+            "./results/test_qr_decode_image_fail_missing.png",
+            "./results/test_qr_decode_image_fail_message.json",
+            &Default::default(),
+        );
+        assert!(
+            result.is_err(),
+            "Decoding should fail for a missing image file"
+        );
+    }
+
+    #[test]
+    fn test_noise_open_share_wrong_identity_fail() {
+        let dkg_dir = "./results/test_noise_open_share_wrong_identity_fail_dkg";
+        let identities_dir = "./results/test_noise_open_share_wrong_identity_fail_identities";
+        let identity_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_noise_open_share_wrong_identity_fail_identity_{}.json", i))
+            .collect();
+        let state_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_noise_open_share_wrong_identity_fail_state_{}.json", i))
+            .collect();
+        for (i, (identity_file, state_file)) in identity_files.iter().zip(&state_files).enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, identities_dir, &Default::default()).unwrap();
+            dkg::round_one(i as u32 + 1, 2, 2, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        dkg::round_two(&state_files[0], dkg_dir, dkg_dir, &Default::default()).unwrap();
+        let plain_file = Path::new(dkg_dir).join("share_1_2.json");
+        noise::seal_share(&identity_files[0], identities_dir, &plain_file, &Default::default()).unwrap();
+        // Participant 2 replaces their identity after the share was sealed.
+        identity::generate_identity(2, &identity_files[1], identities_dir, &Default::default()).unwrap();
+        let result = noise::open_share(&identity_files[1], identities_dir, &plain_file, &Default::default());
+        assert!(
+            result.is_err(),
+            "Opening a share sealed to a different identity should fail"
+        );
+        for file in identity_files.iter().chain(&state_files) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dkg_dir).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_substituted_commitments_fail() {
+        let share_files = run_dkg("test_substituted_commitments_fail", 2, 3);
+        let identities_dir = "./results/test_substituted_commitments_fail_identities";
+        let session_file = "./results/test_substituted_commitments_fail_session.json";
+        let message = "hi, this is a test";
+        let identities: Vec<IdentityFiles> = (1..=2)
+            .map(|i| IdentityFiles {
+                identity_file: format!("./results/test_substituted_commitments_fail_identity_{}.json", i),
+                identities_dir: identities_dir.to_string(),
+            })
+            .collect();
+        let public_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_substituted_commitments_fail_public_commitments_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_substituted_commitments_fail_secret_commitments_{}.json", i))
+            .collect();
+        let partial_file = "./results/test_substituted_commitments_fail_partial_signature.json";
+        session::new_session(message, SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default()).unwrap();
+        let session_id = session::load_session(session_file).unwrap().session_id;
+        for (i, identity) in identities.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, &identity.identity_file, identities_dir, &Default::default())
+                .unwrap();
+            signing::commit(&session_id, &share_files[i], &public_files[i], &secret_files[i], signing::CommitOptions::default())
+                .unwrap();
+            identity::sign_message_file(&identity.identity_file, &public_files[i]).unwrap();
+        }
+        // The coordinator swaps participant 2's commitments for its own, keeping the signature.
+        let read_value = |file: &str| -> serde_json::Value {
+            serde_json::from_str(&fs::read_to_string(file).unwrap()).unwrap()
+        };
+        let mut commitments = read_value(&public_files[1]);
+        commitments["commitments"] = read_value(&public_files[0])["commitments"].clone();
+        fs::write(&public_files[1], commitments.to_string()).unwrap();
+        for public_file in &public_files {
+            session::add_commitments(session_file, public_file, None, &Default::default()).unwrap();
+        }
+        let result = session::partial_sign(
+            message,
+            SIGNING_CONTEXT,
+            &share_files[0],
+            &secret_files[0],
+            session_file,
+            partial_file,
+            Some(&identities[0]),
+            &[],
+            &Default::default(),
+        );
+        assert!(
+            result.is_err(),
+            "Signing should fail when a signer's commitments were substituted"
+        );
+        for file in share_files
+            .iter()
+            .chain(identities.iter().map(|identity| &identity.identity_file))
+            .chain(&public_files)
+            .chain(&secret_files)
+        {
+            remove_file(file).unwrap();
+        }
+        remove_file(session_file).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_echo_broadcast_mismatch_fail() {
+        let share_files = run_dkg("test_echo_broadcast_mismatch_fail", 2, 3);
+        let session_file = "./results/test_echo_broadcast_mismatch_fail_session.json";
+        let echo_file = "./results/test_echo_broadcast_mismatch_fail_echo_2.json";
+        let partial_file = "./results/test_echo_broadcast_mismatch_fail_partial_signature.json";
+        let message = "hi, this is a test";
+        let files = |kind: &str| -> Vec<String> {
+            (1..=3)
+                .map(|i| format!("./results/test_echo_broadcast_mismatch_fail_{}_{}.json", kind, i))
+                .collect()
+        };
+        let (public_files, secret_files) = (files("public_commitments"), files("secret_commitments"));
+        session::new_session(message, SIGNING_CONTEXT, &share_files[0], vec![1, 2], session_file, &Default::default()).unwrap();
+        let session_id = session::load_session(session_file).unwrap().session_id;
+        for i in 0..3 {
+            signing::commit(&session_id, &share_files[i], &public_files[i], &secret_files[i], signing::CommitOptions::default())
+                .unwrap();
+        }
+        // The coordinator shows participant 2 a roster with participant 3 instead of 1.
+        let other_roster =
+            signing::load_roster(&[public_files[1].clone(), public_files[2].clone()]).unwrap();
+        echo::write_echo(message, SIGNING_CONTEXT, &share_files[1], &other_roster, echo_file, &Default::default()).unwrap();
+        for public_file in public_files.iter().take(2) {
+            session::add_commitments(session_file, public_file, None, &Default::default()).unwrap();
+        }
+        let result = session::partial_sign(
+            message,
+            SIGNING_CONTEXT,
+            &share_files[0],
+            &secret_files[0],
+            session_file,
+            partial_file,
+            None,
+            &[echo_file.to_string()],
+            &Default::default(),
+        );
+        let err = result.err().expect("Signing should fail when another signer echoed a different roster");
+        assert!(matches!(err.downcast_ref(), Some(frost_cli::error::FrostCliError::InvalidMessage { index: 2, .. })));
+        for file in share_files.iter().chain(&public_files).chain(&secret_files) {
+            remove_file(file).unwrap();
+        }
+        remove_file(session_file).unwrap();
+        remove_file(echo_file).unwrap();
+    }
+
+    #[test]
+    fn test_decrypt_share_wrong_identity_fail() {
+        let identities_dir = "./results/test_decrypt_share_wrong_identity_fail_identities";
+        let roster_file = "./results/test_decrypt_share_wrong_identity_fail_roster.json";
+        let bundles_dir = "./results/test_decrypt_share_wrong_identity_fail_bundles";
+        let share_file = "./results/test_decrypt_share_wrong_identity_fail_share.json";
+        let identity_files: Vec<String> = (1..=2)
+            .map(|i| format!("./results/test_decrypt_share_wrong_identity_fail_identity_{}.json", i))
+            .collect();
+        for (i, identity_file) in identity_files.iter().enumerate() {
+            identity::generate_identity(i as u32 + 1, identity_file, identities_dir, &Default::default()).unwrap();
+        }
+        dealer::write_roster(identities_dir, 2, roster_file, &Default::default()).unwrap();
+        dealer::generate_encrypted_shares(2, 2, roster_file, bundles_dir, &Default::default()).unwrap();
+        // Participant 2 replaces their identity after the bundles were sealed.
+        identity::generate_identity(2, &identity_files[1], identities_dir, &Default::default()).unwrap();
+        let bundle_file = format!("{}/share_bundle_2.json", bundles_dir);
+        let result = dealer::decrypt_share(&identity_files[1], &bundle_file, share_file, &Default::default());
+        assert!(
+            result.is_err(),
+            "Decrypting a share bundle sealed to a different identity should fail"
+        );
+        for file in &identity_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(roster_file).unwrap();
+        remove_dir_all(bundles_dir).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_decrypt_share_verification_shares_fail() {
+        let identities_dir = "./results/test_decrypt_share_verification_shares_fail_identities";
+        let roster_file = "./results/test_decrypt_share_verification_shares_fail_roster.json";
+        let bundles_dir = "./results/test_decrypt_share_verification_shares_fail_bundles";
+        let identity_file = "./results/test_decrypt_share_verification_shares_fail_identity_1.json";
+        // Only participant 1's private identity is kept.
+        for i in [2, 3, 1] {
+            identity::generate_identity(i, identity_file, identities_dir, &Default::default()).unwrap();
+        }
+        dealer::write_roster(identities_dir, 3, roster_file, &Default::default()).unwrap();
+        dealer::generate_encrypted_shares(2, 3, roster_file, bundles_dir, &Default::default()).unwrap();
+        let identity = identity::load_identity(identity_file).unwrap();
+        let bundle_file = format!("{}/share_bundle_1.json", bundles_dir);
+        let bundle: dealer::ShareBundle = serde_json::from_slice(&fs::read(&bundle_file).unwrap()).unwrap();
+        assert!(dealer::open_share_bundle(&identity, &bundle).is_ok(), "Failed to open an untouched bundle");
+
+        // A share that is not the one its verification share commits to.
+        let mut swapped = bundle;
+        swapped.verification_shares.swap(0, 1);
+        let result = dealer::open_share_bundle(&identity, &swapped);
+        assert!(result.is_err(), "Expected a share that does not match its verification share to fail");
+
+        // A verification share of another participant that is not a share of the group key.
+        let mut forged = swapped;
+        forged.verification_shares.swap(0, 1);
+        forged.verification_shares[2] = forged.verification_shares[1];
+        let err = dealer::open_share_bundle(&identity, &forged).err().expect("Expected inconsistent verification shares to fail");
+        assert!(
+            matches!(err.downcast_ref::<frost_cli::error::FrostCliError>(), Some(frost_cli::error::FrostCliError::InvalidShare { .. })),
+            "Expected an invalid share error: {}",
+            err
+        );
+
+        remove_file(identity_file).unwrap();
+        remove_file(roster_file).unwrap();
+        remove_dir_all(bundles_dir).unwrap();
+        remove_dir_all(identities_dir).unwrap();
+    }
+
+    #[test]
+    fn test_vault_location_without_path_fail() {
+        let result = load_participant_share("vault://secret", &Default::default());
+        assert!(
+            result.is_err(),
+            "Loading a share should fail for a Vault location without a secret path"
+        );
+    }
+
+    #[test]
+    fn test_mnemonic_typo_fail() {
+        let share_files = run_dkg("test_mnemonic_typo_fail", 2, 2);
+        let restored_file = "./results/test_mnemonic_typo_fail_restored_share.json";
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let mnemonic = backup::export_mnemonic(&share_files[0], &Default::default()).unwrap();
+        // A misspelled word is not in the BIP39 word list.
+        let mut words: Vec<&str> = mnemonic.split_whitespace().collect();
+        words[0] = "frostt";
+        let result = backup::import_mnemonic(
+            &words.join(" "),
+            share.index,
+            &hex::encode(share.group_key),
+            share.threshold,
+            share.participants,
+            restored_file,
+            &Default::default(),
+        );
+        assert!(result.is_err(), "Importing a mistyped mnemonic should fail");
+        assert!(!Path::new(restored_file).exists());
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_paper_backup_uncorrectable_fail() {
+        let share_files = run_dkg("test_paper_backup_uncorrectable_fail", 2, 2);
+        let backup_file = "./results/test_paper_backup_uncorrectable_fail.txt";
+        let restored_file = "./results/test_paper_backup_uncorrectable_fail_restored_share.json";
+        let backup = backup::export_paper(&share_files[0], &Default::default()).unwrap();
+        // Two mistyped characters on one line are more than its CRC can correct.
+        let typed: Vec<String> = backup
+            .lines()
+            .map(|line| {
+                let mut line = line.to_string();
+                if line.starts_with("02:") {
+                    for position in [4, 10] {
+                        let typo = if &line[position..position + 1] == "X" { "Y" } else { "X" };
+                        line.replace_range(position..position + 1, typo);
+                    }
+                }
+                line
+            })
+            .collect();
+        fs::write(backup_file, typed.join("\n")).unwrap();
+        let result = backup::recover_paper(backup_file, restored_file, &Default::default());
+        assert!(result.is_err(), "Recovering a backup with two typos on a line should fail");
+        assert!(!Path::new(restored_file).exists());
+        for file in share_files.iter().map(String::as_str).chain([backup_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_keystore_share_sign_fail() {
+        let keystore_dir = Path::new("./results/test_keystore_share_sign_fail");
+        let share_files = run_dkg("test_keystore_share_sign_fail", 2, 2);
+        keystore::import_key(keystore_dir, "board", &share_files[0], &Default::default()).unwrap();
+        let result = keystore::dealer_key_file(keystore_dir, "board");
+        assert!(
+            result.is_err(),
+            "Signing with a key that only holds one participant's share should fail"
+        );
+        let result = keystore::import_key(keystore_dir, "../board", &share_files[1], &Default::default());
+        assert!(result.is_err(), "Importing a key outside the keystore should fail");
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(keystore_dir).unwrap();
+    }
+
+    #[test]
+    fn test_unversioned_key_file_fail() {
+        let keys_file = "./results/test_unversioned_key_file_fail_frost_keys.json";
+        let signature_file = "./results/test_unversioned_key_file_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let mut keys: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(keys_file).unwrap()).unwrap();
+        keys.as_object_mut().unwrap().remove("version");
+        fs::write(keys_file, keys.to_string()).unwrap();
+        let result = sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file);
+        assert!(result.is_err(), "Signing should fail with an unversioned key file");
+        keys["version"] = 3.into();
+        fs::write(keys_file, keys.to_string()).unwrap();
+        let result = sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file);
+        assert!(result.is_err(), "Signing should fail with a key file from a newer version");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_tampered_share_file_fail() {
+        let share_files = run_dkg("test_tampered_share_file_fail", 2, 2);
+        let mut share: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&share_files[0]).unwrap()).unwrap();
+        share["threshold"] = 1.into();
+        fs::write(&share_files[0], share.to_string()).unwrap();
+        let result = load_participant_share(&share_files[0], &Default::default());
+        assert!(result.is_err(), "Loading a tampered share file should fail");
+        assert!(result.err().unwrap().to_string().contains("corrupted"));
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_truncated_binary_file_fail() {
+        let keys_file = "./results/test_truncated_binary_file_fail_frost_keys.bin";
+        let signature_file = "./results/test_truncated_binary_file_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let keys: serde_json::Value = format::decode(&fs::read(keys_file).unwrap()).unwrap();
+        let bytes = format::encode_as(&keys, Format::Bincode).unwrap();
+        fs::write(keys_file, &bytes[..bytes.len() - 8]).unwrap();
+        let result = sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file);
+        assert!(result.is_err(), "Signing should fail with a truncated bincode key file");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_signature_format_mismatch_fail() {
+        let keys_file = "./results/test_signature_format_mismatch_fail_frost_keys.json";
+        let signature_file = "./results/test_signature_format_mismatch_fail_signature.json";
+        let hex_file = "./results/test_signature_format_mismatch_fail_signature.hex";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        fs::write(hex_file, signature::encode(&raw, SignatureFormat::Hex, Format::Json).unwrap()).unwrap();
+        let result = validate_signature_as(message, SIGNING_CONTEXT, keys_file, hex_file, Some(SignatureFormat::Base64), &frost_cli::io::envelope::EnvelopeOptions::default());
+        assert!(result.is_err(), "Verifying a hex signature as base64 should fail");
+        for file in [keys_file, signature_file, hex_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_pem_wrong_label_fail() {
+        let keys_file = "./results/test_pem_wrong_label_fail_frost_keys.json";
+        let signature_file = "./results/test_pem_wrong_label_fail_signature.json";
+        let pem_signature_file = "./results/test_pem_wrong_label_fail_signature.pem";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let bytes = fs::read(signature_file).unwrap();
+        let raw = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        fs::write(pem_signature_file, signature::encode(&raw, SignatureFormat::Pem, Format::Json).unwrap()).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, pem_signature_file, signature_file);
+        assert!(result.is_err(), "A PEM signature should not be accepted as a group key");
+        for file in [keys_file, signature_file, pem_signature_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn test_ed25519_wrong_message_fail() {
+        let keys_file = "./results/test_ed25519_wrong_message_fail_frost_keys.json";
+        let signature_file = "./results/test_ed25519_wrong_message_fail_signature.json";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Ed25519).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let result = validate_signature("hi, this is another test", SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_err(), "An Ed25519 signature should not verify for another message");
+        let result = export_group_key(keys_file, true, &Default::default());
+        assert!(result.is_err(), "An Ed25519 group key should not be armored as a FROST group key");
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn test_secp256k1_wrong_key_fail() {
+        let keys_file = "./results/test_secp256k1_wrong_key_fail_frost_keys.json";
+        let other_keys_file = "./results/test_secp256k1_wrong_key_fail_other_frost_keys.json";
+        let signature_file = "./results/test_secp256k1_wrong_key_fail_signature.json";
+        let message = "hi, this is a test";
+        frost_cli::generate_keys_with_ciphersuite(2, 3, keys_file, frost_cli::backend::Ciphersuite::Secp256k1).unwrap();
+        frost_cli::generate_keys_with_ciphersuite(2, 3, other_keys_file, frost_cli::backend::Ciphersuite::Secp256k1).unwrap();
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, other_keys_file, signature_file);
+        assert!(result.is_err(), "A secp256k1 signature should not verify under another group key");
+        for file in [keys_file, other_keys_file, signature_file] {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_ristretto255_test_vectors_fail() {
+        let vectors_file = "./results/test_ristretto255_test_vectors_fail_vectors.json";
+        fs::write(vectors_file, "{}").unwrap();
+        let result = frost_cli::backend::check_test_vectors(frost_cli::backend::Ciphersuite::Ristretto255, vectors_file);
+        assert!(
+            result.unwrap_err().to_string().contains("earlier draft"),
+            "frost-dalek should not claim to pass the RFC 9591 test vectors"
+        );
+        remove_file(vectors_file).unwrap();
+    }
+
+    #[test]
+    fn test_jws_ristretto255_fail() {
+        let keys_file = "./results/test_jws_ristretto255_fail_frost_keys.json";
+        let jws_file = "./results/test_jws_ristretto255_fail_signature.jws";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::sign_message_as("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, jws_file, frost_cli::io::envelope::OutFormat::Jws, &frost_cli::io::envelope::EnvelopeOptions::default(), None);
+        assert!(result.is_err(), "JWS has no algorithm for ristretto255 signatures");
+        assert!(!Path::new(jws_file).exists());
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_sshsig_ristretto255_fail() {
+        let keys_file = "./results/test_sshsig_ristretto255_fail_frost_keys.json";
+        let sshsig_file = "./results/test_sshsig_ristretto255_fail_signature.sig";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::export_ssh_public_key(keys_file, &Default::default());
+        assert!(result.is_err(), "OpenSSH has no key type for ristretto255 group keys");
+        let result = frost_cli::sign_message_as("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, sshsig_file, frost_cli::io::envelope::OutFormat::Sshsig, &frost_cli::io::envelope::EnvelopeOptions::default(), None);
+        assert!(result.is_err(), "ristretto255 keys should not make SSH signatures");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_openpgp_ristretto255_fail() {
+        let keys_file = "./results/test_openpgp_ristretto255_fail_frost_keys.json";
+        let signature_file = "./results/test_openpgp_ristretto255_fail_signature.asc";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::sign_message_as("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file, frost_cli::io::envelope::OutFormat::Openpgp, &frost_cli::io::envelope::EnvelopeOptions::default(), None);
+        assert!(result.is_err(), "ristretto255 keys should not make OpenPGP signatures");
+        assert!(!Path::new(signature_file).exists());
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_cosign_bundle_ristretto255_fail() {
+        let keys_file = "./results/test_cosign_bundle_ristretto255_fail_frost_keys.json";
+        let bundle_file = "./results/test_cosign_bundle_ristretto255_fail.bundle";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::sign_message_as("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, bundle_file, frost_cli::io::envelope::OutFormat::CosignBundle, &frost_cli::io::envelope::EnvelopeOptions::default(), None);
+        assert!(result.is_err(), "ristretto255 keys should not make cosign bundles");
+        assert!(!Path::new(bundle_file).exists());
+        assert!(frost_cli::export_pkix_public_key(keys_file, &Default::default()).is_err());
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_cose_ristretto255_fail() {
+        let keys_file = "./results/test_cose_ristretto255_fail_frost_keys.json";
+        let cose_file = "./results/test_cose_ristretto255_fail.cose";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::sign_message_as("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, cose_file, frost_cli::io::envelope::OutFormat::Cose, &frost_cli::io::envelope::EnvelopeOptions::default(), None);
+        assert!(result.is_err(), "ristretto255 keys should not make COSE signatures");
+        assert!(!Path::new(cose_file).exists());
+        assert!(frost_cli::export_cose_key(keys_file, &Default::default()).is_err());
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_dsse_payload_type() {
+        use frost_cli::io::envelope::{EnvelopeOptions, OutFormat};
+        let keys_file = "./results/test_dsse_payload_type_frost_keys.json";
+        let envelope_file = "./results/test_dsse_payload_type.dsse.json";
+        let message = "hi, this is a test";
+        let text = EnvelopeOptions { payload_type: "text/plain".to_string(), ..Default::default() };
+        generate_keys(2, 3, keys_file).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, envelope_file, OutFormat::Dsse, &text, None).unwrap();
+        let envelope: serde_json::Value = serde_json::from_str(&fs::read_to_string(envelope_file).unwrap()).unwrap();
+        assert_eq!(envelope["payloadType"], "text/plain");
+        let result = validate_signature_as(message, SIGNING_CONTEXT, keys_file, envelope_file, None, &text);
+        assert!(result.is_ok(), "Failed to verify a DSSE envelope with its payload type: {:?}", result.err());
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, envelope_file);
+        assert!(result.is_err(), "A DSSE envelope should not verify with the default payload type");
+        remove_file(keys_file).unwrap();
+        remove_file(envelope_file).unwrap();
+    }
+
+    #[test]
+    fn test_dsse_payload_type_fail() {
+        let keys_file = "./results/test_dsse_payload_type_fail_frost_keys.json";
+        let envelope_file = "./results/test_dsse_payload_type_fail.dsse.json";
+        let message = "hi, this is a test";
+        generate_keys(2, 3, keys_file).unwrap();
+        frost_cli::sign_message_as(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, envelope_file, frost_cli::io::envelope::OutFormat::Dsse, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        let mut envelope: serde_json::Value = serde_json::from_str(&fs::read_to_string(envelope_file).unwrap()).unwrap();
+        envelope["payloadType"] = "text/plain".into();
+        fs::write(envelope_file, serde_json::to_vec(&envelope).unwrap()).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, envelope_file);
+        assert!(result.is_err(), "A DSSE envelope with another payload type should not verify");
+        remove_file(keys_file).unwrap();
+        remove_file(envelope_file).unwrap();
+    }
+
+    #[test]
+    fn test_attest_invalid_predicate_fail() {
+        let keys_file = "./results/test_attest_invalid_predicate_fail_frost_keys.json";
+        let predicate_file = "./results/test_attest_invalid_predicate_fail_predicate.json";
+        let envelope_file = "./results/test_attest_invalid_predicate_fail.dsse.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(predicate_file, "[1, 2, 3]").unwrap();
+        let predicate = frost_cli::attest::Predicate::Custom {
+            predicate_type: "https://example.com/predicate/v1".to_string(),
+            predicate_file: predicate_file.to_string(),
+        };
+        let result = frost_cli::attest::attest(&[keys_file.to_string()], &predicate, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, envelope_file, None, None);
+        assert!(result.is_err(), "A predicate that is not a JSON object should be rejected");
+        assert!(!Path::new(envelope_file).exists());
+        remove_file(keys_file).unwrap();
+        remove_file(predicate_file).unwrap();
+    }
+
+    #[test]
+    fn test_git_sign_ristretto255_fail() {
+        let keys_file = "./results/test_git_sign_ristretto255_fail_frost_keys.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let args: Vec<String> = ["--status-fd=2", "-bsau", "Release Team"].iter().map(|arg| arg.to_string()).collect();
+        let (mut signature, mut status) = (Vec::new(), Vec::new());
+        let result = frost_cli::git::run(&args, keys_file, vec![0, 1], 3, None, &b"a commit"[..], &mut signature, &mut status, &Default::default());
+        assert!(result.is_err(), "ristretto255 keys should not sign git commits");
+        assert!(signature.is_empty());
+        assert!(!String::from_utf8(status).unwrap().contains("SIG_CREATED"));
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_tuf_float_metadata_fail() {
+        let keys_file = "./results/test_tuf_float_metadata_fail_frost_keys.json";
+        let metadata_file = "./results/test_tuf_float_metadata_fail_targets.json";
+        let metadata = r#"{"signed": {"_type": "targets", "version": 1.5, "targets": {}}, "signatures": []}"#;
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(metadata_file, metadata).unwrap();
+        let result = frost_cli::tuf::sign_metadata(metadata_file, vec![0, 1], 3, keys_file, None, None, &Default::default());
+        assert!(result.is_err(), "Metadata with floating point numbers has no canonical JSON");
+        assert_eq!(fs::read_to_string(metadata_file).unwrap(), metadata);
+        remove_file(keys_file).unwrap();
+        remove_file(metadata_file).unwrap();
+    }
+
+    #[test]
+    fn test_jcs_invalid_json_fail() {
+        let result = frost_cli::io::jcs::canonicalize_as("hi, this is a test", frost_cli::io::jcs::Canonicalization::Jcs);
+        assert!(result.is_err(), "A message that is not JSON cannot be canonicalized");
+        let result = frost_cli::io::jcs::canonicalize_as("hi, this is a test", frost_cli::io::jcs::Canonicalization::None);
+        assert_eq!(result.unwrap(), b"hi, this is a test");
+    }
+
+    #[test]
+    fn test_signature_metadata_other_group_fail() {
+        let keys_file = "./results/test_signature_metadata_other_group_fail_frost_keys.json";
+        let signature_file = "./results/test_signature_metadata_other_group_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let message = "hi, this is a test";
+        sign_message(message, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        // Keep the valid signature but name another group key in its metadata.
+        let bytes = fs::read(signature_file).unwrap();
+        let sig = signature::decode(&bytes, signature_file, SignatureFormat::Json).unwrap();
+        let metadata = frost_cli::SignatureMetadata::new(frost_cli::backend::Ciphersuite::Ristretto255, SIGNING_CONTEXT, &[7u8; 32], vec![1, 2]).unwrap();
+        fs::write(signature_file, signature::encode_with_metadata(&sig, Some(&metadata), SignatureFormat::Json, Format::Json).unwrap()).unwrap();
+        let result = validate_signature(message, SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(result.is_err(), "Expected metadata naming another group key to be rejected");
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_rfc3161_timestamp_missing_fail() {
+        let keys_file = "./results/test_rfc3161_timestamp_missing_fail_frost_keys.json";
+        let signature_file = "./results/test_rfc3161_timestamp_missing_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let result = frost_cli::timestamp::verify_signature_timestamp(signature_file, keys_file);
+        assert!(result.is_err(), "Expected a signature without a timestamp token to be rejected");
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_json_output_unknown_mode_fail() {
+        let result = "yaml".parse::<frost_cli::io::output::OutputMode>();
+        assert!(result.is_err(), "Expected an unknown output mode to be rejected");
+    }
+
+    #[test]
+    fn test_sign_binary_file_changed_fail() {
+        let keys_file = "./results/test_sign_binary_file_changed_fail_frost_keys.json";
+        let data_file = "./results/test_sign_binary_file_changed_fail_data.bin";
+        let signature_file = "./results/test_sign_binary_file_changed_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(data_file, [0u8, 159, 146, 150, 255]).unwrap();
+        frost_cli::sign_file(data_file, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file, SignatureFormat::Json, &Default::default(), None).unwrap();
+        fs::write(data_file, [0u8, 159, 146, 150, 254]).unwrap();
+        let result = frost_cli::validate_file_signature(data_file, SIGNING_CONTEXT, keys_file, signature_file, None, &Default::default());
+        assert!(result.is_err(), "Expected a changed file to be rejected");
+        remove_file(keys_file).unwrap();
+        remove_file(data_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_sign_manifest_ambiguous_entry_fail() {
+        let manifest_file = "./results/test_sign_manifest_ambiguous_entry_fail_manifest.json";
+        fs::write(
+            manifest_file,
+            r#"{"entries": [{"message": "release v1", "file": "artifact.bin", "signature_file": "release.sig"}]}"#,
+        )
+        .unwrap();
+        let result = frost_cli::manifest::load_manifest(manifest_file);
+        assert!(result.is_err(), "Expected an entry with both a message and a file to be rejected");
+        remove_file(manifest_file).unwrap();
+    }
+
+    #[test]
+    fn test_verify_manifest_changed_entry_fail() {
+        let dir = "./results/test_verify_manifest_changed_entry_fail";
+        let keys_file = "./results/test_verify_manifest_changed_entry_fail_frost_keys.json";
+        let manifest_file = format!("{}/manifest.json", dir);
+        fs::create_dir_all(dir).unwrap();
+        generate_keys(2, 3, keys_file).unwrap();
+        fs::write(format!("{}/artifact.bin", dir), [7u8; 1000]).unwrap();
+        fs::write(
+            &manifest_file,
+            r#"{"entries": [
+                {"message": "release v1", "signature_file": "release.sig"},
+                {"file": "artifact.bin", "signature_file": "artifact.bin.sig"}
+            ]}"#,
+        )
+        .unwrap();
+        frost_cli::manifest::sign_manifest(&manifest_file, SIGNING_CONTEXT, vec![0, 2], 3, keys_file, &frost_cli::io::envelope::EnvelopeOptions::default(), None).unwrap();
+        fs::write(format!("{}/artifact.bin", dir), [8u8; 1000]).unwrap();
+        let result = frost_cli::manifest::verify_manifest(&manifest_file, SIGNING_CONTEXT, keys_file, None, &Default::default());
+        assert!(result.is_err(), "Expected a manifest with a changed file to fail");
+        remove_file(keys_file).unwrap();
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_generate_keys_progress_invalid_threshold_fail() {
+        use std::cell::Cell;
+        let keys_file = "./results/test_generate_keys_progress_invalid_threshold_fail_frost_keys.json";
+        let updates = Cell::new(0);
+        let result = frost_cli::generate_keys_with_progress(5, 3, keys_file, frost_cli::backend::Ciphersuite::Ristretto255, &|_| {
+            updates.set(updates.get() + 1)
+        },
+            &Default::default(),);
+        assert!(result.is_err(), "Expected a threshold above the number of participants to be rejected");
+        assert_eq!(updates.get(), 0);
+        assert!(!Path::new(keys_file).exists());
+    }
+
+    #[test]
+    fn test_output_mode_quiet_fail() {
+        let result = "quiet".parse::<frost_cli::io::output::OutputMode>();
+        assert!(result.is_err(), "Expected quiet to be a verbosity flag rather than an output mode");
+    }
+
+    #[test]
+    fn test_verify_wrong_message_invalid_signature_fail() {
+        let keys_file = "./results/test_verify_wrong_message_invalid_signature_fail_frost_keys.json";
+        let signature_file = "./results/test_verify_wrong_message_invalid_signature_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let result = validate_signature("hi, this is another test", SIGNING_CONTEXT, keys_file, signature_file);
+        assert!(
+            result.unwrap_err().is::<frost_cli::InvalidSignature>(),
+            "Expected a signature on another message to be an invalid signature"
+        );
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_keystore_key_names_not_a_directory_fail() {
+        let keystore_file = "./results/test_keystore_key_names_not_a_directory_fail";
+        fs::write(keystore_file, "not a keystore").unwrap();
+        let result = keystore::key_names(Path::new(keystore_file));
+        assert!(result.is_err(), "Expected a keystore that is a file to be rejected");
+        remove_file(keystore_file).unwrap();
+    }
+
+    #[test]
+    fn test_config_var_missing_file_fail() {
+        std::env::set_var("FROST_TEST_CONFIG_MISSING_FILE", "./results/test_config_var_missing_file_fail_secret");
+        let result = frost_cli::config::var("FROST_TEST_CONFIG_MISSING");
+        assert!(result.is_err(), "Expected a _FILE variable naming a missing file to be rejected");
+        std::env::remove_var("FROST_TEST_CONFIG_MISSING_FILE");
+    }
+
+    #[test]
+    fn test_signature_metadata_other_context_fail() {
+        use frost_cli::backend::Ciphersuite;
+        let group_key = [7u8; 32];
+        let mut metadata = frost_cli::SignatureMetadata::new(Ciphersuite::Ristretto255, SIGNING_CONTEXT, &group_key, vec![1, 2]).unwrap();
+        assert!(metadata.check(Ciphersuite::Ristretto255, SIGNING_CONTEXT, &group_key).is_ok());
+        metadata.context = "release-signing".to_string();
+        let err = metadata.check(Ciphersuite::Ristretto255, SIGNING_CONTEXT, &group_key).unwrap_err();
+        assert!(
+            err.to_string().contains("release-signing"),
+            "Expected a signature of another context to name it: {}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_inspect_key_not_a_key_fail() {
+        let keys_file = "./results/test_inspect_key_not_a_key_fail_frost_keys.json";
+        let signature_file = "./results/test_inspect_key_not_a_key_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).unwrap();
+        let result = frost_cli::inspect::inspect_key(signature_file, &Default::default());
+        assert!(result.is_err(), "Expected a signature file to be rejected as a key");
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_inspect_signature_not_a_signature_fail() {
+        let keys_file = "./results/test_inspect_signature_not_a_signature_fail_frost_keys.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::inspect::inspect_signature(keys_file, None);
+        assert!(result.is_err(), "Expected a key file to be rejected as a signature");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_check_compatibility_other_group_fail() {
+        let keys_file = "./results/test_check_compatibility_other_group_fail_frost_keys.json";
+        let other_keys_file = "./results/test_check_compatibility_other_group_fail_other_frost_keys.json";
+        let signature_file = "./results/test_check_compatibility_other_group_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        generate_keys(2, 3, other_keys_file).unwrap();
+        sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, other_keys_file, signature_file).unwrap();
+        let report = frost_cli::check::check_compatibility(keys_file, Some(signature_file), None, SIGNING_CONTEXT, &Default::default());
+        assert!(!report.is_compatible(), "Expected a signature of another group to be a mismatch");
+        assert!(report.findings.iter().any(|finding| !finding.ok && finding.subject == "signature_file"));
+        remove_file(keys_file).unwrap();
+        remove_file(other_keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_verify_in_memory_wrong_message_fail() {
+        use frost_cli::backend::Ciphersuite;
+        let frost_keys = frost_cli::generate_frost_keys(2, 3, Ciphersuite::Ristretto255, &|_| {}).unwrap();
+        let signature = frost_cli::sign_with_keys(b"hi, this is a test", SIGNING_CONTEXT, &frost_keys, &[0, 1]).unwrap();
+        let result = frost_cli::verify_with_group_key(b"another message", SIGNING_CONTEXT, frost_keys.ciphersuite, &frost_keys.group_key, &signature);
+        let err = result.expect_err("Expected the signature to fail for another message");
+        assert!(err.is::<frost_cli::InvalidSignature>());
+        let result = frost_cli::sign_with_keys(b"hi, this is a test", SIGNING_CONTEXT, &frost_keys, &[0]);
+        assert!(result.is_err(), "Expected fewer signers than the threshold to fail");
+    }
+
+    #[test]
+    fn test_sign_invalid_signer_error_kind_fail() {
+        use frost_cli::error::FrostCliError;
+        let keys_file = "./results/test_sign_invalid_signer_error_kind_fail_frost_keys.json";
+        let signature_file = "./results/test_sign_invalid_signer_error_kind_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let err = sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 5], 3, keys_file, signature_file).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidParameters(_))));
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_round_one_with_rng_invalid_index_fail() {
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let state_file = "./results/test_dkg_round_one_with_rng_invalid_index_fail_state.json";
+        let output_dir = "./results/test_dkg_round_one_with_rng_invalid_index_fail";
+        let result = dkg::round_one_with_rng(4, 2, 3, state_file, output_dir, &Default::default(), &mut StdRng::seed_from_u64(7));
+        assert!(result.is_err(), "Expected a participant index above n to be rejected");
+        assert!(!Path::new(state_file).exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_async_verify_wrong_message_fail() {
+        use frost_cli::nonblocking;
+        let keys_file = "./results/test_async_verify_wrong_message_fail_frost_keys.json";
+        let signature_file = "./results/test_async_verify_wrong_message_fail_signature.json";
+        let err = nonblocking::sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).await.unwrap_err();
+        assert!(frost_cli::error::is_io_error(&*err), "Expected a missing key file to stay an I/O error: {}", err);
+        generate_keys(2, 3, keys_file).unwrap();
+        nonblocking::sign_message("hi, this is a test", SIGNING_CONTEXT, vec![0, 1], 3, keys_file, signature_file).await.unwrap();
+        let err = nonblocking::validate_signature("another message", SIGNING_CONTEXT, keys_file, signature_file).await.unwrap_err();
+        assert!(err.is::<frost_cli::InvalidSignature>(), "Expected an invalid signature: {}", err);
+        remove_file(keys_file).unwrap();
+        remove_file(signature_file).unwrap();
+    }
+
+    #[test]
+    fn test_file_keystore_trait_dealer_key_fail() {
+        use frost_cli::keystore::{FileKeyStore, KeyStore};
+        let keystore_dir = "./results/test_file_keystore_trait_dealer_key_fail";
+        let signature_file = "./results/test_file_keystore_trait_dealer_key_fail_signature.json";
+        let keystore = FileKeyStore::new(keystore_dir);
+        let key_file = keystore::new_key_file(keystore.dir(), "dealer").unwrap();
+        generate_keys(2, 3, &key_file).unwrap();
+        assert!(keystore.load_group_key("dealer").is_ok());
+        assert!(keystore.load_share("dealer").is_err(), "Expected a dealer key file not to load as a share");
+        let share = frost_cli::ParticipantShare { index: 1, share: [1; 32], group_key: [0; 32], threshold: 2, participants: 3, verification_shares: Vec::new() };
+        assert!(keystore.store_share("dealer", &share).is_err(), "Expected an existing name to be rejected");
+        let result = frost_cli::sign_message_with_keystore("hi, this is a test", SIGNING_CONTEXT, &keystore, &["dealer".to_string()], signature_file, None);
+        assert!(result.is_err(), "Expected signing with a dealer key as a share to fail");
+        let result = frost_cli::validate_signature_with_keystore("hi, this is a test", SIGNING_CONTEXT, &keystore, "missing", signature_file, None);
+        assert!(result.is_err(), "Expected a missing key to fail");
+        remove_dir_all(keystore_dir).unwrap();
+    }
+
+    #[test]
+    fn test_transport_timeout_and_wrong_share_fail() {
+        use frost_cli::net::transport::{self, MemoryTransport, Transport};
+        use std::time::Duration;
+        let work_dir = "./results/test_transport_timeout_and_wrong_share_fail";
+        let mut network = MemoryTransport::network(2).into_iter();
+        let first = network.next().unwrap().with_timeout(Duration::from_millis(200));
+        let share_file = format!("{}/share_1.json", work_dir);
+        let err = transport::run_dkg(&first, 2, 2, &format!("{}/dkg", work_dir), &share_file, &Default::default()).unwrap_err();
+        assert!(err.to_string().contains("Timed out"), "Expected a missing participant to time out: {}", err);
+        assert!(first.receive(2, transport::MessageKind::DkgShare).is_err());
+        let share_files = run_dkg("test_transport_timeout_and_wrong_share_fail", 2, 3);
+        let result = transport::run_signing(&first, "hi, this is a test", SIGNING_CONTEXT, "transport", &share_files[1], &[1, 2], &format!("{}/sign", work_dir), &format!("{}/signature.json", work_dir), &Default::default());
+        assert!(result.is_err(), "Expected another participant's share to be rejected");
+        let result = transport::run_signing(&first, "hi, this is a test", SIGNING_CONTEXT, "transport", &share_files[0], &[2, 3], &format!("{}/sign", work_dir), &format!("{}/signature.json", work_dir), &Default::default());
+        assert!(result.is_err(), "Expected a participant outside the signers to be rejected");
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(work_dir).unwrap();
+    }
+
+    #[test]
+    fn test_protocol_message_version_fail() {
+        use frost_cli::messages;
+        let message = serde_json::json!({ "partial_signature": { "session_id": "session", "index": 2, "z": vec![7u8; 32] } });
+        let future = serde_json::to_vec(&serde_json::json!({ "version": 2, "message": message })).unwrap();
+        assert!(messages::decode(&future).is_err(), "Expected another schema version to be rejected");
+        let unversioned = serde_json::to_vec(&serde_json::json!({ "message": message })).unwrap();
+        assert!(messages::decode(&unversioned).is_err(), "Expected a message without a version to be rejected");
+        let unknown = serde_json::to_vec(&serde_json::json!({ "version": 1, "message": { "round3": {} } })).unwrap();
+        assert!(messages::decode(&unknown).is_err(), "Expected an unknown message to be rejected");
+    }
+
+    #[test]
+    fn test_participant_operations_in_memory_fail() {
+        let share_files = run_dkg("test_participant_operations_in_memory_fail", 2, 3);
+        let shares: Vec<_> = share_files.iter().map(|share_file| load_participant_share(share_file, &Default::default()).unwrap()).collect();
+        let (public, secret) = signing::commit_with_share(SESSION_ID, &shares[0], signing::CommitOptions::default()).unwrap();
+        let (other, _) = signing::commit_with_share(SESSION_ID, &shares[1], signing::CommitOptions::default()).unwrap();
+        let roster = vec![public.clone(), other.clone()];
+        let result = signing::partial_sign_with_share("hi, this is a test", SIGNING_CONTEXT, &shares[0], &secret, &roster[..1]);
+        assert!(result.is_err(), "Expected a roster smaller than the threshold to fail");
+        let result = signing::partial_sign_with_share("hi, this is a test", SIGNING_CONTEXT, &shares[2], &secret, &roster);
+        assert!(result.is_err(), "Expected another participant's secret commitments to fail");
+        let (_, remaining) = signing::partial_sign_with_share("hi, this is a test", SIGNING_CONTEXT, &shares[0], &secret, &roster).unwrap();
+        let result = signing::partial_sign_with_share("another message", SIGNING_CONTEXT, &shares[0], &remaining, &roster);
+        assert!(result.is_err(), "Expected reusing burnt nonces to fail");
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "cdylib")]
+    fn test_ffi_errors_fail() {
+        use frost_cli::ffi;
+        use std::ffi::{CStr, CString};
+        let key_file = CString::new("./results/test_ffi_errors_fail_keys.json").unwrap();
+        let signature_file = CString::new("./results/test_ffi_errors_fail_signature.json").unwrap();
+        let message = CString::new("hi, this is a test").unwrap();
+        let code = unsafe { ffi::frost_verify(std::ptr::null(), std::ptr::null(), key_file.as_ptr(), signature_file.as_ptr()) };
+        assert_eq!(code, ffi::FROST_INVALID_ARGUMENT);
+        let error = unsafe { CStr::from_ptr(ffi::frost_last_error()) }.to_str().unwrap();
+        assert!(error.contains("message is null"), "Unexpected error: {}", error);
+        assert_eq!(unsafe { ffi::frost_generate_keys(4, 3, key_file.as_ptr(), std::ptr::null()) }, ffi::FROST_INVALID_PARAMETERS);
+        generate_keys(2, 3, key_file.to_str().unwrap()).unwrap();
+        sign_message("another message", SIGNING_CONTEXT, vec![0, 1], 3, key_file.to_str().unwrap(), signature_file.to_str().unwrap()).unwrap();
+        let code = unsafe { ffi::frost_verify(message.as_ptr(), std::ptr::null(), key_file.as_ptr(), signature_file.as_ptr()) };
+        assert_eq!(code, ffi::FROST_INVALID_SIGNATURE);
+        remove_file(key_file.to_str().unwrap()).unwrap();
+        remove_file(signature_file.to_str().unwrap()).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "python")]
+    fn test_python_module_exceptions_fail() {
+        use pyo3::prelude::*;
+        use pyo3::types::IntoPyDict;
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = pyo3::wrap_pymodule!(frost_cli::python::frost_dalek_cli)(py);
+            let locals = [("frost", module)].into_py_dict_bound(py);
+            let script = r#"
+keys = "./results/test_python_module_exceptions_fail_keys.json"
+signature = "./results/test_python_module_exceptions_fail_signature.json"
+try:
+    frost.generate_keys(4, 3, keys)
+    raise AssertionError("Expected a threshold above the participants to fail")
+except frost.InvalidParametersError as err:
+    assert isinstance(err, frost.FrostCliError)
+frost.generate_keys(2, 3, keys)
+frost.sign_message("another message", [0, 1], 3, keys, signature)
+try:
+    frost.validate_signature("hi, this is a test", keys, signature)
+    raise AssertionError("Expected a signature of another message to fail")
+except frost.InvalidSignatureError:
+    pass
+try:
+    frost.validate_signature("hi, this is a test", keys, "./results/test_python_module_exceptions_fail_missing.json")
+    raise AssertionError("Expected a missing signature file to fail")
+except OSError:
+    pass
+"#;
+            py.run_bound(script, None, Some(&locals)).unwrap();
+        });
+        remove_file("./results/test_python_module_exceptions_fail_keys.json").unwrap();
+        remove_file("./results/test_python_module_exceptions_fail_signature.json").unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "uniffi")]
+    fn test_mobile_signer_fail() {
+        use frost_cli::mobile::{self, SignerError};
+        let share_files = run_dkg("test_mobile_signer_fail", 2, 3);
+        let share = fs::read_to_string(&share_files[0]).unwrap();
+        let commitments = mobile::commit(SESSION_ID.to_string(), share.clone()).unwrap();
+        let roster = format!("[{}]", commitments.public_commitments);
+        let result = mobile::partial_sign("hi, this is a test".to_string(), share.clone(), commitments.secret_commitments.clone(), roster, None);
+        assert!(matches!(result, Err(SignerError::InvalidParameters { .. })), "Expected a roster smaller than the threshold to fail");
+        let mut tampered: serde_json::Value = serde_json::from_str(&share).unwrap();
+        tampered["index"] = 2.into();
+        let result = mobile::commit(SESSION_ID.to_string(), tampered.to_string());
+        assert!(result.is_err(), "Expected a tampered share to fail its checksum");
+        let result = mobile::decrypt_share("{}".to_string(), "{}".to_string());
+        assert!(matches!(result, Err(SignerError::Failed { .. })), "Expected malformed JSON to fail");
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_refresh_fail() {
+        let share_files = run_dkg("test_refresh_fail", 2, 3);
+        let dir = "./results/test_refresh_fail";
+        let state_files: Vec<String> = (1..=3).map(|i| format!("./results/test_refresh_fail_state_{}.json", i)).collect();
+        for (share_file, state_file) in share_files.iter().zip(&state_files) {
+            frost_cli::refresh::round_one("refresh-1", share_file, state_file, dir, &Default::default()).unwrap();
+        }
+
+        // A polynomial that does not share zero would change the group secret.
+        let message_file = format!("{}/refresh_2.json", dir);
+        let mut message: serde_json::Value = serde_json::from_str(&fs::read_to_string(&message_file).unwrap()).unwrap();
+        message["commitments"][0] = message["commitments"][1].clone();
+        fs::write(&message_file, message.to_string()).unwrap();
+        let result = frost_cli::refresh::finish(&state_files[0], &share_files[0], dir, &share_files[0], &Default::default());
+        let err = result.err().expect("Expected a nonzero sharing to fail");
+        assert!(
+            matches!(err.downcast_ref::<frost_cli::error::FrostCliError>(), Some(frost_cli::error::FrostCliError::Dkg { index: 2, .. })),
+            "Expected participant 2 to be blamed: {}",
+            err
+        );
+        let result = frost_cli::refresh::finish(&state_files[0], &share_files[1], dir, &share_files[0], &Default::default());
+        assert!(result.is_err(), "Expected another participant's share to fail");
+
+        // Round messages of an earlier refresh are refused in a later one.
+        let earlier_dir = "./results/test_refresh_fail_earlier";
+        for (session_id, round_dir) in [("refresh-0", earlier_dir), ("refresh-1", dir)] {
+            for (share_file, state_file) in share_files.iter().zip(&state_files) {
+                frost_cli::refresh::round_one(session_id, share_file, state_file, round_dir, &Default::default()).unwrap();
+            }
+        }
+        for file in ["refresh_2.json", "refresh_share_2_1.json"] {
+            let replayed = format!("{}/{}", dir, file);
+            let original = fs::read(&replayed).unwrap();
+            fs::copy(format!("{}/{}", earlier_dir, file), &replayed).unwrap();
+            let result = frost_cli::refresh::finish(&state_files[0], &share_files[0], dir, &share_files[0], &Default::default());
+            let err = result.expect_err("Expected a replayed round message to fail");
+            assert!(err.to_string().contains("belongs to refresh refresh-0, expected refresh-1"), "Unexpected error: {}", err);
+            fs::write(&replayed, original).unwrap();
+        }
+        let result = frost_cli::refresh::finish(&state_files[0], &share_files[0], dir, &share_files[0], &Default::default());
+        assert!(result.is_ok(), "Refresh failed: {:?}", result.err());
+
+        for file in share_files.iter().chain(&state_files[1..]) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+        remove_dir_all(earlier_dir).unwrap();
+    }
+
+    #[test]
+    fn test_reshare_fail() {
+        let keys_file = "./results/test_reshare_fail_keys.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let frost_keys = frost_cli::load_frost_keys(keys_file, &Default::default()).unwrap();
+        let result = frost_cli::reshare::reshare_frost_keys(&frost_keys, &[0], 2, 3, &mut rand::rngs::OsRng);
+        assert!(result.is_err(), "Expected fewer dealers than the threshold to fail");
+        let result = frost_cli::reshare::reshare_frost_keys(&frost_keys, &[0, 0], 2, 3, &mut rand::rngs::OsRng);
+        assert!(result.is_err(), "Expected a repeated dealer to fail");
+        remove_file(keys_file).unwrap();
+
+        // A dealer message made for another set of dealers is rejected.
+        let share_files = run_dkg("test_reshare_fail", 2, 3);
+        let dir = "./results/test_reshare_fail";
+        frost_cli::reshare::round_one(&share_files[0], &[1, 2], 2, 2, dir, &Default::default()).unwrap();
+        frost_cli::reshare::round_one(&share_files[1], &[1, 2], 2, 2, dir, &Default::default()).unwrap();
+        let impostor_dir = "./results/test_reshare_fail_impostor";
+        frost_cli::reshare::round_one(&share_files[0], &[1, 3], 2, 2, impostor_dir, &Default::default()).unwrap();
+        fs::copy(format!("{}/reshare_1.json", impostor_dir), format!("{}/reshare_1.json", dir)).unwrap();
+        let result = frost_cli::reshare::finish(1, &[1, 2], dir, "./results/test_reshare_fail_new_share.json", &Default::default());
+        assert!(result.is_err(), "Expected reshare messages of different dealer sets to fail");
+        let result = frost_cli::reshare::round_one(&share_files[2], &[1, 2], 2, 2, dir, &Default::default());
+        assert!(result.is_err(), "Expected a participant that is not a dealer to fail");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+        remove_dir_all(impostor_dir).unwrap();
+    }
+
+    #[test]
+    fn test_enroll_fail() {
+        let share_files = run_dkg("test_enroll_fail", 2, 3);
+        let dir = "./results/test_enroll_fail";
+        let new_share_file = "./results/test_enroll_fail_new_share.json";
+        let result = frost_cli::enroll::round_one(&share_files[0], &[1], 4, dir, &Default::default());
+        assert!(result.is_err(), "Expected fewer helpers than the threshold to fail");
+        let result = frost_cli::enroll::round_one(&share_files[0], &[1, 2], 2, dir, &Default::default());
+        assert!(result.is_err(), "Expected a helper's index for the new participant to fail");
+        let result = frost_cli::enroll::round_one(&share_files[0], &[1, 2], 3, dir, &Default::default());
+        assert!(result.is_err(), "Expected an existing participant's index to be left to repair");
+
+        // A helper that sends a wrong sum is caught against the public shares.
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_one(share_file, &[1, 2], 4, dir, &Default::default()).unwrap();
+        }
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_two(share_file, &[1, 2], 4, dir, dir, &Default::default()).unwrap();
+        }
+        let sum_file = format!("{}/enroll_share_2_4.json", dir);
+        let mut sum: serde_json::Value = serde_json::from_str(&fs::read_to_string(&sum_file).unwrap()).unwrap();
+        sum["share"] = serde_json::to_value([1u8; 32]).unwrap();
+        fs::write(&sum_file, sum.to_string()).unwrap();
+        let result = frost_cli::enroll::finish(4, &[1, 2], dir, new_share_file, &Default::default());
+        let err = result.err().expect("Expected a wrong sum to fail");
+        assert!(
+            matches!(err.downcast_ref::<frost_cli::error::FrostCliError>(), Some(frost_cli::error::FrostCliError::InvalidShare { .. })),
+            "Expected an invalid share error: {}",
+            err
+        );
+        assert!(!Path::new(new_share_file).exists(), "No share should be saved");
+        let result = frost_cli::enroll::update_share(&share_files[0], 2, &Default::default());
+        assert!(result.is_err(), "Expected fewer participants than the share records to fail");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_revoke_fail() {
+        let dir = "./results/test_revoke_fail";
+        fs::create_dir_all(dir).unwrap();
+        let keys_file = "./results/test_revoke_fail/frost_keys.json";
+        let revoked_file = "./results/test_revoke_fail/frost_keys_revoked.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        let result = frost_cli::revoke::revoke_keys(keys_file, &[0, 1], &[2], revoked_file, &Default::default());
+        assert!(result.is_err(), "Expected a signer holding a revoked share to fail");
+        let result = frost_cli::revoke::revoke_keys(keys_file, &[0, 1], &[4], revoked_file, &Default::default());
+        assert!(result.is_err(), "Expected revoking an unknown participant to fail");
+        assert!(!Path::new(revoked_file).exists(), "No keys should be saved");
+
+        // A statement signed by another group is not recorded.
+        let share_files = run_dkg("test_revoke_fail", 2, 3);
+        let share_file = format!("{}/participant_share.json", dir);
+        let statement_file = format!("{}/revocation.json", dir);
+        let signature_file = format!("{}/signature.json", dir);
+        fs::copy(&share_files[0], &share_file).unwrap();
+        let result = frost_cli::revoke::round_one(&share_file, &[1, 3], &[3], dir, &Default::default());
+        assert!(result.is_err(), "Expected a revoked dealer to fail");
+        frost_cli::revoke::new_statement(&share_file, &[3], &statement_file, &Default::default()).unwrap();
+        let statement = fs::read_to_string(&statement_file).unwrap();
+        sign_message(&statement, SIGNING_CONTEXT, vec![0, 1], 3, keys_file, &signature_file).unwrap();
+        let result = frost_cli::revoke::record(&share_file, &statement_file, &signature_file, &Default::default());
+        assert!(result.is_err(), "Expected a signature by another group to fail");
+        assert!(!Path::new(&format!("{}/revocations.json", dir)).exists(), "No revocation should be recorded");
+        assert!(frost_cli::revoke::revoked_indices(&share_file, &Default::default()).unwrap().is_empty());
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_share_verify_fail() {
+        let share_files = run_dkg("test_share_verify_fail", 2, 3);
+        let other_files = run_dkg("test_share_verify_fail_other", 2, 3);
+        let tampered_file = "./results/test_share_verify_fail_tampered.json";
+        let result = backup::verify_share(&share_files[0], &other_files[1..2], &Default::default());
+        assert!(result.is_err(), "Expected a share of another group to fail");
+        let result = backup::verify_share(&share_files[0], &share_files[..1], &Default::default());
+        assert!(result.is_err(), "Expected the same share given twice to fail");
+
+        // A share that was changed no longer signs with the others.
+        let mut share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        share.share = [1u8; 32];
+        save_participant_share(tampered_file, &share, &Default::default()).unwrap();
+        let result = backup::verify_share(tampered_file, &share_files[1..2], &Default::default());
+        let err = result.err().expect("Expected a tampered share to fail");
+        assert!(
+            matches!(err.downcast_ref::<frost_cli::error::FrostCliError>(), Some(frost_cli::error::FrostCliError::InvalidShare { .. })),
+            "Expected an invalid share error: {}",
+            err
+        );
+
+        for share_file in share_files.iter().chain(&other_files).map(String::as_str).chain([tampered_file]) {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_fail() {
+        let share_files = run_dkg("test_reconstruct_fail", 2, 3);
+        let other_files = run_dkg("test_reconstruct_fail_other", 2, 3);
+        let tampered_file = "./results/test_reconstruct_fail_tampered.json";
+        let secret_file = "./results/test_reconstruct_fail_secret.json";
+        let result = frost_cli::reconstruct::reconstruct(&share_files[..1], secret_file, &Default::default());
+        assert!(result.is_err(), "Expected fewer shares than the threshold to fail");
+        let twice = vec![share_files[0].clone(), share_files[0].clone()];
+        let result = frost_cli::reconstruct::reconstruct(&twice, secret_file, &Default::default());
+        assert!(result.is_err(), "Expected the same share given twice to fail");
+        let mixed = vec![share_files[0].clone(), other_files[1].clone()];
+        let result = frost_cli::reconstruct::reconstruct(&mixed, secret_file, &Default::default());
+        assert!(result.is_err(), "Expected shares of different groups to fail");
+
+        // A changed share gives a key that does not match the group key.
+        let mut share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        share.share = [1u8; 32];
+        save_participant_share(tampered_file, &share, &Default::default()).unwrap();
+        let tampered = vec![tampered_file.to_string(), share_files[1].clone()];
+        let result = frost_cli::reconstruct::reconstruct(&tampered, secret_file, &Default::default());
+        assert!(result.is_err(), "Expected a tampered share to fail");
+        assert!(!Path::new(secret_file).exists(), "No secret key should be saved");
+
+        for file in share_files.iter().chain(&other_files).map(String::as_str).chain([tampered_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_repair_fail() {
+        let share_files = run_dkg("test_repair_fail", 2, 3);
+        let dir = "./results/test_repair_fail";
+        let repaired_file = "./results/test_repair_fail_repaired.json";
+        let result = frost_cli::repair::round_one(&share_files[0], &[1, 2], 4, dir, &Default::default());
+        assert!(result.is_err(), "Expected an index outside the group to fail");
+        let result = frost_cli::repair::round_one(&share_files[0], &[1, 2], 2, dir, &Default::default());
+        assert!(result.is_err(), "Expected a helper's own index to fail");
+
+        // Messages of an enrollment cannot be finished as a repair.
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_one(share_file, &[1, 2], 4, dir, &Default::default()).unwrap();
+        }
+        for share_file in &share_files[..2] {
+            frost_cli::enroll::round_two(share_file, &[1, 2], 4, dir, dir, &Default::default()).unwrap();
+        }
+        let result = frost_cli::repair::finish(4, &[1, 2], dir, repaired_file, &Default::default());
+        assert!(result.is_err(), "Expected a new participant's index to fail");
+        assert!(!Path::new(repaired_file).exists(), "No share should be saved");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_fail() {
+        let dir = "./results/test_rotate_fail";
+        let old_file = "./results/test_rotate_fail/old/frost_keys.json";
+        let new_file = "./results/test_rotate_fail/new/frost_keys.json";
+        let statement_file = "./results/test_rotate_fail/rotation.json";
+        let signature_file = "./results/test_rotate_fail/signature.json";
+        let rotations_file = "./results/test_rotate_fail/new/rotations.json";
+        for sub_dir in ["old", "new"] {
+            fs::create_dir_all(format!("{}/{}", dir, sub_dir)).unwrap();
+        }
+        generate_keys(2, 3, old_file).unwrap();
+        generate_keys(2, 3, new_file).unwrap();
+        frost_cli::rotate::new_statement(old_file, new_file, None, statement_file, &Default::default()).unwrap();
+        let statement = fs::read_to_string(statement_file).unwrap();
+
+        // A statement signed by the new group instead of the old one is not recorded.
+        sign_message(&statement, SIGNING_CONTEXT, vec![0, 1], 3, new_file, signature_file).unwrap();
+        let result = frost_cli::rotate::record(old_file, new_file, statement_file, signature_file, &Default::default());
+        assert!(result.is_err(), "Expected a signature by the new group to fail");
+        assert!(!Path::new(rotations_file).exists(), "No rotation should be recorded");
+
+        // A tampered record fails the history check.
+        sign_message(&statement, SIGNING_CONTEXT, vec![0, 1], 3, old_file, signature_file).unwrap();
+        frost_cli::rotate::record(old_file, new_file, statement_file, signature_file, &Default::default()).unwrap();
+        let records = fs::read_to_string(rotations_file).unwrap();
+        fs::write(rotations_file, records.replace("valid_from\\\":", "valid_from\\\":1")).unwrap();
+        let result = frost_cli::rotate::history(new_file, &Default::default());
+        assert!(result.is_err(), "Expected a tampered rotation to fail");
+
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_commitment_pool_fail() {
+        let share_files = run_dkg("test_commitment_pool_fail", 2, 3);
+        let dir = "./results/test_commitment_pool_fail";
+        fs::create_dir_all(dir).unwrap();
+        let pool_file = "./results/test_commitment_pool_fail/pool.json";
+        let public_file = "./results/test_commitment_pool_fail/public.json";
+        let secret_file = "./results/test_commitment_pool_fail/secret.json";
+        let result = signing::generate_commitments(&share_files[0], 0, pool_file, &Default::default());
+        assert!(result.is_err(), "Expected an empty pool to fail");
+        signing::generate_commitments(&share_files[0], 1, pool_file, &Default::default()).unwrap();
+
+        // The pool of one participant cannot be used or extended by another.
+        let result = signing::generate_commitments(&share_files[1], 1, pool_file, &Default::default());
+        assert!(result.is_err(), "Expected another participant's pool to fail");
+        let result = signing::commit_from_pool(SESSION_ID, &share_files[1], pool_file, public_file, secret_file, &Default::default());
+        assert!(result.is_err(), "Expected another participant's pool to fail");
+
+        // A used up pool gives no more commitments.
+        signing::commit_from_pool(SESSION_ID, &share_files[0], pool_file, public_file, secret_file, &Default::default()).unwrap();
+        remove_file(public_file).unwrap();
+        let result = signing::commit_from_pool(SESSION_ID, &share_files[0], pool_file, public_file, secret_file, &Default::default());
+        assert!(result.is_err(), "Expected a used up pool to fail");
+        assert!(!Path::new(public_file).exists(), "No commitments should be written");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_nonce_state_fail() {
+        use frost_cli::error::FrostCliError;
+        let share_files = run_dkg("test_nonce_state_fail", 2, 3);
+        let dir = "./results/test_nonce_state_fail";
+        fs::create_dir_all(dir).unwrap();
+        let message = "hi, this is a test";
+        let public_files: Vec<String> = (1..=2).map(|i| format!("{}/public_{}.json", dir, i)).collect();
+        let secret_files: Vec<String> = (1..=2).map(|i| format!("{}/secret_{}.json", dir, i)).collect();
+        let backup_file = "./results/test_nonce_state_fail/secret_backup.json";
+        let partial_file = "./results/test_nonce_state_fail/partial.json";
+        let state_file = frost_cli::nonces::state_file(&secret_files[0]);
+        let is_nonce_reuse = |err: &Box<dyn std::error::Error>| matches!(err.downcast_ref(), Some(FrostCliError::NonceReuse { .. }));
+
+        // A restored copy of used secret commitments cannot sign again.
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        fs::copy(&secret_files[0], backup_file).unwrap();
+        signing::partial_sign(message, SIGNING_CONTEXT, &share_files[0], &secret_files[0], &public_files, partial_file, &Default::default()).unwrap();
+        remove_file(partial_file).unwrap();
+        let err = signing::partial_sign("another message", SIGNING_CONTEXT, &share_files[0], backup_file, &public_files, partial_file, &Default::default()).unwrap_err();
+        assert!(is_nonce_reuse(&err), "Expected reusing nonces to fail: {}", err);
+        assert!(!Path::new(partial_file).exists(), "No partial signature should be written");
+
+        // Unused nonces do not sign when the state is missing or rolled back.
+        let state = fs::read(&state_file).unwrap();
+        remove_file(&state_file).unwrap();
+        let err = signing::partial_sign(message, SIGNING_CONTEXT, &share_files[1], &secret_files[1], &public_files, partial_file, &Default::default()).unwrap_err();
+        assert!(is_nonce_reuse(&err), "Expected a missing nonce state to fail: {}", err);
+        let rolled_back = serde_json::json!({ "unused": [], "used": [] });
+        fs::write(&state_file, serde_json::to_vec(&rolled_back).unwrap()).unwrap();
+        let err = signing::partial_sign(message, SIGNING_CONTEXT, &share_files[1], &secret_files[1], &public_files, partial_file, &Default::default()).unwrap_err();
+        assert!(is_nonce_reuse(&err), "Expected a rolled back nonce state to fail: {}", err);
+        fs::write(&state_file, state).unwrap();
+        let result = signing::partial_sign(message, SIGNING_CONTEXT, &share_files[1], &secret_files[1], &public_files, partial_file, &Default::default());
+        assert!(result.is_ok(), "Failed to partial sign with the restored state: {:?}", result.err());
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_commit_with_count_fail() {
+        let share_files = run_dkg("test_commit_with_count_fail", 2, 3);
+        let dir = "./results/test_commit_with_count_fail";
+        let partials_dir = "./results/test_commit_with_count_fail/partials";
+        fs::create_dir_all(partials_dir).unwrap();
+        let message = "hi, this is a test";
+        let signature_file = "./results/test_commit_with_count_fail/signature.json";
+        let public_files: Vec<String> = (1..=2).map(|i| format!("{}/public_{}.json", dir, i)).collect();
+        let secret_files: Vec<String> = (1..=2).map(|i| format!("{}/secret_{}.json", dir, i)).collect();
+        let partial_files: Vec<String> = (1..=2).map(|i| format!("{}/partial_{}.json", partials_dir, i)).collect();
+        let result = signing::commit(SESSION_ID, &share_files[0], &public_files[0], &secret_files[0], signing::CommitOptions { count: 0, ..Default::default() });
+        assert!(result.is_err(), "Expected committing to no nonces to fail");
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions { count: 2, ..Default::default() }).unwrap();
+        }
+
+        // An index without commitments, or one already used, does not sign.
+        let result = signing::partial_sign_at(message, SIGNING_CONTEXT, &share_files[0], &secret_files[0], &public_files, 2, &partial_files[0], &Default::default());
+        assert!(result.is_err(), "Expected an index without commitments to fail");
+        for ((share_file, secret_file), partial_file) in share_files.iter().zip(&secret_files).zip(&partial_files) {
+            signing::partial_sign_at(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, 0, partial_file, &Default::default()).unwrap();
+        }
+        let result = signing::partial_sign_at("another message", SIGNING_CONTEXT, &share_files[0], &secret_files[0], &public_files, 0, &partial_files[0], &Default::default());
+        assert!(result.is_err(), "Expected reusing an index to fail");
+
+        // Partial signatures do not aggregate at another index than they were made at.
+        let result = signing::aggregate_at(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, 1, signature_file, SignatureFormat::Json, &Default::default(), None);
+        assert!(result.is_err(), "Expected aggregating at another index to fail");
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_ws_commitment_refill_fail() {
+        let share_files = run_dkg("test_ws_commitment_refill_fail", 2, 3);
+        let state_dir = "./results/test_ws_commitment_refill_fail_state";
+
+        // A refill that never generates anything is refused before connecting.
+        for (min_commitments, batch_size) in [(0, 10), (10, 0)] {
+            let refill = ws::PoolRefill { min_commitments, batch_size };
+            let result = ws::run_participant_with_pool("ws://127.0.0.1:1", &share_files[0], SIGNING_CONTEXT, state_dir, Some(&refill), None, None, &Default::default());
+            assert!(result.is_err(), "Expected an empty refill to fail");
+        }
+        assert!(!Path::new(state_dir).exists(), "No pool should be created");
+
+        for share_file in &share_files {
+            remove_file(share_file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_generate_keys_many_participants_fail() {
+        let keys_file = "./results/test_generate_keys_many_participants_fail_frost_keys.json";
+        let signature_file = "./results/test_generate_keys_many_participants_fail_signature.json";
+        generate_keys(20, 40, keys_file).unwrap();
+
+        // One signer short of the threshold cannot sign.
+        let signers: Vec<u32> = (0..19).collect();
+        let result = sign_message("hi, this is a test", SIGNING_CONTEXT, signers, 40, keys_file, signature_file);
+        assert!(result.is_err(), "Expected signing below the threshold to fail");
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_bench_fail() {
+        let result = frost_cli::bench::run(&[2], &[3], 0);
+        assert!(result.is_err(), "Expected zero rounds to fail");
+        let result = frost_cli::bench::run(&[4, 5], &[3], 1);
+        assert!(result.is_err(), "Expected no threshold fitting the participants to fail");
+        let result = frost_cli::bench::run(&[0], &[3], 1);
+        assert!(result.is_err(), "Expected a zero threshold to fail");
+    }
+
+    #[test]
+    fn test_generate_frost_keys_large_group_fail() {
+        let ciphersuite = frost_cli::backend::Ciphersuite::Ristretto255;
+        let mut frost_keys = frost_cli::generate_frost_keys(40, 60, ciphersuite, &|_| {}).unwrap();
+        let other_keys = frost_cli::generate_frost_keys(40, 60, ciphersuite, &|_| {}).unwrap();
+
+        // A share of another group, even at the same index, does not sign with this group.
+        frost_keys.private_shares[59].0 = other_keys.private_shares[59].0;
+        let signers: Vec<u32> = (20..60).collect();
+        let result = frost_cli::sign_with_keys(b"hi, this is a test", SIGNING_CONTEXT, &frost_keys, &signers);
+        assert!(result.is_err(), "Expected a share of another group to fail");
+    }
+
+    #[test]
+    fn test_generate_share_files_fail() {
+        let shares_dir = "./results/test_generate_share_files_fail";
+        let result = frost_cli::generate_share_files(4, 3, shares_dir, &|_| {}, &Default::default());
+        assert!(result.is_err(), "Expected a threshold above the number of participants to fail");
+        assert!(!frost_cli::keygen::share_file(shares_dir, 1).exists(), "No share should be saved");
+
+        // Shares of two runs belong to different groups and do not sign together.
+        let other_dir = "./results/test_generate_share_files_fail_other";
+        frost_cli::generate_share_files(2, 3, shares_dir, &|_| {}, &Default::default()).unwrap();
+        frost_cli::generate_share_files(2, 3, other_dir, &|_| {}, &Default::default()).unwrap();
+        let share_files = vec![
+            frost_cli::keygen::share_file(shares_dir, 1).to_string_lossy().into_owned(),
+            frost_cli::keygen::share_file(other_dir, 2).to_string_lossy().into_owned(),
+        ];
+        let signature_file = "./results/test_generate_share_files_fail_signature.json";
+        let result = sign_message_with_shares("hi, this is a test", SIGNING_CONTEXT, &share_files, signature_file, None);
+        assert!(result.is_err(), "Expected shares of different groups to fail");
+        remove_dir_all(shares_dir).unwrap();
+        remove_dir_all(other_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_blame_fail() {
+        let dkg_dir = "./results/test_dkg_blame_fail";
+        let state_files: Vec<String> = (1..=4).map(|i| format!("./results/test_dkg_blame_fail_state_{}.json", i)).collect();
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, 2, 4, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+
+        // Participant 3's proof no longer matches and participant 4 drops a commitment.
+        let message_file = format!("{}/round1_3.json", dkg_dir);
+        let mut message: serde_json::Value = serde_json::from_str(&fs::read_to_string(&message_file).unwrap()).unwrap();
+        message["commitments"][0] = message["commitments"][1].clone();
+        fs::write(&message_file, message.to_string()).unwrap();
+        let message_file = format!("{}/round1_4.json", dkg_dir);
+        let mut message: serde_json::Value = serde_json::from_str(&fs::read_to_string(&message_file).unwrap()).unwrap();
+        message["commitments"].as_array_mut().unwrap().pop();
+        fs::write(&message_file, message.to_string()).unwrap();
+
+        let err = dkg::round_two(&state_files[0], dkg_dir, dkg_dir, &Default::default()).err().expect("Expected invalid round 1 messages to fail");
+        match err.downcast_ref::<frost_cli::error::FrostCliError>() {
+            Some(frost_cli::error::FrostCliError::Misbehaving { participants, reason }) => {
+                assert_eq!(participants, &vec![3, 4]);
+                assert!(reason.contains("participant 4 sent 1 commitments, expected 2"), "Unexpected reason: {}", reason);
+            }
+            _ => panic!("Expected participants 3 and 4 to be blamed: {}", err),
+        }
+        assert!(!Path::new(&format!("{}/share_1_2.json", dkg_dir)).exists(), "No shares should be written");
+        for file in &state_files {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_dkg_complaints_fail() {
+        let dkg_dir = "./results/test_dkg_complaints_fail";
+        let evidence_file = "./results/test_dkg_complaints_fail_evidence.json";
+        let share_file = "./results/test_dkg_complaints_fail_share.json";
+        let state_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_dkg_complaints_fail_state_{}.json", i))
+            .collect();
+        for (i, state_file) in state_files.iter().enumerate() {
+            dkg::round_one(i as u32 + 1, 2, 3, state_file, dkg_dir, &Default::default()).unwrap();
+        }
+        for state_file in &state_files {
+            dkg::round_two(state_file, dkg_dir, dkg_dir, &Default::default()).unwrap();
+        }
+
+        // Participant 3 sends participant 1 nothing and never answers the complaint.
+        remove_file(format!("{}/share_3_1.json", dkg_dir)).unwrap();
+        dkg::complain(&state_files[0], dkg_dir, dkg_dir, &Default::default()).unwrap();
+        let err = dkg::resolve(&state_files[1], dkg_dir, share_file, false, evidence_file, &Default::default())
+            .err()
+            .expect("Expected an unanswered complaint to abort");
+        match err.downcast_ref::<frost_cli::error::FrostCliError>() {
+            Some(frost_cli::error::FrostCliError::Misbehaving { participants, .. }) => assert_eq!(participants, &vec![3]),
+            _ => panic!("Expected participant 3 to be disqualified: {}", err),
+        }
+        assert!(!Path::new(share_file).exists(), "No share should be saved");
+        let evidence: dkg::DkgEvidence = serde_json::from_str(&fs::read_to_string(evidence_file).unwrap()).unwrap();
+        assert_eq!(evidence.disqualified, vec![3]);
+        assert!(evidence.reveals.is_empty(), "Participant 3 revealed nothing");
+
+        // Going on without participant 3 still fails on a share nobody complained about.
+        remove_file(format!("{}/share_2_1.json", dkg_dir)).unwrap();
+        let result = dkg::resolve(&state_files[0], dkg_dir, share_file, true, evidence_file, &Default::default());
+        assert!(result.is_err(), "Expected a share nobody complained about to fail");
+
+        for file in &state_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(evidence_file).unwrap();
+        remove_dir_all(dkg_dir).unwrap();
+    }
+
+    #[test]
+    fn test_participant_ids_fail() {
+        let keys_file = "./results/test_participant_ids_fail_frost_keys.json";
+        let signature_file = "./results/test_participant_ids_fail_signature.json";
+        generate_keys(2, 3, keys_file).unwrap();
+        assert!("0".parse::<frost_cli::ParticipantId>().is_err(), "Participant 0 should be rejected");
+        assert!("x".parse::<frost_cli::ParticipantId>().is_err(), "A non-number should be rejected");
+
+        let id = |index| frost_cli::ParticipantId::new(index).unwrap();
+        let err = frost_cli::signer_positions(keys_file, &[id(1), id(4)], &Default::default()).err().expect("Participant 4 has no share");
+        assert!(err.to_string().contains("Participant 4 has no share"), "Unexpected error: {}", err);
+        let result = frost_cli::signer_positions(keys_file, &[id(2), id(2)], &Default::default());
+        assert!(result.is_err(), "A participant given twice should be rejected");
+
+        // Positions passed to the library directly are checked as well.
+        let result = sign_message("hi", SIGNING_CONTEXT, vec![1, 1], 3, keys_file, signature_file);
+        assert!(result.is_err(), "A position given twice should be rejected");
+        let result = sign_message("hi", SIGNING_CONTEXT, vec![1, 3], 3, keys_file, signature_file);
+        assert!(result.is_err(), "Position 3 is past the last share");
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+        remove_file(keys_file).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_signers_fail() {
+        let signature_file = "./results/test_duplicate_signers_fail_signature.json";
+        let public_commitments_file = "./results/test_duplicate_signers_fail_public_commitments.json";
+        let secret_commitments_file = "./results/test_duplicate_signers_fail_secret_commitments.json";
+        let share_files = run_dkg("test_duplicate_signers_fail", 2, 3);
+
+        // The same share twice makes up the threshold in number only.
+        let duplicated = vec![share_files[0].clone(), share_files[0].clone()];
+        let err = sign_message_with_shares("hi", SIGNING_CONTEXT, &duplicated, signature_file, None).err().expect("Expected a duplicate signer to fail");
+        assert!(err.to_string().contains("Participant 1 is among the signers more than once"), "Unexpected error: {}", err);
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+
+        // Two commitment files of the same participant are rejected before signing.
+        signing::commit(SESSION_ID, &share_files[0], public_commitments_file, secret_commitments_file, signing::CommitOptions::default()).unwrap();
+        let commitment_files = vec![public_commitments_file.to_string(), public_commitments_file.to_string()];
+        let result = signing::load_roster(&commitment_files);
+        assert!(result.is_err(), "Expected duplicate commitments to fail");
+        for file in share_files.iter().map(String::as_str).chain([public_commitments_file, secret_commitments_file]) {
+            remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_verify_partial_signature_fail() {
+        let share_files = run_dkg("test_verify_partial_signature_fail", 2, 3);
+        let partials_dir = "./results/test_verify_partial_signature_fail_partials";
+        let signature_file = "./results/test_verify_partial_signature_fail_signature.json";
+        fs::create_dir_all(partials_dir).unwrap();
+        let public_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_verify_partial_signature_fail_public_{}.json", i))
+            .collect();
+        let secret_files: Vec<String> = (1..=3)
+            .map(|i| format!("./results/test_verify_partial_signature_fail_secret_{}.json", i))
+            .collect();
+        let message = "hi, this is a test";
+        for ((share_file, public_file), secret_file) in share_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        for (i, (share_file, secret_file)) in share_files.iter().zip(&secret_files).enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i + 1);
+            signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, &partial_file, &Default::default()).unwrap();
+        }
+
+        // Participant 2 sends garbage in place of its partial signature.
+        let partial_file = format!("{}/partial_2.json", partials_dir);
+        let mut partial: serde_json::Value = serde_json::from_str(&fs::read_to_string(&partial_file).unwrap()).unwrap();
+        let other: serde_json::Value = serde_json::from_str(&fs::read_to_string(format!("{}/partial_1.json", partials_dir)).unwrap()).unwrap();
+        partial["z"] = other["z"].clone();
+        fs::write(&partial_file, partial.to_string()).unwrap();
+        let err = signing::aggregate(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, signature_file)
+            .err()
+            .expect("Expected an invalid partial signature to fail");
+        match err.downcast_ref::<frost_cli::error::FrostCliError>() {
+            Some(frost_cli::error::FrostCliError::Aggregation { signers, reason }) => {
+                assert_eq!(signers, &vec![2]);
+                assert!(reason.contains("participant 2 sent a partial signature that does not verify"), "Unexpected reason: {}", reason);
+            }
+            _ => panic!("Expected participant 2 to be blamed: {}", err),
+        }
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+
+        // Participant 2 signs with a share of another group and publishes that share's public
+        // key, which its partial signature verifies against, but its verification share does not.
+        let other_files = run_dkg("test_verify_partial_signature_fail_other", 2, 3);
+        let signer_files = [&share_files[0], &other_files[1], &share_files[2]];
+        for ((share_file, public_file), secret_file) in signer_files.iter().zip(&public_files).zip(&secret_files) {
+            signing::commit(SESSION_ID, share_file, public_file, secret_file, signing::CommitOptions::default()).unwrap();
+        }
+        for (i, (share_file, secret_file)) in signer_files.iter().zip(&secret_files).enumerate() {
+            let partial_file = format!("{}/partial_{}.json", partials_dir, i + 1);
+            signing::partial_sign(message, SIGNING_CONTEXT, share_file, secret_file, &public_files, &partial_file, &Default::default()).unwrap();
+        }
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let group_key = frost_dalek::GroupKey::from_bytes(share.group_key).unwrap();
+        let roster = signing::load_roster(&public_files).unwrap();
+        let message_hash = frost_dalek::compute_message_hash(SIGNING_CONTEXT.as_bytes(), message.as_bytes());
+        for i in 1..=3 {
+            let partial: signing::PartialSignature = serde_json::from_str(&fs::read_to_string(format!("{}/partial_{}.json", partials_dir, i)).unwrap()).unwrap();
+            let result = signing::verify_partial_signature(&message_hash, &group_key, &roster, &share.verification_shares, &partial, 0);
+            assert_eq!(result.is_ok(), i != 2, "Only the partial signature of participant 2 should fail: {:?}", result.err());
+        }
+        let err = signing::aggregate(message, SIGNING_CONTEXT, &share_files[0], &public_files, partials_dir, signature_file)
+            .err()
+            .expect("Expected a partial signature with a share of another group to fail");
+        match err.downcast_ref::<frost_cli::error::FrostCliError>() {
+            Some(frost_cli::error::FrostCliError::Aggregation { signers, reason }) => {
+                assert_eq!(signers, &vec![2]);
+                assert!(reason.contains("participant 2 sent a partial signature that does not verify"), "Unexpected reason: {}", reason);
+            }
+            _ => panic!("Expected participant 2 to be blamed: {}", err),
+        }
+        assert!(!Path::new(signature_file).exists(), "No signature should be saved");
+
+        for file in share_files.iter().chain(&other_files).chain(&public_files).chain(&secret_files) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(partials_dir).unwrap();
+    }
+
+    #[test]
+    fn test_transcript_fail() {
+        use sha2::{Digest, Sha256};
+        let share_files = run_dkg("test_transcript_fail", 2, 3);
+        let transcript_file = "./results/test_transcript_fail.jsonl";
+        let signature_file = "./results/test_transcript_fail_signature.json";
+        let _ = remove_file(transcript_file);
+        let message = "hi, this is a test";
+        sign_message_with_shares(message, SIGNING_CONTEXT, &share_files[..2], signature_file, None).unwrap();
+        let share = load_participant_share(&share_files[0], &Default::default()).unwrap();
+        let message_hash = frost_dalek::compute_message_hash(SIGNING_CONTEXT.as_bytes(), message.as_bytes());
+        let entry = |sequence: u64, previous: String, signature: &str| {
+            let mut entry = serde_json::json!({
+                "sequence": sequence,
+                "timestamp": 0,
+                "previous": previous,
+                "event": "signed",
+                "ciphersuite": "ristretto255",
+                "group_key": hex::encode(share.group_key),
+                "threshold": 2,
+                "signers": [1, 2],
+                "message_hash": hex::encode(message_hash),
+                "signature": signature,
+            })
+            .to_string();
+            entry.push('\n');
+            entry
+        };
+        let signature = frost_cli::load_signature(signature_file, None).unwrap().to_bytes();
+        let first = entry(1, hex::encode(Sha256::digest(b"")), &hex::encode(signature));
+        let second_previous = hex::encode(Sha256::digest(first.trim_end().as_bytes()));
+
+        // An untouched transcript checks.
+        fs::write(transcript_file, first.clone() + &entry(2, second_previous.clone(), &hex::encode(signature))).unwrap();
+        assert!(transcript::read_transcript(transcript_file).is_ok());
+
+        // Removing an entry breaks the chain.
+        fs::write(transcript_file, entry(2, second_previous.clone(), &hex::encode(signature))).unwrap();
+        let err = transcript::read_transcript(transcript_file).err().expect("Expected a removed entry to be noticed");
+        assert!(err.to_string().contains("transcript was altered"), "Unexpected error: {}", err);
+
+        // Changing an entry breaks the chain after it.
+        let changed = first.replace("\"signers\":[1,2]", "\"signers\":[1,3]");
+        assert_ne!(changed, first);
+        fs::write(transcript_file, changed + &entry(2, second_previous, &hex::encode(signature))).unwrap();
+        let err = transcript::read_transcript(transcript_file).err().expect("Expected a changed entry to be noticed");
+        assert!(err.to_string().contains("Line 2"), "Unexpected error: {}", err);
+
+        // A forged signature does not verify.
+        let mut forged = signature;
+        forged[40] ^= 1;
+        fs::write(transcript_file, entry(1, hex::encode(Sha256::digest(b"")), &hex::encode(forged))).unwrap();
+        assert!(transcript::read_transcript(transcript_file).is_err(), "Expected a forged signature to be noticed");
+
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+        remove_file(transcript_file).unwrap();
+    }
+
+    #[test]
+    fn test_deterministic_keys_fail() {
+        use frost_cli::backend::Ciphersuite;
+        use frost_cli::error::FrostCliError;
+        use frost_cli::generate_frost_keys_with_rng;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+        let keys_file = "./results/test_deterministic_keys_fail.json";
+
+        // Invalid parameters fail before anything is drawn.
+        let err = generate_frost_keys_with_rng(4, 3, Ciphersuite::Ristretto255, &|_| {}, &mut StdRng::seed_from_u64(7)).err().unwrap();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidParameters(_))));
+        let err = generate_frost_keys_with_rng(0, 3, Ciphersuite::Ristretto255, &|_| {}, &mut StdRng::seed_from_u64(7)).err().unwrap();
+        assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidParameters(_))));
+        for (t, n) in [(0, 3), (0, 0), (1, 0)] {
+            let err = frost_cli::generate_frost_keys(t, n, Ciphersuite::Ristretto255, &|_| {}).err().unwrap();
+            assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidParameters(_))), "Unexpected error for t={} n={}: {}", t, n, err);
+        }
+
+        // Without the feature, seeded keys are refused and nothing is written.
+        if cfg!(not(feature = "insecure-deterministic")) {
+            let err = frost_cli::generate_keys_from_seed(2, 3, keys_file, Ciphersuite::Ristretto255, b"demo", &|_| {}, &Default::default()).unwrap_err();
+            assert!(err.to_string().contains("insecure-deterministic"), "Unexpected error: {}", err);
+            assert!(!Path::new(keys_file).exists());
+        }
+    }
+
+    #[test]
+    fn test_parse_files_fail() {
+        use frost_cli::error::FrostCliError;
+        use frost_cli::{parse_frost_keys, parse_participant_share, parse_signature, ParticipantShare};
+        let share_file = "./results/test_parse_files_fail_share.json";
+
+        // Bincode nested deeper than JSON allows is refused instead of overflowing the stack.
+        let mut bytes = format::BINCODE_MAGIC.to_vec();
+        for _ in 0..100_000 {
+            bytes.extend([7, 1]);
+        }
+        bytes.push(0);
+        let err = format::decode::<serde_json::Value>(&bytes).unwrap_err();
+        assert!(err.to_string().contains("nested deeper"), "Unexpected error: {}", err);
+        assert!(parse_frost_keys(&bytes, "nested", &Default::default()).is_err());
+
+        // A share whose index or threshold does not fit its participants is refused on load.
+        for (index, threshold) in [(0, 2), (4, 2), (1, 0), (1, 4)] {
+            let share = ParticipantShare { index, share: [1; 32], group_key: [0; 32], threshold, participants: 3, verification_shares: Vec::new() };
+            save_participant_share(share_file, &share, &Default::default()).unwrap();
+            let err = load_participant_share(share_file, &Default::default()).err().unwrap();
+            assert!(matches!(err.downcast_ref(), Some(FrostCliError::InvalidShare { share, .. }) if share == share_file));
+            assert!(parse_participant_share(&fs::read(share_file).unwrap(), share_file, &Default::default()).is_err());
+        }
+
+        // Malformed files fail with an error.
+        for bytes in [&b""[..], b"{", b"[[[[", b"\xd9\xd9\xf7\x9f", b"FROSTBIN\x08\xff\xff\xff\xff\x0f"] {
+            assert!(parse_frost_keys(bytes, "malformed", &Default::default()).is_err());
+            assert!(parse_participant_share(bytes, "malformed", &Default::default()).is_err());
+            assert!(parse_signature(bytes, "malformed", None).is_err());
+            assert!(frost_cli::messages::decode(bytes).is_err());
+        }
+        remove_file(share_file).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_daemon_fail() {
+        use frost_cli::daemon;
+        let share_files = run_dkg("test_daemon_fail", 2, 3);
+        let dir = "./results/test_daemon_fail";
+        fs::create_dir_all(dir).unwrap();
+        let socket = format!("{}/daemon.sock", dir);
+        let (serve_socket, share_file) = (socket.clone(), share_files[0].clone());
+        thread::spawn(move || daemon::serve(&serve_socket, &share_file, &Default::default()).unwrap());
+        while !Path::new(&socket).exists() {
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+
+        // A second daemon on the same socket is refused, and so are unknown methods and bad params.
+        assert!(daemon::serve(&socket, &share_files[1], &Default::default()).is_err());
+        let err = daemon::call(&socket, "sign", serde_json::Value::Null).unwrap_err();
+        assert!(err.to_string().contains("-32601"), "Unexpected error: {}", err);
+        let err = daemon::call(&socket, "commit", serde_json::json!({ "session": SESSION_ID })).unwrap_err();
+        assert!(err.to_string().contains("-32602"), "Unexpected error: {}", err);
+
+        // Signing without commitments, committing twice and signing twice are refused.
+        let commitment_files: Vec<String> = (1..=2).map(|i| format!("{}/commitments_{}.json", dir, i)).collect();
+        let partial_file = format!("{}/partial_1.json", dir);
+        signing::commit(SESSION_ID, &share_files[1], &commitment_files[1], &format!("{}/secret_2.json", dir), signing::CommitOptions::default()).unwrap();
+        // Commitments of participant 1 the daemon did not make itself
+        signing::commit(SESSION_ID, &share_files[0], &commitment_files[0], &format!("{}/secret_1.json", dir), signing::CommitOptions::default()).unwrap();
+        let err = daemon::partial_sign(&socket, "hi, this is a test", SIGNING_CONTEXT, &commitment_files, &partial_file, &Default::default()).unwrap_err();
+        assert!(err.to_string().contains("never committed"), "Unexpected error: {}", err);
+        daemon::commit(&socket, SESSION_ID, &commitment_files[0], &Default::default()).unwrap();
+        assert!(daemon::commit(&socket, SESSION_ID, &commitment_files[0], &Default::default()).is_err());
+        daemon::partial_sign(&socket, "hi, this is a test", SIGNING_CONTEXT, &commitment_files, &partial_file, &Default::default()).unwrap();
+        let err = daemon::partial_sign(&socket, "another message", SIGNING_CONTEXT, &commitment_files, &partial_file, &Default::default()).unwrap_err();
+        assert!(err.to_string().contains("already signed"), "Unexpected error: {}", err);
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_http_api_fail() {
+        use frost_cli::net::api;
+        let share_files = run_dkg("test_http_api_fail", 2, 3);
+        let other_share_files = run_dkg("test_http_api_fail_other", 2, 3);
+        let dir = "./results/test_http_api_fail";
+        let keystore_dir = Path::new(dir).join("keys");
+        let clients_file = format!("{}/clients.json", dir);
+        let sessions_dir = format!("{}/sessions", dir);
+        let url = "http://127.0.0.1:47020";
+        fs::create_dir_all(dir).unwrap();
+        keystore::import_key(&keystore_dir, "alice", &share_files[0], &Default::default()).unwrap();
+        keystore::import_key(&keystore_dir, "mallory", &other_share_files[1], &Default::default()).unwrap();
+        let bot = api::add_client(&clients_file, "release-bot", true, None, &Default::default()).unwrap();
+        let alice = api::add_client(&clients_file, "alice", false, Some("alice"), &Default::default()).unwrap();
+        let mallory = api::add_client(&clients_file, "mallory", false, Some("mallory"), &Default::default()).unwrap();
+        assert!(api::add_client(&clients_file, "alice", false, None, &Default::default()).is_err());
+        let (serve_keystore, serve_clients, serve_sessions) = (keystore_dir.clone(), clients_file.clone(), sessions_dir.clone());
+        thread::spawn(move || api::serve("127.0.0.1:47020", &serve_keystore, &serve_clients, &serve_sessions, &Default::default()).unwrap());
+        thread::sleep(std::time::Duration::from_millis(500));
+
+        let status = |result: Result<ureq::Response, ureq::Error>| match result {
+            Err(ureq::Error::Status(code, _)) => code,
+            Ok(response) => response.status(),
+            Err(err) => panic!("Request failed: {}", err),
+        };
+        let bearer = |token: &str| format!("Bearer {}", token);
+        let create = |token: Option<&str>| {
+            let request = ureq::post(&format!("{}/sessions", url));
+            let request = match token {
+                Some(token) => request.set("Authorization", &bearer(token)),
+                None => request,
+            };
+            request.send_json(serde_json::json!({ "key": "alice", "message": "hi, this is a test" }))
+        };
+
+        // Requests without a known token, and clients beyond their permissions, are refused.
+        assert_eq!(status(create(None)), 401);
+        assert_eq!(status(create(Some("not a token"))), 401);
+        assert_eq!(status(create(Some(&alice))), 403);
+        let session: api::ApprovalSession = create(Some(&bot)).unwrap().into_json().unwrap();
+        let approvals_url = format!("{}/sessions/{}/approvals", url, session.session_id);
+        assert_eq!(status(ureq::post(&approvals_url).set("Authorization", &bearer(&bot)).call()), 403);
+
+        // A share of another group, a second approval and an early signature are refused.
+        assert_eq!(status(ureq::post(&approvals_url).set("Authorization", &bearer(&mallory)).call()), 403);
+        assert_eq!(status(ureq::post(&approvals_url).set("Authorization", &bearer(&alice)).call()), 200);
+        assert_eq!(status(ureq::post(&approvals_url).set("Authorization", &bearer(&alice)).call()), 409);
+        let signature_url = format!("{}/sessions/{}/signature", url, session.session_id);
+        assert_eq!(status(ureq::get(&signature_url).set("Authorization", &bearer(&bot)).call()), 404);
+        let unknown_url = format!("{}/sessions/{}", url, "00".repeat(16));
+        assert_eq!(status(ureq::get(&unknown_url).set("Authorization", &bearer(&bot)).call()), 404);
+        for file in share_files.iter().chain(&other_share_files) {
+            remove_file(file).unwrap();
+        }
+        remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_metrics_fail() {
+        use frost_cli::metrics;
+        let share_files = run_dkg("test_metrics_fail", 2, 2);
+        let signature_file = "./results/test_metrics_fail_signature.json";
+        let invalid = "frost_verifications_total{kind=\"signature\",result=\"invalid\"}";
+        let count = |metrics: &str, name: &str| -> u64 {
+            metrics.lines().find_map(|line| line.strip_prefix(name)?.trim().parse().ok()).unwrap()
+        };
+        let before = count(&metrics::render(), invalid);
+        sign_message_with_shares("hi, this is a test", SIGNING_CONTEXT, &share_files, signature_file, None).unwrap();
+
+        // A signature over another message is counted as invalid.
+        assert!(validate_signature("hi, this is another test", SIGNING_CONTEXT, &share_files[0], signature_file).is_err());
+        assert!(count(&metrics::render(), invalid) > before, "Invalid signature was not counted");
+        for file in &share_files {
+            remove_file(file).unwrap();
+        }
+        remove_file(signature_file).unwrap();
+    }
 }